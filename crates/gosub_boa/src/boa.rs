@@ -0,0 +1,245 @@
+use std::cell::{Cell, RefCell};
+use std::fmt::Display;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use boa_engine::{Context, JsError, JsNativeError, JsObject, JsString};
+
+pub use array::*;
+pub use compile::*;
+pub use context::*;
+pub use function::*;
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSError, JSException, JSRuntime};
+use gosub_webexecutor::Error;
+use module::BoaModuleLoader;
+pub use object::*;
+pub use promise::*;
+pub use value::*;
+
+mod array;
+mod compile;
+mod context;
+mod function;
+mod module;
+mod object;
+mod promise;
+mod value;
+
+//Boa keeps all state in its context, so this is just a dummy struct for the wrapper
+#[derive(Default)]
+pub struct BoaEngine;
+
+impl BoaEngine {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+//Boa context is stored in a Rc<>, so we can attach it to Values, ...
+#[derive(Clone)]
+pub struct BoaContext {
+    ctx: Rc<BoaCtx>,
+}
+
+struct BoaCtx {
+    context: RefCell<Context>,
+    /// Context that boa handed to the native function which is currently running, if any
+    active: Cell<Option<NonNull<Context>>>,
+    modules: Rc<BoaModuleLoader>,
+}
+
+impl Default for BoaContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoaContext {
+    pub fn new() -> Self {
+        let modules = Rc::new(BoaModuleLoader::default());
+        let context = Context::builder()
+            .module_loader(modules.clone())
+            .build()
+            .expect("failed to create a boa context");
+
+        Self::from_parts(context, modules)
+    }
+
+    /// Wraps an existing boa context. Modules can only import other modules when the context is created with `new`,
+    /// as boa needs the module loader when the context is built.
+    pub fn from_context(context: Context) -> Self {
+        Self::from_parts(context, Rc::default())
+    }
+
+    fn from_parts(context: Context, modules: Rc<BoaModuleLoader>) -> Self {
+        Self {
+            ctx: Rc::new(BoaCtx {
+                context: RefCell::new(context),
+                active: Cell::new(None),
+                modules,
+            }),
+        }
+    }
+
+    pub(crate) fn modules(&self) -> Rc<BoaModuleLoader> {
+        self.ctx.modules.clone()
+    }
+
+    /// Runs `f` with the boa context. Calls must not be nested, everything that needs the context inside `f` has
+    /// to use the reference that is passed to it.
+    pub fn with<R>(&self, f: impl FnOnce(&mut Context) -> R) -> R {
+        if let Some(mut active) = self.ctx.active.get() {
+            // SAFETY: the pointer comes from the `&mut Context` of a native function that is still running (see
+            // `enter`). That reference isn't used while the native function runs our callback.
+            return f(unsafe { active.as_mut() });
+        }
+
+        f(&mut self.ctx.context.borrow_mut())
+    }
+
+    /// Makes `context` (which boa passed to a native function) the context that is used by `with` while `f` runs
+    pub(crate) fn enter<R>(&self, context: &mut Context, f: impl FnOnce() -> R) -> R {
+        struct Restore<'a>(&'a Cell<Option<NonNull<Context>>>, Option<NonNull<Context>>);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.1);
+            }
+        }
+
+        let _restore = Restore(&self.ctx.active, self.ctx.active.replace(Some(NonNull::from(context))));
+
+        f()
+    }
+
+    pub(crate) fn error(error: impl Display) -> JsError {
+        JsNativeError::error().with_message(error.to_string()).into()
+    }
+
+    pub(crate) fn report_exception(error: JsError, context: &mut Context) -> Error {
+        let value = error.to_opaque(context);
+
+        let mut exception = JSException {
+            message: value
+                .to_string(context)
+                .map_or_else(|_| error.to_string(), |message| message.to_std_string_escaped()),
+            ..Default::default()
+        };
+
+        if let Some(object) = value.as_object() {
+            if let Some(name) = Self::string_property(object, "name", context) {
+                exception.name = name;
+            }
+            if let Some(message) = Self::string_property(object, "message", context) {
+                exception.message = message;
+            }
+            exception.stack = Self::string_property(object, "stack", context);
+        }
+
+        Error::JS(JSError::Exception(exception))
+    }
+
+    fn string_property(object: &JsObject, name: &str, context: &mut Context) -> Option<String> {
+        let value = object.get(JsString::from(name), context).ok()?;
+
+        value.as_string().map(|value| value.to_std_string_escaped())
+    }
+}
+
+impl JSRuntime for BoaEngine {
+    type Context = BoaContext;
+    type Value = BoaValue;
+    type Object = BoaObject;
+    type Compiled = BoaCompiled;
+    type GetterCB = GetterCallback;
+    type SetterCB = SetterCallback;
+    type Function = BoaFunction;
+    type FunctionVariadic = BoaFunctionVariadic;
+    type Array = BoaArray;
+    type Promise = BoaPromise;
+    type FunctionCallBack = BoaFunctionCallBack;
+    type FunctionCallBackVariadic = BoaFunctionCallBackVariadic;
+    type Args = BoaArgs;
+    type VariadicArgs = BoaVariadicArgs;
+    type VariadicArgsInternal = BoaVariadicArgsInternal;
+
+    fn new_context(&mut self) -> Result<Self::Context> {
+        Ok(BoaContext::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_webexecutor::js::{JSContext, JSError, JSRuntime, JSValue};
+    use gosub_webexecutor::Error;
+
+    use crate::BoaEngine;
+
+    #[test]
+    fn boa_js_execution() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context
+            .run(
+                r#"
+            let a = 1000;
+            a + 234
+        "#,
+            )
+            .unwrap();
+
+        assert!(value.is_number());
+        assert_eq!(value.as_number().unwrap(), 1234.0);
+    }
+
+    #[test]
+    fn boa_run_invalid_syntax() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let result = context.run(
+            r#"
+        console.log(Hello World!);
+        1234
+        "#,
+        );
+
+        let err = result.err().expect("invalid syntax should fail");
+        let Some(Error::JS(JSError::Exception(exception))) = err.downcast_ref::<Error>() else {
+            panic!("expected an exception, got: {err}");
+        };
+
+        assert_eq!(exception.name, "SyntaxError");
+    }
+
+    #[test]
+    fn boa_uncaught_exception() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let err = context.run("throw new RangeError('out of range')").err().unwrap();
+        let Some(Error::JS(JSError::Exception(exception))) = err.downcast_ref::<Error>() else {
+            panic!("expected an exception, got: {err}");
+        };
+
+        assert_eq!(exception.name, "RangeError");
+        assert_eq!(exception.message, "out of range");
+
+        let err = context.run("throw 'plain value'").err().unwrap();
+        assert_eq!(err.to_string(), "js: exception: plain value");
+    }
+
+    #[test]
+    fn boa_promise_jobs() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        context
+            .run("globalThis.resolved = false; Promise.resolve().then(() => { globalThis.resolved = true })")
+            .unwrap();
+
+        assert!(context.run("resolved").unwrap().as_bool().unwrap());
+    }
+}
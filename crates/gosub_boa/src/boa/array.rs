@@ -0,0 +1,190 @@
+use boa_engine::object::builtins::JsArray;
+use boa_engine::{js_string, JsObject};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{AsArray, JSArray, JSRuntime, Ref};
+
+use crate::{BoaContext, BoaEngine, BoaValue};
+
+pub struct BoaArray {
+    pub value: JsArray,
+    pub ctx: BoaContext,
+    next: usize,
+}
+
+impl BoaArray {
+    pub fn from_array(ctx: BoaContext, value: JsArray) -> Self {
+        Self { value, ctx, next: 0 }
+    }
+}
+
+impl Iterator for BoaArray {
+    type Item = BoaValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.len() {
+            return None;
+        }
+        let value = self.get(self.next).ok();
+
+        self.next += 1;
+
+        value
+    }
+}
+
+impl AsArray for BoaArray {
+    type Runtime = BoaEngine;
+
+    fn array(&self) -> Result<Ref<<Self::Runtime as JSRuntime>::Array>> {
+        Ok(Ref::Ref(self))
+    }
+}
+
+impl JSArray for BoaArray {
+    type RT = BoaEngine;
+
+    fn get(&self, index: usize) -> Result<<Self::RT as JSRuntime>::Value> {
+        let value = self.ctx.with(|context| {
+            JsObject::get(&self.value, index as u32, context).map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaValue::from_value(self.ctx.clone(), value))
+    }
+
+    fn set(&self, index: usize, value: &BoaValue) -> Result<()> {
+        self.ctx.with(|context| {
+            JsObject::set(&self.value, index as u32, value.value.clone(), true, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+
+    fn push(&self, value: BoaValue) -> Result<()> {
+        self.ctx.with(|context| {
+            self.value
+                .push(value.value, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+
+    fn pop(&self) -> Result<<Self::RT as JSRuntime>::Value> {
+        let value = self.ctx.with(|context| {
+            self.value
+                .pop(context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaValue::from_value(self.ctx.clone(), value))
+    }
+
+    fn remove(&self, index: usize) -> Result<()> {
+        self.ctx.with(|context| {
+            JsObject::delete_property_or_throw(&self.value, index as u32, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.ctx
+            .with(|context| self.value.length(context))
+            .map_or(0, |len| len as usize)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn new(ctx: BoaContext, cap: usize) -> Result<Self> {
+        let value = ctx.with(|context| {
+            let array = JsArray::new(context);
+
+            JsObject::set(&array, js_string!("length"), cap as u32, true, context)
+                .map(|_| array)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(Self::from_array(ctx, value))
+    }
+
+    fn new_with_data(ctx: BoaContext, data: &[BoaValue]) -> Result<Self> {
+        let value = ctx.with(|context| JsArray::from_iter(data.iter().map(|v| v.value.clone()), context));
+
+        Ok(Self::from_array(ctx, value))
+    }
+
+    fn as_value(&self) -> <Self::RT as JSRuntime>::Value {
+        BoaValue::from_value(self.ctx.clone(), self.value.clone().into())
+    }
+
+    fn as_vec(&self) -> Vec<<Self::RT as JSRuntime>::Value> {
+        let mut vec = Vec::with_capacity(self.len());
+        for i in 0..self.len() {
+            vec.push(self.get(i).unwrap());
+        }
+        vec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_webexecutor::js::{
+        ArrayConversion, IntoJSValue, IntoRustValue, JSArray, JSContext, JSObject, JSRuntime, JSValue,
+    };
+
+    use crate::{BoaArray, BoaEngine, BoaValue};
+
+    #[test]
+    fn set_and_get() {
+        let mut engine = BoaEngine::new();
+        let context = engine.new_context().unwrap();
+
+        let array = BoaArray::new(context.clone(), 2).unwrap();
+        array.set(0, &1234.0.to_js_value(context.clone()).unwrap()).unwrap();
+        array.set(1, &"Hello World!".to_js_value(context).unwrap()).unwrap();
+
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.get(0).unwrap().as_number().unwrap(), 1234.0);
+        assert_eq!(array.get(1).unwrap().as_string().unwrap(), "Hello World!");
+        assert!(array.get(2).unwrap().is_undefined());
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut engine = BoaEngine::new();
+        let context = engine.new_context().unwrap();
+
+        let array = BoaArray::new(context.clone(), 2).unwrap();
+
+        array.push(1234.0.to_js_value(context.clone()).unwrap()).unwrap();
+        array.push("Hello World!".to_js_value(context).unwrap()).unwrap();
+
+        assert_eq!(array.len(), 4);
+        assert_eq!(array.pop().unwrap().as_string().unwrap(), "Hello World!");
+        assert_eq!(array.len(), 3);
+    }
+
+    #[test]
+    fn conversion() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let array: BoaArray = [1, 2, 3].to_js_array(context.clone()).unwrap();
+        context
+            .new_global_object("test")
+            .unwrap()
+            .set_property("array", &array.as_value())
+            .unwrap();
+
+        let value = context.run("test.array.map((x) => x * 2)").unwrap();
+        let doubled: Vec<i32> = value.to_rust_value().unwrap();
+
+        assert_eq!(doubled, vec![2, 4, 6]);
+        assert_eq!(BoaValue::new_array(context, &[1, 2]).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,31 @@
+use boa_engine::Script;
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSCompiled, JSRuntime};
+
+use crate::{BoaContext, BoaEngine, BoaValue};
+
+pub struct BoaCompiled {
+    pub(crate) compiled: Script,
+    pub(crate) context: BoaContext,
+}
+
+impl JSCompiled for BoaCompiled {
+    type RT = BoaEngine;
+
+    fn run(&mut self) -> Result<<Self::RT as JSRuntime>::Value> {
+        let value = self.context.with(|context| {
+            let value = self
+                .compiled
+                .evaluate(context)
+                .map_err(|e| BoaContext::report_exception(e, context));
+
+            //settle promises, like V8 does when a script is done
+            context.run_jobs();
+
+            value
+        })?;
+
+        Ok(BoaValue::from_value(self.context.clone(), value))
+    }
+}
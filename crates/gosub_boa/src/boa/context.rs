@@ -0,0 +1,77 @@
+use std::rc::Rc;
+
+use boa_engine::object::ObjectInitializer;
+use boa_engine::property::Attribute;
+use boa_engine::{JsString, Script, Source};
+
+use url::Url;
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSCompiled, JSContext, JSRuntime, ModuleLoader};
+
+use crate::{BoaCompiled, BoaContext, BoaEngine, BoaObject, BoaPromise};
+
+impl JSContext for BoaContext {
+    type RT = BoaEngine;
+
+    fn run(&mut self, code: &str) -> Result<<Self::RT as JSRuntime>::Value> {
+        self.compile(code)?.run()
+    }
+
+    fn compile(&mut self, code: &str) -> Result<<Self::RT as JSRuntime>::Compiled> {
+        let compiled = self.with(|context| {
+            Script::parse(Source::from_bytes(code), None, context).map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaCompiled {
+            compiled,
+            context: self.clone(),
+        })
+    }
+
+    fn run_compiled(
+        &mut self,
+        compiled: &mut <Self::RT as JSRuntime>::Compiled,
+    ) -> Result<<Self::RT as JSRuntime>::Value> {
+        compiled.run()
+    }
+
+    fn run_microtasks(&mut self) {
+        self.with(|context| context.run_jobs());
+    }
+
+    fn set_module_loader(&mut self, loader: Rc<ModuleLoader>) {
+        self.modules().set_loader(loader);
+    }
+
+    fn run_module(&mut self, url: Option<&Url>, source: &str) -> Result<<Self::RT as JSRuntime>::Promise> {
+        let modules = self.modules();
+
+        let promise = self.with(|context| {
+            let module = modules
+                .parse(url, source, context)
+                .map_err(|e| BoaContext::report_exception(e, context))?;
+
+            // boa links and evaluates the module in promise jobs once its imports are loaded
+            let promise = module.load_link_evaluate(context);
+            context.run_jobs();
+
+            Ok::<_, gosub_webexecutor::Error>(promise)
+        })?;
+
+        Ok(BoaPromise::from_promise(self.clone(), promise))
+    }
+
+    fn new_global_object(&mut self, name: &str) -> Result<<Self::RT as JSRuntime>::Object> {
+        let object = self.with(|context| {
+            let object = ObjectInitializer::new(context).build();
+
+            context
+                .register_global_property(JsString::from(name), object.clone(), Attribute::all())
+                .map(|_| object)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaObject::from_object(self.clone(), object))
+    }
+}
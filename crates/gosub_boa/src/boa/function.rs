@@ -0,0 +1,390 @@
+use core::fmt::Display;
+
+use boa_engine::object::builtins::JsFunction;
+use boa_engine::object::FunctionObjectBuilder;
+use boa_engine::{JsError, JsResult, JsValue, NativeFunction};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{
+    Args, IntoRustValue, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic, JSFunctionVariadic, JSRuntime,
+    VariadicArgs, VariadicArgsInternal,
+};
+
+use crate::{BoaContext, BoaEngine, BoaValue};
+
+pub struct BoaFunction {
+    pub ctx: BoaContext,
+    pub function: JsFunction,
+}
+
+pub struct BoaFunctionCallBack {
+    ctx: BoaContext,
+    args: BoaArgs,
+    ret: Option<JsValue>,
+    error: Option<JsError>,
+}
+
+pub struct BoaArgs {
+    next: usize,
+    args: Vec<JsValue>,
+}
+
+impl Iterator for BoaArgs {
+    type Item = JsValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.args.get(self.next)?.clone();
+        self.next += 1;
+        Some(value)
+    }
+}
+
+impl Args for BoaArgs {
+    type RT = BoaEngine;
+
+    fn get(&self, index: usize, ctx: <Self::RT as JSRuntime>::Context) -> Option<<Self::RT as JSRuntime>::Value> {
+        let value = self.args.get(index)?;
+
+        Some(BoaValue::from_value(ctx, value.clone()))
+    }
+
+    fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    fn as_vec(&self, ctx: <Self::RT as JSRuntime>::Context) -> Vec<<Self::RT as JSRuntime>::Value> {
+        self.args
+            .iter()
+            .map(|value| BoaValue::from_value(ctx.clone(), value.clone()))
+            .collect()
+    }
+}
+
+impl JSFunctionCallBack for BoaFunctionCallBack {
+    type RT = BoaEngine;
+
+    fn context(&mut self) -> <Self::RT as JSRuntime>::Context {
+        self.ctx.clone()
+    }
+
+    fn args(&mut self) -> &<Self::RT as JSRuntime>::Args {
+        &self.args
+    }
+
+    fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    fn error(&mut self, error: impl Display) {
+        self.error = Some(BoaContext::error(error));
+    }
+
+    fn ret(&mut self, value: <Self::RT as JSRuntime>::Value) {
+        self.ret = Some(value.value);
+    }
+}
+
+/// Result of a native function call, like V8 it is an error when the callback neither returned a value nor failed
+fn call_result(ret: Option<JsValue>, error: Option<JsError>) -> JsResult<JsValue> {
+    match (error, ret) {
+        (Some(error), _) => Err(error),
+        (None, Some(value)) => Ok(value),
+        (None, None) => Err(BoaContext::error("function was not called")),
+    }
+}
+
+/// Creates a boa function that runs `f` with the context it is called in
+pub(crate) fn native_function(
+    ctx: &BoaContext,
+    length: usize,
+    f: impl Fn(&BoaContext, &[JsValue]) -> JsResult<JsValue> + 'static,
+) -> JsFunction {
+    let native = {
+        let ctx = ctx.clone();
+        // SAFETY: the closure doesn't own any garbage collected values besides the ones of the context handle, and
+        // those are kept alive for as long as the handle lives.
+        unsafe { NativeFunction::from_closure(move |_, args, context| ctx.enter(context, || f(&ctx, args))) }
+    };
+
+    ctx.with(|context| {
+        FunctionObjectBuilder::new(context.realm(), native)
+            .length(length)
+            .build()
+    })
+}
+
+fn call_function(ctx: &BoaContext, function: &JsFunction, args: &[BoaValue]) -> Result<BoaValue> {
+    let args = args.iter().map(|arg| arg.value.clone()).collect::<Vec<_>>();
+
+    let value = ctx.with(|context| {
+        function
+            .call(&JsValue::undefined(), &args, context)
+            .map_err(|e| BoaContext::report_exception(e, context))
+    })?;
+
+    Ok(BoaValue::from_value(ctx.clone(), value))
+}
+
+impl JSFunction for BoaFunction {
+    type RT = BoaEngine;
+
+    fn new(
+        ctx: <Self::RT as JSRuntime>::Context,
+        f: impl Fn(&mut <Self::RT as JSRuntime>::FunctionCallBack) + 'static,
+    ) -> Result<Self> {
+        let function = native_function(&ctx, 0, move |ctx, args| {
+            let mut cb = BoaFunctionCallBack {
+                ctx: ctx.clone(),
+                args: BoaArgs {
+                    next: 0,
+                    args: args.to_vec(),
+                },
+                ret: None,
+                error: None,
+            };
+
+            f(&mut cb);
+
+            call_result(cb.ret, cb.error)
+        });
+
+        Ok(Self { ctx, function })
+    }
+
+    fn call(&mut self, args: &[<Self::RT as JSRuntime>::Value]) -> Result<<Self::RT as JSRuntime>::Value> {
+        call_function(&self.ctx, &self.function, args)
+    }
+}
+
+pub struct BoaFunctionVariadic {
+    pub ctx: BoaContext,
+    pub function: JsFunction,
+}
+
+pub struct BoaFunctionCallBackVariadic {
+    ctx: BoaContext,
+    args: BoaVariadicArgsInternal,
+    ret: Option<JsValue>,
+    error: Option<JsError>,
+}
+
+pub struct BoaVariadicArgsInternal {
+    next: usize,
+    args: Vec<JsValue>,
+}
+
+impl Iterator for BoaVariadicArgsInternal {
+    type Item = JsValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.args.get(self.next)?.clone();
+        self.next += 1;
+        Some(value)
+    }
+}
+
+impl VariadicArgsInternal for BoaVariadicArgsInternal {
+    type RT = BoaEngine;
+
+    fn get(&self, index: usize, ctx: <Self::RT as JSRuntime>::Context) -> Option<<Self::RT as JSRuntime>::Value> {
+        let value = self.args.get(index)?;
+
+        Some(BoaValue::from_value(ctx, value.clone()))
+    }
+
+    fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    fn as_vec(&self, ctx: <Self::RT as JSRuntime>::Context) -> Vec<<Self::RT as JSRuntime>::Value> {
+        self.args
+            .iter()
+            .map(|value| BoaValue::from_value(ctx.clone(), value.clone()))
+            .collect()
+    }
+
+    fn variadic(&self, ctx: <Self::RT as JSRuntime>::Context) -> <Self::RT as JSRuntime>::VariadicArgs {
+        BoaVariadicArgs { args: self.as_vec(ctx) }
+    }
+
+    fn variadic_start(
+        &self,
+        start: usize,
+        ctx: <Self::RT as JSRuntime>::Context,
+    ) -> <Self::RT as JSRuntime>::VariadicArgs {
+        BoaVariadicArgs {
+            args: self
+                .args
+                .iter()
+                .skip(start)
+                .map(|value| BoaValue::from_value(ctx.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+pub struct BoaVariadicArgs {
+    args: Vec<BoaValue>,
+}
+
+impl VariadicArgs for BoaVariadicArgs {
+    type RT = BoaEngine;
+
+    fn get(&self, index: usize) -> Option<&<Self::RT as JSRuntime>::Value> {
+        self.args.get(index)
+    }
+
+    fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    fn as_vec(&self) -> &Vec<<Self::RT as JSRuntime>::Value> {
+        &self.args
+    }
+
+    fn as_vec_as<T>(&self) -> Vec<T>
+    where
+        <Self::RT as JSRuntime>::Value: IntoRustValue<T>,
+    {
+        self.args.iter().map(|x| x.to_rust_value().unwrap()).collect()
+    }
+
+    fn get_as<T>(&self, index: usize) -> Option<T>
+    where
+        <Self::RT as JSRuntime>::Value: IntoRustValue<T>,
+    {
+        self.args.get(index).map(|x| x.to_rust_value().unwrap())
+    }
+}
+
+impl JSFunctionCallBackVariadic for BoaFunctionCallBackVariadic {
+    type RT = BoaEngine;
+
+    fn context(&mut self) -> <Self::RT as JSRuntime>::Context {
+        self.ctx.clone()
+    }
+
+    fn args(&mut self) -> &<Self::RT as JSRuntime>::VariadicArgsInternal {
+        &self.args
+    }
+
+    fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    fn error(&mut self, error: impl Display) {
+        self.error = Some(BoaContext::error(error));
+    }
+
+    fn ret(&mut self, value: <Self::RT as JSRuntime>::Value) {
+        self.ret = Some(value.value);
+    }
+}
+
+impl JSFunctionVariadic for BoaFunctionVariadic {
+    type RT = BoaEngine;
+
+    fn new(
+        ctx: <Self::RT as JSRuntime>::Context,
+        f: impl Fn(&mut <Self::RT as JSRuntime>::FunctionCallBackVariadic) + 'static,
+    ) -> Result<Self> {
+        let function = native_function(&ctx, 0, move |ctx, args| {
+            let mut cb = BoaFunctionCallBackVariadic {
+                ctx: ctx.clone(),
+                args: BoaVariadicArgsInternal {
+                    next: 0,
+                    args: args.to_vec(),
+                },
+                ret: None,
+                error: None,
+            };
+
+            f(&mut cb);
+
+            call_result(cb.ret, cb.error)
+        });
+
+        Ok(Self { ctx, function })
+    }
+
+    fn call(&mut self, args: &[<Self::RT as JSRuntime>::Value]) -> Result<<Self::RT as JSRuntime>::Value> {
+        call_function(&self.ctx, &self.function, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_webexecutor::js::{IntoJSValue, JSContext, JSObject, JSValue};
+
+    use super::*;
+
+    #[test]
+    fn function_test() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let function = BoaFunction::new(context.clone(), |cb| {
+            let ctx = cb.context();
+            let Some(value) = cb.args().get(0, ctx.clone()) else {
+                cb.error("no argument");
+                return;
+            };
+
+            let value = value.as_number().unwrap() * 2.0;
+            cb.ret(value.to_js_value(ctx).unwrap());
+        })
+        .unwrap();
+
+        let obj = context.new_global_object("test").unwrap();
+        obj.set_method("double", &function).unwrap();
+
+        assert_eq!(context.run("test.double(21)").unwrap().as_number().unwrap(), 42.0);
+
+        let err = context.run("test.double()").err().unwrap();
+        assert!(err.to_string().contains("no argument"));
+    }
+
+    #[test]
+    fn function_call() {
+        let mut engine = BoaEngine::new();
+        let context = engine.new_context().unwrap();
+
+        let mut function = BoaFunction::new(context.clone(), |cb| {
+            let ctx = cb.context();
+            let sum: f64 = cb
+                .args()
+                .as_vec(ctx.clone())
+                .iter()
+                .map(|v| v.as_number().unwrap())
+                .sum();
+            cb.ret(sum.to_js_value(ctx).unwrap());
+        })
+        .unwrap();
+
+        let args: [BoaValue; 2] = [1.to_js_value(context.clone()).unwrap(), 2.to_js_value(context).unwrap()];
+
+        assert_eq!(function.call(&args).unwrap().as_number().unwrap(), 3.0);
+    }
+
+    #[test]
+    fn function_variadic() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let function = BoaFunctionVariadic::new(context.clone(), |cb| {
+            let ctx = cb.context();
+            let args = cb.args().variadic_start(1, ctx.clone());
+            let joined = args.as_vec_as::<String>().join("-");
+            cb.ret(joined.to_js_value(ctx).unwrap());
+        })
+        .unwrap();
+
+        let obj = context.new_global_object("test").unwrap();
+        obj.set_method_variadic("join", &function).unwrap();
+
+        assert_eq!(
+            context.run("test.join(0, 'a', 'b', 3)").unwrap().as_string().unwrap(),
+            "a-b-3"
+        );
+    }
+}
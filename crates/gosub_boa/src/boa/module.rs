@@ -0,0 +1,92 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use boa_engine::module::{self, Referrer};
+use boa_engine::{Context, JsError, JsNativeError, JsResult, JsString, Module, Source};
+use url::Url;
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSError, ModuleLoader};
+use gosub_webexecutor::Error;
+
+/// Module loader of a boa context. Boa builds the module graphs itself and asks this loader for every import, which
+/// resolves and fetches them with the `ModuleLoader` of the document.
+#[derive(Default)]
+pub(crate) struct BoaModuleLoader {
+    loader: RefCell<Option<Rc<ModuleLoader>>>,
+    /// Modules by URL, so a module that is imported more than once is only evaluated once
+    modules: RefCell<HashMap<Url, Module>>,
+    /// URLs of the modules, imports are resolved relative to the module that imports them
+    urls: RefCell<HashMap<Module, Url>>,
+}
+
+impl BoaModuleLoader {
+    pub(crate) fn set_loader(&self, loader: Rc<ModuleLoader>) {
+        *self.loader.borrow_mut() = Some(loader);
+    }
+
+    fn loader(&self) -> Result<Rc<ModuleLoader>> {
+        self.loader.borrow().clone().ok_or_else(|| {
+            Error::JS(JSError::Module(
+                "modules can't be used without a module loader".to_owned(),
+            ))
+            .into()
+        })
+    }
+
+    /// Parses the module at `url`, its imports are loaded when it's evaluated. An inline module script has no URL,
+    /// its imports are resolved against the document and it can't be imported itself.
+    pub(crate) fn parse(&self, url: Option<&Url>, source: &str, context: &mut Context) -> JsResult<Module> {
+        let module = Module::parse(Source::from_bytes(source), None, context)?;
+
+        let url = match url {
+            Some(url) => {
+                self.modules
+                    .borrow_mut()
+                    .entry(url.clone())
+                    .or_insert_with(|| module.clone());
+                url.clone()
+            }
+            None => self.loader().map_err(type_error)?.base().clone(),
+        };
+        self.urls.borrow_mut().insert(module.clone(), url);
+
+        Ok(module)
+    }
+
+    fn load(&self, referrer: &Referrer, specifier: &str, context: &mut Context) -> JsResult<Module> {
+        let loader = self.loader().map_err(type_error)?;
+
+        // classic scripts have no URL, their imports are resolved against the document
+        let referrer = match referrer {
+            Referrer::Module(module) => self.urls.borrow().get(module).cloned(),
+            Referrer::Realm(_) | Referrer::Script(_) => None,
+        };
+
+        let url = loader.resolve(specifier, referrer.as_ref()).map_err(type_error)?;
+        if let Some(module) = self.modules.borrow().get(&url) {
+            return Ok(module.clone());
+        }
+
+        let source = loader.fetch(&url).map_err(type_error)?;
+        self.parse(Some(&url), &source, context)
+    }
+}
+
+impl module::ModuleLoader for BoaModuleLoader {
+    fn load_imported_module(
+        &self,
+        referrer: Referrer,
+        specifier: JsString,
+        finish_load: Box<dyn FnOnce(JsResult<Module>, &mut Context)>,
+        context: &mut Context,
+    ) {
+        let result = self.load(&referrer, &specifier.to_std_string_escaped(), context);
+        finish_load(result, context);
+    }
+}
+
+fn type_error(error: anyhow::Error) -> JsError {
+    JsNativeError::typ().with_message(error.to_string()).into()
+}
@@ -0,0 +1,282 @@
+use core::fmt::Display;
+
+use boa_engine::property::PropertyDescriptor;
+use boa_engine::{js_string, JsError, JsObject, JsString, JsValue};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{IntoRustValue, JSError, JSGetterCallback, JSObject, JSRuntime, JSSetterCallback};
+use gosub_webexecutor::Error;
+
+use crate::{native_function, BoaContext, BoaEngine, BoaFunction, BoaFunctionVariadic, BoaValue};
+
+pub struct BoaObject {
+    pub ctx: BoaContext,
+    pub value: JsObject,
+}
+
+impl BoaObject {
+    pub fn new(ctx: BoaContext) -> Result<BoaObject> {
+        let value = ctx.with(|context| JsObject::with_object_proto(context.intrinsics()));
+        Ok(BoaObject { ctx, value })
+    }
+
+    pub fn from_object(ctx: BoaContext, value: JsObject) -> Self {
+        Self { ctx, value }
+    }
+}
+
+pub struct GetterCallback {
+    ctx: BoaContext,
+    ret: JsValue,
+    error: Option<JsError>,
+}
+
+impl JSGetterCallback for GetterCallback {
+    type RT = BoaEngine;
+
+    fn context(&mut self) -> &mut <Self::RT as JSRuntime>::Context {
+        &mut self.ctx
+    }
+
+    fn error(&mut self, error: impl Display) {
+        self.error = Some(BoaContext::error(error));
+    }
+
+    fn ret(&mut self, value: <Self::RT as JSRuntime>::Value) {
+        self.ret = value.value;
+    }
+}
+
+pub struct SetterCallback {
+    ctx: BoaContext,
+    value: BoaValue,
+    error: Option<JsError>,
+}
+
+impl JSSetterCallback for SetterCallback {
+    type RT = BoaEngine;
+
+    fn context(&mut self) -> &mut <Self::RT as JSRuntime>::Context {
+        &mut self.ctx
+    }
+
+    fn error(&mut self, error: impl Display) {
+        self.error = Some(BoaContext::error(error));
+    }
+
+    fn value(&mut self) -> &<Self::RT as JSRuntime>::Value {
+        &self.value
+    }
+}
+
+impl JSObject for BoaObject {
+    type RT = BoaEngine;
+
+    fn set_property(&self, name: &str, value: &BoaValue) -> Result<()> {
+        self.ctx.with(|context| {
+            self.value
+                .set(JsString::from(name), value.value.clone(), true, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+
+    fn get_property(&self, name: &str) -> Result<<Self::RT as JSRuntime>::Value> {
+        let value = self.ctx.with(|context| {
+            self.value
+                .get(JsString::from(name), context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaValue::from_value(self.ctx.clone(), value))
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        let names = self.ctx.with(|context| {
+            let object = context.intrinsics().constructors().object().constructor();
+
+            object
+                .get(js_string!("keys"), context)
+                .and_then(|keys| {
+                    let Some(keys) = keys.as_callable() else {
+                        return Err(BoaContext::error("Object.keys is not a function"));
+                    };
+
+                    keys.call(&object.clone().into(), &[self.value.clone().into()], context)
+                })
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        BoaValue::from_value(self.ctx.clone(), names).to_rust_value()
+    }
+
+    fn call_method(
+        &self,
+        name: &str,
+        args: &[&<Self::RT as JSRuntime>::Value],
+    ) -> Result<<Self::RT as JSRuntime>::Value> {
+        let func = self.get_property(name)?.value;
+
+        let Some(func) = func.as_callable() else {
+            return Err(Error::JS(JSError::Generic("property is not a function".to_owned())).into());
+        };
+
+        let args: Vec<JsValue> = args.iter().map(|v| v.value.clone()).collect();
+
+        let ret = self.ctx.with(|context| {
+            func.call(&self.value.clone().into(), &args, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaValue::from_value(self.ctx.clone(), ret))
+    }
+
+    fn set_method(&self, name: &str, func: &BoaFunction) -> Result<()> {
+        self.set_property(
+            name,
+            &BoaValue::from_value(self.ctx.clone(), func.function.clone().into()),
+        )
+    }
+
+    fn set_method_variadic(&self, name: &str, func: &BoaFunctionVariadic) -> Result<()> {
+        self.set_property(
+            name,
+            &BoaValue::from_value(self.ctx.clone(), func.function.clone().into()),
+        )
+    }
+
+    fn set_property_accessor(
+        &self,
+        name: &str,
+        getter: Box<dyn Fn(&mut <Self::RT as JSRuntime>::GetterCB)>,
+        setter: Box<dyn Fn(&mut <Self::RT as JSRuntime>::SetterCB)>,
+    ) -> Result<()> {
+        let getter = native_function(&self.ctx, 0, move |ctx, _| {
+            let mut gc = GetterCallback {
+                ctx: ctx.clone(),
+                ret: JsValue::undefined(),
+                error: None,
+            };
+
+            getter(&mut gc);
+
+            match gc.error {
+                Some(error) => Err(error),
+                None => Ok(gc.ret),
+            }
+        });
+
+        let setter = native_function(&self.ctx, 1, move |ctx, args| {
+            let value = args.first().cloned().unwrap_or_else(JsValue::undefined);
+
+            let mut sc = SetterCallback {
+                ctx: ctx.clone(),
+                value: BoaValue::from_value(ctx.clone(), value),
+                error: None,
+            };
+
+            setter(&mut sc);
+
+            match sc.error {
+                Some(error) => Err(error),
+                None => Ok(JsValue::undefined()),
+            }
+        });
+
+        let descriptor = PropertyDescriptor::builder()
+            .get(getter)
+            .set(setter)
+            .enumerable(true)
+            .configurable(true)
+            .build();
+
+        self.ctx.with(|context| {
+            self.value
+                .define_property_or_throw(JsString::from(name), descriptor, context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use gosub_webexecutor::js::{IntoJSValue, JSContext, JSValue};
+
+    use super::*;
+
+    #[test]
+    fn properties() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let obj = context.new_global_object("test").unwrap();
+        obj.set_property("number", &1234.to_js_value(context.clone()).unwrap())
+            .unwrap();
+        obj.set_property("string", &"Hello".to_js_value(context.clone()).unwrap())
+            .unwrap();
+
+        assert_eq!(obj.get_property("number").unwrap().as_number().unwrap(), 1234.0);
+        assert_eq!(context.run("test.string").unwrap().as_string().unwrap(), "Hello");
+        assert_eq!(obj.keys().unwrap(), vec!["number", "string"]);
+    }
+
+    #[test]
+    fn call_method() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let obj = context
+            .run("({ base: 40, add(a) { return this.base + a } })")
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let arg = 2.to_js_value(context.clone()).unwrap();
+        assert_eq!(obj.call_method("add", &[&arg]).unwrap().as_number().unwrap(), 42.0);
+        assert!(obj.call_method("base", &[]).is_err());
+    }
+
+    #[test]
+    fn accessor() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let field = Rc::new(RefCell::new(1.0));
+        let obj = context.new_global_object("test").unwrap();
+
+        let getter = {
+            let field = Rc::clone(&field);
+            Box::new(move |cb: &mut GetterCallback| {
+                let value = field.borrow().to_js_value(cb.context().clone()).unwrap();
+                cb.ret(value);
+            })
+        };
+
+        let setter = {
+            let field = Rc::clone(&field);
+            Box::new(move |cb: &mut SetterCallback| match cb.value().as_number() {
+                Ok(value) if value >= 0.0 => *field.borrow_mut() = value,
+                _ => cb.error("field must be positive"),
+            })
+        };
+
+        obj.set_property_accessor("field", getter, setter).unwrap();
+
+        assert_eq!(
+            context
+                .run("test.field += 41; test.field")
+                .unwrap()
+                .as_number()
+                .unwrap(),
+            42.0
+        );
+        assert_eq!(*field.borrow(), 42.0);
+        assert!(context.run("test.field = -1").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use boa_engine::builtins::promise::PromiseState as BoaPromiseState;
+use boa_engine::object::builtins::{JsPromise, ResolvingFunctions};
+use boa_engine::{js_string, JsValue};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSError, JSPromise, JSRuntime, PromiseState};
+use gosub_webexecutor::Error;
+
+use crate::{BoaContext, BoaEngine, BoaFunction, BoaValue};
+
+pub struct BoaPromise {
+    pub ctx: BoaContext,
+    pub value: JsPromise,
+    /// Only set for promises that are created from rust
+    functions: Option<ResolvingFunctions>,
+}
+
+impl BoaPromise {
+    pub fn from_promise(ctx: BoaContext, value: JsPromise) -> Self {
+        Self {
+            ctx,
+            value,
+            functions: None,
+        }
+    }
+
+    fn functions(&self) -> Result<&ResolvingFunctions> {
+        self.functions.as_ref().ok_or_else(|| {
+            Error::JS(JSError::Generic(
+                "only promises created from rust can be settled".to_owned(),
+            ))
+            .into()
+        })
+    }
+
+    fn settle(&self, resolve: bool, value: &BoaValue) -> Result<()> {
+        let functions = self.functions()?;
+        let function = if resolve { &functions.resolve } else { &functions.reject };
+
+        self.ctx.with(|context| {
+            function
+                .call(&JsValue::undefined(), &[value.value.clone()], context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(())
+    }
+}
+
+impl JSPromise for BoaPromise {
+    type RT = BoaEngine;
+
+    fn new(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self> {
+        let (value, functions) = ctx.with(JsPromise::new_pending);
+
+        Ok(Self {
+            ctx,
+            value,
+            functions: Some(functions),
+        })
+    }
+
+    fn resolve(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()> {
+        self.settle(true, value)
+    }
+
+    fn reject(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()> {
+        self.settle(false, value)
+    }
+
+    fn state(&self) -> PromiseState<<Self::RT as JSRuntime>::Value> {
+        match self.value.state() {
+            BoaPromiseState::Pending => PromiseState::Pending,
+            BoaPromiseState::Fulfilled(value) => PromiseState::Fulfilled(BoaValue::from_value(self.ctx.clone(), value)),
+            BoaPromiseState::Rejected(value) => PromiseState::Rejected(BoaValue::from_value(self.ctx.clone(), value)),
+        }
+    }
+
+    fn then(&self, on_fulfilled: &BoaFunction, on_rejected: &BoaFunction) -> Result<Self> {
+        let value = self.ctx.with(|context| {
+            self.value
+                .get(js_string!("then"), context)
+                .and_then(|then| {
+                    let Some(then) = then.as_callable() else {
+                        return Err(BoaContext::error("promise.then is not a function"));
+                    };
+
+                    then.call(
+                        &self.value.clone().into(),
+                        &[
+                            on_fulfilled.function.clone().into(),
+                            on_rejected.function.clone().into(),
+                        ],
+                        context,
+                    )
+                })
+                .and_then(|promise| JsPromise::from_object(promise.to_object(context)?))
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(Self::from_promise(self.ctx.clone(), value))
+    }
+
+    fn as_value(&self) -> <Self::RT as JSRuntime>::Value {
+        BoaValue::from_value(self.ctx.clone(), self.value.clone().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use gosub_webexecutor::js::{
+        Args, IntoJSValue, JSContext, JSFunction, JSFunctionCallBack, JSObject, JSRuntime, JSValue, PromiseState,
+    };
+
+    use super::*;
+
+    #[test]
+    fn resolve_from_rust() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let promise = BoaPromise::new(context.clone()).unwrap();
+        let obj = context.new_global_object("test").unwrap();
+        obj.set_property("promise", &promise.as_value()).unwrap();
+
+        context
+            .run("globalThis.result = 'pending'; test.promise.then((value) => { globalThis.result = value })")
+            .unwrap();
+        assert!(matches!(promise.state(), PromiseState::Pending));
+
+        promise.resolve(&"done".to_js_value(context.clone()).unwrap()).unwrap();
+        context.run_microtasks();
+
+        let PromiseState::Fulfilled(value) = promise.state() else {
+            panic!("promise should be fulfilled");
+        };
+        assert_eq!(value.as_string().unwrap(), "done");
+        assert_eq!(context.run("result").unwrap().as_string().unwrap(), "done");
+    }
+
+    #[test]
+    fn await_from_rust() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context
+            .run("(async () => { await null; throw new TypeError('failed') })()")
+            .unwrap();
+        assert!(value.is_promise());
+
+        let rejected = Rc::new(RefCell::new(String::new()));
+
+        let on_fulfilled = BoaFunction::new(context.clone(), |cb| {
+            let ret = BoaValue::new_undefined(cb.context()).unwrap();
+            cb.ret(ret);
+        })
+        .unwrap();
+
+        let on_rejected = {
+            let rejected = Rc::clone(&rejected);
+            BoaFunction::new(context.clone(), move |cb| {
+                let ctx = cb.context();
+                let reason = cb.args().get(0, ctx.clone()).unwrap();
+                *rejected.borrow_mut() = reason.as_string().unwrap();
+                cb.ret(BoaValue::new_undefined(ctx).unwrap());
+            })
+            .unwrap()
+        };
+
+        value.as_promise().unwrap().then(&on_fulfilled, &on_rejected).unwrap();
+        context.run_microtasks();
+
+        assert_eq!(*rejected.borrow(), "TypeError: failed");
+    }
+
+    #[test]
+    fn settle_promise_from_javascript() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let promise = context.run("Promise.resolve(42)").unwrap().as_promise().unwrap();
+
+        assert!(matches!(promise.state(), PromiseState::Fulfilled(value) if value.as_number().unwrap() == 42.0));
+        assert!(promise
+            .resolve(&BoaValue::new_undefined(context.clone()).unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,519 @@
+use boa_engine::object::builtins::{JsArray, JsArrayBuffer, JsDataView, JsDate, JsPromise, JsTypedArray};
+use boa_engine::{js_string, Context, JsBigInt, JsObject, JsResult, JsString, JsSymbol, JsValue};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{
+    ArrayConversion, AsArray, IntoJSValue, JSArray, JSError, JSObject, JSRuntime, JSType, JSValue, Ref, TypedArrayKind,
+};
+use gosub_webexecutor::Error;
+
+use crate::{BoaArray, BoaContext, BoaEngine, BoaObject, BoaPromise};
+
+pub struct BoaValue {
+    pub context: BoaContext,
+    pub value: JsValue,
+}
+
+impl BoaValue {
+    pub fn from_value(ctx: BoaContext, value: JsValue) -> Self {
+        Self { context: ctx, value }
+    }
+}
+
+impl From<BoaArray> for BoaValue {
+    fn from(array: BoaArray) -> Self {
+        Self {
+            context: array.ctx,
+            value: array.value.into(),
+        }
+    }
+}
+
+impl From<BoaObject> for BoaValue {
+    fn from(object: BoaObject) -> Self {
+        Self {
+            context: object.ctx,
+            value: object.value.into(),
+        }
+    }
+}
+
+impl From<BoaPromise> for BoaValue {
+    fn from(promise: BoaPromise) -> Self {
+        Self {
+            context: promise.ctx,
+            value: promise.value.into(),
+        }
+    }
+}
+
+fn conversion_error(message: &str) -> anyhow::Error {
+    Error::JS(JSError::Conversion(message.to_owned())).into()
+}
+
+/// Creates an object with the global constructor of the given name, like `new Uint8Array(...args)`
+fn construct(name: &str, args: &[JsValue], context: &mut Context) -> JsResult<JsObject> {
+    let constructor = context.global_object().get(JsString::from(name), context)?;
+
+    let Some(constructor) = constructor.as_constructor() else {
+        return Err(BoaContext::error(format!("{name} is not a constructor")));
+    };
+
+    constructor.construct(args, None, context)
+}
+
+impl BoaValue {
+    fn object_is(&self, check: impl FnOnce(JsObject) -> bool) -> bool {
+        self.value.as_object().is_some_and(|object| check(object.clone()))
+    }
+
+    /// Copies the bytes of a view (a TypedArray or DataView) or of a whole ArrayBuffer
+    fn read_bytes(&self, context: &mut Context) -> JsResult<Vec<u8>> {
+        let Some(object) = self.value.as_object() else {
+            return Err(BoaContext::error("could not convert to bytes"));
+        };
+
+        let args = if JsArrayBuffer::from_object(object.clone()).is_ok() {
+            vec![self.value.clone()]
+        } else {
+            vec![
+                object.get(js_string!("buffer"), context)?,
+                object.get(js_string!("byteOffset"), context)?,
+                object.get(js_string!("byteLength"), context)?,
+            ]
+        };
+
+        let bytes = construct("Uint8Array", &args, context)?;
+        let length = bytes.get(js_string!("length"), context)?.to_u32(context)?;
+
+        (0..length)
+            .map(|index| bytes.get(index, context)?.to_uint8(context))
+            .collect()
+    }
+}
+
+impl AsArray for BoaValue {
+    type Runtime = BoaEngine;
+
+    fn array(&self) -> Result<Ref<<Self::Runtime as JSRuntime>::Array>> {
+        Ok(Ref::Owned(self.as_array()?))
+    }
+}
+
+impl JSValue for BoaValue {
+    type RT = BoaEngine;
+
+    fn as_string(&self) -> Result<String> {
+        let value = self.context.with(|context| {
+            self.value
+                .to_string(context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(value.to_std_string_escaped())
+    }
+
+    fn as_number(&self) -> Result<f64> {
+        Ok(self.context.with(|context| {
+            self.value
+                .to_number(context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?)
+    }
+
+    fn as_bool(&self) -> Result<bool> {
+        Ok(self.value.to_boolean())
+    }
+
+    fn as_object(&self) -> Result<<Self::RT as JSRuntime>::Object> {
+        let object = self.context.with(|context| {
+            self.value
+                .to_object(context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(BoaObject::from_object(self.context.clone(), object))
+    }
+
+    fn as_array(&self) -> Result<<Self::RT as JSRuntime>::Array> {
+        let Some(object) = self.value.as_object().filter(|object| object.is_array()) else {
+            return Err(Error::JS(JSError::Conversion("could not convert to array".to_owned())).into());
+        };
+
+        let array = JsArray::from_object(object.clone())
+            .map_err(|_| Error::JS(JSError::Conversion("could not convert to array".to_owned())))?;
+
+        Ok(BoaArray::from_array(self.context.clone(), array))
+    }
+
+    fn as_promise(&self) -> Result<<Self::RT as JSRuntime>::Promise> {
+        let promise = self
+            .value
+            .as_object()
+            .and_then(|object| JsPromise::from_object(object.clone()).ok())
+            .ok_or_else(|| conversion_error("could not convert to promise"))?;
+
+        Ok(BoaPromise::from_promise(self.context.clone(), promise))
+    }
+
+    fn as_bigint(&self) -> Result<i64> {
+        let Some(bigint) = self.value.as_bigint() else {
+            return Err(conversion_error("could not convert to bigint"));
+        };
+
+        bigint
+            .to_string()
+            .parse()
+            .map_err(|_| conversion_error("bigint does not fit in an i64"))
+    }
+
+    fn as_symbol_description(&self) -> Result<Option<String>> {
+        let Some(symbol) = self.value.as_symbol() else {
+            return Err(conversion_error("could not convert to symbol"));
+        };
+
+        Ok(symbol
+            .description()
+            .map(|description| description.to_std_string_escaped()))
+    }
+
+    fn as_date(&self) -> Result<f64> {
+        if !self.is_date() {
+            return Err(conversion_error("could not convert to date"));
+        }
+
+        self.as_object()?.call_method("getTime", &[])?.as_number()
+    }
+
+    fn as_bytes(&self) -> Result<Vec<u8>> {
+        if !self.is_array_buffer()
+            && !self.is_typed_array()
+            && !self.object_is(|object| JsDataView::from_object(object).is_ok())
+        {
+            return Err(conversion_error("could not convert to bytes"));
+        }
+
+        Ok(self.context.with(|context| {
+            self.read_bytes(context)
+                .map_err(|e| BoaContext::report_exception(e, context))
+        })?)
+    }
+
+    fn typed_array_kind(&self) -> Option<TypedArrayKind> {
+        if !self.is_typed_array() {
+            return None;
+        }
+
+        let object = self.value.as_object()?;
+        let name = self
+            .context
+            .with(|context| object.get(JsSymbol::to_string_tag(), context))
+            .ok()?;
+
+        TypedArrayKind::from_name(&name.as_string()?.to_std_string_escaped())
+    }
+
+    fn is_string(&self) -> bool {
+        self.value.is_string()
+    }
+
+    fn is_number(&self) -> bool {
+        self.value.is_number()
+    }
+
+    fn is_bool(&self) -> bool {
+        self.value.is_boolean()
+    }
+
+    fn is_object(&self) -> bool {
+        self.value.is_object()
+    }
+
+    fn is_array(&self) -> bool {
+        self.value.as_object().is_some_and(JsObject::is_array)
+    }
+
+    fn is_null(&self) -> bool {
+        self.value.is_null()
+    }
+
+    fn is_undefined(&self) -> bool {
+        self.value.is_undefined()
+    }
+
+    fn is_function(&self) -> bool {
+        self.value.is_callable()
+    }
+
+    fn is_promise(&self) -> bool {
+        self.object_is(|object| JsPromise::from_object(object).is_ok())
+    }
+
+    fn is_bigint(&self) -> bool {
+        self.value.is_bigint()
+    }
+
+    fn is_symbol(&self) -> bool {
+        self.value.is_symbol()
+    }
+
+    fn is_date(&self) -> bool {
+        self.object_is(|object| JsDate::from_object(object).is_ok())
+    }
+
+    fn is_array_buffer(&self) -> bool {
+        self.object_is(|object| JsArrayBuffer::from_object(object).is_ok())
+    }
+
+    fn is_typed_array(&self) -> bool {
+        self.object_is(|object| JsTypedArray::from_object(object).is_ok())
+    }
+
+    fn type_of(&self) -> JSType {
+        if self.is_string() {
+            JSType::String
+        } else if self.is_number() {
+            JSType::Number
+        } else if self.is_bool() {
+            JSType::Boolean
+        } else if self.is_array() {
+            JSType::Array
+        } else if self.is_null() {
+            JSType::Null
+        } else if self.is_undefined() {
+            JSType::Undefined
+        } else if self.is_bigint() {
+            JSType::BigInt
+        } else if self.is_symbol() {
+            JSType::Symbol
+        } else if self.is_function() {
+            JSType::Function
+        } else if self.is_promise() {
+            JSType::Promise
+        } else if self.is_date() {
+            JSType::Date
+        } else if self.is_array_buffer() {
+            JSType::ArrayBuffer
+        } else if self.is_typed_array() {
+            JSType::TypedArray
+        } else if self.is_object() {
+            JSType::Object
+        } else {
+            JSType::Other(self.value.type_of().to_owned())
+        }
+    }
+
+    fn new_object(ctx: <Self::RT as JSRuntime>::Context) -> Result<<Self::RT as JSRuntime>::Object> {
+        BoaObject::new(ctx)
+    }
+
+    fn new_array<T: IntoJSValue<Self, Value = Self>>(
+        ctx: <Self::RT as JSRuntime>::Context,
+        value: &[T],
+    ) -> Result<<Self::RT as JSRuntime>::Array> {
+        value.to_js_array(ctx)
+    }
+
+    fn new_empty_array(ctx: <Self::RT as JSRuntime>::Context) -> Result<<Self::RT as JSRuntime>::Array> {
+        BoaArray::new(ctx, 0)
+    }
+
+    fn new_string(ctx: <Self::RT as JSRuntime>::Context, value: &str) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsString::from(value).into()))
+    }
+
+    fn new_number<N: Into<f64>>(ctx: <Self::RT as JSRuntime>::Context, value: N) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsValue::new(value.into())))
+    }
+
+    fn new_bool(ctx: <Self::RT as JSRuntime>::Context, value: bool) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsValue::new(value)))
+    }
+
+    fn new_null(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsValue::null()))
+    }
+
+    fn new_undefined(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsValue::undefined()))
+    }
+
+    fn new_bigint(ctx: <Self::RT as JSRuntime>::Context, value: i64) -> Result<Self> {
+        Ok(Self::from_value(ctx, JsBigInt::new(value).into()))
+    }
+
+    fn new_symbol(ctx: <Self::RT as JSRuntime>::Context, description: Option<&str>) -> Result<Self> {
+        let Some(symbol) = JsSymbol::new(description.map(JsString::from)) else {
+            return Err(conversion_error("could not create a symbol"));
+        };
+
+        Ok(Self::from_value(ctx, symbol.into()))
+    }
+
+    fn new_date(ctx: <Self::RT as JSRuntime>::Context, time: f64) -> Result<Self> {
+        let date = ctx.with(|context| {
+            construct("Date", &[JsValue::new(time)], context).map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(Self::from_value(ctx, date.into()))
+    }
+
+    fn new_array_buffer(ctx: <Self::RT as JSRuntime>::Context, data: &[u8]) -> Result<Self> {
+        let buffer = ctx.with(|context| {
+            JsArrayBuffer::from_byte_block(data.to_vec(), context).map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(Self::from_value(ctx, buffer.into()))
+    }
+
+    fn new_typed_array(ctx: <Self::RT as JSRuntime>::Context, kind: TypedArrayKind, data: &[u8]) -> Result<Self> {
+        if data.len() % kind.element_size() != 0 {
+            return Err(conversion_error("the data is not a whole number of elements"));
+        }
+
+        let buffer = Self::new_array_buffer(ctx.clone(), data)?;
+
+        let array = ctx.with(|context| {
+            construct(kind.name(), &[buffer.value], context).map_err(|e| BoaContext::report_exception(e, context))
+        })?;
+
+        Ok(Self::from_value(ctx, array.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_webexecutor::js::{IntoRustValue, JSContext};
+
+    use super::*;
+
+    #[test]
+    fn string() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run(r#""Hello World!""#).unwrap();
+
+        assert!(value.is_string());
+        assert_eq!(value.type_of(), JSType::String);
+        assert_eq!(value.as_string().unwrap(), "Hello World!");
+    }
+
+    #[test]
+    fn number() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("1234 + 0.5").unwrap();
+
+        assert!(value.is_number());
+        assert_eq!(value.type_of(), JSType::Number);
+        let number: f64 = value.to_rust_value().unwrap();
+        assert_eq!(number, 1234.5);
+    }
+
+    #[test]
+    fn types() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let cases = [
+            ("true", JSType::Boolean),
+            ("null", JSType::Null),
+            ("undefined", JSType::Undefined),
+            ("[1, 2]", JSType::Array),
+            ("({})", JSType::Object),
+            ("(function () {})", JSType::Function),
+            ("Symbol('a')", JSType::Symbol),
+            ("10n", JSType::BigInt),
+            ("new Date()", JSType::Date),
+            ("Promise.resolve()", JSType::Promise),
+            ("new ArrayBuffer(4)", JSType::ArrayBuffer),
+            ("new Float32Array(2)", JSType::TypedArray),
+        ];
+
+        for (code, expected) in cases {
+            assert_eq!(context.run(code).unwrap().type_of(), expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn new_values() {
+        let mut engine = BoaEngine::new();
+        let context = engine.new_context().unwrap();
+
+        let value = BoaValue::new_string(context.clone(), "Hello").unwrap();
+        assert_eq!(value.as_string().unwrap(), "Hello");
+
+        let value = BoaValue::new_number(context.clone(), 42).unwrap();
+        assert_eq!(value.as_number().unwrap(), 42.0);
+
+        let value = BoaValue::new_bool(context.clone(), true).unwrap();
+        assert!(value.as_bool().unwrap());
+
+        assert!(BoaValue::new_null(context.clone()).unwrap().is_null());
+        assert!(BoaValue::new_undefined(context).unwrap().is_undefined());
+    }
+
+    #[test]
+    fn bigint_and_symbol() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        assert_eq!(context.run("2n ** 40n").unwrap().as_bigint().unwrap(), 1 << 40);
+        assert!(context.run("2n ** 64n").unwrap().as_bigint().is_err());
+
+        let value = context.run("Symbol('token')").unwrap();
+        assert_eq!(value.as_symbol_description().unwrap().as_deref(), Some("token"));
+
+        let value = BoaValue::new_symbol(context.clone(), None).unwrap();
+        assert_eq!(value.as_symbol_description().unwrap(), None);
+        assert_eq!(BoaValue::new_bigint(context, -5).unwrap().as_bigint().unwrap(), -5);
+    }
+
+    #[test]
+    fn date() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("new Date(Date.UTC(2024, 0, 1))").unwrap();
+        assert_eq!(value.as_date().unwrap(), 1_704_067_200_000.0);
+
+        let value = BoaValue::new_date(context.clone(), 0.0).unwrap();
+        context
+            .new_global_object("test")
+            .unwrap()
+            .set_property("date", &value)
+            .unwrap();
+        assert_eq!(
+            context.run("test.date.toISOString()").unwrap().as_string().unwrap(),
+            "1970-01-01T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn buffers() {
+        let mut engine = BoaEngine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("new Uint16Array([1, 258]).buffer").unwrap();
+        assert_eq!(value.as_bytes().unwrap(), vec![1, 0, 2, 1]);
+
+        let value = context.run("new Uint8Array([1, 2, 3, 4]).subarray(1, 3)").unwrap();
+        assert_eq!(value.typed_array_kind(), Some(TypedArrayKind::Uint8));
+        assert_eq!(value.as_bytes().unwrap(), vec![2, 3]);
+
+        let value = BoaValue::new_typed_array(context.clone(), TypedArrayKind::Int16, &[255, 255, 2, 0]).unwrap();
+        context
+            .new_global_object("test")
+            .unwrap()
+            .set_property("array", &value)
+            .unwrap();
+        assert_eq!(context.run("test.array.join()").unwrap().as_string().unwrap(), "-1,2");
+        assert!(BoaValue::new_typed_array(context.clone(), TypedArrayKind::Int32, &[1, 2]).is_err());
+
+        let value = BoaValue::new_array_buffer(context, &[7, 8]).unwrap();
+        assert_eq!(value.as_bytes().unwrap(), vec![7, 8]);
+        assert_eq!(value.typed_array_kind(), None);
+    }
+}
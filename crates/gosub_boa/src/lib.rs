@@ -0,0 +1,7 @@
+//! Javascript engine backend on top of [Boa](https://boajs.dev), a javascript engine written in pure Rust.
+//!
+//! Unlike the V8 backend, this one also builds for `wasm32`.
+
+mod boa;
+
+pub use boa::*;
@@ -31,6 +31,9 @@ pub trait StorageAdapter: Send + Sync {
     /// can get mutable.
     fn set(&self, key: &str, value: Setting);
 
+    /// Removes a setting from the storage. Removing a key that is not stored does nothing.
+    fn remove(&self, key: &str);
+
     /// Retrieves all the settings in the storage in one go. This is used for preloading the settings
     /// into the ConfigStore and is more performant normally than calling get_setting manually for each
     /// setting.
@@ -107,8 +107,8 @@ impl Serialize for Setting {
     where
         S: Serializer,
     {
-        let s = self.to_string();
-        serializer.collect_str(&s)
+        // Serialize with the type prefix, so the setting can be deserialized again
+        serializer.collect_str(self)
     }
 }
 
@@ -136,7 +136,7 @@ impl Display for Setting {
                     result.push(',');
                 }
                 result.pop();
-                write!(f, "m: {result}")
+                write!(f, "m:{result}")
             }
         }
     }
@@ -56,7 +56,14 @@ impl StorageAdapter for JsonStorageAdapter {
         let mut lock = self.elements.lock().unwrap();
         lock.insert(key.to_owned(), value);
 
-        // self.write_file()
+        self.write_file(&lock);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut lock = self.elements.lock().unwrap();
+        if lock.remove(key).is_some() {
+            self.write_file(&lock);
+        }
     }
 
     fn all(&self) -> Result<HashMap<String, Setting>> {
@@ -94,18 +101,46 @@ impl JsonStorageAdapter {
         }
     }
 
-    /// Write the self.elements hashmap back to the file by truncating the file and writing the
-    /// data again.
-    #[allow(dead_code)]
-    fn write_file(&mut self) {
+    /// Write the elements back to the file by truncating the file and writing the data again.
+    fn write_file(&self, elements: &HashMap<String, Setting>) {
         // @TODO: We need some kind of OS lock file here. We should protect against concurrent threads but also
         // against concurrent processes.
-        let mut file = File::open(&self.path).expect("failed to open json file");
+        let mut file = File::options()
+            .write(true)
+            .open(&self.path)
+            .expect("failed to open json file");
 
-        let json = serde_json::to_string_pretty(&self.elements).expect("failed to serialize");
+        let json = serde_json::to_string_pretty(elements).expect("failed to serialize");
 
         file.set_len(0).expect("failed to truncate file");
         file.seek(std::io::SeekFrom::Start(0)).expect("failed to seek");
         file.write_all(json.as_bytes()).expect("failed to write file");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persists_settings() {
+        let path = std::env::temp_dir()
+            .join(format!("gosub-json-storage-{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = fs::remove_file(&path);
+
+        let adapter = JsonStorageAdapter::try_from(&path).unwrap();
+        adapter.set("foo", Setting::String("hello world".into()));
+        adapter.set("bar", Setting::UInt(42));
+        adapter.set("baz", Setting::Bool(true));
+        adapter.remove("baz");
+
+        let reloaded = JsonStorageAdapter::try_from(&path).unwrap();
+        assert_eq!(reloaded.get("foo"), Some(Setting::String("hello world".into())));
+        assert_eq!(reloaded.get("bar"), Some(Setting::UInt(42)));
+        assert_eq!(reloaded.get("baz"), None);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -30,6 +30,11 @@ impl StorageAdapter for MemoryStorageAdapter {
         lock.insert(key.to_owned(), value);
     }
 
+    fn remove(&self, key: &str) {
+        let mut lock = self.settings.lock().unwrap();
+        lock.remove(key);
+    }
+
     fn all(&self) -> Result<HashMap<String, Setting>> {
         let lock = self.settings.lock().unwrap();
         Ok(lock.clone())
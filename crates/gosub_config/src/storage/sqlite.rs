@@ -18,11 +18,17 @@ impl TryFrom<&String> for SqliteStorageAdapter {
 
         let query = "CREATE TABLE IF NOT EXISTS settings (
             id INTEGER PRIMARY KEY,
-            key TEXT NOT NULL,
+            key TEXT NOT NULL UNIQUE,
             value TEXT NOT NULL
         )";
         conn.execute(query)?;
 
+        // Tables that were created before keys were unique can hold several rows for a key. Only the last written
+        // row of every key is kept, so the unique index can be created.
+        let query = "DELETE FROM settings WHERE id NOT IN (SELECT MAX(id) FROM settings GROUP BY key);
+            CREATE UNIQUE INDEX IF NOT EXISTS settings_key ON settings(key)";
+        conn.execute(query)?;
+
         Ok(SqliteStorageAdapter {
             connection: Mutex::new(conn),
         })
@@ -33,11 +39,16 @@ impl StorageAdapter for SqliteStorageAdapter {
     fn get(&self, key: &str) -> Option<Setting> {
         let db_lock = self.connection.lock().unwrap();
 
-        let query = "SELECT * FROM settings WHERE key = :key";
+        let query = "SELECT value FROM settings WHERE key = :key";
         let mut statement = db_lock.prepare(query).unwrap();
         statement.bind((":key", key)).unwrap();
 
-        match Setting::from_str(key) {
+        if !matches!(statement.next(), Ok(sqlite::State::Row)) {
+            return None;
+        }
+        let value = statement.read::<String, _>(0).ok()?;
+
+        match Setting::from_str(&value) {
             Ok(setting) => Some(setting),
             Err(err) => {
                 warn!("problem reading from sqlite: {err}");
@@ -57,6 +68,16 @@ impl StorageAdapter for SqliteStorageAdapter {
         statement.next().unwrap();
     }
 
+    fn remove(&self, key: &str) {
+        let db_lock = self.connection.lock().unwrap();
+
+        let query = "DELETE FROM settings WHERE key = :key";
+        let mut statement = db_lock.prepare(query).unwrap();
+        statement.bind((":key", key)).unwrap();
+
+        statement.next().unwrap();
+    }
+
     fn all(&self) -> Result<HashMap<String, Setting>> {
         let db_lock = self.connection.lock().unwrap();
 
@@ -73,3 +94,42 @@ impl StorageAdapter for SqliteStorageAdapter {
         Ok(settings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn migrates_duplicate_keys() {
+        let path = std::env::temp_dir()
+            .join(format!("gosub-sqlite-storage-{}.db", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = fs::remove_file(&path);
+
+        // A table from before keys were unique, with two rows for the same key
+        let conn = sqlite::open(&path).unwrap();
+        conn.execute(format!(
+            "CREATE TABLE settings (id INTEGER PRIMARY KEY, key TEXT NOT NULL, value TEXT NOT NULL);
+            INSERT INTO settings (key, value) VALUES ('foo', '{}'), ('foo', '{}'), ('bar', '{}')",
+            Setting::UInt(1),
+            Setting::UInt(2),
+            Setting::Bool(true),
+        ))
+        .unwrap();
+        drop(conn);
+
+        let adapter = SqliteStorageAdapter::try_from(&path).unwrap();
+        assert_eq!(adapter.get("foo"), Some(Setting::UInt(2)));
+        assert_eq!(adapter.all().unwrap().len(), 2);
+
+        // Setting a key replaces its row instead of adding another one
+        adapter.set("foo", Setting::UInt(3));
+        assert_eq!(adapter.get("foo"), Some(Setting::UInt(3)));
+        assert_eq!(adapter.all().unwrap().len(), 2);
+
+        drop(adapter);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -6,6 +6,7 @@ use crate::stylesheet::{
     MatcherType,
 };
 use gosub_shared::errors::{CssError, CssResult};
+use gosub_shared::font::FontFace;
 use gosub_shared::traits::css3::CssOrigin;
 
 /*
@@ -68,12 +69,22 @@ pub fn convert_ast_to_stylesheet(css_ast: &CssNode, origin: CssOrigin, url: &str
 
     let mut sheet = CssStylesheet {
         rules: vec![],
+        font_faces: vec![],
         origin,
         url: url.to_string(),
         parse_log: vec![],
     };
 
     for node in css_ast.as_stylesheet() {
+        if let NodeType::AtRule { name, block, .. } = &*node.node_type {
+            if name.eq_ignore_ascii_case("font-face") {
+                if let Some(face) = block.as_ref().and_then(convert_font_face) {
+                    sheet.font_faces.push(face);
+                }
+            }
+            continue;
+        }
+
         if !node.is_rule() {
             continue;
         }
@@ -175,54 +186,85 @@ pub fn convert_ast_to_stylesheet(css_ast: &CssNode, origin: CssOrigin, url: &str
             rule.selectors.push(selector);
         }
 
-        for declaration in declarations.iter() {
-            if !declaration.is_block() {
-                continue;
+        for block in declarations.iter() {
+            if block.is_block() {
+                rule.declarations.extend(convert_declarations(block));
             }
+        }
 
-            let block = declaration.as_block();
-            for declaration in block.iter() {
-                if !declaration.is_declaration() {
-                    continue;
-                }
+        sheet.rules.push(rule);
+    }
+    Ok(sheet)
+}
 
-                let (property, nodes, important) = declaration.as_declaration();
+/// Converts the declarations in a block, declarations without any (valid) value are skipped
+pub fn convert_declarations(block: &CssNode) -> Vec<CssDeclaration> {
+    let mut declarations = vec![];
 
-                // Convert the nodes into CSS Values
-                let mut css_values = vec![];
-                for node in nodes.iter() {
-                    if let Ok(value) = CssValue::parse_ast_node(node) {
-                        css_values.push(value);
-                    }
-                }
+    for declaration in block.as_block().iter() {
+        if !declaration.is_declaration() {
+            continue;
+        }
 
-                if css_values.is_empty() {
-                    continue;
-                }
+        let (property, nodes, important) = declaration.as_declaration();
 
-                let value = if css_values.len() == 1 {
-                    css_values.pop().expect("unreachable")
-                } else {
-                    CssValue::List(css_values)
-                };
-
-                rule.declarations.push(CssDeclaration {
-                    property: property.clone(),
-                    value,
-                    important: *important,
-                });
+        // Convert the nodes into CSS Values
+        let mut css_values = vec![];
+        for node in nodes.iter() {
+            if let Ok(value) = CssValue::parse_ast_node(node) {
+                css_values.push(value);
             }
         }
 
-        sheet.rules.push(rule);
+        if css_values.is_empty() {
+            continue;
+        }
+
+        let value = if css_values.len() == 1 {
+            css_values.pop().expect("unreachable")
+        } else {
+            CssValue::List(css_values)
+        };
+
+        declarations.push(CssDeclaration {
+            property: property.clone(),
+            value,
+            important: *important,
+        });
     }
-    Ok(sheet)
+
+    declarations
+}
+
+/// Converts the block of a `@font-face` rule into a font face, or `None` when the face misses required descriptors
+fn convert_font_face(block: &CssNode) -> Option<FontFace> {
+    if !block.is_block() {
+        return None;
+    }
+
+    let descriptors = block
+        .as_block()
+        .iter()
+        .filter(|node| node.is_declaration())
+        .map(|node| {
+            let (property, nodes, _) = node.as_declaration();
+            let values = nodes
+                .iter()
+                .filter_map(|node| CssValue::parse_ast_node(node).ok())
+                .collect::<Vec<_>>();
+
+            (property.clone(), values)
+        })
+        .collect::<Vec<_>>();
+
+    FontFace::from_descriptors(&descriptors)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::Css3;
+    use gosub_shared::font::{FontDisplay, FontFaceStyle, FontSource};
     use gosub_shared::traits::ParserConfig;
 
     #[test]
@@ -282,4 +324,50 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn convert_font_faces() {
+        let stylesheet = Css3::parse_str(
+            r#"
+            @font-face {
+                font-family: "Open Sans";
+                src: local(Open Sans), url(/fonts/open-sans.woff2) format("woff2"), url("open-sans.ttf");
+                font-weight: 300 700;
+                font-style: italic;
+                unicode-range: U+0000-00FF, U+4??;
+                font-display: swap;
+            }
+            @font-face { font-family: Broken; }
+            p { font-family: "Open Sans", sans-serif; }
+            "#,
+            ParserConfig::default(),
+            CssOrigin::User,
+            "test.css",
+        )
+        .unwrap();
+
+        assert_eq!(stylesheet.rules.len(), 1);
+        assert_eq!(stylesheet.font_faces.len(), 1);
+
+        let face = &stylesheet.font_faces[0];
+        assert_eq!(face.family, "Open Sans");
+        assert_eq!(
+            face.sources,
+            vec![
+                FontSource::Local("Open Sans".into()),
+                FontSource::Url {
+                    url: "/fonts/open-sans.woff2".into(),
+                    format: Some("woff2".into()),
+                },
+                FontSource::Url {
+                    url: "open-sans.ttf".into(),
+                    format: None,
+                },
+            ]
+        );
+        assert_eq!(face.weight, (300.0, 700.0));
+        assert_eq!(face.style, FontFaceStyle::Italic);
+        assert_eq!(face.unicode_range, vec![(0x0, 0xFF), (0x400, 0x4FF)]);
+        assert_eq!(face.display, FontDisplay::Swap);
+    }
 }
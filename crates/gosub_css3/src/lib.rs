@@ -1,5 +1,5 @@
-use crate::ast::convert_ast_to_stylesheet;
-use crate::stylesheet::CssStylesheet;
+use crate::ast::{convert_ast_to_stylesheet, convert_declarations};
+use crate::stylesheet::{CssDeclaration, CssStylesheet};
 use crate::tokenizer::Tokenizer;
 
 use gosub_shared::byte_stream::{ByteStream, Encoding, Location};
@@ -75,6 +75,25 @@ impl<'stream> Css3<'stream> {
         Css3::new(stream, config, origin, source_url).parse()
     }
 
+    /// Parses a list of declarations, like the value of a `style` attribute. Declarations that can't be parsed are
+    /// skipped.
+    pub fn parse_declarations(data: &str) -> CssResult<Vec<CssDeclaration>> {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(data, Some(Encoding::UTF8));
+        stream.close();
+
+        let config = ParserConfig {
+            context: Context::Declaration,
+            ignore_errors: true,
+            match_values: false,
+            ..Default::default()
+        };
+
+        let block = Css3::new(&mut stream, config, CssOrigin::Author, "").parse_declaration_list()?;
+
+        Ok(convert_declarations(&block))
+    }
+
     fn parse(&mut self) -> CssResult<CssStylesheet> {
         if self.config.context != Context::Stylesheet {
             return Err(CssError::new("Expected a stylesheet context"));
@@ -147,4 +166,12 @@ mod tests {
         // let w = Walker::new(&binding);
         // w.walk_stdout();
     }
+
+    #[test]
+    fn parse_declarations() {
+        let declarations = Css3::parse_declarations("color: red; margin: 0 auto !important; width: 50%").unwrap();
+
+        let css = declarations.iter().map(CssDeclaration::to_css).collect::<Vec<_>>();
+        assert_eq!(css, vec!["color: red;", "margin: 0 auto !important;", "width: 50%;"]);
+    }
 }
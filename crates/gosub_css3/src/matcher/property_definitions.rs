@@ -520,7 +520,7 @@ mod tests {
 
     #[test]
     fn test_parse_definition_file() {
-        assert_eq!(CSS_DEFINITIONS.len(), 620);
+        assert_eq!(CSS_DEFINITIONS.len(), 626);
     }
 
     #[test]
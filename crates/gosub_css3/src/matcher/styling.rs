@@ -13,14 +13,29 @@ use std::collections::HashMap;
 use crate::matcher::property_definitions::get_css_definitions;
 use crate::stylesheet::{Combinator, CssSelector, CssSelectorPart, CssValue, MatcherType, Specificity};
 
-// Matches a complete selector (all parts) against the given node(id)
+/// Pseudo-elements that can also be written with a single colon, from before they got their own syntax
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["before", "after", "first-line", "first-letter"];
+
+// Matches a complete selector (all parts) against the given node(id). With a pseudo-element, only the selectors
+// that end in that pseudo-element match, otherwise only the selectors without one do
 pub(crate) fn match_selector<D: Document<C>, C: CssSystem>(
     document: DocumentHandle<D, C>,
     node_id: NodeId,
     selector: &CssSelector,
+    pseudo_element: Option<&str>,
 ) -> (bool, Specificity) {
     for part in &selector.parts {
-        if match_selector_parts(DocumentHandle::clone(&document), node_id, part) {
+        if selector_pseudo_element(part) != pseudo_element {
+            continue;
+        }
+
+        // The pseudo-element itself is not matched against the node
+        let element_parts = match pseudo_element {
+            Some(_) => &part[..part.len() - 1],
+            None => part.as_slice(),
+        };
+
+        if match_selector_parts(DocumentHandle::clone(&document), node_id, element_parts) {
             return (true, Specificity::from(part.as_slice()));
         }
     }
@@ -28,6 +43,15 @@ pub(crate) fn match_selector<D: Document<C>, C: CssSystem>(
     (false, Specificity::new(0, 0, 0))
 }
 
+/// Returns the name of the pseudo-element a selector ends in, like `before` for `p::before`
+pub(crate) fn selector_pseudo_element(parts: &[CssSelectorPart]) -> Option<&str> {
+    match parts.last()? {
+        CssSelectorPart::PseudoElement(name) => Some(name),
+        CssSelectorPart::PseudoClass(name) if LEGACY_PSEUDO_ELEMENTS.contains(&name.as_str()) => Some(name),
+        _ => None,
+    }
+}
+
 fn consume<'a, T>(this: &mut &'a [T]) -> Option<&'a T> {
     let last = this.last()?;
 
@@ -570,10 +594,10 @@ impl gosub_shared::traits::css3::CssProperty for CssProperty {
     }
 
     fn as_number(&self) -> Option<f32> {
-        if let CssValue::Number(num) = &self.actual {
-            Some(*num)
-        } else {
-            None
+        match &self.actual {
+            CssValue::Number(num) => Some(*num),
+            CssValue::Zero => Some(0.0),
+            _ => None,
         }
     }
 
@@ -585,9 +609,29 @@ impl gosub_shared::traits::css3::CssProperty for CssProperty {
         }
     }
 
+    fn as_function(&self) -> Option<(&str, &[Self::Value])> {
+        if let CssValue::Function(name, args) = &self.actual {
+            Some((name, args))
+        } else {
+            None
+        }
+    }
+
+    fn as_values(&self) -> Vec<Self::Value> {
+        if let CssValue::List(list) = &self.actual {
+            list.to_vec()
+        } else {
+            vec![self.actual.clone()]
+        }
+    }
+
     fn is_none(&self) -> bool {
         matches!(self.actual, CssValue::None)
     }
+
+    fn to_css(&self) -> String {
+        self.actual.to_css()
+    }
 }
 
 /// Map of all declared values for a single node. Note that these are only the defined properties, not
@@ -595,6 +639,8 @@ impl gosub_shared::traits::css3::CssProperty for CssProperty {
 #[derive(Debug)]
 pub struct CssProperties {
     pub properties: HashMap<String, CssProperty>,
+    /// Properties of the pseudo-elements of the node, by the name of the pseudo-element
+    pub pseudo_elements: HashMap<String, CssProperties>,
     pub dirty: bool,
 }
 
@@ -608,6 +654,7 @@ impl CssProperties {
     pub fn new() -> Self {
         Self {
             properties: HashMap::new(),
+            pseudo_elements: HashMap::new(),
             dirty: true,
         }
     }
@@ -651,6 +698,14 @@ impl CssPropertyMap for CssProperties {
     fn is_dirty(&self) -> bool {
         self.dirty
     }
+
+    fn pseudo_element(&self, name: &str) -> Option<&Self> {
+        self.pseudo_elements.get(name)
+    }
+
+    fn take_pseudo_element(&mut self, name: &str) -> Option<Self> {
+        self.pseudo_elements.remove(name)
+    }
 }
 
 #[cfg(test)]
@@ -672,6 +727,22 @@ mod tests {
         assert!(prop.is_none());
     }
 
+    #[test]
+    fn selector_pseudo_elements() {
+        let parts = vec![
+            CssSelectorPart::Type("p".into()),
+            CssSelectorPart::PseudoElement("marker".into()),
+        ];
+        assert_eq!(selector_pseudo_element(&parts), Some("marker"));
+
+        // The old single colon syntax is only valid for the pseudo-elements of CSS2
+        let parts = vec![CssSelectorPart::PseudoClass("before".into())];
+        assert_eq!(selector_pseudo_element(&parts), Some("before"));
+
+        let parts = vec![CssSelectorPart::PseudoClass("hover".into())];
+        assert_eq!(selector_pseudo_element(&parts), None);
+    }
+
     #[test]
     fn border_prop_test() {
         let mut prop = CssProperty::new("border");
@@ -104,7 +104,7 @@ impl Css3<'_> {
         // parse block. They may or may not have nested rules depending on the is_declaration and block type
         let node = match name.to_lowercase().as_str() {
             "container" => Some(self.parse_block(mode)?),
-            "font-face" => Some(self.parse_block(BlockParseMode::StyleBlock)?),
+            "font-face" => Some(self.parse_at_rule_font_face_block()?),
             "import" => None,
             "layer" => Some(self.parse_block(BlockParseMode::RegularBlock)?),
             "media" => Some(self.parse_block(mode)?),
@@ -1,11 +1,75 @@
-use crate::node::Node;
+use crate::node::{Node, NodeType};
+use crate::tokenizer::TokenType;
 use crate::Css3;
+use gosub_shared::byte_stream::Location;
 use gosub_shared::errors::CssResult;
 
 impl Css3<'_> {
+    /// Parses the descriptors of a `@font-face` block
     pub fn parse_at_rule_font_face_block(&mut self) -> CssResult<Node> {
         log::trace!("parse_at_rule_font_face_block");
 
-        todo!();
+        let loc = self.tokenizer.current_location();
+        let mut children = Vec::new();
+
+        while !self.tokenizer.eof() {
+            let t = self.consume_any()?;
+            match t.token_type {
+                TokenType::RCurly => {
+                    // The caller consumes the closing brace
+                    self.tokenizer.reconsume();
+                    break;
+                }
+                TokenType::Whitespace(_) | TokenType::Comment(_) | TokenType::Semicolon => {
+                    // just eat the token
+                }
+                TokenType::Ident(name) if name.eq_ignore_ascii_case("unicode-range") => {
+                    children.push(self.parse_raw_descriptor(name, t.location)?);
+                }
+                _ => {
+                    self.tokenizer.reconsume();
+                    if let Some(declaration) = self.parse_declaration()? {
+                        children.push(declaration);
+                    }
+                }
+            }
+        }
+
+        Ok(Node::new(NodeType::Block { children }, loc))
+    }
+
+    /// Parses a descriptor whose value is kept as source text. Unicode ranges like `U+4E00-9FFF` are tokenized as
+    /// numbers and dimensions that can't be turned back into the range, so they are read from the source instead
+    fn parse_raw_descriptor(&mut self, property: String, loc: Location) -> CssResult<Node> {
+        log::trace!("parse_raw_descriptor");
+
+        self.consume_whitespace_comments();
+        self.consume(TokenType::Colon)?;
+        self.consume_whitespace_comments();
+
+        let value_loc = self.tokenizer.lookahead(0).location;
+        let mut end = value_loc.offset;
+
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Semicolon | TokenType::RCurly | TokenType::Eof => break,
+                _ => {
+                    self.tokenizer.consume();
+                    end = self.tokenizer.lookahead(0).location.offset;
+                }
+            }
+        }
+
+        let value = self.tokenizer.slice(value_loc.offset, end).trim().to_string();
+
+        Ok(Node::new(
+            NodeType::Declaration {
+                property,
+                value: vec![Node::new(NodeType::Raw { value }, value_loc)],
+                important: false,
+            },
+            loc,
+        ))
     }
 }
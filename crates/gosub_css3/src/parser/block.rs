@@ -49,6 +49,13 @@ impl Css3<'_> {
         }
     }
 
+    /// Parses declarations that are not surrounded by curly braces, like the value of a `style` attribute
+    pub(crate) fn parse_declaration_list(&mut self) -> CssResult<Node> {
+        log::trace!("parse_declaration_list");
+
+        self.parse_block(BlockParseMode::StyleBlock)
+    }
+
     pub fn parse_block(&mut self, mode: BlockParseMode) -> CssResult<Node> {
         log::trace!("parse_block with parse mode: {:?}", mode);
 
@@ -2,11 +2,16 @@ use core::fmt::Debug;
 use gosub_shared::byte_stream::Location;
 use gosub_shared::errors::CssError;
 use gosub_shared::errors::CssResult;
-use gosub_shared::traits::css3::CssOrigin;
+use gosub_shared::font::FontFace;
+use gosub_shared::traits::css3::{CssOrigin, StyleDeclaration};
+use gosub_shared::traits::ParserConfig;
 use std::cmp::Ordering;
 use std::fmt::Display;
 
 use crate::colors::RgbColor;
+use crate::Css3;
+
+mod serialize;
 
 /// Severity of a CSS error
 #[derive(Debug, PartialEq)]
@@ -99,6 +104,8 @@ impl Debug for CssLog {
 pub struct CssStylesheet {
     /// List of rules found in this stylesheet
     pub rules: Vec<CssRule>,
+    /// Font faces declared with `@font-face` rules
+    pub font_faces: Vec<FontFace>,
     /// Origin of the stylesheet (user agent, author, user)
     pub origin: CssOrigin,
     /// Url or file path where the stylesheet was found
@@ -115,6 +122,78 @@ impl gosub_shared::traits::css3::CssStylesheet for CssStylesheet {
     fn url(&self) -> &str {
         &self.url
     }
+
+    fn font_faces(&self) -> &[FontFace] {
+        &self.font_faces
+    }
+
+    fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    fn rule_css_text(&self, index: usize) -> Option<String> {
+        self.rules.get(index).map(CssRule::to_css)
+    }
+
+    fn rule_selector_text(&self, index: usize) -> Option<String> {
+        let rule = self.rules.get(index)?;
+
+        Some(
+            rule.selectors
+                .iter()
+                .map(CssSelector::to_css)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    fn rule_declarations(&self, index: usize) -> Option<Vec<StyleDeclaration>> {
+        let rule = self.rules.get(index)?;
+
+        Some(rule.declarations.iter().map(StyleDeclaration::from).collect())
+    }
+
+    fn set_rule_declarations(&mut self, index: usize, css_text: &str) -> CssResult<()> {
+        let declarations = Css3::parse_declarations(css_text)?;
+
+        match self.rules.get_mut(index) {
+            Some(rule) => {
+                rule.declarations = declarations;
+                Ok(())
+            }
+            None => Err(CssError::new(format!("there is no rule at index {index}").as_str())),
+        }
+    }
+
+    fn insert_rule(&mut self, rule: &str, index: usize) -> CssResult<()> {
+        if index > self.rules.len() {
+            return Err(CssError::new(format!("can't insert a rule at index {index}").as_str()));
+        }
+
+        let config = ParserConfig {
+            match_values: false,
+            ..Default::default()
+        };
+        let mut sheet = Css3::parse_str(rule, config, self.origin, &self.url)?;
+
+        if sheet.rules.len() != 1 || !sheet.font_faces.is_empty() {
+            return Err(CssError::new("expected a single style rule"));
+        }
+
+        self.rules.insert(index, sheet.rules.remove(0));
+
+        Ok(())
+    }
+
+    fn delete_rule(&mut self, index: usize) -> CssResult<()> {
+        if index >= self.rules.len() {
+            return Err(CssError::new(format!("there is no rule at index {index}").as_str()));
+        }
+
+        self.rules.remove(index);
+
+        Ok(())
+    }
 }
 
 /// A CSS rule, which contains a list of selectors and a list of declarations
@@ -148,6 +227,16 @@ pub struct CssDeclaration {
     pub important: bool,
 }
 
+impl From<&CssDeclaration> for StyleDeclaration {
+    fn from(declaration: &CssDeclaration) -> Self {
+        Self {
+            property: declaration.property.clone(),
+            value: declaration.value.to_css(),
+            important: declaration.important,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct CssSelector {
     // List of parts that make up this selector
@@ -476,6 +565,7 @@ impl CssValue {
             }
 
             crate::node::NodeType::Comma => Ok(CssValue::Comma),
+            crate::node::NodeType::Raw { value } => Ok(CssValue::String(value)),
 
             _ => Err(CssError::new(
                 format!("Cannot convert node to CssValue: {:?}", node).as_str(),
@@ -560,18 +650,14 @@ impl gosub_shared::traits::css3::CssValue for CssValue {
     }
 
     fn as_color(&self) -> Option<(f32, f32, f32, f32)> {
-        if let CssValue::Color(color) = &self {
-            Some((color.r, color.g, color.b, color.a))
-        } else {
-            None
-        }
+        self.to_color().map(|color| (color.r, color.g, color.b, color.a))
     }
 
     fn as_number(&self) -> Option<f32> {
-        if let CssValue::Number(num) = &self {
-            Some(*num)
-        } else {
-            None
+        match &self {
+            CssValue::Number(num) => Some(*num),
+            CssValue::Zero => Some(0.0),
+            _ => None,
         }
     }
 
@@ -583,6 +669,14 @@ impl gosub_shared::traits::css3::CssValue for CssValue {
         }
     }
 
+    fn as_function(&self) -> Option<(&str, &[Self])> {
+        if let CssValue::Function(name, args) = &self {
+            Some((name, args))
+        } else {
+            None
+        }
+    }
+
     fn is_comma(&self) -> bool {
         matches!(self, CssValue::Comma)
     }
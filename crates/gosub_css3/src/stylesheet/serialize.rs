@@ -0,0 +1,266 @@
+//! Serialization of values, selectors, declarations and rules back to CSS text, as used by the CSSOM
+//! (<https://drafts.csswg.org/cssom/#serializing-css-values>)
+use crate::stylesheet::{Combinator, CssDeclaration, CssRule, CssSelector, CssSelectorPart, CssValue, MatcherType};
+use std::fmt::Write;
+
+impl CssValue {
+    /// Serializes the value as CSS
+    pub fn to_css(&self) -> String {
+        match self {
+            CssValue::None => "none".to_string(),
+            CssValue::Color(color) => {
+                let (r, g, b) = (color.r.round(), color.g.round(), color.b.round());
+                if color.a >= 255.0 {
+                    format!("rgb({r}, {g}, {b})")
+                } else {
+                    format!("rgba({r}, {g}, {b}, {})", number(color.a / 255.0))
+                }
+            }
+            CssValue::Zero => "0".to_string(),
+            CssValue::Number(value) => number(*value),
+            CssValue::Percentage(value) => format!("{}%", number(*value)),
+            CssValue::String(value) => {
+                // identifiers, hashes and strings all end up as strings, only the latter need quotes
+                if value.starts_with('#') || is_identifier(value) {
+                    value.clone()
+                } else {
+                    string(value)
+                }
+            }
+            CssValue::Unit(value, unit) => format!("{}{unit}", number(*value)),
+            CssValue::Function(name, args) => format!("{name}({})", list(args)),
+            CssValue::Initial => "initial".to_string(),
+            CssValue::Inherit => "inherit".to_string(),
+            CssValue::Comma => ",".to_string(),
+            CssValue::List(values) => list(values),
+        }
+    }
+}
+
+impl CssSelector {
+    /// Serializes the selector list as CSS, like `ul > li, h1.title`
+    pub fn to_css(&self) -> String {
+        let mut css = String::new();
+
+        for (i, parts) in self.parts.iter().enumerate() {
+            if i > 0 {
+                css.push_str(", ");
+            }
+
+            for part in parts {
+                let _ = match part {
+                    CssSelectorPart::Universal => write!(css, "*"),
+                    CssSelectorPart::Type(name) => write!(css, "{name}"),
+                    CssSelectorPart::Class(name) => write!(css, ".{name}"),
+                    CssSelectorPart::Id(name) => write!(css, "#{name}"),
+                    CssSelectorPart::PseudoClass(name) => write!(css, ":{name}"),
+                    CssSelectorPart::PseudoElement(name) => write!(css, "::{name}"),
+                    CssSelectorPart::Combinator(Combinator::Descendant) => write!(css, " "),
+                    CssSelectorPart::Combinator(Combinator::Namespace) => write!(css, "|"),
+                    CssSelectorPart::Combinator(combinator) => write!(css, " {combinator} "),
+                    CssSelectorPart::Attribute(selector) => {
+                        if selector.matcher == MatcherType::None {
+                            write!(css, "[{}]", selector.name)
+                        } else {
+                            let flags = if selector.case_insensitive { " i" } else { "" };
+                            write!(
+                                css,
+                                "[{}{}{}{flags}]",
+                                selector.name,
+                                selector.matcher,
+                                string(&selector.value)
+                            )
+                        }
+                    }
+                };
+            }
+        }
+
+        css
+    }
+}
+
+impl CssDeclaration {
+    /// Serializes the declaration as CSS, like `color: red !important;`
+    pub fn to_css(&self) -> String {
+        let important = if self.important { " !important" } else { "" };
+        format!("{}: {}{important};", self.property, self.value.to_css())
+    }
+}
+
+impl CssRule {
+    /// Serializes the rule as CSS, like `h1 { color: red; margin: 0; }`
+    pub fn to_css(&self) -> String {
+        let selectors = self
+            .selectors
+            .iter()
+            .map(CssSelector::to_css)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut css = format!("{selectors} {{");
+        for declaration in &self.declarations {
+            css.push(' ');
+            css.push_str(&declaration.to_css());
+        }
+        css.push_str(" }");
+
+        css
+    }
+
+    /// Serializes the declarations of the rule as CSS, like `color: red; margin: 0;`
+    pub fn declarations_to_css(&self) -> String {
+        self.declarations
+            .iter()
+            .map(CssDeclaration::to_css)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Serializes a list of values, which are separated by spaces or commas
+fn list(values: &[CssValue]) -> String {
+    let mut css = String::new();
+
+    for value in values {
+        if value == &CssValue::Comma {
+            css.push(',');
+            continue;
+        }
+
+        if !css.is_empty() {
+            css.push(' ');
+        }
+        css.push_str(&value.to_css());
+    }
+
+    css
+}
+
+/// Serializes a number with at most six decimals, without a trailing `.0`
+fn number(value: f32) -> String {
+    let value = (value as f64 * 1_000_000.0).round() / 1_000_000.0;
+    if value == 0.0 {
+        return "0".to_string();
+    }
+
+    value.to_string()
+}
+
+/// Serializes a string in double quotes
+fn string(value: &str) -> String {
+    let mut css = String::with_capacity(value.len() + 2);
+
+    css.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                css.push('\\');
+                css.push(c);
+            }
+            '\0' => css.push('\u{FFFD}'),
+            c if c.is_control() => {
+                let _ = write!(css, "\\{:x} ", c as u32);
+            }
+            c => css.push(c),
+        }
+    }
+    css.push('"');
+
+    css
+}
+
+/// Returns true when the value can be written as an identifier, without quotes
+fn is_identifier(value: &str) -> bool {
+    let name = value
+        .strip_prefix("--")
+        .or_else(|| value.strip_prefix('-'))
+        .unwrap_or(value);
+
+    let Some(first) = name.chars().next() else {
+        return value == "--";
+    };
+
+    (first.is_ascii_alphabetic() || first == '_' || !first.is_ascii())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::RgbColor;
+    use crate::stylesheet::AttributeSelector;
+
+    #[test]
+    fn values() {
+        assert_eq!(CssValue::Unit(1.5, "px".into()).to_css(), "1.5px");
+        assert_eq!(CssValue::Number(0.1).to_css(), "0.1");
+        assert_eq!(CssValue::Percentage(-0.0).to_css(), "0%");
+        assert_eq!(CssValue::String("sans-serif".into()).to_css(), "sans-serif");
+        assert_eq!(CssValue::String("#fff".into()).to_css(), "#fff");
+        assert_eq!(CssValue::String("Open \"Sans\"".into()).to_css(), r#""Open \"Sans\"""#);
+        assert_eq!(
+            CssValue::Color(RgbColor::new(255.0, 0.0, 0.0, 255.0)).to_css(),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(
+            CssValue::Color(RgbColor::new(0.0, 0.0, 0.0, 127.5)).to_css(),
+            "rgba(0, 0, 0, 0.5)"
+        );
+        assert_eq!(
+            CssValue::List(vec![
+                CssValue::String("Arial".into()),
+                CssValue::Comma,
+                CssValue::Function("url".into(), vec![CssValue::String("a b.woff".into())]),
+            ])
+            .to_css(),
+            r#"Arial, url("a b.woff")"#
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let rule = CssRule {
+            selectors: vec![CssSelector {
+                parts: vec![
+                    vec![
+                        CssSelectorPart::Type("ul".into()),
+                        CssSelectorPart::Combinator(Combinator::Child),
+                        CssSelectorPart::Type("li".into()),
+                        CssSelectorPart::PseudoClass("hover".into()),
+                    ],
+                    vec![
+                        CssSelectorPart::Id("main".into()),
+                        CssSelectorPart::Combinator(Combinator::Descendant),
+                        CssSelectorPart::Attribute(Box::new(AttributeSelector {
+                            name: "lang".into(),
+                            matcher: MatcherType::DashMatch,
+                            value: "en".into(),
+                            case_insensitive: true,
+                        })),
+                    ],
+                ],
+            }],
+            declarations: vec![
+                CssDeclaration {
+                    property: "margin".into(),
+                    value: CssValue::List(vec![CssValue::Zero, CssValue::String("auto".into())]),
+                    important: false,
+                },
+                CssDeclaration {
+                    property: "color".into(),
+                    value: CssValue::String("red".into()),
+                    important: true,
+                },
+            ],
+        };
+
+        assert_eq!(
+            rule.to_css(),
+            r#"ul > li:hover, #main [lang|="en" i] { margin: 0 auto; color: red !important; }"#
+        );
+        assert_eq!(rule.declarations_to_css(), "margin: 0 auto; color: red !important;");
+    }
+}
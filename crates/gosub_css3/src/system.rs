@@ -3,18 +3,22 @@ use crate::functions::calc::resolve_calc;
 use crate::functions::var::resolve_var;
 use crate::matcher::property_definitions::get_css_definitions;
 use crate::matcher::shorthands::FixList;
-use crate::matcher::styling::{match_selector, CssProperties, CssProperty, DeclarationProperty};
-use crate::stylesheet::{CssDeclaration, CssValue, Specificity};
+use crate::matcher::styling::{
+    match_selector, selector_pseudo_element, CssProperties, CssProperty, DeclarationProperty,
+};
+use crate::stylesheet::{CssDeclaration, CssRule, CssStylesheet, CssValue, Specificity};
 use crate::{load_default_useragent_stylesheet, Css3};
 use gosub_shared::document::DocumentHandle;
 use gosub_shared::errors::CssResult;
 use gosub_shared::node::NodeId;
-use gosub_shared::traits::css3::{CssOrigin, CssPropertyMap, CssSystem};
+use gosub_shared::traits::css3::{CssOrigin, CssPropertyMap, CssSystem, StyleDeclaration};
 use gosub_shared::traits::document::Document;
 use gosub_shared::traits::node::{ElementDataType, Node, TextDataType};
 use gosub_shared::traits::render_tree::{RenderTree, RenderTreeNode};
 use gosub_shared::traits::ParserConfig;
+use itertools::Itertools;
 use log::warn;
+use std::collections::HashMap;
 use std::slice;
 
 #[derive(Debug, Clone)]
@@ -47,57 +51,81 @@ impl CssSystem for Css3System {
 
         let mut fix_list = FixList::new();
 
+        // Properties and shorthands of the pseudo-elements, by the name of the pseudo-element
+        let mut pseudo_elements: HashMap<String, (CssProperties, FixList)> = HashMap::new();
+
         for sheet in sheets {
             for rule in &sheet.rules {
                 for selector in rule.selectors().iter() {
-                    let (matched, specificity) = match_selector(DocumentHandle::clone(&handle), id, selector);
+                    let (matched, specificity) = match_selector(DocumentHandle::clone(&handle), id, selector, None);
+
+                    if matched {
+                        add_rule_declarations(
+                            &mut css_map_entry,
+                            &mut fix_list,
+                            sheet,
+                            rule,
+                            specificity,
+                            node,
+                            &handle,
+                        );
+                    }
 
-                    if !matched {
+                    // Only elements have pseudo-elements
+                    if !node.is_element_node() {
                         continue;
                     }
 
-                    // Selector matched, so we add all declared values to the map
-                    for declaration in rule.declarations().iter() {
-                        // Step 1: find the property in our CSS definition list
-                        let Some(definition) = definitions.find_property(&declaration.property) else {
-                            // If not found, we skip this declaration
-                            warn!("Definition is not found for property {:?}", declaration.property);
-                            continue;
-                        };
-
-                        let value = resolve_functions(&declaration.value, node, handle.clone());
+                    let names = selector
+                        .parts
+                        .iter()
+                        .filter_map(|parts| selector_pseudo_element(parts))
+                        .unique()
+                        .collect::<Vec<_>>();
 
-                        let match_value = if let CssValue::List(value) = &value {
-                            &**value
-                        } else {
-                            slice::from_ref(&value)
-                        };
+                    for name in names {
+                        let (matched, specificity) =
+                            match_selector(DocumentHandle::clone(&handle), id, selector, Some(name));
 
-                        // Check if the declaration matches the definition and return the "expanded" order
-                        let res = definition.matches_and_shorthands(match_value, &mut fix_list);
-                        if !res {
-                            warn!("Declaration does not match definition: {:?}", declaration);
+                        if !matched {
                             continue;
                         }
 
-                        // create property for the given values
-                        let property_name = declaration.property.clone();
-                        let decl = CssDeclaration {
-                            property: property_name.to_string(),
-                            value,
-                            important: declaration.important,
-                        };
+                        let (map, fix_list) = pseudo_elements
+                            .entry(name.to_string())
+                            .or_insert_with(|| (CssProperties::new(), FixList::new()));
 
-                        add_property_to_map(&mut css_map_entry, sheet, specificity.clone(), &decl);
+                        add_rule_declarations(map, fix_list, sheet, rule, specificity, node, &handle);
                     }
                 }
             }
         }
 
+        // Declarations of the style attribute take precedence over all rules of the author stylesheets
+        if let Some(style) = node.get_element_data().and_then(|data| data.attribute("style")) {
+            let (sheet, rule) = inline_style_rule(style, handle.get().url().map(|url| url.to_string()));
+            add_rule_declarations(
+                &mut css_map_entry,
+                &mut fix_list,
+                &sheet,
+                &rule,
+                Specificity::new(u32::MAX, 0, 0),
+                node,
+                &handle,
+            );
+        }
+
         fix_list.resolve_nested(definitions);
 
         fix_list.apply(&mut css_map_entry);
 
+        for (name, (mut map, mut fix_list)) in pseudo_elements {
+            fix_list.resolve_nested(definitions);
+            fix_list.apply(&mut map);
+
+            css_map_entry.pseudo_elements.insert(name, map);
+        }
+
         Some(css_map_entry)
     }
 
@@ -105,9 +133,49 @@ impl CssSystem for Css3System {
         Self::resolve_inheritance(tree, tree.root(), &Vec::new());
     }
 
+    fn inheritance_from<T: RenderTree<Self>>(tree: &mut T, node_id: T::NodeId) {
+        // The parent already holds both its own and its inherited properties, so we only need to look one level up
+        let inherit_props = tree
+            .get_parent(node_id)
+            .and_then(|parent_id| tree.get_node(parent_id))
+            .map(|parent| {
+                parent
+                    .props()
+                    .iter()
+                    .filter(|(name, _)| prop_is_inherit(name))
+                    .map(|(name, prop)| (name.to_owned(), prop.actual.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Self::resolve_inheritance(tree, node_id, &inherit_props);
+    }
+
     fn load_default_useragent_stylesheet() -> Self::Stylesheet {
         load_default_useragent_stylesheet()
     }
+
+    fn parse_declarations(str: &str) -> Vec<StyleDeclaration> {
+        let definitions = get_css_definitions();
+
+        Css3::parse_declarations(str)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|mut declaration| {
+                if !declaration.property.starts_with("--") {
+                    declaration.property = declaration.property.to_ascii_lowercase();
+                }
+
+                let values = match &declaration.value {
+                    CssValue::List(values) => values.as_slice(),
+                    value => slice::from_ref(value),
+                };
+
+                let definition = definitions.find_property(&declaration.property)?;
+                definition.matches(values).then(|| StyleDeclaration::from(&declaration))
+            })
+            .collect()
+    }
 }
 
 impl Css3System {
@@ -147,6 +215,14 @@ impl Css3System {
             }
         }
 
+        // Pseudo-elements that style a part of the node, like `::first-line`, only override the properties they
+        // declare, so nothing is inherited into them
+        for pseudo in current_node.props_mut().pseudo_elements.values_mut() {
+            for prop in pseudo.properties.values_mut() {
+                prop.compute_value();
+            }
+        }
+
         let Some(children) = tree.get_children(node_id) else {
             return;
         };
@@ -157,6 +233,77 @@ impl Css3System {
     }
 }
 
+/// Returns the declarations of a `style` attribute as a rule of an author stylesheet, so they can be added like the
+/// declarations of any other rule
+fn inline_style_rule(style: &str, document_url: Option<String>) -> (CssStylesheet, CssRule) {
+    let declarations = Css3::parse_declarations(style).unwrap_or_else(|err| {
+        warn!("Error while parsing style attribute: {err}");
+        vec![]
+    });
+
+    let sheet = CssStylesheet {
+        rules: vec![],
+        font_faces: vec![],
+        origin: CssOrigin::Author,
+        url: document_url.map(|url| format!("{url}#style")).unwrap_or_default(),
+        parse_log: vec![],
+    };
+
+    let rule = CssRule {
+        selectors: vec![],
+        declarations,
+    };
+
+    (sheet, rule)
+}
+
+/// Adds the declarations of a rule that matched a node (or one of its pseudo-elements) to its property map
+fn add_rule_declarations<D: Document<Css3System>>(
+    css_map_entry: &mut CssProperties,
+    fix_list: &mut FixList,
+    sheet: &CssStylesheet,
+    rule: &CssRule,
+    specificity: Specificity,
+    node: &D::Node,
+    handle: &DocumentHandle<D, Css3System>,
+) {
+    let definitions = get_css_definitions();
+
+    for declaration in rule.declarations().iter() {
+        // Step 1: find the property in our CSS definition list
+        let Some(definition) = definitions.find_property(&declaration.property) else {
+            // If not found, we skip this declaration
+            warn!("Definition is not found for property {:?}", declaration.property);
+            continue;
+        };
+
+        let value = resolve_functions(&declaration.value, node, handle.clone());
+
+        let match_value = if let CssValue::List(value) = &value {
+            &**value
+        } else {
+            slice::from_ref(&value)
+        };
+
+        // Check if the declaration matches the definition and return the "expanded" order
+        let res = definition.matches_and_shorthands(match_value, fix_list);
+        if !res {
+            warn!("Declaration does not match definition: {:?}", declaration);
+            continue;
+        }
+
+        // create property for the given values
+        let property_name = declaration.property.clone();
+        let decl = CssDeclaration {
+            property: property_name.to_string(),
+            value,
+            important: declaration.important,
+        };
+
+        add_property_to_map(css_map_entry, sheet, specificity.clone(), &decl);
+    }
+}
+
 pub fn prop_is_inherit(name: &str) -> bool {
     get_css_definitions()
         .find_property(name)
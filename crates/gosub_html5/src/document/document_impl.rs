@@ -1,6 +1,6 @@
 use crate::DocumentHandle;
 use core::fmt::Debug;
-use gosub_shared::traits::document::{Document as OtherDocument, Document, DocumentType};
+use gosub_shared::traits::document::{Document as OtherDocument, Document, DocumentMutation, DocumentType, Script};
 use std::cell::RefCell;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -21,11 +21,15 @@ use crate::node::data::text::TextData;
 use crate::node::node_impl::{NodeDataTypeInternal, NodeImpl};
 use crate::node::visitor::Visitor;
 use gosub_shared::byte_stream::Location;
+use gosub_shared::errors::{CssError, CssResult};
 use gosub_shared::node::NodeId;
 use gosub_shared::traits::css3::CssSystem;
 use gosub_shared::traits::node::Node;
 use gosub_shared::traits::node::QuirksMode;
 
+/// Number of mutations that are kept track of separately before the whole document is considered to be changed
+const MAX_PENDING_MUTATIONS: usize = 256;
+
 /// Defines a document
 #[derive(Debug)]
 pub struct DocumentImpl<C: CssSystem> {
@@ -42,6 +46,10 @@ pub struct DocumentImpl<C: CssSystem> {
     pub quirks_mode: QuirksMode,
     /// Loaded stylesheets as extracted from the document
     pub stylesheets: Vec<C::Stylesheet>,
+    /// Mutations made to the document that have not been picked up by the render tree yet
+    mutations: Vec<DocumentMutation>,
+    /// Scripts found by the parser that have not been picked up by the script runner yet
+    scripts: Vec<Script>,
 }
 
 impl<C: CssSystem> PartialEq for DocumentImpl<C> {
@@ -70,6 +78,8 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
             doctype: document_type,
             quirks_mode: QuirksMode::NoQuirks,
             stylesheets: Vec::new(),
+            mutations: Vec::new(),
+            scripts: Vec::new(),
         };
 
         let mut doc_handle = DocumentHandle(Rc::new(RefCell::new(doc)), Default::default());
@@ -132,6 +142,31 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
 
     fn add_stylesheet(&mut self, stylesheet: C::Stylesheet) {
         self.stylesheets.push(stylesheet);
+        self.record_mutation(DocumentMutation::StylesheetsChanged);
+    }
+
+    fn update_stylesheet<R>(
+        &mut self,
+        index: usize,
+        f: impl FnOnce(&mut C::Stylesheet) -> CssResult<R>,
+    ) -> CssResult<R> {
+        let Some(stylesheet) = self.stylesheets.get_mut(index) else {
+            let message = format!("there is no stylesheet at index {index}");
+            return Err(CssError::new(&message));
+        };
+
+        let result = f(stylesheet)?;
+        self.record_mutation(DocumentMutation::StylesheetsChanged);
+
+        Ok(result)
+    }
+
+    fn add_script(&mut self, script: Script) {
+        self.scripts.push(script);
+    }
+
+    fn take_scripts(&mut self) -> Vec<Script> {
+        std::mem::take(&mut self.scripts)
     }
 
     /// returns the root node
@@ -169,6 +204,8 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
         let mut node = self.arena.node(node_id).unwrap();
         node.parent = Some(parent_id);
         self.update_node(node);
+
+        self.record_mutation(DocumentMutation::ChildrenChanged(parent_id));
     }
 
     // /// returns the root node
@@ -187,6 +224,8 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
             let mut node = self.node_by_id(node_id).expect("node not found").clone();
             node.set_parent(None);
             self.update_node(node);
+
+            self.record_mutation(DocumentMutation::ChildrenChanged(parent_id));
         }
     }
 
@@ -211,6 +250,7 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
         }
 
         self.on_document_node_mutation(&node);
+        self.record_mutation(DocumentMutation::NodeChanged(node.id()));
         self.arena.update_node(node);
     }
 
@@ -221,6 +261,7 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
         }
 
         self.on_document_node_mutation(node);
+        self.record_mutation(DocumentMutation::NodeChanged(node.id()));
         self.arena.update_node(node.clone());
     }
 
@@ -237,6 +278,7 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
         }
 
         self.arena.delete_node(node_id);
+        self.record_mutation(DocumentMutation::NodeRemoved(node_id));
     }
 
     // /// Returns the parent node of the given node, or None when no parent is found
@@ -370,6 +412,10 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
         )
     }
 
+    fn take_mutations(&mut self) -> Vec<DocumentMutation> {
+        std::mem::take(&mut self.mutations)
+    }
+
     fn write(&self) -> String {
         self.write_from_node(NodeId::root())
     }
@@ -384,6 +430,21 @@ impl<C: CssSystem> Document<C> for DocumentImpl<C> {
 }
 
 impl<C: CssSystem> DocumentImpl<C> {
+    /// Records a mutation, unless it has been recorded already (which happens a lot during tree construction). When
+    /// there are too many pending mutations, they are replaced by a single mutation for the whole document.
+    fn record_mutation(&mut self, mutation: DocumentMutation) {
+        if self.mutations.contains(&mutation) || self.mutations.first() == Some(&DocumentMutation::DocumentChanged) {
+            return;
+        }
+
+        if self.mutations.len() >= MAX_PENDING_MUTATIONS {
+            self.mutations = vec![DocumentMutation::DocumentChanged];
+            return;
+        }
+
+        self.mutations.push(mutation);
+    }
+
     // Called whenever a node is being mutated in the document.
     fn on_document_node_mutation(&mut self, node: &NodeImpl<C>) {
         // self.on_document_node_mutation_update_id_in_node(node);
@@ -621,10 +682,12 @@ mod tests {
     use crate::parser::tree_builder::TreeBuilder;
     use gosub_css3::system::Css3System;
     use gosub_shared::byte_stream::Location;
+    use gosub_shared::traits::css3::{CssOrigin, CssStylesheet};
     use gosub_shared::traits::document::DocumentBuilder;
     use gosub_shared::traits::node::ClassList;
     use gosub_shared::traits::node::ElementDataType;
     use gosub_shared::traits::node::NodeType;
+    use gosub_shared::traits::ParserConfig;
     use std::collections::HashMap;
 
     type Document = DocumentImpl<Css3System>;
@@ -2274,4 +2337,98 @@ mod tests {
         current_node_id = tree_iterator.next();
         assert_eq!(current_node_id.unwrap(), div_id_2);
     }
+
+    #[test]
+    fn document_mutations() {
+        let mut doc_handle = DocumentBuilderImpl::new_document(None);
+
+        let div_node = Document::new_element_node(
+            doc_handle.clone(),
+            "div",
+            Some(HTML_NAMESPACE),
+            HashMap::new(),
+            Location::default(),
+        );
+        let div_id = doc_handle.get_mut().register_node_at(div_node, NodeId::root(), None);
+
+        let mutations = doc_handle.get_mut().take_mutations();
+        assert!(mutations.contains(&DocumentMutation::ChildrenChanged(NodeId::root())));
+        assert!(doc_handle.get_mut().take_mutations().is_empty());
+
+        let mut div_node = doc_handle.get().node_by_id(div_id).unwrap().clone();
+        div_node
+            .get_element_data_mut()
+            .unwrap()
+            .attributes
+            .insert("class".into(), "foo".into());
+        doc_handle.get_mut().update_node(div_node);
+        assert_eq!(
+            doc_handle.get_mut().take_mutations(),
+            vec![DocumentMutation::NodeChanged(div_id)]
+        );
+
+        doc_handle.get_mut().delete_node_by_id(div_id);
+        let mutations = doc_handle.get_mut().take_mutations();
+        assert_eq!(mutations.last(), Some(&DocumentMutation::NodeRemoved(div_id)));
+
+        let config = ParserConfig::default();
+        let sheet = Css3System::parse_str("p { color: red; }", config, CssOrigin::Author, "").unwrap();
+        doc_handle.get_mut().add_stylesheet(sheet);
+        doc_handle.get_mut().take_mutations();
+
+        let result = doc_handle.get_mut().update_stylesheet(0, |sheet| sheet.delete_rule(1));
+        assert!(result.is_err());
+        assert!(doc_handle.get_mut().update_stylesheet(1, |_| Ok(())).is_err());
+        assert!(doc_handle.get_mut().take_mutations().is_empty());
+
+        doc_handle
+            .get_mut()
+            .update_stylesheet(0, |sheet| sheet.delete_rule(0))
+            .unwrap();
+        assert_eq!(
+            doc_handle.get_mut().take_mutations(),
+            vec![DocumentMutation::StylesheetsChanged]
+        );
+    }
+
+    #[test]
+    fn document_mutations_are_coalesced() {
+        let mut doc_handle = DocumentBuilderImpl::new_document(None);
+
+        let add_div = |doc_handle: &mut DocumentHandle<DocumentImpl<Css3System>, Css3System>| {
+            let div_node = Document::new_element_node(
+                doc_handle.clone(),
+                "div",
+                Some(HTML_NAMESPACE),
+                HashMap::new(),
+                Location::default(),
+            );
+            doc_handle.get_mut().register_node_at(div_node, NodeId::root(), None)
+        };
+
+        let ids = [add_div(&mut doc_handle), add_div(&mut doc_handle)];
+        doc_handle.get_mut().take_mutations();
+
+        // Changing the same node again does not record a new mutation
+        for id in ids {
+            let node = doc_handle.get().node_by_id(id).unwrap().clone();
+            doc_handle.get_mut().update_node(node.clone());
+            doc_handle.get_mut().update_node(node);
+        }
+        assert_eq!(
+            doc_handle.get_mut().take_mutations(),
+            vec![
+                DocumentMutation::NodeChanged(ids[0]),
+                DocumentMutation::NodeChanged(ids[1])
+            ]
+        );
+
+        for _ in 0..MAX_PENDING_MUTATIONS {
+            add_div(&mut doc_handle);
+        }
+        assert_eq!(
+            doc_handle.get_mut().take_mutations(),
+            vec![DocumentMutation::DocumentChanged]
+        );
+    }
 }
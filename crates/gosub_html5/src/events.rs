@@ -0,0 +1,499 @@
+//! DOM events (<https://dom.spec.whatwg.org/#events>).
+//!
+//! The listeners of all nodes of a document are kept in [`EventTargets`], which dispatches an event through the
+//! capture, target and bubble phases of the path from the root of the document to the target node.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use log::warn;
+
+use gosub_shared::document::DocumentHandle;
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::css3::CssSystem;
+use gosub_shared::traits::document::Document;
+use gosub_shared::traits::node::Node;
+use gosub_shared::types::Result;
+
+/// The callback of an event listener
+pub type EventCallback = Rc<dyn Fn(&mut Event) -> Result<()>>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventPhase {
+    #[default]
+    None,
+    Capturing,
+    AtTarget,
+    Bubbling,
+}
+
+/// State of the modifier keys while the event happened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+/// The members of the event interfaces that inherit from `Event`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum EventData {
+    #[default]
+    None,
+    /// `MouseEvent`, the position is relative to the viewport
+    Mouse {
+        client_x: f64,
+        client_y: f64,
+        button: i16,
+        buttons: u16,
+        modifiers: Modifiers,
+    },
+    /// `WheelEvent`, the deltas are in pixels
+    Wheel {
+        client_x: f64,
+        client_y: f64,
+        delta_x: f64,
+        delta_y: f64,
+        modifiers: Modifiers,
+    },
+    /// `KeyboardEvent`
+    Keyboard {
+        key: String,
+        code: String,
+        repeat: bool,
+        modifiers: Modifiers,
+    },
+    /// `InputEvent`
+    Input { data: Option<String>, input_type: String },
+}
+
+/// Options of an event that are set when it is created (<https://dom.spec.whatwg.org/#dictdef-eventinit>)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventInit {
+    pub bubbles: bool,
+    pub cancelable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub event_type: String,
+    pub bubbles: bool,
+    pub cancelable: bool,
+    /// True for events that are dispatched by the user agent, false for events that are created by scripts
+    pub is_trusted: bool,
+    /// Time of the event, in milliseconds since the time origin of the document
+    pub time_stamp: f64,
+    pub data: EventData,
+    target: Option<NodeId>,
+    current_target: Option<NodeId>,
+    phase: EventPhase,
+    canceled: bool,
+    stop_propagation: bool,
+    stop_immediate_propagation: bool,
+    in_passive_listener: bool,
+}
+
+impl Event {
+    pub fn new(event_type: &str, init: EventInit) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            bubbles: init.bubbles,
+            cancelable: init.cancelable,
+            is_trusted: false,
+            time_stamp: 0.0,
+            data: EventData::None,
+            target: None,
+            current_target: None,
+            phase: EventPhase::None,
+            canceled: false,
+            stop_propagation: false,
+            stop_immediate_propagation: false,
+            in_passive_listener: false,
+        }
+    }
+
+    /// Creates an event that is dispatched by the user agent, with the `bubbles` and `cancelable` flags that the UI
+    /// Events spec defines for its type (<https://w3c.github.io/uievents/#events-mouse-types>)
+    pub fn trusted(event_type: &str, data: EventData, time_stamp: f64) -> Self {
+        let (bubbles, cancelable) = match event_type {
+            "mouseenter" | "mouseleave" | "focus" | "blur" => (false, false),
+            "input" => (true, false),
+            _ => (true, true),
+        };
+
+        Self {
+            is_trusted: true,
+            time_stamp,
+            data,
+            ..Self::new(event_type, EventInit { bubbles, cancelable })
+        }
+    }
+
+    pub fn target(&self) -> Option<NodeId> {
+        self.target
+    }
+
+    /// The node whose listeners are being invoked, `None` when the event isn't being dispatched
+    pub fn current_target(&self) -> Option<NodeId> {
+        self.current_target
+    }
+
+    pub fn event_phase(&self) -> EventPhase {
+        self.phase
+    }
+
+    /// Cancels the default action of the event, unless it can't be canceled or the listener is passive
+    pub fn prevent_default(&mut self) {
+        if self.cancelable && !self.in_passive_listener {
+            self.canceled = true;
+        }
+    }
+
+    pub fn default_prevented(&self) -> bool {
+        self.canceled
+    }
+
+    /// Stops the event from reaching other nodes, the other listeners of the current node are still invoked
+    pub fn stop_propagation(&mut self) {
+        self.stop_propagation = true;
+    }
+
+    /// Stops the event from reaching any other listener
+    pub fn stop_immediate_propagation(&mut self) {
+        self.stop_propagation = true;
+        self.stop_immediate_propagation = true;
+    }
+}
+
+/// Options of `addEventListener` (<https://dom.spec.whatwg.org/#dictdef-addeventlisteneroptions>)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListenerOptions {
+    pub capture: bool,
+    pub once: bool,
+    pub passive: bool,
+}
+
+struct Listener {
+    event_type: String,
+    callback: EventCallback,
+    options: ListenerOptions,
+    /// Set when the listener is removed, so a dispatch that already collected it won't invoke it anymore
+    removed: Cell<bool>,
+}
+
+/// The event listeners of the nodes of a document. Clones share the same listeners, so listeners can add and remove
+/// listeners while an event is dispatched.
+#[derive(Clone, Default)]
+pub struct EventTargets {
+    listeners: Rc<RefCell<HashMap<NodeId, Vec<Rc<Listener>>>>>,
+}
+
+impl EventTargets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a listener to a node. A listener that is already added with the same type, callback and capture flag is
+    /// not added again
+    pub fn add_event_listener(
+        &self,
+        target: NodeId,
+        event_type: &str,
+        callback: EventCallback,
+        options: ListenerOptions,
+    ) {
+        let mut listeners = self.listeners.borrow_mut();
+        let listeners = listeners.entry(target).or_default();
+
+        if listeners
+            .iter()
+            .any(|listener| listener.matches(event_type, &callback, options.capture))
+        {
+            return;
+        }
+
+        listeners.push(Rc::new(Listener {
+            event_type: event_type.to_string(),
+            callback,
+            options,
+            removed: Cell::new(false),
+        }));
+    }
+
+    pub fn remove_event_listener(&self, target: NodeId, event_type: &str, callback: &EventCallback, capture: bool) {
+        let mut listeners = self.listeners.borrow_mut();
+
+        let Some(listeners) = listeners.get_mut(&target) else {
+            return;
+        };
+
+        listeners.retain(|listener| {
+            if listener.matches(event_type, callback, capture) {
+                listener.removed.set(true);
+                return false;
+            }
+
+            true
+        });
+    }
+
+    /// Removes all listeners of a node, like when it is removed from the document
+    pub fn remove_all(&self, target: NodeId) {
+        if let Some(listeners) = self.listeners.borrow_mut().remove(&target) {
+            for listener in listeners {
+                listener.removed.set(true);
+            }
+        }
+    }
+
+    pub fn has_listeners(&self, target: NodeId, event_type: &str) -> bool {
+        self.listeners
+            .borrow()
+            .get(&target)
+            .is_some_and(|listeners| listeners.iter().any(|listener| listener.event_type == event_type))
+    }
+
+    /// Dispatches an event to a node of the document (<https://dom.spec.whatwg.org/#concept-event-dispatch>). Returns
+    /// false when a listener canceled the event, so its default action must not run.
+    ///
+    /// The path of the event is fixed before the first listener is invoked, and the document isn't borrowed while
+    /// listeners run, so they are free to change it.
+    pub fn dispatch<D: Document<C>, C: CssSystem>(
+        &self,
+        handle: &DocumentHandle<D, C>,
+        target: NodeId,
+        event: &mut Event,
+    ) -> bool {
+        let path = event_path(&*handle.get(), target);
+
+        self.dispatch_path(&path, event)
+    }
+
+    /// Dispatches an event along a path that starts at the target and ends at the root
+    fn dispatch_path(&self, path: &[NodeId], event: &mut Event) -> bool {
+        event.target = path.first().copied();
+        event.canceled = false;
+        event.stop_propagation = false;
+        event.stop_immediate_propagation = false;
+
+        for (idx, node) in path.iter().enumerate().rev() {
+            let phase = if idx == 0 {
+                EventPhase::AtTarget
+            } else {
+                EventPhase::Capturing
+            };
+
+            self.invoke(*node, event, phase, true);
+
+            if event.stop_propagation {
+                break;
+            }
+        }
+
+        for (idx, node) in path.iter().enumerate() {
+            if event.stop_propagation {
+                break;
+            }
+
+            let phase = if idx == 0 {
+                EventPhase::AtTarget
+            } else if event.bubbles {
+                EventPhase::Bubbling
+            } else {
+                break;
+            };
+
+            self.invoke(*node, event, phase, false);
+        }
+
+        event.phase = EventPhase::None;
+        event.current_target = None;
+        event.stop_propagation = false;
+        event.stop_immediate_propagation = false;
+
+        !event.canceled
+    }
+
+    /// Invokes the capture or the non-capture listeners of a node
+    fn invoke(&self, node: NodeId, event: &mut Event, phase: EventPhase, capture: bool) {
+        // Listeners that are added while the event is dispatched are not invoked for this event
+        let Some(listeners) = self.listeners.borrow().get(&node).cloned() else {
+            return;
+        };
+
+        event.phase = phase;
+        event.current_target = Some(node);
+
+        for listener in listeners {
+            if listener.removed.get() || listener.event_type != event.event_type || listener.options.capture != capture
+            {
+                continue;
+            }
+
+            if listener.options.once {
+                self.remove_event_listener(node, &listener.event_type, &listener.callback, capture);
+            }
+
+            event.in_passive_listener = listener.options.passive;
+
+            if let Err(e) = (listener.callback)(event) {
+                warn!("Uncaught error in event listener for {}: {e}", event.event_type);
+            }
+
+            event.in_passive_listener = false;
+
+            if event.stop_immediate_propagation {
+                return;
+            }
+        }
+    }
+}
+
+/// The path of an event, from the target up to the root of the document
+fn event_path<D: Document<C>, C: CssSystem>(doc: &D, target: NodeId) -> Vec<NodeId> {
+    let mut path = vec![target];
+    let mut current = target;
+
+    while let Some(parent) = doc.node_by_id(current).and_then(|node| node.parent_id()) {
+        path.push(parent);
+        current = parent;
+    }
+
+    path
+}
+
+impl Listener {
+    fn matches(&self, event_type: &str, callback: &EventCallback, capture: bool) -> bool {
+        self.event_type == event_type && Rc::ptr_eq(&self.callback, callback) && self.options.capture == capture
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html_compile;
+    use gosub_css3::system::Css3System;
+
+    /// Returns a listener that records the phase and the current target, and the list it records into
+    fn recorder(name: &'static str) -> (Rc<RefCell<Vec<String>>>, impl Fn(&'static str) -> EventCallback) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let make = {
+            let log = Rc::clone(&log);
+            move |label: &'static str| -> EventCallback {
+                let log = Rc::clone(&log);
+                Rc::new(move |event: &mut Event| {
+                    log.borrow_mut()
+                        .push(format!("{name} {label} {:?}", event.event_phase()));
+                    Ok(())
+                })
+            }
+        };
+
+        (log, make)
+    }
+
+    #[test]
+    fn dispatch_phases() {
+        let handle = html_compile::<Css3System>(r#"<div id="outer"><span id="inner">text</span></div>"#);
+        let doc = handle.get();
+
+        let outer = doc.node_by_named_id("outer").unwrap().id();
+        let inner = doc.node_by_named_id("inner").unwrap().id();
+
+        let targets = EventTargets::new();
+        let (log, listener) = recorder("click");
+
+        let capture = ListenerOptions {
+            capture: true,
+            ..Default::default()
+        };
+
+        targets.add_event_listener(outer, "click", listener("outer"), ListenerOptions::default());
+        targets.add_event_listener(outer, "click", listener("outer-capture"), capture);
+        targets.add_event_listener(inner, "click", listener("inner"), ListenerOptions::default());
+        targets.add_event_listener(inner, "keydown", listener("keydown"), ListenerOptions::default());
+
+        let mut event = Event::trusted("click", EventData::None, 0.0);
+        assert!(targets.dispatch(&handle, inner, &mut event));
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "click outer-capture Capturing",
+                "click inner AtTarget",
+                "click outer Bubbling"
+            ]
+        );
+        assert_eq!(event.target(), Some(inner));
+        assert_eq!(event.current_target(), None);
+    }
+
+    #[test]
+    fn stop_propagation_and_prevent_default() {
+        let handle = html_compile::<Css3System>(r#"<div id="outer"><a id="link" href="/">link</a></div>"#);
+        let doc = handle.get();
+
+        let outer = doc.node_by_named_id("outer").unwrap().id();
+        let link = doc.node_by_named_id("link").unwrap().id();
+
+        let targets = EventTargets::new();
+        let (log, listener) = recorder("click");
+
+        let stop: EventCallback = Rc::new(|event: &mut Event| {
+            event.prevent_default();
+            event.stop_propagation();
+            Ok(())
+        });
+
+        targets.add_event_listener(link, "click", stop, ListenerOptions::default());
+        targets.add_event_listener(outer, "click", listener("outer"), ListenerOptions::default());
+
+        let mut event = Event::trusted("click", EventData::None, 0.0);
+        assert!(!targets.dispatch(&handle, link, &mut event));
+        assert!(log.borrow().is_empty());
+
+        // Passive listeners can't cancel the event
+        let passive = ListenerOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let cancel: EventCallback = Rc::new(|event: &mut Event| {
+            event.prevent_default();
+            Ok(())
+        });
+
+        targets.add_event_listener(outer, "wheel", cancel, passive);
+
+        let mut event = Event::trusted("wheel", EventData::None, 0.0);
+        assert!(targets.dispatch(&handle, link, &mut event));
+    }
+
+    #[test]
+    fn once_listeners_are_removed() {
+        let handle = html_compile::<Css3System>(r#"<p id="p">text</p>"#);
+        let doc = handle.get();
+
+        let p = doc.node_by_named_id("p").unwrap().id();
+
+        let targets = EventTargets::new();
+        let (log, listener) = recorder("input");
+
+        let once = ListenerOptions {
+            once: true,
+            ..Default::default()
+        };
+
+        targets.add_event_listener(p, "input", listener("p"), once);
+
+        for _ in 0..2 {
+            let mut event = Event::trusted("input", EventData::None, 0.0);
+            targets.dispatch(&handle, p, &mut event);
+        }
+
+        assert_eq!(*log.borrow(), ["input p AtTarget"]);
+        assert!(!targets.has_listeners(p, "input"));
+    }
+}
@@ -13,6 +13,7 @@ use gosub_shared::traits::document::DocumentBuilder as _;
 pub mod document;
 pub mod dom;
 pub mod errors;
+pub mod events;
 pub mod node;
 pub mod parser;
 pub mod tokenizer;
@@ -20,7 +20,9 @@ use gosub_shared::byte_stream::{ByteStream, Location};
 use gosub_shared::document::DocumentHandle;
 use gosub_shared::node::NodeId;
 use gosub_shared::traits::css3::{CssOrigin, CssSystem};
-use gosub_shared::traits::document::{Document, DocumentBuilder, DocumentFragment, DocumentType};
+use gosub_shared::traits::document::{
+    Document, DocumentBuilder, DocumentFragment, DocumentType, Script, ScriptKind, ScriptSource, ScriptTiming,
+};
 use gosub_shared::traits::html5::ParserOptions;
 use gosub_shared::traits::node::TextDataType;
 use gosub_shared::traits::node::{ElementDataType, Node, QuirksMode};
@@ -1054,7 +1056,7 @@ where
                     }
                     Token::EndTag { name, .. } if name == "script" => {
                         // @todo: If the active speculative HTML parser is null and the JavaScript execution context stack is empty, then perform a microtask checkpoint.
-                        let _script_node = current_node!(self);
+                        let script_node_id = current_node!(self).id();
 
                         self.open_elements.pop();
                         self.insertion_mode = self.original_insertion_mode;
@@ -1064,7 +1066,9 @@ where
 
                         self.script_nesting_level += 1;
 
-                        // do script stuff
+                        // Scripts are not executed by the parser, they are handed over to the script runner
+                        // through the document
+                        self.prepare_script(script_node_id);
 
                         self.script_nesting_level -= 1;
                         if self.script_nesting_level == 0 {
@@ -4201,6 +4205,84 @@ where
         }
     }
 
+    /// Prepares a script element as its end tag has been seen. Scripts that need to be executed are added to the
+    /// document together with their timing: module scripts are deferred by default, classic scripts only when
+    /// they have a `src` and a `defer` attribute.
+    fn prepare_script(&mut self, node_id: NodeId) {
+        let node = get_node_by_id!(self.document, node_id);
+        let attributes = get_element_data!(node).attributes().clone();
+
+        let kind = match script_type(&attributes) {
+            Some(kind) => kind,
+            None => return,
+        };
+
+        if !self.scripting_enabled {
+            return;
+        }
+
+        // We support modules, so classic scripts marked as fallback are not needed
+        if kind == ScriptKind::Classic && attributes.contains_key("nomodule") {
+            return;
+        }
+
+        let source = match attributes.get("src") {
+            Some(_) if kind == ScriptKind::ImportMap => {
+                self.parse_error("external import maps are not supported");
+                return;
+            }
+            Some(src) if src.is_empty() => {
+                self.parse_error("script element with an empty src attribute");
+                return;
+            }
+            Some(src) => {
+                let url = match self.document.get().url() {
+                    Some(base) => base.join(src),
+                    None => Url::parse(src),
+                };
+
+                match url {
+                    Ok(url) => ScriptSource::External(url),
+                    Err(err) => {
+                        self.parse_error(format!("invalid script src '{}': {}", src, err).as_str());
+                        return;
+                    }
+                }
+            }
+            None => {
+                let mut text = String::new();
+                for child_id in node.children() {
+                    if let Some(data) = get_node_by_id!(self.document, *child_id).get_text_data() {
+                        text.push_str(data.value());
+                    }
+                }
+
+                if text.is_empty() {
+                    return;
+                }
+
+                ScriptSource::Inline(text)
+            }
+        };
+
+        let is_external = matches!(source, ScriptSource::External(_));
+        let timing = match kind {
+            ScriptKind::Module if attributes.contains_key("async") => ScriptTiming::Async,
+            ScriptKind::Module => ScriptTiming::Deferred,
+            ScriptKind::ImportMap => ScriptTiming::Blocking,
+            ScriptKind::Classic if is_external && attributes.contains_key("async") => ScriptTiming::Async,
+            ScriptKind::Classic if is_external && attributes.contains_key("defer") => ScriptTiming::Deferred,
+            ScriptKind::Classic => ScriptTiming::Blocking,
+        };
+
+        self.document.get_mut().add_script(Script {
+            node_id,
+            kind,
+            source,
+            timing,
+        });
+    }
+
     fn handle_link_element(&mut self, attributes: HashMap<String, String>) {
         if attributes.contains_key("rel") && attributes.contains_key("itemprop") {
             // cannot have them both
@@ -4273,6 +4355,45 @@ where
     }
 }
 
+/// MIME types that mark a script as a classic JavaScript script
+const JAVASCRIPT_MIME_TYPES: [&str; 16] = [
+    "application/ecmascript",
+    "application/javascript",
+    "application/x-ecmascript",
+    "application/x-javascript",
+    "text/ecmascript",
+    "text/javascript",
+    "text/javascript1.0",
+    "text/javascript1.1",
+    "text/javascript1.2",
+    "text/javascript1.3",
+    "text/javascript1.4",
+    "text/javascript1.5",
+    "text/jscript",
+    "text/livescript",
+    "text/x-ecmascript",
+    "text/x-javascript",
+];
+
+/// Determines the kind of script from the `type` and `language` attributes, or None when it is a data block that
+/// should not be executed
+fn script_type(attributes: &HashMap<String, String>) -> Option<ScriptKind> {
+    let ty = match (attributes.get("type"), attributes.get("language")) {
+        (Some(ty), _) if ty.is_empty() => return Some(ScriptKind::Classic),
+        (None, Some(language)) if language.is_empty() => return Some(ScriptKind::Classic),
+        (None, None) => return Some(ScriptKind::Classic),
+        (Some(ty), _) => ty.trim().to_ascii_lowercase(),
+        (None, Some(language)) => format!("text/{}", language.to_ascii_lowercase()),
+    };
+
+    match ty.as_str() {
+        "module" => Some(ScriptKind::Module),
+        "importmap" => Some(ScriptKind::ImportMap),
+        ty if JAVASCRIPT_MIME_TYPES.contains(&ty) => Some(ScriptKind::Classic),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -4636,4 +4757,72 @@ mod test {
         assert_eq!(div.id, NodeId::from(4usize));
         assert_eq!(div.get_element_data().unwrap().name(), "div");
     }
+
+    #[test]
+    fn prepare_scripts() {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(
+            r#"<script>inline()</script>
+            <script type="module" src="app.js"></script>
+            <script type="module" async>import "./a.js"</script>
+            <script src="lib.js" defer></script>
+            <script src="ads.js" async></script>
+            <script type="importmap">{"imports": {}}</script>
+            <script type="text/template">not a script</script>
+            <script nomodule>legacy()</script>
+            <script></script>"#,
+            Some(Encoding::UTF8),
+        );
+        stream.close();
+
+        let base = Url::parse("https://example.com/app/index.html").unwrap();
+        let mut doc_handle = DocumentBuilderImpl::new_document(Some(base));
+        let _ =
+            Html5Parser::<DocumentImpl<Css3System>, Css3System>::parse_document(&mut stream, doc_handle.clone(), None);
+
+        let scripts: Vec<_> = doc_handle
+            .get_mut()
+            .take_scripts()
+            .into_iter()
+            .map(|script| (script.kind, script.source, script.timing))
+            .collect();
+
+        let url = |path: &str| ScriptSource::External(Url::parse(path).unwrap());
+        assert_eq!(
+            scripts,
+            vec![
+                (
+                    ScriptKind::Classic,
+                    ScriptSource::Inline("inline()".into()),
+                    ScriptTiming::Blocking
+                ),
+                (
+                    ScriptKind::Module,
+                    url("https://example.com/app/app.js"),
+                    ScriptTiming::Deferred
+                ),
+                (
+                    ScriptKind::Module,
+                    ScriptSource::Inline(r#"import "./a.js""#.into()),
+                    ScriptTiming::Async
+                ),
+                (
+                    ScriptKind::Classic,
+                    url("https://example.com/app/lib.js"),
+                    ScriptTiming::Deferred
+                ),
+                (
+                    ScriptKind::Classic,
+                    url("https://example.com/app/ads.js"),
+                    ScriptTiming::Async
+                ),
+                (
+                    ScriptKind::ImportMap,
+                    ScriptSource::Inline(r#"{"imports": {}}"#.into()),
+                    ScriptTiming::Blocking
+                ),
+            ]
+        );
+        assert!(doc_handle.get_mut().take_scripts().is_empty());
+    }
 }
@@ -1,8 +1,13 @@
 //! Console api as described by <https://console.spec.whatwg.org/>
 mod buffer;
 mod formatter;
+mod interop;
 mod writable_printer;
 
+pub use buffer::Buffer;
+pub use formatter::ConsoleValue;
+pub use writable_printer::WritablePrinter;
+
 use crate::console::formatter::Formatter;
 use std::collections::HashMap;
 use std::fmt;
@@ -28,6 +33,7 @@ pub enum LogLevel {
     Dir,
     Dirxml,
     Trace,
+    Table,
 }
 
 impl fmt::Display for LogLevel {
@@ -131,10 +137,12 @@ impl Console {
         self.logger(LogLevel::Log, data);
     }
 
-    /// Emit table if tabular data is supported
-    pub fn table(&mut self, tabular_data: String, _properties: &[&str]) {
-        // TODO: needs implementation
-        self.printer.print(LogLevel::Log, &[&tabular_data], &[]);
+    /// Emit table if tabular data is supported, otherwise the data is logged
+    pub fn table(&mut self, tabular_data: &ConsoleValue, properties: &[&str]) {
+        match formatter::table(tabular_data, properties) {
+            Some(table) => self.printer.print(LogLevel::Table, &[&table], &[]),
+            None => self.log(&[tabular_data]),
+        }
     }
 
     /// Emit a trace message
@@ -154,8 +162,9 @@ impl Console {
         self.printer.print(LogLevel::Dir, &[&item], options);
     }
 
-    pub fn dirxml(&self, _data: &[&dyn fmt::Display]) {
-        todo!()
+    /// Emit the given data as a list of xml/html nodes (if possible)
+    pub fn dirxml(&mut self, data: &[&dyn fmt::Display]) {
+        self.logger(LogLevel::Dirxml, data);
     }
 
     /// Create a counter named "label"
@@ -253,32 +262,35 @@ impl Console {
         }
         let message = message.trim_end();
 
+        let Some(timer) = self.timer_map.get(label) else {
+            let warning = format!("Timer '{label}' does not exist");
+            self.logger(LogLevel::Warn, &[&warning]);
+            return;
+        };
+
         let cur = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(n) => n.as_millis(),
             Err(_) => 0,
         };
 
-        let concat = format!(
-            "{}: {}ms{}",
-            label.to_owned(),
-            cur - self.timer_map.get(label).unwrap().start,
-            message
-        );
+        let concat = format!("{}: {}ms{}", label.to_owned(), cur.saturating_sub(timer.start), message);
         self.printer.print(LogLevel::TimeLog, &[&concat], &[]);
     }
 
     /// End the timer with the given label
     pub fn time_end(&mut self, label: &str) {
+        let Some(timer) = self.timer_map.remove(label) else {
+            let warning = format!("Timer '{label}' does not exist");
+            self.logger(LogLevel::Warn, &[&warning]);
+            return;
+        };
+
         let end = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(n) => n.as_millis(),
             Err(_) => 0,
         };
 
-        let concat = format!(
-            "{}: {}ms",
-            label.to_owned(),
-            end - self.timer_map.get(label).unwrap().start
-        );
+        let concat = format!("{}: {}ms", label.to_owned(), end.saturating_sub(timer.start));
         self.printer.print(LogLevel::TimeEnd, &[&concat], &[]);
     }
 
@@ -310,7 +322,7 @@ pub trait Printer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::console::{buffer::Buffer, writable_printer::WritablePrinter};
+
     use regex::Regex;
     use std::cell::RefCell;
     use std::rc::Rc;
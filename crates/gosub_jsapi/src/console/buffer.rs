@@ -10,13 +10,11 @@ pub struct Buffer {
 
 impl Buffer {
     /// Creates a new buffer
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self::default()
     }
 
     /// Converts the buffer to a String
-    #[allow(dead_code)]
     pub fn try_to_string(&self) -> Result<String> {
         Ok(String::from_utf8(self.buf.clone())?)
     }
@@ -1,5 +1,87 @@
 use std::fmt;
 
+/// A value as it is passed to the console from the javascript engine
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleValue {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A function with its name (empty when anonymous)
+    Function(String),
+    Array(Vec<ConsoleValue>),
+    /// An object with its own enumerable properties in order
+    Object(Vec<(String, ConsoleValue)>),
+    /// A value that is displayed as-is, like objects that are nested too deep to inspect
+    Opaque(String),
+}
+
+impl ConsoleValue {
+    /// Returns true when the value is truthy according to javascript
+    #[must_use]
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            ConsoleValue::Undefined | ConsoleValue::Null => false,
+            ConsoleValue::Bool(b) => *b,
+            ConsoleValue::Number(n) => *n != 0.0 && !n.is_nan(),
+            ConsoleValue::String(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+
+    /// Formats the value the way it is shown inside an object or array, where strings are quoted
+    #[must_use]
+    pub fn inspect(&self) -> String {
+        match self {
+            ConsoleValue::String(s) => format!("'{s}'"),
+            ConsoleValue::Array(items) if items.is_empty() => "[]".to_string(),
+            ConsoleValue::Array(items) => {
+                let items = items.iter().map(ConsoleValue::inspect).collect::<Vec<_>>();
+                format!("[ {} ]", items.join(", "))
+            }
+            ConsoleValue::Object(props) if props.is_empty() => "{}".to_string(),
+            ConsoleValue::Object(props) => {
+                let props = props
+                    .iter()
+                    .map(|(key, value)| format!("{key}: {}", value.inspect()))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", props.join(", "))
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConsoleValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsoleValue::Undefined => write!(f, "undefined"),
+            ConsoleValue::Null => write!(f, "null"),
+            ConsoleValue::Bool(b) => write!(f, "{b}"),
+            ConsoleValue::Number(n) => write!(f, "{}", format_number(*n)),
+            ConsoleValue::String(s) => write!(f, "{s}"),
+            ConsoleValue::Function(name) if name.is_empty() => write!(f, "[Function (anonymous)]"),
+            ConsoleValue::Function(name) => write!(f, "[Function: {name}]"),
+            ConsoleValue::Array(_) | ConsoleValue::Object(_) => write!(f, "{}", self.inspect()),
+            ConsoleValue::Opaque(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// Formats a number the way javascript converts it to a string
+fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if n.fract() == 0.0 && n.abs() < 1e21 {
+        format!("{n:.0}")
+    } else {
+        n.to_string()
+    }
+}
+
 /// Formatting structure
 pub struct Formatter;
 
@@ -10,13 +92,228 @@ impl Formatter {
         Self {}
     }
 
-    /// Formats the given string based on the formatting arguments and data provided
+    /// Formats the given arguments. When there is more than one argument, format specifiers (%s, %d, %i, %f,
+    /// %o, %O and %c) in the first argument are substituted by the arguments that follow. Any arguments
+    /// that are left are appended, separated by spaces.
     pub fn format(&self, args: &[&dyn fmt::Display]) -> String {
+        let Some((first, rest)) = args.split_first() else {
+            return String::new();
+        };
+
+        let target = first.to_string();
+        let mut rest = rest.iter();
         let mut s = String::new();
-        for arg in args {
-            s.push_str(&format!("{arg} "));
+
+        let mut chars = target.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                s.push(c);
+                continue;
+            }
+
+            match chars.peek() {
+                Some('%') => {
+                    chars.next();
+                    s.push('%');
+                }
+                Some(&spec) if "sdifoOc".contains(spec) => {
+                    let Some(arg) = rest.next() else {
+                        // Specifiers without an argument are kept as-is
+                        s.push(c);
+                        continue;
+                    };
+                    chars.next();
+
+                    let arg = arg.to_string();
+                    match spec {
+                        'd' | 'i' => s.push_str(&to_integer(&arg)),
+                        'f' => s.push_str(&to_float(&arg)),
+                        // Styling is not supported by text based printers
+                        'c' => {}
+                        _ => s.push_str(&arg),
+                    }
+                }
+                _ => s.push(c),
+            }
+        }
+
+        for arg in rest {
+            s.push_str(&format!(" {arg}"));
+        }
+
+        s
+    }
+}
+
+/// Converts the argument to an integer like `parseInt` does
+fn to_integer(arg: &str) -> String {
+    match arg.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() => format_number(n.trunc()),
+        _ => "NaN".to_string(),
+    }
+}
+
+/// Converts the argument to a float like `parseFloat` does
+fn to_float(arg: &str) -> String {
+    match arg.trim().parse::<f64>() {
+        Ok(n) => format_number(n),
+        _ => "NaN".to_string(),
+    }
+}
+
+/// Renders tabular data as a table. Arrays are indexed by position and objects by their property names. When
+/// properties are given, only those columns are shown. Returns `None` when the data is not tabular.
+pub fn table(data: &ConsoleValue, properties: &[&str]) -> Option<String> {
+    let rows: Vec<(String, &ConsoleValue)> = match data {
+        ConsoleValue::Array(items) => items.iter().enumerate().map(|(i, v)| (i.to_string(), v)).collect(),
+        ConsoleValue::Object(props) => props.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        _ => return None,
+    };
+
+    let mut columns: Vec<String> = properties.iter().map(|p| (*p).to_string()).collect();
+    let mut has_values = false;
+    for (_, value) in &rows {
+        let keys: Vec<String> = match value {
+            ConsoleValue::Array(items) => (0..items.len()).map(|i| i.to_string()).collect(),
+            ConsoleValue::Object(props) => props.iter().map(|(k, _)| k.clone()).collect(),
+            _ => {
+                has_values = true;
+                vec![]
+            }
+        };
+        if properties.is_empty() {
+            for key in keys {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
         }
+    }
+
+    let mut header = vec!["(index)".to_string()];
+    header.extend(columns.iter().cloned());
+    if has_values {
+        header.push("Values".to_string());
+    }
+
+    let mut lines = vec![];
+    for (index, value) in &rows {
+        let mut line = vec![index.clone()];
+        for column in &columns {
+            let cell = match value {
+                ConsoleValue::Array(items) => column.parse::<usize>().ok().and_then(|i| items.get(i)),
+                ConsoleValue::Object(props) => props.iter().find(|(k, _)| k == column).map(|(_, v)| v),
+                _ => None,
+            };
+            line.push(cell.map(ConsoleValue::inspect).unwrap_or_default());
+        }
+        if has_values {
+            let cell = match value {
+                ConsoleValue::Array(_) | ConsoleValue::Object(_) => String::new(),
+                _ => value.inspect(),
+            };
+            line.push(cell);
+        }
+        lines.push(line);
+    }
+
+    let widths = header
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            lines
+                .iter()
+                .map(|line| line[i].chars().count())
+                .fold(h.chars().count(), usize::max)
+        })
+        .collect::<Vec<_>>();
+
+    let border = |left: &str, mid: &str, right: &str| {
+        let cells = widths.iter().map(|w| "─".repeat(w + 2)).collect::<Vec<_>>();
+        format!("{left}{}{right}", cells.join(mid))
+    };
+    let row = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!(" {cell}{} ", " ".repeat(w - cell.chars().count())))
+            .collect::<Vec<_>>();
+        format!("│{}│", cells.join("│"))
+    };
+
+    let mut out = vec![border("┌", "┬", "┐"), row(&header), border("├", "┼", "┤")];
+    out.extend(lines.iter().map(|line| row(line)));
+    out.push(border("└", "┴", "┘"));
+
+    Some(out.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutions() {
+        let f = Formatter::new();
+
+        assert_eq!(f.format(&[&"foo", &"bar", &1i32]), "foo bar 1");
+        assert_eq!(f.format(&[&"%s is %d years", &"Bob", &42.7f64]), "Bob is 42 years");
+        assert_eq!(f.format(&[&"%f%% %i", &"1.5", &"x"]), "1.5% NaN");
+        assert_eq!(f.format(&[&"%c%o and %s", &"color: red", &"a"]), "a and %s");
+        assert_eq!(f.format(&[&"%s", &"a", &"b"]), "a b");
+    }
+
+    #[test]
+    fn values() {
+        let value = ConsoleValue::Object(vec![
+            ("a".to_string(), ConsoleValue::Number(1.0)),
+            (
+                "b".to_string(),
+                ConsoleValue::Array(vec![ConsoleValue::String("x".into())]),
+            ),
+            ("c".to_string(), ConsoleValue::Function("foo".into())),
+        ]);
+
+        assert_eq!(value.to_string(), "{ a: 1, b: [ 'x' ], c: [Function: foo] }");
+        assert_eq!(ConsoleValue::String("x".into()).to_string(), "x");
+        assert_eq!(ConsoleValue::Number(0.5).to_string(), "0.5");
+    }
+
+    #[test]
+    fn tables() {
+        let data = ConsoleValue::Array(vec![
+            ConsoleValue::Object(vec![
+                ("a".to_string(), ConsoleValue::Number(1.0)),
+                ("b".to_string(), ConsoleValue::String("x".into())),
+            ]),
+            ConsoleValue::Object(vec![("a".to_string(), ConsoleValue::Number(2.0))]),
+            ConsoleValue::Bool(true),
+        ]);
+
+        assert_eq!(
+            table(&data, &[]).unwrap(),
+            "\
+┌─────────┬───┬─────┬────────┐
+│ (index) │ a │ b   │ Values │
+├─────────┼───┼─────┼────────┤
+│ 0       │ 1 │ 'x' │        │
+│ 1       │ 2 │     │        │
+│ 2       │   │     │ true   │
+└─────────┴───┴─────┴────────┘"
+        );
+
+        assert_eq!(
+            table(&data, &["b"]).unwrap(),
+            "\
+┌─────────┬─────┬────────┐
+│ (index) │ b   │ Values │
+├─────────┼─────┼────────┤
+│ 0       │ 'x' │        │
+│ 1       │     │        │
+│ 2       │     │ true   │
+└─────────┴─────┴────────┘"
+        );
 
-        s.trim_end().to_owned()
+        assert!(table(&ConsoleValue::Number(1.0), &[]).is_none());
     }
 }
@@ -0,0 +1,265 @@
+use crate::console::{Console, ConsoleValue};
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{
+    JSArray, JSContext, JSFunctionCallBackVariadic, JSFunctionVariadic, JSInterop, JSObject, JSRuntime, JSType,
+    JSValue, TypedArrayKind, VariadicArgsInternal,
+};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/// Objects and arrays nested deeper than this are not inspected
+const MAX_DEPTH: usize = 2;
+
+/// A console method that is called with the converted javascript arguments
+type Method = fn(&mut Console, &[ConsoleValue]);
+
+impl JSInterop for Console {
+    fn implement<RT: JSRuntime>(s: Rc<RefCell<Self>>, mut ctx: RT::Context) -> Result<()> {
+        let obj = ctx.new_global_object("console")?;
+
+        let methods: [(&str, Method); 17] = [
+            ("log", |c, args| c.log(&display(args))),
+            ("info", |c, args| c.info(&display(args))),
+            ("warn", |c, args| c.warn(&display(args))),
+            ("error", |c, args| c.error(&display(args))),
+            ("debug", |c, args| c.debug(&display(args))),
+            ("trace", |c, args| c.trace(&display(args))),
+            ("assert", |c, args| {
+                let condition = args.first().is_some_and(ConsoleValue::is_truthy);
+                c.assert(condition, &display(args.get(1..).unwrap_or_default()));
+            }),
+            ("clear", |c, _| c.clear()),
+            ("count", |c, args| c.count(&label(args))),
+            ("countReset", |c, args| c.count_reset(&label(args))),
+            ("group", |c, args| c.group(&display(args))),
+            ("groupCollapsed", |c, args| c.group_collapsed(&display(args))),
+            ("groupEnd", |c, _| {
+                c.group_end();
+            }),
+            ("time", |c, args| c.time(&label(args))),
+            ("timeLog", |c, args| {
+                c.time_log(&label(args), &display(args.get(1..).unwrap_or_default()));
+            }),
+            ("timeEnd", |c, args| c.time_end(&label(args))),
+            ("table", |c, args| {
+                let properties = match args.get(1) {
+                    Some(ConsoleValue::Array(props)) => props.iter().map(ToString::to_string).collect(),
+                    _ => vec![],
+                };
+                let properties = properties.iter().map(String::as_str).collect::<Vec<_>>();
+                c.table(args.first().unwrap_or(&ConsoleValue::Undefined), &properties);
+            }),
+        ];
+
+        for (name, method) in methods {
+            let console = Rc::clone(&s);
+            let function = RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+                let ctx = cb.context();
+                let args = cb
+                    .args()
+                    .as_vec(ctx.clone())
+                    .iter()
+                    .map(|arg| to_console_value::<RT>(arg, 0))
+                    .collect::<Vec<_>>();
+
+                method(&mut console.borrow_mut(), &args);
+
+                match RT::Value::new_undefined(ctx) {
+                    Ok(value) => cb.ret(value),
+                    Err(e) => cb.error(e),
+                }
+            })?;
+            obj.set_method_variadic(name, &function)?;
+        }
+
+        let dir = {
+            let console = Rc::clone(&s);
+            RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+                let ctx = cb.context();
+                let item = cb
+                    .args()
+                    .get(0, ctx.clone())
+                    .map_or(ConsoleValue::Undefined, |arg| to_console_value::<RT>(&arg, 0));
+
+                console.borrow_mut().dir(&item.inspect(), &[]);
+
+                match RT::Value::new_undefined(ctx) {
+                    Ok(value) => cb.ret(value),
+                    Err(e) => cb.error(e),
+                }
+            })?
+        };
+        obj.set_method_variadic("dir", &dir)?;
+
+        let dirxml = {
+            let console = Rc::clone(&s);
+            RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+                let ctx = cb.context();
+                let args = cb
+                    .args()
+                    .as_vec(ctx.clone())
+                    .iter()
+                    .map(|arg| to_console_value::<RT>(arg, 0))
+                    .collect::<Vec<_>>();
+
+                console.borrow_mut().dirxml(&display(&args));
+
+                match RT::Value::new_undefined(ctx) {
+                    Ok(value) => cb.ret(value),
+                    Err(e) => cb.error(e),
+                }
+            })?
+        };
+        obj.set_method_variadic("dirxml", &dirxml)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the arguments as displayable values for the console
+fn display(args: &[ConsoleValue]) -> Vec<&dyn fmt::Display> {
+    args.iter().map(|arg| arg as &dyn fmt::Display).collect()
+}
+
+/// Returns the label that is passed as the first argument, or "default" when there is none
+fn label(args: &[ConsoleValue]) -> String {
+    match args.first() {
+        None | Some(ConsoleValue::Undefined) => "default".to_string(),
+        Some(label) => label.to_string(),
+    }
+}
+
+/// Converts a javascript value to a console value, inspecting arrays and objects up to `MAX_DEPTH` levels deep
+fn to_console_value<RT: JSRuntime>(value: &RT::Value, depth: usize) -> ConsoleValue {
+    match value.type_of() {
+        JSType::Undefined => ConsoleValue::Undefined,
+        JSType::Null => ConsoleValue::Null,
+        JSType::Boolean => ConsoleValue::Bool(value.as_bool().unwrap_or_default()),
+        JSType::Number => ConsoleValue::Number(value.as_number().unwrap_or(f64::NAN)),
+        JSType::String => ConsoleValue::String(value.as_string().unwrap_or_default()),
+        JSType::Function => ConsoleValue::Function(
+            value
+                .as_object()
+                .and_then(|obj| obj.get_property("name"))
+                .and_then(|name| name.as_string())
+                .unwrap_or_default(),
+        ),
+        JSType::Array if depth >= MAX_DEPTH => ConsoleValue::Opaque("[Array]".to_string()),
+        JSType::Array => match value.as_array() {
+            Ok(array) => ConsoleValue::Array(
+                array
+                    .as_vec()
+                    .iter()
+                    .map(|item| to_console_value::<RT>(item, depth + 1))
+                    .collect(),
+            ),
+            Err(_) => ConsoleValue::Opaque("[Array]".to_string()),
+        },
+        JSType::Object if depth >= MAX_DEPTH => ConsoleValue::Opaque("[Object]".to_string()),
+        JSType::Object => {
+            let Ok(obj) = value.as_object() else {
+                return ConsoleValue::Opaque("[Object]".to_string());
+            };
+
+            let props = obj
+                .keys()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|key| {
+                    let value = obj.get_property(&key).ok()?;
+                    Some((key, to_console_value::<RT>(&value, depth + 1)))
+                })
+                .collect();
+            ConsoleValue::Object(props)
+        }
+        JSType::BigInt => ConsoleValue::Opaque(format!("{}n", value.as_string().unwrap_or_default())),
+        JSType::Symbol => ConsoleValue::Opaque(format!(
+            "Symbol({})",
+            value.as_symbol_description().ok().flatten().unwrap_or_default()
+        )),
+        JSType::Date => ConsoleValue::Opaque(value.as_string().unwrap_or_else(|_| "[Date]".to_string())),
+        JSType::Promise => ConsoleValue::Opaque("Promise {}".to_string()),
+        JSType::ArrayBuffer => ConsoleValue::Opaque(format!(
+            "ArrayBuffer {{ byteLength: {} }}",
+            value.as_bytes().map(|bytes| bytes.len()).unwrap_or_default()
+        )),
+        JSType::TypedArray => {
+            let name = value.typed_array_kind().map_or("TypedArray", TypedArrayKind::name);
+            ConsoleValue::Opaque(format!("{name} [{}]", value.as_string().unwrap_or_default()))
+        }
+        JSType::Other(name) => ConsoleValue::Opaque(value.as_string().unwrap_or(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{Buffer, WritablePrinter};
+    use gosub_v8::V8Engine;
+
+    #[test]
+    fn console_in_javascript() {
+        let buffer = Rc::new(RefCell::new(Buffer::new()));
+        let console = Console::new(Box::new(WritablePrinter::new(Rc::clone(&buffer))));
+
+        let mut runtime = V8Engine::new();
+        let mut ctx = runtime.new_context().unwrap();
+        Console::implement::<V8Engine>(Rc::new(RefCell::new(console)), ctx.clone()).unwrap();
+
+        ctx.run(
+            r#"
+            console.log("%s is %d years", "Bob", 42.5, { a: [1, "x"] });
+            console.warn("plain", null, undefined, true);
+            console.count();
+            console.count();
+            console.table([{ a: 1, b: "x" }, { a: 2 }]);
+            console.assert(1 === 2, "math");
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            buffer.borrow().try_to_string().unwrap(),
+            "\
+[log] Bob is 42 years { a: [ 1, 'x' ] }
+[warn] plain null undefined true
+[count] default: 1
+[count] default: 2
+┌─────────┬───┬─────┐
+│ (index) │ a │ b   │
+├─────────┼───┼─────┤
+│ 0       │ 1 │ 'x' │
+│ 1       │ 2 │     │
+└─────────┴───┴─────┘
+[assert] Assertion failed: math
+"
+        );
+    }
+
+    #[test]
+    fn console_special_values() {
+        let buffer = Rc::new(RefCell::new(Buffer::new()));
+        let console = Console::new(Box::new(WritablePrinter::new(Rc::clone(&buffer))));
+
+        let mut runtime = V8Engine::new();
+        let mut ctx = runtime.new_context().unwrap();
+        Console::implement::<V8Engine>(Rc::new(RefCell::new(console)), ctx.clone()).unwrap();
+
+        ctx.run(
+            r#"
+            console.log(12n, Symbol("id"), Promise.resolve(1));
+            console.log(new ArrayBuffer(8), new Uint8Array([1, 2]));
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            buffer.borrow().try_to_string().unwrap(),
+            "\
+[log] 12n Symbol(id) Promise {}
+[log] ArrayBuffer { byteLength: 8 } Uint8Array [1,2]
+"
+        );
+    }
+}
@@ -12,26 +12,23 @@ pub struct Group {
 type Writer<W> = Rc<RefCell<W>>;
 
 /// A writable printer that can be used to write to a buffer
-pub(crate) struct WritablePrinter<W: Write> {
+pub struct WritablePrinter<W: Write> {
     writer: Writer<W>,
     groups: Vec<Group>,
 }
 
 impl<W: Write> WritablePrinter<W> {
     /// Creates a new writable printer
-    #[allow(dead_code)]
     pub fn new(writer: Rc<RefCell<W>>) -> Self {
         Self { writer, groups: vec![] }
     }
 
     /// Returns a reference to the writer
-    #[allow(dead_code)]
     pub fn get_writer(&self) -> &Writer<W> {
         &self.writer
     }
 
     /// Returns the writer
-    #[allow(dead_code)]
     pub fn into_writer(self) -> Writer<W> {
         self.writer
     }
@@ -67,9 +64,23 @@ impl<W: Write> Printer for WritablePrinter<W> {
         let mut writer = self.writer.borrow_mut();
 
         let _ = match log_level {
-            LogLevel::Info | LogLevel::Warn | LogLevel::Error | LogLevel::Log | LogLevel::Assert => {
+            LogLevel::Info
+            | LogLevel::Warn
+            | LogLevel::Error
+            | LogLevel::Log
+            | LogLevel::Debug
+            | LogLevel::Assert
+            | LogLevel::Trace
+            | LogLevel::Dir
+            | LogLevel::Dirxml
+            | LogLevel::Count
+            | LogLevel::CountReset => {
                 writeln!(writer, "{group_prefix}[{log_level}] {data}")
             }
+            LogLevel::TimeLog => writeln!(writer, "{group_prefix}{data}"),
+            LogLevel::Table => data
+                .lines()
+                .try_for_each(|line| writeln!(writer, "{group_prefix}{line}")),
             LogLevel::Group => writeln!(writer, "{group_prefix}Expanded group: {data}"),
             LogLevel::GroupCollapsed => {
                 writeln!(writer, "{group_prefix}Collapsed group: {data}")
@@ -94,7 +105,7 @@ impl<W: Write> Printer for WritablePrinter<W> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::console::buffer::Buffer;
+    use crate::console::Buffer;
 
     #[test]
     fn printer() {
@@ -0,0 +1,440 @@
+//! CSS object model (<https://drafts.csswg.org/cssom/>)
+//!
+//! Scripts see the author stylesheets of the document as `document.styleSheets`, the `style` attribute of an element
+//! as `element.style`, and the styles of the render tree through `getComputedStyle()`. Changes to stylesheets and
+//! `style` attributes are recorded as mutations of the document, so the render tree restyles the affected nodes once
+//! it applies the mutations. That happens at the latest when a script asks for a computed style.
+//!
+//! Until there are DOM bindings, elements are minimal objects that only know their node id and their `style`.
+use anyhow::anyhow;
+use gosub_shared::document::DocumentHandle;
+use gosub_shared::errors::CssResult;
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::css3::{CssOrigin, CssProperty, CssPropertyMap, CssStylesheet, CssSystem, StyleDeclaration};
+use gosub_shared::traits::document::Document;
+use gosub_shared::traits::node::{ElementDataType, Node};
+use gosub_shared::traits::render_tree::{RenderTree, RenderTreeNode};
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{
+    IntoJSValue, JSContext, JSFunctionCallBackVariadic, JSFunctionVariadic, JSObject, JSRuntime, JSValue,
+    VariadicArgsInternal,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The document and render tree that the CSSOM of a context works on
+struct Cssom<C: CssSystem, D: Document<C>, T> {
+    document: DocumentHandle<D, C>,
+    render_tree: Rc<RefCell<T>>,
+}
+
+impl<C, D, T> Cssom<C, D, T>
+where
+    C: CssSystem,
+    D: Document<C>,
+    T: RenderTree<C, NodeId = NodeId>,
+{
+    /// Returns the indices of the author stylesheets in the stylesheets of the document, the user agent stylesheet
+    /// is not visible to scripts
+    fn author_sheets(&self) -> Vec<usize> {
+        self.document
+            .get()
+            .stylesheets()
+            .iter()
+            .enumerate()
+            .filter(|(_, sheet)| sheet.origin() == CssOrigin::Author)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Calls `f` with the author stylesheet at the index
+    fn read_sheet<R>(&self, index: usize, f: impl FnOnce(&C::Stylesheet) -> Option<R>) -> Result<R> {
+        let Some(&sheet) = self.author_sheets().get(index) else {
+            return Err(anyhow!("there is no stylesheet at index {index}"));
+        };
+
+        f(&self.document.get().stylesheets()[sheet]).ok_or_else(|| anyhow!("there is no rule at the given index"))
+    }
+
+    /// Calls `f` to change the author stylesheet at the index, which restyles the whole document
+    fn update_sheet(&self, index: usize, f: impl FnOnce(&mut C::Stylesheet) -> CssResult<()>) -> Result<()> {
+        let Some(&sheet) = self.author_sheets().get(index) else {
+            return Err(anyhow!("there is no stylesheet at index {index}"));
+        };
+
+        let mut document = self.document.clone();
+        let mut doc = document.get_mut();
+
+        doc.update_stylesheet(sheet, f).map_err(|err| anyhow!("{err}"))
+    }
+
+    /// Returns the declarations of the `style` attribute of the element
+    fn inline_style(&self, node_id: NodeId) -> Result<Vec<StyleDeclaration>> {
+        let doc = self.document.get();
+        let data = doc
+            .node_by_id(node_id)
+            .and_then(|node| node.get_element_data())
+            .ok_or_else(|| anyhow!("node {node_id} is not an element"))?;
+
+        Ok(data
+            .attribute("style")
+            .map(|style| C::parse_declarations(style))
+            .unwrap_or_default())
+    }
+
+    /// Replaces the `style` attribute of the element
+    fn set_inline_style(&self, node_id: NodeId, css_text: &str) -> Result<()> {
+        let mut document = self.document.clone();
+        let mut doc = document.get_mut();
+
+        let mut node = doc
+            .node_by_id(node_id)
+            .filter(|node| node.is_element_node())
+            .cloned()
+            .ok_or_else(|| anyhow!("node {node_id} is not an element"))?;
+
+        if let Some(data) = node.get_element_data_mut() {
+            data.add_attribute("style", css_text);
+        }
+        doc.update_node(node);
+
+        Ok(())
+    }
+
+    /// Returns the computed styles of the node in the render tree, after it has been brought up to date with the
+    /// changes to the document. A node that isn't rendered has no styles.
+    fn computed_style(&self, node_id: NodeId) -> Vec<StyleDeclaration> {
+        let mut tree = self.render_tree.borrow_mut();
+        tree.apply_mutations();
+
+        let Some(node) = tree.get_node_mut(node_id) else {
+            return vec![];
+        };
+
+        let mut declarations = node
+            .props_mut()
+            .iter_mut()
+            .map(|(name, property)| {
+                property.compute_value();
+
+                StyleDeclaration {
+                    property: name.to_string(),
+                    value: property.to_css(),
+                    important: false,
+                }
+            })
+            .collect::<Vec<_>>();
+        declarations.sort_by(|a, b| a.property.cmp(&b.property));
+
+        declarations
+    }
+
+    /// Returns the node of the element with the given `id` attribute
+    fn element_by_id(&self, id: &str) -> Option<NodeId> {
+        self.document.get().node_by_named_id(id).map(|node| node.id())
+    }
+
+    /// Returns the node of the root element of the document
+    fn document_element(&self) -> Option<NodeId> {
+        let doc = self.document.get();
+
+        doc.get_root()
+            .children()
+            .iter()
+            .copied()
+            .find(|id| doc.node_by_id(*id).is_some_and(|node| node.is_element_node()))
+    }
+}
+
+/// Installs `document.styleSheets`, `element.style` for the elements that `document.getElementById()` and
+/// `document.documentElement` return, and `getComputedStyle()` on the global scope of the context. Computed styles are
+/// read from the render tree of the document.
+pub fn install<RT, C, D, T>(
+    mut ctx: RT::Context,
+    document: DocumentHandle<D, C>,
+    render_tree: Rc<RefCell<T>>,
+) -> Result<()>
+where
+    RT: JSRuntime,
+    RT::Context: 'static,
+    C: CssSystem + 'static,
+    D: Document<C> + 'static,
+    T: RenderTree<C, NodeId = NodeId> + 'static,
+{
+    let cssom = Rc::new(Cssom { document, render_tree });
+
+    let native = ctx.new_global_object("__gosub_cssom")?;
+
+    let method = |name: &str, f: fn(&Cssom<C, D, T>, &[RT::Value], RT::Context) -> Result<RT::Value>| -> Result<()> {
+        let cssom = Rc::clone(&cssom);
+        let function = RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let ctx = cb.context();
+            let args = cb.args().as_vec(ctx.clone());
+
+            match f(&cssom, &args, ctx) {
+                Ok(value) => cb.ret(value),
+                Err(e) => cb.error(e),
+            }
+        })?;
+        native.set_method_variadic(name, &function)
+    };
+
+    method("parse", |_, args, ctx| {
+        declarations::<RT>(ctx, C::parse_declarations(&string_arg::<RT>(args, 0)?))
+    })?;
+
+    method("styleSheetCount", |cssom, _, ctx| {
+        RT::Value::new_number(ctx, cssom.author_sheets().len() as f64)
+    })?;
+    method("ruleCount", |cssom, args, ctx| {
+        let count = cssom.read_sheet(index_arg::<RT>(args, 0)?, |sheet| Some(sheet.rule_count()))?;
+        RT::Value::new_number(ctx, count as f64)
+    })?;
+    method("ruleText", |cssom, args, ctx| {
+        let rule = index_arg::<RT>(args, 1)?;
+        let text = cssom.read_sheet(index_arg::<RT>(args, 0)?, |sheet| sheet.rule_css_text(rule))?;
+        RT::Value::new_string(ctx, &text)
+    })?;
+    method("selectorText", |cssom, args, ctx| {
+        let rule = index_arg::<RT>(args, 1)?;
+        let text = cssom.read_sheet(index_arg::<RT>(args, 0)?, |sheet| sheet.rule_selector_text(rule))?;
+        RT::Value::new_string(ctx, &text)
+    })?;
+    method("ruleStyle", |cssom, args, ctx| {
+        let rule = index_arg::<RT>(args, 1)?;
+        let style = cssom.read_sheet(index_arg::<RT>(args, 0)?, |sheet| sheet.rule_declarations(rule))?;
+        declarations::<RT>(ctx, style)
+    })?;
+    method("setRuleStyle", |cssom, args, ctx| {
+        let (rule, css_text) = (index_arg::<RT>(args, 1)?, string_arg::<RT>(args, 2)?);
+        cssom.update_sheet(index_arg::<RT>(args, 0)?, |sheet| {
+            sheet.set_rule_declarations(rule, &css_text)
+        })?;
+        RT::Value::new_undefined(ctx)
+    })?;
+    method("insertRule", |cssom, args, ctx| {
+        let sheet = index_arg::<RT>(args, 0)?;
+        let (rule, index) = (string_arg::<RT>(args, 1)?, index_arg::<RT>(args, 2)?);
+        if index > cssom.read_sheet(sheet, |sheet| Some(sheet.rule_count()))? {
+            return Err(index_size_error(format!("can't insert a rule at index {index}")));
+        }
+        cssom.update_sheet(sheet, |sheet| sheet.insert_rule(&rule, index))?;
+        RT::Value::new_undefined(ctx)
+    })?;
+    method("deleteRule", |cssom, args, ctx| {
+        let (sheet, index) = (index_arg::<RT>(args, 0)?, index_arg::<RT>(args, 1)?);
+        if index >= cssom.read_sheet(sheet, |sheet| Some(sheet.rule_count()))? {
+            return Err(index_size_error(format!("there is no rule at index {index}")));
+        }
+        cssom.update_sheet(sheet, |sheet| sheet.delete_rule(index))?;
+        RT::Value::new_undefined(ctx)
+    })?;
+
+    method("elementById", |cssom, args, ctx| {
+        optional_node::<RT>(ctx, cssom.element_by_id(&string_arg::<RT>(args, 0)?))
+    })?;
+    method("documentElement", |cssom, _, ctx| {
+        optional_node::<RT>(ctx, cssom.document_element())
+    })?;
+
+    method("inlineStyle", |cssom, args, ctx| {
+        declarations::<RT>(ctx, cssom.inline_style(node_arg::<RT>(args, 0)?)?)
+    })?;
+    method("setInlineStyle", |cssom, args, ctx| {
+        cssom.set_inline_style(node_arg::<RT>(args, 0)?, &string_arg::<RT>(args, 1)?)?;
+        RT::Value::new_undefined(ctx)
+    })?;
+    method("computedStyle", |cssom, args, ctx| {
+        declarations::<RT>(ctx, cssom.computed_style(node_arg::<RT>(args, 0)?))
+    })?;
+
+    ctx.run(include_str!("cssom/cssom.js"))?;
+
+    Ok(())
+}
+
+/// Returns the argument at the index as a string
+fn string_arg<RT: JSRuntime>(args: &[RT::Value], index: usize) -> Result<String> {
+    match args.get(index) {
+        Some(value) => value.as_string(),
+        None => Err(anyhow!("missing argument {index}")),
+    }
+}
+
+/// Returns the argument at the index as an index into a list. Negative, fractional and too large numbers (or NaN) are
+/// no valid index.
+fn index_arg<RT: JSRuntime>(args: &[RT::Value], index: usize) -> Result<usize> {
+    let Some(value) = args.get(index) else {
+        return Err(anyhow!("missing argument {index}"));
+    };
+
+    let number = value.as_number()?;
+    if !(0.0..=u32::MAX as f64).contains(&number) || number.fract() != 0.0 {
+        return Err(index_size_error(format!("{number} is not a valid index")));
+    }
+
+    Ok(number as usize)
+}
+
+/// Returns an error that the javascript side turns into an `IndexSizeError` DOMException
+fn index_size_error(message: String) -> anyhow::Error {
+    anyhow!("IndexSizeError: {message}")
+}
+
+/// Returns the argument at the index as the id of a node
+fn node_arg<RT: JSRuntime>(args: &[RT::Value], index: usize) -> Result<NodeId> {
+    index_arg::<RT>(args, index).map(NodeId::from)
+}
+
+/// Returns the id of the node, or null when there is none
+fn optional_node<RT: JSRuntime>(ctx: RT::Context, node_id: Option<NodeId>) -> Result<RT::Value> {
+    match node_id {
+        Some(node_id) => RT::Value::new_number(ctx, usize::from(node_id) as f64),
+        None => RT::Value::new_null(ctx),
+    }
+}
+
+/// Converts declarations to a flat list of property, value and priority triples, which is what the javascript side
+/// expects
+fn declarations<RT: JSRuntime>(ctx: RT::Context, declarations: Vec<StyleDeclaration>) -> Result<RT::Value> {
+    let list = declarations
+        .into_iter()
+        .flat_map(|declaration| {
+            let priority = if declaration.important { "important" } else { "" };
+            [declaration.property, declaration.value, priority.to_string()]
+        })
+        .collect::<Vec<_>>();
+
+    list.as_slice().to_js_value(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_css3::system::Css3System;
+    use gosub_html5::document::builder::DocumentBuilderImpl;
+    use gosub_html5::document::document_impl::DocumentImpl;
+    use gosub_html5::parser::Html5Parser;
+    use gosub_shared::byte_stream::{ByteStream, Encoding};
+    use gosub_shared::traits::document::DocumentBuilder;
+    use gosub_taffy::TaffyLayouter;
+    use gosub_v8::{V8Context, V8Engine};
+
+    type Doc = DocumentImpl<Css3System>;
+    type Tree = gosub_rendering::render_tree::RenderTree<TaffyLayouter, Doc, Css3System>;
+
+    #[test]
+    fn cssom_in_javascript() {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(
+            r#"<html><head><style>p { color: red; } .big { font-size: 20px; }</style></head>
+            <body><p id="text" style="margin-top: 4px">hello</p></body></html>"#,
+            Some(Encoding::UTF8),
+        );
+        stream.close();
+
+        let handle = DocumentBuilderImpl::new_document(None);
+        let _ = Html5Parser::<Doc, Css3System>::parse_document(&mut stream, handle.clone(), None);
+        let tree = Rc::new(RefCell::new(Tree::from_document(handle.clone())));
+
+        let mut runtime = V8Engine::new();
+        let mut ctx: V8Context = runtime.new_context().unwrap();
+        install::<V8Engine, _, _, _>(ctx.clone(), handle.clone(), tree).unwrap();
+
+        let result = ctx
+            .run(
+                r##"
+                var sheet = document.styleSheets[0];
+                var p = document.getElementById("text");
+                var before = [document.styleSheets.length, sheet.cssRules.length, sheet.cssRules[1].cssText,
+                    getComputedStyle(p).color, getComputedStyle(p).marginTop];
+
+                sheet.insertRule("#text { color: blue }", 2);
+                sheet.cssRules[0].style.backgroundColor = "yellow";
+                var inserted = [sheet.cssRules.length, sheet.cssRules[2].selectorText, sheet.cssRules[0].cssText,
+                    getComputedStyle(p).color, getComputedStyle(p).backgroundColor];
+
+                p.style.color = "green";
+                p.style.setProperty("margin-top", "not a length");
+                var inline = [p.style.cssText, getComputedStyle(p).color];
+
+                p.style.removeProperty("color");
+                sheet.deleteRule(2);
+                [before.join(), inserted.join(), inline.join(), getComputedStyle(p).color].join(" | ");
+                "##,
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.as_string().unwrap(),
+            "1,2,.big { font-size: 20px; },red,4px \
+             | 3,#text,p { color: red; background-color: yellow; },blue,yellow \
+             | margin-top: 4px; color: green;,green \
+             | red"
+        );
+
+        let style = handle
+            .get()
+            .node_by_named_id("text")
+            .unwrap()
+            .get_element_data()
+            .unwrap()
+            .attribute("style")
+            .cloned();
+        assert_eq!(style.as_deref(), Some("margin-top: 4px;"));
+    }
+
+    #[test]
+    fn invalid_rule_indices() {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(
+            r#"<html><head><style>p { color: red; }</style></head><body></body></html>"#,
+            Some(Encoding::UTF8),
+        );
+        stream.close();
+
+        let mut handle = DocumentBuilderImpl::new_document(None);
+        let _ = Html5Parser::<Doc, Css3System>::parse_document(&mut stream, handle.clone(), None);
+        let tree = Rc::new(RefCell::new(Tree::from_document(handle.clone())));
+
+        let mut runtime = V8Engine::new();
+        let mut ctx: V8Context = runtime.new_context().unwrap();
+        install::<V8Engine, _, _, _>(ctx.clone(), handle.clone(), tree).unwrap();
+        handle.get_mut().take_mutations();
+
+        let result = ctx
+            .run(
+                r##"
+                function attempt(f) {
+                    try {
+                        f();
+                        return "ok";
+                    } catch (e) {
+                        return e.message.startsWith("IndexSizeError") ? "IndexSizeError" : e.name;
+                    }
+                }
+
+                var sheet = document.styleSheets[0];
+                [
+                    attempt(() => sheet.insertRule("div { color: blue }", 2)),
+                    attempt(() => sheet.deleteRule(1)),
+                    attempt(() => __gosub_cssom.insertRule(0, "div { color: blue }", -1)),
+                    attempt(() => __gosub_cssom.insertRule(0, "div { color: blue }", NaN)),
+                    attempt(() => __gosub_cssom.insertRule(0, "div { color: blue }", 2)),
+                    attempt(() => __gosub_cssom.deleteRule(0, 0.5)),
+                    attempt(() => __gosub_cssom.deleteRule(0, 1)),
+                    attempt(() => sheet.insertRule("not a rule", 0)),
+                    sheet.cssRules.length,
+                ].join();
+                "##,
+            )
+            .unwrap();
+
+        assert_eq!(
+            result.as_string().unwrap(),
+            "IndexSizeError,IndexSizeError,IndexSizeError,IndexSizeError,IndexSizeError,IndexSizeError,\
+             IndexSizeError,SyntaxError,1"
+        );
+        assert!(handle.get_mut().take_mutations().is_empty());
+    }
+}
@@ -0,0 +1,423 @@
+// CSS object model (https://drafts.csswg.org/cssom/) on top of the native __gosub_cssom object. Declaration blocks
+// have no state of their own: they read the declarations of their owner (a rule, a style attribute or a computed
+// style) on every access and write all of them back on every change.
+(function (native) {
+    "use strict";
+
+    function domError(name, message) {
+        const error = new Error(message);
+        error.name = name;
+        return error;
+    }
+
+    // The native side passes declarations as a flat list of property, value and priority triples
+    function declarations(list) {
+        const result = [];
+        for (let i = 0; i + 2 < list.length; i += 3) {
+            result.push({ property: list[i], value: list[i + 1], priority: list[i + 2] });
+        }
+        return result;
+    }
+
+    function serialize(list) {
+        return list
+            .map((decl) => `${decl.property}: ${decl.value}${decl.priority ? " !" + decl.priority : ""};`)
+            .join(" ");
+    }
+
+    // Property names are case-insensitive, except for custom properties
+    function normalize(property) {
+        property = String(property);
+        return property.startsWith("--") ? property : property.toLowerCase();
+    }
+
+    // Maps attribute names like `backgroundColor` and `cssFloat` to the name of the property
+    function propertyName(prop) {
+        if (prop === "cssFloat") {
+            return "float";
+        }
+        return prop.replace(/[A-Z]/g, (c) => "-" + c.toLowerCase());
+    }
+
+    // Owner of every declaration block: { read(), write(text), parentRule }, write is null when it is read-only
+    const owners = new WeakMap();
+
+    function read(style) {
+        return declarations(owners.get(style).read());
+    }
+
+    function write(style, list) {
+        const owner = owners.get(style);
+        if (!owner.write) {
+            throw domError("NoModificationAllowedError", "The declarations of a computed style can't be modified");
+        }
+        owner.write(serialize(list));
+    }
+
+    class CSSStyleDeclaration {
+        get cssText() {
+            return serialize(read(this));
+        }
+
+        set cssText(text) {
+            write(this, declarations(native.parse(String(text))));
+        }
+
+        get length() {
+            return read(this).length;
+        }
+
+        get parentRule() {
+            return owners.get(this).parentRule;
+        }
+
+        item(index) {
+            const decl = read(this)[index >>> 0];
+            return decl ? decl.property : "";
+        }
+
+        getPropertyValue(property) {
+            property = normalize(property);
+            const decl = read(this).find((decl) => decl.property === property);
+            return decl ? decl.value : "";
+        }
+
+        getPropertyPriority(property) {
+            property = normalize(property);
+            const decl = read(this).find((decl) => decl.property === property);
+            return decl ? decl.priority : "";
+        }
+
+        setProperty(property, value, priority = "") {
+            property = normalize(property);
+            value = value === null ? "" : String(value);
+            priority = String(priority).toLowerCase();
+
+            if (value === "") {
+                this.removeProperty(property);
+                return;
+            }
+            if (priority !== "" && priority !== "important") {
+                return;
+            }
+
+            // Invalid values are ignored
+            const parsed = declarations(native.parse(`${property}: ${value}${priority ? " !important" : ""}`));
+            if (parsed.length !== 1 || parsed[0].property !== property) {
+                return;
+            }
+
+            const list = read(this);
+            const index = list.findIndex((decl) => decl.property === property);
+            if (index === -1) {
+                list.push(parsed[0]);
+            } else {
+                list[index] = parsed[0];
+            }
+            write(this, list);
+        }
+
+        removeProperty(property) {
+            property = normalize(property);
+            const list = read(this);
+            const decl = list.find((decl) => decl.property === property);
+            if (!decl) {
+                return "";
+            }
+
+            write(this, list.filter((other) => other !== decl));
+            return decl.value;
+        }
+    }
+
+    // Properties that are not defined by CSSStyleDeclaration are CSS properties (`style.color`)
+    function isProperty(target, prop) {
+        return typeof prop === "string" && !(prop in target) && /^(-|[a-z])[a-zA-Z-]*$/.test(prop);
+    }
+
+    const styleHandler = {
+        get(target, prop, receiver) {
+            if (isProperty(target, prop)) {
+                return target.getPropertyValue(propertyName(prop));
+            }
+            return Reflect.get(target, prop, receiver);
+        },
+
+        set(target, prop, value) {
+            if (isProperty(target, prop)) {
+                target.setProperty(propertyName(prop), value);
+                return true;
+            }
+            return Reflect.set(target, prop, value);
+        },
+    };
+
+    function styleDeclaration(owner) {
+        const target = new CSSStyleDeclaration();
+        const proxy = new Proxy(target, styleHandler);
+        owners.set(target, owner);
+        owners.set(proxy, owner);
+        return proxy;
+    }
+
+    // Lists with indexed properties (`sheet.cssRules[0]`)
+    const listHandler = {
+        get(target, prop, receiver) {
+            if (typeof prop === "string" && /^\d+$/.test(prop)) {
+                const item = target.item(Number(prop));
+                return item === null ? undefined : item;
+            }
+            return Reflect.get(target, prop, receiver);
+        },
+    };
+
+    function list(target) {
+        return new Proxy(target, listHandler);
+    }
+
+    // Index of every stylesheet and its rule objects, which are kept in the order of the rules of the stylesheet
+    const sheets = new WeakMap();
+
+    function rulesOf(sheet) {
+        const state = sheets.get(sheet);
+        const count = native.ruleCount(state.index);
+
+        // The rules can also be changed outside of the CSSOM, like by the parser
+        state.rules.length = Math.min(state.rules.length, count);
+        while (state.rules.length < count) {
+            state.rules.push(new CSSStyleRule(sheet));
+        }
+        return state.rules;
+    }
+
+    const ruleSheets = new WeakMap();
+    const ruleStyles = new WeakMap();
+
+    // Returns the index of the stylesheet and of the rule in it
+    function position(rule) {
+        const sheet = ruleSheets.get(rule);
+        const index = rulesOf(sheet).indexOf(rule);
+        if (index === -1) {
+            throw domError("InvalidStateError", "The rule has been removed from its stylesheet");
+        }
+        return [sheets.get(sheet).index, index];
+    }
+
+    class CSSRule {
+        get parentStyleSheet() {
+            return ruleSheets.get(this);
+        }
+
+        get parentRule() {
+            return null;
+        }
+    }
+    CSSRule.STYLE_RULE = 1;
+
+    class CSSStyleRule extends CSSRule {
+        constructor(sheet) {
+            super();
+            ruleSheets.set(this, sheet);
+        }
+
+        get type() {
+            return CSSRule.STYLE_RULE;
+        }
+
+        get cssText() {
+            return native.ruleText(...position(this));
+        }
+
+        get selectorText() {
+            return native.selectorText(...position(this));
+        }
+
+        get style() {
+            if (!ruleStyles.has(this)) {
+                ruleStyles.set(
+                    this,
+                    styleDeclaration({
+                        read: () => native.ruleStyle(...position(this)),
+                        write: (text) => native.setRuleStyle(...position(this), text),
+                        parentRule: this,
+                    }),
+                );
+            }
+            return ruleStyles.get(this);
+        }
+    }
+
+    class CSSRuleList {
+        get length() {
+            return rulesOf(ruleLists.get(this)).length;
+        }
+
+        item(index) {
+            const rule = rulesOf(ruleLists.get(this))[index >>> 0];
+            return rule === undefined ? null : rule;
+        }
+
+        *[Symbol.iterator]() {
+            yield* rulesOf(ruleLists.get(this));
+        }
+    }
+
+    const ruleLists = new WeakMap();
+
+    class CSSStyleSheet {
+        get type() {
+            return "text/css";
+        }
+
+        get ownerRule() {
+            return null;
+        }
+
+        get cssRules() {
+            const state = sheets.get(this);
+            if (!state.cssRules) {
+                const target = new CSSRuleList();
+                state.cssRules = list(target);
+                ruleLists.set(target, this);
+                ruleLists.set(state.cssRules, this);
+            }
+            return state.cssRules;
+        }
+
+        insertRule(rule, index = 0) {
+            const rules = rulesOf(this);
+            index = index >>> 0;
+            if (index > rules.length) {
+                throw domError("IndexSizeError", `Can't insert a rule at index ${index}`);
+            }
+
+            try {
+                native.insertRule(sheets.get(this).index, String(rule), index);
+            } catch (e) {
+                if (String(e.message).startsWith("IndexSizeError")) {
+                    throw domError("IndexSizeError", `Can't insert a rule at index ${index}`);
+                }
+                throw domError("SyntaxError", `Failed to parse the rule: ${e.message}`);
+            }
+            rules.splice(index, 0, new CSSStyleRule(this));
+            return index;
+        }
+
+        deleteRule(index) {
+            const rules = rulesOf(this);
+            index = index >>> 0;
+            if (index >= rules.length) {
+                throw domError("IndexSizeError", `There is no rule at index ${index}`);
+            }
+
+            native.deleteRule(sheets.get(this).index, index);
+            rules.splice(index, 1);
+        }
+    }
+
+    // Stylesheet objects by their index in the author stylesheets of the document
+    const styleSheetObjects = [];
+
+    function styleSheet(index) {
+        if (!styleSheetObjects[index]) {
+            const sheet = new CSSStyleSheet();
+            sheets.set(sheet, { index, rules: [], cssRules: null });
+            styleSheetObjects[index] = sheet;
+        }
+        return styleSheetObjects[index];
+    }
+
+    class StyleSheetList {
+        get length() {
+            return native.styleSheetCount();
+        }
+
+        item(index) {
+            index = index >>> 0;
+            return index < this.length ? styleSheet(index) : null;
+        }
+
+        *[Symbol.iterator]() {
+            for (let i = 0; i < this.length; i++) {
+                yield styleSheet(i);
+            }
+        }
+    }
+
+    const styleSheets = list(new StyleSheetList());
+
+    // Elements by node id, so the same node is always the same object
+    const nodeIds = new WeakMap();
+    const elements = new Map();
+    const inlineStyles = new WeakMap();
+
+    class Element {
+        get style() {
+            if (!inlineStyles.has(this)) {
+                const id = nodeIds.get(this);
+                inlineStyles.set(
+                    this,
+                    styleDeclaration({
+                        read: () => native.inlineStyle(id),
+                        write: (text) => native.setInlineStyle(id, text),
+                        parentRule: null,
+                    }),
+                );
+            }
+            return inlineStyles.get(this);
+        }
+    }
+
+    function element(id) {
+        if (id === null) {
+            return null;
+        }
+        if (!elements.has(id)) {
+            const element = new Element();
+            nodeIds.set(element, id);
+            elements.set(id, element);
+        }
+        return elements.get(id);
+    }
+
+    function getComputedStyle(element) {
+        if (!nodeIds.has(element)) {
+            throw new TypeError("getComputedStyle() expects an element");
+        }
+
+        const id = nodeIds.get(element);
+        return styleDeclaration({
+            read: () => native.computedStyle(id),
+            write: null,
+            parentRule: null,
+        });
+    }
+
+    if (typeof globalThis.document !== "object" || globalThis.document === null) {
+        globalThis.document = {};
+    }
+
+    Object.defineProperty(globalThis.document, "styleSheets", {
+        get: () => styleSheets,
+        configurable: true,
+    });
+    if (!("documentElement" in globalThis.document)) {
+        Object.defineProperty(globalThis.document, "documentElement", {
+            get: () => element(native.documentElement()),
+            configurable: true,
+        });
+    }
+    if (typeof globalThis.document.getElementById !== "function") {
+        globalThis.document.getElementById = (id) => element(native.elementById(String(id)));
+    }
+
+    Object.assign(globalThis, {
+        CSSRule,
+        CSSRuleList,
+        CSSStyleDeclaration,
+        CSSStyleRule,
+        CSSStyleSheet,
+        StyleSheetList,
+        getComputedStyle,
+    });
+})(__gosub_cssom);
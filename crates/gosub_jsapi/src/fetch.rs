@@ -0,0 +1,361 @@
+//! Fetch api (<https://fetch.spec.whatwg.org/>) and `XMLHttpRequest` (<https://xhr.spec.whatwg.org/>)
+//!
+//! `Headers`, `Request`, `Response`, `fetch()` and `XMLHttpRequest` are defined in javascript (`fetch/fetch.js`) on
+//! top of a native `__gosub_net` object. Asynchronous requests are sent from a task on the networking task source
+//! of the event loop, which settles the promise (or fires the events) of the request once it has completed.
+use gosub_net::http::cors::{self, CredentialsMode, FilteredResponse, RequestMode};
+use gosub_net::http::fetcher::Fetcher;
+use gosub_net::http::request::Request;
+use gosub_shared::types::Result;
+use gosub_webexecutor::event_loop::{EventLoop, TaskSource};
+use gosub_webexecutor::js::{
+    IntoJSValue, JSArray, JSContext, JSFunctionCallBackVariadic, JSFunctionVariadic, JSObject, JSRuntime, JSValue,
+    VariadicArgsInternal,
+};
+use log::warn;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use url::Url;
+
+/// A request as it is sent from javascript
+struct PendingRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    mode: RequestMode,
+    credentials: CredentialsMode,
+}
+
+/// Sends the requests of the scripts of a document
+pub struct Fetch {
+    /// Url of the document, which resolves relative urls and decides the origin of the requests
+    document_url: Url,
+    fetcher: Fetcher,
+    event_loop: EventLoop,
+    /// Requests that have completed but have not been taken by javascript yet
+    completed: HashMap<u32, std::result::Result<FilteredResponse, String>>,
+    next_id: u32,
+}
+
+impl Fetch {
+    pub fn new(document_url: Url, event_loop: EventLoop) -> Self {
+        Self {
+            fetcher: Fetcher::new(document_url.clone()),
+            document_url,
+            event_loop,
+            completed: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Returns the id of a new request
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Sends the request and keeps its result until it is taken
+    fn perform(&mut self, id: u32, pending: PendingRequest) {
+        let mut req = Request::new(&pending.method, &pending.url, "HTTP/1.1");
+        for (name, value) in pending.headers {
+            req.headers.set(name, value);
+        }
+        req.body = pending.body.into_bytes();
+
+        let result = cors::fetch(
+            &self.fetcher,
+            req,
+            &self.document_url,
+            pending.mode,
+            pending.credentials,
+        )
+        .map_err(|e| e.to_string());
+        if let Err(e) = &result {
+            warn!("fetch of {} failed: {e}", pending.url);
+        }
+
+        self.completed.insert(id, result);
+    }
+
+    /// Installs the fetch api on the global scope of the context. Asynchronous requests are completed on the
+    /// event loop of `fetch`, which is why the context has to outlive the calls that are made from it.
+    pub fn install<RT: JSRuntime>(fetch: Rc<RefCell<Fetch>>, mut ctx: RT::Context) -> Result<()>
+    where
+        RT::Context: 'static,
+    {
+        let net = ctx.new_global_object("__gosub_net")?;
+
+        let send = {
+            let fetch = Rc::clone(&fetch);
+            let ctx = ctx.clone();
+            RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+                let cb_ctx = cb.context();
+                let args = cb.args().as_vec(cb_ctx.clone());
+
+                let pending = match pending_request::<RT>(&args) {
+                    Ok(pending) => pending,
+                    Err(e) => {
+                        cb.error(e);
+                        return;
+                    }
+                };
+                let sync = args.get(7).is_some_and(|sync| sync.as_bool().unwrap_or_default());
+
+                let id = fetch.borrow_mut().next_id();
+                if sync {
+                    fetch.borrow_mut().perform(id, pending);
+                } else {
+                    let task_fetch = Rc::clone(&fetch);
+                    let mut task_ctx = ctx.clone();
+                    fetch.borrow().event_loop.queue_task(TaskSource::Networking, move || {
+                        task_fetch.borrow_mut().perform(id, pending);
+                        task_ctx.run(&format!("__gosub_net.complete({id})"))?;
+                        Ok(())
+                    });
+                }
+
+                match RT::Value::new_number(cb_ctx, id) {
+                    Ok(value) => cb.ret(value),
+                    Err(e) => cb.error(e),
+                }
+            })?
+        };
+        net.set_method_variadic("send", &send)?;
+
+        let take = {
+            let fetch = Rc::clone(&fetch);
+            RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+                let cb_ctx = cb.context();
+                let id = cb
+                    .args()
+                    .get(0, cb_ctx.clone())
+                    .and_then(|id| id.as_number().ok())
+                    .unwrap_or_default() as u32;
+
+                let Some(result) = fetch.borrow_mut().completed.remove(&id) else {
+                    cb.error(format!("no completed request with id {id}"));
+                    return;
+                };
+
+                match result_object::<RT>(result, cb_ctx) {
+                    Ok(value) => cb.ret(value),
+                    Err(e) => cb.error(e),
+                }
+            })?
+        };
+        net.set_method_variadic("take", &take)?;
+
+        ctx.run(include_str!("fetch/fetch.js"))?;
+
+        Ok(())
+    }
+}
+
+/// Reads the arguments of `__gosub_net.send(method, url, headerNames, headerValues, body, mode, credentials, sync)`
+fn pending_request<RT: JSRuntime>(args: &[RT::Value]) -> Result<PendingRequest> {
+    let string = |index: usize| -> Result<String> {
+        match args.get(index) {
+            Some(value) => value.as_string(),
+            None => Ok(String::new()),
+        }
+    };
+    let strings = |index: usize| -> Result<Vec<String>> {
+        match args.get(index) {
+            Some(value) => value.as_array()?.as_vec().iter().map(|v| v.as_string()).collect(),
+            None => Ok(vec![]),
+        }
+    };
+
+    let mode = string(5)?;
+    let credentials = string(6)?;
+
+    Ok(PendingRequest {
+        method: string(0)?,
+        url: string(1)?,
+        headers: strings(2)?.into_iter().zip(strings(3)?).collect(),
+        body: string(4)?,
+        mode: RequestMode::from_name(&mode).unwrap_or(RequestMode::Cors),
+        credentials: CredentialsMode::from_name(&credentials).unwrap_or(CredentialsMode::SameOrigin),
+    })
+}
+
+/// Converts the result of a request to the object that the javascript side expects: either `{ error }`, or
+/// `{ type, status, statusText, url, headerNames, headerValues, body }`
+fn result_object<RT: JSRuntime>(
+    result: std::result::Result<FilteredResponse, String>,
+    ctx: RT::Context,
+) -> Result<RT::Value> {
+    let obj = RT::Value::new_object(ctx.clone())?;
+
+    let filtered = match result {
+        Ok(filtered) => filtered,
+        Err(e) => {
+            obj.set_property("error", &RT::Value::new_string(ctx, &e)?)?;
+            return Ok(obj.into());
+        }
+    };
+
+    let response = filtered.response;
+    let (names, values): (Vec<String>, Vec<String>) = response
+        .headers
+        .sorted()
+        .into_iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.clone()))
+        .unzip();
+
+    obj.set_property(
+        "type",
+        &RT::Value::new_string(ctx.clone(), filtered.response_type.name())?,
+    )?;
+    obj.set_property("status", &RT::Value::new_number(ctx.clone(), response.status)?)?;
+    obj.set_property(
+        "statusText",
+        &RT::Value::new_string(ctx.clone(), &response.status_text)?,
+    )?;
+    obj.set_property("url", &RT::Value::new_string(ctx.clone(), &response.url)?)?;
+    obj.set_property("headerNames", &names.as_slice().to_js_value(ctx.clone())?)?;
+    obj.set_property("headerValues", &values.as_slice().to_js_value(ctx.clone())?)?;
+    obj.set_property(
+        "body",
+        &RT::Value::new_string(ctx, &String::from_utf8_lossy(&response.body))?,
+    )?;
+
+    Ok(obj.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_v8::{V8Context, V8Engine};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    /// Starts a local http server that answers every connection with the next of the given responses. Returns the
+    /// url of the server and a receiver of the request heads it got
+    fn stub_server(responses: Vec<&'static str>) -> (Url, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let (tx, rx) = channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap_or(0);
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+
+                let _ = tx.send(head);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, rx)
+    }
+
+    fn context(document_url: Url, event_loop: EventLoop) -> V8Context<'static> {
+        let mut runtime = V8Engine::new();
+        let ctx = runtime.new_context().unwrap();
+        let fetch = Rc::new(RefCell::new(Fetch::new(document_url, event_loop)));
+        Fetch::install::<V8Engine>(fetch, ctx.clone()).unwrap();
+        ctx
+    }
+
+    #[test]
+    fn same_origin_fetch() {
+        let (url, requests) = stub_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nX-Custom: yes\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+        ]);
+        let event_loop = EventLoop::new();
+        let mut ctx = context(url, event_loop.clone());
+
+        ctx.run(
+            r#"
+            var result = "pending";
+            fetch("/data", { headers: { "X-Test": "1" } })
+                .then((r) => r.text().then((t) => { result = [r.status, r.type, r.headers.get("x-custom"), t].join(" "); }));
+            "#,
+        )
+        .unwrap();
+        assert_eq!(ctx.run("result").unwrap().as_string().unwrap(), "pending");
+
+        event_loop.tick(0.0);
+
+        assert_eq!(ctx.run("result").unwrap().as_string().unwrap(), "200 basic yes hello");
+        let head = requests.recv().unwrap().to_ascii_lowercase();
+        assert!(head.starts_with("get /data http/1.1"));
+        assert!(head.contains("x-test: 1"));
+    }
+
+    #[test]
+    fn cross_origin_fetch() {
+        let (url, requests) = stub_server(vec![
+            "HTTP/1.1 200 OK\r\nAccess-Control-Allow-Origin: http://example.com\r\nX-Custom: yes\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+        ]);
+        let event_loop = EventLoop::new();
+        let mut ctx = context(Url::parse("http://example.com/").unwrap(), event_loop.clone());
+
+        ctx.run(&format!(
+            r#"
+            var allowed = "pending";
+            var denied = "pending";
+            fetch("{url}allowed").then((r) => {{ allowed = r.type + " " + r.headers.get("x-custom"); }});
+            fetch("{url}denied").then(() => {{ denied = "resolved"; }}, (e) => {{ denied = e.name; }});
+            "#
+        ))
+        .unwrap();
+        event_loop.tick(0.0);
+        event_loop.tick(0.0);
+
+        assert_eq!(ctx.run("allowed").unwrap().as_string().unwrap(), "cors null");
+        assert_eq!(ctx.run("denied").unwrap().as_string().unwrap(), "TypeError");
+        assert!(requests
+            .recv()
+            .unwrap()
+            .to_ascii_lowercase()
+            .contains("origin: http://example.com"));
+    }
+
+    #[test]
+    fn sync_xhr() {
+        let (url, _requests) = stub_server(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 7\r\nConnection: close\r\n\r\nmissing",
+        ]);
+        let mut ctx = context(url, EventLoop::new());
+
+        let result = ctx
+            .run(
+                r#"
+                var states = [];
+                var xhr = new XMLHttpRequest();
+                xhr.onreadystatechange = () => states.push(xhr.readyState);
+                xhr.open("GET", "/missing", false);
+                xhr.send();
+                [xhr.status, xhr.statusText, xhr.responseText, states.join(",")].join(" ");
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(result.as_string().unwrap(), "404 Not Found missing 1,2,3,4");
+    }
+}
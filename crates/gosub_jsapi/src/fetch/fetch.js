@@ -0,0 +1,380 @@
+// Fetch api (https://fetch.spec.whatwg.org/) and XMLHttpRequest (https://xhr.spec.whatwg.org/) on top of the
+// native __gosub_net object, which sends the requests and calls `complete` when a request has finished.
+(function (net) {
+    "use strict";
+
+    // Callbacks of the requests that are in flight, by their id
+    const pending = new Map();
+
+    net.complete = function (id) {
+        const callback = pending.get(id);
+        pending.delete(id);
+        if (callback) {
+            callback(net.take(id));
+        }
+    };
+
+    function normalizeName(name) {
+        name = String(name).toLowerCase();
+        if (!/^[!#$%&'*+\-.^_`|~0-9a-z]+$/.test(name)) {
+            throw new TypeError("Invalid header name: " + name);
+        }
+        return name;
+    }
+
+    class Headers {
+        constructor(init) {
+            this._map = new Map();
+
+            if (init instanceof Headers) {
+                init.forEach((value, name) => this.append(name, value));
+            } else if (Array.isArray(init)) {
+                init.forEach(([name, value]) => this.append(name, value));
+            } else if (init) {
+                Object.keys(init).forEach((name) => this.append(name, init[name]));
+            }
+        }
+
+        append(name, value) {
+            name = normalizeName(name);
+            value = String(value).trim();
+            const current = this._map.get(name);
+            this._map.set(name, current === undefined ? value : current + ", " + value);
+        }
+
+        set(name, value) {
+            this._map.set(normalizeName(name), String(value).trim());
+        }
+
+        get(name) {
+            const value = this._map.get(normalizeName(name));
+            return value === undefined ? null : value;
+        }
+
+        has(name) {
+            return this._map.has(normalizeName(name));
+        }
+
+        delete(name) {
+            this._map.delete(normalizeName(name));
+        }
+
+        forEach(callback, thisArg) {
+            for (const [name, value] of this.entries()) {
+                callback.call(thisArg, value, name, this);
+            }
+        }
+
+        entries() {
+            const entries = Array.from(this._map.entries());
+            entries.sort((a, b) => (a[0] < b[0] ? -1 : a[0] > b[0] ? 1 : 0));
+            return entries[Symbol.iterator]();
+        }
+
+        keys() {
+            return Array.from(this.entries(), (entry) => entry[0])[Symbol.iterator]();
+        }
+
+        values() {
+            return Array.from(this.entries(), (entry) => entry[1])[Symbol.iterator]();
+        }
+
+        [Symbol.iterator]() {
+            return this.entries();
+        }
+    }
+
+    class Body {
+        _initBody(body) {
+            this._body = body === null || body === undefined ? null : String(body);
+            this.bodyUsed = false;
+        }
+
+        _consume() {
+            if (this.bodyUsed) {
+                return Promise.reject(new TypeError("Body has already been consumed"));
+            }
+            this.bodyUsed = true;
+            return Promise.resolve(this._body === null ? "" : this._body);
+        }
+
+        text() {
+            return this._consume();
+        }
+
+        json() {
+            return this._consume().then(JSON.parse);
+        }
+    }
+
+    class Request extends Body {
+        constructor(input, init = {}) {
+            super();
+
+            const base = input instanceof Request ? input : {};
+            this.url = input instanceof Request ? input.url : String(input);
+            this.method = String(init.method || base.method || "GET").toUpperCase();
+            this.headers = new Headers(init.headers || base.headers);
+            this.mode = init.mode || base.mode || "cors";
+            this.credentials = init.credentials || base.credentials || "same-origin";
+
+            const body = "body" in init ? init.body : base._body;
+            if (body !== null && body !== undefined && (this.method === "GET" || this.method === "HEAD")) {
+                throw new TypeError("Request with GET/HEAD method cannot have body");
+            }
+            this._initBody(body);
+        }
+
+        clone() {
+            if (this.bodyUsed) {
+                throw new TypeError("Body has already been consumed");
+            }
+            return new Request(this);
+        }
+    }
+
+    class Response extends Body {
+        constructor(body = null, init = {}) {
+            super();
+
+            this.status = init.status === undefined ? 200 : init.status;
+            this.statusText = init.statusText === undefined ? "" : String(init.statusText);
+            this.headers = new Headers(init.headers);
+            this.type = "default";
+            this.url = "";
+            this._initBody(body);
+        }
+
+        get ok() {
+            return this.status >= 200 && this.status < 300;
+        }
+
+        clone() {
+            if (this.bodyUsed) {
+                throw new TypeError("Body has already been consumed");
+            }
+            const response = new Response(this._body, this);
+            response.type = this.type;
+            response.url = this.url;
+            return response;
+        }
+
+        static error() {
+            const response = new Response(null, { status: 0 });
+            response.type = "error";
+            return response;
+        }
+
+        static json(data, init = {}) {
+            const headers = new Headers(init.headers);
+            if (!headers.has("content-type")) {
+                headers.set("content-type", "application/json");
+            }
+            return new Response(JSON.stringify(data), Object.assign({}, init, { headers }));
+        }
+    }
+
+    function send(request, sync) {
+        const names = [];
+        const values = [];
+        request.headers.forEach((value, name) => {
+            names.push(name);
+            values.push(value);
+        });
+
+        const body = request._body === null ? "" : request._body;
+        return net.send(request.method, request.url, names, values, body, request.mode, request.credentials, sync);
+    }
+
+    function headersOf(result) {
+        const headers = new Headers();
+        result.headerNames.forEach((name, i) => headers.append(name, result.headerValues[i]));
+        return headers;
+    }
+
+    function fetch(input, init) {
+        return new Promise((resolve, reject) => {
+            const request = new Request(input, init);
+            const id = send(request, false);
+
+            pending.set(id, (result) => {
+                if (result.error !== undefined) {
+                    reject(new TypeError("Failed to fetch: " + result.error));
+                    return;
+                }
+
+                const response = new Response(result.body, {
+                    status: result.status,
+                    statusText: result.statusText,
+                    headers: headersOf(result),
+                });
+                response.type = result.type;
+                response.url = result.url;
+                resolve(response);
+            });
+        });
+    }
+
+    class XMLHttpRequest {
+        constructor() {
+            this.readyState = XMLHttpRequest.UNSENT;
+            this.responseType = "";
+            this.withCredentials = false;
+            this.onreadystatechange = null;
+            this.onload = null;
+            this.onerror = null;
+            this.onabort = null;
+            this.onloadend = null;
+            this._listeners = new Map();
+            this._reset();
+        }
+
+        _reset() {
+            this.status = 0;
+            this.statusText = "";
+            this.responseURL = "";
+            this.responseText = "";
+            this.response = "";
+            this._responseHeaders = new Headers();
+            this._id = undefined;
+        }
+
+        open(method, url, async = true) {
+            if (this._id !== undefined) {
+                pending.delete(this._id);
+            }
+
+            this._method = String(method).toUpperCase();
+            this._url = String(url);
+            this._async = async !== false;
+            this._headers = new Headers();
+            this._sent = false;
+            this._reset();
+            this._setState(XMLHttpRequest.OPENED);
+        }
+
+        setRequestHeader(name, value) {
+            if (this.readyState !== XMLHttpRequest.OPENED || this._sent) {
+                throw new Error("InvalidStateError: the request is not opened");
+            }
+            this._headers.append(name, value);
+        }
+
+        send(body = null) {
+            if (this.readyState !== XMLHttpRequest.OPENED || this._sent) {
+                throw new Error("InvalidStateError: the request is not opened");
+            }
+            if (this._method === "GET" || this._method === "HEAD") {
+                body = null;
+            }
+
+            this._sent = true;
+            const request = new Request(this._url, {
+                method: this._method,
+                headers: this._headers,
+                body,
+                mode: "cors",
+                credentials: this.withCredentials ? "include" : "same-origin",
+            });
+
+            const id = send(request, !this._async);
+            if (!this._async) {
+                const result = net.take(id);
+                this._complete(result);
+                if (result.error !== undefined) {
+                    throw new Error("NetworkError: " + result.error);
+                }
+                return;
+            }
+
+            this._id = id;
+            pending.set(id, (result) => {
+                this._id = undefined;
+                this._complete(result);
+            });
+        }
+
+        abort() {
+            if (this._id === undefined) {
+                return;
+            }
+
+            pending.delete(this._id);
+            this._reset();
+            this._sent = false;
+            this._setState(XMLHttpRequest.DONE);
+            this._fire("abort");
+            this._fire("loadend");
+            this.readyState = XMLHttpRequest.UNSENT;
+        }
+
+        getResponseHeader(name) {
+            return this.readyState >= XMLHttpRequest.HEADERS_RECEIVED ? this._responseHeaders.get(name) : null;
+        }
+
+        getAllResponseHeaders() {
+            let headers = "";
+            this._responseHeaders.forEach((value, name) => {
+                headers += name + ": " + value + "\r\n";
+            });
+            return headers;
+        }
+
+        addEventListener(type, listener) {
+            if (!this._listeners.has(type)) {
+                this._listeners.set(type, []);
+            }
+            this._listeners.get(type).push(listener);
+        }
+
+        removeEventListener(type, listener) {
+            const listeners = this._listeners.get(type) || [];
+            const index = listeners.indexOf(listener);
+            if (index !== -1) {
+                listeners.splice(index, 1);
+            }
+        }
+
+        _fire(type) {
+            const event = { type, target: this, currentTarget: this };
+
+            const handler = this["on" + type];
+            if (typeof handler === "function") {
+                handler.call(this, event);
+            }
+            (this._listeners.get(type) || []).slice().forEach((listener) => listener.call(this, event));
+        }
+
+        _setState(state) {
+            this.readyState = state;
+            this._fire("readystatechange");
+        }
+
+        _complete(result) {
+            if (result.error !== undefined) {
+                this._reset();
+                this._setState(XMLHttpRequest.DONE);
+                this._fire("error");
+                this._fire("loadend");
+                return;
+            }
+
+            this.status = result.status;
+            this.statusText = result.statusText;
+            this.responseURL = result.url;
+            this._responseHeaders = headersOf(result);
+            this._setState(XMLHttpRequest.HEADERS_RECEIVED);
+            this._setState(XMLHttpRequest.LOADING);
+
+            this.responseText = result.body;
+            this.response = this.responseType === "json" ? JSON.parse(result.body) : result.body;
+            this._setState(XMLHttpRequest.DONE);
+            this._fire("load");
+            this._fire("loadend");
+        }
+    }
+
+    Object.assign(XMLHttpRequest, { UNSENT: 0, OPENED: 1, HEADERS_RECEIVED: 2, LOADING: 3, DONE: 4 });
+
+    Object.assign(globalThis, { Headers, Request, Response, fetch, XMLHttpRequest });
+})(__gosub_net);
@@ -3,4 +3,74 @@
 //! This crate supplies the Javascript APIs that can be called by the javascript engine running in the browser.
 //!
 
+use crate::console::{Console, Printer};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::fetch::Fetch;
+use crate::storage::{StorageGuard, WebStorage};
+use gosub_shared::types::Result;
+use gosub_webexecutor::event_loop::EventLoop;
+use gosub_webexecutor::js::{JSInterop, JSRuntime};
+use std::cell::RefCell;
+use std::rc::Rc;
+use url::Url;
+
 pub mod console;
+pub mod cssom;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fetch;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script;
+pub mod storage;
+pub mod timers;
+
+/// Creates a new javascript context with the web APIs installed on its global scope. Console output is sent to
+/// the given printer, timers and microtasks are scheduled on the event loop.
+pub fn new_context<RT: JSRuntime>(
+    runtime: &mut RT,
+    printer: Box<dyn Printer>,
+    event_loop: EventLoop,
+) -> Result<RT::Context>
+where
+    RT::Context: 'static,
+{
+    let ctx = runtime.new_context()?;
+
+    Console::implement::<RT>(Rc::new(RefCell::new(Console::new(printer))), ctx.clone())?;
+    timers::install::<RT>(ctx.clone(), event_loop)?;
+
+    Ok(ctx)
+}
+
+/// A javascript context of a document, see `new_document_context`
+pub struct DocumentContext<RT: JSRuntime> {
+    pub ctx: RT::Context,
+    /// Unregisters the `storage` event listeners of the document once the context is dropped
+    _storage: StorageGuard,
+}
+
+/// Creates a new javascript context for a document at `document_url`. Next to the APIs of `new_context`, it has
+/// `localStorage` and `sessionStorage` from the given stores, and `fetch()` and `XMLHttpRequest` that resolve
+/// relative urls against the document.
+pub fn new_document_context<RT: JSRuntime>(
+    runtime: &mut RT,
+    printer: Box<dyn Printer>,
+    event_loop: EventLoop,
+    document_url: &Url,
+    local: &WebStorage,
+    session: &WebStorage,
+) -> Result<DocumentContext<RT>>
+where
+    RT::Context: 'static,
+{
+    let ctx = new_context(runtime, printer, event_loop.clone())?;
+
+    let storage = storage::install::<RT>(ctx.clone(), document_url, local, session, event_loop.clone())?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    Fetch::install::<RT>(
+        Rc::new(RefCell::new(Fetch::new(document_url.clone(), event_loop))),
+        ctx.clone(),
+    )?;
+
+    Ok(DocumentContext { ctx, _storage: storage })
+}
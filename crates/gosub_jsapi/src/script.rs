@@ -0,0 +1,116 @@
+//! Fetching of external scripts and modules
+//!
+//! The script runner of gosub_webexecutor fetches `<script src>` and the imports of module scripts through a
+//! `ScriptFetcher`. This one fetches them over the network (or from disk for `file:` urls) with gosub_net. Modules
+//! are fetched with the CORS checks of gosub_net, so a document can only import modules from other origins when
+//! they allow it.
+use anyhow::anyhow;
+use gosub_net::http::cors::{self, CredentialsMode, RequestMode};
+use gosub_net::http::fetcher::Fetcher;
+use gosub_net::http::request::Request;
+use gosub_shared::traits::document::ScriptKind;
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::ScriptFetcher;
+use url::Url;
+
+/// Fetches scripts with gosub_net
+pub struct NetScriptFetcher {
+    fetcher: Fetcher,
+    document_url: Url,
+}
+
+impl NetScriptFetcher {
+    pub fn new(document_url: Url) -> Self {
+        Self {
+            fetcher: Fetcher::new(document_url.clone()),
+            document_url,
+        }
+    }
+}
+
+impl ScriptFetcher for NetScriptFetcher {
+    fn fetch(&self, url: &Url, kind: ScriptKind) -> Result<String> {
+        // The origin of a local file is opaque, which would fail every CORS check, so local documents can import
+        // local modules without one
+        let local = url.scheme() == "file" && self.document_url.scheme() == "file";
+
+        let response = if kind == ScriptKind::Module && !local {
+            let req = Request::new("GET", url.as_str(), "HTTP/1.1");
+            cors::fetch(
+                &self.fetcher,
+                req,
+                &self.document_url,
+                RequestMode::Cors,
+                CredentialsMode::SameOrigin,
+            )?
+            .response
+        } else {
+            self.fetcher.get_url(url)?
+        };
+
+        if !(200..300).contains(&response.status) {
+            return Err(anyhow!("fetching script {url} failed with status {}", response.status));
+        }
+
+        Ok(String::from_utf8_lossy(&response.body).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn fetch_file() {
+        let path = std::env::temp_dir().join(format!("gosub-script-{}.js", std::process::id()));
+        std::fs::write(&path, "export const answer = 42;").unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let fetcher = NetScriptFetcher::new(url.clone());
+        assert_eq!(
+            fetcher.fetch(&url, ScriptKind::Module).unwrap(),
+            "export const answer = 42;"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(fetcher.fetch(&url, ScriptKind::Classic).is_err());
+    }
+
+    #[test]
+    fn modules_need_cors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/lib.js", listener.local_addr().unwrap())).unwrap();
+
+        // The server answers every request without allowing other origins
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    return;
+                };
+
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                    line.clear();
+                }
+
+                let body = "export default 1;";
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+
+        let fetcher = NetScriptFetcher::new(Url::parse("https://example.com/").unwrap());
+        assert_eq!(fetcher.fetch(&url, ScriptKind::Classic).unwrap(), "export default 1;");
+        assert!(fetcher.fetch(&url, ScriptKind::Module).is_err());
+
+        let fetcher = NetScriptFetcher::new(url.join("/index.html").unwrap());
+        assert_eq!(fetcher.fetch(&url, ScriptKind::Module).unwrap(), "export default 1;");
+    }
+}
@@ -0,0 +1,633 @@
+//! Web storage (<https://html.spec.whatwg.org/multipage/webstorage.html>)
+//!
+//! `localStorage` and `sessionStorage` hold key/value pairs per origin. The pairs of an origin are kept in memory
+//! and written through to a `StorageAdapter` of gosub_config, so local storage persists across sessions when it is
+//! backed by the json or sqlite adapter. Changes that one document makes are announced to the other documents of
+//! the same origin with `storage` events.
+use anyhow::anyhow;
+use gosub_config::settings::Setting;
+use gosub_config::storage::MemoryStorageAdapter;
+use gosub_config::StorageAdapter;
+use gosub_shared::types::Result;
+use gosub_webexecutor::event_loop::{EventLoop, TaskSource};
+use gosub_webexecutor::js::{
+    JSContext, JSFunctionCallBackVariadic, JSFunctionVariadic, JSObject, JSRuntime, JSValue, VariadicArgsInternal,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+use url::{Origin, Url};
+
+/// Default quota of an origin, in UTF-16 code units of its keys and values
+pub const DEFAULT_QUOTA: usize = 5 * 1024 * 1024;
+
+/// A change that a document made to the storage of its origin
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageEvent {
+    /// The key that changed, `None` when the storage was cleared
+    pub key: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    /// Url of the document that made the change
+    pub url: String,
+}
+
+/// A change would make the storage of the origin exceed its quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaExceededError;
+
+impl fmt::Display for QuotaExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the quota of the storage has been exceeded")
+    }
+}
+
+impl std::error::Error for QuotaExceededError {}
+
+/// Returns the size of a key or value, which counts against the quota
+fn size(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// The key/value pairs of one origin
+struct Area {
+    items: BTreeMap<String, String>,
+    /// Size of all keys and values
+    used: usize,
+    /// Prefix of the keys of this origin in the storage adapter, `None` when the area is not persisted
+    prefix: Option<String>,
+}
+
+struct Listener {
+    id: u32,
+    origin: Origin,
+    callback: Rc<dyn Fn(&StorageEvent)>,
+}
+
+struct Inner {
+    adapter: Box<dyn StorageAdapter>,
+    quota: usize,
+    areas: HashMap<Origin, Area>,
+    listeners: Vec<Listener>,
+    next_listener: u32,
+}
+
+impl Inner {
+    /// Returns the area of the origin, loading it from the adapter the first time
+    fn area(&mut self, origin: &Origin) -> &mut Area {
+        let adapter = &self.adapter;
+
+        self.areas.entry(origin.clone()).or_insert_with(|| {
+            // Opaque origins are unique to their document, so there is no point in persisting them
+            let prefix = origin.is_tuple().then(|| format!("{} ", origin.ascii_serialization()));
+
+            let mut items = BTreeMap::new();
+            if let Some(prefix) = &prefix {
+                for (key, value) in adapter.all().unwrap_or_default() {
+                    if let Some(key) = key.strip_prefix(prefix.as_str()) {
+                        items.insert(key.to_string(), value.to_string());
+                    }
+                }
+            }
+
+            let used = items.iter().map(|(key, value)| size(key) + size(value)).sum();
+            Area { items, used, prefix }
+        })
+    }
+
+    /// Returns the callbacks of the listeners that are notified of a change by `source`
+    fn listeners(&self, origin: &Origin, source: u32) -> Vec<Rc<dyn Fn(&StorageEvent)>> {
+        self.listeners
+            .iter()
+            .filter(|listener| listener.id != source && listener.origin == *origin)
+            .map(|listener| Rc::clone(&listener.callback))
+            .collect()
+    }
+}
+
+/// The storage of all origins for one kind of web storage. Clones share the same storage.
+#[derive(Clone)]
+pub struct WebStorage {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl WebStorage {
+    /// Returns storage that is backed by the given adapter, like `localStorage`. Every origin can store up to
+    /// `quota` UTF-16 code units.
+    pub fn new(adapter: Box<dyn StorageAdapter>, quota: usize) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(Inner {
+                adapter,
+                quota,
+                areas: HashMap::new(),
+                listeners: vec![],
+                next_listener: 1,
+            })),
+        }
+    }
+
+    /// Returns storage that only lives in memory, like the `sessionStorage` of a top-level browsing context
+    pub fn in_memory(quota: usize) -> Self {
+        Self::new(Box::new(MemoryStorageAdapter::new()), quota)
+    }
+
+    /// Opens the storage for a document. `on_event` is called for every change that another document of the same
+    /// origin makes.
+    pub fn open(&self, document_url: &Url, on_event: impl Fn(&StorageEvent) + 'static) -> Storage {
+        let mut inner = self.inner.borrow_mut();
+
+        let id = inner.next_listener;
+        inner.next_listener += 1;
+
+        let origin = document_url.origin();
+        inner.listeners.push(Listener {
+            id,
+            origin: origin.clone(),
+            callback: Rc::new(on_event),
+        });
+
+        Storage {
+            web_storage: self.clone(),
+            origin,
+            url: document_url.to_string(),
+            listener: id,
+        }
+    }
+
+    /// Stops notifying a document of changes. This is a no-op while the listeners are being notified.
+    fn remove_listener(&self, id: u32) {
+        if let Ok(mut inner) = self.inner.try_borrow_mut() {
+            inner.listeners.retain(|listener| listener.id != id);
+        }
+    }
+}
+
+/// The storage of the origin of one document (the `Storage` interface)
+pub struct Storage {
+    web_storage: WebStorage,
+    origin: Origin,
+    url: String,
+    listener: u32,
+}
+
+impl Storage {
+    /// Returns the number of items
+    pub fn len(&self) -> usize {
+        self.web_storage.inner.borrow_mut().area(&self.origin).items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the key of the item at the index
+    pub fn key(&self, index: usize) -> Option<String> {
+        let mut inner = self.web_storage.inner.borrow_mut();
+        inner.area(&self.origin).items.keys().nth(index).cloned()
+    }
+
+    pub fn get_item(&self, key: &str) -> Option<String> {
+        let mut inner = self.web_storage.inner.borrow_mut();
+        inner.area(&self.origin).items.get(key).cloned()
+    }
+
+    /// Stores the item, unless that would exceed the quota of the origin
+    pub fn set_item(&self, key: &str, value: &str) -> std::result::Result<(), QuotaExceededError> {
+        let mut inner = self.web_storage.inner.borrow_mut();
+        let quota = inner.quota;
+        let area = inner.area(&self.origin);
+
+        let old_value = area.items.get(key).cloned();
+        if old_value.as_deref() == Some(value) {
+            return Ok(());
+        }
+
+        let used = area.used - old_value.as_deref().map_or(0, |old| size(key) + size(old)) + size(key) + size(value);
+        if used > quota {
+            return Err(QuotaExceededError);
+        }
+
+        area.used = used;
+        area.items.insert(key.to_string(), value.to_string());
+        if let Some(prefix) = area.prefix.clone() {
+            inner
+                .adapter
+                .set(&format!("{prefix}{key}"), Setting::String(value.to_string()));
+        }
+
+        let listeners = inner.listeners(&self.origin, self.listener);
+        drop(inner);
+
+        self.notify(
+            &listeners,
+            StorageEvent {
+                key: Some(key.to_string()),
+                old_value,
+                new_value: Some(value.to_string()),
+                url: self.url.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn remove_item(&self, key: &str) {
+        let mut inner = self.web_storage.inner.borrow_mut();
+        let area = inner.area(&self.origin);
+
+        let Some(old_value) = area.items.remove(key) else {
+            return;
+        };
+        area.used -= size(key) + size(&old_value);
+        if let Some(prefix) = area.prefix.clone() {
+            inner.adapter.remove(&format!("{prefix}{key}"));
+        }
+
+        let listeners = inner.listeners(&self.origin, self.listener);
+        drop(inner);
+
+        self.notify(
+            &listeners,
+            StorageEvent {
+                key: Some(key.to_string()),
+                old_value: Some(old_value),
+                new_value: None,
+                url: self.url.clone(),
+            },
+        );
+    }
+
+    /// Removes all items of the origin
+    pub fn clear(&self) {
+        let mut inner = self.web_storage.inner.borrow_mut();
+        let area = inner.area(&self.origin);
+
+        if area.items.is_empty() {
+            return;
+        }
+        let items = std::mem::take(&mut area.items);
+        area.used = 0;
+        if let Some(prefix) = area.prefix.clone() {
+            for key in items.keys() {
+                inner.adapter.remove(&format!("{prefix}{key}"));
+            }
+        }
+
+        let listeners = inner.listeners(&self.origin, self.listener);
+        drop(inner);
+
+        self.notify(
+            &listeners,
+            StorageEvent {
+                key: None,
+                old_value: None,
+                new_value: None,
+                url: self.url.clone(),
+            },
+        );
+    }
+
+    fn notify(&self, listeners: &[Rc<dyn Fn(&StorageEvent)>], event: StorageEvent) {
+        for listener in listeners {
+            listener(&event);
+        }
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        self.web_storage.remove_listener(self.listener);
+    }
+}
+
+/// Unregisters the `storage` event listeners of a document when it is dropped.
+///
+/// The listeners hold on to the javascript context of the document to dispatch the events, and the context holds on
+/// to the storages of the document, so the listeners aren't unregistered when the context goes away. The guard has
+/// to be dropped together with the context instead.
+#[must_use = "the storage event listeners are unregistered when the guard is dropped"]
+pub struct StorageGuard {
+    listeners: Vec<(WebStorage, u32)>,
+}
+
+impl Drop for StorageGuard {
+    fn drop(&mut self) {
+        for (web_storage, id) in &self.listeners {
+            web_storage.remove_listener(*id);
+        }
+    }
+}
+
+/// The storages of a document, by the name of their area in javascript
+struct Areas {
+    local: Storage,
+    session: Storage,
+}
+
+impl Areas {
+    fn get(&self, name: &str) -> Result<&Storage> {
+        match name {
+            "local" => Ok(&self.local),
+            "session" => Ok(&self.session),
+            _ => Err(anyhow!("unknown storage area: {name}")),
+        }
+    }
+}
+
+/// Storage events that still have to be dispatched in javascript, with the name of their area
+type PendingEvents = Rc<RefCell<VecDeque<(&'static str, StorageEvent)>>>;
+
+/// Installs `localStorage` and `sessionStorage` for a document at `document_url` on the global scope of the
+/// context. Changes by other documents are dispatched as `storage` events on the global scope, from tasks on the
+/// event loop, until the returned guard is dropped.
+pub fn install<RT: JSRuntime>(
+    mut ctx: RT::Context,
+    document_url: &Url,
+    local: &WebStorage,
+    session: &WebStorage,
+    event_loop: EventLoop,
+) -> Result<StorageGuard>
+where
+    RT::Context: 'static,
+{
+    let pending: PendingEvents = Rc::new(RefCell::new(VecDeque::new()));
+
+    let on_event = |area: &'static str| {
+        let pending = Rc::clone(&pending);
+        let event_loop = event_loop.clone();
+        let ctx = ctx.clone();
+        move |event: &StorageEvent| {
+            pending.borrow_mut().push_back((area, event.clone()));
+
+            let mut ctx = ctx.clone();
+            event_loop.queue_task(TaskSource::DomManipulation, move || {
+                ctx.run("__gosub_storage.dispatchEvent()")?;
+                Ok(())
+            });
+        }
+    };
+
+    let areas = Rc::new(Areas {
+        local: local.open(document_url, on_event("local")),
+        session: session.open(document_url, on_event("session")),
+    });
+
+    let guard = StorageGuard {
+        listeners: vec![
+            (local.clone(), areas.local.listener),
+            (session.clone(), areas.session.listener),
+        ],
+    };
+
+    let native = ctx.new_global_object("__gosub_storage")?;
+
+    let method = |name: &str, f: fn(&Storage, &[RT::Value], RT::Context) -> Result<RT::Value>| -> Result<()> {
+        let areas = Rc::clone(&areas);
+        let function = RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let ctx = cb.context();
+            let args = cb.args().as_vec(ctx.clone());
+
+            let result = match args.first() {
+                Some(area) => area
+                    .as_string()
+                    .and_then(|area| areas.get(&area).and_then(|storage| f(storage, &args[1..], ctx))),
+                None => Err(anyhow!("no storage area given")),
+            };
+
+            match result {
+                Ok(value) => cb.ret(value),
+                Err(e) => cb.error(e),
+            }
+        })?;
+        native.set_method_variadic(name, &function)
+    };
+
+    method("length", |storage, _, ctx| {
+        RT::Value::new_number(ctx, storage.len() as f64)
+    })?;
+    method("key", |storage, args, ctx| {
+        let index = args.first().map_or(Ok(0.0), |index| index.as_number())?;
+        optional_string::<RT>(ctx, storage.key(index as usize))
+    })?;
+    method("getItem", |storage, args, ctx| {
+        optional_string::<RT>(ctx, storage.get_item(&string_arg::<RT>(args, 0)?))
+    })?;
+    method("setItem", |storage, args, ctx| {
+        let stored = storage
+            .set_item(&string_arg::<RT>(args, 0)?, &string_arg::<RT>(args, 1)?)
+            .is_ok();
+        RT::Value::new_bool(ctx, stored)
+    })?;
+    method("removeItem", |storage, args, ctx| {
+        storage.remove_item(&string_arg::<RT>(args, 0)?);
+        RT::Value::new_undefined(ctx)
+    })?;
+    method("clear", |storage, _, ctx| {
+        storage.clear();
+        RT::Value::new_undefined(ctx)
+    })?;
+
+    let take_event = RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+        let ctx = cb.context();
+        let event = pending.borrow_mut().pop_front();
+
+        match event_object::<RT>(ctx, event) {
+            Ok(value) => cb.ret(value),
+            Err(e) => cb.error(e),
+        }
+    })?;
+    native.set_method_variadic("takeEvent", &take_event)?;
+
+    ctx.run(include_str!("storage/storage.js"))?;
+
+    Ok(guard)
+}
+
+/// Returns the argument at the index as a string
+fn string_arg<RT: JSRuntime>(args: &[RT::Value], index: usize) -> Result<String> {
+    match args.get(index) {
+        Some(value) => value.as_string(),
+        None => Err(anyhow!("missing argument {index}")),
+    }
+}
+
+/// Returns the string, or null when there is none
+fn optional_string<RT: JSRuntime>(ctx: RT::Context, value: Option<String>) -> Result<RT::Value> {
+    match value {
+        Some(value) => RT::Value::new_string(ctx, &value),
+        None => RT::Value::new_null(ctx),
+    }
+}
+
+/// Converts a pending event to `{ area, key, oldValue, newValue, url }`, or null when there is no event
+fn event_object<RT: JSRuntime>(ctx: RT::Context, event: Option<(&str, StorageEvent)>) -> Result<RT::Value> {
+    let Some((area, event)) = event else {
+        return RT::Value::new_null(ctx);
+    };
+
+    let obj = RT::Value::new_object(ctx.clone())?;
+    obj.set_property("area", &RT::Value::new_string(ctx.clone(), area)?)?;
+    obj.set_property("key", &optional_string::<RT>(ctx.clone(), event.key)?)?;
+    obj.set_property("oldValue", &optional_string::<RT>(ctx.clone(), event.old_value)?)?;
+    obj.set_property("newValue", &optional_string::<RT>(ctx.clone(), event.new_value)?)?;
+    obj.set_property("url", &RT::Value::new_string(ctx, &event.url)?)?;
+
+    Ok(obj.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_config::storage::JsonStorageAdapter;
+    use gosub_v8::{V8Context, V8Engine};
+
+    #[test]
+    fn items_and_quota() {
+        let storage = WebStorage::in_memory(10);
+        let url = Url::parse("https://example.com/page").unwrap();
+        let a = storage.open(&url, |_| {});
+        let b = storage.open(&Url::parse("https://example.org/").unwrap(), |_| {});
+
+        assert!(a.set_item("foo", "bar").is_ok());
+        assert!(a.set_item("baz", "1").is_ok());
+        assert_eq!(a.set_item("quux", "too long"), Err(QuotaExceededError));
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.key(0).as_deref(), Some("baz"));
+        assert_eq!(a.get_item("foo").as_deref(), Some("bar"));
+        assert!(b.is_empty());
+
+        a.remove_item("foo");
+        assert!(a.set_item("quux", "ab").is_ok());
+        a.clear();
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn events() {
+        let storage = WebStorage::in_memory(DEFAULT_QUOTA);
+        let events = Rc::new(RefCell::new(vec![]));
+
+        let listener = |name: &'static str| {
+            let events = Rc::clone(&events);
+            move |event: &StorageEvent| events.borrow_mut().push((name, event.clone()))
+        };
+        let a = storage.open(&Url::parse("https://example.com/a").unwrap(), listener("a"));
+        let _b = storage.open(&Url::parse("https://example.com/b").unwrap(), listener("b"));
+        let _c = storage.open(&Url::parse("https://example.org/").unwrap(), listener("c"));
+
+        a.set_item("foo", "bar").unwrap();
+        a.set_item("foo", "bar").unwrap();
+        a.clear();
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                (
+                    "b",
+                    StorageEvent {
+                        key: Some("foo".to_string()),
+                        old_value: None,
+                        new_value: Some("bar".to_string()),
+                        url: "https://example.com/a".to_string(),
+                    }
+                ),
+                (
+                    "b",
+                    StorageEvent {
+                        key: None,
+                        old_value: None,
+                        new_value: None,
+                        url: "https://example.com/a".to_string(),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn persists_in_adapter() {
+        let path = std::env::temp_dir()
+            .join(format!("gosub-web-storage-{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = std::fs::remove_file(&path);
+        let url = Url::parse("https://example.com/").unwrap();
+
+        let storage = WebStorage::new(Box::new(JsonStorageAdapter::try_from(&path).unwrap()), DEFAULT_QUOTA);
+        let document = storage.open(&url, |_| {});
+        document.set_item("visits", "3").unwrap();
+        document.set_item("removed", "yes").unwrap();
+        document.remove_item("removed");
+
+        let reloaded = WebStorage::new(Box::new(JsonStorageAdapter::try_from(&path).unwrap()), DEFAULT_QUOTA);
+        let document = reloaded.open(&url, |_| {});
+        assert_eq!(document.get_item("visits").as_deref(), Some("3"));
+        assert_eq!(document.len(), 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn storage_in_javascript() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let local = WebStorage::in_memory(20);
+        let session = WebStorage::in_memory(DEFAULT_QUOTA);
+        let event_loop = EventLoop::new();
+
+        let mut runtime = V8Engine::new();
+        let mut ctx: V8Context = runtime.new_context().unwrap();
+        let _guard = install::<V8Engine>(ctx.clone(), &url, &local, &session, event_loop.clone()).unwrap();
+
+        let result = ctx
+            .run(
+                r#"
+                localStorage.setItem("foo", "bar");
+                localStorage.count = 1;
+                sessionStorage.other = "x";
+                var quota;
+                try { localStorage.big = "more than the quota allows"; } catch (e) { quota = e.name; }
+                [localStorage.foo, localStorage.getItem("count"), localStorage.length, Object.keys(localStorage).join(","),
+                 "count" in localStorage, sessionStorage.length, quota].join(" ");
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            result.as_string().unwrap(),
+            "bar 1 2 count,foo true 1 QuotaExceededError"
+        );
+
+        ctx.run(r#"var changed = []; addEventListener("storage", (e) => changed.push(e.key + "=" + e.newValue));"#)
+            .unwrap();
+        let other = local.open(&Url::parse("https://example.com/other").unwrap(), |_| {});
+        other.set_item("foo", "baz").unwrap();
+        event_loop.tick(0.0);
+
+        assert_eq!(ctx.run("changed.join()").unwrap().as_string().unwrap(), "foo=baz");
+        assert_eq!(ctx.run("localStorage.foo").unwrap().as_string().unwrap(), "baz");
+    }
+
+    #[test]
+    fn guard_unregisters_listeners() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let local = WebStorage::in_memory(DEFAULT_QUOTA);
+        let session = WebStorage::in_memory(DEFAULT_QUOTA);
+        let event_loop = EventLoop::new();
+
+        let mut runtime = V8Engine::new();
+        let ctx: V8Context = runtime.new_context().unwrap();
+        let guard = install::<V8Engine>(ctx.clone(), &url, &local, &session, event_loop.clone()).unwrap();
+        assert_eq!(local.inner.borrow().listeners.len(), 1);
+        assert_eq!(session.inner.borrow().listeners.len(), 1);
+
+        drop(guard);
+        drop(ctx);
+        assert!(local.inner.borrow().listeners.is_empty());
+        assert!(session.inner.borrow().listeners.is_empty());
+
+        // Changes are no longer dispatched to the context
+        let other = local.open(&Url::parse("https://example.com/other").unwrap(), |_| {});
+        other.set_item("foo", "bar").unwrap();
+        assert!(!event_loop.has_pending_tasks());
+    }
+}
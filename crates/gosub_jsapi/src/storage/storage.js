@@ -0,0 +1,165 @@
+// Web storage (https://html.spec.whatwg.org/multipage/webstorage.html) on top of the native __gosub_storage object.
+// Storage areas are proxies, so items can also be read and written as properties (`localStorage.foo = "bar"`).
+(function (native) {
+    "use strict";
+
+    // Name of the native storage area ("local" or "session") of every storage object and its proxy
+    const areas = new WeakMap();
+
+    class Storage {
+        get length() {
+            return native.length(areas.get(this));
+        }
+
+        key(index) {
+            return native.key(areas.get(this), index >>> 0);
+        }
+
+        getItem(key) {
+            return native.getItem(areas.get(this), String(key));
+        }
+
+        setItem(key, value) {
+            if (!native.setItem(areas.get(this), String(key), String(value))) {
+                const error = new Error("The quota of the storage has been exceeded");
+                error.name = "QuotaExceededError";
+                throw error;
+            }
+        }
+
+        removeItem(key) {
+            native.removeItem(areas.get(this), String(key));
+        }
+
+        clear() {
+            native.clear(areas.get(this));
+        }
+    }
+
+    // Properties that are not defined by the Storage interface are items
+    function isItem(target, prop) {
+        return typeof prop === "string" && !(prop in target);
+    }
+
+    const handler = {
+        get(target, prop, receiver) {
+            if (isItem(target, prop)) {
+                const value = target.getItem(prop);
+                return value === null ? undefined : value;
+            }
+            return Reflect.get(target, prop, receiver);
+        },
+
+        set(target, prop, value) {
+            if (isItem(target, prop)) {
+                target.setItem(prop, value);
+                return true;
+            }
+            return Reflect.set(target, prop, value);
+        },
+
+        has(target, prop) {
+            return prop in target || (typeof prop === "string" && target.getItem(prop) !== null);
+        },
+
+        deleteProperty(target, prop) {
+            if (isItem(target, prop)) {
+                target.removeItem(prop);
+                return true;
+            }
+            return Reflect.deleteProperty(target, prop);
+        },
+
+        ownKeys(target) {
+            const keys = [];
+            for (let i = 0; i < target.length; i++) {
+                keys.push(target.key(i));
+            }
+            return keys.concat(Reflect.ownKeys(target).filter((key) => !keys.includes(key)));
+        },
+
+        getOwnPropertyDescriptor(target, prop) {
+            if (isItem(target, prop)) {
+                const value = target.getItem(prop);
+                return value === null ? undefined : { value, writable: true, enumerable: true, configurable: true };
+            }
+            return Reflect.getOwnPropertyDescriptor(target, prop);
+        },
+    };
+
+    function storage(area) {
+        const target = new Storage();
+        const proxy = new Proxy(target, handler);
+        areas.set(target, area);
+        areas.set(proxy, area);
+        return proxy;
+    }
+
+    const storages = { local: storage("local"), session: storage("session") };
+
+    class StorageEvent {
+        constructor(type, init = {}) {
+            this.type = String(type);
+            this.key = init.key === undefined ? null : init.key;
+            this.oldValue = init.oldValue === undefined ? null : init.oldValue;
+            this.newValue = init.newValue === undefined ? null : init.newValue;
+            this.url = init.url === undefined ? "" : init.url;
+            this.storageArea = init.storageArea === undefined ? null : init.storageArea;
+        }
+    }
+
+    // The global scope is the target of storage events, when it isn't an event target yet it becomes a minimal one
+    if (typeof globalThis.addEventListener !== "function") {
+        const listeners = new Map();
+
+        globalThis.addEventListener = function (type, listener) {
+            if (!listeners.has(type)) {
+                listeners.set(type, []);
+            }
+            if (!listeners.get(type).includes(listener)) {
+                listeners.get(type).push(listener);
+            }
+        };
+
+        globalThis.removeEventListener = function (type, listener) {
+            const list = listeners.get(type) || [];
+            const index = list.indexOf(listener);
+            if (index !== -1) {
+                list.splice(index, 1);
+            }
+        };
+
+        globalThis.dispatchEvent = function (event) {
+            const handler = globalThis["on" + event.type];
+            if (typeof handler === "function") {
+                handler.call(globalThis, event);
+            }
+            (listeners.get(event.type) || []).slice().forEach((listener) => listener.call(globalThis, event));
+            return true;
+        };
+    }
+
+    native.dispatchEvent = function () {
+        const data = native.takeEvent();
+        if (data === null) {
+            return;
+        }
+
+        globalThis.dispatchEvent(
+            new StorageEvent("storage", {
+                key: data.key,
+                oldValue: data.oldValue,
+                newValue: data.newValue,
+                url: data.url,
+                storageArea: storages[data.area],
+            }),
+        );
+    };
+
+    Object.assign(globalThis, {
+        Storage,
+        StorageEvent,
+        localStorage: storages.local,
+        sessionStorage: storages.session,
+    });
+})(__gosub_storage);
@@ -0,0 +1,233 @@
+//! Timers (<https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#timers>), `queueMicrotask` and
+//! animation frames
+//!
+//! `setTimeout`, `setInterval`, `clearTimeout`, `clearInterval`, `requestAnimationFrame`, `cancelAnimationFrame` and
+//! `queueMicrotask` are defined in javascript (`timers/timers.js`) on top of a native `__gosub_timers` object. The
+//! callbacks are kept on the javascript side, the native side schedules them on the event loop and calls back into
+//! javascript when they are due.
+use gosub_shared::types::Result;
+use gosub_webexecutor::event_loop::EventLoop;
+use gosub_webexecutor::js::{
+    JSContext, JSFunctionCallBackVariadic, JSFunctionVariadic, JSObject, JSRuntime, JSValue, VariadicArgsInternal,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Installs the timer functions on the global scope of the context. Their callbacks run from the event loop, which
+/// is why the context has to outlive the calls that are made from it.
+pub fn install<RT: JSRuntime>(mut ctx: RT::Context, event_loop: EventLoop) -> Result<()>
+where
+    RT::Context: 'static,
+{
+    let timers = ctx.new_global_object("__gosub_timers")?;
+
+    let set_timer = {
+        let event_loop = event_loop.clone();
+        let ctx = ctx.clone();
+        RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let cb_ctx = cb.context();
+            let args = cb.args().as_vec(cb_ctx.clone());
+
+            let timeout = args.first().and_then(|t| t.as_number().ok()).unwrap_or_default();
+            let repeat = args.get(1).is_some_and(|r| r.as_bool().unwrap_or_default());
+
+            // The callback needs the handle of its timer, which is only known once the timer has been created
+            let handle = Rc::new(Cell::new(0));
+            let callback = {
+                let handle = Rc::clone(&handle);
+                let mut ctx = ctx.clone();
+                move || {
+                    ctx.run(&format!("__gosub_timers.fire({})", handle.get()))?;
+                    Ok(())
+                }
+            };
+
+            let id = if repeat {
+                event_loop.set_interval(callback, timeout)
+            } else {
+                event_loop.set_timeout(callback, timeout)
+            };
+            handle.set(id);
+
+            match RT::Value::new_number(cb_ctx, id) {
+                Ok(value) => cb.ret(value),
+                Err(e) => cb.error(e),
+            }
+        })?
+    };
+    timers.set_method_variadic("setTimer", &set_timer)?;
+
+    let clear_timer = {
+        let event_loop = event_loop.clone();
+        RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let cb_ctx = cb.context();
+            if let Some(id) = handle_arg::<RT>(cb, cb_ctx) {
+                event_loop.clear_timer(id);
+            }
+        })?
+    };
+    timers.set_method_variadic("clearTimer", &clear_timer)?;
+
+    let request_frame = {
+        let event_loop = event_loop.clone();
+        let ctx = ctx.clone();
+        RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let cb_ctx = cb.context();
+
+            let handle = Rc::new(Cell::new(0));
+            let callback = {
+                let handle = Rc::clone(&handle);
+                let mut ctx = ctx.clone();
+                move |time: f64| {
+                    ctx.run(&format!("__gosub_timers.frame({}, {time})", handle.get()))?;
+                    Ok(())
+                }
+            };
+
+            let id = event_loop.request_animation_frame(callback);
+            handle.set(id);
+
+            match RT::Value::new_number(cb_ctx, id) {
+                Ok(value) => cb.ret(value),
+                Err(e) => cb.error(e),
+            }
+        })?
+    };
+    timers.set_method_variadic("requestAnimationFrame", &request_frame)?;
+
+    let cancel_frame = {
+        let event_loop = event_loop.clone();
+        RT::FunctionVariadic::new(ctx.clone(), move |cb| {
+            let cb_ctx = cb.context();
+            if let Some(id) = handle_arg::<RT>(cb, cb_ctx) {
+                event_loop.cancel_animation_frame(id);
+            }
+        })?
+    };
+    timers.set_method_variadic("cancelAnimationFrame", &cancel_frame)?;
+
+    let queue_microtask = {
+        let ctx = ctx.clone();
+        RT::FunctionVariadic::new(ctx.clone(), move |_| {
+            let mut ctx = ctx.clone();
+            event_loop.queue_microtask(move || {
+                ctx.run("__gosub_timers.microtask()")?;
+                Ok(())
+            });
+        })?
+    };
+    timers.set_method_variadic("queueMicrotask", &queue_microtask)?;
+
+    ctx.run(include_str!("timers/timers.js"))?;
+
+    Ok(())
+}
+
+/// Reads the handle of a timer or an animation frame request from the first argument
+fn handle_arg<RT: JSRuntime>(cb: &mut RT::FunctionCallBackVariadic, ctx: RT::Context) -> Option<u32> {
+    cb.args()
+        .get(0, ctx)
+        .and_then(|id| id.as_number().ok())
+        .map(|id| id as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_v8::{V8Context, V8Engine};
+
+    fn context(event_loop: EventLoop) -> V8Context<'static> {
+        let mut runtime = V8Engine::new();
+        let ctx = runtime.new_context().unwrap();
+        install::<V8Engine>(ctx.clone(), event_loop).unwrap();
+        ctx
+    }
+
+    fn log(ctx: &mut V8Context) -> String {
+        ctx.run("log.join(\" \")").unwrap().as_string().unwrap()
+    }
+
+    #[test]
+    fn timeouts_and_intervals() {
+        let event_loop = EventLoop::new();
+        let mut ctx = context(event_loop.clone());
+
+        ctx.run(
+            r#"
+            var log = [];
+            setTimeout((a, b) => log.push("timeout " + a + b), 25, 1, 2);
+            var cleared = setTimeout(() => log.push("cleared"), 10);
+            clearTimeout(cleared);
+            var interval = setInterval(() => {
+                log.push("interval");
+                if (log.filter((entry) => entry === "interval").length === 2) {
+                    clearInterval(interval);
+                }
+            }, 10);
+            setTimeout("log.push('string')", 5);
+            "#,
+        )
+        .unwrap();
+
+        event_loop.tick(5.0);
+        assert_eq!(log(&mut ctx), "string");
+
+        event_loop.tick(10.0);
+        event_loop.tick(20.0);
+        event_loop.tick(30.0);
+        assert_eq!(log(&mut ctx), "string interval interval timeout 12");
+        assert_eq!(event_loop.next_timer_deadline(), None);
+    }
+
+    #[test]
+    fn microtasks_run_before_the_next_task() {
+        let event_loop = EventLoop::new();
+        let mut ctx = context(event_loop.clone());
+
+        ctx.run(
+            r#"
+            var log = [];
+            setTimeout(() => {
+                log.push("task");
+                queueMicrotask(() => {
+                    log.push("microtask");
+                    queueMicrotask(() => log.push("nested microtask"));
+                });
+            });
+            setTimeout(() => log.push("next task"));
+            "#,
+        )
+        .unwrap();
+
+        event_loop.tick(0.0);
+        assert_eq!(log(&mut ctx), "task microtask nested microtask next task");
+
+        let error = ctx.run("try { queueMicrotask(1) } catch (e) { e.name }").unwrap();
+        assert_eq!(error.as_string().unwrap(), "TypeError");
+    }
+
+    #[test]
+    fn animation_frames() {
+        let event_loop = EventLoop::new();
+        let mut ctx = context(event_loop.clone());
+
+        ctx.run(
+            r#"
+            var log = [];
+            requestAnimationFrame((time) => {
+                log.push("frame " + time);
+                requestAnimationFrame((time) => log.push("next frame " + time));
+            });
+            cancelAnimationFrame(requestAnimationFrame(() => log.push("cancelled")));
+            "#,
+        )
+        .unwrap();
+
+        event_loop.run_animation_frames(16.0);
+        assert_eq!(log(&mut ctx), "frame 16");
+
+        event_loop.run_animation_frames(32.0);
+        assert_eq!(log(&mut ctx), "frame 16 next frame 32");
+        assert!(!event_loop.has_animation_frames());
+    }
+}
@@ -0,0 +1,101 @@
+// Timers (https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#timers), microtask queuing and
+// animation frames on top of the native __gosub_timers object. The native side schedules the callbacks on the event
+// loop and calls `fire`, `frame` or `microtask` when they are due.
+(function (native) {
+    "use strict";
+
+    // Callbacks of the active timers and animation frame requests, by their handle
+    const timers = new Map();
+    const frames = new Map();
+    // Callbacks of queueMicrotask, the native side runs them in the order they were queued
+    const microtasks = [];
+
+    native.fire = function (id) {
+        const timer = timers.get(id);
+        if (!timer) {
+            return;
+        }
+        if (!timer.repeat) {
+            timers.delete(id);
+        }
+        timer.callback();
+    };
+
+    native.frame = function (id, time) {
+        const callback = frames.get(id);
+        frames.delete(id);
+        if (callback) {
+            callback(time);
+        }
+    };
+
+    native.microtask = function () {
+        const callback = microtasks.shift();
+        if (callback) {
+            callback();
+        }
+    };
+
+    function handlerOf(handler, args) {
+        if (typeof handler === "function") {
+            return () => handler.apply(globalThis, args);
+        }
+        // A string handler is evaluated in the global scope
+        const code = String(handler);
+        return () => (0, eval)(code);
+    }
+
+    function setTimer(handler, timeout, args, repeat) {
+        const id = native.setTimer(Number(timeout) || 0, repeat);
+        timers.set(id, { callback: handlerOf(handler, args), repeat });
+        return id;
+    }
+
+    function setTimeout(handler, timeout = 0, ...args) {
+        return setTimer(handler, timeout, args, false);
+    }
+
+    function setInterval(handler, timeout = 0, ...args) {
+        return setTimer(handler, timeout, args, true);
+    }
+
+    // clearTimeout and clearInterval share the same handles
+    function clearTimeout(id = 0) {
+        id = Number(id) >>> 0;
+        timers.delete(id);
+        native.clearTimer(id);
+    }
+
+    function requestAnimationFrame(callback) {
+        if (typeof callback !== "function") {
+            throw new TypeError("The callback provided as parameter 1 is not a function");
+        }
+        const id = native.requestAnimationFrame();
+        frames.set(id, callback);
+        return id;
+    }
+
+    function cancelAnimationFrame(id) {
+        id = Number(id) >>> 0;
+        frames.delete(id);
+        native.cancelAnimationFrame(id);
+    }
+
+    function queueMicrotask(callback) {
+        if (typeof callback !== "function") {
+            throw new TypeError("The callback provided as parameter 1 is not a function");
+        }
+        microtasks.push(callback);
+        native.queueMicrotask();
+    }
+
+    Object.assign(globalThis, {
+        setTimeout,
+        setInterval,
+        clearTimeout,
+        clearInterval: clearTimeout,
+        requestAnimationFrame,
+        cancelAnimationFrame,
+        queueMicrotask,
+    });
+})(__gosub_timers);
@@ -28,6 +28,9 @@ pub enum Error {
     #[error("there was a problem: {0}")]
     Generic(String),
 
+    #[error("cors: {0}")]
+    Cors(String),
+
     #[error("failed to parse url: {0}")]
     Url(#[from] url::ParseError),
 }
@@ -1,5 +1,6 @@
 pub use ureq;
 
+pub mod cors;
 pub mod fetcher;
 pub mod headers;
 pub mod request;
@@ -0,0 +1,593 @@
+//! Same-origin and CORS checks of the fetch standard (<https://fetch.spec.whatwg.org/#http-cors-protocol>)
+//!
+//! Requests from a document to its own origin are sent as-is. Requests to other origins are either refused, made
+//! opaque or checked with the CORS protocol, depending on the mode of the request.
+use crate::errors::Error;
+use crate::http::fetcher::{redirect, Fetcher, MAX_REDIRECTS};
+use crate::http::headers::Headers;
+use crate::http::request::Request;
+use crate::http::response::Response;
+use gosub_shared::types::Result;
+use url::{Origin, Url};
+
+/// How requests to other origins are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestMode {
+    /// Requests to other origins fail
+    SameOrigin,
+    /// Requests to other origins are checked with the CORS protocol
+    Cors,
+    /// Requests to other origins are limited to simple requests, and their responses are opaque
+    NoCors,
+}
+
+impl RequestMode {
+    /// Returns the mode by its name in the fetch standard, like "same-origin"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "same-origin" => Some(Self::SameOrigin),
+            "cors" => Some(Self::Cors),
+            "no-cors" => Some(Self::NoCors),
+            _ => None,
+        }
+    }
+}
+
+/// When cookies are sent with a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialsMode {
+    Omit,
+    SameOrigin,
+    Include,
+}
+
+impl CredentialsMode {
+    /// Returns the mode by its name in the fetch standard, like "include"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "omit" => Some(Self::Omit),
+            "same-origin" => Some(Self::SameOrigin),
+            "include" => Some(Self::Include),
+            _ => None,
+        }
+    }
+}
+
+/// How much of a response is exposed to the document (<https://fetch.spec.whatwg.org/#concept-response-type>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseType {
+    /// A same-origin response
+    Basic,
+    /// A cross-origin response that passed the CORS check, with only the safelisted and exposed headers
+    Cors,
+    /// A no-cors response to another origin, without status, headers or body
+    Opaque,
+}
+
+impl ResponseType {
+    /// Returns the name of the type in the fetch standard
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Basic => "basic",
+            Self::Cors => "cors",
+            Self::Opaque => "opaque",
+        }
+    }
+}
+
+/// A response together with how much of it may be exposed
+#[derive(Debug)]
+pub struct FilteredResponse {
+    pub response_type: ResponseType,
+    pub response: Response,
+}
+
+/// Headers that scripts are not allowed to set (<https://fetch.spec.whatwg.org/#forbidden-request-header>)
+const FORBIDDEN_REQUEST_HEADERS: [&str; 21] = [
+    "accept-charset",
+    "accept-encoding",
+    "access-control-request-headers",
+    "access-control-request-method",
+    "connection",
+    "content-length",
+    "cookie",
+    "cookie2",
+    "date",
+    "dnt",
+    "expect",
+    "host",
+    "keep-alive",
+    "origin",
+    "referer",
+    "set-cookie",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "via",
+];
+
+/// Response headers that are always exposed on CORS responses
+const SAFELISTED_RESPONSE_HEADERS: [&str; 7] = [
+    "cache-control",
+    "content-language",
+    "content-length",
+    "content-type",
+    "expires",
+    "last-modified",
+    "pragma",
+];
+
+/// Returns true when the header can not be set by scripts
+pub fn is_forbidden_request_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    FORBIDDEN_REQUEST_HEADERS.contains(&name.as_str()) || name.starts_with("proxy-") || name.starts_with("sec-")
+}
+
+/// Returns true for methods that can be sent to other origins without a preflight request
+pub fn is_safelisted_method(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD" | "POST")
+}
+
+/// Returns true for headers that can be sent to other origins without a preflight request
+/// (<https://fetch.spec.whatwg.org/#cors-safelisted-request-header>)
+pub fn is_safelisted_request_header(name: &str, value: &str) -> bool {
+    if value.len() > 128 {
+        return false;
+    }
+
+    match name.to_ascii_lowercase().as_str() {
+        "accept" | "accept-language" | "content-language" => true,
+        "content-type" => {
+            let essence = value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+            matches!(
+                essence.as_str(),
+                "application/x-www-form-urlencoded" | "multipart/form-data" | "text/plain"
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Returns the lowercased and sorted names of the headers that are not safelisted
+fn unsafe_header_names(headers: &Headers) -> Vec<String> {
+    let mut names = headers
+        .all()
+        .iter()
+        .filter(|(name, value)| !is_safelisted_request_header(name, value))
+        .map(|(name, _)| name.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Returns true when a request to another origin has to be preceded by a preflight request
+pub fn needs_preflight(req: &Request) -> bool {
+    !is_safelisted_method(&req.method) || !unsafe_header_names(&req.headers).is_empty()
+}
+
+/// Returns the preflight request that asks the server of `req` whether the request may be sent from `origin`
+pub fn preflight_request(req: &Request, origin: &Origin) -> Request {
+    let mut preflight = Request::new("OPTIONS", &req.uri, &req.version);
+    preflight.headers.set_str("Origin", &origin.ascii_serialization());
+    preflight.headers.set_str("Access-Control-Request-Method", &req.method);
+
+    let names = unsafe_header_names(&req.headers);
+    if !names.is_empty() {
+        preflight
+            .headers
+            .set_str("Access-Control-Request-Headers", &names.join(","));
+    }
+
+    preflight
+}
+
+/// Returns the comma separated values of a response header
+fn header_values(response: &Response, name: &str) -> Vec<String> {
+    response
+        .headers
+        .get_ignore_case(name)
+        .map(|value| value.split(',').map(|v| v.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Checks whether the response may be shared with `origin` (<https://fetch.spec.whatwg.org/#cors-check>)
+pub fn check_access(response: &Response, origin: &Origin, credentials: bool) -> Result<()> {
+    let serialized = origin.ascii_serialization();
+
+    match response.headers.get_ignore_case("Access-Control-Allow-Origin") {
+        Some(allowed) if allowed == "*" && !credentials => {}
+        Some(allowed) if *allowed == serialized => {}
+        Some(allowed) => {
+            return Err(Error::Cors(format!("origin {serialized} is not allowed, only {allowed} is")).into());
+        }
+        None => return Err(Error::Cors("no Access-Control-Allow-Origin header present".to_string()).into()),
+    }
+
+    if credentials
+        && !response
+            .headers
+            .get_ignore_case("Access-Control-Allow-Credentials")
+            .is_some_and(|value| value == "true")
+    {
+        return Err(Error::Cors("credentials are not allowed".to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Checks the response to a preflight request for `req`
+pub fn check_preflight(response: &Response, req: &Request, origin: &Origin, credentials: bool) -> Result<()> {
+    if !response.is_ok() {
+        return Err(Error::Cors(format!("preflight request failed with status {}", response.status)).into());
+    }
+
+    check_access(response, origin, credentials)?;
+
+    let methods = header_values(response, "Access-Control-Allow-Methods");
+    let wildcard = |values: &[String]| !credentials && values.iter().any(|v| v == "*");
+    if !is_safelisted_method(&req.method) && !wildcard(&methods) && !methods.iter().any(|m| *m == req.method) {
+        return Err(Error::Cors(format!("method {} is not allowed", req.method)).into());
+    }
+
+    let allowed_headers = header_values(response, "Access-Control-Allow-Headers");
+    if !wildcard(&allowed_headers) {
+        for name in unsafe_header_names(&req.headers) {
+            if !allowed_headers.iter().any(|h| h.eq_ignore_ascii_case(&name)) {
+                return Err(Error::Cors(format!("header {name} is not allowed")).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the headers of a CORS response that are neither safelisted nor exposed by the server
+pub fn filter_cors_headers(response: &mut Response) {
+    let exposed = header_values(response, "Access-Control-Expose-Headers");
+
+    let mut headers = Headers::new();
+    for (name, value) in response.headers.all() {
+        let lower = name.to_ascii_lowercase();
+        if SAFELISTED_RESPONSE_HEADERS.contains(&lower.as_str()) || exposed.iter().any(|e| e.eq_ignore_ascii_case(name))
+        {
+            headers.set(name.clone(), value.clone());
+        }
+    }
+
+    response.headers = headers;
+}
+
+/// Sends a request from a document at `document_url`, applying the same-origin policy and the CORS protocol.
+/// Forbidden request headers are removed before the request is sent. Redirects are followed by hand, so the checks
+/// apply to every hop: once a request has left the origin of the document, the response is a CORS or opaque
+/// response, also when it is redirected back.
+pub fn fetch(
+    fetcher: &Fetcher,
+    mut req: Request,
+    document_url: &Url,
+    mode: RequestMode,
+    credentials: CredentialsMode,
+) -> Result<FilteredResponse> {
+    req.uri = document_url.join(&req.uri)?.to_string();
+
+    let mut headers = Headers::new();
+    for (name, value) in req.headers.all() {
+        if !is_forbidden_request_header(name) {
+            headers.set(name.clone(), value.clone());
+        }
+    }
+    req.headers = headers;
+
+    let mut origin = document_url.origin();
+    let mut tainting = ResponseType::Basic;
+    let mut redirects = 0;
+
+    loop {
+        let url = Url::parse(&req.uri)?;
+        if url.origin() != origin && tainting == ResponseType::Basic {
+            tainting = match mode {
+                RequestMode::SameOrigin => {
+                    return Err(Error::Cors(format!("request to {url} is not same-origin")).into());
+                }
+                RequestMode::NoCors => ResponseType::Opaque,
+                RequestMode::Cors => ResponseType::Cors,
+            };
+        }
+
+        let mut response = fetch_hop(fetcher, &mut req, &origin, tainting, credentials)?;
+
+        let Some(next) = redirect(&req, &response)? else {
+            return Ok(match tainting {
+                ResponseType::Basic => FilteredResponse {
+                    response_type: ResponseType::Basic,
+                    response,
+                },
+                ResponseType::Cors => {
+                    filter_cors_headers(&mut response);
+                    FilteredResponse {
+                        response_type: ResponseType::Cors,
+                        response,
+                    }
+                }
+                // The response is sent, but nothing of it is exposed to the document
+                ResponseType::Opaque => FilteredResponse {
+                    response_type: ResponseType::Opaque,
+                    response: Response::new(),
+                },
+            });
+        };
+
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(Error::Cors("too many redirects".to_string()).into());
+        }
+
+        let next_url = Url::parse(&next.uri)?;
+        if tainting == ResponseType::Cors && (!next_url.username().is_empty() || next_url.password().is_some()) {
+            return Err(Error::Cors(format!("redirect to {next_url} includes credentials")).into());
+        }
+
+        // A redirect from another origin to yet another origin can't be attributed to the document anymore, so the
+        // rest of the chain is sent with an opaque origin, which is serialized as "null"
+        if next_url.origin() != url.origin() && url.origin() != origin {
+            origin = Origin::new_opaque();
+        }
+
+        req = next;
+    }
+}
+
+/// Sends one request of a redirect chain with the checks of its response tainting. The Origin header of an earlier
+/// CORS hop is replaced, and redirect responses have to pass the CORS check as well
+fn fetch_hop(
+    fetcher: &Fetcher,
+    req: &mut Request,
+    origin: &Origin,
+    tainting: ResponseType,
+    credentials: CredentialsMode,
+) -> Result<Response> {
+    // Same-origin credentials are only sent as long as the request hasn't left the origin of the document
+    let include = match credentials {
+        CredentialsMode::Omit => false,
+        CredentialsMode::SameOrigin => tainting == ResponseType::Basic,
+        CredentialsMode::Include => true,
+    };
+
+    match tainting {
+        ResponseType::Basic => fetcher.send(req, include),
+        ResponseType::Opaque => {
+            if !is_safelisted_method(&req.method) {
+                return Err(Error::Cors(format!("method {} is not allowed in no-cors mode", req.method)).into());
+            }
+
+            // Only the CORS-safelisted headers can be sent without asking the other origin first
+            let mut headers = Headers::new();
+            for (name, value) in req.headers.all() {
+                if is_safelisted_request_header(name, value) {
+                    headers.set(name.clone(), value.clone());
+                }
+            }
+            req.headers = headers;
+
+            fetcher.send(req, include)
+        }
+        ResponseType::Cors => {
+            let mut headers = Headers::new();
+            for (name, value) in req.headers.all() {
+                if !name.eq_ignore_ascii_case("Origin") {
+                    headers.set(name.clone(), value.clone());
+                }
+            }
+            req.headers = headers;
+
+            if needs_preflight(req) {
+                let preflight = fetcher.send(&preflight_request(req, origin), false)?;
+                check_preflight(&preflight, req, origin, include)?;
+            }
+
+            req.headers.set_str("Origin", &origin.ascii_serialization());
+            let response = fetcher.send(req, include)?;
+            check_access(&response, origin, include)?;
+
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    /// Starts a local http server that answers every connection with the next of the given responses. Returns the
+    /// url of the server and a receiver of the request heads it got
+    fn stub_server(responses: Vec<String>) -> (Url, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let (tx, rx) = channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+
+                let _ = tx.send(head);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, rx)
+    }
+
+    /// Starts a server for the document that redirects to a second server on another origin, which answers with
+    /// `response`. Returns the url of the document and the request heads of the second server
+    fn redirect_to_other_origin(response: &str) -> (Url, Receiver<String>) {
+        let (other, requests) = stub_server(vec![response.to_string()]);
+        let (document, _) = stub_server(vec![format!(
+            "HTTP/1.1 302 Found\r\nLocation: {other}secret\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )]);
+
+        (document, requests)
+    }
+
+    const SECRET: &str = "HTTP/1.1 200 OK\r\nX-Secret: 1\r\nContent-Length: 6\r\nConnection: close\r\n\r\nsecret";
+
+    fn fetch_redirect(document: &Url, mode: RequestMode) -> Result<FilteredResponse> {
+        let fetcher = Fetcher::new(document.clone());
+        let req = Request::new("GET", "/redirect", "HTTP/1.1");
+        fetch(&fetcher, req, document, mode, CredentialsMode::SameOrigin)
+    }
+
+    #[test]
+    fn same_origin_redirect_to_other_origin() {
+        // Without CORS headers the response of the other origin is not exposed
+        let (document, requests) = redirect_to_other_origin(SECRET);
+        assert!(fetch_redirect(&document, RequestMode::Cors).is_err());
+        let head = requests.recv().unwrap().to_ascii_lowercase();
+        assert!(head.starts_with("get /secret http/1.1"));
+        assert!(head.contains(&format!("origin: {}", document.origin().ascii_serialization())));
+
+        let (document, _) = redirect_to_other_origin(SECRET);
+        assert!(fetch_redirect(&document, RequestMode::SameOrigin).is_err());
+
+        let (document, _) = redirect_to_other_origin(SECRET);
+        let opaque = fetch_redirect(&document, RequestMode::NoCors).unwrap();
+        assert_eq!(opaque.response_type, ResponseType::Opaque);
+        assert!(opaque.response.body.is_empty());
+
+        // With CORS headers the response is a CORS response, with only the exposed headers
+        let (document, _) = redirect_to_other_origin(
+            "HTTP/1.1 200 OK\r\nAccess-Control-Allow-Origin: *\r\nX-Secret: 1\r\nContent-Length: 6\r\nConnection: close\r\n\r\nshared",
+        );
+        let cors = fetch_redirect(&document, RequestMode::Cors).unwrap();
+        assert_eq!(cors.response_type, ResponseType::Cors);
+        assert_eq!(cors.response.body, b"shared");
+        assert!(cors.response.headers.get_ignore_case("X-Secret").is_none());
+    }
+
+    #[test]
+    fn no_cors_headers() {
+        let (other, requests) = stub_server(vec![SECRET.to_string()]);
+        let document = Url::parse("http://example.com/").unwrap();
+        let fetcher = Fetcher::new(document.clone());
+
+        let mut req = Request::new("GET", other.as_str(), "HTTP/1.1");
+        req.headers.set_str("Accept", "text/plain");
+        req.headers.set_str("Content-Type", "application/json");
+        req.headers.set_str("X-Token", "secret");
+
+        let opaque = fetch(
+            &fetcher,
+            req,
+            &document,
+            RequestMode::NoCors,
+            CredentialsMode::SameOrigin,
+        )
+        .unwrap();
+        assert_eq!(opaque.response_type, ResponseType::Opaque);
+
+        // Only the safelisted headers are sent
+        let head = requests.recv().unwrap().to_ascii_lowercase();
+        assert!(head.contains("accept: text/plain"));
+        assert!(!head.contains("content-type"));
+        assert!(!head.contains("x-token"));
+    }
+
+    fn response(headers: &[(&str, &str)]) -> Response {
+        let mut response = Response::from(vec![]);
+        for (name, value) in headers {
+            response.headers.set_str(name, value);
+        }
+        response
+    }
+
+    #[test]
+    fn preflight() {
+        let origin = Url::parse("https://example.com/page").unwrap().origin();
+
+        let mut req = Request::new("GET", "https://api.example.org/data", "HTTP/1.1");
+        req.headers.set_str("Accept", "application/json");
+        req.headers.set_str("Content-Type", "text/plain; charset=utf-8");
+        assert!(!needs_preflight(&req));
+
+        req.headers.set_str("X-Token", "secret");
+        req.headers.set_str("Content-Type", "application/json");
+        assert!(needs_preflight(&req));
+
+        let preflight = preflight_request(&req, &origin);
+        assert_eq!(preflight.method, "OPTIONS");
+        assert_eq!(preflight.headers.get("Origin").unwrap(), "https://example.com");
+        assert_eq!(
+            preflight.headers.get("Access-Control-Request-Headers").unwrap(),
+            "content-type,x-token"
+        );
+
+        let allowed = response(&[
+            ("access-control-allow-origin", "https://example.com"),
+            ("access-control-allow-headers", "Content-Type, X-Token"),
+        ]);
+        assert!(check_preflight(&allowed, &req, &origin, false).is_ok());
+
+        let missing = response(&[
+            ("access-control-allow-origin", "*"),
+            ("access-control-allow-headers", "Content-Type"),
+        ]);
+        assert!(check_preflight(&missing, &req, &origin, false).is_err());
+
+        req.method = "PUT".to_string();
+        assert!(check_preflight(&allowed, &req, &origin, false).is_err());
+    }
+
+    #[test]
+    fn access() {
+        let origin = Url::parse("https://example.com").unwrap().origin();
+
+        let wildcard = response(&[("Access-Control-Allow-Origin", "*")]);
+        assert!(check_access(&wildcard, &origin, false).is_ok());
+        assert!(check_access(&wildcard, &origin, true).is_err());
+
+        let credentials = response(&[
+            ("Access-Control-Allow-Origin", "https://example.com"),
+            ("Access-Control-Allow-Credentials", "true"),
+        ]);
+        assert!(check_access(&credentials, &origin, true).is_ok());
+
+        let other = response(&[("Access-Control-Allow-Origin", "https://example.org")]);
+        assert!(check_access(&other, &origin, false).is_err());
+        assert!(check_access(&response(&[]), &origin, false).is_err());
+    }
+
+    #[test]
+    fn filtered_headers() {
+        let mut cors = response(&[
+            ("Content-Type", "text/plain"),
+            ("X-Secret", "1"),
+            ("X-Exposed", "2"),
+            ("Access-Control-Expose-Headers", "x-exposed"),
+        ]);
+        filter_cors_headers(&mut cors);
+
+        let mut names = cors.headers.all().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["Content-Type", "X-Exposed"]);
+
+        assert!(is_forbidden_request_header("Cookie"));
+        assert!(is_forbidden_request_header("Sec-Fetch-Mode"));
+        assert!(!is_forbidden_request_header("X-Token"));
+    }
+}
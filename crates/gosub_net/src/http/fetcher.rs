@@ -1,9 +1,21 @@
 use super::response::Response;
+use crate::http::headers::Headers;
 use crate::http::request::Request;
 use anyhow::bail;
 use gosub_shared::types::Result;
 use url::{ParseError, Url};
 
+/// Maximum number of redirects that are followed for a request (<https://fetch.spec.whatwg.org/#http-redirect-fetch>)
+pub const MAX_REDIRECTS: usize = 20;
+
+/// Headers that describe the body of a request, which are dropped when a redirect turns the request into a GET
+const REQUEST_BODY_HEADERS: [&str; 4] = [
+    "content-encoding",
+    "content-language",
+    "content-location",
+    "content-type",
+];
+
 pub struct Fetcher {
     base_url: Url,
     client: ureq::Agent,
@@ -13,7 +25,7 @@ impl Fetcher {
     pub fn new(base: Url) -> Self {
         Self {
             base_url: base,
-            client: ureq::Agent::new(),
+            client: no_redirects(),
         }
     }
 
@@ -21,9 +33,12 @@ impl Fetcher {
         let scheme = url.scheme();
 
         let resp = if scheme == "http" || scheme == "https" {
-            let response = self.client.get(url.as_str()).call()?;
+            let response = self.fetch(&Request::new("GET", url.as_str(), "HTTP/1.1"), true)?;
+            if response.status >= 400 {
+                bail!("{url}: status code {}", response.status);
+            }
 
-            response.try_into()?
+            response
         } else if scheme == "file" {
             let path = &url.as_str()[7..];
 
@@ -43,8 +58,72 @@ impl Fetcher {
         self.get_url(&url)
     }
 
-    pub fn get_req(&self, _url: &Request) {
-        todo!()
+    /// Sends the request and returns the response, also when its status is not successful. Redirects are
+    /// followed. Cookies that were set by earlier responses are only sent (and stored) when `credentials` is set.
+    pub fn fetch(&self, req: &Request, credentials: bool) -> Result<Response> {
+        let mut response = self.send(req, credentials)?;
+        let mut next = redirect(req, &response)?;
+
+        let mut redirects = 0;
+        while let Some(req) = next {
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                bail!("too many redirects");
+            }
+
+            response = self.send(&req, credentials)?;
+            next = redirect(&req, &response)?;
+        }
+
+        Ok(response)
+    }
+
+    /// Sends the request and returns the response without following redirects, so the caller can check every hop
+    /// of a redirect chain, like the CORS checks do. Cookies are only sent (and stored) when `credentials` is set.
+    pub fn send(&self, req: &Request, credentials: bool) -> Result<Response> {
+        let url = self.parse_url(&req.uri)?;
+
+        match url.scheme() {
+            "http" | "https" => {}
+            "file" if req.method == "GET" => return self.get_url(&url),
+            _ => bail!("Unsupported scheme"),
+        }
+
+        let anonymous;
+        let agent = if credentials {
+            &self.client
+        } else {
+            anonymous = no_redirects();
+            &anonymous
+        };
+
+        let mut request = agent.request(&req.method, url.as_str());
+        for (name, value) in req.headers.all() {
+            request = request.set(name, value);
+        }
+
+        if credentials && req.cookies.iter().next().is_some() {
+            let cookies = req
+                .cookies
+                .iter()
+                .map(|cookie| cookie.stripped().to_string())
+                .collect::<Vec<_>>();
+            request = request.set("Cookie", &cookies.join("; "));
+        }
+
+        let result = if req.body.is_empty() {
+            request.call()
+        } else {
+            request.send_bytes(&req.body)
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(response.try_into()?)
     }
 
     fn parse_url(&self, url: &str) -> Result<Url> {
@@ -57,3 +136,88 @@ impl Fetcher {
         Ok(parsed_url?)
     }
 }
+
+/// Redirects are followed by hand, so every hop can be checked
+fn no_redirects() -> ureq::Agent {
+    ureq::AgentBuilder::new().redirects(0).build()
+}
+
+/// Returns the request that follows the redirect of a response, or `None` when the response isn't a redirect
+/// (<https://fetch.spec.whatwg.org/#http-redirect-fetch>). Redirects to other schemes than http and https fail.
+pub fn redirect(req: &Request, response: &Response) -> Result<Option<Request>> {
+    if !matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+        return Ok(None);
+    }
+
+    let Some(location) = response.headers.get_ignore_case("Location") else {
+        return Ok(None);
+    };
+
+    let current = Url::parse(&response.url).or_else(|_| Url::parse(&req.uri))?;
+    let url = current.join(location)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("redirect to unsupported scheme: {}", url.scheme());
+    }
+
+    let mut next = Request::new(&req.method, url.as_str(), &req.version);
+    next.headers = req.headers.clone();
+    next.cookies = req.cookies.clone();
+    next.body = req.body.clone();
+
+    // A POST that is redirected with 301 or 302, and anything but a HEAD that is redirected with 303, becomes a GET
+    // without a body
+    if (matches!(response.status, 301 | 302) && req.method == "POST")
+        || (response.status == 303 && req.method != "GET" && req.method != "HEAD")
+    {
+        next.method = "GET".to_string();
+        next.body = vec![];
+
+        let mut headers = Headers::new();
+        for (name, value) in req.headers.all() {
+            if !REQUEST_BODY_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                headers.set(name.clone(), value.clone());
+            }
+        }
+        next.headers = headers;
+    }
+
+    Ok(Some(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect_response(status: u16, location: &str) -> Response {
+        let mut response = Response::new();
+        response.status = status;
+        response.url = "https://example.com/a/b".to_string();
+        response.headers.set_str("Location", location);
+        response
+    }
+
+    #[test]
+    fn redirects() {
+        let mut req = Request::new("POST", "https://example.com/a/b", "HTTP/1.1");
+        req.headers.set_str("Content-Type", "text/plain");
+        req.headers.set_str("X-Token", "1");
+        req.body = b"body".to_vec();
+
+        let next = redirect(&req, &redirect_response(307, "c")).unwrap().unwrap();
+        assert_eq!(next.uri, "https://example.com/a/c");
+        assert_eq!(next.method, "POST");
+        assert_eq!(next.body, b"body");
+
+        let next = redirect(&req, &redirect_response(302, "https://example.org/"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.uri, "https://example.org/");
+        assert_eq!(next.method, "GET");
+        assert!(next.body.is_empty());
+        assert!(next.headers.get("Content-Type").is_none());
+        assert_eq!(next.headers.get("X-Token").unwrap(), "1");
+
+        assert!(redirect(&req, &redirect_response(200, "c")).unwrap().is_none());
+        assert!(redirect(&req, &redirect_response(301, "file:///etc/passwd")).is_err());
+    }
+}
@@ -30,6 +30,14 @@ impl Headers {
         self.headers.get(key)
     }
 
+    /// Returns the value of the header, where the name is matched case-insensitively
+    pub fn get_ignore_case(&self, key: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
     /// Returns all the header entries. Note that there is no ordering in here!
     pub fn all(&self) -> &HashMap<String, String> {
         &self.headers
@@ -56,5 +64,7 @@ mod tests {
         headers.set_str("Content-Type", "text/html");
         assert_eq!(headers.get("Content-Type").unwrap(), "text/html");
         assert_eq!(headers.all().len(), 1);
+        assert_eq!(headers.get_ignore_case("content-type").unwrap(), "text/html");
+        assert!(headers.get("content-type").is_none());
     }
 }
@@ -8,6 +8,8 @@ pub struct Response {
     pub status: u16,
     pub status_text: String,
     pub version: String,
+    /// Url of the response, which differs from the requested url when redirects were followed
+    pub url: String,
     pub headers: Headers,
     pub cookies: HashMap<String, String>,
     pub body: Vec<u8>,
@@ -19,6 +21,7 @@ impl Response {
             status: 0,
             status_text: "".to_string(),
             version: "HTTP/1.1".to_string(),
+            url: String::new(),
             headers: Default::default(),
             cookies: Default::default(),
             body: vec![],
@@ -45,6 +48,7 @@ impl TryFrom<ureq::Response> for Response {
             status: value.status(),
             status_text: value.status_text().to_string(),
             version: value.http_version().to_string(),
+            url: value.get_url().to_string(),
             headers: get_headers(&value),
             body,
             cookies: Default::default(),
@@ -62,6 +66,7 @@ impl From<Vec<u8>> for Response {
             status: 200,
             status_text: "OK".to_string(),
             version: "HTTP/1.1".to_string(),
+            url: String::new(),
             headers: Default::default(),
             cookies: Default::default(),
             body,
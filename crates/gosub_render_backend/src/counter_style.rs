@@ -0,0 +1,168 @@
+//! Representations of counter values, as used by list markers and the `counter()` function of generated content
+//! (<https://www.w3.org/TR/css-counter-styles-3/>)
+
+const LOWER_GREEK: &str = "αβγδεζηθικλμνξοπρστυφχψω";
+
+const ROMAN: [(i32, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+/// A predefined counter style, or a string that is used as the marker of a list item
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum CounterStyle {
+    #[default]
+    Decimal,
+    DecimalLeadingZero,
+    LowerRoman,
+    UpperRoman,
+    LowerAlpha,
+    UpperAlpha,
+    LowerGreek,
+    Disc,
+    Circle,
+    Square,
+    DisclosureOpen,
+    DisclosureClosed,
+    None,
+    String(String),
+}
+
+impl CounterStyle {
+    /// Parses a `list-style-type` or the style argument of `counter()`. Counter styles that are not supported fall
+    /// back to decimal, like the spec asks for styles that are not defined
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "decimal" => Self::Decimal,
+            "decimal-leading-zero" => Self::DecimalLeadingZero,
+            "lower-roman" => Self::LowerRoman,
+            "upper-roman" => Self::UpperRoman,
+            "lower-alpha" | "lower-latin" => Self::LowerAlpha,
+            "upper-alpha" | "upper-latin" => Self::UpperAlpha,
+            "lower-greek" => Self::LowerGreek,
+            "disc" => Self::Disc,
+            "circle" => Self::Circle,
+            "square" => Self::Square,
+            "disclosure-open" => Self::DisclosureOpen,
+            "disclosure-closed" => Self::DisclosureClosed,
+            "none" => Self::None,
+            // Strings and keywords have the same value, an unknown keyword only consists of letters and dashes
+            _ if value.chars().all(|c| c.is_ascii_alphabetic() || c == '-') => Self::Decimal,
+            _ => Self::String(value.to_string()),
+        }
+    }
+
+    /// Returns the representation of a counter value in this style
+    pub fn format(&self, value: i32) -> String {
+        match self {
+            Self::Decimal => value.to_string(),
+            // The width includes the sign, so -5 is padded to -05
+            Self::DecimalLeadingZero if value < 0 => format!("{value:03}"),
+            Self::DecimalLeadingZero => format!("{value:02}"),
+            Self::LowerRoman => roman(value).unwrap_or_else(|| value.to_string()),
+            Self::UpperRoman => roman(value)
+                .map(|roman| roman.to_uppercase())
+                .unwrap_or_else(|| value.to_string()),
+            Self::LowerAlpha => alphabetic(value, &('a'..='z').collect::<Vec<_>>()),
+            Self::UpperAlpha => alphabetic(value, &('A'..='Z').collect::<Vec<_>>()),
+            Self::LowerGreek => alphabetic(value, &LOWER_GREEK.chars().collect::<Vec<_>>()),
+            Self::Disc => "•".to_string(),
+            Self::Circle => "◦".to_string(),
+            Self::Square => "▪".to_string(),
+            Self::DisclosureOpen => "▾".to_string(),
+            Self::DisclosureClosed => "▸".to_string(),
+            Self::None => String::new(),
+            Self::String(value) => value.clone(),
+        }
+    }
+
+    /// Returns the content of the marker of a list item with this style, the symbols are followed by a space and the
+    /// numbers by a period and a space
+    pub fn marker(&self, value: i32) -> String {
+        match self {
+            Self::Disc | Self::Circle | Self::Square | Self::DisclosureOpen | Self::DisclosureClosed => {
+                format!("{} ", self.format(value))
+            }
+            Self::None | Self::String(_) => self.format(value),
+            _ => format!("{}. ", self.format(value)),
+        }
+    }
+}
+
+/// Roman numerals are only defined from 1 to 3999
+fn roman(mut value: i32) -> Option<String> {
+    if !(1..4000).contains(&value) {
+        return None;
+    }
+
+    let mut result = String::new();
+
+    for (amount, numeral) in ROMAN {
+        while value >= amount {
+            result.push_str(numeral);
+            value -= amount;
+        }
+    }
+
+    Some(result)
+}
+
+/// Counts like spreadsheet columns: a, b, ..., z, aa, ab. Values below 1 can't be represented and use decimal
+fn alphabetic(value: i32, symbols: &[char]) -> String {
+    if value < 1 {
+        return value.to_string();
+    }
+
+    let base = symbols.len() as i32;
+    let mut value = value;
+    let mut result = Vec::new();
+
+    while value > 0 {
+        value -= 1;
+        result.push(symbols[(value % base) as usize]);
+        value /= base;
+    }
+
+    result.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_counter_values() {
+        assert_eq!(CounterStyle::parse("decimal").format(12), "12");
+        assert_eq!(CounterStyle::parse("decimal-leading-zero").format(7), "07");
+        assert_eq!(CounterStyle::parse("decimal-leading-zero").format(-5), "-05");
+        assert_eq!(CounterStyle::parse("decimal-leading-zero").format(-15), "-15");
+        assert_eq!(CounterStyle::parse("decimal-leading-zero").format(123), "123");
+        assert_eq!(CounterStyle::parse("lower-roman").format(1994), "mcmxciv");
+        assert_eq!(CounterStyle::parse("upper-roman").format(4), "IV");
+        assert_eq!(CounterStyle::parse("upper-roman").format(0), "0");
+        assert_eq!(CounterStyle::parse("lower-alpha").format(28), "ab");
+        assert_eq!(CounterStyle::parse("upper-latin").format(26), "Z");
+        assert_eq!(CounterStyle::parse("lower-greek").format(2), "β");
+        // Unsupported styles are shown as decimal numbers
+        assert_eq!(CounterStyle::parse("hebrew").format(3), "3");
+    }
+
+    #[test]
+    fn list_markers() {
+        assert_eq!(CounterStyle::parse("decimal").marker(3), "3. ");
+        assert_eq!(CounterStyle::parse("disc").marker(3), "• ");
+        assert_eq!(CounterStyle::parse("none").marker(3), "");
+        assert_eq!(CounterStyle::parse("→ ").marker(3), "→ ");
+    }
+}
@@ -29,7 +29,7 @@ pub trait LayoutTree<L: Layouter>: Sized {
 }
 
 pub trait Layouter: Sized + Clone {
-    type Cache: Default;
+    type Cache: LayoutCache;
     type Layout: Layout;
 
     type TextLayout: TextLayout;
@@ -39,6 +39,11 @@ pub trait Layouter: Sized + Clone {
     fn layout<LT: LayoutTree<Self>>(&self, tree: &mut LT, root: LT::NodeId, space: SizeU32) -> Result<()>;
 }
 
+pub trait LayoutCache: Default {
+    /// Drops any cached layout results for the node, so it will be laid out again on the next layout pass
+    fn invalidate(&mut self);
+}
+
 pub trait Layout: Default {
     /// Returns the relative upper left pos of the content box
     fn rel_pos(&self) -> Point;
@@ -116,17 +121,26 @@ pub trait Node {
     type Property: CssProperty;
 
     fn get_property(&self, name: &str) -> Option<&Self::Property>;
+
+    /// Returns a property of a pseudo-element that styles a part of the node, like `::first-line`
+    fn get_pseudo_property(&self, pseudo: &str, name: &str) -> Option<&Self::Property>;
+
     fn text_data(&self) -> Option<&str>;
 
     fn text_size(&self) -> Option<Size>;
 
+    /// Returns the value of an element attribute that influences the layout (like `colspan` and `rowspan`)
+    fn get_attribute(&self, name: &str) -> Option<&str>;
+
     /// This can only return true if the `Layout::COLLAPSE_INLINE` is set true for the layouter
     ///
     fn is_anon_inline_parent(&self) -> bool;
 }
 
 pub trait HasTextLayout<L: Layouter> {
-    fn set_text_layout(&mut self, layout: L::TextLayout);
+    /// Sets the glyph runs of a text node. Text that is split over multiple lines, or that has parts with a different
+    /// direction, consists of multiple runs
+    fn set_text_layouts(&mut self, layouts: Vec<L::TextLayout>);
 }
 
 pub trait TextLayout {
@@ -135,6 +149,9 @@ pub trait TextLayout {
 
     fn size(&self) -> Size;
 
+    /// Position of the run relative to the position of its text node
+    fn offset(&self) -> Point;
+
     fn glyphs(&self) -> &[Glyph];
 
     fn font(&self) -> &Self::Font;
@@ -143,9 +160,18 @@ pub trait TextLayout {
 
     fn coords(&self) -> &[i16];
 
-    fn decorations(&self) -> &Decoration;
+    /// Returns the decorations of the text, from the outermost decorating box to the innermost
+    fn decorations(&self) -> &[Decoration];
+
+    /// Returns true when the text is not painted, because its web font is still loading
+    fn invisible(&self) -> bool;
+
+    /// Returns the color of the run when it differs from the color of its text node, like in a `::first-line`
+    fn color(&self) -> Option<(f32, f32, f32, f32)>;
 }
 
+/// The text decoration lines of a decorating box (<https://www.w3.org/TR/css-text-decor-3/#line-decoration>). Text is
+/// painted with the decorations of all its decorating ancestors, each with their own style and color
 #[derive(Debug, Clone, Default)]
 pub struct Decoration {
     pub underline: bool,
@@ -154,14 +180,27 @@ pub struct Decoration {
 
     pub color: (f32, f32, f32, f32),
     pub style: DecorationStyle,
-    pub width: f32,
+    /// Thickness of the lines, `None` uses the thickness the font recommends
+    pub width: Option<f32>,
 
-    pub underline_offset: f32,
+    /// Distance of the underline from its default position, `None` uses the position the font recommends
+    pub underline_offset: Option<f32>,
+    /// The underline is placed below the descenders of the glyphs (`text-underline-position: under`)
+    pub underline_under: bool,
+    /// Underlines and overlines are interrupted where they would cross the glyphs (`text-decoration-skip-ink`)
+    pub skip_ink: bool,
 
     pub x_offset: f32,
 }
 
-#[derive(Debug, Clone, Default)]
+impl Decoration {
+    /// Returns true when the decoration draws any line
+    pub fn has_lines(&self) -> bool {
+        self.underline || self.overline || self.line_through
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum DecorationStyle {
     #[default]
     Solid,
@@ -170,3 +209,16 @@ pub enum DecorationStyle {
     Dashed,
     Wavy,
 }
+
+impl DecorationStyle {
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "solid" => Self::Solid,
+            "double" => Self::Double,
+            "dotted" => Self::Dotted,
+            "dashed" => Self::Dashed,
+            "wavy" => Self::Wavy,
+            _ => return None,
+        })
+    }
+}
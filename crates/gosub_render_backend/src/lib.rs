@@ -8,9 +8,12 @@ use gosub_shared::types::Result;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use smallvec::SmallVec;
 
+pub mod counter_style;
 pub mod geo;
 pub mod layout;
 pub mod svg;
+pub mod text;
+pub mod writing_mode;
 
 pub trait WindowHandle: HasDisplayHandle + HasWindowHandle + Send + Sync + Clone {}
 
@@ -74,6 +77,12 @@ pub trait Scene<B: RenderBackend> {
 
     fn debug_draw_simple_text(&mut self, text: &str, pos: Point, size: FP);
     fn apply_scene(&mut self, scene: &B::Scene, transform: Option<B::Transform>);
+    /// Draws a blurred, rounded rectangle, either outside or inside of a box
+    fn draw_shadow(&mut self, shadow: &RenderShadow<B>);
+    /// Applies another scene like `apply_scene`, but blurred with a gaussian blur with the given standard deviation
+    fn apply_blurred_scene(&mut self, scene: &B::Scene, transform: Option<B::Transform>, std_dev: FP);
+    /// Composites another scene as a group, with a transform, an opacity and a blend mode
+    fn apply_layer(&mut self, scene: &B::Scene, layer: &RenderLayer<B>);
     fn reset(&mut self);
 
     fn new() -> Self;
@@ -156,6 +165,119 @@ impl<B: RenderBackend> RenderText<B> {
     }
 }
 
+/// A shadow of a box. An outer shadow is only painted outside the box, an inset shadow only inside of it
+pub struct RenderShadow<B: RenderBackend> {
+    /// The box that casts the shadow
+    pub rect: B::Rect,
+    pub radius: Option<B::BorderRadius>,
+    /// The shape of the shadow before it is blurred
+    pub shadow: B::Rect,
+    pub shadow_radius: FP,
+    pub color: B::Color,
+    /// Standard deviation of the gaussian blur
+    pub std_dev: FP,
+    pub inset: bool,
+    pub transform: Option<B::Transform>,
+}
+
+impl<B: RenderBackend> RenderShadow<B> {
+    pub fn new(rect: B::Rect, shadow: B::Rect, color: B::Color) -> Self {
+        Self {
+            rect,
+            radius: None,
+            shadow,
+            shadow_radius: 0.0,
+            color,
+            std_dev: 0.0,
+            inset: false,
+            transform: None,
+        }
+    }
+
+    pub fn transform(&mut self, transform: B::Transform) {
+        self.transform = Some(transform);
+    }
+}
+
+/// How a layer is blended with what is below it (<https://www.w3.org/TR/compositing-1/#blending>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "normal" => Self::Normal,
+            "multiply" => Self::Multiply,
+            "screen" => Self::Screen,
+            "overlay" => Self::Overlay,
+            "darken" => Self::Darken,
+            "lighten" => Self::Lighten,
+            "color-dodge" => Self::ColorDodge,
+            "color-burn" => Self::ColorBurn,
+            "hard-light" => Self::HardLight,
+            "soft-light" => Self::SoftLight,
+            "difference" => Self::Difference,
+            "exclusion" => Self::Exclusion,
+            "hue" => Self::Hue,
+            "saturation" => Self::Saturation,
+            "color" => Self::Color,
+            "luminosity" => Self::Luminosity,
+            _ => return None,
+        })
+    }
+}
+
+/// A group of drawings that is composited as a whole, like an element with a transform, an opacity or a blend mode
+pub struct RenderLayer<B: RenderBackend> {
+    pub transform: Option<B::Transform>,
+    pub opacity: FP,
+    pub blend_mode: BlendMode,
+    /// The area the layer is clipped to, in the coordinates of the scene the layer is applied to
+    pub clip: Option<B::Rect>,
+    pub clip_radius: Option<B::BorderRadius>,
+}
+
+impl<B: RenderBackend> RenderLayer<B> {
+    pub fn new() -> Self {
+        Self {
+            transform: None,
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
+            clip: None,
+            clip_radius: None,
+        }
+    }
+
+    /// Whether the layer changes anything, a layer that doesn't can be painted directly
+    pub fn is_noop(&self) -> bool {
+        self.transform.is_none() && self.opacity >= 1.0 && self.blend_mode == BlendMode::Normal && self.clip.is_none()
+    }
+}
+
+impl<B: RenderBackend> Default for RenderLayer<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct RenderBorder<B: RenderBackend> {
     pub border: B::Border,
     pub transform: Option<B::Transform>,
@@ -512,7 +634,13 @@ pub trait Gradient<B: RenderBackend> {
         Self::new_radial_two_point(center, radius, center, radius, stops)
     }
 
+    /// Angles are in degrees, clockwise from the positive x-axis
     fn new_sweep(center: Point, start_angle: FP, end_angle: FP, stops: ColorStops<B>) -> Self;
+
+    /// Repeats the color stops outside of the gradient, instead of extending the first and last color
+    fn repeat(self) -> Self
+    where
+        Self: Sized;
 }
 
 pub trait Color {
@@ -0,0 +1,286 @@
+//! Processing of the text of text nodes, as described by the white space processing rules and text transformations
+//! of the CSS Text Module Level 3 (<https://www.w3.org/TR/css-text-3/>)
+
+/// Zero width space, used to add break opportunities to text that may break anywhere
+pub const ZWSP: char = '\u{200B}';
+
+/// Word joiner, used to remove the break opportunities from text that may not wrap
+pub const WORD_JOINER: char = '\u{2060}';
+
+/// The behavior of the `white-space` property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhiteSpace {
+    /// Sequences of spaces and tabs are collapsed into a single space
+    pub collapse: bool,
+    /// Segment breaks (newlines) are kept as forced line breaks
+    pub preserve_breaks: bool,
+    /// Lines may be wrapped at soft wrap opportunities
+    pub wrap: bool,
+}
+
+impl WhiteSpace {
+    pub const NORMAL: Self = Self {
+        collapse: true,
+        preserve_breaks: false,
+        wrap: true,
+    };
+
+    pub fn parse(value: &str) -> Self {
+        let (collapse, preserve_breaks, wrap) = match value {
+            "nowrap" => (true, false, false),
+            "pre" => (false, true, false),
+            "pre-wrap" | "break-spaces" => (false, true, true),
+            "pre-line" => (true, true, true),
+            _ => (true, false, true),
+        };
+
+        Self {
+            collapse,
+            preserve_breaks,
+            wrap,
+        }
+    }
+}
+
+impl Default for WhiteSpace {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// The case transformation of the `text-transform` property
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextTransform {
+    #[default]
+    None,
+    Uppercase,
+    Lowercase,
+    Capitalize,
+}
+
+impl TextTransform {
+    pub fn parse(value: &str) -> Self {
+        // `full-width` and `full-size-kana` can be combined with the case transformation, but aren't supported
+        value
+            .split_whitespace()
+            .find_map(|value| match value {
+                "uppercase" => Some(Self::Uppercase),
+                "lowercase" => Some(Self::Lowercase),
+                "capitalize" => Some(Self::Capitalize),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Collapses and removes white space according to phase I of the white space processing rules. Collapsible white
+/// space at the boundaries of the text is kept, since it can only be removed when the surrounding text is known.
+pub fn process_white_space(text: &str, white_space: WhiteSpace, tab_size: usize) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    if !white_space.collapse {
+        return expand_tabs(&text, tab_size);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let breaks = text.matches('\n').count();
+
+    // Spaces and tabs around a segment break are removed, the segment break itself becomes a space, unless
+    // segment breaks are preserved
+    for (idx, line) in text.split('\n').enumerate() {
+        if idx > 0 {
+            result.push(if white_space.preserve_breaks { '\n' } else { ' ' });
+        }
+
+        let mut line = line;
+        if idx > 0 {
+            line = line.trim_start_matches([' ', '\t']);
+        }
+        if idx < breaks {
+            line = line.trim_end_matches([' ', '\t']);
+        }
+
+        result.push_str(line);
+    }
+
+    let mut collapsed = String::with_capacity(result.len());
+    let mut last_was_space = false;
+
+    for c in result.chars() {
+        let c = if c == '\t' { ' ' } else { c };
+
+        if c == ' ' {
+            if last_was_space {
+                continue;
+            }
+            last_was_space = true;
+        } else {
+            last_was_space = c == '\n' && !white_space.preserve_breaks;
+        }
+
+        collapsed.push(c);
+    }
+
+    collapsed
+}
+
+/// Replaces the tabs with spaces up to the next tab stop
+fn expand_tabs(text: &str, tab_size: usize) -> String {
+    if !text.contains('\t') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut column = 0;
+
+    for c in text.chars() {
+        match c {
+            '\t' if tab_size > 0 => {
+                let spaces = tab_size - column % tab_size;
+                result.push_str(&" ".repeat(spaces));
+                column += spaces;
+            }
+            '\t' => {}
+            '\n' => {
+                result.push(c);
+                column = 0;
+            }
+            _ => {
+                result.push(c);
+                column += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// Applies the case transformation of `text-transform`
+pub fn transform_text(text: &str, transform: TextTransform) -> String {
+    match transform {
+        TextTransform::None => text.to_string(),
+        TextTransform::Uppercase => text.to_uppercase(),
+        TextTransform::Lowercase => text.to_lowercase(),
+        TextTransform::Capitalize => {
+            let mut result = String::with_capacity(text.len());
+            let mut word_start = true;
+
+            for c in text.chars() {
+                if word_start && c.is_alphanumeric() {
+                    result.extend(c.to_uppercase());
+                } else {
+                    result.push(c);
+                }
+
+                word_start = c.is_whitespace() || c == ZWSP || (word_start && !c.is_alphanumeric());
+            }
+
+            result
+        }
+    }
+}
+
+/// Adds a break opportunity between all characters of the words in the text, for `word-break: break-all`
+pub fn insert_break_opportunities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut prev: Option<char> = None;
+
+    for c in text.chars() {
+        if let Some(prev) = prev {
+            if is_word_char(prev) && is_word_char(c) {
+                result.push(ZWSP);
+            }
+        }
+
+        result.push(c);
+        prev = Some(c);
+    }
+
+    result
+}
+
+/// Removes the break opportunities between all characters of the text, for text that may not wrap (like
+/// `white-space: nowrap`) in a line that may. Forced line breaks are kept.
+pub fn remove_break_opportunities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() * 4);
+    let mut prev: Option<char> = None;
+
+    for c in text.chars() {
+        if prev.is_some_and(|prev| prev != '\n') && c != '\n' {
+            result.push(WORD_JOINER);
+        }
+
+        result.push(c);
+        prev = Some(c);
+    }
+
+    result
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && c != ZWSP
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapse_white_space() {
+        let text = "  hello \t  world \n   foo\n\nbar ";
+
+        assert_eq!(
+            process_white_space(text, WhiteSpace::parse("normal"), 8),
+            " hello world foo bar "
+        );
+        assert_eq!(
+            process_white_space(text, WhiteSpace::parse("pre-line"), 8),
+            " hello world\nfoo\n\nbar "
+        );
+    }
+
+    #[test]
+    fn preserve_white_space() {
+        let text = "a\tb  c\n\tx";
+
+        assert_eq!(
+            process_white_space(text, WhiteSpace::parse("pre"), 4),
+            "a   b  c\n    x"
+        );
+        assert_eq!(process_white_space(text, WhiteSpace::parse("pre-wrap"), 0), "ab  c\nx");
+    }
+
+    #[test]
+    fn white_space_values() {
+        assert!(!WhiteSpace::parse("nowrap").wrap);
+        assert!(!WhiteSpace::parse("pre").collapse);
+        assert!(WhiteSpace::parse("break-spaces").wrap);
+        assert_eq!(WhiteSpace::parse("unknown"), WhiteSpace::NORMAL);
+    }
+
+    #[test]
+    fn text_transforms() {
+        assert_eq!(transform_text("hello world", TextTransform::Uppercase), "HELLO WORLD");
+        assert_eq!(transform_text("Hello World", TextTransform::Lowercase), "hello world");
+        assert_eq!(
+            transform_text("hello (big) world", TextTransform::Capitalize),
+            "Hello (Big) World"
+        );
+        assert_eq!(TextTransform::parse("full-width uppercase"), TextTransform::Uppercase);
+    }
+
+    #[test]
+    fn break_opportunities() {
+        assert_eq!(insert_break_opportunities("ab c"), "a\u{200B}b c");
+        assert_eq!(
+            insert_break_opportunities(&insert_break_opportunities("abc")),
+            "a\u{200B}b\u{200B}c"
+        );
+    }
+
+    #[test]
+    fn no_break_opportunities() {
+        assert_eq!(remove_break_opportunities("a b"), "a\u{2060} \u{2060}b");
+        assert_eq!(remove_break_opportunities("a\nb"), "a\nb");
+    }
+}
@@ -0,0 +1,136 @@
+//! The direction of text and of the lines of a box, as described by the CSS Writing Modes Module Level 3
+//! (<https://www.w3.org/TR/css-writing-modes-3/>)
+
+/// Left-to-right mark, a strong left-to-right character without a glyph
+pub const LRM: char = '\u{200E}';
+/// Right-to-left mark, a strong right-to-left character without a glyph
+pub const RLM: char = '\u{200F}';
+/// Left-to-right override, all characters up to the end of the paragraph are treated as strong left-to-right
+pub const LRO: char = '\u{202D}';
+/// Right-to-left override, all characters up to the end of the paragraph are treated as strong right-to-left
+pub const RLO: char = '\u{202E}';
+
+/// The `writing-mode` property, which decides whether lines are horizontal or vertical, and in which direction the
+/// lines are stacked
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WritingMode {
+    #[default]
+    HorizontalTb,
+    VerticalRl,
+    VerticalLr,
+    SidewaysRl,
+    SidewaysLr,
+}
+
+impl WritingMode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "vertical-rl" | "tb-rl" | "tb" => Self::VerticalRl,
+            "vertical-lr" => Self::VerticalLr,
+            "sideways-rl" => Self::SidewaysRl,
+            "sideways-lr" => Self::SidewaysLr,
+            _ => Self::HorizontalTb,
+        }
+    }
+
+    /// Returns true when the lines are vertical
+    pub fn is_vertical(self) -> bool {
+        self != Self::HorizontalTb
+    }
+
+    /// Returns true when vertical lines are stacked from left to right
+    pub fn is_left_to_right(self) -> bool {
+        matches!(self, Self::VerticalLr | Self::SidewaysLr)
+    }
+}
+
+/// The `unicode-bidi` property, which decides how an element takes part in the bidirectional algorithm
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeBidi {
+    #[default]
+    Normal,
+    Embed,
+    Isolate,
+    BidiOverride,
+    IsolateOverride,
+    Plaintext,
+}
+
+impl UnicodeBidi {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "embed" => Self::Embed,
+            "isolate" => Self::Isolate,
+            "bidi-override" => Self::BidiOverride,
+            "isolate-override" => Self::IsolateOverride,
+            "plaintext" => Self::Plaintext,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Returns true when the directionality of the characters is overridden by the `direction` of the element
+    pub fn is_override(self) -> bool {
+        matches!(self, Self::BidiOverride | Self::IsolateOverride)
+    }
+}
+
+/// Returns the bidi control characters that are inserted before the text of a paragraph, so the bidi algorithm
+/// (<https://www.unicode.org/reports/tr9/>) resolves it with `direction` as its base direction. The algorithm would
+/// otherwise take the direction of the first strong character, which is what `unicode-bidi: plaintext` asks for.
+/// Left-to-right text without any right-to-left characters doesn't need a mark, as it can't be resolved differently
+pub fn bidi_prefix(text: &str, rtl: bool, unicode_bidi: UnicodeBidi) -> String {
+    if unicode_bidi == UnicodeBidi::Plaintext {
+        return String::new();
+    }
+
+    let mut prefix = String::new();
+
+    if rtl || text.chars().any(is_rtl_char) {
+        prefix.push(if rtl { RLM } else { LRM });
+    }
+
+    if unicode_bidi.is_override() {
+        prefix.push(if rtl { RLO } else { LRO });
+    }
+
+    prefix
+}
+
+/// Returns true for characters of right-to-left scripts like Hebrew and Arabic, which can change the base direction
+/// that the bidi algorithm detects
+pub fn is_rtl_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_set_the_base_direction() {
+        assert_eq!(bidi_prefix("hello", false, UnicodeBidi::Normal), "");
+        assert_eq!(bidi_prefix("שלום hello", false, UnicodeBidi::Normal), "\u{200E}");
+        assert_eq!(bidi_prefix("hello", true, UnicodeBidi::Isolate), "\u{200F}");
+        assert_eq!(
+            bidi_prefix("hello", true, UnicodeBidi::BidiOverride),
+            "\u{200F}\u{202E}"
+        );
+        assert_eq!(bidi_prefix("hello", false, UnicodeBidi::IsolateOverride), "\u{202D}");
+        assert_eq!(bidi_prefix("مرحبا", true, UnicodeBidi::Plaintext), "");
+    }
+
+    #[test]
+    fn writing_modes() {
+        assert!(WritingMode::parse("vertical-rl").is_vertical());
+        assert!(WritingMode::parse("vertical-lr").is_left_to_right());
+        assert!(!WritingMode::parse("vertical-rl").is_left_to_right());
+        assert_eq!(WritingMode::parse("horizontal-tb"), WritingMode::HorizontalTb);
+    }
+}
@@ -7,3 +7,4 @@ pub mod position;
 // pub mod macos_render_tree;
 pub mod render_tree;
 pub mod text;
+pub mod transform;
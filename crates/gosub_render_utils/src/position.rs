@@ -1,14 +1,25 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 use rstar::{RTree, RTreeObject, AABB};
 
 use crate::render_tree::RenderTree;
+use crate::transform::{around, element_transform, map_point};
 use gosub_render_backend::layout::{Layout, LayoutTree, Layouter};
-use gosub_render_backend::RenderBackend;
+use gosub_render_backend::{RenderBackend, Transform};
 use gosub_shared::node::NodeId;
-use gosub_shared::traits::css3::CssSystem;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem};
 use gosub_shared::traits::document::Document;
 
+/// The layer an element is painted in. Elements in the document layer scroll with the page, fixed elements don't
+/// move at all, and sticky elements are moved by the offset of their sticky root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Document,
+    Fixed,
+    Sticky(NodeId),
+}
+
 #[derive(Debug)]
 pub struct Element {
     id: NodeId,
@@ -18,41 +29,154 @@ pub struct Element {
     height: f32,
     radius: Option<(f32, f32, f32, f32)>,
     z_index: i32,
+    layer: Layer,
+    /// Maps points in the coordinates of the layer to the untransformed element, when the element or one of its
+    /// ancestors has a transform
+    inverse: Option<[f32; 6]>,
+    /// The bounding box of the element after it is transformed (x, y, width, height)
+    bounds: (f32, f32, f32, f32),
+}
+
+impl Element {
+    /// Checks if the point (in the coordinates of the layer) is inside the element
+    fn hit(&self, point: (f32, f32)) -> bool {
+        let (x, y) = match self.inverse {
+            Some(inverse) => map_point(inverse, point),
+            None => point,
+        };
+
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height && contains(self, x, y)
+    }
 }
 
 impl RTreeObject for Element {
     type Envelope = AABB<(f32, f32)>;
     fn envelope(&self) -> Self::Envelope {
-        let lower = (self.x, self.y);
-        let upper = (self.x + self.width, self.y + self.height);
+        let lower = (self.bounds.0, self.bounds.1);
+        let upper = (self.bounds.0 + self.bounds.2, self.bounds.1 + self.bounds.3);
         AABB::from_corners(lower, upper)
     }
 }
 
+/// The rectangles a sticky element is positioned with, all in document coordinates (x, y, width, height)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickyConstraint {
+    /// The border box of the element at its normal position
+    bounds: (f32, f32, f32, f32),
+    /// The content box of the containing block, the element never leaves it
+    containing_block: (f32, f32, f32, f32),
+    /// The padding box of the nearest scroll container, or `None` when that is the viewport
+    scroll_container: Option<(f32, f32, f32, f32)>,
+    /// The top, right, bottom and left insets, `None` for `auto`
+    insets: (Option<f32>, Option<f32>, Option<f32>, Option<f32>),
+}
+
+impl StickyConstraint {
+    /// Computes the offset of the element from its normal position, for the given scroll position and viewport size
+    pub fn offset(&self, scroll: (f32, f32), viewport: (f32, f32)) -> (f32, f32) {
+        let view = self
+            .scroll_container
+            .unwrap_or((scroll.0, scroll.1, viewport.0, viewport.1));
+
+        let (top, right, bottom, left) = self.insets;
+
+        let x = sticky_axis(
+            (self.bounds.0, self.bounds.2),
+            (self.containing_block.0, self.containing_block.2),
+            (view.0, view.2),
+            (left, right),
+        );
+
+        let y = sticky_axis(
+            (self.bounds.1, self.bounds.3),
+            (self.containing_block.1, self.containing_block.3),
+            (view.1, view.3),
+            (top, bottom),
+        );
+
+        (x, y)
+    }
+}
+
+/// Computes the sticky offset on a single axis. All ranges are (start, length), the insets are (start, end)
+fn sticky_axis(
+    bounds: (f32, f32),
+    containing: (f32, f32),
+    view: (f32, f32),
+    insets: (Option<f32>, Option<f32>),
+) -> f32 {
+    let mut offset = 0.0f32;
+
+    // The element sticks to the start edge of the view, but doesn't leave the containing block at the end
+    if let Some(start) = insets.0 {
+        let stuck = view.0 + start - bounds.0;
+        let limit = containing.0 + containing.1 - (bounds.0 + bounds.1);
+
+        offset = offset.max(stuck.min(limit));
+    }
+
+    // The element sticks to the end edge of the view, but doesn't leave the containing block at the start
+    if let Some(end) = insets.1 {
+        let stuck = view.0 + view.1 - end - (bounds.0 + bounds.1);
+        let limit = containing.0 - bounds.0;
+
+        if stuck < 0.0 {
+            offset = stuck.max(limit.min(0.0));
+        }
+    }
+
+    offset
+}
+
 #[derive(Default)]
 pub struct PositionTree {
     tree: RTree<Element>,
+    sticky: HashMap<NodeId, StickyConstraint>,
+    sticky_offsets: HashMap<NodeId, (f32, f32)>,
+    /// The scroll position of the viewport, positive when scrolled down or right
+    scroll: (f32, f32),
+}
+
+/// State that is passed down while walking the render tree
+#[derive(Clone, Copy)]
+struct Walk {
+    pos: (f32, f32),
+    z_index: i32,
+    layer: Layer,
+    /// The content box of the parent element
+    parent_content: (f32, f32, f32, f32),
+    scroll_container: Option<(f32, f32, f32, f32)>,
+    /// The transform of the closest transformed ancestor, including the transforms of its own ancestors
+    transform: Option<[f32; 6]>,
 }
 
 impl PositionTree {
     pub fn from_tree<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem>(
         from_tree: &RenderTree<L, D, C>,
     ) -> Self {
-        let mut tree = RTree::new();
+        let mut tree = Self::default();
 
         //TODO: we somehow need to get the border radius and a potential stacking context of the element here
 
-        Self::add_node_to_tree(from_tree, from_tree.root, 0, &mut tree, (0.0, 0.0));
+        let walk = Walk {
+            pos: (0.0, 0.0),
+            z_index: 0,
+            layer: Layer::Document,
+            parent_content: (0.0, 0.0, 0.0, 0.0),
+            scroll_container: None,
+            transform: None,
+        };
+
+        tree.add_node_to_tree::<B, L, D, C>(from_tree, from_tree.root, walk);
 
-        Self { tree }
+        tree
     }
 
-    fn add_node_to_tree<L: Layouter, D: Document<C>, C: CssSystem>(
+    fn add_node_to_tree<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem>(
+        &mut self,
         from_tree: &RenderTree<L, D, C>,
         id: NodeId,
-        z_index: i32,
-        tree: &mut RTree<Element>,
-        mut pos: (f32, f32),
+        mut walk: Walk,
     ) {
         let Some(layout) = from_tree.get_layout(id) else {
             return;
@@ -60,92 +184,279 @@ impl PositionTree {
 
         let p = layout.rel_pos();
 
-        pos.0 += p.x;
-        pos.1 += p.y;
+        walk.pos.0 += p.x;
+        walk.pos.1 += p.y;
 
         let size = layout.size();
+
+        let node = from_tree.get_node(id);
+        let property = |name: &str| {
+            node.and_then(|node| node.properties.get(name))
+                .and_then(|prop| prop.as_string())
+                .map(|s| s.to_string())
+        };
+
+        match (property("position").as_deref(), walk.layer) {
+            (Some("fixed"), _) => walk.layer = Layer::Fixed,
+            (Some("sticky"), Layer::Document) => {
+                let inset = |name: &str| {
+                    node.and_then(|node| node.properties.get(name))
+                        .filter(|prop| prop.as_string() != Some("auto"))
+                        .map(|prop| prop.unit_to_px())
+                };
+
+                self.sticky.insert(
+                    id,
+                    StickyConstraint {
+                        bounds: (walk.pos.0, walk.pos.1, size.width, size.height),
+                        containing_block: walk.parent_content,
+                        scroll_container: walk.scroll_container,
+                        insets: (inset("top"), inset("right"), inset("bottom"), inset("left")),
+                    },
+                );
+
+                walk.layer = Layer::Sticky(id);
+            }
+            _ => {}
+        }
+
+        let local = node.and_then(|node| {
+            element_transform::<B::Transform, C>(&node.properties, size.width, size.height)
+                .map(|transform| around(transform, walk.pos.0, walk.pos.1))
+        });
+
+        if let Some(local) = local {
+            let transform = match walk.transform {
+                Some(parent) => {
+                    let mut transform = B::Transform::from_matrix(parent);
+                    transform *= local;
+                    transform
+                }
+                None => local,
+            };
+
+            walk.transform = Some(transform.as_matrix());
+        }
+
+        let rect = (walk.pos.0, walk.pos.1, size.width, size.height);
+
+        let (inverse, bounds) = match walk.transform {
+            Some(matrix) => {
+                let transform = B::Transform::from_matrix(matrix);
+
+                // A transform that collapses the element to a line or a point also collapses its descendants, none of
+                // them can be hit
+                if transform.determinant().abs() <= f32::EPSILON {
+                    return;
+                }
+
+                (Some(transform.inverse().as_matrix()), transformed_bounds(matrix, rect))
+            }
+            None => (None, rect),
+        };
+
         let element = Element {
             id,
-            x: pos.0,
-            y: pos.1,
+            x: walk.pos.0,
+            y: walk.pos.1,
             width: size.width,
             height: size.height,
             radius: None, //TODO: border radius
-            z_index,
+            z_index: walk.z_index,
+            layer: walk.layer,
+            inverse,
+            bounds,
         };
 
-        tree.insert(element);
+        self.tree.insert(element);
+
+        let border = layout.border();
+        let padding = layout.padding();
+
+        // Layouts return the sides as (top, right, bottom, left) in (x1, y1, x2, y2)
+        let (top, right, bottom, left) = (
+            border.x1 + padding.x1,
+            border.y1 + padding.y1,
+            border.x2 + padding.x2,
+            border.y2 + padding.y2,
+        );
+
+        walk.parent_content = (
+            walk.pos.0 + left,
+            walk.pos.1 + top,
+            size.width - left - right,
+            size.height - top - bottom,
+        );
+
+        let clips = |name: &str| property(name).is_some_and(|overflow| overflow != "visible");
+
+        if clips("overflow-x") || clips("overflow-y") {
+            walk.scroll_container = Some((
+                walk.pos.0 + border.y2,
+                walk.pos.1 + border.x1,
+                size.width - border.y1 - border.y2,
+                size.height - border.x1 - border.x2,
+            ));
+        }
+
+        walk.z_index += 1;
 
         for child in from_tree.children(id).unwrap_or_default() {
-            Self::add_node_to_tree(from_tree, child, z_index + 1, tree, pos);
+            self.add_node_to_tree::<B, L, D, C>(from_tree, child, walk);
         }
     }
 
+    /// Updates the scroll position of the viewport, and moves the sticky elements accordingly
+    pub fn set_scroll(&mut self, scroll: (f32, f32), viewport: (f32, f32)) {
+        self.scroll = scroll;
+
+        self.sticky_offsets = self
+            .sticky
+            .iter()
+            .map(|(id, constraint)| (*id, constraint.offset(scroll, viewport)))
+            .collect();
+    }
+
+    /// Returns the current offset of a sticky element from its normal position
+    pub fn sticky_offset(&self, id: NodeId) -> (f32, f32) {
+        self.sticky_offsets.get(&id).copied().unwrap_or((0.0, 0.0))
+    }
+
+    /// Returns the ids of all sticky elements
+    pub fn sticky_elements(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.sticky.keys().copied()
+    }
+
+    /// Finds the element at the given point in the viewport. Fixed elements are above sticky elements, and sticky
+    /// elements are above the other elements of the document
     pub fn find(&self, x: f32, y: f32) -> Option<NodeId> {
-        let envelope = AABB::from_point((x, y));
+        let rank = |layer: Layer| match layer {
+            Layer::Document => 0,
+            Layer::Sticky(_) => 1,
+            Layer::Fixed => 2,
+        };
 
-        self.tree
-            .locate_in_envelope_intersecting(&envelope)
-            .filter(|e| {
-                let Some(radi) = e.radius else {
-                    return true;
-                };
+        let document = (x + self.scroll.0, y + self.scroll.1);
 
-                let middle = (e.x + e.width / 2.0, e.y + e.height / 2.0);
-
-                match middle.0.total_cmp(&x) {
-                    Ordering::Equal => true,
-                    Ordering::Less => {
-                        match middle.1.total_cmp(&y) {
-                            Ordering::Equal => true,
-                            // top left
-                            Ordering::Less => {
-                                if (e.x + radi.0) > x && (e.y + radi.0) > y {
-                                    return is_point_in_circle((e.x + radi.0, e.y + radi.0), radi.0, (x, y));
-                                }
-                                false
-                            }
-                            // top right
-                            Ordering::Greater => {
-                                if (e.x + e.width - radi.1) < x && (e.y + radi.1) < y {
-                                    return is_point_in_circle((e.x + radi.1, e.y + radi.1), radi.1, (x, y));
-                                }
-
-                                false
-                            }
-                        }
-                    }
-                    Ordering::Greater => {
-                        match middle.1.total_cmp(&y) {
-                            Ordering::Equal => true,
-                            // bottom left
-                            Ordering::Less => {
-                                if (e.x + radi.2) > x && (e.y + e.height - radi.2) < y {
-                                    return is_point_in_circle((e.x + radi.2, e.y + e.height - radi.2), radi.2, (x, y));
-                                }
-                                false
-                            }
-                            // bottom right
-                            Ordering::Greater => {
-                                if (e.x + e.width - radi.3) < x && (e.y + e.height - radi.3) < y {
-                                    return is_point_in_circle((e.x + radi.3, e.y + radi.3), radi.3, (x, y));
-                                }
-                                false
-                            }
-                        }
-                    }
+        let fixed = self.hits((x, y)).filter(|e| e.layer == Layer::Fixed);
+
+        let sticky = self.sticky.keys().flat_map(|root| {
+            let offset = self.sticky_offset(*root);
+            let point = (document.0 - offset.0, document.1 - offset.1);
+
+            self.hits(point).filter(move |e| e.layer == Layer::Sticky(*root))
+        });
+
+        let document = self.hits(document).filter(|e| e.layer == Layer::Document);
+
+        // The element on the highest layer with the highest z-index wins. Ties are not ordered by paint order, so the
+        // first element found is kept
+        fixed
+            .chain(sticky)
+            .chain(document)
+            .reduce(|a, b| {
+                let order = (rank(a.layer), a.z_index).cmp(&(rank(b.layer), b.z_index));
+                if order != Ordering::Less {
+                    a
+                } else {
+                    b
                 }
             })
-            .reduce(|a, b| if a.z_index >= b.z_index { a } else { b }) // >= because we just hope that the last-drawn element is last in the list
             .map(|e| e.id)
     }
 
+    /// Returns all elements that contain the point, in the coordinate space of their layer
+    fn hits(&self, (x, y): (f32, f32)) -> impl Iterator<Item = &Element> {
+        let envelope = AABB::from_point((x, y));
+
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .filter(move |e| e.hit((x, y)))
+    }
+
     pub fn get_node(&self, id: NodeId) -> Option<&Element> {
         self.tree.iter().find(|e| e.id == id)
     }
 
+    /// Returns the position of the element in the document at the current scroll position, taking the sticky offset
+    /// and the scroll position for fixed elements into account
     pub fn position(&self, id: NodeId) -> Option<(f32, f32)> {
-        self.get_node(id).map(|e| (e.x, e.y))
+        self.get_node(id).map(|e| match e.layer {
+            Layer::Document => (e.x, e.y),
+            Layer::Fixed => (e.x + self.scroll.0, e.y + self.scroll.1),
+            Layer::Sticky(root) => {
+                let offset = self.sticky_offset(root);
+                (e.x + offset.0, e.y + offset.1)
+            }
+        })
+    }
+
+    pub fn layer(&self, id: NodeId) -> Option<Layer> {
+        self.get_node(id).map(|e| e.layer)
+    }
+}
+
+/// Returns the bounding box of a rectangle (x, y, width, height) after it is transformed
+fn transformed_bounds(matrix: [f32; 6], (x, y, width, height): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+    let corners = [(x, y), (x + width, y), (x, y + height), (x + width, y + height)].map(|p| map_point(matrix, p));
+
+    let min_x = corners.iter().map(|p| p.0).fold(f32::INFINITY, f32::min);
+    let min_y = corners.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let max_x = corners.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max);
+    let max_y = corners.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+/// Checks if the point is inside the element, taking the border radius into account
+fn contains(e: &Element, x: f32, y: f32) -> bool {
+    let Some(radi) = e.radius else {
+        return true;
+    };
+
+    let middle = (e.x + e.width / 2.0, e.y + e.height / 2.0);
+
+    match middle.0.total_cmp(&x) {
+        Ordering::Equal => true,
+        Ordering::Less => {
+            match middle.1.total_cmp(&y) {
+                Ordering::Equal => true,
+                // top left
+                Ordering::Less => {
+                    if (e.x + radi.0) > x && (e.y + radi.0) > y {
+                        return is_point_in_circle((e.x + radi.0, e.y + radi.0), radi.0, (x, y));
+                    }
+                    false
+                }
+                // top right
+                Ordering::Greater => {
+                    if (e.x + e.width - radi.1) < x && (e.y + radi.1) < y {
+                        return is_point_in_circle((e.x + radi.1, e.y + radi.1), radi.1, (x, y));
+                    }
+
+                    false
+                }
+            }
+        }
+        Ordering::Greater => {
+            match middle.1.total_cmp(&y) {
+                Ordering::Equal => true,
+                // bottom left
+                Ordering::Less => {
+                    if (e.x + radi.2) > x && (e.y + e.height - radi.2) < y {
+                        return is_point_in_circle((e.x + radi.2, e.y + e.height - radi.2), radi.2, (x, y));
+                    }
+                    false
+                }
+                // bottom right
+                Ordering::Greater => {
+                    if (e.x + e.width - radi.3) < x && (e.y + e.height - radi.3) < y {
+                        return is_point_in_circle((e.x + radi.3, e.y + radi.3), radi.3, (x, y));
+                    }
+                    false
+                }
+            }
+        }
     }
 }
 
@@ -156,3 +467,134 @@ fn is_point_in_circle(circle_center: (f32, f32), circle_radius: f32, point: (f32
 
     distance <= circle_radius
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::tests::{assert_point, Matrix};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn element(id: usize, rect: (f32, f32, f32, f32), z_index: i32, layer: Layer) -> Element {
+        Element {
+            id: NodeId::from(id),
+            x: rect.0,
+            y: rect.1,
+            width: rect.2,
+            height: rect.3,
+            radius: None,
+            z_index,
+            layer,
+            inverse: None,
+            bounds: rect,
+        }
+    }
+
+    fn transformed(id: usize, rect: (f32, f32, f32, f32), z_index: i32, transform: Matrix) -> Element {
+        Element {
+            inverse: Some(transform.inverse().as_matrix()),
+            bounds: transformed_bounds(transform.as_matrix(), rect),
+            ..element(id, rect, z_index, Layer::Document)
+        }
+    }
+
+    #[test]
+    fn sticky_to_the_start() {
+        let axis = |view: f32| sticky_axis((100.0, 20.0), (0.0, 300.0), (view, 500.0), (Some(10.0), None));
+
+        // Not scrolled far enough to stick yet
+        assert_eq!(axis(50.0), 0.0);
+        assert_eq!(axis(150.0), 60.0);
+        // The element stays inside the containing block
+        assert_eq!(axis(400.0), 180.0);
+    }
+
+    #[test]
+    fn sticky_to_the_end() {
+        let axis = |view: f32| sticky_axis((500.0, 20.0), (400.0, 300.0), (view, 300.0), (None, Some(10.0)));
+
+        assert_eq!(axis(300.0), 0.0);
+        assert_eq!(axis(150.0), -80.0);
+        // The element doesn't move before the start of the containing block
+        assert_eq!(axis(0.0), -100.0);
+    }
+
+    #[test]
+    fn sticky_constraint_on_both_axes() {
+        let mut constraint = StickyConstraint {
+            bounds: (100.0, 100.0, 50.0, 20.0),
+            containing_block: (0.0, 0.0, 300.0, 300.0),
+            scroll_container: None,
+            insets: (Some(10.0), None, None, Some(5.0)),
+        };
+
+        assert_eq!(constraint.offset((0.0, 0.0), (800.0, 600.0)), (0.0, 0.0));
+        assert_eq!(constraint.offset((200.0, 150.0), (800.0, 600.0)), (105.0, 60.0));
+        assert_eq!(constraint.offset((400.0, 400.0), (800.0, 600.0)), (150.0, 180.0));
+
+        // The element sticks to its scroll container instead of the viewport
+        constraint.scroll_container = Some((0.0, 50.0, 300.0, 100.0));
+        assert_eq!(constraint.offset((400.0, 400.0), (800.0, 600.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn layers() {
+        let mut tree = PositionTree::default();
+        tree.tree
+            .insert(element(1, (0.0, 0.0, 100.0, 1000.0), 0, Layer::Document));
+        tree.tree
+            .insert(element(2, (0.0, 0.0, 100.0, 20.0), 1, Layer::Sticky(NodeId::from(2))));
+        tree.tree.insert(element(3, (80.0, 0.0, 20.0, 20.0), 1, Layer::Fixed));
+        tree.sticky.insert(
+            NodeId::from(2),
+            StickyConstraint {
+                bounds: (0.0, 0.0, 100.0, 20.0),
+                containing_block: (0.0, 0.0, 100.0, 1000.0),
+                scroll_container: None,
+                insets: (Some(0.0), None, None, None),
+            },
+        );
+
+        tree.set_scroll((0.0, 50.0), (100.0, 100.0));
+
+        // Fixed elements are above sticky elements, and sticky elements are above the document
+        assert_eq!(tree.find(10.0, 5.0), Some(NodeId::from(2)));
+        assert_eq!(tree.find(90.0, 5.0), Some(NodeId::from(3)));
+        assert_eq!(tree.find(10.0, 50.0), Some(NodeId::from(1)));
+
+        assert_eq!(tree.position(NodeId::from(2)), Some((0.0, 50.0)));
+        assert_eq!(tree.position(NodeId::from(3)), Some((80.0, 50.0)));
+        assert_eq!(tree.layer(NodeId::from(2)), Some(Layer::Sticky(NodeId::from(2))));
+    }
+
+    #[test]
+    fn nested_transforms() {
+        // The parent is rotated a quarter turn around its center, the child is translated inside of it, both
+        // combined the same way as the transforms from the render tree are
+        let parent = around(Matrix::rotate(FRAC_PI_2), 50.0, 50.0);
+        let child = parent * around(Matrix::translate(20.0, 0.0), 5.0, 5.0);
+
+        let mut tree = PositionTree::default();
+        tree.tree.insert(transformed(1, (0.0, 0.0, 100.0, 100.0), 0, parent));
+        tree.tree.insert(transformed(2, (0.0, 0.0, 10.0, 10.0), 1, child));
+
+        // The child ends up at (90, 20) - (100, 30)
+        let bounds = tree.get_node(NodeId::from(2)).unwrap().bounds;
+        assert_point((bounds.0, bounds.1), (90.0, 20.0));
+        assert_point((bounds.2, bounds.3), (10.0, 10.0));
+
+        assert_eq!(tree.find(95.0, 25.0), Some(NodeId::from(2)));
+        // The untransformed position of the child only hits the parent
+        assert_eq!(tree.find(5.0, 5.0), Some(NodeId::from(1)));
+        assert_eq!(tree.find(25.0, 5.0), Some(NodeId::from(1)));
+        assert_eq!(tree.find(150.0, 25.0), None);
+    }
+
+    #[test]
+    fn scaled_bounds() {
+        let matrix = Matrix::scale_xy(2.0, 0.5).as_matrix();
+        assert_eq!(
+            transformed_bounds(matrix, (10.0, 10.0, 20.0, 20.0)),
+            (20.0, 5.0, 40.0, 10.0)
+        );
+    }
+}
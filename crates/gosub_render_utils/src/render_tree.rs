@@ -1,10 +1,13 @@
 use gosub_html5::document::document_impl::TreeIterator;
-use gosub_render_backend::layout::{HasTextLayout, Layout, LayoutTree, Layouter, TextLayout};
+use gosub_render_backend::layout::{HasTextLayout, Layout, LayoutCache, LayoutTree, Layouter, TextLayout};
+use gosub_render_backend::text::{
+    insert_break_opportunities, process_white_space, transform_text, TextTransform, WhiteSpace,
+};
 use gosub_render_backend::{layout, Size};
 use gosub_shared::document::DocumentHandle;
 use gosub_shared::node::NodeId;
-use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem};
-use gosub_shared::traits::document::Document;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssStylesheet, CssSystem};
+use gosub_shared::traits::document::{Document, DocumentMutation};
 use gosub_shared::traits::node::NodeData;
 use gosub_shared::traits::node::{ElementDataType, Node as DocumentNode, TextDataType};
 use gosub_shared::types::Result;
@@ -12,6 +15,7 @@ use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 
 mod desc;
+mod generated;
 
 const INLINE_ELEMENTS: [&str; 31] = [
     "a", "abbr", "acronym", "b", "bdo", "big", "br", "button", "cite", "code", "dfn", "em", "i", "img", "input", "kbd",
@@ -19,6 +23,17 @@ const INLINE_ELEMENTS: [&str; 31] = [
     "tt", "var",
 ];
 
+/// Element attributes that are needed during layout or for generated content, and are therefore copied into the
+/// render tree
+const LAYOUT_ATTRIBUTES: [&str; 6] = ["colspan", "rowspan", "span", "start", "value", "placeholder"];
+
+/// Anonymous nodes get their ids from a separate range, so they never collide with nodes that are added to the
+/// document after the render tree has been generated
+const ANONYMOUS_NODE_ID_START: usize = usize::MAX / 2;
+
+/// Number of spaces a tab is wide when `tab-size` is not set
+const DEFAULT_TAB_SIZE: usize = 8;
+
 /// Map of all declared values for all nodes in the document
 #[derive(Debug)]
 pub struct RenderTree<L: Layouter, D: Document<C>, C: CssSystem> {
@@ -100,7 +115,7 @@ impl<L: Layouter, D: Document<C>, C: CssSystem> RenderTree<L, D, C> {
             nodes: HashMap::with_capacity(capacity),
             root: NodeId::root(),
             dirty: false,
-            next_id: NodeId::from(1u64),
+            next_id: NodeId::from(ANONYMOUS_NODE_ID_START),
             handle: None,
         };
 
@@ -113,6 +128,7 @@ impl<L: Layouter, D: Document<C>, C: CssSystem> RenderTree<L, D, C> {
                 parent: None,
                 name: String::from("root"),
                 namespace: None,
+                layout_attributes: Vec::new(),
                 data: RenderNodeData::Document,
                 cache: L::Cache::default(),
                 layout: L::Layout::default(),
@@ -223,74 +239,355 @@ impl<L: Layouter, D: Document<C>, C: CssSystem> RenderTree<L, D, C> {
         render_tree
     }
 
-    fn generate_from(&mut self, handle: DocumentHandle<D, C>) {
+    fn generate_from(&mut self, mut handle: DocumentHandle<D, C>) {
         // Iterate the complete document tree
 
         let iter_handle = DocumentHandle::clone(&handle);
 
         for current_node_id in TreeIterator::new(iter_handle) {
-            let doc = handle.get();
+            self.generate_node(&handle, current_node_id);
+        }
 
-            let node = doc.node_by_id(current_node_id).unwrap();
+        self.remove_unrenderable_nodes();
 
-            let Some(properties) = C::properties_from_node(node, doc.stylesheets(), handle.clone(), current_node_id)
-            else {
-                //we need to remove it  from the parent in the render tree and from the document
+        C::inheritance(self);
 
-                // todo!("unrenderable node");
+        self.process_text(self.root);
 
-                continue;
-            };
+        self.resolve_generated_content();
+
+        if L::COLLAPSE_INLINE {
+            self.collapse_inline(self.root);
+        }
 
-            let data = node.data();
+        // All mutations made while building the document are already part of the render tree
+        handle.get_mut().take_mutations();
+        self.handle = Some(handle);
 
-            let render_data = match RenderNodeData::from_node_data(data) {
-                ControlFlow::Ok(data) => data,
-                ControlFlow::Drop => continue,
-                ControlFlow::Error(e) => {
-                    log::error!("Failed to create node data for node: {current_node_id:?} ({e}");
-                    continue;
-                }
+        // self.print_tree();
+    }
+
+    /// Generates the render node for a single document node. Returns false when the node is not renderable.
+    fn generate_node(&mut self, handle: &DocumentHandle<D, C>, current_node_id: NodeId) -> bool {
+        let doc = handle.get();
+
+        let Some(node) = doc.node_by_id(current_node_id) else {
+            return false;
+        };
+
+        let Some(properties) = C::properties_from_node(node, doc.stylesheets(), handle.clone(), current_node_id) else {
+            //we need to remove it  from the parent in the render tree and from the document
+
+            // todo!("unrenderable node");
+
+            return false;
+        };
+
+        let data = node.data();
+
+        let render_data = match RenderNodeData::from_node_data(data) {
+            ControlFlow::Ok(data) => data,
+            ControlFlow::Drop => return false,
+            ControlFlow::Error(e) => {
+                log::error!("Failed to create node data for node: {current_node_id:?} ({e}");
+                return false;
+            }
+        };
+
+        let mut namespace: Option<String> = None;
+        let mut layout_attributes = Vec::new();
+
+        let name = match data {
+            NodeData::Element(data) => {
+                namespace = Some(data.namespace().to_string());
+                layout_attributes = LAYOUT_ATTRIBUTES
+                    .iter()
+                    .filter_map(|name| Some((name.to_string(), data.attributes().get(*name)?.clone())))
+                    .collect();
+                data.name().to_string()
+            }
+            NodeData::Text(_) => "#text".to_owned(),
+            NodeData::Document(_) => "#document".to_owned(),
+            _ => String::new(),
+        };
+
+        let render_tree_node = RenderTreeNode {
+            id: current_node_id,
+            properties,
+            children: node.children().to_vec(),
+            parent: node.parent_id(),
+            name, // We might be able to move node into render_tree_node
+            namespace,
+            layout_attributes,
+            data: render_data,
+            cache: L::Cache::default(),
+            layout: L::Layout::default(),
+        };
+
+        self.nodes.insert(current_node_id, render_tree_node);
+
+        self.generate_pseudo_elements(current_node_id);
+
+        true
+    }
+
+    /// Generates the render nodes for the given document node and all its renderable descendants
+    fn generate_subtree(&mut self, handle: &DocumentHandle<D, C>, node_id: NodeId) {
+        if !self.generate_node(handle, node_id) {
+            return;
+        }
+
+        let children = handle
+            .get()
+            .node_by_id(node_id)
+            .map(|node| node.children().to_vec())
+            .unwrap_or_default();
+
+        for child_id in children {
+            self.generate_subtree(handle, child_id);
+        }
+    }
+
+    /// Applies all mutations that have been made to the document since the render tree was generated or last
+    /// updated. Only the subtrees affected by a mutation are restyled, all other nodes keep their computed styles
+    /// and their layout caches.
+    ///
+    /// Returns true when the render tree has been changed.
+    pub fn apply_mutations(&mut self) -> bool {
+        let Some(mut handle) = self.handle.clone() else {
+            return false;
+        };
+
+        let mutations = handle.get_mut().take_mutations();
+        if mutations.is_empty() {
+            return false;
+        }
+
+        // `:has()` makes the style of a node depend on its descendants, so any change can affect every ancestor
+        let has_relational_selectors = {
+            let doc = handle.get();
+            doc.stylesheets().iter().any(|sheet| {
+                (0..sheet.rule_count())
+                    .filter_map(|index| sheet.rule_selector_text(index))
+                    .any(|selector| selector.contains(":has("))
+            })
+        };
+
+        let mut restyle_ids = Vec::new();
+
+        for mutation in mutations {
+            let id = match mutation {
+                _ if has_relational_selectors => self.root,
+                // Sibling combinators (`+` and `~`) can make the change affect the siblings of the node as well
+                DocumentMutation::NodeChanged(id) => handle
+                    .get()
+                    .node_by_id(id)
+                    .and_then(|node| node.parent_id())
+                    .unwrap_or(id),
+                DocumentMutation::ChildrenChanged(id) | DocumentMutation::NodeRemoved(id) => id,
+                // A new stylesheet can match any node, so everything needs to be restyled
+                DocumentMutation::StylesheetsChanged | DocumentMutation::DocumentChanged => self.root,
             };
 
-            let mut namespace: Option<String> = None;
+            if !restyle_ids.contains(&id) {
+                restyle_ids.push(id);
+            }
+        }
+
+        // There is no need to restyle a node when one of its ancestors is restyled as well
+        let restyle_ids = {
+            let doc = handle.get();
+
+            restyle_ids
+                .iter()
+                .copied()
+                .filter(|id| {
+                    let mut parent = doc.node_by_id(*id).and_then(|node| node.parent_id());
+                    while let Some(parent_id) = parent {
+                        if restyle_ids.contains(&parent_id) {
+                            return false;
+                        }
+                        parent = doc.node_by_id(parent_id).and_then(|node| node.parent_id());
+                    }
+                    true
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for id in restyle_ids {
+            self.restyle_subtree(&handle, id);
+        }
+
+        self.dirty = true;
+
+        true
+    }
 
-            let name = match data {
-                NodeData::Element(data) => {
-                    namespace = Some(data.namespace().to_string());
-                    data.name().to_string()
+    /// Regenerates and restyles the render nodes of the given document node and its descendants
+    fn restyle_subtree(&mut self, handle: &DocumentHandle<D, C>, id: NodeId) {
+        let (in_document, dom_parent) = {
+            let doc = handle.get();
+            match doc.node_by_id(id) {
+                Some(node) => (node.is_root() || node.parent_id().is_some(), node.parent_id()),
+                None => (false, None),
+            }
+        };
+
+        if !in_document {
+            self.remove_subtree(id);
+            return;
+        }
+
+        if !self.nodes.contains_key(&id) {
+            // The node has not been rendered before, so it must be generated by its parent. When the parent isn't
+            // rendered either, the node will not be rendered at all.
+            if let Some(parent_id) = dom_parent {
+                if self.nodes.contains_key(&parent_id) {
+                    self.restyle_subtree(handle, parent_id);
                 }
-                NodeData::Text(_) => "#text".to_owned(),
-                NodeData::Document(_) => "#document".to_owned(),
-                _ => String::new(),
+            }
+            return;
+        }
+
+        let Some(old_node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+
+        // The render parent can be an anonymous node, so we must keep it instead of the document parent
+        let parent = old_node.parent;
+        let was_inline = old_node.is_inline();
+
+        for node_id in self.get_child_node_ids(id) {
+            self.nodes.remove(&node_id);
+        }
+
+        self.generate_subtree(handle, id);
+        self.remove_unrenderable_nodes_from(id);
+
+        let Some(node) = self.nodes.get_mut(&id) else {
+            if let Some(parent_node) = parent.and_then(|parent_id| self.nodes.get_mut(&parent_id)) {
+                parent_node.children.retain(|child_id| *child_id != id);
+            }
+            self.invalidate_layout(parent);
+            return;
+        };
+
+        node.parent = parent;
+
+        if L::COLLAPSE_INLINE && node.is_inline() != was_inline {
+            // The node needs to be (un)wrapped in an anonymous inline node, which is done by the parent
+            if let Some(parent_id) = dom_parent {
+                self.restyle_subtree(handle, parent_id);
+                return;
+            }
+        }
+
+        C::inheritance_from(self, id);
+
+        self.process_text(id);
+
+        // Counters can be changed by the subtree, so the content of pseudo-elements after it can change as well
+        self.resolve_generated_content();
+
+        if L::COLLAPSE_INLINE {
+            self.collapse_inline(id);
+        }
+
+        self.invalidate_layout(parent);
+    }
+
+    /// Applies the white space processing and the text transformations to all text nodes in the subtree
+    fn process_text(&mut self, id: NodeId) {
+        for node_id in self.get_child_node_ids(id) {
+            let Some(node) = self.nodes.get_mut(&node_id) else {
+                continue;
             };
 
-            let render_tree_node = RenderTreeNode {
-                id: current_node_id,
-                properties,
-                children: node.children().to_vec(),
-                parent: node.parent_id(),
-                name, // We might be able to move node into render_tree_node
-                namespace,
-                data: render_data,
-                cache: L::Cache::default(),
-                layout: L::Layout::default(),
+            let property = |name: &str| {
+                node.properties
+                    .get(name)
+                    .and_then(|prop| prop.as_string())
+                    .map(|s| s.to_string())
             };
 
-            self.nodes.insert(current_node_id, render_tree_node);
+            let white_space = property("white-space")
+                .map(|value| WhiteSpace::parse(&value))
+                .unwrap_or_default();
+
+            let transform = property("text-transform")
+                .map(|value| TextTransform::parse(&value))
+                .unwrap_or_default();
+
+            let break_all = property("word-break").as_deref() == Some("break-all");
+
+            let tab_size = node
+                .properties
+                .get("tab-size")
+                .and_then(|prop| prop.as_number())
+                .map(|size| size as usize)
+                .unwrap_or(DEFAULT_TAB_SIZE);
+
+            let RenderNodeData::Text(data) = &mut node.data else {
+                continue;
+            };
+
+            let mut text = process_white_space(&data.text, white_space, tab_size);
+            text = transform_text(&text, transform);
+
+            if break_all {
+                text = insert_break_opportunities(&text);
+            }
+
+            data.text = text;
         }
+    }
 
-        self.next_id = handle.get().peek_next_id();
+    /// Removes the given node and all its descendants from the render tree
+    fn remove_subtree(&mut self, id: NodeId) {
+        let parent = self.nodes.get(&id).and_then(|node| node.parent);
 
-        self.remove_unrenderable_nodes();
+        for node_id in self.get_child_node_ids(id) {
+            self.delete_node(&node_id);
+        }
 
-        C::inheritance(self);
+        self.invalidate_layout(parent);
+    }
 
-        if L::COLLAPSE_INLINE {
-            self.collapse_inline(self.root);
+    /// Drops the cached layout of all nodes, so the whole tree is laid out again. This is needed when the fonts of the
+    /// text change, like when a web font has been loaded
+    pub fn invalidate_layouts(&mut self) {
+        for node in self.nodes.values_mut() {
+            node.cache.invalidate();
         }
+    }
 
-        // self.print_tree();
+    /// Drops the cached layout of the given node and all its ancestors, as their layout may depend on a changed child
+    fn invalidate_layout(&mut self, mut id: Option<NodeId>) {
+        while let Some(node_id) = id {
+            let Some(node) = self.nodes.get_mut(&node_id) else {
+                break;
+            };
+
+            node.cache.invalidate();
+            id = node.parent;
+        }
+    }
+
+    /// Removes all unrenderable nodes from the subtree starting at the given node
+    fn remove_unrenderable_nodes_from(&mut self, node_id: NodeId) {
+        let mut delete_list = Vec::new();
+
+        for id in self.get_child_node_ids(node_id) {
+            if let Some(prop) = self.get_property(id, "display") {
+                if prop.as_string() == Some("none") {
+                    delete_list.append(&mut self.get_child_node_ids(id));
+                }
+            }
+        }
+
+        for id in delete_list {
+            self.delete_node(&id);
+        }
     }
 
     /// Removes all unrenderable nodes from the render tree
@@ -357,6 +654,7 @@ impl<L: Layouter, D: Document<C>, C: CssSystem> RenderTree<L, D, C> {
                         parent: Some(node_id),
                         name: "#anonymous".to_string(),
                         namespace: None,
+                        layout_attributes: Vec::new(),
                         data: RenderNodeData::AnonymousInline,
                         cache: L::Cache::default(),
                         layout: L::Layout::default(),
@@ -437,6 +735,14 @@ impl<L: Layouter, D: Document<C>, C: CssSystem> gosub_shared::traits::render_tre
     fn get_children(&self, id: Self::NodeId) -> Option<Vec<Self::NodeId>> {
         self.get_children(id).cloned()
     }
+
+    fn get_parent(&self, id: Self::NodeId) -> Option<Self::NodeId> {
+        self.get_node(id).and_then(|node| node.parent)
+    }
+
+    fn apply_mutations(&mut self) -> bool {
+        self.apply_mutations()
+    }
 }
 
 impl<L: Layouter, C: CssSystem> gosub_shared::traits::render_tree::RenderTreeNode<C> for RenderTreeNode<L, C> {
@@ -485,14 +791,18 @@ impl<L: Layouter> Debug for RenderNodeData<L> {
 
 pub struct TextData<L: Layouter> {
     pub text: String,
-    pub layout: Option<L::TextLayout>,
+    /// The glyph runs of the text, empty when the text hasn't been laid out
+    pub layouts: Vec<L::TextLayout>,
 }
 
 impl<L: Layouter> Debug for TextData<L> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TextData")
             .field("text", &self.text)
-            .field("layout", &self.layout.as_ref().map(|x| x.dbg_layout()))
+            .field(
+                "layouts",
+                &self.layouts.iter().map(|x| x.dbg_layout()).collect::<Vec<_>>(),
+            )
             .finish()
     }
 }
@@ -508,9 +818,13 @@ impl<L: Layouter> RenderNodeData<L> {
         ControlFlow::Ok(match node {
             NodeData::Element(_) => RenderNodeData::Element,
             NodeData::Text(data) => {
-                let text = pre_transform_text(data.string_value());
+                // The white space is processed after the inheritance, when the `white-space` of the node is known
+                let text = data.string_value();
 
-                RenderNodeData::Text(Box::new(TextData { text, layout: None }))
+                RenderNodeData::Text(Box::new(TextData {
+                    text,
+                    layouts: Vec::new(),
+                }))
             }
             NodeData::Document(_) => RenderNodeData::Document,
             _ => return ControlFlow::Drop,
@@ -518,25 +832,6 @@ impl<L: Layouter> RenderNodeData<L> {
     }
 }
 
-fn pre_transform_text(text: String) -> String {
-    let mut new_text = String::with_capacity(text.len());
-
-    let mut last_was_ws = false;
-    for c in text.chars() {
-        if c.is_whitespace() {
-            if !last_was_ws {
-                new_text.push(' ');
-                last_was_ws = true;
-            }
-        } else {
-            new_text.push(c);
-            last_was_ws = false;
-        }
-    }
-
-    new_text
-}
-
 pub struct RenderTreeNode<L: Layouter, C: CssSystem> {
     pub id: NodeId,
     pub properties: C::PropertyMap,
@@ -544,6 +839,8 @@ pub struct RenderTreeNode<L: Layouter, C: CssSystem> {
     pub parent: Option<NodeId>,
     pub name: String,
     pub namespace: Option<String>,
+    /// Attributes of the element that are needed for layout (see `LAYOUT_ATTRIBUTES`)
+    pub layout_attributes: Vec<(String, String)>,
     pub data: RenderNodeData<L>,
     pub cache: L::Cache,
     pub layout: L::Layout,
@@ -584,6 +881,26 @@ impl<L: Layouter, C: CssSystem> RenderTreeNode<L, C> {
             return true;
         }
 
+        // Markers outside of the list item are positioned next to it, instead of being part of its first line
+        if self
+            .layout_attributes
+            .iter()
+            .any(|(name, value)| name == "marker" && value == "outside")
+        {
+            return false;
+        }
+
+        // Floats are taken out of the inline flow and laid out as blocks
+        let floated = self
+            .properties
+            .get("float")
+            .and_then(|prop| prop.as_string())
+            .is_some_and(|float| float != "none");
+
+        if floated {
+            return false;
+        }
+
         if let Some(d) = self.properties.get("display").and_then(|prop| {
             let val = prop.as_string()?;
 
@@ -601,6 +918,11 @@ impl<L: Layouter, C: CssSystem> RenderTreeNode<L, C> {
             return d;
         }
 
+        // Pseudo-elements are inline unless their `display` says otherwise
+        if self.name.starts_with("::") {
+            return true;
+        }
+
         let tag_name = self.name.to_lowercase();
 
         INLINE_ELEMENTS.contains(&tag_name.as_str())
@@ -608,9 +930,9 @@ impl<L: Layouter, C: CssSystem> RenderTreeNode<L, C> {
 }
 
 impl<L: Layouter, C: CssSystem> HasTextLayout<L> for RenderTreeNode<L, C> {
-    fn set_text_layout(&mut self, layout: L::TextLayout) {
+    fn set_text_layouts(&mut self, layouts: Vec<L::TextLayout>) {
         if let RenderNodeData::Text(text) = &mut self.data {
-            text.layout = Some(layout);
+            text.layouts = layouts;
         }
     }
 }
@@ -621,6 +943,11 @@ impl<L: Layouter, C: CssSystem> layout::Node for RenderTreeNode<L, C> {
     fn get_property(&self, name: &str) -> Option<&Self::Property> {
         self.properties.get(name)
     }
+
+    fn get_pseudo_property(&self, pseudo: &str, name: &str) -> Option<&Self::Property> {
+        self.properties.pseudo_element(pseudo)?.get(name)
+    }
+
     fn text_data(&self) -> Option<&str> {
         if let RenderNodeData::Text(text) = &self.data {
             Some(&text.text)
@@ -631,7 +958,18 @@ impl<L: Layouter, C: CssSystem> layout::Node for RenderTreeNode<L, C> {
 
     fn text_size(&self) -> Option<Size> {
         if let RenderNodeData::Text(text) = &self.data {
-            text.layout.as_ref().map(|layout| layout.size())
+            // The size of the box around all runs of the text
+            text.layouts.iter().fold(None, |size: Option<Size>, layout| {
+                let offset = layout.offset();
+                let run = layout.size();
+                let width = offset.x + run.width;
+                let height = offset.y + run.height;
+
+                Some(match size {
+                    Some(size) => Size::new(size.width.max(width), size.height.max(height)),
+                    None => Size::new(width, height),
+                })
+            })
         } else {
             None
         }
@@ -640,6 +978,13 @@ impl<L: Layouter, C: CssSystem> layout::Node for RenderTreeNode<L, C> {
     fn is_anon_inline_parent(&self) -> bool {
         matches!(self.data, RenderNodeData::AnonymousInline)
     }
+
+    fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.layout_attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
 }
 
 /// Generates a render tree for the given document based on its loaded stylesheets
@@ -703,3 +1048,63 @@ pub fn generate_render_tree<L: Layouter, D: Document<C>, C: CssSystem>(
 //     fn element_enter(&mut self, tree: &RenderTree, node: &RenderTreeNode, data: &ElementData);
 //     fn element_leave(&mut self, tree: &RenderTree, node: &RenderTreeNode, data: &ElementData);
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_css3::system::Css3System;
+    use gosub_html5::document::builder::DocumentBuilderImpl;
+    use gosub_html5::document::document_impl::DocumentImpl;
+    use gosub_html5::parser::Html5Parser;
+    use gosub_shared::byte_stream::{ByteStream, Encoding};
+    use gosub_shared::traits::document::DocumentBuilder;
+    use gosub_taffy::TaffyLayouter;
+
+    type Doc = DocumentImpl<Css3System>;
+
+    fn render_tree(html: &str) -> RenderTree<TaffyLayouter, Doc, Css3System> {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(html, Some(Encoding::UTF8));
+        stream.close();
+
+        let mut handle = DocumentBuilderImpl::new_document(None);
+        Html5Parser::<Doc, Css3System>::parse_document(&mut stream, handle.clone(), None).unwrap();
+        handle
+            .get_mut()
+            .add_stylesheet(Css3System::load_default_useragent_stylesheet());
+
+        RenderTree::from_document(handle)
+    }
+
+    fn property(tree: &RenderTree<TaffyLayouter, Doc, Css3System>, id: &str, name: &str) -> Option<String> {
+        let handle = tree.handle.clone().unwrap();
+        let node_id = handle.get().node_by_named_id(id).unwrap().id();
+
+        tree.nodes
+            .get(&node_id)
+            .and_then(|node| node.properties.get(name))
+            .and_then(|prop| prop.as_string())
+            .map(|value| value.to_string())
+    }
+
+    #[test]
+    fn apply_class_mutation() {
+        let mut tree = render_tree(
+            r#"<style>.foo { text-transform: uppercase } .foo + p { white-space: pre }</style>
+            <div id="a">a</div><p id="b">b</p>"#,
+        );
+        assert_ne!(property(&tree, "a", "text-transform").as_deref(), Some("uppercase"));
+        assert_ne!(property(&tree, "b", "white-space").as_deref(), Some("pre"));
+
+        let mut handle = tree.handle.clone().unwrap();
+        let mut node = handle.get().node_by_named_id("a").unwrap().clone();
+        node.get_element_data_mut().unwrap().add_attribute("class", "foo");
+        handle.get_mut().update_node(node);
+
+        assert!(tree.apply_mutations());
+        assert_eq!(property(&tree, "a", "text-transform").as_deref(), Some("uppercase"));
+        // The following sibling is matched by a sibling combinator, so it must be restyled as well
+        assert_eq!(property(&tree, "b", "white-space").as_deref(), Some("pre"));
+        assert!(!tree.apply_mutations());
+    }
+}
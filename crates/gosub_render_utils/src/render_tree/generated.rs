@@ -0,0 +1,516 @@
+//! Generated content: the boxes of the `::marker`, `::before` and `::after` pseudo-elements and their text, which can
+//! contain counters and quotes (<https://www.w3.org/TR/css-content-3/>, <https://www.w3.org/TR/css-lists-3/>)
+
+use gosub_render_backend::counter_style::CounterStyle;
+use gosub_render_backend::layout::{LayoutCache, Layouter};
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem, CssValue};
+use gosub_shared::traits::document::Document;
+
+use crate::render_tree::{RenderNodeData, RenderTree, RenderTreeNode, TextData};
+
+/// The counter that is incremented by every list item
+const LIST_ITEM: &str = "list-item";
+
+/// Quotes that are used when `quotes` is `auto`, for the outer and the nested levels
+const DEFAULT_QUOTES: [(&str, &str); 2] = [("\u{201C}", "\u{201D}"), ("\u{2018}", "\u{2019}")];
+
+/// The counters that are in scope while walking the tree in document order
+#[derive(Debug, Default)]
+struct Counters {
+    /// Counter instances with their value and the depth of the element that created them, innermost last. An
+    /// instance is in scope for the element, its following siblings and their descendants
+    instances: Vec<(String, i32, usize)>,
+    /// Number of quotes that have been opened, but not closed yet
+    quote_depth: usize,
+}
+
+impl Counters {
+    /// Creates a new counter instance. A counter that was created by a previous sibling is replaced
+    fn reset(&mut self, name: &str, value: i32, depth: usize) {
+        if let Some(idx) = self.instances.iter().rposition(|(n, _, d)| n == name && *d == depth) {
+            self.instances.remove(idx);
+        }
+
+        self.instances.push((name.to_string(), value, depth));
+    }
+
+    /// Updates the innermost counter with the name, a counter that is not in scope is created first
+    fn update(&mut self, name: &str, depth: usize, update: impl FnOnce(i32) -> i32) {
+        match self.instances.iter_mut().rev().find(|(n, _, _)| n == name) {
+            Some((_, value, _)) => *value = update(*value),
+            None => self.instances.push((name.to_string(), update(0), depth)),
+        }
+    }
+
+    /// Value of the innermost counter, as used by `counter()`
+    fn value(&self, name: &str) -> i32 {
+        self.instances
+            .iter()
+            .rev()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, value, _)| *value)
+            .unwrap_or(0)
+    }
+
+    /// Values of all counters with the name from the outermost to the innermost, as used by `counters()`
+    fn values(&self, name: &str) -> Vec<i32> {
+        let values = self
+            .instances
+            .iter()
+            .filter(|(n, _, _)| n == name)
+            .map(|(_, value, _)| *value)
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            vec![0]
+        } else {
+            values
+        }
+    }
+
+    /// Ends the scope of the counters that were created by the children of an element at the depth
+    fn leave(&mut self, depth: usize) {
+        self.instances.retain(|(_, _, d)| *d <= depth);
+    }
+}
+
+impl<L: Layouter, D: Document<C>, C: CssSystem> RenderTree<L, D, C> {
+    /// Adds the boxes of the `::marker`, `::before` and `::after` pseudo-elements to an element. Their text is
+    /// resolved later by `resolve_generated_content`, as counters depend on the elements that come before them
+    pub(super) fn generate_pseudo_elements(&mut self, id: NodeId) {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+
+        if !node.is_element() {
+            return;
+        }
+
+        let is_list_item = computed_keyword::<C>(&mut node.properties, "display").as_deref() == Some("list-item");
+
+        let marker = node.properties.take_pseudo_element("marker");
+        let before = node
+            .properties
+            .take_pseudo_element("before")
+            .and_then(generates_box::<C>);
+        let after = node
+            .properties
+            .take_pseudo_element("after")
+            .and_then(generates_box::<C>);
+
+        // The placeholder text of a form control is shown while the control has no value
+        let has_placeholder = matches!(node.name.as_str(), "input" | "textarea")
+            && node.children.is_empty()
+            && layout_attribute(node, "placeholder").is_some_and(|text| !text.is_empty())
+            && !layout_attribute(node, "value").is_some_and(|value| !value.is_empty());
+
+        let placeholder = has_placeholder.then(|| node.properties.take_pseudo_element("placeholder"));
+
+        let mut leading = Vec::new();
+
+        if let Some(placeholder) = placeholder {
+            leading.push(self.insert_pseudo_element(id, "::placeholder", placeholder.unwrap_or_default()));
+        }
+
+        if is_list_item {
+            leading.push(self.insert_pseudo_element(id, "::marker", marker.unwrap_or_default()));
+        }
+
+        if let Some(before) = before {
+            leading.push(self.insert_pseudo_element(id, "::before", before));
+        }
+
+        let after = after.map(|after| self.insert_pseudo_element(id, "::after", after));
+
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.children.splice(0..0, leading);
+            node.children.extend(after);
+        }
+    }
+
+    /// Inserts a pseudo-element box with an empty text node for its content
+    fn insert_pseudo_element(&mut self, parent: NodeId, name: &str, properties: C::PropertyMap) -> NodeId {
+        let id = self.next_id;
+        let text_id = id.next();
+        self.next_id = text_id.next();
+
+        self.nodes.insert(
+            id,
+            RenderTreeNode {
+                id,
+                properties,
+                children: vec![text_id],
+                parent: Some(parent),
+                name: name.to_string(),
+                namespace: None,
+                layout_attributes: Vec::new(),
+                data: RenderNodeData::Element,
+                cache: L::Cache::default(),
+                layout: L::Layout::default(),
+            },
+        );
+
+        self.nodes.insert(
+            text_id,
+            RenderTreeNode {
+                id: text_id,
+                properties: C::PropertyMap::default(),
+                children: Vec::new(),
+                parent: Some(id),
+                name: "#text".to_string(),
+                namespace: None,
+                layout_attributes: Vec::new(),
+                data: RenderNodeData::Text(Box::new(TextData {
+                    text: String::new(),
+                    layouts: Vec::new(),
+                })),
+                cache: L::Cache::default(),
+                layout: L::Layout::default(),
+            },
+        );
+
+        id
+    }
+
+    /// Fills in the text of all pseudo-elements. The whole tree is walked in document order, as a counter can be
+    /// changed by any element before the pseudo-element
+    pub(super) fn resolve_generated_content(&mut self) {
+        let mut counters = Counters::default();
+
+        self.resolve_content(self.root, &mut counters, 0);
+    }
+
+    fn resolve_content(&mut self, id: NodeId, counters: &mut Counters, depth: usize) {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+
+        match node.data {
+            RenderNodeData::Text(_) => return,
+            RenderNodeData::AnonymousInline => {
+                // Anonymous boxes are not part of the document, their children are siblings of the anonymous box
+                for child_id in node.children.clone() {
+                    self.resolve_content(child_id, counters, depth);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        update_counters(node, counters, depth);
+
+        if node.name.starts_with("::") {
+            self.fill_pseudo_element(id, counters);
+        }
+
+        let children = self
+            .nodes
+            .get(&id)
+            .map(|node| node.children.clone())
+            .unwrap_or_default();
+
+        for child_id in children {
+            self.resolve_content(child_id, counters, depth + 1);
+        }
+
+        counters.leave(depth);
+    }
+
+    /// Sets the text of a pseudo-element to its resolved content, and invalidates its layout when it changed
+    fn fill_pseudo_element(&mut self, id: NodeId, counters: &mut Counters) {
+        let Some(node) = self.nodes.get(&id) else {
+            return;
+        };
+
+        let parent = node.parent.and_then(|parent_id| self.nodes.get(&parent_id));
+
+        let mut content = node
+            .properties
+            .get("content")
+            .filter(|prop| !prop.is_none() && prop.as_string() != Some("normal"))
+            .map(|prop| resolve_value::<C>(&prop.as_values(), &node.properties, counters))
+            .unwrap_or_default();
+
+        if node.name == "::placeholder" {
+            content = Content {
+                text: parent
+                    .and_then(|parent| layout_attribute(parent, "placeholder"))
+                    .unwrap_or_default()
+                    .to_string(),
+                image: None,
+            };
+        }
+
+        let mut outside = false;
+
+        if node.name == "::marker" {
+            let keyword = |name: &str| {
+                parent
+                    .and_then(|parent| parent.properties.get(name))
+                    .and_then(|prop| prop.as_string())
+            };
+
+            outside = keyword("list-style-position") != Some("inside");
+
+            if !node
+                .properties
+                .get("content")
+                .is_some_and(|prop| prop.as_string() != Some("normal"))
+            {
+                let image = parent
+                    .and_then(|parent| parent.properties.get("list-style-image"))
+                    .and_then(|prop| prop.as_function())
+                    .filter(|(name, _)| *name == "url")
+                    .and_then(|(_, args)| args.first()?.as_string().map(|url| url.to_string()));
+
+                content = match image {
+                    Some(image) => Content {
+                        text: String::new(),
+                        image: Some(image),
+                    },
+                    None => Content {
+                        text: CounterStyle::parse(keyword("list-style-type").unwrap_or("disc"))
+                            .marker(counters.value(LIST_ITEM)),
+                        image: None,
+                    },
+                };
+            }
+        }
+
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+
+        let mut changed = set_layout_attribute(node, "src", content.image);
+        changed |= set_layout_attribute(node, "marker", outside.then(|| "outside".to_string()));
+
+        let Some(text_id) = node.children.first().copied() else {
+            return;
+        };
+
+        let Some(RenderNodeData::Text(data)) = self.nodes.get_mut(&text_id).map(|node| &mut node.data) else {
+            return;
+        };
+
+        let old_text = std::mem::replace(&mut data.text, content.text);
+
+        // The content is processed like the text of the document
+        self.process_text(text_id);
+
+        if let Some(RenderNodeData::Text(data)) = self.nodes.get(&text_id).map(|node| &node.data) {
+            changed |= data.text != old_text;
+        }
+
+        if changed {
+            self.invalidate_layout(Some(text_id));
+        }
+    }
+}
+
+/// The resolved value of the `content` property
+#[derive(Debug, Default)]
+struct Content {
+    text: String,
+    /// Url of an image that replaces the text
+    image: Option<String>,
+}
+
+/// Resolves the strings, counters and quotes of a `content` value
+fn resolve_value<C: CssSystem>(values: &[C::Value], props: &C::PropertyMap, counters: &mut Counters) -> Content {
+    let mut content = Content::default();
+
+    for value in values {
+        if let Some(list) = value.as_list() {
+            // Functions like `attr()` are resolved into a nested list
+            let nested = resolve_value::<C>(&list, props, counters);
+            content.text.push_str(&nested.text);
+            content.image = content.image.or(nested.image);
+        } else if let Some((name, args)) = value.as_function() {
+            let args = args
+                .split(|arg| arg.is_comma())
+                .map(|arg| arg.first().and_then(|value| value.as_string()).unwrap_or_default())
+                .collect::<Vec<_>>();
+
+            match (name, args.as_slice()) {
+                ("counter", [counter, style @ ..]) => {
+                    let style = CounterStyle::parse(style.first().copied().unwrap_or("decimal"));
+                    content.text.push_str(&style.format(counters.value(counter)));
+                }
+                ("counters", [counter, separator, style @ ..]) => {
+                    let style = CounterStyle::parse(style.first().copied().unwrap_or("decimal"));
+                    let values = counters.values(counter).into_iter().map(|value| style.format(value));
+                    content.text.push_str(&values.collect::<Vec<_>>().join(separator));
+                }
+                ("url", [url, ..]) => content.image = Some(url.to_string()),
+                _ => {}
+            }
+        } else if let Some(number) = value.as_number() {
+            content.text.push_str(&number.to_string());
+        } else if let Some(value) = value.as_string() {
+            match value {
+                "open-quote" => {
+                    content.text.push_str(&quote::<C>(props, counters.quote_depth, true));
+                    counters.quote_depth += 1;
+                }
+                "close-quote" => {
+                    counters.quote_depth = counters.quote_depth.saturating_sub(1);
+                    content.text.push_str(&quote::<C>(props, counters.quote_depth, false));
+                }
+                "no-open-quote" => counters.quote_depth += 1,
+                "no-close-quote" => counters.quote_depth = counters.quote_depth.saturating_sub(1),
+                _ => content.text.push_str(value),
+            }
+        }
+    }
+
+    content
+}
+
+/// Returns the opening or closing quote for a nesting level, from the pairs of strings in `quotes`. The last pair is
+/// used for all deeper levels
+fn quote<C: CssSystem>(props: &C::PropertyMap, depth: usize, open: bool) -> String {
+    let values = props.get("quotes").map(|prop| prop.as_values()).unwrap_or_default();
+
+    if values
+        .first()
+        .is_some_and(|value| value.is_none() || value.as_string() == Some("none"))
+    {
+        return String::new();
+    }
+
+    let pairs = values
+        .chunks_exact(2)
+        .filter_map(|pair| Some((pair[0].as_string()?, pair[1].as_string()?)))
+        .collect::<Vec<_>>();
+
+    let (open_quote, close_quote) = match pairs.get(depth).or(pairs.last()) {
+        Some(pair) => *pair,
+        None => DEFAULT_QUOTES[depth.min(DEFAULT_QUOTES.len() - 1)],
+    };
+
+    if open { open_quote } else { close_quote }.to_string()
+}
+
+/// Computes a property and returns its keyword
+fn computed_keyword<C: CssSystem>(props: &mut C::PropertyMap, name: &str) -> Option<String> {
+    let prop = props.get_mut(name)?;
+    prop.compute_value();
+    prop.as_string().map(|value| value.to_string())
+}
+
+/// Returns the properties of a `::before` or `::after` pseudo-element when it generates a box, which it only does
+/// when it has content
+fn generates_box<C: CssSystem>(mut props: C::PropertyMap) -> Option<C::PropertyMap> {
+    if computed_keyword::<C>(&mut props, "display").as_deref() == Some("none") {
+        return None;
+    }
+
+    let prop = props.get_mut("content")?;
+    prop.compute_value();
+
+    if prop.is_none() || matches!(prop.as_string(), Some("none" | "normal")) {
+        return None;
+    }
+
+    Some(props)
+}
+
+/// Applies the `counter-reset`, `counter-increment` and `counter-set` of an element, in that order
+fn update_counters<L: Layouter, C: CssSystem>(node: &RenderTreeNode<L, C>, counters: &mut Counters, depth: usize) {
+    let attribute = |name: &str| {
+        node.layout_attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .and_then(|(_, value)| value.trim().parse::<i32>().ok())
+    };
+
+    let is_list_item = node
+        .properties
+        .get("display")
+        .and_then(|prop| prop.as_string())
+        .is_some_and(|display| display == "list-item");
+
+    for (name, value) in counter_list::<C>(&node.properties, "counter-reset") {
+        // The numbering of an ordered list starts at its `start` attribute
+        let start = match node.name.as_str() {
+            "ol" if name == LIST_ITEM => attribute("start").map_or(0, |start| start.saturating_sub(1)),
+            _ => 0,
+        };
+
+        counters.reset(&name, value.unwrap_or(start), depth);
+    }
+
+    let increments = counter_list::<C>(&node.properties, "counter-increment");
+
+    // List items increment the list-item counter, unless they increment it themselves
+    if is_list_item && !increments.iter().any(|(name, _)| name == LIST_ITEM) {
+        counters.update(LIST_ITEM, depth, |value| value.saturating_add(1));
+    }
+
+    for (name, value) in increments {
+        counters.update(&name, depth, |current| current.saturating_add(value.unwrap_or(1)));
+    }
+
+    for (name, value) in counter_list::<C>(&node.properties, "counter-set") {
+        counters.update(&name, depth, |_| value.unwrap_or(0));
+    }
+
+    // The `value` attribute of a list item sets its number
+    if let Some(value) = attribute("value").filter(|_| is_list_item) {
+        counters.update(LIST_ITEM, depth, |_| value);
+    }
+}
+
+/// Parses a list of counter names that can be followed by an integer, like `chapter section 2`
+fn counter_list<C: CssSystem>(props: &C::PropertyMap, name: &str) -> Vec<(String, Option<i32>)> {
+    let Some(prop) = props.get(name) else {
+        return Vec::new();
+    };
+
+    let mut counters: Vec<(String, Option<i32>)> = Vec::new();
+
+    for value in prop.as_values() {
+        if let Some(number) = value.as_number() {
+            if let Some((_, value)) = counters.last_mut() {
+                *value = Some(number as i32);
+            }
+        } else if let Some(name) = value.as_string().filter(|name| *name != "none") {
+            counters.push((name.to_string(), None));
+        }
+    }
+
+    counters
+}
+
+fn layout_attribute<L: Layouter, C: CssSystem>(node: &RenderTreeNode<L, C>, name: &str) -> Option<&str> {
+    node.layout_attributes
+        .iter()
+        .find(|(attr, _)| attr == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Sets or removes a layout attribute of a pseudo-element, returns true when it changed
+fn set_layout_attribute<L: Layouter, C: CssSystem>(
+    node: &mut RenderTreeNode<L, C>,
+    name: &str,
+    value: Option<String>,
+) -> bool {
+    let old = node.layout_attributes.iter().position(|(attr, _)| attr == name);
+
+    match (old, value) {
+        (Some(idx), Some(value)) if node.layout_attributes[idx].1 != value => {
+            node.layout_attributes[idx].1 = value;
+            true
+        }
+        (Some(idx), None) => {
+            node.layout_attributes.remove(idx);
+            true
+        }
+        (None, Some(value)) => {
+            node.layout_attributes.push((name.to_string(), value));
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,453 @@
+//! CSS transforms (<https://www.w3.org/TR/css-transforms-2/>). Only the 2D part of the transform functions is
+//! supported, the z components of 3D functions are ignored.
+
+use gosub_render_backend::geo::FP;
+use gosub_render_backend::Transform;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem, CssValue};
+
+/// Returns the transform of an element from its `translate`, `rotate`, `scale`, `transform` and `transform-origin`
+/// properties, in coordinates relative to the top left corner of its border box. Returns `None` when the element
+/// isn't transformed
+pub fn element_transform<T: Transform, C: CssSystem>(properties: &C::PropertyMap, width: FP, height: FP) -> Option<T> {
+    let values = |name: &str| {
+        properties
+            .get(name)
+            .map(|prop| prop.as_values())
+            .filter(|values| !is_none(values))
+    };
+
+    let translate = values("translate").and_then(|values| parse_translate::<T, _>(&values, width, height));
+    let rotate = values("rotate").and_then(|values| parse_rotate::<T, _>(&values));
+    let scale = values("scale").and_then(|values| parse_scale::<T, _>(&values));
+    let transform = values("transform").and_then(|values| parse_transform_list::<T, _>(&values, width, height));
+
+    let mut result: Option<T> = None;
+    for t in [translate, rotate, scale, transform].into_iter().flatten() {
+        result = Some(match result {
+            Some(mut result) => {
+                result *= t;
+                result
+            }
+            None => t,
+        });
+    }
+
+    let result = result?;
+
+    let (x, y) = values("transform-origin")
+        .and_then(|values| parse_origin(&values, width, height))
+        .unwrap_or((width / 2.0, height / 2.0));
+
+    Some(around(result, x, y))
+}
+
+/// Applies a transform around the given point instead of the origin
+pub fn around<T: Transform>(transform: T, x: FP, y: FP) -> T {
+    let mut result = T::translate(x, y);
+    result *= transform;
+    result *= T::translate(-x, -y);
+    result
+}
+
+/// Maps a point with a matrix as returned by `Transform::as_matrix`
+pub fn map_point(matrix: [FP; 6], (x, y): (FP, FP)) -> (FP, FP) {
+    let [a, b, c, d, e, f] = matrix;
+
+    (a * x + c * y + e, b * x + d * y + f)
+}
+
+fn is_none<V: CssValue>(values: &[V]) -> bool {
+    match values {
+        [value] => value.is_none() || value.as_string() == Some("none"),
+        _ => values.is_empty(),
+    }
+}
+
+/// Parses an angle and returns it in radians
+fn parse_angle<V: CssValue>(value: &V) -> Option<FP> {
+    if let Some((value, unit)) = value.as_unit() {
+        return match unit {
+            "deg" => Some(value.to_radians()),
+            "rad" => Some(value),
+            "grad" => Some((value * 0.9).to_radians()),
+            "turn" => Some(value * std::f32::consts::TAU),
+            _ => None,
+        };
+    }
+
+    value.as_number().filter(|num| *num == 0.0)
+}
+
+/// Parses a length or a percentage of `size`
+fn parse_length<V: CssValue>(value: &V, size: FP) -> Option<FP> {
+    if let Some(percent) = value.as_percentage() {
+        return Some(percent / 100.0 * size);
+    }
+
+    if value.as_unit().is_some() {
+        return Some(value.unit_to_px());
+    }
+
+    value.as_number().filter(|num| *num == 0.0)
+}
+
+/// Parses a number or a percentage as a factor
+fn parse_factor<V: CssValue>(value: &V) -> Option<FP> {
+    value
+        .as_percentage()
+        .map(|percent| percent / 100.0)
+        .or_else(|| value.as_number())
+}
+
+fn parse_translate<T: Transform, V: CssValue>(values: &[V], width: FP, height: FP) -> Option<T> {
+    let x = parse_length(values.first()?, width)?;
+    let y = match values.get(1) {
+        Some(value) => parse_length(value, height)?,
+        None => 0.0,
+    };
+
+    Some(T::translate(x, y))
+}
+
+fn parse_rotate<T: Transform, V: CssValue>(values: &[V]) -> Option<T> {
+    let angle = values.iter().find_map(parse_angle)?;
+
+    // Rotations around the x or y axis only project onto the plane as a scale, which is not supported
+    let axis = values.iter().filter_map(|value| value.as_string()).next();
+    if axis.is_some_and(|axis| axis != "z") {
+        return None;
+    }
+
+    Some(T::rotate(angle))
+}
+
+fn parse_scale<T: Transform, V: CssValue>(values: &[V]) -> Option<T> {
+    let x = parse_factor(values.first()?)?;
+    let y = match values.get(1) {
+        Some(value) => parse_factor(value)?,
+        None => x,
+    };
+
+    Some(T::scale_xy(x, y))
+}
+
+/// Parses a list of transform functions, the functions are applied from right to left
+fn parse_transform_list<T: Transform, V: CssValue>(values: &[V], width: FP, height: FP) -> Option<T> {
+    let mut result = T::IDENTITY;
+
+    for value in values {
+        let (name, args) = value.as_function()?;
+        let args = args.iter().filter(|arg| !arg.is_comma()).collect::<Vec<_>>();
+
+        result *= parse_transform_function::<T, V>(&name.to_ascii_lowercase(), &args, width, height)?;
+    }
+
+    Some(result)
+}
+
+fn parse_transform_function<T: Transform, V: CssValue>(name: &str, args: &[&V], width: FP, height: FP) -> Option<T> {
+    let length = |idx: usize, size: FP| args.get(idx).and_then(|arg| parse_length(*arg, size));
+    let factor = |idx: usize| args.get(idx).and_then(|arg| parse_factor(*arg));
+    let angle = |idx: usize| args.get(idx).and_then(|arg| parse_angle(*arg));
+
+    Some(match name {
+        "matrix" => {
+            let mut matrix = [0.0; 6];
+            for (idx, value) in matrix.iter_mut().enumerate() {
+                *value = args.get(idx)?.as_number()?;
+            }
+
+            T::from_matrix(matrix)
+        }
+        "translate" => T::translate(length(0, width)?, length(1, height).unwrap_or(0.0)),
+        "translate3d" => T::translate(length(0, width)?, length(1, height)?),
+        "translatex" => T::translate(length(0, width)?, 0.0),
+        "translatey" => T::translate(0.0, length(0, height)?),
+        "translatez" => T::IDENTITY,
+        "scale" => {
+            let x = factor(0)?;
+            T::scale_xy(x, factor(1).unwrap_or(x))
+        }
+        "scale3d" => T::scale_xy(factor(0)?, factor(1)?),
+        "scalex" => T::scale_xy(factor(0)?, 1.0),
+        "scaley" => T::scale_xy(1.0, factor(0)?),
+        "scalez" => T::IDENTITY,
+        "rotate" | "rotatez" => T::rotate(angle(0)?),
+        "skew" => T::skew_xy(angle(0)?.tan(), angle(1).unwrap_or(0.0).tan()),
+        "skewx" => T::skew_x(angle(0)?.tan()),
+        "skewy" => T::skew_y(angle(0)?.tan()),
+        _ => return None,
+    })
+}
+
+/// Parses `transform-origin` into a point relative to the top left corner of the border box
+fn parse_origin<V: CssValue>(values: &[V], width: FP, height: FP) -> Option<(FP, FP)> {
+    let mut x = None;
+    let mut y = None;
+
+    for (idx, value) in values.iter().take(2).enumerate() {
+        match value.as_string() {
+            Some("left") => x = Some(0.0),
+            Some("right") => x = Some(width),
+            Some("top") => y = Some(0.0),
+            Some("bottom") => y = Some(height),
+            // Center is the default for an axis that isn't given by another value
+            Some("center") => {}
+            // Lengths are horizontal in the first position and vertical in the second
+            _ if idx == 0 => x = Some(parse_length(value, width)?),
+            _ => y = Some(parse_length(value, height)?),
+        }
+    }
+
+    Some((x.unwrap_or(width / 2.0), y.unwrap_or(height / 2.0)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use gosub_css3::matcher::styling::{CssProperties, CssProperty};
+    use gosub_css3::stylesheet::CssValue as Value;
+    use gosub_css3::system::Css3System;
+    use gosub_render_backend::geo::Point;
+    use std::f32::consts::FRAC_PI_2;
+    use std::ops::{Mul, MulAssign};
+
+    /// A plain affine matrix laid out like `Transform::as_matrix`, so transforms can be tested without a render
+    /// backend
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub(crate) struct Matrix(pub [FP; 6]);
+
+    impl Mul for Matrix {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            let [a1, b1, c1, d1, e1, f1] = self.0;
+            let [a2, b2, c2, d2, e2, f2] = rhs.0;
+
+            Matrix([
+                a1 * a2 + c1 * b2,
+                b1 * a2 + d1 * b2,
+                a1 * c2 + c1 * d2,
+                b1 * c2 + d1 * d2,
+                a1 * e2 + c1 * f2 + e1,
+                b1 * e2 + d1 * f2 + f1,
+            ])
+        }
+    }
+
+    impl MulAssign for Matrix {
+        fn mul_assign(&mut self, rhs: Self) {
+            *self = *self * rhs;
+        }
+    }
+
+    impl Transform for Matrix {
+        const IDENTITY: Self = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        const FLIP_X: Self = Matrix([-1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        const FLIP_Y: Self = Matrix([1.0, 0.0, 0.0, -1.0, 0.0, 0.0]);
+
+        fn scale(s: FP) -> Self {
+            Self::scale_xy(s, s)
+        }
+
+        fn scale_xy(sx: FP, sy: FP) -> Self {
+            Matrix([sx, 0.0, 0.0, sy, 0.0, 0.0])
+        }
+
+        fn translate(x: FP, y: FP) -> Self {
+            Matrix([1.0, 0.0, 0.0, 1.0, x, y])
+        }
+
+        fn rotate(angle: FP) -> Self {
+            let (sin, cos) = angle.sin_cos();
+            Matrix([cos, sin, -sin, cos, 0.0, 0.0])
+        }
+
+        fn rotate_around(angle: FP, center: Point) -> Self {
+            around(Self::rotate(angle), center.x, center.y)
+        }
+
+        fn skew_x(angle: FP) -> Self {
+            Self::skew_xy(angle, 0.0)
+        }
+
+        fn skew_y(angle: FP) -> Self {
+            Self::skew_xy(0.0, angle)
+        }
+
+        fn skew_xy(angle_x: FP, angle_y: FP) -> Self {
+            Matrix([1.0, angle_y, angle_x, 1.0, 0.0, 0.0])
+        }
+
+        fn pre_scale(self, s: FP) -> Self {
+            self * Self::scale(s)
+        }
+
+        fn pre_scale_xy(self, sx: FP, sy: FP) -> Self {
+            self * Self::scale_xy(sx, sy)
+        }
+
+        fn pre_translate(self, x: FP, y: FP) -> Self {
+            self * Self::translate(x, y)
+        }
+
+        fn pre_rotate(self, angle: FP) -> Self {
+            self * Self::rotate(angle)
+        }
+
+        fn pre_rotate_around(self, angle: FP, center: Point) -> Self {
+            self * Self::rotate_around(angle, center)
+        }
+
+        fn then_scale(self, s: FP) -> Self {
+            Self::scale(s) * self
+        }
+
+        fn then_scale_xy(self, sx: FP, sy: FP) -> Self {
+            Self::scale_xy(sx, sy) * self
+        }
+
+        fn then_translate(self, x: FP, y: FP) -> Self {
+            Self::translate(x, y) * self
+        }
+
+        fn then_rotate(self, angle: FP) -> Self {
+            Self::rotate(angle) * self
+        }
+
+        fn then_rotate_around(self, angle: FP, center: Point) -> Self {
+            Self::rotate_around(angle, center) * self
+        }
+
+        fn as_matrix(&self) -> [FP; 6] {
+            self.0
+        }
+
+        fn from_matrix(matrix: [FP; 6]) -> Self {
+            Matrix(matrix)
+        }
+
+        fn determinant(&self) -> FP {
+            let [a, b, c, d, _, _] = self.0;
+            a * d - b * c
+        }
+
+        fn inverse(self) -> Self {
+            let [a, b, c, d, e, f] = self.0;
+            let det = self.determinant();
+
+            Matrix([
+                d / det,
+                -b / det,
+                -c / det,
+                a / det,
+                (c * f - d * e) / det,
+                (b * e - a * f) / det,
+            ])
+        }
+
+        fn with_translation(&self, translation: Point) -> Self {
+            let [a, b, c, d, _, _] = self.0;
+            Matrix([a, b, c, d, translation.x, translation.y])
+        }
+    }
+
+    pub(crate) fn assert_point(actual: (FP, FP), expected: (FP, FP)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn px(value: FP) -> Value {
+        Value::Unit(value, "px".into())
+    }
+
+    fn function(name: &str, args: Vec<Value>) -> Value {
+        Value::Function(name.into(), args)
+    }
+
+    fn properties(values: Vec<(&str, Value)>) -> CssProperties {
+        let mut properties = CssProperties::new();
+
+        for (name, value) in values {
+            let mut property = CssProperty::new(name);
+            property.actual = value;
+            property.dirty = false;
+            properties.properties.insert(name.to_string(), property);
+        }
+
+        properties
+    }
+
+    #[test]
+    fn around_moves_the_origin() {
+        let transform = around(Matrix::rotate(FRAC_PI_2), 10.0, 10.0).as_matrix();
+
+        assert_point(map_point(transform, (10.0, 10.0)), (10.0, 10.0));
+        assert_point(map_point(transform, (20.0, 10.0)), (10.0, 20.0));
+        assert_point(map_point(transform, (10.0, 0.0)), (20.0, 10.0));
+    }
+
+    #[test]
+    fn transform_origin() {
+        let rotate = function("rotate", vec![Value::Unit(90.0, "deg".into())]);
+
+        // The border box is rotated around its center by default
+        let props = properties(vec![("transform", rotate.clone())]);
+        let transform = element_transform::<Matrix, Css3System>(&props, 100.0, 20.0).unwrap();
+        assert_point(map_point(transform.as_matrix(), (50.0, 10.0)), (50.0, 10.0));
+        assert_point(map_point(transform.as_matrix(), (0.0, 0.0)), (60.0, -40.0));
+
+        let origin = Value::List(vec![Value::String("left".into()), Value::String("top".into())]);
+        let props = properties(vec![("transform", rotate), ("transform-origin", origin)]);
+        let transform = element_transform::<Matrix, Css3System>(&props, 100.0, 20.0).unwrap();
+        assert_point(map_point(transform.as_matrix(), (0.0, 0.0)), (0.0, 0.0));
+        assert_point(map_point(transform.as_matrix(), (100.0, 0.0)), (0.0, 100.0));
+
+        // A translation is not affected by the origin, and percentages are relative to the border box
+        let props = properties(vec![(
+            "translate",
+            Value::List(vec![px(10.0), Value::Percentage(50.0)]),
+        )]);
+        let transform = element_transform::<Matrix, Css3System>(&props, 100.0, 20.0).unwrap();
+        assert_point(map_point(transform.as_matrix(), (0.0, 0.0)), (10.0, 10.0));
+
+        let props = properties(vec![("transform", Value::String("none".into()))]);
+        assert!(element_transform::<Matrix, Css3System>(&props, 100.0, 20.0).is_none());
+    }
+
+    #[test]
+    fn transform_lists_apply_from_right_to_left() {
+        let values = vec![
+            function("translate", vec![px(10.0)]),
+            function("scale", vec![Value::Number(2.0)]),
+        ];
+
+        let transform = parse_transform_list::<Matrix, _>(&values, 0.0, 0.0).unwrap();
+        assert_point(map_point(transform.as_matrix(), (1.0, 1.0)), (12.0, 2.0));
+
+        let values = vec![function("perspective", vec![px(10.0)])];
+        assert!(parse_transform_list::<Matrix, _>(&values, 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(
+            parse_angle(&Value::Unit(0.5, "turn".into())),
+            Some(std::f32::consts::PI)
+        );
+        assert!((parse_angle(&Value::Unit(100.0, "grad".into())).unwrap() - FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(parse_angle(&Value::Unit(1.0, "px".into())), None);
+        assert_eq!(parse_angle(&Value::Number(0.0)), Some(0.0));
+
+        assert_eq!(parse_length(&Value::Percentage(25.0), 200.0), Some(50.0));
+        assert_eq!(parse_length(&Value::Number(3.0), 200.0), None);
+
+        let origin = [Value::String("right".into()), px(5.0)];
+        assert_eq!(parse_origin(&origin, 100.0, 20.0), Some((100.0, 5.0)));
+
+        let origin = [Value::String("bottom".into())];
+        assert_eq!(parse_origin(&origin, 100.0, 20.0), Some((50.0, 20.0)));
+    }
+}
@@ -8,29 +8,49 @@ use gosub_net::http::fetcher::Fetcher;
 use gosub_render_backend::geo::{Size, SizeU32, FP};
 use gosub_render_backend::layout::{Layout, LayoutTree, Layouter, TextLayout};
 use gosub_render_backend::svg::SvgRenderer;
+use gosub_render_backend::writing_mode::WritingMode;
 use gosub_render_backend::{
-    Border, BorderSide, BorderStyle, Brush, Color, ImageBuffer, NodeDesc, Rect, RenderBackend, RenderBorder,
-    RenderRect, RenderText, Scene as TScene, Text, Transform,
+    BlendMode, Border, BorderSide, BorderStyle, Brush, Color, ImageBuffer, NodeDesc, Rect, RenderBackend, RenderBorder,
+    RenderLayer, RenderRect, RenderText, Scene as TScene, Text, Transform,
 };
 
-use gosub_rendering::position::PositionTree;
+use gosub_rendering::position::{Layer, PositionTree};
 use gosub_rendering::render_tree::{RenderNodeData, RenderTree, RenderTreeNode};
+use gosub_rendering::transform::{around, element_transform};
+use gosub_shared::document::DocumentHandle;
 use gosub_shared::node::NodeId;
 use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem};
 use gosub_shared::traits::document::Document;
 use gosub_shared::traits::html5::Html5Parser;
 use gosub_shared::traits::node::{ElementDataType, Node};
 use gosub_shared::types::Result;
+use gosub_typeface::web_fonts::WEB_FONTS;
 
 use crate::debug::scale::px_scale;
+use crate::draw::background::{clips_to_text, has_fixed_background, render_background, text_brush, Area};
+use crate::draw::font::{fonts_pending, load_requested_fonts, register_font_faces};
 use crate::draw::img::request_img;
+use crate::draw::selection::selection_colors;
+use crate::draw::shadow::{current_color, render_box_shadows, text_shadows};
 use crate::render_tree::{load_html_rendertree, TreeDrawer};
 
+mod background;
+pub(crate) mod font;
+mod gradient;
 mod img;
+pub(crate) mod selection;
+mod shadow;
 
 pub trait SceneDrawer<B: RenderBackend, L: Layouter, LT: LayoutTree<L>, D: Document<C>, C: CssSystem> {
     fn draw(&mut self, backend: &mut B, data: &mut B::WindowData<'_>, size: SizeU32) -> bool;
     fn mouse_move(&mut self, backend: &mut B, x: FP, y: FP) -> bool;
+    /// Called when the left mouse button is pressed, at the position of the last `mouse_move`
+    fn mouse_down(&mut self, backend: &mut B) -> bool;
+    fn mouse_up(&mut self, backend: &mut B) -> bool;
+
+    /// Returns the element of the document at a position in the viewport, which is the target of mouse events
+    fn hit_test(&mut self, x: FP, y: FP) -> Option<NodeId>;
+    fn document(&self) -> Option<DocumentHandle<D, C>>;
 
     fn scroll(&mut self, point: Point);
     fn from_url<P>(url: Url, layouter: L, debug: bool) -> Result<Self>
@@ -62,8 +82,32 @@ where
     <<B as RenderBackend>::Text as Text>::Font: From<<<L as Layouter>::TextLayout as TextLayout>::Font>,
 {
     fn draw(&mut self, backend: &mut B, data: &mut B::WindowData<'_>, size: SizeU32) -> bool {
+        if self.tree.apply_mutations() {
+            // Only the restyled subtrees need a new layout, all other nodes are served from the layout cache
+            self.tree_scene = None;
+            self.dirty = true;
+
+            if let Some(handle) = &self.tree.handle {
+                register_font_faces(&*handle.get());
+            }
+        }
+
+        let font_generation = WEB_FONTS
+            .lock()
+            .map(|mut web_fonts| web_fonts.poll())
+            .unwrap_or(self.font_generation);
+
+        if font_generation != self.font_generation {
+            // A web font has been loaded or a block period has ended, so all text needs to be measured again
+            self.font_generation = font_generation;
+            self.tree.invalidate_layouts();
+            self.tree_scene = None;
+            self.dirty = true;
+        }
+
         if !self.dirty && self.size == Some(size) {
-            return false;
+            // Keep drawing while web fonts are loading, so they are swapped in as soon as they are available
+            return fonts_pending();
         }
 
         if self.tree_scene.is_none() || self.size != Some(size) {
@@ -83,19 +127,39 @@ where
                 scene_transform.set_xy(x, y);
             }
 
+            let (scroll_x, scroll_y) = self
+                .scene_transform
+                .as_ref()
+                .map(|transform| (-transform.tx(), -transform.ty()))
+                .unwrap_or((0.0, 0.0));
+
             let mut drawer = Drawer {
                 scene: &mut scene,
                 drawer: self,
                 svg: B::SVGRenderer::new(),
+                in_layer: false,
+                viewport: Area::new(scroll_x, scroll_y, size.width as FP, size.height as FP),
+                text_clip: None,
             };
 
             drawer.render(size);
 
             self.tree_scene = Some(scene);
 
+            load_requested_fonts();
+
             self.size = Some(size);
         }
 
+        let scroll = self
+            .scene_transform
+            .as_ref()
+            .map(|transform| (-transform.tx(), -transform.ty()))
+            .unwrap_or((0.0, 0.0));
+
+        self.position
+            .set_scroll(scroll, (size.width as f32, size.height as f32));
+
         backend.reset(data);
 
         let bg = Rect::new(0.0, 0.0, size.width as FP, size.height as FP);
@@ -115,6 +179,22 @@ where
             backend.apply_scene(data, scene, self.scene_transform.clone());
         }
 
+        for (id, scene) in &self.sticky_scenes {
+            let (x, y) = self.position.sticky_offset(*id);
+
+            let transform = self
+                .scene_transform
+                .clone()
+                .unwrap_or(B::Transform::IDENTITY)
+                .then_translate(x, y);
+
+            backend.apply_scene(data, scene, Some(transform));
+        }
+
+        if let Some(scene) = &self.fixed_scene {
+            backend.apply_scene(data, scene, None);
+        }
+
         if self.dirty {
             if let Some(id) = self.selected_element {
                 self.debug_annotate(id);
@@ -144,16 +224,21 @@ where
             return true;
         }
 
-        false
+        fonts_pending()
     }
 
     fn mouse_move(&mut self, _backend: &mut B, x: FP, y: FP) -> bool {
-        let x = x - self.scene_transform.clone().unwrap_or(B::Transform::IDENTITY).tx();
-        let y = y - self.scene_transform.clone().unwrap_or(B::Transform::IDENTITY).ty();
-
+        // The position tree knows the scroll position, so it takes coordinates in the viewport
         if let Some(e) = self.position.find(x, y) {
             if self.last_hover != Some(e) {
                 self.last_hover = Some(e);
+
+                if self.selection.extend(&self.tree, e) {
+                    self.tree_scene = None;
+                    self.dirty = true;
+                    return true;
+                }
+
                 if self.debug {
                     return self.debug_annotate(e);
                 }
@@ -163,6 +248,42 @@ where
         false
     }
 
+    fn mouse_down(&mut self, _backend: &mut B) -> bool {
+        if !self.selection.start(self.last_hover) {
+            return false;
+        }
+
+        self.tree_scene = None;
+        self.dirty = true;
+
+        true
+    }
+
+    fn mouse_up(&mut self, _backend: &mut B) -> bool {
+        self.selection.end();
+
+        false
+    }
+
+    fn hit_test(&mut self, x: FP, y: FP) -> Option<NodeId> {
+        let mut id = self.position.find(x, y)?;
+
+        // Text, anonymous boxes and pseudo-elements are not targets, their closest element is
+        loop {
+            let node = self.tree.get_node(id)?;
+
+            if node.is_element() && !node.name.starts_with("::") {
+                return Some(id);
+            }
+
+            id = node.parent?;
+        }
+    }
+
+    fn document(&self) -> Option<DocumentHandle<D, C>> {
+        self.tree.handle.clone()
+    }
+
     fn scroll(&mut self, point: Point) {
         let mut transform = self.scene_transform.take().unwrap_or(B::Transform::IDENTITY);
 
@@ -182,6 +303,11 @@ where
 
         self.scene_transform = Some(transform);
 
+        // Fixed backgrounds stay in place while the element they are clipped to moves
+        if self.fixed_backgrounds {
+            self.tree_scene = None;
+        }
+
         self.dirty = true;
     }
 
@@ -232,6 +358,12 @@ struct Drawer<'s, 't, B: RenderBackend, L: Layouter, D: Document<C>, C: CssSyste
     scene: &'s mut B::Scene,
     drawer: &'t mut TreeDrawer<B, L, D, C>,
     svg: B::SVGRenderer,
+    /// We are rendering the subtree of a fixed or sticky element into its own scene
+    in_layer: bool,
+    /// The visible part of the scene that is rendered, in its own coordinates
+    viewport: Area,
+    /// The closest ancestor (and its position) with a background that is painted behind its text
+    text_clip: Option<(NodeId, Point)>,
 }
 
 impl<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem> Drawer<'_, '_, B, L, D, C>
@@ -249,10 +381,31 @@ where
 
         self.drawer.position = PositionTree::from_tree::<B, L, D, C>(&self.drawer.tree);
 
+        self.drawer.fixed_scene = None;
+        self.drawer.sticky_scenes.clear();
+        self.drawer.fixed_backgrounds = false;
+
         self.render_node_with_children(self.drawer.tree.root, Point::ZERO);
     }
 
-    fn render_node_with_children(&mut self, id: NodeId, mut pos: Point) {
+    fn render_node_with_children(&mut self, id: NodeId, pos: Point) {
+        if !self.in_layer {
+            match self.drawer.position.layer(id) {
+                Some(Layer::Fixed) => return self.render_layer(id, pos, None),
+                Some(Layer::Sticky(root)) if root == id => return self.render_layer(id, pos, Some(id)),
+                _ => {}
+            }
+        }
+
+        let layer = self.compositing_layer(id, pos);
+        if !layer.is_noop() {
+            return self.render_composited(id, pos, layer);
+        }
+
+        self.render_node_and_children(id, pos);
+    }
+
+    fn render_node_and_children(&mut self, id: NodeId, mut pos: Point) {
         let err = self.render_node(id, &mut pos);
         if let Err(e) = err {
             eprintln!("Error rendering node: {}", e);
@@ -263,8 +416,157 @@ where
             return;
         };
 
-        for child in children {
-            self.render_node_with_children(child, pos);
+        let clips_text = self
+            .drawer
+            .tree
+            .get_node(id)
+            .is_some_and(|node| clips_to_text::<C>(&node.properties));
+
+        let parent_clip = self.text_clip;
+        if clips_text {
+            self.text_clip = Some((id, pos));
+        }
+
+        if let Some(transform) = self.writing_mode_transform(id, pos) {
+            // The content of a vertical box is laid out sideways, and rotated into place as a whole
+            let mut scene = B::Scene::new();
+
+            std::mem::swap(self.scene, &mut scene);
+            for child in children {
+                self.render_node_with_children(child, pos);
+            }
+            std::mem::swap(self.scene, &mut scene);
+
+            let mut layer = RenderLayer::new();
+            layer.transform = Some(transform);
+            self.scene.apply_layer(&scene, &layer);
+        } else {
+            for child in children {
+                self.render_node_with_children(child, pos);
+            }
+        }
+
+        self.text_clip = parent_clip;
+
+        if let Some(node) = self.drawer.tree.get_node(id) {
+            render_outline::<B, L, C>(node, self.scene, &pos);
+        }
+    }
+
+    /// Returns the rotation of the content of a box with a vertical `writing-mode` inside a horizontal one. Lines are
+    /// laid out from top to bottom, and turned a quarter clockwise so they run from top to bottom and are stacked
+    /// from right to left
+    fn writing_mode_transform(&self, id: NodeId, pos: Point) -> Option<B::Transform> {
+        let is_vertical = |id: NodeId| {
+            self.drawer.tree.get_node(id).is_some_and(|node| {
+                node.properties
+                    .get("writing-mode")
+                    .and_then(|prop| prop.as_string())
+                    .is_some_and(|mode| WritingMode::parse(mode).is_vertical())
+            })
+        };
+
+        if !is_vertical(id) {
+            return None;
+        }
+
+        // Anonymous inline boxes don't have a style of their own, so the parent element is the one around them
+        let mut parent = self.drawer.tree.parent_id(id);
+        while let Some(parent_id) = parent {
+            let anonymous = self
+                .drawer
+                .tree
+                .get_node(parent_id)
+                .is_some_and(|node| matches!(node.data, RenderNodeData::AnonymousInline));
+
+            if !anonymous {
+                break;
+            }
+
+            parent = self.drawer.tree.parent_id(parent_id);
+        }
+
+        if parent.is_some_and(is_vertical) {
+            return None;
+        }
+
+        let width = self.drawer.tree.get_node(id)?.layout.size().width;
+
+        let rotation = B::Transform::from_matrix([0.0, 1.0, -1.0, 0.0, width, 0.0]);
+
+        Some(around(rotation, pos.x, pos.y))
+    }
+
+    /// Returns the transform, opacity and blend mode that the subtree of an element is composited with
+    fn compositing_layer(&self, id: NodeId, pos: Point) -> RenderLayer<B> {
+        let mut layer = RenderLayer::new();
+
+        let Some(node) = self.drawer.tree.get_node(id) else {
+            return layer;
+        };
+
+        let p = node.layout.rel_pos();
+        let size = node.layout.size();
+
+        layer.transform = element_transform::<B::Transform, C>(&node.properties, size.width, size.height)
+            .map(|transform| around(transform, pos.x + p.x, pos.y + p.y));
+
+        layer.opacity = node
+            .properties
+            .get("opacity")
+            .and_then(|prop| prop.as_percentage().map(|percent| percent / 100.0).or(prop.as_number()))
+            .map(|opacity| opacity.clamp(0.0, 1.0))
+            .unwrap_or(1.0);
+
+        layer.blend_mode = node
+            .properties
+            .get("mix-blend-mode")
+            .and_then(|prop| prop.as_string())
+            .and_then(BlendMode::parse)
+            .unwrap_or_default();
+
+        layer
+    }
+
+    /// Renders the subtree of an element into its own scene, and composites it as a group
+    fn render_composited(&mut self, id: NodeId, pos: Point, layer: RenderLayer<B>) {
+        let mut scene = B::Scene::new();
+
+        std::mem::swap(self.scene, &mut scene);
+        self.render_node_and_children(id, pos);
+        std::mem::swap(self.scene, &mut scene);
+
+        self.scene.apply_layer(&scene, &layer);
+    }
+
+    /// Renders a fixed or sticky subtree into its own scene, so it can be moved independently of the document
+    fn render_layer(&mut self, id: NodeId, pos: Point, sticky: Option<NodeId>) {
+        let mut layer = B::Scene::new();
+
+        // The fixed scene is painted without the scroll transform, so the viewport is at its origin
+        let viewport = self.viewport;
+        if sticky.is_none() {
+            self.viewport.x = 0.0;
+            self.viewport.y = 0.0;
+        }
+
+        std::mem::swap(self.scene, &mut layer);
+        self.in_layer = true;
+
+        self.render_node_with_children(id, pos);
+
+        self.in_layer = false;
+        std::mem::swap(self.scene, &mut layer);
+
+        self.viewport = viewport;
+
+        match sticky {
+            Some(id) => self.drawer.sticky_scenes.push((id, layer)),
+            None => self
+                .drawer
+                .fixed_scene
+                .get_or_insert_with(B::Scene::new)
+                .apply_scene(&layer, None),
         }
     }
 
@@ -275,26 +577,49 @@ where
         pos.x += p.x as FP;
         pos.y += p.y as FP;
 
-        let (border_radius, new_size) =
-            render_bg::<B, L, C>(node, self.scene, pos, &mut self.svg, &self.drawer.fetcher);
+        self.drawer.fixed_backgrounds |= has_fixed_background::<C>(&node.properties);
+
+        let (border_radius, new_size) = render_bg::<B, L, C>(
+            node,
+            self.scene,
+            pos,
+            self.viewport,
+            &mut self.svg,
+            &self.drawer.fetcher,
+        );
 
         let mut size_change = new_size;
 
-        if node.name == "img" {
-            let Some(handle) = self.drawer.tree.handle.as_ref() else {
-                return Err(anyhow!("No document handle"));
-            };
+        // Pseudo-elements have the url of their generated image as a layout attribute
+        let generated_src = node
+            .layout_attributes
+            .iter()
+            .find(|(name, _)| name == "src")
+            .map(|(_, src)| src.clone());
 
-            let doc = handle.get();
+        let src = match generated_src {
+            Some(src) => Some(src),
+            None if node.name == "img" => {
+                let Some(handle) = self.drawer.tree.handle.as_ref() else {
+                    return Err(anyhow!("No document handle"));
+                };
 
-            let dom_node = doc.node_by_id(id).ok_or(anyhow!("Node not found"))?;
+                let doc = handle.get();
 
-            let element = dom_node.get_element_data().ok_or(anyhow!("Node is not an element"))?;
+                let dom_node = doc.node_by_id(id).ok_or(anyhow!("Node not found"))?;
 
-            let src = element
-                .attribute("src")
-                .ok_or(anyhow!("Image element has no src attribute"))?;
+                let element = dom_node.get_element_data().ok_or(anyhow!("Node is not an element"))?;
 
+                let src = element
+                    .attribute("src")
+                    .ok_or(anyhow!("Image element has no src attribute"))?;
+
+                Some(src.to_string())
+            }
+            None => None,
+        };
+
+        if let Some(src) = src {
             let url = src.as_str();
 
             let size = node.layout.size_or().map(|x| x.u32());
@@ -316,7 +641,18 @@ where
             render_image::<B>(img, self.scene, *pos, size, border_radius, fit)?;
         }
 
-        render_text::<B, L, C>(node, self.scene, pos);
+        let brush = self.text_clip.and_then(|(clip_id, clip_pos)| {
+            let clip_node = self.drawer.tree.get_node(clip_id)?;
+            text_brush::<B, L, C>(clip_node, clip_pos, *pos)
+        });
+
+        let selection = self
+            .drawer
+            .selection
+            .contains(id)
+            .then(|| selection_colors(&self.drawer.tree, id));
+
+        render_text::<B, L, C>(node, self.scene, pos, brush, selection);
 
         if let Some(new) = size_change {
             let node = self
@@ -338,6 +674,8 @@ fn render_text<B: RenderBackend, L: Layouter, C: CssSystem>(
     node: &RenderTreeNode<L, C>,
     scene: &mut B::Scene,
     pos: &Point,
+    brush: Option<B::Brush>,
+    selection: Option<(Option<Color>, Option<Color>)>,
 ) where
     <<B as RenderBackend>::Text as Text>::Font: From<<<L as Layouter>::TextLayout as TextLayout>::Font>,
 {
@@ -356,23 +694,85 @@ fn render_text<B: RenderBackend, L: Layouter, C: CssSystem>(
         .map(|color| Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8))
         .unwrap_or(Color::BLACK);
 
-    if let RenderNodeData::Text(text) = &node.data {
-        let Some(layout) = text.layout.as_ref() else {
-            warn!("No layout for text node");
-            return;
-        };
+    let RenderNodeData::Text(text) = &node.data else {
+        return;
+    };
 
-        let text: B::Text = Text::new::<L::TextLayout>(layout);
+    if text.layouts.is_empty() {
+        warn!("No layout for text node");
+        return;
+    }
 
-        let size = node.layout.size();
+    // The first shadow is on top, so the list is painted back to front, below the text itself
+    let shadows = text_shadows::<C>(&node.properties);
+    let current = current_color::<C>(&node.properties);
 
-        let rect = Rect::new(pos.x as FP, pos.y as FP, size.width as FP, size.height as FP);
+    // A text node has a layout for every line and every direction run it is split into
+    for layout in &text.layouts {
+        // Text is hidden during the block period of its web font, but still takes up space
+        if layout.invisible() {
+            continue;
+        }
+
+        let offset = layout.offset();
+        let size = layout.size();
+
+        let rect = Rect::new(
+            (pos.x + offset.x) as FP,
+            (pos.y + offset.y) as FP,
+            size.width as FP,
+            size.height as FP,
+        );
+
+        let (selection_color, selection_background) = selection.unwrap_or_default();
+
+        if let Some(background) = selection_background {
+            // The run is positioned at its baseline, the highlight covers the line box around it
+            let font_size = layout.font_size();
+            let top = pos.y + offset.y - font_size * 0.8 - (size.height - font_size) / 2.0;
+
+            scene.draw_rect(&RenderRect {
+                rect: Rect::new((pos.x + offset.x) as FP, top as FP, size.width as FP, size.height as FP),
+                transform: None,
+                radius: None,
+                brush: Brush::color(background),
+                brush_transform: None,
+                border: None,
+            });
+        }
+
+        for shadow in shadows.iter().rev() {
+            let mut shadow_scene = B::Scene::new();
+            shadow_scene.draw_text(&RenderText {
+                text: Text::new::<L::TextLayout>(layout),
+                rect,
+                transform: None,
+                brush: Brush::color(shadow.color::<B>(current)),
+                brush_transform: None,
+            });
+
+            let offset = B::Transform::translate(shadow.offset_x, shadow.offset_y);
+            scene.apply_blurred_scene(&shadow_scene, Some(offset), shadow.std_dev());
+        }
+
+        let text: B::Text = Text::new::<L::TextLayout>(layout);
 
         let render_text = RenderText {
             text,
             rect,
             transform: None,
-            brush: Brush::color(color),
+            brush: match selection_color {
+                Some(color) => Brush::color(color),
+                // Runs in a `::first-line` or `::first-letter` can have their own color
+                None => brush.clone().unwrap_or_else(|| {
+                    Brush::color(
+                        layout
+                            .color()
+                            .map(|color| Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8))
+                            .unwrap_or(color),
+                    )
+                }),
+            },
             brush_transform: None,
         };
 
@@ -384,15 +784,125 @@ fn render_bg<B: RenderBackend, L: Layouter, C: CssSystem>(
     node: &RenderTreeNode<L, C>,
     scene: &mut B::Scene,
     pos: &Point,
+    viewport: Area,
     svg: &mut B::SVGRenderer,
     fetcher: &Fetcher,
 ) -> ((FP, FP, FP, FP), Option<SizeU32>) {
-    let bg_color = node
+    let border_radius = border_radius(node);
+
+    let border = get_border::<B, L, C>(node).map(|border| RenderBorder::new(border));
+
+    render_box_shadows::<B, L, C>(node, scene, *pos, border_radius, false);
+
+    let img_size = render_background::<B, L, C>(node, scene, *pos, border_radius, viewport, svg, fetcher);
+
+    render_box_shadows::<B, L, C>(node, scene, *pos, border_radius, true);
+
+    let size = node.layout.size();
+
+    // The border is painted on top of the background
+    if let Some(border) = border {
+        let rect = Rect::new(pos.x as FP, pos.y as FP, size.width as FP, size.height as FP);
+
+        let rect = RenderRect {
+            rect,
+            transform: None,
+            radius: Some(B::BorderRadius::from(border_radius)),
+            brush: Brush::color(Color::TRANSPARENT),
+            brush_transform: None,
+            border: Some(border),
+        };
+
+        scene.draw_rect(&rect);
+    }
+
+    (border_radius, img_size)
+}
+
+/// Paints the outline of an element outside its border box, on top of the element and its descendants
+fn render_outline<B: RenderBackend, L: Layouter, C: CssSystem>(
+    node: &RenderTreeNode<L, C>,
+    scene: &mut B::Scene,
+    pos: &Point,
+) {
+    let style = node
+        .properties
+        .get("outline-style")
+        .and_then(|prop| prop.as_string())
+        .map(|style| if style == "auto" { "solid" } else { style })
+        .map(BorderStyle::from_str)
+        .unwrap_or(BorderStyle::None);
+
+    if matches!(style, BorderStyle::None | BorderStyle::Hidden) {
+        return;
+    }
+
+    let width = node
+        .properties
+        .get("outline-width")
+        .map(|prop| match prop.as_string() {
+            Some("thin") => 1.0,
+            Some("medium") => 3.0,
+            Some("thick") => 5.0,
+            _ => prop.unit_to_px(),
+        })
+        .unwrap_or(3.0);
+
+    if width <= 0.0 {
+        return;
+    }
+
+    let offset = node
+        .properties
+        .get("outline-offset")
+        .map(|prop| prop.unit_to_px())
+        .unwrap_or(0.0);
+
+    let color = node
         .properties
-        .get("background-color")
+        .get("outline-color")
         .and_then(|prop| prop.parse_color())
-        .map(|color| Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8));
+        .or_else(|| node.properties.get("color").and_then(|prop| prop.parse_color()))
+        .unwrap_or((0.0, 0.0, 0.0, 255.0));
+
+    let brush = Brush::color(Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8));
+
+    let size = node.layout.size();
+    let grow = offset + width;
 
+    let rect = Rect::new(
+        pos.x - grow,
+        pos.y - grow,
+        (size.width + grow * 2.0).max(0.0),
+        (size.height + grow * 2.0).max(0.0),
+    );
+
+    // The outline follows the rounded corners of the border box
+    let radius = border_radius(node);
+    let grow_radius = |radius: FP| if radius > 0.0 { (radius + grow).max(0.0) } else { 0.0 };
+    let radius = (
+        grow_radius(radius.0),
+        grow_radius(radius.1),
+        grow_radius(radius.2),
+        grow_radius(radius.3),
+    );
+
+    let border = B::Border::new(BorderSide::new(width, style, brush));
+
+    let rect = RenderRect {
+        rect,
+        transform: None,
+        radius: Some(B::BorderRadius::from(radius)),
+        brush: Brush::color(Color::TRANSPARENT),
+        brush_transform: None,
+        border: Some(RenderBorder::new(border)),
+    };
+
+    scene.draw_rect(&rect);
+}
+
+/// Returns the radii of the corners of the border box
+fn border_radius<L: Layouter, C: CssSystem>(node: &RenderTreeNode<L, C>) -> (FP, FP, FP, FP) {
     let border_radius_left = node
         .properties
         .get("border-radius-left")
@@ -417,75 +927,12 @@ fn render_bg<B: RenderBackend, L: Layouter, C: CssSystem>(
         .map(|prop| prop.unit_to_px() as f64)
         .unwrap_or(0.0);
 
-    let border_radius = (
+    (
         border_radius_top as FP,
         border_radius_right as FP,
         border_radius_bottom as FP,
         border_radius_left as FP,
-    );
-
-    let border = get_border::<B, L, C>(node).map(|border| RenderBorder::new(border));
-
-    if let Some(bg_color) = bg_color {
-        let size = node.layout.size();
-
-        let rect = Rect::new(pos.x as FP, pos.y as FP, size.width as FP, size.height as FP);
-
-        let rect = RenderRect {
-            rect,
-            transform: None,
-            radius: Some(B::BorderRadius::from(border_radius)),
-            brush: Brush::color(bg_color),
-            brush_transform: None,
-            border,
-        };
-
-        scene.draw_rect(&rect);
-    } else if let Some(border) = border {
-        let size = node.layout.size();
-
-        let rect = Rect::new(pos.x as FP, pos.y as FP, size.width as FP, size.height as FP);
-
-        let rect = RenderRect {
-            rect,
-            transform: None,
-            radius: Some(B::BorderRadius::from(border_radius)),
-            brush: Brush::color(Color::TRANSPARENT),
-            brush_transform: None,
-            border: Some(border),
-        };
-
-        scene.draw_rect(&rect);
-    }
-
-    let background_image = node
-        .properties
-        .get("background-image")
-        .and_then(|prop| prop.as_string());
-
-    let mut img_size = None;
-
-    if let Some(url) = background_image {
-        let size = node.layout.size_or().map(|x| x.u32());
-
-        let img = match request_img(fetcher, svg, url, size) {
-            Ok(img) => img,
-            Err(e) => {
-                eprintln!("Error loading image: {:?}", e);
-                return (border_radius, None);
-            }
-        };
-
-        if size.is_none() {
-            img_size = Some(img.size());
-        }
-
-        let _ = render_image::<B>(img, scene, *pos, node.layout.size(), border_radius, "fill").map_err(|e| {
-            eprintln!("Error rendering image: {:?}", e);
-        });
-    }
-
-    (border_radius, img_size)
+    )
 }
 
 enum Side {
@@ -0,0 +1,840 @@
+//! The CSS background model (<https://www.w3.org/TR/css-backgrounds-3/#backgrounds>). A background consists of a
+//! color and any number of image layers, which are sized, positioned and tiled in their positioning area, and
+//! clipped to their painting area.
+
+use gosub_net::http::fetcher::Fetcher;
+use gosub_render_backend::geo::{Point, SizeU32, FP};
+use gosub_render_backend::layout::{Layout, Layouter};
+use gosub_render_backend::{
+    Brush, Color, ImageBuffer, Rect, RenderBackend, RenderLayer, RenderRect, Scene as TScene, Transform,
+};
+use gosub_rendering::render_tree::RenderTreeNode;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem, CssValue};
+
+use crate::draw::gradient::parse_gradient;
+use crate::draw::img::request_img;
+
+/// Maximum number of tiles that are painted for a single layer, small tiles in a large box are skipped beyond this
+const MAX_TILES: usize = 10_000;
+
+type Radii = (FP, FP, FP, FP);
+
+type Value<C> = <<<C as CssSystem>::PropertyMap as CssPropertyMap>::Property as CssProperty>::Value;
+
+/// A rectangle in the coordinates of the scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: FP,
+    pub y: FP,
+    pub width: FP,
+    pub height: FP,
+}
+
+impl Area {
+    pub fn new(x: FP, y: FP, width: FP, height: FP) -> Self {
+        Self { x, y, width, height }
+    }
+
+    fn inset(self, (top, right, bottom, left): (FP, FP, FP, FP)) -> Self {
+        Self {
+            x: self.x + left,
+            y: self.y + top,
+            width: (self.width - left - right).max(0.0),
+            height: (self.height - top - bottom).max(0.0),
+        }
+    }
+
+    fn intersect(self, other: Self) -> Option<Self> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+
+        (right > x && bottom > y).then(|| Self::new(x, y, right - x, bottom - y))
+    }
+
+    fn contains(self, other: Self) -> bool {
+        self.x <= other.x
+            && self.y <= other.y
+            && self.x + self.width >= other.x + other.width
+            && self.y + self.height >= other.y + other.height
+    }
+
+    pub fn rect<B: RenderBackend>(self) -> B::Rect {
+        B::Rect::new(self.x, self.y, self.width, self.height)
+    }
+}
+
+/// A length, percentage or a combination of both (like `right 10px` in a position). Percentages are resolved when
+/// the size of the reference box is known
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub px: FP,
+    pub percent: FP,
+}
+
+impl Length {
+    pub const ZERO: Self = Self::px(0.0);
+    pub const CENTER: Self = Self::percent(50.0);
+
+    pub const fn px(px: FP) -> Self {
+        Self { px, percent: 0.0 }
+    }
+
+    pub const fn percent(percent: FP) -> Self {
+        Self { px: 0.0, percent }
+    }
+
+    pub fn parse<V: CssValue>(value: &V) -> Option<Self> {
+        if let Some(percent) = value.as_percentage() {
+            return Some(Self::percent(percent));
+        }
+
+        if value.as_unit().is_some() {
+            return Some(Self::px(value.unit_to_px()));
+        }
+
+        value.as_number().filter(|num| *num == 0.0).map(|_| Self::ZERO)
+    }
+
+    pub fn resolve(self, size: FP) -> FP {
+        self.px + size * self.percent / 100.0
+    }
+
+    /// Returns the length measured from the opposite edge
+    fn flip(self) -> Self {
+        Self {
+            px: -self.px,
+            percent: 100.0 - self.percent,
+        }
+    }
+}
+
+/// Parses a `<position>`, and returns the horizontal and vertical offset. Missing values default to `center`
+pub fn parse_position<V: CssValue>(values: &[V]) -> Option<(Length, Length)> {
+    let keyword = |value: &V| match value.as_string() {
+        Some("left") | Some("top") => Some(Length::percent(0.0)),
+        Some("center") => Some(Length::CENTER),
+        Some("right") | Some("bottom") => Some(Length::percent(100.0)),
+        _ => Length::parse(value),
+    };
+
+    let is_vertical = |value: &V| matches!(value.as_string(), Some("top") | Some("bottom"));
+    let is_horizontal = |value: &V| matches!(value.as_string(), Some("left") | Some("right"));
+
+    match values {
+        [value] if is_vertical(value) => Some((Length::CENTER, keyword(value)?)),
+        [value] => Some((keyword(value)?, Length::CENTER)),
+        [first, second] if is_vertical(first) || is_horizontal(second) => Some((keyword(second)?, keyword(first)?)),
+        [first, second] => Some((keyword(first)?, keyword(second)?)),
+        // Edge keywords that are followed by an offset from that edge, like `right 10px bottom 20%`
+        [first, first_offset, second, second_offset] => {
+            let ((x, x_offset), (y, y_offset)) = if is_vertical(first) {
+                ((second, second_offset), (first, first_offset))
+            } else {
+                ((first, first_offset), (second, second_offset))
+            };
+
+            let from_edge = |edge: &V, offset: &V| {
+                let offset = Length::parse(offset)?;
+
+                Some(match edge.as_string() {
+                    Some("right") | Some("bottom") => offset.flip(),
+                    _ => offset,
+                })
+            };
+
+            Some((from_edge(x, x_offset)?, from_edge(y, y_offset)?))
+        }
+        _ => None,
+    }
+}
+
+/// The boxes of an element that a background can be positioned in, or clipped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualBox {
+    Border,
+    Padding,
+    Content,
+}
+
+impl VisualBox {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "border-box" => Some(Self::Border),
+            "padding-box" => Some(Self::Padding),
+            "content-box" => Some(Self::Content),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clip {
+    Box(VisualBox),
+    /// The background is only painted behind the text of the element
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    Repeat,
+    Space,
+    Round,
+    NoRepeat,
+}
+
+impl Repeat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "repeat" => Some(Self::Repeat),
+            "space" => Some(Self::Space),
+            "round" => Some(Self::Round),
+            "no-repeat" => Some(Self::NoRepeat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BackgroundSize {
+    Cover,
+    Contain,
+    /// Width and height, `None` is `auto`
+    Size(Option<Length>, Option<Length>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BackgroundImage<V> {
+    Url(String),
+    Gradient(String, Vec<V>),
+}
+
+/// A single background layer, with the values of all background properties for that layer
+#[derive(Debug, Clone)]
+struct Layer<V> {
+    image: Option<BackgroundImage<V>>,
+    position: (Length, Length),
+    size: BackgroundSize,
+    repeat: (Repeat, Repeat),
+    origin: VisualBox,
+    clip: Clip,
+    /// `background-attachment: fixed`, the layer is positioned in the viewport instead of the element
+    fixed: bool,
+}
+
+/// Returns the background layers of an element, from top to bottom. The number of layers is the number of
+/// `background-image` values, the values of the other properties are repeated when there are less of them
+fn layers<V: CssValue, M: CssPropertyMap>(properties: &M) -> Vec<Layer<V>>
+where
+    M::Property: CssProperty<Value = V>,
+{
+    let values = |name: &str| -> Vec<Vec<V>> {
+        properties
+            .get(name)
+            .map(|prop| {
+                prop.as_values()
+                    .split(|value| value.is_comma())
+                    .map(|values| values.to_vec())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let images = values("background-image");
+    let positions = values("background-position");
+    let sizes = values("background-size");
+    let repeats = values("background-repeat");
+    let origins = values("background-origin");
+    let clips = values("background-clip");
+    let attachments = values("background-attachment");
+
+    let keyword = |list: &[Vec<V>], idx: usize| -> Option<String> {
+        let values = list.get(idx % list.len().max(1))?;
+        values.first()?.as_string().map(|s| s.to_string())
+    };
+
+    let layer_count = images.len().max(1);
+
+    (0..layer_count)
+        .map(|idx| {
+            let cycle = |list: &[Vec<V>]| list.get(idx % list.len().max(1)).cloned().unwrap_or_default();
+
+            Layer {
+                image: images.get(idx).and_then(|values| parse_image(values)),
+                position: parse_position(&cycle(&positions)).unwrap_or((Length::percent(0.0), Length::percent(0.0))),
+                size: parse_size(&cycle(&sizes)),
+                repeat: parse_repeat(&cycle(&repeats)),
+                origin: keyword(&origins, idx)
+                    .and_then(|value| VisualBox::parse(&value))
+                    .unwrap_or(VisualBox::Padding),
+                clip: match keyword(&clips, idx).as_deref() {
+                    Some("text") => Clip::Text,
+                    Some(value) => Clip::Box(VisualBox::parse(value).unwrap_or(VisualBox::Border)),
+                    None => Clip::Box(VisualBox::Border),
+                },
+                fixed: keyword(&attachments, idx).as_deref() == Some("fixed"),
+            }
+        })
+        .collect()
+}
+
+fn parse_image<V: CssValue>(values: &[V]) -> Option<BackgroundImage<V>> {
+    let [value] = values else {
+        return None;
+    };
+
+    if let Some((name, args)) = value.as_function() {
+        return match name {
+            "url" => args
+                .first()
+                .and_then(|url| url.as_string())
+                .map(|url| BackgroundImage::Url(url.to_string())),
+            _ if name.ends_with("gradient") => Some(BackgroundImage::Gradient(name.to_string(), args.to_vec())),
+            _ => None,
+        };
+    }
+
+    match value.as_string() {
+        Some("none") | Some("") | None => None,
+        Some(url) => Some(BackgroundImage::Url(url.to_string())),
+    }
+}
+
+fn parse_size<V: CssValue>(values: &[V]) -> BackgroundSize {
+    let length = |value: &V| match value.as_string() {
+        Some("auto") => Some(None),
+        _ => Length::parse(value).map(Some),
+    };
+
+    match values {
+        [value] if value.as_string() == Some("cover") => BackgroundSize::Cover,
+        [value] if value.as_string() == Some("contain") => BackgroundSize::Contain,
+        [width] => BackgroundSize::Size(length(width).flatten(), None),
+        [width, height] => BackgroundSize::Size(length(width).flatten(), length(height).flatten()),
+        _ => BackgroundSize::Size(None, None),
+    }
+}
+
+fn parse_repeat<V: CssValue>(values: &[V]) -> (Repeat, Repeat) {
+    let keywords = values.iter().filter_map(|value| value.as_string()).collect::<Vec<_>>();
+
+    match keywords[..] {
+        ["repeat-x"] => (Repeat::Repeat, Repeat::NoRepeat),
+        ["repeat-y"] => (Repeat::NoRepeat, Repeat::Repeat),
+        [both] => {
+            let repeat = Repeat::parse(both).unwrap_or(Repeat::Repeat);
+            (repeat, repeat)
+        }
+        [x, y] => (
+            Repeat::parse(x).unwrap_or(Repeat::Repeat),
+            Repeat::parse(y).unwrap_or(Repeat::Repeat),
+        ),
+        _ => (Repeat::Repeat, Repeat::Repeat),
+    }
+}
+
+/// Returns the size of a single tile of a layer, for an image with the given intrinsic size. Gradients have no
+/// intrinsic size
+fn tile_size(size: BackgroundSize, intrinsic: Option<(FP, FP)>, area: Area) -> (FP, FP) {
+    let intrinsic = intrinsic.filter(|(width, height)| *width > 0.0 && *height > 0.0);
+
+    match (size, intrinsic) {
+        (BackgroundSize::Cover, Some((width, height))) => {
+            let scale = (area.width / width).max(area.height / height);
+            (width * scale, height * scale)
+        }
+        (BackgroundSize::Contain, Some((width, height))) => {
+            let scale = (area.width / width).min(area.height / height);
+            (width * scale, height * scale)
+        }
+        (BackgroundSize::Cover | BackgroundSize::Contain, None) => (area.width, area.height),
+        (BackgroundSize::Size(width, height), intrinsic) => {
+            let width = width.map(|width| width.resolve(area.width));
+            let height = height.map(|height| height.resolve(area.height));
+
+            match (width, height, intrinsic) {
+                (Some(width), Some(height), _) => (width, height),
+                (Some(width), None, Some((w, h))) => (width, width * h / w),
+                (None, Some(height), Some((w, h))) => (height * w / h, height),
+                (None, None, Some(size)) => size,
+                (width, height, None) => (width.unwrap_or(area.width), height.unwrap_or(area.height)),
+            }
+        }
+    }
+}
+
+/// Scales a tile, so a whole number of tiles fits in the area
+fn round_tile(tile: FP, area: FP) -> FP {
+    if tile <= 0.0 {
+        return tile;
+    }
+
+    area / (area / tile).round().max(1.0)
+}
+
+/// Returns the offsets of the tiles on one axis, that are needed to cover the painting area. `position` is the
+/// offset of the tile that is placed by `background-position`
+fn tile_offsets(repeat: Repeat, area: (FP, FP), tile: FP, position: FP, clip: (FP, FP)) -> Vec<FP> {
+    let (area_start, area_len) = area;
+    let (clip_start, clip_len) = clip;
+
+    if tile <= 0.0 {
+        return Vec::new();
+    }
+
+    let (start, step) = match repeat {
+        Repeat::NoRepeat => return vec![position],
+        Repeat::Repeat | Repeat::Round => (position, tile),
+        Repeat::Space => {
+            let count = (area_len / tile).floor();
+
+            // When the image doesn't fit twice, it is placed like it isn't repeated
+            if count < 2.0 {
+                return vec![position];
+            }
+
+            let gap = (area_len - count * tile) / (count - 1.0);
+
+            (area_start, tile + gap)
+        }
+    };
+
+    let first = start - ((start - clip_start) / step).ceil() * step;
+    let end = clip_start + clip_len;
+
+    let count = ((end - first) / step).ceil().max(0.0) as usize;
+
+    (0..count.min(MAX_TILES))
+        .map(|idx| first + idx as FP * step)
+        .filter(|offset| offset + tile > clip_start)
+        .collect()
+}
+
+/// Returns the radii of a box inside the border box. The radii are `(top-left, top-right, bottom-left, bottom-right)`
+/// like `BorderRadius` expects them, and shrink with the distance to the border box
+fn inner_radii(radii: Radii, (top, right, bottom, left): (FP, FP, FP, FP)) -> Radii {
+    (
+        (radii.0 - top.max(left)).max(0.0),
+        (radii.1 - top.max(right)).max(0.0),
+        (radii.2 - bottom.max(left)).max(0.0),
+        (radii.3 - bottom.max(right)).max(0.0),
+    )
+}
+
+/// The border, padding and content box of an element
+pub struct Boxes {
+    border: Area,
+    /// Width of the border on the top, right, bottom and left side
+    border_widths: (FP, FP, FP, FP),
+    /// Distance between the padding box and the content box
+    padding: (FP, FP, FP, FP),
+}
+
+impl Boxes {
+    pub fn new(layout: &impl Layout, pos: Point) -> Self {
+        let size = layout.size();
+
+        let border = layout.border();
+        let padding = layout.padding();
+
+        // Layouts return the sides as (top, right, bottom, left) in (x1, y1, x2, y2)
+        Self {
+            border: Area::new(pos.x, pos.y, size.width, size.height),
+            border_widths: (border.x1, border.y1, border.x2, border.y2),
+            padding: (padding.x1, padding.y1, padding.x2, padding.y2),
+        }
+    }
+
+    pub fn area(&self, visual_box: VisualBox) -> Area {
+        match visual_box {
+            VisualBox::Border => self.border,
+            VisualBox::Padding => self.border.inset(self.border_widths),
+            VisualBox::Content => self.border.inset(self.border_widths).inset(self.padding),
+        }
+    }
+
+    pub fn radii(&self, visual_box: VisualBox, radii: Radii) -> Radii {
+        let (top, right, bottom, left) = self.border_widths;
+
+        match visual_box {
+            VisualBox::Border => radii,
+            VisualBox::Padding => inner_radii(radii, self.border_widths),
+            VisualBox::Content => inner_radii(
+                radii,
+                (
+                    top + self.padding.0,
+                    right + self.padding.1,
+                    bottom + self.padding.2,
+                    left + self.padding.3,
+                ),
+            ),
+        }
+    }
+}
+
+/// Paints the background color and the background layers of an element at `pos`. Layers with
+/// `background-attachment: fixed` are positioned in the `viewport`, which is the visible area of the scene. Returns the
+/// size of the first background image, when the element doesn't have a size yet
+pub fn render_background<B: RenderBackend, L: Layouter, C: CssSystem>(
+    node: &RenderTreeNode<L, C>,
+    scene: &mut B::Scene,
+    pos: Point,
+    radii: Radii,
+    viewport: Area,
+    svg: &mut B::SVGRenderer,
+    fetcher: &Fetcher,
+) -> Option<SizeU32> {
+    let boxes = Boxes::new(&node.layout, pos);
+    let layers = layers::<Value<C>, _>(&node.properties);
+
+    // The color is clipped like the bottom layer
+    if let Some(Clip::Box(clip)) = layers.last().map(|layer| layer.clip) {
+        let color = node
+            .properties
+            .get("background-color")
+            .and_then(|prop| prop.parse_color())
+            .map(|color| B::Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8));
+
+        if let Some(color) = color {
+            let rect = RenderRect {
+                rect: boxes.area(clip).rect::<B>(),
+                transform: None,
+                radius: Some(B::BorderRadius::from(boxes.radii(clip, radii))),
+                brush: Brush::color(color),
+                brush_transform: None,
+                border: None,
+            };
+
+            scene.draw_rect(&rect);
+        }
+    }
+
+    let mut img_size = None;
+
+    for layer in layers.iter().rev() {
+        let (Some(image), Clip::Box(clip)) = (&layer.image, layer.clip) else {
+            continue;
+        };
+
+        let area = if layer.fixed {
+            viewport
+        } else {
+            boxes.area(layer.origin)
+        };
+
+        let clip_area = boxes.area(clip);
+        let clip_radii = boxes.radii(clip, radii);
+
+        match image {
+            BackgroundImage::Url(url) => {
+                let img = match request_img(fetcher, svg, url, None) {
+                    Ok(img) => img,
+                    Err(e) => {
+                        eprintln!("Error loading image: {:?}", e);
+                        continue;
+                    }
+                };
+
+                if node.layout.size_or().is_none() && img_size.is_none() {
+                    img_size = Some(img.size());
+                }
+
+                let intrinsic = img.size_tuple();
+                let tiles = tiles(layer, Some(intrinsic), area, clip_area);
+
+                let scale = |tile: Area| {
+                    B::Transform::scale_xy(tile.width / intrinsic.0, tile.height / intrinsic.1)
+                        .then_translate(tile.x, tile.y)
+                };
+
+                match img {
+                    ImageBuffer::Image(img) => {
+                        let brush = B::Brush::image(img);
+
+                        for tile in tiles {
+                            paint_tile::<B>(scene, &brush, Some(scale(tile)), tile, clip_area, clip_radii);
+                        }
+                    }
+                    ImageBuffer::Scene(img, _) => {
+                        // Scenes can't be clipped per tile, so all tiles are clipped at once
+                        let mut tiled = B::Scene::new();
+                        for tile in tiles {
+                            tiled.apply_scene(&img, Some(scale(tile)));
+                        }
+
+                        let mut layer = RenderLayer::new();
+                        layer.clip = Some(clip_area.rect::<B>());
+                        layer.clip_radius = Some(B::BorderRadius::from(clip_radii));
+
+                        scene.apply_layer(&tiled, &layer);
+                    }
+                }
+            }
+            BackgroundImage::Gradient(name, args) => {
+                let tiles = tiles(layer, None, area, clip_area);
+
+                let Some(tile) = tiles.first() else {
+                    continue;
+                };
+
+                // The gradient is created for a tile at the origin, and moved to every tile with the brush transform
+                let Some(gradient) = parse_gradient::<B, _>(name, args, Point::ZERO, tile.width, tile.height) else {
+                    continue;
+                };
+
+                let brush = B::Brush::gradient(gradient.gradient);
+
+                for tile in tiles {
+                    let transform = match gradient.transform.clone() {
+                        Some(transform) => transform.then_translate(tile.x, tile.y),
+                        None => B::Transform::translate(tile.x, tile.y),
+                    };
+
+                    paint_tile::<B>(scene, &brush, Some(transform), tile, clip_area, clip_radii);
+                }
+            }
+        }
+    }
+
+    img_size
+}
+
+/// Returns the areas of the tiles of a layer that are visible in the clip area. When there are more than `MAX_TILES`
+/// of them, only the first rows are returned
+fn tiles<V>(layer: &Layer<V>, intrinsic: Option<(FP, FP)>, area: Area, clip: Area) -> Vec<Area> {
+    let (mut width, mut height) = tile_size(layer.size, intrinsic, area);
+
+    let (repeat_x, repeat_y) = layer.repeat;
+
+    if repeat_x == Repeat::Round {
+        let rounded = round_tile(width, area.width);
+
+        // An auto height keeps the aspect ratio, when only the width is rounded
+        if repeat_y != Repeat::Round && matches!(layer.size, BackgroundSize::Size(_, None)) && width > 0.0 {
+            height *= rounded / width;
+        }
+
+        width = rounded;
+    }
+
+    if repeat_y == Repeat::Round {
+        let rounded = round_tile(height, area.height);
+
+        if repeat_x != Repeat::Round && matches!(layer.size, BackgroundSize::Size(None, _)) && height > 0.0 {
+            width *= rounded / height;
+        }
+
+        height = rounded;
+    }
+
+    if width <= 0.0 || height <= 0.0 {
+        return Vec::new();
+    }
+
+    let x = area.x + layer.position.0.px + (area.width - width) * layer.position.0.percent / 100.0;
+    let y = area.y + layer.position.1.px + (area.height - height) * layer.position.1.percent / 100.0;
+
+    let xs = tile_offsets(repeat_x, (area.x, area.width), width, x, (clip.x, clip.width));
+    let mut ys = tile_offsets(repeat_y, (area.y, area.height), height, y, (clip.y, clip.height));
+
+    ys.truncate((MAX_TILES / xs.len().max(1)).max(1));
+
+    ys.iter()
+        .flat_map(|y| xs.iter().map(move |x| Area::new(*x, *y, width, height)))
+        .collect()
+}
+
+/// Paints a single tile, clipped to the clip area. A tile that covers the whole clip area gets its rounded corners
+fn paint_tile<B: RenderBackend>(
+    scene: &mut B::Scene,
+    brush: &B::Brush,
+    brush_transform: Option<B::Transform>,
+    tile: Area,
+    clip: Area,
+    clip_radii: Radii,
+) {
+    let (area, radius) = if tile.contains(clip) {
+        (clip, Some(B::BorderRadius::from(clip_radii)))
+    } else {
+        match tile.intersect(clip) {
+            Some(area) => (area, None),
+            None => return,
+        }
+    };
+
+    let rect = RenderRect {
+        rect: area.rect::<B>(),
+        transform: None,
+        radius,
+        brush: brush.clone(),
+        brush_transform,
+        border: None,
+    };
+
+    scene.draw_rect(&rect);
+}
+
+/// Returns true when the element has a background layer with `background-attachment: fixed`, which has to be painted
+/// again when the document scrolls
+pub fn has_fixed_background<C: CssSystem>(properties: &C::PropertyMap) -> bool {
+    layers::<Value<C>, _>(properties)
+        .iter()
+        .any(|layer| layer.fixed && layer.image.is_some())
+}
+
+/// Returns true when the background of the element is painted behind its text, with `background-clip: text`
+pub fn clips_to_text<C: CssSystem>(properties: &C::PropertyMap) -> bool {
+    layers::<Value<C>, _>(properties)
+        .iter()
+        .any(|layer| layer.clip == Clip::Text)
+}
+
+/// Returns the brush for text that is painted with the background of an element with `background-clip: text`. The
+/// element is at `pos`, and the brush is created for text at `text_pos`
+pub fn text_brush<B: RenderBackend, L: Layouter, C: CssSystem>(
+    node: &RenderTreeNode<L, C>,
+    pos: Point,
+    text_pos: Point,
+) -> Option<B::Brush> {
+    let boxes = Boxes::new(&node.layout, pos);
+    let layers = layers::<Value<C>, _>(&node.properties);
+
+    // Text is painted with a single brush, so only the top gradient is used
+    //TODO: images and multiple layers behind text
+    let gradient = layers.iter().find_map(|layer| match (&layer.image, layer.clip) {
+        (Some(BackgroundImage::Gradient(name, args)), Clip::Text) => {
+            let area = boxes.area(layer.origin);
+            let origin = Point::new(area.x - text_pos.x, area.y - text_pos.y);
+
+            parse_gradient::<B, _>(name, args, origin, area.width, area.height)
+        }
+        _ => None,
+    });
+
+    if let Some(gradient) = gradient {
+        return Some(B::Brush::gradient(gradient.gradient));
+    }
+
+    if layers.last().map(|layer| layer.clip) != Some(Clip::Text) {
+        return None;
+    }
+
+    node.properties
+        .get("background-color")
+        .and_then(|prop| prop.parse_color())
+        .map(|color| {
+            B::Brush::color(B::Color::rgba(
+                color.0 as u8,
+                color.1 as u8,
+                color.2 as u8,
+                color.3 as u8,
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_css3::stylesheet::CssValue;
+
+    use super::*;
+
+    fn value(s: &str) -> CssValue {
+        CssValue::String(s.to_string())
+    }
+
+    #[test]
+    fn positions() {
+        assert_eq!(
+            parse_position(&[value("top")]),
+            Some((Length::CENTER, Length::percent(0.0)))
+        );
+        assert_eq!(
+            parse_position(&[value("bottom"), value("left")]),
+            Some((Length::percent(0.0), Length::percent(100.0)))
+        );
+        assert_eq!(
+            parse_position(&[
+                value("right"),
+                CssValue::Unit(10.0, "px".to_string()),
+                value("top"),
+                CssValue::Percentage(20.0)
+            ]),
+            Some((
+                Length {
+                    px: -10.0,
+                    percent: 100.0
+                },
+                Length::percent(20.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn tile_sizes() {
+        let area = Area::new(0.0, 0.0, 200.0, 100.0);
+
+        assert_eq!(
+            tile_size(BackgroundSize::Cover, Some((50.0, 50.0)), area),
+            (200.0, 200.0)
+        );
+        assert_eq!(
+            tile_size(BackgroundSize::Contain, Some((50.0, 50.0)), area),
+            (100.0, 100.0)
+        );
+        assert_eq!(tile_size(BackgroundSize::Contain, None, area), (200.0, 100.0));
+
+        let width = BackgroundSize::Size(Some(Length::percent(50.0)), None);
+        assert_eq!(tile_size(width, Some((50.0, 25.0)), area), (100.0, 50.0));
+        assert_eq!(tile_size(width, None, area), (100.0, 100.0));
+
+        let auto = BackgroundSize::Size(None, None);
+        assert_eq!(tile_size(auto, Some((30.0, 40.0)), area), (30.0, 40.0));
+    }
+
+    #[test]
+    fn tiles_cover_clip_area() {
+        assert_eq!(
+            tile_offsets(Repeat::Repeat, (0.0, 100.0), 30.0, 50.0, (0.0, 100.0)),
+            vec![-10.0, 20.0, 50.0, 80.0]
+        );
+        assert_eq!(
+            tile_offsets(Repeat::NoRepeat, (0.0, 100.0), 30.0, 50.0, (0.0, 100.0)),
+            vec![50.0]
+        );
+        assert_eq!(
+            tile_offsets(Repeat::Space, (0.0, 100.0), 30.0, 50.0, (0.0, 100.0)),
+            vec![0.0, 35.0, 70.0]
+        );
+        assert_eq!(round_tile(30.0, 100.0), 100.0 / 3.0);
+    }
+
+    #[test]
+    fn many_small_tiles() {
+        let layer = Layer::<CssValue> {
+            image: None,
+            position: (Length::percent(0.0), Length::percent(0.0)),
+            size: BackgroundSize::Size(None, None),
+            repeat: (Repeat::Repeat, Repeat::Repeat),
+            origin: VisualBox::Padding,
+            clip: Clip::Box(VisualBox::Border),
+            fixed: false,
+        };
+
+        let area = Area::new(0.0, 0.0, 1000.0, 1000.0);
+        let tiles = tiles(&layer, Some((1.0, 1.0)), area, area);
+
+        assert_eq!(tiles.len(), MAX_TILES);
+        assert_eq!(tiles.first(), Some(&Area::new(0.0, 0.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn repeat_values() {
+        assert_eq!(parse_repeat(&[value("repeat-x")]), (Repeat::Repeat, Repeat::NoRepeat));
+        assert_eq!(parse_repeat(&[value("space")]), (Repeat::Space, Repeat::Space));
+        assert_eq!(
+            parse_repeat(&[value("round"), value("no-repeat")]),
+            (Repeat::Round, Repeat::NoRepeat)
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Loading of web fonts (<https://www.w3.org/TR/css-fonts-4/#font-face-loading>). Layout requests the faces it
+//! needs from the web font registry, and the faces are loaded in the background, so the page is painted with a
+//! fallback font (or without text during the block period) until they are available.
+
+use log::warn;
+use url::Url;
+
+use gosub_net::http::fetcher::Fetcher;
+use gosub_shared::font::FontSource;
+use gosub_shared::traits::css3::{CssStylesheet, CssSystem};
+use gosub_shared::traits::document::Document;
+use gosub_typeface::web_fonts::{FontRequest, WEB_FONTS};
+use gosub_typeface::woff::decode_font;
+
+/// Collects the `@font-face` rules of the stylesheets of a document into the web font registry
+pub fn register_font_faces<D: Document<C>, C: CssSystem>(doc: &D) {
+    let doc_url = doc.url().map(|url| url.to_string()).unwrap_or_default();
+
+    let faces = doc
+        .stylesheets()
+        .iter()
+        .flat_map(|sheet| {
+            // The user agent stylesheet is not loaded from a url, its sources would be relative to the document
+            let base_url = match Url::parse(sheet.url()) {
+                Ok(_) => sheet.url().to_string(),
+                Err(_) => doc_url.clone(),
+            };
+
+            sheet
+                .font_faces()
+                .iter()
+                .map(move |face| (face.clone(), base_url.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    match WEB_FONTS.lock() {
+        Ok(mut web_fonts) => web_fonts.set_faces(faces),
+        Err(e) => warn!("Failed to register font faces: {e}"),
+    }
+}
+
+/// Starts loading the faces that layout requested, every face is loaded on its own thread
+pub fn load_requested_fonts() {
+    let requests = match WEB_FONTS.lock() {
+        Ok(mut web_fonts) => web_fonts.take_requests(),
+        Err(_) => return,
+    };
+
+    for request in requests {
+        std::thread::spawn(move || {
+            let data = load_face(&request);
+
+            if let Ok(mut web_fonts) = WEB_FONTS.lock() {
+                web_fonts.finish(request.id, data);
+            }
+        });
+    }
+}
+
+/// Returns true while faces are loading, the document needs to be drawn again when they are done
+pub fn fonts_pending() -> bool {
+    WEB_FONTS.lock().is_ok_and(|web_fonts| web_fonts.pending())
+}
+
+/// Tries the url sources of a face in order, and returns the first one that can be decoded
+fn load_face(request: &FontRequest) -> Option<Vec<u8>> {
+    let base = Url::parse(&request.base_url).ok()?;
+    let fetcher = Fetcher::new(base.clone());
+
+    for source in &request.sources {
+        let FontSource::Url { url, .. } = source else {
+            continue;
+        };
+
+        if !source.is_supported() {
+            continue;
+        }
+
+        let Ok(url) = base.join(url) else {
+            warn!("Invalid font url: {url}");
+            continue;
+        };
+
+        let res = match fetcher.get_url(&url) {
+            Ok(res) if res.is_ok() => res,
+            Ok(res) => {
+                warn!("Could not load font {url}. Status code {}", res.status);
+                continue;
+            }
+            Err(e) => {
+                warn!("Could not load font {url}: {e}");
+                continue;
+            }
+        };
+
+        match decode_font(res.body) {
+            Some(data) => return Some(data),
+            None => warn!("Unsupported font format: {url}"),
+        }
+    }
+
+    None
+}
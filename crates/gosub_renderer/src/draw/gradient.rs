@@ -0,0 +1,538 @@
+//! CSS gradients (<https://www.w3.org/TR/css-images-3/#gradients>), painted with the gradients of the render backend
+
+use gosub_render_backend::geo::{Point, FP};
+use gosub_render_backend::{Color, ColorStop, ColorStops, Gradient, RenderBackend, Transform};
+use gosub_shared::traits::css3::CssValue;
+
+use crate::draw::background::{parse_position, Length};
+
+/// Number of stops that are used to approximate the interpolation curve of a color hint
+const HINT_STEPS: usize = 8;
+
+type Rgba = (f32, f32, f32, f32);
+
+/// Parses an `<angle>` and returns it in degrees
+fn parse_angle<V: CssValue>(value: &V) -> Option<FP> {
+    if let Some((value, unit)) = value.as_unit() {
+        return match unit {
+            "deg" => Some(value),
+            "rad" => Some(value.to_degrees()),
+            "grad" => Some(value * 0.9),
+            "turn" => Some(value * 360.0),
+            _ => None,
+        };
+    }
+
+    value.as_number().filter(|num| *num == 0.0)
+}
+
+/// A color stop or a color hint (when there is no color) as written in the gradient. The position is a fraction of
+/// the gradient line
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stop {
+    color: Option<Rgba>,
+    position: Option<FP>,
+}
+
+/// A gradient, ready to be painted into the box it belongs to
+pub struct CssGradient<B: RenderBackend> {
+    pub gradient: B::Gradient,
+    /// Transform of the brush, radial gradients are elliptical by scaling a circular gradient
+    pub transform: Option<B::Transform>,
+}
+
+/// Parses a gradient function (`linear-gradient()`, `radial-gradient()`, `conic-gradient()` or one of their
+/// `repeating-*` variants) for a box at `origin` with the given size. Returns `None` when the value isn't a valid
+/// gradient
+pub fn parse_gradient<B: RenderBackend, V: CssValue>(
+    name: &str,
+    args: &[V],
+    origin: Point,
+    width: FP,
+    height: FP,
+) -> Option<CssGradient<B>> {
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+
+    let (repeating, name) = match name.strip_prefix("repeating-") {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+
+    let groups = args.split(|arg| arg.is_comma()).collect::<Vec<_>>();
+
+    let (prelude, stops) = match groups.split_first() {
+        Some((first, rest)) if is_prelude(first) => (*first, rest),
+        _ => (&[][..], &groups[..]),
+    };
+
+    let (gradient, transform) = match name {
+        "linear-gradient" => {
+            let angle = match prelude {
+                [] => 180.0,
+                [to, sides @ ..] if to.as_string() == Some("to") => side_angle(sides, width, height)?,
+                [angle] => parse_angle(angle)?,
+                _ => return None,
+            };
+
+            // The gradient line goes through the center, and is long enough for the corners to get the first and
+            // last color
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let length = (width * sin).abs() + (height * cos).abs();
+
+            let stops = parse_stops(stops, |value| {
+                Length::parse(value).map(|len| len.resolve(length) / length)
+            })?;
+            let (stops, first, last) = resolve_stops(stops, repeating)?;
+
+            let center = Point::new(origin.x + width / 2.0, origin.y + height / 2.0);
+            let direction = Point::new(sin * length, -cos * length);
+
+            let point = |offset: FP| {
+                Point::new(
+                    center.x + direction.x * (offset - 0.5),
+                    center.y + direction.y * (offset - 0.5),
+                )
+            };
+
+            (B::Gradient::new_linear(point(first), point(last), stops), None)
+        }
+        "radial-gradient" => {
+            let (shape, at) = match prelude.iter().position(|value| value.as_string() == Some("at")) {
+                Some(idx) => (&prelude[..idx], parse_position(&prelude[idx + 1..])?),
+                None => (prelude, (Length::CENTER, Length::CENTER)),
+            };
+
+            let center = Point::new(at.0.resolve(width), at.1.resolve(height));
+            let (rx, ry) = radial_size(shape, center, width, height)?;
+
+            if rx <= 0.0 || ry <= 0.0 {
+                return None;
+            }
+
+            let stops = parse_stops(stops, |value| Length::parse(value).map(|len| len.resolve(rx) / rx))?;
+            let (stops, first, last) = resolve_stops(stops, repeating)?;
+
+            let center = Point::new(origin.x + center.x, origin.y + center.y);
+
+            // Elliptical gradients are drawn as a circle with the horizontal radius, that is scaled vertically
+            let transform = (rx != ry).then(|| {
+                B::Transform::translate(-center.x, -center.y)
+                    .then_scale_xy(1.0, ry / rx)
+                    .then_translate(center.x, center.y)
+            });
+
+            (
+                B::Gradient::new_radial_two_point(center, first * rx, center, last * rx, stops),
+                transform,
+            )
+        }
+        "conic-gradient" => {
+            let (from, at) = parse_conic_prelude(prelude)?;
+
+            let stops = parse_stops(stops, |value| {
+                value
+                    .as_percentage()
+                    .map(|percent| percent / 100.0)
+                    .or_else(|| parse_angle(value).map(|angle| angle / 360.0))
+            })?;
+            let (stops, first, last) = resolve_stops(stops, repeating)?;
+
+            let center = Point::new(origin.x + at.0.resolve(width), origin.y + at.1.resolve(height));
+
+            // Conic gradients start at the top, sweep gradients at the positive x-axis
+            let start = from - 90.0;
+
+            (
+                B::Gradient::new_sweep(center, start + first * 360.0, start + last * 360.0, stops),
+                None,
+            )
+        }
+        _ => return None,
+    };
+
+    let gradient = if repeating { gradient.repeat() } else { gradient };
+
+    Some(CssGradient { gradient, transform })
+}
+
+/// Returns true when the first argument of the gradient describes the gradient, instead of being a color stop
+fn is_prelude<V: CssValue>(values: &[V]) -> bool {
+    let Some(first) = values.first() else {
+        return false;
+    };
+
+    match first.as_string() {
+        Some(keyword) => matches!(
+            keyword,
+            "to" | "at"
+                | "from"
+                | "circle"
+                | "ellipse"
+                | "closest-side"
+                | "closest-corner"
+                | "farthest-side"
+                | "farthest-corner"
+        ),
+        None => !may_be_color(first),
+    }
+}
+
+/// Colors are either keywords, colors or color functions, so anything else is a length, percentage or angle
+fn may_be_color<V: CssValue>(value: &V) -> bool {
+    value.as_string().is_some() || value.as_color().is_some() || value.as_function().is_some()
+}
+
+/// Returns the angle of a `to <side-or-corner>` linear gradient in degrees. For corners the angle depends on the
+/// size of the box, so the gradient line is perpendicular to the diagonal between the neighbouring corners
+fn side_angle<V: CssValue>(sides: &[V], width: FP, height: FP) -> Option<FP> {
+    let mut x: FP = 0.0;
+    let mut y: FP = 0.0;
+
+    for side in sides {
+        match side.as_string()? {
+            "left" => x = -1.0,
+            "right" => x = 1.0,
+            "top" => y = -1.0,
+            "bottom" => y = 1.0,
+            _ => return None,
+        }
+    }
+
+    match (x == 0.0, y == 0.0) {
+        (true, true) => None,
+        (false, true) | (true, false) => Some(x.atan2(-y).to_degrees().rem_euclid(360.0)),
+        (false, false) => Some((x * height).atan2(-y * width).to_degrees().rem_euclid(360.0)),
+    }
+}
+
+/// Returns the horizontal and vertical radius of a radial gradient with the given `<radial-shape>` and
+/// `<radial-size>` values
+fn radial_size<V: CssValue>(values: &[V], center: Point, width: FP, height: FP) -> Option<(FP, FP)> {
+    let mut circle = None;
+    let mut extent = "farthest-corner";
+    let mut lengths = Vec::new();
+
+    for value in values {
+        match value.as_string() {
+            Some("circle") => circle = Some(true),
+            Some("ellipse") => circle = Some(false),
+            Some(keyword @ ("closest-side" | "closest-corner" | "farthest-side" | "farthest-corner")) => {
+                extent = keyword
+            }
+            _ => lengths.push(Length::parse(value)?),
+        }
+    }
+
+    let circle = circle.unwrap_or(lengths.len() == 1);
+
+    match lengths[..] {
+        [radius] if circle && radius.percent == 0.0 => return Some((radius.px, radius.px)),
+        [rx, ry] if !circle => return Some((rx.resolve(width), ry.resolve(height))),
+        [] => {}
+        _ => return None,
+    }
+
+    let dx = [center.x.abs(), (width - center.x).abs()];
+    let dy = [center.y.abs(), (height - center.y).abs()];
+
+    let (closest, (sx, sy)) = match extent {
+        "closest-side" | "closest-corner" => (true, (dx[0].min(dx[1]), dy[0].min(dy[1]))),
+        _ => (false, (dx[0].max(dx[1]), dy[0].max(dy[1]))),
+    };
+
+    Some(match (extent, circle) {
+        ("closest-side" | "farthest-side", true) => {
+            let radius = if closest { sx.min(sy) } else { sx.max(sy) };
+            (radius, radius)
+        }
+        ("closest-side" | "farthest-side", false) => (sx, sy),
+        (_, true) => {
+            let radius = sx.hypot(sy);
+            (radius, radius)
+        }
+        // The ellipse goes through the corner, with the same aspect ratio as for the side
+        (_, false) => (sx * std::f32::consts::SQRT_2, sy * std::f32::consts::SQRT_2),
+    })
+}
+
+/// Parses `[ from <angle> ]? [ at <position> ]?` of a conic gradient
+fn parse_conic_prelude<V: CssValue>(values: &[V]) -> Option<(FP, (Length, Length))> {
+    let mut from = 0.0;
+    let mut at = (Length::CENTER, Length::CENTER);
+
+    let mut values = values;
+
+    if let [keyword, angle, rest @ ..] = values {
+        if keyword.as_string() == Some("from") {
+            from = parse_angle(angle)?;
+            values = rest;
+        }
+    }
+
+    if let [keyword, position @ ..] = values {
+        if keyword.as_string() != Some("at") {
+            return None;
+        }
+
+        at = parse_position(position)?;
+    } else if !values.is_empty() {
+        return None;
+    }
+
+    Some((from, at))
+}
+
+/// Parses the color stops and color hints. The position of a stop is converted to a fraction of the gradient line
+/// with `position`
+fn parse_stops<V: CssValue>(groups: &[&[V]], position: impl Fn(&V) -> Option<FP>) -> Option<Vec<Stop>> {
+    let mut stops = Vec::with_capacity(groups.len());
+
+    for group in groups {
+        match group {
+            [hint] if !may_be_color(hint) => {
+                stops.push(Stop {
+                    color: None,
+                    position: Some(position(hint)?),
+                });
+            }
+            [color, positions @ ..] if positions.len() <= 2 => {
+                let color = Some(color.as_color()?);
+
+                if positions.is_empty() {
+                    stops.push(Stop { color, position: None });
+                }
+
+                for value in *positions {
+                    stops.push(Stop {
+                        color,
+                        position: Some(position(value)?),
+                    });
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(stops)
+}
+
+/// Resolves the positions of the stops, and converts them to the stops of the backend. The offsets of the stops are
+/// relative to the first and last stop, which are returned as well, so the gradient can be placed between them.
+fn resolve_stops<B: RenderBackend>(stops: Vec<Stop>, repeating: bool) -> Option<(ColorStops<B>, FP, FP)> {
+    let stops = resolve_positions(stops)?;
+
+    let first = stops.first()?.0;
+    let last = stops.last()?.0;
+
+    let to_color = |color: Rgba| B::Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8);
+
+    // A gradient without a length has no colors to interpolate, a repeating gradient uses the average color
+    if last - first <= FP::EPSILON {
+        let color = if repeating {
+            average_color(&stops)
+        } else {
+            stops.last()?.1
+        };
+
+        let stops = [0.0, 1.0]
+            .into_iter()
+            .map(|offset| ColorStop {
+                offset,
+                color: to_color(color),
+            })
+            .collect();
+
+        return Some((stops, first, first + 1.0));
+    }
+
+    let stops = stops
+        .into_iter()
+        .map(|(offset, color)| ColorStop {
+            offset: (offset - first) / (last - first),
+            color: to_color(color),
+        })
+        .collect();
+
+    Some((stops, first, last))
+}
+
+/// Fixes up the positions of the stops as described in
+/// <https://www.w3.org/TR/css-images-3/#color-stop-fixup>, and replaces the color hints with stops that follow the
+/// interpolation curve of the hint
+fn resolve_positions(mut stops: Vec<Stop>) -> Option<Vec<(FP, Rgba)>> {
+    let color_stops = stops.iter().filter(|stop| stop.color.is_some()).count();
+
+    // A gradient needs at least one color, and hints must be between two colors
+    if color_stops == 0 || stops.first()?.color.is_none() || stops.last()?.color.is_none() {
+        return None;
+    }
+
+    if let Some(first) = stops.first_mut() {
+        first.position.get_or_insert(0.0);
+    }
+
+    if let Some(last) = stops.last_mut() {
+        last.position.get_or_insert(1.0);
+    }
+
+    // A position can't be smaller than any position before it
+    let mut max = FP::MIN;
+    for stop in &mut stops {
+        if let Some(position) = &mut stop.position {
+            *position = position.max(max);
+            max = *position;
+        }
+    }
+
+    // Stops without a position are spread evenly between the stops around them
+    let mut idx = 0;
+    while idx < stops.len() {
+        if stops[idx].position.is_some() {
+            idx += 1;
+            continue;
+        }
+
+        let start = idx - 1;
+        let end = (idx..stops.len()).find(|idx| stops[*idx].position.is_some())?;
+
+        let from = stops[start].position?;
+        let to = stops[end].position?;
+
+        for (n, stop) in stops[idx..end].iter_mut().enumerate() {
+            stop.position = Some(from + (to - from) * (n + 1) as FP / (end - start) as FP);
+        }
+
+        idx = end;
+    }
+
+    let mut resolved = Vec::with_capacity(stops.len());
+
+    for (idx, stop) in stops.iter().enumerate() {
+        let position = stop.position?;
+
+        if let Some(color) = stop.color {
+            resolved.push((position, color));
+            continue;
+        }
+
+        let (Some(&(from, from_color)), Some(next)) = (resolved.last(), stops.get(idx + 1)) else {
+            return None;
+        };
+
+        let to = next.position?;
+        let to_color = next.color?;
+
+        if to - from <= FP::EPSILON {
+            continue;
+        }
+
+        let hint = (position - from) / (to - from);
+
+        if hint <= 0.0 {
+            resolved.push((from, to_color));
+            continue;
+        }
+
+        if hint >= 1.0 {
+            resolved.push((to, from_color));
+            continue;
+        }
+
+        // The color at the hint is halfway between the two colors
+        let exponent = 0.5f32.ln() / hint.ln();
+
+        for step in 1..HINT_STEPS {
+            let t = step as FP / HINT_STEPS as FP;
+
+            resolved.push((from + (to - from) * t, mix(from_color, to_color, t.powf(exponent))));
+        }
+    }
+
+    Some(resolved)
+}
+
+fn mix(from: Rgba, to: Rgba, t: f32) -> Rgba {
+    (
+        from.0 + (to.0 - from.0) * t,
+        from.1 + (to.1 - from.1) * t,
+        from.2 + (to.2 - from.2) * t,
+        from.3 + (to.3 - from.3) * t,
+    )
+}
+
+fn average_color(stops: &[(FP, Rgba)]) -> Rgba {
+    let count = stops.len().max(1) as f32;
+
+    let sum = stops.iter().fold((0.0, 0.0, 0.0, 0.0), |sum, (_, color)| {
+        (sum.0 + color.0, sum.1 + color.1, sum.2 + color.2, sum.3 + color.3)
+    });
+
+    (sum.0 / count, sum.1 / count, sum.2 / count, sum.3 / count)
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_css3::stylesheet::CssValue;
+
+    use super::*;
+
+    const RED: Rgba = (255.0, 0.0, 0.0, 255.0);
+    const BLUE: Rgba = (0.0, 0.0, 255.0, 255.0);
+
+    fn stop(color: Option<Rgba>, position: Option<FP>) -> Stop {
+        Stop { color, position }
+    }
+
+    #[test]
+    fn missing_positions_are_spread_evenly() {
+        let stops = vec![
+            stop(Some(RED), None),
+            stop(Some(BLUE), None),
+            stop(Some(RED), None),
+            stop(Some(BLUE), Some(0.75)),
+        ];
+
+        let positions = resolve_positions(stops)
+            .unwrap()
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+
+        assert_eq!(positions, vec![0.0, 0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn positions_are_clamped_to_previous_stops() {
+        let stops = vec![stop(Some(RED), Some(0.5)), stop(Some(BLUE), Some(0.2))];
+
+        let positions = resolve_positions(stops).unwrap();
+
+        assert_eq!(positions, vec![(0.5, RED), (0.5, BLUE)]);
+    }
+
+    #[test]
+    fn color_hints() {
+        let stops = vec![stop(Some(RED), None), stop(None, Some(0.5)), stop(Some(BLUE), None)];
+        let resolved = resolve_positions(stops).unwrap();
+
+        assert_eq!(resolved.len(), HINT_STEPS + 1);
+        assert_eq!(resolved[HINT_STEPS / 2], (0.5, mix(RED, BLUE, 0.5)));
+
+        assert!(resolve_positions(vec![stop(None, Some(0.5)), stop(Some(BLUE), None)]).is_none());
+    }
+
+    #[test]
+    fn corner_angles() {
+        let value = |s: &str| CssValue::String(s.to_string());
+
+        let angle = |sides: &[CssValue], width, height| side_angle(sides, width, height).unwrap();
+
+        assert!((angle(&[value("right")], 100.0, 50.0) - 90.0).abs() < 0.001);
+        assert!((angle(&[value("bottom")], 100.0, 50.0) - 180.0).abs() < 0.001);
+        assert!((angle(&[value("left")], 100.0, 50.0) - 270.0).abs() < 0.001);
+        assert!((angle(&[value("top"), value("right")], 100.0, 100.0) - 45.0).abs() < 0.001);
+        assert!((angle(&[value("bottom"), value("left")], 100.0, 100.0) - 225.0).abs() < 0.001);
+    }
+}
@@ -0,0 +1,132 @@
+//! Text that is selected by dragging the mouse. A selection covers whole text nodes, from the node where the drag
+//! started to the node under the cursor, and is painted with the style of `::selection`
+//! (<https://www.w3.org/TR/css-pseudo-4/#highlight-pseudos>)
+
+use gosub_render_backend::layout::Layouter;
+use gosub_render_backend::Color;
+use gosub_rendering::render_tree::{RenderNodeData, RenderTree};
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem};
+use gosub_shared::traits::document::Document;
+
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Node under the cursor when the mouse button was pressed, `None` when the mouse is not dragged
+    anchor: Option<NodeId>,
+    /// The selected text nodes in document order
+    nodes: Vec<NodeId>,
+}
+
+impl Selection {
+    /// Starts a new selection at the node under the cursor. Returns true when a previous selection was cleared
+    pub fn start(&mut self, anchor: Option<NodeId>) -> bool {
+        self.anchor = anchor;
+
+        !std::mem::take(&mut self.nodes).is_empty()
+    }
+
+    /// Stops dragging, the selected nodes stay selected until a new selection is started
+    pub fn end(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Extends the selection to the node under the cursor while dragging. Returns true when the selection changed
+    pub fn extend<L: Layouter, D: Document<C>, C: CssSystem>(
+        &mut self,
+        tree: &RenderTree<L, D, C>,
+        focus: NodeId,
+    ) -> bool {
+        let Some(anchor) = self.anchor else {
+            return false;
+        };
+
+        let mut order = Vec::new();
+        document_order(tree, tree.root, &mut order);
+
+        let subtree = |id: NodeId| {
+            order
+                .iter()
+                .position(|(node_id, _)| *node_id == id)
+                .map(|idx| (idx, order[idx].1))
+        };
+
+        let (Some(anchor), Some(focus)) = (subtree(anchor), subtree(focus)) else {
+            return false;
+        };
+
+        // The selection covers both subtrees and everything in between
+        let from = anchor.0.min(focus.0);
+        let to = anchor.1.max(focus.1);
+
+        let nodes = order[from..=to]
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| {
+                tree.get_node(*id)
+                    .is_some_and(|node| matches!(node.data, RenderNodeData::Text(_)))
+            })
+            .collect::<Vec<_>>();
+
+        if nodes == self.nodes {
+            return false;
+        }
+
+        self.nodes = nodes;
+
+        true
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains(&id)
+    }
+}
+
+/// Lists the nodes of a subtree in document order, together with the index of the last node of their own subtree
+fn document_order<L: Layouter, D: Document<C>, C: CssSystem>(
+    tree: &RenderTree<L, D, C>,
+    id: NodeId,
+    order: &mut Vec<(NodeId, usize)>,
+) {
+    let idx = order.len();
+    order.push((id, idx));
+
+    for child in tree.get_children(id).cloned().unwrap_or_default() {
+        document_order(tree, child, order);
+    }
+
+    order[idx].1 = order.len() - 1;
+}
+
+/// Returns the text color and the background color of `::selection` for a text node. Each property is taken from the
+/// nearest element that declares it in its `::selection`
+pub fn selection_colors<L: Layouter, D: Document<C>, C: CssSystem>(
+    tree: &RenderTree<L, D, C>,
+    id: NodeId,
+) -> (Option<Color>, Option<Color>) {
+    let mut color = None;
+    let mut background = None;
+
+    let mut parent = tree.get_node(id).and_then(|node| node.parent);
+
+    while let Some(parent_id) = parent {
+        let Some(node) = tree.get_node(parent_id) else {
+            break;
+        };
+
+        if let Some(selection) = node.properties.pseudo_element("selection") {
+            let parse = |name: &str| {
+                selection
+                    .get(name)
+                    .and_then(|prop| prop.parse_color())
+                    .map(|color| Color::rgba(color.0 as u8, color.1 as u8, color.2 as u8, color.3 as u8))
+            };
+
+            color = color.or_else(|| parse("color"));
+            background = background.or_else(|| parse("background-color"));
+        }
+
+        parent = node.parent;
+    }
+
+    (color, background)
+}
@@ -0,0 +1,237 @@
+//! Box shadows (<https://www.w3.org/TR/css-backgrounds-3/#box-shadow>) and text shadows
+//! (<https://www.w3.org/TR/css-text-decor-3/#text-shadow-property>).
+
+use gosub_render_backend::geo::{Point, FP};
+use gosub_render_backend::layout::Layouter;
+use gosub_render_backend::{Color, RenderBackend, RenderShadow, Scene as TScene};
+use gosub_rendering::render_tree::RenderTreeNode;
+use gosub_shared::traits::css3::{CssProperty, CssPropertyMap, CssSystem, CssValue};
+
+use crate::draw::background::{Area, Boxes, VisualBox};
+
+type Radii = (FP, FP, FP, FP);
+
+type Rgba = (f32, f32, f32, f32);
+
+/// A single shadow of a `box-shadow` or `text-shadow` list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub offset_x: FP,
+    pub offset_y: FP,
+    pub blur: FP,
+    pub spread: FP,
+    /// `None` is `currentcolor`
+    pub color: Option<Rgba>,
+    pub inset: bool,
+}
+
+impl Shadow {
+    /// Standard deviation of the gaussian blur, which is half the blur radius
+    pub fn std_dev(&self) -> FP {
+        self.blur / 2.0
+    }
+
+    pub fn color<B: RenderBackend>(&self, current: Rgba) -> B::Color {
+        let (r, g, b, a) = self.color.unwrap_or(current);
+        B::Color::rgba(r as u8, g as u8, b as u8, a as u8)
+    }
+}
+
+/// Parses a comma separated list of shadows. Shadows with less than two lengths are ignored
+pub fn parse_shadows<V: CssValue>(values: &[V]) -> Vec<Shadow> {
+    if values.len() == 1 && values[0].is_none() {
+        return Vec::new();
+    }
+
+    values
+        .split(|value| value.is_comma())
+        .filter_map(parse_shadow)
+        .collect()
+}
+
+fn parse_shadow<V: CssValue>(values: &[V]) -> Option<Shadow> {
+    let mut lengths = Vec::with_capacity(4);
+    let mut color = None;
+    let mut inset = false;
+
+    for value in values {
+        match value.as_string() {
+            Some(s) if s.eq_ignore_ascii_case("inset") => inset = true,
+            Some(s) if s.eq_ignore_ascii_case("currentcolor") => color = None,
+            _ if value.as_unit().is_some() || value.as_number() == Some(0.0) => lengths.push(value.unit_to_px()),
+            _ => color = Some(value.as_color()?),
+        }
+    }
+
+    if lengths.len() < 2 {
+        return None;
+    }
+
+    Some(Shadow {
+        offset_x: lengths[0],
+        offset_y: lengths[1],
+        blur: lengths.get(2).copied().unwrap_or(0.0).max(0.0),
+        spread: lengths.get(3).copied().unwrap_or(0.0),
+        color,
+        inset,
+    })
+}
+
+/// Returns the `color` of an element, which shadows without a color use
+pub fn current_color<C: CssSystem>(properties: &C::PropertyMap) -> Rgba {
+    properties
+        .get("color")
+        .and_then(|prop| prop.parse_color())
+        .unwrap_or((0.0, 0.0, 0.0, 255.0))
+}
+
+/// Returns the text shadows of an element
+pub fn text_shadows<C: CssSystem>(properties: &C::PropertyMap) -> Vec<Shadow> {
+    properties
+        .get("text-shadow")
+        .map(|prop| parse_shadows(&prop.as_values()))
+        .unwrap_or_default()
+}
+
+/// Returns the shape of a shadow before it is blurred: the box offset by the shadow offset, and grown by the spread
+/// for outer shadows or shrunk by it for inset shadows
+fn shadow_area(area: Area, shadow: &Shadow) -> Area {
+    let spread = if shadow.inset { -shadow.spread } else { shadow.spread };
+
+    Area::new(
+        area.x + shadow.offset_x - spread,
+        area.y + shadow.offset_y - spread,
+        (area.width + spread * 2.0).max(0.0),
+        (area.height + spread * 2.0).max(0.0),
+    )
+}
+
+/// Returns the corner radius of a shadow shape. Blurred shapes only support a single radius, so the largest corner is
+/// used, adjusted by the spread
+fn shadow_radius(radii: Radii, shadow: &Shadow) -> FP {
+    let radius = radii.0.max(radii.1).max(radii.2).max(radii.3);
+    if radius <= 0.0 {
+        return 0.0;
+    }
+
+    let spread = if shadow.inset { -shadow.spread } else { shadow.spread };
+
+    (radius + spread).max(0.0)
+}
+
+/// Paints either the outer or the inset box shadows of an element at `pos`. Outer shadows are painted below the
+/// background and inset shadows above it, both below the border
+pub fn render_box_shadows<B: RenderBackend, L: Layouter, C: CssSystem>(
+    node: &RenderTreeNode<L, C>,
+    scene: &mut B::Scene,
+    pos: Point,
+    radii: Radii,
+    inset: bool,
+) {
+    let Some(prop) = node.properties.get("box-shadow") else {
+        return;
+    };
+
+    let shadows = parse_shadows(&prop.as_values());
+    if shadows.is_empty() {
+        return;
+    }
+
+    let current = current_color::<C>(&node.properties);
+    let boxes = Boxes::new(&node.layout, pos);
+
+    let visual_box = if inset { VisualBox::Padding } else { VisualBox::Border };
+    let area = boxes.area(visual_box);
+    let radii = boxes.radii(visual_box, radii);
+
+    // The first shadow is on top, so the list is painted back to front
+    for shadow in shadows.iter().rev().filter(|shadow| shadow.inset == inset) {
+        let shape = shadow_area(area, shadow);
+        if !inset && (shape.width <= 0.0 || shape.height <= 0.0) {
+            continue;
+        }
+
+        let mut render = RenderShadow::<B>::new(area.rect::<B>(), shape.rect::<B>(), shadow.color::<B>(current));
+        render.radius = Some(B::BorderRadius::from(radii));
+        render.shadow_radius = shadow_radius(radii, shadow);
+        render.std_dev = shadow.std_dev();
+        render.inset = inset;
+
+        scene.draw_shadow(&render);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gosub_css3::colors::RgbColor;
+    use gosub_css3::stylesheet::CssValue;
+
+    use super::*;
+
+    fn shadow(offset_x: FP, offset_y: FP, blur: FP, spread: FP, inset: bool) -> Shadow {
+        Shadow {
+            offset_x,
+            offset_y,
+            blur,
+            spread,
+            color: None,
+            inset,
+        }
+    }
+
+    #[test]
+    fn shadow_lists() {
+        let px = |v: f32| CssValue::Unit(v, "px".to_string());
+        let red = CssValue::Color(RgbColor::new(255.0, 0.0, 0.0, 255.0));
+
+        let values = [
+            px(1.0),
+            px(2.0),
+            px(3.0),
+            red,
+            CssValue::Comma,
+            CssValue::String("inset".to_string()),
+            CssValue::Zero,
+            px(4.0),
+            CssValue::Comma,
+            px(5.0),
+        ];
+
+        let shadows = parse_shadows(&values);
+        assert_eq!(shadows.len(), 2);
+        assert_eq!(shadows[0].blur, 3.0);
+        assert_eq!(shadows[0].color, Some((255.0, 0.0, 0.0, 255.0)));
+        assert!(!shadows[0].inset);
+        assert_eq!((shadows[1].offset_x, shadows[1].offset_y), (0.0, 4.0));
+        assert_eq!(shadows[1].color, None);
+        assert!(shadows[1].inset);
+
+        assert!(parse_shadows(&[CssValue::None]).is_empty());
+    }
+
+    #[test]
+    fn spread_grows_outer_and_shrinks_inset_shadows() {
+        let area = Area::new(10.0, 10.0, 100.0, 50.0);
+
+        let outer = shadow_area(area, &shadow(5.0, 5.0, 0.0, 2.0, false));
+        assert_eq!(outer, Area::new(13.0, 13.0, 104.0, 54.0));
+
+        let inset = shadow_area(area, &shadow(5.0, 5.0, 0.0, 2.0, true));
+        assert_eq!(inset, Area::new(17.0, 17.0, 96.0, 46.0));
+
+        let collapsed = shadow_area(area, &shadow(0.0, 0.0, 0.0, -30.0, false));
+        assert_eq!(collapsed.height, 0.0);
+    }
+
+    #[test]
+    fn radius_follows_spread() {
+        let radii = (4.0, 8.0, 0.0, 0.0);
+
+        assert_eq!(shadow_radius(radii, &shadow(0.0, 0.0, 0.0, 2.0, false)), 10.0);
+        assert_eq!(shadow_radius(radii, &shadow(0.0, 0.0, 0.0, 10.0, true)), 0.0);
+        assert_eq!(
+            shadow_radius((0.0, 0.0, 0.0, 0.0), &shadow(0.0, 0.0, 0.0, 2.0, false)),
+            0.0
+        );
+    }
+}
@@ -16,6 +16,9 @@ use gosub_shared::traits::document::{Document, DocumentBuilder};
 use gosub_shared::traits::html5::Html5Parser;
 use url::Url;
 
+use crate::draw::font::register_font_faces;
+use crate::draw::selection::Selection;
+
 pub struct TreeDrawer<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem> {
     pub(crate) fetcher: Fetcher,
     pub(crate) tree: RenderTree<L, D, C>,
@@ -27,12 +30,26 @@ pub struct TreeDrawer<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSyste
     pub(crate) dirty: bool,
     pub(crate) debugger_scene: Option<B::Scene>,
     pub(crate) tree_scene: Option<B::Scene>,
+    /// Fixed elements, which are painted without the scroll transform
+    pub(crate) fixed_scene: Option<B::Scene>,
+    /// Sticky elements with the id of the sticky element, these are moved by their sticky offset when scrolling
+    pub(crate) sticky_scenes: Vec<(NodeId, B::Scene)>,
+    /// The document has backgrounds with `background-attachment: fixed`, so it is painted again when scrolled
+    pub(crate) fixed_backgrounds: bool,
     pub(crate) selected_element: Option<NodeId>,
+    /// Text selected with the mouse
+    pub(crate) selection: Selection,
     pub(crate) scene_transform: Option<B::Transform>,
+    /// Generation of the web fonts the tree has been laid out with
+    pub(crate) font_generation: u64,
 }
 
 impl<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem> TreeDrawer<B, L, D, C> {
     pub fn new(tree: RenderTree<L, D, C>, layouter: L, url: Url, debug: bool) -> Self {
+        if let Some(handle) = &tree.handle {
+            register_font_faces(&*handle.get());
+        }
+
         Self {
             tree,
             layouter,
@@ -43,8 +60,13 @@ impl<B: RenderBackend, L: Layouter, D: Document<C>, C: CssSystem> TreeDrawer<B,
             debugger_scene: None,
             dirty: false,
             tree_scene: None,
+            fixed_scene: None,
+            sticky_scenes: Vec::new(),
+            fixed_backgrounds: false,
             selected_element: None,
+            selection: Selection::default(),
             scene_transform: None,
+            font_generation: 0,
             fetcher: Fetcher::new(url),
         }
     }
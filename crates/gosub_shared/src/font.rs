@@ -0,0 +1,379 @@
+//! Font faces declared with `@font-face` rules (<https://www.w3.org/TR/css-fonts-4/#font-face-rule>), and the font
+//! matching algorithm that selects a face for the `font-weight` and `font-style` of an element.
+
+use std::time::Duration;
+
+use crate::traits::css3::CssValue;
+
+/// Font formats that can be loaded, faces with other formats in their `format()` hint are skipped
+const SUPPORTED_FORMATS: [&str; 5] = ["woff", "truetype", "opentype", "ttf", "otf"];
+
+/// A single entry of the `src` descriptor
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSource {
+    /// A font file, the url is relative to the stylesheet that declared the face
+    Url { url: String, format: Option<String> },
+    /// A font that is installed on the system
+    Local(String),
+}
+
+impl FontSource {
+    /// Returns false when the `format()` hint names a format that can't be loaded
+    pub fn is_supported(&self) -> bool {
+        match self {
+            FontSource::Url {
+                format: Some(format), ..
+            } => SUPPORTED_FORMATS.iter().any(|f| f.eq_ignore_ascii_case(format)),
+            _ => true,
+        }
+    }
+}
+
+/// The `font-display` descriptor, which decides how text is painted while a face is loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FontDisplay {
+    #[default]
+    Auto,
+    Block,
+    Swap,
+    Fallback,
+    Optional,
+}
+
+impl FontDisplay {
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value.to_ascii_lowercase().as_str() {
+            "auto" => Self::Auto,
+            "block" => Self::Block,
+            "swap" => Self::Swap,
+            "fallback" => Self::Fallback,
+            "optional" => Self::Optional,
+            _ => return None,
+        })
+    }
+
+    /// Time after the start of the load during which text is invisible
+    pub fn block_period(&self) -> Duration {
+        match self {
+            Self::Auto | Self::Block => Duration::from_secs(3),
+            Self::Swap => Duration::ZERO,
+            Self::Fallback | Self::Optional => Duration::from_millis(100),
+        }
+    }
+
+    /// Time after the block period during which a loaded face still replaces the fallback font. `None` is infinite
+    pub fn swap_period(&self) -> Option<Duration> {
+        match self {
+            Self::Auto | Self::Block | Self::Swap => None,
+            Self::Fallback => Some(Duration::from_secs(3)),
+            Self::Optional => Some(Duration::ZERO),
+        }
+    }
+
+    /// Returns true when a face that finished loading after `elapsed` may still be used
+    pub fn accepts_load_after(&self, elapsed: Duration) -> bool {
+        match self.swap_period() {
+            Some(swap) => elapsed <= self.block_period() + swap,
+            None => true,
+        }
+    }
+}
+
+/// Value of the `font-style` descriptor, and of the `font-style` property when matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FontFaceStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl FontFaceStyle {
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value.to_ascii_lowercase().as_str() {
+            "normal" => Self::Normal,
+            "italic" => Self::Italic,
+            "oblique" => Self::Oblique,
+            _ => return None,
+        })
+    }
+
+    /// Order in which face styles are preferred for this desired style, lower is better
+    fn preference(self, face: Self) -> u8 {
+        let order = match self {
+            Self::Normal => [Self::Normal, Self::Oblique, Self::Italic],
+            Self::Italic => [Self::Italic, Self::Oblique, Self::Normal],
+            Self::Oblique => [Self::Oblique, Self::Italic, Self::Normal],
+        };
+
+        order.iter().position(|style| *style == face).unwrap_or(order.len()) as u8
+    }
+}
+
+/// A font face declared with an `@font-face` rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFace {
+    pub family: String,
+    pub sources: Vec<FontSource>,
+    /// Range of weights the face covers, a single weight has the same minimum and maximum
+    pub weight: (f32, f32),
+    pub style: FontFaceStyle,
+    /// Inclusive code point ranges the face is used for, the face covers all code points when it is empty
+    pub unicode_range: Vec<(u32, u32)>,
+    pub display: FontDisplay,
+}
+
+impl FontFace {
+    /// Creates a face from the descriptors of an `@font-face` block. Returns `None` when the `font-family` or the
+    /// `src` descriptor is missing, as the face can't be used without them
+    pub fn from_descriptors<V: CssValue>(descriptors: &[(String, Vec<V>)]) -> Option<Self> {
+        let mut face = FontFace {
+            family: String::new(),
+            sources: Vec::new(),
+            weight: (400.0, 400.0),
+            style: FontFaceStyle::Normal,
+            unicode_range: Vec::new(),
+            display: FontDisplay::Auto,
+        };
+
+        for (name, values) in descriptors {
+            match name.to_ascii_lowercase().as_str() {
+                "font-family" => face.family = join_words(values),
+                "src" => face.sources = parse_sources(values),
+                "font-weight" => {
+                    if let Some(weight) = parse_weight_range(values) {
+                        face.weight = weight;
+                    }
+                }
+                "font-style" => {
+                    if let Some(style) = values
+                        .first()
+                        .and_then(|v| v.as_string())
+                        .and_then(FontFaceStyle::parse)
+                    {
+                        face.style = style;
+                    }
+                }
+                "unicode-range" => face.unicode_range = parse_unicode_range(&join_words(values)),
+                "font-display" => {
+                    if let Some(display) = values.first().and_then(|v| v.as_string()).and_then(FontDisplay::parse) {
+                        face.display = display;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if face.family.is_empty() || face.sources.is_empty() {
+            return None;
+        }
+
+        Some(face)
+    }
+
+    /// Returns true when the `unicode-range` of the face contains the character
+    pub fn covers(&self, c: char) -> bool {
+        let c = c as u32;
+
+        self.unicode_range.is_empty() || self.unicode_range.iter().any(|(from, to)| (*from..=*to).contains(&c))
+    }
+
+    /// Returns true when the face covers at least one character of the text
+    pub fn covers_any(&self, text: &str) -> bool {
+        self.unicode_range.is_empty() || text.chars().any(|c| self.covers(c))
+    }
+
+    /// Sort key of the face for the desired style and weight, the face with the lowest key matches best. Style is
+    /// narrowed down before weight, as described by the font matching algorithm
+    /// (<https://www.w3.org/TR/css-fonts-4/#font-style-matching>)
+    pub fn match_key(&self, style: FontFaceStyle, weight: f32) -> (u8, u8, u32) {
+        let (tier, distance) = weight_match(self.weight, weight);
+
+        (style.preference(self.style), tier, (distance * 100.0) as u32)
+    }
+}
+
+/// Ranks a weight range for a desired weight in tiers. Weights between 400 and 500 first look at heavier weights
+/// up to 500, then at lighter weights, and then at heavier weights above 500. Lighter weights look at lighter
+/// weights first and heavier weights at heavier weights first
+fn weight_match((min, max): (f32, f32), desired: f32) -> (u8, f32) {
+    if (min..=max).contains(&desired) {
+        return (0, 0.0);
+    }
+
+    let heavier = min > desired;
+    let distance = if heavier { min - desired } else { desired - max };
+
+    let tier = if (400.0..=500.0).contains(&desired) {
+        match heavier {
+            true if min <= 500.0 => 1,
+            false => 2,
+            true => 3,
+        }
+    } else if desired < 400.0 {
+        if heavier {
+            2
+        } else {
+            1
+        }
+    } else if heavier {
+        1
+    } else {
+        2
+    };
+
+    (tier, distance)
+}
+
+/// Joins the words of a value that consists of multiple identifiers, like an unquoted family name
+fn join_words<V: CssValue>(values: &[V]) -> String {
+    values
+        .iter()
+        .filter_map(|value| value.as_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_sources<V: CssValue>(values: &[V]) -> Vec<FontSource> {
+    values
+        .split(|value| value.is_comma())
+        .filter_map(|entry| {
+            let mut source = None;
+
+            for value in entry {
+                let Some((name, args)) = value.as_function() else {
+                    continue;
+                };
+
+                match name.to_ascii_lowercase().as_str() {
+                    "url" => {
+                        source = Some(FontSource::Url {
+                            url: join_words(args),
+                            format: None,
+                        })
+                    }
+                    "local" => source = Some(FontSource::Local(join_words(args))),
+                    "format" => {
+                        if let Some(FontSource::Url { format, .. }) = &mut source {
+                            *format = Some(join_words(args));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            source
+        })
+        .collect()
+}
+
+fn parse_weight<V: CssValue>(value: &V) -> Option<f32> {
+    if let Some(number) = value.as_number() {
+        return Some(number.clamp(1.0, 1000.0));
+    }
+
+    match value.as_string()? {
+        "normal" => Some(400.0),
+        "bold" => Some(700.0),
+        _ => None,
+    }
+}
+
+fn parse_weight_range<V: CssValue>(values: &[V]) -> Option<(f32, f32)> {
+    if values.first()?.as_string() == Some("auto") {
+        return Some((1.0, 1000.0));
+    }
+
+    let min = parse_weight(values.first()?)?;
+    let max = values.get(1).and_then(parse_weight).unwrap_or(min);
+
+    Some((min.min(max), min.max(max)))
+}
+
+/// Parses a comma separated list of unicode ranges like `U+26`, `U+0-7F` or `U+4??`
+pub fn parse_unicode_range(value: &str) -> Vec<(u32, u32)> {
+    value
+        .split(',')
+        .filter_map(|range| {
+            let range = range.trim();
+            let range = range.strip_prefix("U+").or_else(|| range.strip_prefix("u+"))?;
+
+            let (from, to) = match range.split_once('-') {
+                Some((from, to)) => (u32::from_str_radix(from, 16).ok()?, u32::from_str_radix(to, 16).ok()?),
+                None if range.contains('?') => (
+                    u32::from_str_radix(&range.replace('?', "0"), 16).ok()?,
+                    u32::from_str_radix(&range.replace('?', "F"), 16).ok()?,
+                ),
+                None => {
+                    let c = u32::from_str_radix(range, 16).ok()?;
+                    (c, c)
+                }
+            };
+
+            (from <= to).then_some((from, to.min(0x10FFFF)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(weight: (f32, f32), style: FontFaceStyle) -> FontFace {
+        FontFace {
+            family: "Test".to_string(),
+            sources: vec![FontSource::Local("Test".to_string())],
+            weight,
+            style,
+            unicode_range: Vec::new(),
+            display: FontDisplay::Auto,
+        }
+    }
+
+    #[test]
+    fn unicode_ranges() {
+        assert_eq!(parse_unicode_range("U+26"), vec![(0x26, 0x26)]);
+        assert_eq!(parse_unicode_range("U+0-7F, U+4??"), vec![(0x0, 0x7F), (0x400, 0x4FF)]);
+        assert_eq!(parse_unicode_range("U+FF-00, junk"), vec![]);
+
+        let mut latin = face((400.0, 400.0), FontFaceStyle::Normal);
+        latin.unicode_range = parse_unicode_range("U+0000-00FF");
+        assert!(latin.covers('a'));
+        assert!(!latin.covers('字'));
+        assert!(latin.covers_any("字a"));
+    }
+
+    #[test]
+    fn weight_matching() {
+        let faces = [
+            face((300.0, 300.0), FontFaceStyle::Normal),
+            face((500.0, 500.0), FontFaceStyle::Normal),
+            face((700.0, 700.0), FontFaceStyle::Normal),
+        ];
+
+        let best = |weight: f32| {
+            faces
+                .iter()
+                .min_by_key(|face| face.match_key(FontFaceStyle::Normal, weight))
+                .map(|face| face.weight.0)
+        };
+
+        // 400 prefers heavier weights up to 500 first
+        assert_eq!(best(400.0), Some(500.0));
+        // Light weights look at lighter faces first, bold weights at heavier ones
+        assert_eq!(best(350.0), Some(300.0));
+        assert_eq!(best(600.0), Some(700.0));
+        assert_eq!(best(900.0), Some(700.0));
+    }
+
+    #[test]
+    fn style_is_matched_before_weight() {
+        let normal = face((400.0, 400.0), FontFaceStyle::Normal);
+        let italic = face((700.0, 700.0), FontFaceStyle::Italic);
+        let oblique = face((400.0, 400.0), FontFaceStyle::Oblique);
+
+        let key = |face: &FontFace| face.match_key(FontFaceStyle::Italic, 400.0);
+        assert!(key(&italic) < key(&oblique));
+        assert!(key(&oblique) < key(&normal));
+    }
+}
@@ -8,6 +8,7 @@ extern crate core;
 pub mod byte_stream;
 pub mod document;
 pub mod errors;
+pub mod font;
 pub mod node;
 pub mod timing;
 pub mod traits;
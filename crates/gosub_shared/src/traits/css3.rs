@@ -1,5 +1,6 @@
 use crate::document::DocumentHandle;
 use crate::errors::CssResult;
+use crate::font::FontFace;
 use crate::node::NodeId;
 use crate::traits::document::Document;
 use crate::traits::render_tree::RenderTree;
@@ -40,7 +41,23 @@ pub trait CssSystem: Clone {
 
     fn inheritance<T: RenderTree<Self>>(tree: &mut T);
 
+    /// Resolves inheritance for the subtree starting at the given node only. The ancestors of the node
+    /// must already have their inheritance resolved.
+    fn inheritance_from<T: RenderTree<Self>>(tree: &mut T, node_id: T::NodeId);
+
     fn load_default_useragent_stylesheet() -> Self::Stylesheet;
+
+    /// Parses a list of declarations, like the value of a `style` attribute. Declarations of unknown properties or
+    /// with invalid values are skipped.
+    fn parse_declarations(str: &str) -> Vec<StyleDeclaration>;
+}
+
+/// A declaration as it is seen by the CSSOM, with its value serialized as CSS
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleDeclaration {
+    pub property: String,
+    pub value: String,
+    pub important: bool,
 }
 
 pub trait CssStylesheet: PartialEq {
@@ -49,6 +66,30 @@ pub trait CssStylesheet: PartialEq {
 
     /// Returns the source URL of the stylesheet
     fn url(&self) -> &str;
+
+    /// Returns the font faces declared with `@font-face` rules in the stylesheet
+    fn font_faces(&self) -> &[FontFace];
+
+    /// Returns the number of style rules in the stylesheet
+    fn rule_count(&self) -> usize;
+
+    /// Returns the rule at the index serialized as CSS
+    fn rule_css_text(&self, index: usize) -> Option<String>;
+
+    /// Returns the selectors of the rule at the index serialized as CSS
+    fn rule_selector_text(&self, index: usize) -> Option<String>;
+
+    /// Returns the declarations of the rule at the index
+    fn rule_declarations(&self, index: usize) -> Option<Vec<StyleDeclaration>>;
+
+    /// Replaces the declarations of the rule at the index with the parsed declarations of `css_text`
+    fn set_rule_declarations(&mut self, index: usize, css_text: &str) -> CssResult<()>;
+
+    /// Parses a single style rule and inserts it at the index
+    fn insert_rule(&mut self, rule: &str, index: usize) -> CssResult<()>;
+
+    /// Removes the rule at the index
+    fn delete_rule(&mut self, index: usize) -> CssResult<()>;
 }
 
 pub trait CssPropertyMap: Default + Debug {
@@ -68,6 +109,12 @@ pub trait CssPropertyMap: Default + Debug {
 
     fn make_clean(&mut self);
     fn is_dirty(&self) -> bool;
+
+    /// Returns the properties of a pseudo-element of the node, like `before` for `::before`
+    fn pseudo_element(&self, name: &str) -> Option<&Self>;
+
+    /// Removes the properties of a pseudo-element from the node, so they can be used for a box of its own
+    fn take_pseudo_element(&mut self, name: &str) -> Option<Self>;
 }
 pub trait CssProperty: Debug + Sized {
     type Value: CssValue;
@@ -85,19 +132,28 @@ pub trait CssProperty: Debug + Sized {
 
     fn as_number(&self) -> Option<f32>;
     fn as_list(&self) -> Option<Vec<Self::Value>>;
+    fn as_function(&self) -> Option<(&str, &[Self::Value])>;
+
+    /// Returns the values of the property, a value that isn't a list is returned as a list with a single value
+    fn as_values(&self) -> Vec<Self::Value>;
 
     fn is_none(&self) -> bool;
+
+    /// Serializes the actual value of the property as CSS
+    fn to_css(&self) -> String;
 }
 
-pub trait CssValue: Sized {
+pub trait CssValue: Sized + Clone {
     fn unit_to_px(&self) -> f32;
 
     fn as_string(&self) -> Option<&str>;
     fn as_percentage(&self) -> Option<f32>;
     fn as_unit(&self) -> Option<(f32, &str)>;
+    /// Returns the color of a color value, or of a string value like `red` or `#f00`
     fn as_color(&self) -> Option<(f32, f32, f32, f32)>;
     fn as_number(&self) -> Option<f32>;
     fn as_list(&self) -> Option<Vec<Self>>;
+    fn as_function(&self) -> Option<(&str, &[Self])>;
 
     fn is_comma(&self) -> bool;
 
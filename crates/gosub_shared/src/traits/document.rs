@@ -1,5 +1,6 @@
 use crate::byte_stream::Location;
 use crate::document::DocumentHandle;
+use crate::errors::CssResult;
 use crate::node::NodeId;
 use crate::traits::css3::CssSystem;
 use crate::traits::node::{Node, QuirksMode};
@@ -16,6 +17,62 @@ pub enum DocumentType {
     IframeSrcDoc,
 }
 
+/// A change made to the document that may affect the styling or layout of (a part of) the document
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum DocumentMutation {
+    /// The node itself (attributes, text data) has been changed
+    NodeChanged(NodeId),
+    /// Children have been added to, or removed from the given node
+    ChildrenChanged(NodeId),
+    /// The node has been removed from the document
+    NodeRemoved(NodeId),
+    /// A stylesheet has been added to the document or changed, which means every node could be affected
+    StylesheetsChanged,
+    /// Too many changes have been made to keep track of them separately, so every node could be affected
+    DocumentChanged,
+}
+
+/// Kind of script as determined by the `type` attribute of a `<script>` element
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ScriptKind {
+    /// Classic script, evaluated as a whole in the global scope
+    Classic,
+    /// ES module, fetched together with its imports
+    Module,
+    /// JSON import map that changes how module specifiers are resolved
+    ImportMap,
+}
+
+/// Where the source of a script comes from
+#[derive(PartialEq, Debug, Clone)]
+pub enum ScriptSource {
+    /// Source text inside the `<script>` element
+    Inline(String),
+    /// Source that needs to be fetched from the (already resolved) `src` URL
+    External(Url),
+}
+
+/// When a script needs to be executed
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ScriptTiming {
+    /// Executed right away, in document order
+    Blocking,
+    /// Executed in document order once the document has been parsed
+    Deferred,
+    /// Executed as soon as it is available, in no particular order
+    Async,
+}
+
+/// A script that has been found by the parser and is waiting to be executed
+#[derive(PartialEq, Debug, Clone)]
+pub struct Script {
+    /// The `<script>` element this script belongs to
+    pub node_id: NodeId,
+    pub kind: ScriptKind,
+    pub source: ScriptSource,
+    pub timing: ScriptTiming,
+}
+
 pub trait DocumentBuilder<C: CssSystem> {
     type Document: Document<C>;
 
@@ -69,6 +126,18 @@ pub trait Document<C: CssSystem>: Sized + Display {
 
     fn stylesheets(&self) -> &Vec<C::Stylesheet>;
     fn add_stylesheet(&mut self, stylesheet: C::Stylesheet);
+    /// Calls `f` to modify the stylesheet at the index. Only a successful change is recorded as a mutation, as it
+    /// means every node could be affected.
+    fn update_stylesheet<R>(
+        &mut self,
+        index: usize,
+        f: impl FnOnce(&mut C::Stylesheet) -> CssResult<R>,
+    ) -> CssResult<R>;
+
+    /// Adds a script that has been prepared by the parser
+    fn add_script(&mut self, script: Script);
+    /// Returns all scripts that have been added since the last call and clears the list
+    fn take_scripts(&mut self) -> Vec<Script>;
 
     /// Return the root node of the document
     fn get_root(&self) -> &Self::Node;
@@ -123,6 +192,9 @@ pub trait Document<C: CssSystem>: Sized + Display {
         location: Location,
     ) -> Self::Node;
 
+    /// Returns all mutations that have been made to the document since the last call and clears the list
+    fn take_mutations(&mut self) -> Vec<DocumentMutation>;
+
     fn write(&self) -> String;
     fn write_from_node(&self, node_id: NodeId) -> String;
     fn cloned_node_by_id(&self, node_id: NodeId) -> Option<Self::Node>;
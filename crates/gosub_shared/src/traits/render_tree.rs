@@ -12,6 +12,12 @@ pub trait RenderTree<C: CssSystem> {
     fn get_node_mut(&mut self, id: Self::NodeId) -> Option<&mut Self::Node>;
 
     fn get_children(&self, id: Self::NodeId) -> Option<Vec<Self::NodeId>>;
+
+    fn get_parent(&self, id: Self::NodeId) -> Option<Self::NodeId>;
+
+    /// Restyles the nodes that are affected by the mutations that have been made to the document since the last
+    /// update. Returns true when the tree has been changed.
+    fn apply_mutations(&mut self) -> bool;
 }
 
 pub trait RenderTreeNode<C: CssSystem> {
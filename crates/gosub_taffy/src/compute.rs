@@ -1 +1,5 @@
+pub mod block;
+pub mod float;
 pub mod inline;
+pub mod table;
+pub mod writing_mode;
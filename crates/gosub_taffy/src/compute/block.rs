@@ -0,0 +1,495 @@
+use taffy::{
+    compute_block_layout, compute_hidden_layout, AvailableSpace, Layout, LayoutInput, LayoutOutput, LayoutPartialTree,
+    LengthPercentageAuto, Line, MaybeMath, MaybeResolve, NodeId, Point, Position, Rect, RequestedAxis, ResolveOrZero,
+    RunMode, Size, SizingMode, TraversePartialTree,
+};
+
+use gosub_render_backend::layout::LayoutTree;
+
+use crate::{Clear, Display, Float, LayoutDocument, TaffyLayouter};
+
+/// Lays out a block container in normal flow, placing floated children in the block formatting context and moving
+/// the other children around them. This is used instead of taffy's block layout for containers that have floated
+/// children, or that are inside a block formatting context that has floats.
+///
+/// `bfc_root` is true when the container establishes the block formatting context, in which case it grows to
+/// contain its floats.
+pub fn compute_flow_layout<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    node_id: LT::NodeId,
+    layout_input: LayoutInput,
+    bfc_root: bool,
+) -> LayoutOutput {
+    let taffy_id = NodeId::from(node_id.into());
+
+    if layout_input.run_mode == RunMode::PerformHiddenLayout {
+        return compute_hidden_layout(tree, taffy_id);
+    }
+
+    let style = tree.get_taffy_style(node_id).clone();
+    let parent_width = layout_input.parent_size.width;
+
+    let padding: Rect<f32> = style.padding.resolve_or_zero(parent_width);
+    let border: Rect<f32> = style.border.resolve_or_zero(parent_width);
+    let margin: Rect<f32> = style.margin.resolve_or_zero(parent_width);
+
+    let padding_border = Size {
+        width: padding.left + padding.right + border.left + border.right,
+        height: padding.top + padding.bottom + border.top + border.bottom,
+    };
+
+    let min_size = style.min_size.maybe_resolve(layout_input.parent_size);
+    let max_size = style.max_size.maybe_resolve(layout_input.parent_size);
+
+    let width = layout_input
+        .known_dimensions
+        .width
+        .or_else(|| {
+            style
+                .size
+                .width
+                .maybe_resolve(parent_width)
+                .maybe_clamp(min_size.width, max_size.width)
+        })
+        .or_else(|| match layout_input.available_space.width {
+            AvailableSpace::Definite(available) => {
+                Some((available - margin.left - margin.right).maybe_clamp(min_size.width, max_size.width))
+            }
+            _ => None,
+        });
+
+    let Some(width) = width else {
+        // Intrinsic sizes are computed as if there were no floats, because the floats of the block formatting
+        // context are only known during the actual layout
+        let outer = std::mem::take(&mut tree.1);
+        let output = compute_block_layout(tree, taffy_id, layout_input);
+        tree.1 = outer;
+
+        if layout_input.run_mode == RunMode::ComputeSize {
+            return output;
+        }
+
+        let mut input = layout_input;
+        input.known_dimensions.width = Some(output.size.width);
+
+        return compute_flow_layout(tree, node_id, input, bfc_root);
+    };
+
+    let content_left = border.left + padding.left;
+    let content_width = (width - padding_border.width).max(0.0);
+    let content_right = content_left + content_width;
+
+    let child_parent_size = Size {
+        width: Some(content_width),
+        height: style
+            .size
+            .height
+            .maybe_resolve(layout_input.parent_size.height)
+            .map(|height| height - padding_border.height),
+    };
+
+    let perform_layout = layout_input.run_mode == RunMode::PerformLayout;
+
+    // When measuring, the floats are placed in a copy of the context, so they can be placed again during the layout
+    let saved = (!perform_layout).then(|| tree.1.clone());
+
+    let mut y = border.top + padding.top;
+    let mut pending_margin = 0.0f32;
+    let mut absolute_children = Vec::new();
+
+    for child_taffy in tree.child_ids(taffy_id).collect::<Vec<_>>() {
+        let child = LT::NodeId::from(child_taffy.into());
+
+        let child_style = tree.get_taffy_style(child).clone();
+        let flow = tree.get_flow(child);
+
+        if child_style.display == taffy::Display::None {
+            compute_hidden_layout(tree, child_taffy);
+            continue;
+        }
+
+        if child_style.position == Position::Absolute {
+            absolute_children.push(child_taffy);
+            continue;
+        }
+
+        let child_margin: Rect<f32> = child_style.margin.resolve_or_zero(Some(content_width));
+        let margin_width = child_margin.left + child_margin.right;
+
+        if flow.float != Float::None {
+            let size = float_size(tree, child_taffy, &child_style, content_width - margin_width);
+
+            let position = tree.1.place(
+                flow.float,
+                size.width + margin_width,
+                size.height + child_margin.top + child_margin.bottom,
+                y + pending_margin.max(0.0),
+                content_left,
+                content_right,
+            );
+
+            if perform_layout {
+                let output = layout_child(tree, child_taffy, size, child_parent_size);
+
+                let location = Point {
+                    x: position.x + child_margin.left,
+                    y: position.y + child_margin.top,
+                };
+
+                set_child_layout(tree, child_taffy, &child_style, location, size, output, content_width);
+            }
+
+            continue;
+        }
+
+        let mut margin_top = collapse_margins(pending_margin, child_margin.top);
+
+        if flow.clear != Clear::None {
+            let cleared = tree.1.clear(flow.clear, y + margin_top);
+            if cleared > y + margin_top {
+                // The clearance replaces the collapsed margin
+                margin_top = cleared - y;
+            }
+        }
+
+        y += margin_top;
+
+        let specified_width = child_style.size.width.maybe_resolve(Some(content_width));
+
+        // Boxes that establish a new block formatting context must not overlap the floats, and are moved next to
+        // or below them. Other boxes overlap the floats, only their line boxes are shortened.
+        let avoids_floats = flow.new_bfc || tree.0.get_cache(child).is_some_and(|c| c.display != Display::Taffy);
+
+        let (x, available_width) = if avoids_floats && tree.1.has_floats() {
+            let needed = specified_width.unwrap_or(0.0) + margin_width;
+            let (position, available) = tree.1.find_space(y, needed, 0.0, content_left, content_right);
+
+            y = position.y;
+            (position.x, available)
+        } else {
+            (content_left, content_width)
+        };
+
+        let mut x = x + child_margin.left;
+        let child_width = specified_width.unwrap_or((available_width - margin_width).max(0.0));
+
+        // Blocks with a width and `auto` margins on both sides are centered
+        if specified_width.is_some()
+            && child_style.margin.left == LengthPercentageAuto::Auto
+            && child_style.margin.right == LengthPercentageAuto::Auto
+        {
+            x += ((available_width - child_width) / 2.0).max(0.0);
+        }
+
+        let previous_offset = if avoids_floats {
+            None
+        } else {
+            let offset = tree.1.offset();
+            Some(tree.1.set_offset(Point {
+                x: offset.x + x,
+                y: offset.y + y,
+            }))
+        };
+
+        if !avoids_floats && tree.1.has_floats() {
+            // The cached layout was computed with different floats around it
+            tree.get_cache_mut(child_taffy).clear();
+        }
+
+        let output = tree.compute_child_layout(
+            child_taffy,
+            LayoutInput {
+                run_mode: layout_input.run_mode,
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                known_dimensions: Size {
+                    width: Some(child_width),
+                    height: None,
+                },
+                parent_size: child_parent_size,
+                available_space: Size {
+                    width: AvailableSpace::Definite(child_width),
+                    height: AvailableSpace::MaxContent,
+                },
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        if let Some(offset) = previous_offset {
+            tree.1.set_offset(offset);
+        }
+
+        let size = Size {
+            width: child_width,
+            height: output.size.height,
+        };
+
+        if perform_layout {
+            set_child_layout(
+                tree,
+                child_taffy,
+                &child_style,
+                Point { x, y },
+                size,
+                output,
+                content_width,
+            );
+        }
+
+        y += size.height;
+        pending_margin = child_margin.bottom;
+    }
+
+    y += pending_margin.max(0.0);
+
+    if bfc_root {
+        y = y.max(tree.1.bottom());
+    }
+
+    let content_height = y + padding.bottom + border.bottom;
+
+    let height = layout_input.known_dimensions.height.unwrap_or_else(|| {
+        style
+            .size
+            .height
+            .maybe_resolve(layout_input.parent_size.height)
+            .unwrap_or(content_height)
+            .maybe_clamp(min_size.height, max_size.height)
+    });
+
+    let size = Size { width, height };
+
+    if let Some(saved) = saved {
+        tree.1 = saved;
+    }
+
+    if perform_layout {
+        for child in absolute_children {
+            layout_absolute_child(tree, child, size, border, padding);
+        }
+    }
+
+    LayoutOutput::from_sizes(
+        size,
+        Size {
+            width,
+            height: content_height,
+        },
+    )
+}
+
+/// Floats without a width are shrink-to-fit: as wide as their content, but not wider than the available space
+fn float_size<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    child: NodeId,
+    style: &taffy::Style,
+    available: f32,
+) -> Size<f32> {
+    let parent_size = Size {
+        width: Some(available),
+        height: None,
+    };
+
+    let width = match style.size.width.maybe_resolve(Some(available)) {
+        Some(width) => width,
+        None => {
+            let min = measure_width(tree, child, AvailableSpace::MinContent, parent_size);
+            let max = measure_width(tree, child, AvailableSpace::MaxContent, parent_size);
+
+            max.min(available).max(min)
+        }
+    };
+
+    let output = tree.compute_child_layout(
+        child,
+        LayoutInput {
+            run_mode: RunMode::ComputeSize,
+            sizing_mode: SizingMode::InherentSize,
+            axis: RequestedAxis::Vertical,
+            known_dimensions: Size {
+                width: Some(width),
+                height: None,
+            },
+            parent_size,
+            available_space: Size {
+                width: AvailableSpace::Definite(width),
+                height: AvailableSpace::MaxContent,
+            },
+            vertical_margins_are_collapsible: Line::FALSE,
+        },
+    );
+
+    Size {
+        width,
+        height: output.size.height,
+    }
+}
+
+fn measure_width<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    child: NodeId,
+    width: AvailableSpace,
+    parent_size: Size<Option<f32>>,
+) -> f32 {
+    tree.compute_child_layout(
+        child,
+        LayoutInput {
+            run_mode: RunMode::ComputeSize,
+            sizing_mode: SizingMode::InherentSize,
+            axis: RequestedAxis::Horizontal,
+            known_dimensions: Size::NONE,
+            parent_size,
+            available_space: Size {
+                width,
+                height: AvailableSpace::MaxContent,
+            },
+            vertical_margins_are_collapsible: Line::FALSE,
+        },
+    )
+    .size
+    .width
+}
+
+fn layout_child<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    child: NodeId,
+    size: Size<f32>,
+    parent_size: Size<Option<f32>>,
+) -> LayoutOutput {
+    tree.compute_child_layout(
+        child,
+        LayoutInput {
+            run_mode: RunMode::PerformLayout,
+            sizing_mode: SizingMode::InherentSize,
+            axis: RequestedAxis::Both,
+            known_dimensions: Size {
+                width: Some(size.width),
+                height: Some(size.height),
+            },
+            parent_size,
+            available_space: Size {
+                width: AvailableSpace::Definite(size.width),
+                height: AvailableSpace::Definite(size.height),
+            },
+            vertical_margins_are_collapsible: Line::FALSE,
+        },
+    )
+}
+
+fn set_child_layout<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    child: NodeId,
+    style: &taffy::Style,
+    location: Point<f32>,
+    size: Size<f32>,
+    output: LayoutOutput,
+    container_width: f32,
+) {
+    tree.set_unrounded_layout(
+        child,
+        &Layout {
+            order: 0,
+            location,
+            size,
+            content_size: output.content_size,
+            scrollbar_size: Size::ZERO,
+            border: style.border.resolve_or_zero(Some(container_width)),
+            padding: style.padding.resolve_or_zero(Some(container_width)),
+        },
+    );
+}
+
+/// Absolutely positioned children are positioned with their insets relative to the padding box of the container
+pub fn layout_absolute_child<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    child: NodeId,
+    container_size: Size<f32>,
+    border: Rect<f32>,
+    padding: Rect<f32>,
+) {
+    let style = tree.get_style(child).clone();
+
+    let padding_box = Size {
+        width: container_size.width - border.left - border.right,
+        height: container_size.height - border.top - border.bottom,
+    };
+
+    let parent_size = Size {
+        width: Some(padding_box.width),
+        height: Some(padding_box.height),
+    };
+
+    let left = style.inset.left.maybe_resolve(parent_size.width);
+    let right = style.inset.right.maybe_resolve(parent_size.width);
+    let top = style.inset.top.maybe_resolve(parent_size.height);
+    let bottom = style.inset.bottom.maybe_resolve(parent_size.height);
+
+    let margin: Rect<f32> = style.margin.resolve_or_zero(parent_size.width);
+
+    let mut known = style.size.maybe_resolve(parent_size);
+
+    if let (None, Some(left), Some(right)) = (known.width, left, right) {
+        known.width = Some((padding_box.width - left - right - margin.left - margin.right).max(0.0));
+    }
+
+    if let (None, Some(top), Some(bottom)) = (known.height, top, bottom) {
+        known.height = Some((padding_box.height - top - bottom - margin.top - margin.bottom).max(0.0));
+    }
+
+    let size = tree
+        .compute_child_layout(
+            child,
+            LayoutInput {
+                run_mode: RunMode::ComputeSize,
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                known_dimensions: known,
+                parent_size,
+                available_space: Size {
+                    width: AvailableSpace::Definite(padding_box.width),
+                    height: AvailableSpace::Definite(padding_box.height),
+                },
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        )
+        .size;
+
+    let output = layout_child(tree, child, size, parent_size);
+
+    let x = match (left, right) {
+        (Some(left), _) => left + margin.left,
+        (None, Some(right)) => padding_box.width - right - margin.right - size.width,
+        (None, None) => padding.left + margin.left,
+    };
+
+    let y = match (top, bottom) {
+        (Some(top), _) => top + margin.top,
+        (None, Some(bottom)) => padding_box.height - bottom - margin.bottom - size.height,
+        (None, None) => padding.top + margin.top,
+    };
+
+    let location = Point {
+        x: x + border.left,
+        y: y + border.top,
+    };
+
+    set_child_layout(tree, child, &style, location, size, output, padding_box.width);
+}
+
+/// Collapses two adjoining vertical margins: the largest positive margin plus the most negative margin
+fn collapse_margins(a: f32, b: f32) -> f32 {
+    a.max(b).max(0.0) + a.min(b).min(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn margin_collapsing() {
+        assert_eq!(collapse_margins(10.0, 20.0), 20.0);
+        assert_eq!(collapse_margins(20.0, -5.0), 15.0);
+        assert_eq!(collapse_margins(-10.0, -5.0), -10.0);
+        assert_eq!(collapse_margins(0.0, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,212 @@
+use taffy::Point;
+
+use crate::{Clear, Float};
+
+/// A float that has been placed in a block formatting context. The rect is the margin box of the float, relative
+/// to the border box of the box that established the block formatting context
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PlacedFloat {
+    side: Float,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl PlacedFloat {
+    fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    fn overlaps(&self, y: f32, height: f32) -> bool {
+        // A zero height box still needs to avoid the float it is next to
+        let height = height.max(f32::EPSILON);
+
+        self.y < y + height && y < self.bottom()
+    }
+}
+
+/// The floats of a block formatting context
+#[derive(Debug, Default, Clone)]
+pub struct FloatContext {
+    floats: Vec<PlacedFloat>,
+    /// Position of the box that is currently laid out, relative to the box that established the block formatting
+    /// context. All positions that are passed to and returned from the context use this coordinate space.
+    offset: Point<f32>,
+}
+
+impl FloatContext {
+    pub fn has_floats(&self) -> bool {
+        !self.floats.is_empty()
+    }
+
+    pub fn offset(&self) -> Point<f32> {
+        self.offset
+    }
+
+    /// Sets the position of the box that is laid out next, and returns the previous one, so it can be restored
+    pub fn set_offset(&mut self, offset: Point<f32>) -> Point<f32> {
+        std::mem::replace(&mut self.offset, offset)
+    }
+
+    /// Returns the horizontal space between `left` and `right` that isn't covered by floats, for a box at `y` that
+    /// is `height` high
+    pub fn band(&self, y: f32, height: f32, left: f32, right: f32) -> (f32, f32) {
+        let y = y + self.offset.y;
+
+        let mut left = left + self.offset.x;
+        let mut right = right + self.offset.x;
+
+        for float in self.floats.iter().filter(|float| float.overlaps(y, height)) {
+            match float.side {
+                Float::Left => left = left.max(float.x + float.width),
+                Float::Right => right = right.min(float.x),
+                Float::None => {}
+            }
+        }
+
+        (left - self.offset.x, (right - self.offset.x).max(left - self.offset.x))
+    }
+
+    /// Finds the highest position, not above `y`, where a box of `width` by `height` fits next to the floats.
+    /// Returns the position and the available width at that position
+    pub fn find_space(&self, y: f32, width: f32, height: f32, left: f32, right: f32) -> (Point<f32>, f32) {
+        let mut y = y;
+
+        loop {
+            let (band_left, band_right) = self.band(y, height, left, right);
+
+            if band_right - band_left >= width {
+                return (Point { x: band_left, y }, band_right - band_left);
+            }
+
+            // Move down to the first float that ends in this band, there might be more space below it
+            let next = self
+                .floats
+                .iter()
+                .filter(|float| float.overlaps(y + self.offset.y, height))
+                .map(|float| float.bottom() - self.offset.y)
+                .filter(|bottom| *bottom > y)
+                .fold(None, |min: Option<f32>, bottom| {
+                    Some(min.map_or(bottom, |min| min.min(bottom)))
+                });
+
+            match next {
+                Some(next) => y = next,
+                None => return (Point { x: band_left, y }, band_right - band_left),
+            }
+        }
+    }
+
+    /// Places a float with the given margin box size, as high as possible but not above `y`, and returns its
+    /// position
+    pub fn place(&mut self, side: Float, width: f32, height: f32, y: f32, left: f32, right: f32) -> Point<f32> {
+        // A float can't be placed higher than the floats that were placed before it
+        let min_y = self
+            .floats
+            .last()
+            .map(|float| float.y - self.offset.y)
+            .unwrap_or(y)
+            .max(y);
+
+        let (point, available) = self.find_space(min_y, width, height, left, right);
+
+        let x = match side {
+            Float::Right => point.x + available - width,
+            _ => point.x,
+        };
+
+        self.floats.push(PlacedFloat {
+            side,
+            x: x + self.offset.x,
+            y: point.y + self.offset.y,
+            width,
+            height,
+        });
+
+        Point { x, y: point.y }
+    }
+
+    /// Returns the position a box that clears the given side must be moved down to
+    pub fn clear(&self, clear: Clear, y: f32) -> f32 {
+        self.floats
+            .iter()
+            .filter(|float| match clear {
+                Clear::None => false,
+                Clear::Left => float.side == Float::Left,
+                Clear::Right => float.side == Float::Right,
+                Clear::Both => true,
+            })
+            .map(|float| float.bottom() - self.offset.y)
+            .fold(y, f32::max)
+    }
+
+    /// Returns the bottom of the lowest float, a box that establishes a block formatting context grows to contain it
+    pub fn bottom(&self) -> f32 {
+        self.floats
+            .iter()
+            .map(|float| float.bottom() - self.offset.y)
+            .fold(0.0, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_floats_next_to_each_other() {
+        let mut ctx = FloatContext::default();
+
+        let first = ctx.place(Float::Left, 100.0, 50.0, 0.0, 0.0, 500.0);
+        let second = ctx.place(Float::Left, 100.0, 20.0, 0.0, 0.0, 500.0);
+        let right = ctx.place(Float::Right, 50.0, 10.0, 0.0, 0.0, 500.0);
+
+        assert_eq!(first, Point { x: 0.0, y: 0.0 });
+        assert_eq!(second, Point { x: 100.0, y: 0.0 });
+        assert_eq!(right, Point { x: 450.0, y: 0.0 });
+
+        assert_eq!(ctx.band(0.0, 10.0, 0.0, 500.0), (200.0, 450.0));
+        assert_eq!(ctx.band(30.0, 10.0, 0.0, 500.0), (100.0, 500.0));
+        assert_eq!(ctx.band(60.0, 10.0, 0.0, 500.0), (0.0, 500.0));
+    }
+
+    #[test]
+    fn float_moves_down_when_it_does_not_fit() {
+        let mut ctx = FloatContext::default();
+
+        ctx.place(Float::Left, 300.0, 50.0, 0.0, 0.0, 500.0);
+        let second = ctx.place(Float::Right, 300.0, 50.0, 0.0, 0.0, 500.0);
+
+        assert_eq!(second, Point { x: 200.0, y: 50.0 });
+    }
+
+    #[test]
+    fn clear_floats() {
+        let mut ctx = FloatContext::default();
+
+        ctx.place(Float::Left, 100.0, 50.0, 0.0, 0.0, 500.0);
+        ctx.place(Float::Right, 100.0, 80.0, 0.0, 0.0, 500.0);
+
+        assert_eq!(ctx.clear(Clear::None, 10.0), 10.0);
+        assert_eq!(ctx.clear(Clear::Left, 10.0), 50.0);
+        assert_eq!(ctx.clear(Clear::Right, 10.0), 80.0);
+        assert_eq!(ctx.clear(Clear::Both, 100.0), 100.0);
+        assert_eq!(ctx.bottom(), 80.0);
+    }
+
+    #[test]
+    fn offset_translates_positions() {
+        let mut ctx = FloatContext::default();
+
+        ctx.place(Float::Left, 100.0, 50.0, 0.0, 0.0, 500.0);
+        ctx.set_offset(Point { x: 20.0, y: 30.0 });
+
+        assert_eq!(ctx.band(0.0, 10.0, 0.0, 460.0), (80.0, 460.0));
+        assert_eq!(ctx.clear(Clear::Both, 0.0), 20.0);
+
+        let (point, width) = ctx.find_space(0.0, 400.0, 10.0, 0.0, 460.0);
+        assert_eq!(point, Point { x: 0.0, y: 20.0 });
+        assert_eq!(width, 460.0);
+    }
+}
@@ -1,8 +1,9 @@
+use std::collections::HashMap;
 use std::sync::{LazyLock, Mutex};
 
 use log::warn;
 use parley::layout::{Alignment, PositionedLayoutItem};
-use parley::style::{FontSettings, FontStack, FontStyle, FontVariation, FontWeight, StyleProperty};
+use parley::style::{FontFeature, FontSettings, FontStack, FontStyle, FontVariation, FontWeight, StyleProperty};
 use parley::{FontContext, InlineBox, LayoutContext};
 use taffy::{
     AvailableSpace, CollapsibleMarginSet, Layout, LayoutInput, LayoutOutput, LayoutPartialTree, NodeId, Point, Rect,
@@ -11,14 +12,45 @@ use taffy::{
 
 use gosub_render_backend::geo;
 use gosub_render_backend::layout::{Decoration, DecorationStyle, HasTextLayout, LayoutTree, Node};
+use gosub_render_backend::text::{insert_break_opportunities, remove_break_opportunities, WhiteSpace};
+use gosub_render_backend::writing_mode::{bidi_prefix, UnicodeBidi, WritingMode};
+use gosub_shared::font::FontFaceStyle;
 use gosub_shared::traits::css3::{CssProperty, CssValue};
 use gosub_typeface::font::Glyph;
+use gosub_typeface::web_fonts::{FaceSource, LoadedFace, WEB_FONTS};
 
 use crate::text::{Font, TextLayout};
 use crate::{Display, LayoutDocument, TaffyLayouter};
 
+use fragment::{first_fragment_styles, first_letter_fragment, fragment_color, FragmentStyle};
+
+mod fragment;
+
 static FONT_CX: LazyLock<Mutex<FontContext>> = LazyLock::new(|| Mutex::new(FontContext::default()));
 
+/// Family names of the web font faces that have been registered in the font collection, by the id of the face
+static REGISTERED_FACES: LazyLock<Mutex<HashMap<u64, Vec<String>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Generic font families, which are not quoted in a font stack
+const GENERIC_FAMILIES: [&str; 13] = [
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+    "emoji",
+    "math",
+    "fangsong",
+];
+
+/// Id of the inline box that is inserted at the start of the first line for `text-indent`
+const TEXT_INDENT_ID: u64 = u64::MAX;
+
 pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
     tree: &mut LayoutDocument<LT>,
     nod_id: LT::NodeId,
@@ -32,9 +64,21 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
         return LayoutOutput::HIDDEN;
     };
 
+    let decorations = collect_decorations(tree, nod_id);
+
     let mut str_buf = String::new();
     let mut text_node_data = Vec::new();
     let mut inline_boxes = Vec::new();
+    let mut has_content = false;
+
+    if let Some(indent) = text_indent(tree, nod_id, &children, &layout_input) {
+        inline_boxes.push(InlineBox {
+            id: TEXT_INDENT_ID,
+            index: 0,
+            width: indent,
+            height: 0.0,
+        });
+    }
 
     for child in &children {
         let node_id = NodeId::from((*child).into());
@@ -48,28 +92,53 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
                 continue;
             }
 
-            let only_whitespace = text.chars().all(|c| c.is_whitespace());
-            if only_whitespace {
-                continue;
+            let white_space = node
+                .get_property("white-space")
+                .and_then(|s| s.as_string())
+                .map(WhiteSpace::parse)
+                .unwrap_or_default();
+
+            let mut text = text;
+
+            // A collapsible space is removed when it follows another collapsible space or the start of the line
+            if white_space.collapse && (str_buf.is_empty() || str_buf.ends_with([' ', '\n'])) {
+                text = text.strip_prefix(' ').unwrap_or(text);
+
+                if text.is_empty() {
+                    continue;
+                }
             }
 
-            str_buf.push(' ');
+            has_content |= !white_space.collapse || text.chars().any(|c| c != ' ');
+
+            let text_start = str_buf.len();
             str_buf.push_str(text);
 
-            let font_family = node
-                .get_property("font-family")
+            let overflow_wrap = ["overflow-wrap", "word-wrap"].into_iter().any(|name| {
+                node.get_property(name)
+                    .and_then(|s| s.as_string())
+                    .is_some_and(|s| s == "anywhere" || s == "break-word")
+            }) || node
+                .get_property("word-break")
                 .and_then(|s| s.as_string())
-                .map(|s| s.to_string())
-                .unwrap_or("sans-serif".to_string());
+                .is_some_and(|s| s == "break-word");
+
+            let families = node
+                .get_property("font-family")
+                .map(|prop| parse_font_families(&prop.as_values()))
+                .unwrap_or_default();
 
             let font_size = node.get_property("font-size").map(|s| s.unit_to_px()).unwrap_or(16.0);
 
-            let alignment = parse_alignment(node);
+            let rtl = is_rtl(node);
+            let alignment = parse_alignment(node, rtl);
 
             let font_weight = parse_font_weight(node);
 
             let font_style = parse_font_style(node);
 
+            let (font_family, invisible) = font_stack(&families, font_style, font_weight, &str_buf[text_start..]);
+
             let var_axes = parse_font_axes(node);
 
             let line_height = node.get_property("line-height").and_then(|s| s.as_number());
@@ -78,71 +147,7 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
 
             let letter_spacing = node.get_property("letter-spacing").map(|s| s.unit_to_px());
 
-            let mut underline = false;
-            let mut overline = false;
-            let mut line_through = false;
-            let mut decoration_width = 1.0;
-            let mut decoration_color = (0.0, 0.0, 0.0, 1.0);
-            let mut style = DecorationStyle::Solid;
-            let mut underline_offset = 4.0;
-
-            let color = node.get_property("color").and_then(|s| s.parse_color());
-
-            if let Some(actual_parent) = tree.0.parent_id(nod_id) {
-                if let Some(node) = tree.0.get_node(actual_parent) {
-                    let decoration_line = node.get_property("text-decoration-line");
-
-                    if let Some(decoration_line) = decoration_line {
-                        if let Some(list) = decoration_line.as_list() {
-                            for item in list {
-                                if let Some(s) = item.as_string() {
-                                    match s {
-                                        "underline" => underline = true,
-                                        "overline" => overline = true,
-                                        "line-through" => line_through = true,
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        } else if let Some(s) = decoration_line.as_string() {
-                            match s {
-                                "underline" => underline = true,
-                                "overline" => overline = true,
-                                "line-through" => line_through = true,
-                                _ => {}
-                            }
-                        }
-                    }
-
-                    let decoration_style = node.get_property("text-decoration-style");
-
-                    match decoration_style.as_ref().map(|s| s.as_string()) {
-                        Some(Some("solid")) => style = DecorationStyle::Solid,
-                        Some(Some("double")) => style = DecorationStyle::Double,
-                        Some(Some("dotted")) => style = DecorationStyle::Dotted,
-                        Some(Some("dashed")) => style = DecorationStyle::Dashed,
-                        Some(Some("wavy")) => style = DecorationStyle::Wavy,
-                        _ => {}
-                    }
-
-                    decoration_width = node
-                        .get_property("text-decoration-thickness")
-                        .map(|s| s.unit_to_px())
-                        .unwrap_or(1.0);
-
-                    if let Some(c) = node
-                        .get_property("text-decoration-color")
-                        .and_then(|s| s.parse_color())
-                        .or(color)
-                    {
-                        decoration_color = c;
-                    }
-
-                    if let Some(o) = node.get_property("text-underline-offset").map(|s| s.unit_to_px()) {
-                        underline_offset = o;
-                    }
-                }
-            }
+            let features = parse_font_features(node);
 
             text_node_data.push(TextNodeData {
                 font_family,
@@ -154,17 +159,10 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
                 font_weight,
                 font_style,
                 var_axes,
-
-                decoration: Decoration {
-                    underline,
-                    overline,
-                    line_through,
-                    color: decoration_color,
-                    style,
-                    width: decoration_width,
-                    underline_offset,
-                    x_offset: 0.0,
-                },
+                white_space,
+                overflow_wrap,
+                invisible,
+                features,
 
                 to: str_buf.len(),
                 id: node_id,
@@ -186,6 +184,8 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
                 out.content_size
             };
 
+            has_content = true;
+
             inline_boxes.push(InlineBox {
                 id: node_id.into(),
                 index: str_buf.len(),
@@ -195,269 +195,287 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
         }
     }
 
-    if inline_boxes.is_empty() && str_buf.is_empty() {
+    if !has_content {
         return LayoutOutput::HIDDEN;
     }
 
-    if str_buf.is_empty() {
-        str_buf.push(0 as char);
+    if text_node_data.iter().any(|data| data.white_space.wrap)
+        && text_node_data.iter().any(|data| !data.white_space.wrap)
+    {
+        str_buf = join_unwrapped_text(&str_buf, &mut text_node_data, &mut inline_boxes);
     }
 
-    let mut layout_cx: LayoutContext<usize> = LayoutContext::new();
-    // let mut scale_cx = ScaleContext::new();
+    let (rtl, unicode_bidi, writing_mode) = paragraph_direction(tree, nod_id);
 
-    let Ok(mut lock) = FONT_CX.lock() else {
-        warn!("Failed to get font context");
-        return LayoutOutput::HIDDEN;
-    };
-    let mut builder = layout_cx.ranged_builder(&mut lock, &str_buf, 1.0);
-    let mut align = Alignment::default();
+    // The base direction of the paragraph is set with control characters, which are not painted
+    let prefix = bidi_prefix(&str_buf, rtl, unicode_bidi);
+    if !prefix.is_empty() {
+        str_buf.insert_str(0, &prefix);
 
-    if let Some(default) = text_node_data.first() {
-        builder.push_default(&StyleProperty::FontStack(FontStack::Source(&default.font_family)));
-        builder.push_default(&StyleProperty::FontSize(default.font_size));
-        if let Some(line_height) = default.line_height {
-            builder.push_default(&StyleProperty::LineHeight(line_height));
+        for data in &mut text_node_data {
+            data.to += prefix.len();
         }
-        if let Some(word_spacing) = default.word_spacing {
-            builder.push_default(&StyleProperty::WordSpacing(word_spacing));
-        }
-        if let Some(letter_spacing) = default.letter_spacing {
-            builder.push_default(&StyleProperty::LetterSpacing(letter_spacing));
-        }
-        builder.push_default(&StyleProperty::FontWeight(default.font_weight));
-        builder.push_default(&StyleProperty::FontStyle(default.font_style));
-        builder.push_default(&StyleProperty::FontVariations(FontSettings::List(&default.var_axes)));
-
-        if default.decoration.overline && default.decoration.underline {
-            builder.push_default(&StyleProperty::Underline(true));
-
-            builder.push_default(&StyleProperty::UnderlineSize(Some(default.decoration.width * 2.0)));
-            builder.push_default(&StyleProperty::UnderlineOffset(Some(
-                default.decoration.underline_offset,
-            )));
-        } else if default.decoration.overline {
-            builder.push_default(&StyleProperty::Underline(true));
 
-            builder.push_default(&StyleProperty::UnderlineSize(Some(default.decoration.width)));
-        } else if default.decoration.underline {
-            builder.push_default(&StyleProperty::Underline(true));
-
-            builder.push_default(&StyleProperty::UnderlineSize(Some(default.decoration.width)));
-            builder.push_default(&StyleProperty::UnderlineOffset(Some(
-                default.decoration.underline_offset,
-            )));
+        for inline_box in &mut inline_boxes {
+            inline_box.index += prefix.len();
         }
+    }
 
-        builder.push_default(&StyleProperty::Brush(0));
-
-        align = default.alignment;
+    if str_buf.is_empty() {
+        str_buf.push(0 as char);
+    }
 
-        let mut from = default.to;
+    // The font stacks of the fragments are resolved up front, as that needs the font context
+    let (first_line, first_letter) = first_fragment_styles(tree, nod_id);
+    let [first_line, first_letter] = [first_line, first_letter].map(|style| {
+        style.map(|mut style| {
+            style.resolve_font_stack(&str_buf);
+            style
+        })
+    });
 
-        for (idx, text_node) in text_node_data.get(1..).unwrap_or_default().iter().enumerate() {
-            builder.push(
-                &StyleProperty::FontStack(FontStack::Source(&text_node.font_family)),
-                from..text_node.to,
-            );
-            builder.push(&StyleProperty::FontSize(text_node.font_size), from..text_node.to);
-            if let Some(line_height) = text_node.line_height {
-                builder.push(&StyleProperty::LineHeight(line_height), from..text_node.to);
-            }
-            if let Some(word_spacing) = text_node.word_spacing {
-                builder.push(&StyleProperty::WordSpacing(word_spacing), from..text_node.to);
-            }
-            if let Some(letter_spacing) = text_node.letter_spacing {
-                builder.push(&StyleProperty::LetterSpacing(letter_spacing), from..text_node.to);
-            }
-            builder.push(&StyleProperty::FontWeight(text_node.font_weight), from..text_node.to);
-            builder.push(&StyleProperty::FontStyle(text_node.font_style), from..text_node.to);
-            builder.push(
-                &StyleProperty::FontVariations(FontSettings::List(&text_node.var_axes)),
-                from..text_node.to,
-            );
+    let mut fragments = first_letter_fragment(first_letter.as_ref(), &str_buf, prefix.len(), &inline_boxes)
+        .into_iter()
+        .collect::<Vec<_>>();
 
-            builder.push(&StyleProperty::Brush(idx), from..text_node.to);
+    let max_width = match layout_input.available_space.width {
+        AvailableSpace::Definite(width) => Some(width),
+        AvailableSpace::MinContent => Some(0.0),
+        AvailableSpace::MaxContent => None,
+    };
 
-            if default.decoration.overline && default.decoration.underline {
-                builder.push(&StyleProperty::Underline(true), from..text_node.to);
+    // Lines are only wrapped when the white space of some text allows it. The text that may not wrap is kept together
+    let wrap = text_node_data.iter().any(|data| data.white_space.wrap) || text_node_data.is_empty();
+    let wrap_width = if wrap { max_width } else { None };
 
-                builder.push(
-                    &StyleProperty::UnderlineSize(Some(default.decoration.width * 2.0)),
-                    from..text_node.to,
-                );
-                builder.push(
-                    &StyleProperty::UnderlineOffset(Some(default.decoration.underline_offset + 4.0)),
-                    from..text_node.to,
-                );
-            } else if default.decoration.overline {
-                builder.push(&StyleProperty::Underline(true), from..text_node.to);
+    let mut layout_cx: LayoutContext<usize> = LayoutContext::new();
+    // let mut scale_cx = ScaleContext::new();
 
-                builder.push(
-                    &StyleProperty::UnderlineSize(Some(default.decoration.width)),
-                    from..text_node.to,
-                );
-                builder.push(&StyleProperty::UnderlineOffset(Some(4.0)), from..text_node.to);
-            } else if default.decoration.underline {
-                builder.push(&StyleProperty::Underline(true), from..text_node.to);
+    let Ok(mut lock) = FONT_CX.lock() else {
+        warn!("Failed to get font context");
+        return LayoutOutput::HIDDEN;
+    };
 
-                builder.push(
-                    &StyleProperty::UnderlineSize(Some(default.decoration.width)),
-                    from..text_node.to,
-                );
-                builder.push(
-                    &StyleProperty::UnderlineOffset(Some(default.decoration.underline_offset)),
-                    from..text_node.to,
+    let mut layout = build_layout(
+        &mut layout_cx,
+        &mut lock,
+        &str_buf,
+        &text_node_data,
+        &inline_boxes,
+        &fragments,
+    );
+
+    // Words may only be broken when they would overflow the line otherwise, so the extra break opportunities are
+    // only added when the text doesn't fit
+    if let Some(wrap_width) = wrap_width {
+        if text_node_data.iter().any(|data| data.overflow_wrap) {
+            layout.break_all_lines(Some(wrap_width));
+
+            if layout.width() > wrap_width {
+                str_buf = break_overflowing_words(&str_buf, &mut text_node_data, &mut inline_boxes);
+                fragments = first_letter_fragment(first_letter.as_ref(), &str_buf, prefix.len(), &inline_boxes)
+                    .into_iter()
+                    .collect();
+
+                layout = build_layout(
+                    &mut layout_cx,
+                    &mut lock,
+                    &str_buf,
+                    &text_node_data,
+                    &inline_boxes,
+                    &fragments,
                 );
             }
-
-            builder.push(
-                &StyleProperty::Underline(default.decoration.underline || default.decoration.overline),
-                from..text_node.to,
-            );
-
-            from = text_node.to;
         }
     }
 
-    for inline_box in inline_boxes {
-        builder.push_inline_box(inline_box);
-    }
+    let mut placements = break_lines(tree, &mut layout, &str_buf, &text_node_data, wrap_width, max_width);
 
-    let mut layout = builder.build();
+    // The end of the first line is only known once the lines are broken, so the text is laid out again when the
+    // style of `::first-line` changes the size of the glyphs. The line may end somewhere else with that style,
+    // which is not corrected
+    let first_line_end = layout.lines().next().map(|line| line.text_range().end);
 
-    let max_width = match layout_input.available_space.width {
-        AvailableSpace::Definite(width) => Some(width),
-        AvailableSpace::MinContent => Some(0.0),
-        AvailableSpace::MaxContent => None,
-    };
+    if let (Some(mut first_line), Some(end)) = (first_line, first_line_end) {
+        first_line.range = 0..end;
 
-    layout.break_all_lines(max_width);
+        let relayout = first_line.changes_metrics();
+        fragments.insert(0, first_line);
+
+        if relayout {
+            layout = build_layout(
+                &mut layout_cx,
+                &mut lock,
+                &str_buf,
+                &text_node_data,
+                &inline_boxes,
+                &fragments,
+            );
+            placements = break_lines(tree, &mut layout, &str_buf, &text_node_data, wrap_width, max_width);
+        }
+    }
 
-    layout.align(max_width, align);
+    drop(lock);
 
     let content_size = Size {
         width: layout.width().ceil(),
         height: layout.height().ceil(),
     };
 
-    let mut current_node_idx = 0;
-    let mut current_node_id = LT::NodeId::from(0);
-    let mut current_to = 0;
+    // Lines of `vertical-lr` are stacked in the opposite order, as the box is painted rotated like `vertical-rl`
+    let mirror_lines = writing_mode.is_vertical() && writing_mode.is_left_to_right();
 
-    if let Some(first) = text_node_data.first() {
-        current_node_id = LT::NodeId::from(first.id.into());
-        current_to = first.to;
-    }
+    // A text node has a run for every line it is on and for every direction change in it. The runs are positioned
+    // relative to this box first, and relative to their text node when all lines are done
+    let mut node_runs = text_node_data.iter().map(|_| Vec::new()).collect::<Vec<_>>();
 
-    let mut current_glyph_idx = 0;
+    let mut line_top = 0.0;
 
-    'lines: for line in layout.lines() {
+    for (line_idx, line) in layout.lines().enumerate() {
         let metrics = line.metrics();
 
+        let placement = placements.get(line_idx).copied().unwrap_or_default();
+        // Space that justification added to the items in front of the current one
+        let mut line_extra = 0.0;
+
         let height = metrics.line_height;
 
+        let line_shift = if mirror_lines {
+            content_size.height - 2.0 * line_top - height
+        } else {
+            0.0
+        };
+
+        line_top += height;
+
         for item in line.items() {
             match item {
                 PositionedLayoutItem::GlyphRun(run) => {
-                    let mut offset = 0.0;
-
                     let grun = run.run();
                     let fs = grun.font_size();
+                    let text_range = grun.text_range();
 
-                    let glyphs = run
-                        .glyphs()
-                        .map(|g| {
-                            let gl = Glyph {
-                                id: g.id,
-                                x: g.x + offset,
-                                y: g.y,
-                            };
-
-                            offset += g.advance;
+                    let Some(node_idx) = text_node_data.iter().position(|data| data.to > text_range.start) else {
+                        continue;
+                    };
 
-                            gl
-                        })
-                        .collect::<Vec<_>>();
+                    let mut offset = 0.0;
+                    let mut run_extra = 0.0;
+                    // Advance of the bidi control characters in front of the first painted glyph
+                    let mut lead = 0.0;
+                    // The run is split where the color of `::first-line` or `::first-letter` starts or ends
+                    let mut segments: Vec<RunSegment> = Vec::new();
+
+                    for cluster in grun.visual_clusters() {
+                        let skip = cluster.text_range().end <= prefix.len();
+                        let color = fragment_color(&fragments, cluster.text_range().start);
+
+                        for g in cluster.glyphs() {
+                            if skip {
+                                if segments.is_empty() {
+                                    lead += g.advance;
+                                }
+                                continue;
+                            }
 
-                    let run_y = run.baseline();
+                            if !segments.last().is_some_and(|segment| segment.color == color) {
+                                segments.push(RunSegment {
+                                    color,
+                                    start: offset,
+                                    glyphs: Vec::new(),
+                                });
+                            }
 
-                    current_glyph_idx += glyphs.len();
+                            if let Some(segment) = segments.last_mut() {
+                                segment.glyphs.push(Glyph {
+                                    id: g.id,
+                                    x: g.x + offset - segment.start,
+                                    y: g.y,
+                                });
+                            }
 
-                    if current_glyph_idx > current_to {
-                        current_node_idx += 1;
+                            offset += g.advance;
+                        }
 
-                        if let Some(next) = text_node_data.get(current_node_idx) {
-                            current_to = next.to;
-                            current_node_id = LT::NodeId::from(next.id.into());
-                        } else {
-                            break 'lines;
+                        if !skip
+                            && placement.spacing > 0.0
+                            && str_buf
+                                .get(cluster.text_range())
+                                .is_some_and(|text| text.chars().all(is_space))
+                        {
+                            offset += placement.spacing;
+                            run_extra += placement.spacing;
                         }
                     }
 
-                    let size = geo::Size {
-                        width: run.advance(),
-                        height,
-                    };
+                    if segments.is_empty() {
+                        continue;
+                    }
 
                     let coords = grun.normalized_coords().to_owned();
 
-                    let mut decoration = text_node_data
-                        .get(run.style().brush)
-                        .map(|x| x.decoration.clone())
-                        .unwrap_or_default();
-
-                    if let Some(text) = str_buf.get(grun.text_range()) {
-                        let first_non_ws = text.chars().position(|c| !c.is_whitespace());
+                    // Decorations start at the first glyph that isn't white space
+                    let mut decoration_start = 0.0;
 
-                        match first_non_ws {
-                            None => {}
-                            Some(0) => {}
+                    if let Some(text) = str_buf.get(text_range) {
+                        if let Some(i) = text.chars().position(|c| !c.is_whitespace()).filter(|i| *i > 0) {
+                            let mut xs = segments
+                                .iter()
+                                .flat_map(|segment| segment.glyphs.iter().map(|g| g.x + segment.start));
 
-                            Some(i) => {
-                                if let Some(g) = glyphs.get(i) {
-                                    decoration.x_offset = g.x;
-                                }
+                            if let Some(x) = xs.nth(i) {
+                                decoration_start = x;
                             }
                         }
                     }
 
-                    let text_layout = TextLayout {
-                        size,
-                        font_size: fs,
-                        font: Font(grun.font().clone()),
-                        glyphs,
-                        coords,
-                        decoration,
-                    };
+                    let invisible = text_node_data[node_idx].invisible;
 
-                    let Some(node) = tree.0.get_node(current_node_id) else {
-                        continue;
-                    };
+                    let ends = segments
+                        .iter()
+                        .skip(1)
+                        .map(|segment| segment.start)
+                        .chain([offset])
+                        .collect::<Vec<_>>();
 
-                    node.set_text_layout(text_layout);
+                    for (segment, end) in segments.into_iter().zip(ends) {
+                        let mut decorations = decorations.clone();
 
-                    let size = Size {
-                        width: size.width,
-                        height: size.height,
-                    };
-                    tree.set_unrounded_layout(
-                        NodeId::new(current_node_id.into()),
-                        &Layout {
-                            size,
-                            content_size: size,
-                            scrollbar_size: Size::ZERO,
-                            border: Rect::ZERO,
-                            location: Point {
-                                x: run.offset(),
-                                y: run_y,
+                        for decoration in &mut decorations {
+                            decoration.x_offset = (decoration_start - segment.start).max(0.0);
+                        }
+
+                        let position = geo::Point::new(
+                            run.offset() + placement.offset + line_extra + lead + segment.start,
+                            run.baseline() + line_shift,
+                        );
+
+                        node_runs[node_idx].push((
+                            position,
+                            TextLayout {
+                                size: geo::Size {
+                                    width: end - segment.start,
+                                    height,
+                                },
+                                offset: geo::Point::ZERO,
+                                font_size: fs,
+                                font: Font(grun.font().clone()),
+                                glyphs: segment.glyphs,
+                                coords: coords.clone(),
+                                decorations,
+                                invisible,
+                                color: segment.color,
                             },
-                            order: 0,
-                            padding: Rect::ZERO,
-                        },
-                    );
+                        ));
+                    }
+
+                    line_extra += run_extra;
                 }
                 PositionedLayoutItem::InlineBox(inline_box) => {
+                    if inline_box.id == TEXT_INDENT_ID {
+                        continue;
+                    }
+
                     let id = NodeId::from(inline_box.id);
 
                     let size = Size {
@@ -473,8 +491,8 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
                             scrollbar_size: Size::ZERO,
                             border: Rect::ZERO,
                             location: Point {
-                                x: inline_box.x,
-                                y: inline_box.y,
+                                x: inline_box.x + placement.offset + line_extra,
+                                y: inline_box.y + line_shift,
                             },
                             order: 0,
                             padding: Rect::ZERO,
@@ -485,6 +503,49 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
         }
     }
 
+    for (data, runs) in text_node_data.iter().zip(node_runs) {
+        if runs.is_empty() {
+            continue;
+        }
+
+        // The text node is placed at the left of its leftmost run and the baseline of its first line
+        let x = runs.iter().map(|(pos, _)| pos.x).fold(f32::INFINITY, f32::min);
+        let y = runs.iter().map(|(pos, _)| pos.y).fold(f32::INFINITY, f32::min);
+
+        let mut size = Size::ZERO;
+
+        let layouts = runs
+            .into_iter()
+            .map(|(pos, mut layout)| {
+                layout.offset = geo::Point::new(pos.x - x, pos.y - y);
+
+                size.width = size.width.max(layout.offset.x + layout.size.width);
+                size.height = size.height.max(layout.offset.y + layout.size.height);
+
+                layout
+            })
+            .collect();
+
+        let node_id = LT::NodeId::from(data.id.into());
+
+        if let Some(node) = tree.0.get_node(node_id) {
+            node.set_text_layouts(layouts);
+        }
+
+        tree.set_unrounded_layout(
+            data.id,
+            &Layout {
+                size,
+                content_size: size,
+                scrollbar_size: Size::ZERO,
+                border: Rect::ZERO,
+                location: Point { x, y },
+                order: 0,
+                padding: Rect::ZERO,
+            },
+        );
+    }
+
     let mut size = content_size;
 
     if let AvailableSpace::Definite(width) = layout_input.available_space.width {
@@ -505,6 +566,292 @@ pub fn compute_inline_layout<LT: LayoutTree<TaffyLayouter>>(
     }
 }
 
+/// Placement of a line that is aligned by us instead of parley, because floats shorten it
+#[derive(Debug, Default, Clone, Copy)]
+struct LinePlacement {
+    /// Offset of the line from the left of the box
+    offset: f32,
+    /// Extra space after every space in the line, when it is justified
+    spacing: f32,
+}
+
+/// Breaks the text into lines and aligns them. Lines next to floats are aligned within the space the floats leave
+/// them, so their placement is returned. Parley aligns all other lines.
+fn break_lines<LT: LayoutTree<TaffyLayouter>>(
+    tree: &LayoutDocument<LT>,
+    layout: &mut parley::Layout<usize>,
+    text: &str,
+    text_node_data: &[TextNodeData],
+    wrap_width: Option<f32>,
+    max_width: Option<f32>,
+) -> Vec<LinePlacement> {
+    let align = text_node_data.first().map(|data| data.alignment).unwrap_or_default();
+
+    let mut placements = Vec::new();
+
+    match wrap_width {
+        Some(max_width) if tree.1.has_floats() => {
+            let line_height = text_node_data
+                .first()
+                .map(|data| data.font_size * data.line_height.unwrap_or(1.2))
+                .unwrap_or(16.0);
+
+            let mut lines = layout.break_lines();
+            let mut y = 0.0;
+            // Left edge and width of the space of every line
+            let mut bands = Vec::new();
+
+            loop {
+                let (left, right) = tree.1.band(y, line_height, 0.0, max_width);
+
+                let Some((_, height)) = lines.break_next(right - left) else {
+                    break;
+                };
+
+                bands.push((left, right - left));
+                y += height;
+            }
+
+            lines.finish();
+
+            layout.align(None, Alignment::Start);
+
+            let line_count = layout.len();
+
+            for (idx, (line, (left, width))) in layout.lines().zip(bands).enumerate() {
+                let metrics = line.metrics();
+                let free = (width - (metrics.advance - metrics.trailing_whitespace)).max(0.0);
+
+                let placement = match align {
+                    Alignment::Middle => LinePlacement {
+                        offset: left + free / 2.0,
+                        spacing: 0.0,
+                    },
+                    Alignment::End => LinePlacement {
+                        offset: left + free,
+                        spacing: 0.0,
+                    },
+                    // The last line of a paragraph is not justified
+                    Alignment::Justified if idx + 1 < line_count => {
+                        let spaces = text
+                            .get(line.text_range())
+                            .map(|line| line.trim_end().chars().filter(|c| is_space(*c)).count())
+                            .unwrap_or(0);
+
+                        LinePlacement {
+                            offset: left,
+                            spacing: if spaces > 0 { free / spaces as f32 } else { 0.0 },
+                        }
+                    }
+                    _ => LinePlacement {
+                        offset: left,
+                        spacing: 0.0,
+                    },
+                };
+
+                placements.push(placement);
+            }
+        }
+        _ => {
+            layout.break_all_lines(wrap_width);
+
+            layout.align(max_width, align);
+        }
+    }
+
+    placements
+}
+
+/// Returns true for the characters that justified text is stretched at
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\u{a0}'
+}
+
+/// Builds the parley layout for the text of an inline formatting context, with the styles of the text nodes. The
+/// styles of `::first-line` and `::first-letter` are pushed last, so they override the styles of the text nodes
+fn build_layout(
+    layout_cx: &mut LayoutContext<usize>,
+    font_cx: &mut FontContext,
+    text: &str,
+    text_node_data: &[TextNodeData],
+    inline_boxes: &[InlineBox],
+    fragments: &[FragmentStyle],
+) -> parley::Layout<usize> {
+    let mut builder = layout_cx.ranged_builder(font_cx, text, 1.0);
+
+    if let Some(default) = text_node_data.first() {
+        builder.push_default(&StyleProperty::FontStack(FontStack::Source(&default.font_family)));
+        builder.push_default(&StyleProperty::FontSize(default.font_size));
+        if let Some(line_height) = default.line_height {
+            builder.push_default(&StyleProperty::LineHeight(line_height));
+        }
+        if let Some(word_spacing) = default.word_spacing {
+            builder.push_default(&StyleProperty::WordSpacing(word_spacing));
+        }
+        if let Some(letter_spacing) = default.letter_spacing {
+            builder.push_default(&StyleProperty::LetterSpacing(letter_spacing));
+        }
+        builder.push_default(&StyleProperty::FontWeight(default.font_weight));
+        builder.push_default(&StyleProperty::FontStyle(default.font_style));
+        builder.push_default(&StyleProperty::FontVariations(FontSettings::List(&default.var_axes)));
+
+        builder.push_default(&StyleProperty::FontFeatures(FontSettings::List(&default.features)));
+
+        builder.push_default(&StyleProperty::Brush(0));
+
+        let mut from = default.to;
+
+        for (idx, text_node) in text_node_data.get(1..).unwrap_or_default().iter().enumerate() {
+            builder.push(
+                &StyleProperty::FontStack(FontStack::Source(&text_node.font_family)),
+                from..text_node.to,
+            );
+            builder.push(&StyleProperty::FontSize(text_node.font_size), from..text_node.to);
+            if let Some(line_height) = text_node.line_height {
+                builder.push(&StyleProperty::LineHeight(line_height), from..text_node.to);
+            }
+            if let Some(word_spacing) = text_node.word_spacing {
+                builder.push(&StyleProperty::WordSpacing(word_spacing), from..text_node.to);
+            }
+            if let Some(letter_spacing) = text_node.letter_spacing {
+                builder.push(&StyleProperty::LetterSpacing(letter_spacing), from..text_node.to);
+            }
+            builder.push(&StyleProperty::FontWeight(text_node.font_weight), from..text_node.to);
+            builder.push(&StyleProperty::FontStyle(text_node.font_style), from..text_node.to);
+            builder.push(
+                &StyleProperty::FontVariations(FontSettings::List(&text_node.var_axes)),
+                from..text_node.to,
+            );
+
+            builder.push(
+                &StyleProperty::FontFeatures(FontSettings::List(&text_node.features)),
+                from..text_node.to,
+            );
+
+            builder.push(&StyleProperty::Brush(idx), from..text_node.to);
+
+            from = text_node.to;
+        }
+    }
+
+    for fragment in fragments {
+        let range = fragment.range.clone();
+
+        if let Some(font_stack) = &fragment.font_stack {
+            builder.push(&StyleProperty::FontStack(FontStack::Source(font_stack)), range.clone());
+        }
+        if let Some(font_size) = fragment.font_size {
+            builder.push(&StyleProperty::FontSize(font_size), range.clone());
+        }
+        if let Some(font_weight) = fragment.font_weight {
+            builder.push(&StyleProperty::FontWeight(font_weight), range.clone());
+        }
+        if let Some(font_style) = fragment.font_style {
+            builder.push(&StyleProperty::FontStyle(font_style), range.clone());
+        }
+        if let Some(letter_spacing) = fragment.letter_spacing {
+            builder.push(&StyleProperty::LetterSpacing(letter_spacing), range.clone());
+        }
+        if let Some(word_spacing) = fragment.word_spacing {
+            builder.push(&StyleProperty::WordSpacing(word_spacing), range);
+        }
+    }
+
+    for inline_box in inline_boxes {
+        builder.push_inline_box(inline_box.clone());
+    }
+
+    builder.build()
+}
+
+/// Adds break opportunities to the text of the nodes that allow breaking words with `overflow-wrap`. The text node
+/// ranges and the inline box indices are moved to the new positions in the returned text
+fn break_overflowing_words(text: &str, text_node_data: &mut [TextNodeData], inline_boxes: &mut [InlineBox]) -> String {
+    rewrite_text(text, text_node_data, inline_boxes, |data, text| {
+        (data.overflow_wrap && data.white_space.wrap).then(|| insert_break_opportunities(text))
+    })
+}
+
+/// Removes the break opportunities from the text of the nodes that may not wrap, for when other nodes in the same
+/// lines may. The text node ranges and the inline box indices are moved to the new positions in the returned text
+fn join_unwrapped_text(text: &str, text_node_data: &mut [TextNodeData], inline_boxes: &mut [InlineBox]) -> String {
+    rewrite_text(text, text_node_data, inline_boxes, |data, text| {
+        (!data.white_space.wrap).then(|| remove_break_opportunities(text))
+    })
+}
+
+/// Replaces the text of every text node for which `rewrite` returns a new text, and moves the text node ranges and
+/// the inline box indices along
+fn rewrite_text(
+    text: &str,
+    text_node_data: &mut [TextNodeData],
+    inline_boxes: &mut [InlineBox],
+    rewrite: impl Fn(&TextNodeData, &str) -> Option<String>,
+) -> String {
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut from = 0;
+    let mut moved = vec![(0, 0)];
+
+    for data in text_node_data.iter_mut() {
+        let Some(node_text) = text.get(from..data.to) else {
+            continue;
+        };
+
+        match rewrite(data, node_text) {
+            Some(node_text) => result.push_str(&node_text),
+            None => result.push_str(node_text),
+        }
+
+        from = data.to;
+        data.to = result.len();
+        moved.push((from, data.to));
+    }
+
+    // Inline boxes are always placed between text nodes
+    for inline_box in inline_boxes.iter_mut() {
+        if let Some((_, to)) = moved.iter().rev().find(|(old, _)| *old <= inline_box.index) {
+            inline_box.index = *to;
+        }
+    }
+
+    result
+}
+
+/// Returns the `text-indent` of the first line, when this inline formatting context is at the start of its block
+fn text_indent<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    nod_id: LT::NodeId,
+    children: &[LT::NodeId],
+    layout_input: &LayoutInput,
+) -> Option<f32> {
+    let parent = tree.0.parent_id(nod_id)?;
+    let first = tree.0.children(parent)?.first().copied()?;
+
+    if Into::<u64>::into(first) != Into::<u64>::into(nod_id) {
+        return None;
+    }
+
+    let node = tree.0.get_node(*children.first()?)?;
+    let indent = node.get_property("text-indent")?;
+
+    if let Some(percentage) = indent.as_percentage() {
+        return match layout_input.available_space.width {
+            AvailableSpace::Definite(width) => Some(width * percentage / 100.0),
+            _ => None,
+        };
+    }
+
+    Some(indent.unit_to_px()).filter(|indent| *indent != 0.0)
+}
+
+/// Glyphs of a glyph run that are painted in the same color
+struct RunSegment {
+    color: Option<(f32, f32, f32, f32)>,
+    /// Advance of the run before the first glyph of the segment
+    start: f32,
+    glyphs: Vec<Glyph>,
+}
+
 #[derive(Debug)]
 struct TextNodeData {
     font_family: String,
@@ -516,26 +863,264 @@ struct TextNodeData {
     font_weight: FontWeight, // Axis: WGHT
     font_style: FontStyle,   // Axis: ITAL
     var_axes: Vec<FontVariation>,
-    decoration: Decoration,
+    white_space: WhiteSpace,
+    /// Words may be broken at any point when they would overflow the line
+    overflow_wrap: bool,
+    /// The text is laid out with a fallback font but not painted, while its web font is loading
+    invisible: bool,
+    /// OpenType features of `font-variant-*`, `font-kerning` and `font-feature-settings`
+    features: Vec<FontFeature>,
 
     to: usize,
     id: NodeId,
 }
 
-fn parse_alignment(node: &mut impl Node) -> Alignment {
-    let Some(prop) = node.get_property("text-align") else {
-        return Alignment::Start;
+/// Parses a `font-family` list into family names. Unquoted names can consist of multiple identifiers
+fn parse_font_families<V: CssValue>(values: &[V]) -> Vec<String> {
+    values
+        .split(|value| value.is_comma())
+        .map(|name| {
+            name.iter()
+                .filter_map(|value| value.as_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Builds the font stack for parley from a list of families. Families that are declared with `@font-face` are
+/// replaced by the families of their faces that match the style and weight, and cover part of the text. Returns true
+/// as well when the text should be hidden, because a face is loading within its block period
+fn font_stack(families: &[String], style: FontStyle, weight: FontWeight, text: &str) -> (String, bool) {
+    let mut stack = Vec::with_capacity(families.len());
+    let mut invisible = false;
+
+    let style = match style {
+        FontStyle::Normal => FontFaceStyle::Normal,
+        FontStyle::Italic => FontFaceStyle::Italic,
+        FontStyle::Oblique(_) => FontFaceStyle::Oblique,
     };
 
-    let Some(s) = prop.as_string() else {
-        return Alignment::Start;
+    let (Ok(mut web_fonts), Ok(mut font_cx)) = (WEB_FONTS.lock(), FONT_CX.lock()) else {
+        warn!("Failed to get the web fonts");
+        return (stack_source(families.iter().cloned()), false);
     };
 
-    match s {
+    for family in families {
+        if !web_fonts.has_family(family) {
+            stack.push(family.clone());
+            continue;
+        }
+
+        let selection = web_fonts.select(family, style, weight.value(), text, |name| {
+            font_cx.collection.family_by_name(name).is_some()
+        });
+
+        invisible |= selection.blocked;
+
+        for face in selection.faces {
+            match face {
+                FaceSource::Local(name) => stack.push(name),
+                FaceSource::Data(face) => stack.extend(register_face(&mut font_cx, &face)),
+            }
+        }
+    }
+
+    (stack_source(stack.into_iter()), invisible)
+}
+
+/// Returns the font family names of a web font face, the face is registered in the font collection the first time
+fn register_face(font_cx: &mut FontContext, face: &LoadedFace) -> Vec<String> {
+    let Ok(mut registered) = REGISTERED_FACES.lock() else {
+        return Vec::new();
+    };
+
+    registered
+        .entry(face.id)
+        .or_insert_with(|| {
+            let families = font_cx.collection.register_fonts(face.data.to_vec());
+
+            families
+                .into_iter()
+                .filter_map(|(id, _)| font_cx.collection.family_name(id).map(|name| name.to_string()))
+                .collect()
+        })
+        .clone()
+}
+
+/// Formats family names as a CSS font family list, where all names except the generic families are quoted
+fn stack_source(families: impl Iterator<Item = String>) -> String {
+    let source = families
+        .map(|family| {
+            if GENERIC_FAMILIES.contains(&family.to_ascii_lowercase().as_str()) {
+                family
+            } else {
+                format!("\"{}\"", family.replace('"', ""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if source.is_empty() {
+        return "sans-serif".to_string();
+    }
+
+    source
+}
+
+/// Collects the decorations of the boxes that decorate the text of an inline formatting context, from the outermost box
+/// to the innermost one. Decorations are propagated to all in-flow descendants of a box, but not into atomic inlines,
+/// floats and absolutely positioned boxes (<https://www.w3.org/TR/css-text-decor-3/#line-decoration>)
+fn collect_decorations<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    nod_id: LT::NodeId,
+) -> Vec<Decoration> {
+    let mut decorations = Vec::new();
+    let mut id = tree.0.parent_id(nod_id);
+
+    while let Some(node_id) = id {
+        let Some(node) = tree.0.get_node(node_id) else {
+            break;
+        };
+
+        if let Some(decoration) = parse_decoration(node) {
+            decorations.push(decoration);
+        }
+
+        if blocks_decorations(node) {
+            break;
+        }
+
+        id = tree.0.parent_id(node_id);
+    }
+
+    decorations.reverse();
+    decorations
+}
+
+/// Returns the decoration of a box, or `None` when its `text-decoration-line` doesn't draw any lines
+fn parse_decoration(node: &impl Node) -> Option<Decoration> {
+    let mut decoration = Decoration {
+        skip_ink: true,
+        ..Default::default()
+    };
+
+    for value in node.get_property("text-decoration-line")?.as_values() {
+        match value.as_string() {
+            Some("underline") => decoration.underline = true,
+            Some("overline") => decoration.overline = true,
+            Some("line-through") => decoration.line_through = true,
+            _ => {}
+        }
+    }
+
+    if !decoration.has_lines() {
+        return None;
+    }
+
+    let font_size = node.get_property("font-size").map(|s| s.unit_to_px()).unwrap_or(16.0);
+
+    if let Some(style) = node
+        .get_property("text-decoration-style")
+        .and_then(|s| s.as_string())
+        .and_then(DecorationStyle::parse)
+    {
+        decoration.style = style;
+    }
+
+    decoration.color = node
+        .get_property("text-decoration-color")
+        .and_then(|s| s.parse_color())
+        .or_else(|| node.get_property("color").and_then(|s| s.parse_color()))
+        .unwrap_or((0.0, 0.0, 0.0, 1.0));
+
+    decoration.width = node
+        .get_property("text-decoration-thickness")
+        .and_then(|prop| decoration_length(prop, font_size));
+
+    decoration.underline_offset = node
+        .get_property("text-underline-offset")
+        .and_then(|prop| decoration_length(prop, font_size));
+
+    decoration.underline_under = node
+        .get_property("text-underline-position")
+        .is_some_and(|prop| prop.as_values().iter().any(|value| value.as_string() == Some("under")));
+
+    decoration.skip_ink = node
+        .get_property("text-decoration-skip-ink")
+        .and_then(|s| s.as_string())
+        != Some("none");
+
+    Some(decoration)
+}
+
+/// Resolves the length of a decoration property, percentages are relative to the font size. Keywords like `auto` and
+/// `from-font` return `None`, so the metrics of the font are used
+fn decoration_length(prop: &impl CssProperty, font_size: f32) -> Option<f32> {
+    if prop.as_string().is_some() || prop.is_none() {
+        return None;
+    }
+
+    if let Some(percentage) = prop.as_percentage() {
+        return Some(font_size * percentage / 100.0);
+    }
+
+    Some(prop.unit_to_px())
+}
+
+/// Returns true when the decorations of the ancestors of a box are not propagated into it
+fn blocks_decorations(node: &impl Node) -> bool {
+    let keyword = |name: &str| node.get_property(name).and_then(|s| s.as_string());
+
+    keyword("display").is_some_and(|display| display.starts_with("inline-"))
+        || keyword("float").is_some_and(|float| float != "none")
+        || matches!(keyword("position"), Some("absolute" | "fixed"))
+}
+
+/// Returns the `direction`, `unicode-bidi` and `writing-mode` of the box that contains an inline formatting context
+fn paragraph_direction<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    nod_id: LT::NodeId,
+) -> (bool, UnicodeBidi, WritingMode) {
+    let Some(node) = tree.0.parent_id(nod_id).and_then(|id| tree.0.get_node(id)) else {
+        return (false, UnicodeBidi::Normal, WritingMode::HorizontalTb);
+    };
+
+    let unicode_bidi = node
+        .get_property("unicode-bidi")
+        .and_then(|s| s.as_string())
+        .map(UnicodeBidi::parse)
+        .unwrap_or_default();
+
+    let writing_mode = node
+        .get_property("writing-mode")
+        .and_then(|s| s.as_string())
+        .map(WritingMode::parse)
+        .unwrap_or_default();
+
+    (is_rtl(node), unicode_bidi, writing_mode)
+}
+
+fn is_rtl(node: &impl Node) -> bool {
+    node.get_property("direction").and_then(|s| s.as_string()) == Some("rtl")
+}
+
+/// Parley aligns lines to the left for `Start`, so `start` and `end` are flipped for right-to-left text
+fn parse_alignment(node: &mut impl Node, rtl: bool) -> Alignment {
+    let align = node
+        .get_property("text-align")
+        .and_then(|prop| prop.as_string())
+        .unwrap_or("start");
+
+    match align {
         "left" => Alignment::Start,
-        "center" => Alignment::Middle,
         "right" => Alignment::End,
+        "center" => Alignment::Middle,
         "justify" => Alignment::Justified,
+        "end" if rtl => Alignment::Start,
+        "end" => Alignment::End,
+        _ if rtl => Alignment::End,
         _ => Alignment::Start,
     }
 }
@@ -645,3 +1230,170 @@ fn parse_font_axes(n: &mut impl Node) -> Vec<FontVariation> {
 
     vars
 }
+
+/// Collects the OpenType features of the `font-variant-*` properties and `font-kerning`. The features of
+/// `font-feature-settings` are added last, so they override the features of the other properties
+/// (<https://www.w3.org/TR/css-fonts-4/#font-feature-resolution>)
+fn parse_font_features(node: &mut impl Node) -> Vec<FontFeature> {
+    let mut features = Vec::new();
+
+    for property in VARIANT_PROPERTIES {
+        let Some(prop) = node.get_property(property) else {
+            continue;
+        };
+
+        for value in prop.as_values() {
+            let keyword = match value.as_string() {
+                Some(keyword) => keyword,
+                None if value.is_none() => "none",
+                None => continue,
+            };
+
+            for (tag, value) in variant_features(property, keyword) {
+                set_feature(&mut features, tag, *value);
+            }
+        }
+    }
+
+    let Some(settings) = node.get_property("font-feature-settings") else {
+        return features;
+    };
+
+    for setting in settings.as_values().split(|value| value.is_comma()) {
+        let Some((tag, rest)) = setting.split_first() else {
+            continue;
+        };
+
+        let Some(tag) = tag.as_string().and_then(feature_tag) else {
+            continue;
+        };
+
+        let value = match rest.first() {
+            None => 1,
+            Some(value) => match value.as_string() {
+                Some("on") => 1,
+                Some("off") => 0,
+                Some(_) => continue,
+                None => match value.as_number() {
+                    Some(number) if number >= 0.0 => number as u16,
+                    _ => continue,
+                },
+            },
+        };
+
+        set_feature(&mut features, tag, value);
+    }
+
+    features
+}
+
+/// Properties that enable OpenType features with keywords, in the order their features are applied
+const VARIANT_PROPERTIES: [&str; 7] = [
+    "font-kerning",
+    "font-variant-ligatures",
+    "font-variant-caps",
+    "font-variant-numeric",
+    "font-variant-position",
+    "font-variant-east-asian",
+    "font-variant-alternates",
+];
+
+/// Returns the OpenType features that a keyword of a `font-variant-*` property or `font-kerning` sets
+fn variant_features(property: &str, keyword: &str) -> &'static [(&'static [u8; 4], u16)] {
+    match (property, keyword) {
+        ("font-kerning", "normal") => &[(b"kern", 1)],
+        ("font-kerning", "none") => &[(b"kern", 0)],
+
+        ("font-variant-ligatures", "none") => &[(b"liga", 0), (b"clig", 0), (b"dlig", 0), (b"hlig", 0), (b"calt", 0)],
+        ("font-variant-ligatures", "common-ligatures") => &[(b"liga", 1), (b"clig", 1)],
+        ("font-variant-ligatures", "no-common-ligatures") => &[(b"liga", 0), (b"clig", 0)],
+        ("font-variant-ligatures", "discretionary-ligatures") => &[(b"dlig", 1)],
+        ("font-variant-ligatures", "no-discretionary-ligatures") => &[(b"dlig", 0)],
+        ("font-variant-ligatures", "historical-ligatures") => &[(b"hlig", 1)],
+        ("font-variant-ligatures", "no-historical-ligatures") => &[(b"hlig", 0)],
+        ("font-variant-ligatures", "contextual") => &[(b"calt", 1)],
+        ("font-variant-ligatures", "no-contextual") => &[(b"calt", 0)],
+
+        ("font-variant-caps", "small-caps") => &[(b"smcp", 1)],
+        ("font-variant-caps", "all-small-caps") => &[(b"c2sc", 1), (b"smcp", 1)],
+        ("font-variant-caps", "petite-caps") => &[(b"pcap", 1)],
+        ("font-variant-caps", "all-petite-caps") => &[(b"c2pc", 1), (b"pcap", 1)],
+        ("font-variant-caps", "unicase") => &[(b"unic", 1)],
+        ("font-variant-caps", "titling-caps") => &[(b"titl", 1)],
+
+        ("font-variant-numeric", "lining-nums") => &[(b"lnum", 1)],
+        ("font-variant-numeric", "oldstyle-nums") => &[(b"onum", 1)],
+        ("font-variant-numeric", "proportional-nums") => &[(b"pnum", 1)],
+        ("font-variant-numeric", "tabular-nums") => &[(b"tnum", 1)],
+        ("font-variant-numeric", "diagonal-fractions") => &[(b"frac", 1)],
+        ("font-variant-numeric", "stacked-fractions") => &[(b"afrc", 1)],
+        ("font-variant-numeric", "ordinal") => &[(b"ordn", 1)],
+        ("font-variant-numeric", "slashed-zero") => &[(b"zero", 1)],
+
+        ("font-variant-position", "sub") => &[(b"subs", 1)],
+        ("font-variant-position", "super") => &[(b"sups", 1)],
+
+        ("font-variant-east-asian", "jis78") => &[(b"jp78", 1)],
+        ("font-variant-east-asian", "jis83") => &[(b"jp83", 1)],
+        ("font-variant-east-asian", "jis90") => &[(b"jp90", 1)],
+        ("font-variant-east-asian", "jis04") => &[(b"jp04", 1)],
+        ("font-variant-east-asian", "simplified") => &[(b"smpl", 1)],
+        ("font-variant-east-asian", "traditional") => &[(b"trad", 1)],
+        ("font-variant-east-asian", "full-width") => &[(b"fwid", 1)],
+        ("font-variant-east-asian", "proportional-width") => &[(b"pwid", 1)],
+        ("font-variant-east-asian", "ruby") => &[(b"ruby", 1)],
+
+        ("font-variant-alternates", "historical-forms") => &[(b"hist", 1)],
+
+        _ => &[],
+    }
+}
+
+/// Returns the tag of a feature name, which has to consist of four printable ASCII characters
+fn feature_tag(name: &str) -> Option<&[u8; 4]> {
+    let tag: &[u8; 4] = name.as_bytes().try_into().ok()?;
+
+    tag.iter().all(|c| (0x20..=0x7E).contains(c)).then_some(tag)
+}
+
+/// Sets the value of a feature, replacing the value that an earlier property set for it
+fn set_feature(features: &mut Vec<FontFeature>, tag: &[u8; 4], value: u16) {
+    let tag = u32::from_be_bytes(*tag);
+
+    match features.iter_mut().find(|feature| feature.tag == tag) {
+        Some(feature) => feature.value = value,
+        None => features.push(FontFeature { tag, value }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_keywords_map_to_features() {
+        assert_eq!(
+            variant_features("font-variant-caps", "all-small-caps"),
+            &[(b"c2sc", 1), (b"smcp", 1)]
+        );
+        assert_eq!(variant_features("font-kerning", "none"), &[(b"kern", 0)]);
+        // Keywords only have a meaning for their own property
+        assert!(variant_features("font-variant-caps", "none").is_empty());
+        assert!(variant_features("font-variant-numeric", "normal").is_empty());
+    }
+
+    #[test]
+    fn later_features_override_earlier_ones() {
+        let mut features = Vec::new();
+        set_feature(&mut features, b"liga", 0);
+        set_feature(&mut features, b"tnum", 1);
+        set_feature(&mut features, b"liga", 1);
+
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].tag, u32::from_be_bytes(*b"liga"));
+        assert_eq!(features[0].value, 1);
+
+        assert_eq!(feature_tag("smcp"), Some(b"smcp"));
+        assert_eq!(feature_tag("toolong"), None);
+    }
+}
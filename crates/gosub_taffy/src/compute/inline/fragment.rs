@@ -0,0 +1,228 @@
+//! `::first-line` and `::first-letter` style a fragment of the text of a block instead of a box
+//! (<https://www.w3.org/TR/css-pseudo-4/#first-text-line>). Their styles are pushed over the styles of the text nodes
+//! for the range of the fragment, when the parley layout is built
+
+use std::ops::Range;
+
+use parley::style::{FontStyle, FontWeight};
+use parley::InlineBox;
+
+use gosub_render_backend::layout::{LayoutTree, Node};
+use gosub_render_backend::Size;
+use gosub_shared::traits::css3::CssProperty;
+
+use super::{font_stack, parse_font_families, parse_font_style, parse_font_weight, TEXT_INDENT_ID};
+use crate::{LayoutDocument, TaffyLayouter};
+
+/// Style of a `::first-line` or `::first-letter`, only the properties that the pseudo-element declares are set
+#[derive(Debug, Clone, Default)]
+pub struct FragmentStyle {
+    pub families: Option<Vec<String>>,
+    /// Font stack of the families, see [`FragmentStyle::resolve_font_stack`]
+    pub font_stack: Option<String>,
+    pub font_size: Option<f32>,
+    pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontStyle>,
+    pub letter_spacing: Option<f32>,
+    pub word_spacing: Option<f32>,
+    pub color: Option<(f32, f32, f32, f32)>,
+    /// Range of the text of the inline formatting context that the style applies to
+    pub range: Range<usize>,
+}
+
+impl FragmentStyle {
+    /// Reads the style of a pseudo-element of a block, returns `None` when it doesn't declare any supported property
+    pub fn parse(node: &impl Node, pseudo: &str) -> Option<Self> {
+        let mut node = PseudoNode { node, pseudo };
+
+        let style = Self {
+            families: node
+                .get_property("font-family")
+                .map(|prop| parse_font_families(&prop.as_values())),
+            font_stack: None,
+            font_size: node.get_property("font-size").map(|prop| prop.unit_to_px()),
+            font_weight: node
+                .get_property("font-weight")
+                .is_some()
+                .then(|| parse_font_weight(&mut node)),
+            font_style: node
+                .get_property("font-style")
+                .is_some()
+                .then(|| parse_font_style(&mut node)),
+            letter_spacing: node.get_property("letter-spacing").map(|prop| prop.unit_to_px()),
+            word_spacing: node.get_property("word-spacing").map(|prop| prop.unit_to_px()),
+            color: node.get_property("color").and_then(|prop| prop.parse_color()),
+            range: 0..0,
+        };
+
+        (style.changes_metrics() || style.color.is_some()).then_some(style)
+    }
+
+    /// Resolves the font stack of the declared families. This locks the font context, so it must be done before the
+    /// layout is built
+    pub fn resolve_font_stack(&mut self, text: &str) {
+        if let Some(families) = &self.families {
+            let style = self.font_style.unwrap_or(FontStyle::Normal);
+            let weight = self.font_weight.unwrap_or(FontWeight::NORMAL);
+
+            self.font_stack = Some(font_stack(families, style, weight, text).0);
+        }
+    }
+
+    /// Returns true when the style changes the size of the glyphs, so the text needs to be laid out again
+    pub fn changes_metrics(&self) -> bool {
+        self.families.is_some()
+            || self.font_size.is_some()
+            || self.font_weight.is_some()
+            || self.font_style.is_some()
+            || self.letter_spacing.is_some()
+            || self.word_spacing.is_some()
+    }
+}
+
+/// Returns the `::first-line` and `::first-letter` styles of the block that starts with the inline formatting context
+pub fn first_fragment_styles<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    nod_id: LT::NodeId,
+) -> (Option<FragmentStyle>, Option<FragmentStyle>) {
+    let Some(parent) = tree.0.parent_id(nod_id) else {
+        return (None, None);
+    };
+
+    // Markers outside of the list item are not part of its first line
+    let first = tree.0.children(parent).unwrap_or_default().into_iter().find(|id| {
+        tree.0
+            .get_node(*id)
+            .is_some_and(|node| node.get_attribute("marker") != Some("outside"))
+    });
+
+    if first.map(Into::<u64>::into) != Some(nod_id.into()) {
+        return (None, None);
+    }
+
+    let Some(block) = tree.0.get_node(parent) else {
+        return (None, None);
+    };
+
+    (
+        FragmentStyle::parse(block, "first-line"),
+        FragmentStyle::parse(block, "first-letter"),
+    )
+}
+
+/// Places the `::first-letter` style on the first letter of the text, which has no first letter when an atomic inline
+/// comes before it
+pub fn first_letter_fragment(
+    style: Option<&FragmentStyle>,
+    text: &str,
+    start: usize,
+    inline_boxes: &[InlineBox],
+) -> Option<FragmentStyle> {
+    let range = first_letter(text, start)?;
+
+    if inline_boxes
+        .iter()
+        .any(|inline_box| inline_box.id != TEXT_INDENT_ID && inline_box.index <= range.start)
+    {
+        return None;
+    }
+
+    Some(FragmentStyle {
+        range,
+        ..style?.clone()
+    })
+}
+
+/// Returns the range of the first letter of the text after `start`, together with the punctuation around it
+/// (<https://www.w3.org/TR/css-pseudo-4/#first-letter-pattern>). Text that doesn't start with a letter or a digit
+/// has no first letter
+pub fn first_letter(text: &str, start: usize) -> Option<Range<usize>> {
+    let mut chars = text
+        .get(start..)?
+        .char_indices()
+        .map(|(idx, c)| (idx + start, c))
+        .peekable();
+
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+    let (begin, _) = *chars.peek()?;
+
+    while chars.next_if(|(_, c)| is_punctuation(*c)).is_some() {}
+
+    let (idx, letter) = chars.next()?;
+
+    if !letter.is_alphanumeric() {
+        return None;
+    }
+
+    let mut end = idx + letter.len_utf8();
+
+    while let Some((idx, c)) = chars.next_if(|(_, c)| is_punctuation(*c)) {
+        end = idx + c.len_utf8();
+    }
+
+    Some(begin..end)
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || matches!(c, '«' | '»' | '‘' | '’' | '‚' | '“' | '”' | '„' | '¡' | '¿')
+}
+
+/// Returns the color of the fragment that covers the given position of the text, the last fragment takes precedence
+pub fn fragment_color(fragments: &[FragmentStyle], idx: usize) -> Option<(f32, f32, f32, f32)> {
+    fragments
+        .iter()
+        .rev()
+        .find(|fragment| fragment.color.is_some() && fragment.range.contains(&idx))
+        .and_then(|fragment| fragment.color)
+}
+
+/// A node that reads the properties of one of the pseudo-elements of another node, so the properties can be parsed
+/// by the same functions as the properties of text nodes
+struct PseudoNode<'a, N: Node> {
+    node: &'a N,
+    pseudo: &'a str,
+}
+
+impl<N: Node> Node for PseudoNode<'_, N> {
+    type Property = N::Property;
+
+    fn get_property(&self, name: &str) -> Option<&Self::Property> {
+        self.node.get_pseudo_property(self.pseudo, name)
+    }
+
+    fn get_pseudo_property(&self, _pseudo: &str, _name: &str) -> Option<&Self::Property> {
+        None
+    }
+
+    fn text_data(&self) -> Option<&str> {
+        None
+    }
+
+    fn text_size(&self) -> Option<Size> {
+        None
+    }
+
+    fn get_attribute(&self, _name: &str) -> Option<&str> {
+        None
+    }
+
+    fn is_anon_inline_parent(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_letter_with_punctuation() {
+        assert_eq!(first_letter("Hello", 0), Some(0..1));
+        assert_eq!(first_letter("  \"Hi!\"", 0), Some(2..4));
+        assert_eq!(first_letter("«Ç» va", 0), Some(0..6));
+        assert_eq!(first_letter("\u{200F}abc", 3), Some(3..4));
+        assert_eq!(first_letter("— dash", 0), None);
+        assert_eq!(first_letter("   ", 0), None);
+    }
+}
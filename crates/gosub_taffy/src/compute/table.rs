@@ -0,0 +1,1231 @@
+use std::collections::HashMap;
+
+use taffy::{
+    compute_hidden_layout, AvailableSpace, Layout, LayoutInput, LayoutOutput, LayoutPartialTree, Line, MaybeResolve,
+    NodeId, Point, Rect, RequestedAxis, ResolveOrZero, RunMode, Size, SizingMode,
+};
+
+use gosub_render_backend::layout::{LayoutTree, Node};
+use gosub_shared::traits::css3::{CssProperty, CssValue};
+
+use crate::{Display, LayoutDocument, TaffyLayouter};
+
+/// Maximum number of columns or rows a single cell may span, same limits as the HTML spec uses
+const MAX_COL_SPAN: usize = 1000;
+const MAX_ROW_SPAN: usize = 65534;
+
+/// A cell in the table grid, placed at the first slot it occupies
+#[derive(Debug)]
+struct GridCell<Id> {
+    id: Id,
+    /// The node the cell is positioned relative to (a row, a row group or the table itself for anonymous rows)
+    parent: Id,
+    row: usize,
+    col: usize,
+    row_span: usize,
+    col_span: usize,
+    /// Height of the cell's content, as measured with the final column widths
+    height: f32,
+}
+
+/// A row in the table grid. Anonymous rows (cells that are not wrapped in a table-row) don't have a node
+#[derive(Debug)]
+struct GridRow<Id> {
+    id: Option<Id>,
+    /// The node the row is positioned relative to (a row group or the table itself)
+    parent: Id,
+    /// Height of the row as specified in its style
+    min_height: f32,
+}
+
+/// A row group (thead, tbody, tfoot) spanning the rows `start..end`
+#[derive(Debug)]
+struct GridGroup<Id> {
+    id: Id,
+    start: usize,
+    end: usize,
+}
+
+/// The table grid: all rows, cells and row groups of a table, with the cells placed in their slots
+#[derive(Debug)]
+struct TableGrid<Id> {
+    rows: Vec<GridRow<Id>>,
+    cells: Vec<GridCell<Id>>,
+    groups: Vec<GridGroup<Id>>,
+    captions: Vec<Id>,
+    /// Widths as specified by table-column (and table-column-group) elements
+    column_widths: Vec<Option<f32>>,
+    /// Occupied slots per row, needed to place cells next to cells that span multiple rows
+    occupied: Vec<Vec<bool>>,
+    /// Cells with `rowspan="0"` in the current row group, which span all rows until the end of the group
+    open_cells: Vec<usize>,
+}
+
+impl<Id: Copy> TableGrid<Id> {
+    fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            cells: Vec::new(),
+            groups: Vec::new(),
+            captions: Vec::new(),
+            column_widths: Vec::new(),
+            occupied: Vec::new(),
+            open_cells: Vec::new(),
+        }
+    }
+
+    fn start_row(&mut self, id: Option<Id>, parent: Id, min_height: f32) {
+        self.rows.push(GridRow { id, parent, min_height });
+
+        if self.occupied.len() < self.rows.len() {
+            self.occupied.push(Vec::new());
+        }
+
+        let row = self.rows.len() - 1;
+        for idx in self.open_cells.clone() {
+            let (start, span) = (self.cells[idx].col, self.cells[idx].col_span);
+            for col in start..start + span {
+                self.occupy(row, col);
+            }
+        }
+    }
+
+    fn is_occupied(&self, row: usize, col: usize) -> bool {
+        self.occupied
+            .get(row)
+            .and_then(|cols| cols.get(col))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn occupy(&mut self, row: usize, col: usize) {
+        while self.occupied.len() <= row {
+            self.occupied.push(Vec::new());
+        }
+
+        let cols = &mut self.occupied[row];
+        if cols.len() <= col {
+            cols.resize(col + 1, false);
+        }
+        cols[col] = true;
+    }
+
+    /// Adds a cell to the last row, in the first slot that is not occupied by a cell from a previous row. A row span
+    /// of 0 spans the cell until the end of the row group.
+    fn add_cell(&mut self, id: Id, parent: Id, col_span: usize, row_span: usize) {
+        let Some(row) = self.rows.len().checked_sub(1) else {
+            return;
+        };
+
+        let mut col = 0;
+        while self.is_occupied(row, col) {
+            col += 1;
+        }
+
+        for r in row..row + row_span.max(1) {
+            for c in col..col + col_span {
+                self.occupy(r, c);
+            }
+        }
+
+        if row_span == 0 {
+            self.open_cells.push(self.cells.len());
+        }
+
+        self.cells.push(GridCell {
+            id,
+            parent,
+            row,
+            col,
+            row_span,
+            col_span,
+            height: 0.0,
+        });
+    }
+
+    fn column_count(&self) -> usize {
+        let from_cells = self
+            .cells
+            .iter()
+            .map(|cell| cell.col + cell.col_span)
+            .max()
+            .unwrap_or(0);
+
+        from_cells.max(self.column_widths.len())
+    }
+
+    /// Ends the current row group: cells with a row span of 0 now span until its last row
+    fn end_group(&mut self) {
+        let row_count = self.rows.len();
+        for idx in std::mem::take(&mut self.open_cells) {
+            let cell = &mut self.cells[idx];
+            cell.row_span = row_count - cell.row;
+        }
+    }
+
+    /// Cells that span beyond the last row are clipped to the last row
+    fn clip_row_spans(&mut self) {
+        let row_count = self.rows.len();
+        for cell in &mut self.cells {
+            cell.row_span = cell.row_span.min(row_count - cell.row).max(1);
+        }
+    }
+}
+
+/// Spacing between the cells of the table, and between the cells and the table edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Spacing {
+    /// Space between the outer cells and the table's content edge
+    edge: Size<f32>,
+    /// Space between two adjacent cells. This is negative with collapsed borders, so the borders overlap
+    between: Size<f32>,
+}
+
+/// Lays out a table (or a row group or row outside a table, which gets an anonymous table generated around it)
+pub fn compute_table_layout<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    table_id: LT::NodeId,
+    layout_input: LayoutInput,
+) -> LayoutOutput {
+    if layout_input.run_mode == RunMode::PerformHiddenLayout {
+        return compute_hidden_layout(tree, NodeId::from(table_id.into()));
+    }
+
+    let style = tree.get_taffy_style(table_id).clone();
+    let parent_width = layout_input.parent_size.width;
+
+    let collapse = get_string(tree, table_id, "border-collapse") == Some("collapse".to_string());
+    let fixed = get_string(tree, table_id, "table-layout") == Some("fixed".to_string());
+
+    // Tables in the collapsing border model don't have padding
+    let padding: Rect<f32> = if collapse {
+        Rect::ZERO
+    } else {
+        style.padding.resolve_or_zero(parent_width)
+    };
+    let border: Rect<f32> = style.border.resolve_or_zero(parent_width);
+    let margin: Rect<f32> = style.margin.resolve_or_zero(parent_width);
+
+    let padding_border = Size {
+        width: padding.left + padding.right + border.left + border.right,
+        height: padding.top + padding.bottom + border.top + border.bottom,
+    };
+
+    let mut grid = build_grid(tree, table_id);
+    grid.clip_row_spans();
+
+    let spacing = if collapse {
+        collapsed_spacing(tree, &grid)
+    } else {
+        let spacing = parse_border_spacing(tree, table_id);
+        Spacing {
+            edge: spacing,
+            between: spacing,
+        }
+    };
+
+    let col_count = grid.column_count();
+
+    // The width of the table's content box, if it is known beforehand
+    let specified_width = layout_input
+        .known_dimensions
+        .width
+        .or_else(|| style.size.width.maybe_resolve(parent_width))
+        .map(|width| (width - padding_border.width).max(0.0));
+
+    let spacing_width = if col_count > 0 {
+        spacing.edge.width * 2.0 + spacing.between.width * (col_count - 1) as f32
+    } else {
+        0.0
+    };
+
+    let column_widths = if fixed && specified_width.is_some() {
+        let width = specified_width.unwrap_or_default();
+        fixed_column_widths(tree, &grid, col_count, (width - spacing_width).max(0.0), parent_width)
+    } else {
+        let (min, max) = auto_column_limits(tree, &grid, col_count, spacing, layout_input.parent_size);
+
+        let min_sum: f32 = min.iter().sum();
+        let max_sum: f32 = max.iter().sum();
+
+        let grid_width = match specified_width {
+            Some(width) => (width - spacing_width).max(min_sum),
+            None => match layout_input.available_space.width {
+                AvailableSpace::Definite(available) => {
+                    let available = available - padding_border.width - margin.left - margin.right - spacing_width;
+                    max_sum.min(available).max(min_sum)
+                }
+                AvailableSpace::MinContent => min_sum,
+                AvailableSpace::MaxContent => max_sum,
+            },
+        };
+
+        distribute_widths(&min, &max, grid_width)
+    };
+
+    let mut col_x = Vec::with_capacity(col_count);
+    let mut x = border.left + padding.left + spacing.edge.width;
+    for width in &column_widths {
+        col_x.push(x);
+        x += width + spacing.between.width;
+    }
+
+    let grid_width = column_widths.iter().sum::<f32>() + spacing_width;
+    let cell_size_parent = Size {
+        width: Some(grid_width),
+        height: None,
+    };
+
+    // Row heights are determined by the heights of the cells, with their widths fixed to the column widths
+    let mut row_heights = grid.rows.iter().map(|row| row.min_height).collect::<Vec<_>>();
+
+    for idx in 0..grid.cells.len() {
+        let cell = &grid.cells[idx];
+        let id = cell.id;
+        let width = span_size(&column_widths, cell.col, cell.col_span, spacing.between.width);
+
+        let height = measure_child(
+            tree,
+            id,
+            Size {
+                width: Some(width),
+                height: None,
+            },
+            cell_size_parent,
+            AvailableSpace::Definite(width),
+            RequestedAxis::Vertical,
+        )
+        .height;
+
+        grid.cells[idx].height = height;
+    }
+
+    let mut cell_order = (0..grid.cells.len()).collect::<Vec<_>>();
+    cell_order.sort_by_key(|idx| grid.cells[*idx].row_span);
+
+    for idx in cell_order {
+        let cell = &grid.cells[idx];
+
+        if cell.row_span == 1 {
+            row_heights[cell.row] = row_heights[cell.row].max(cell.height);
+            continue;
+        }
+
+        // Cells spanning multiple rows add any missing height to the last row they span
+        let spanned = span_size(&row_heights, cell.row, cell.row_span, spacing.between.height);
+        if cell.height > spanned {
+            let last = cell.row + cell.row_span - 1;
+            row_heights[last] += cell.height - spanned;
+        }
+    }
+
+    let captions = grid
+        .captions
+        .iter()
+        .map(|id| {
+            let bottom = get_string(tree, *id, "caption-side") == Some("bottom".to_string());
+            let size = measure_child(
+                tree,
+                *id,
+                Size {
+                    width: Some(grid_width),
+                    height: None,
+                },
+                cell_size_parent,
+                AvailableSpace::Definite(grid_width),
+                RequestedAxis::Vertical,
+            );
+
+            (*id, bottom, size.height)
+        })
+        .collect::<Vec<_>>();
+
+    let top_captions: f32 = captions.iter().filter(|c| !c.1).map(|c| c.2).sum();
+    let bottom_captions: f32 = captions.iter().filter(|c| c.1).map(|c| c.2).sum();
+
+    let row_count = grid.rows.len();
+    let mut row_y = Vec::with_capacity(row_count);
+    let mut y = border.top + padding.top + top_captions + spacing.edge.height;
+    for height in &row_heights {
+        row_y.push(y);
+        y += height + spacing.between.height;
+    }
+
+    let rows_height = if row_count > 0 {
+        row_heights.iter().sum::<f32>() + spacing.edge.height * 2.0 + spacing.between.height * (row_count - 1) as f32
+    } else {
+        0.0
+    };
+
+    let content_size = Size {
+        width: grid_width + padding_border.width,
+        height: top_captions + rows_height + bottom_captions + padding_border.height,
+    };
+
+    let specified_height = style.size.height.maybe_resolve(layout_input.parent_size.height);
+
+    let size = Size {
+        width: layout_input.known_dimensions.width.unwrap_or(content_size.width),
+        height: layout_input
+            .known_dimensions
+            .height
+            .unwrap_or_else(|| content_size.height.max(specified_height.unwrap_or(0.0))),
+    };
+
+    if layout_input.run_mode == RunMode::ComputeSize {
+        return LayoutOutput::from_sizes(size, content_size);
+    }
+
+    // Absolute positions (relative to the table's border box) of all nodes that cells and rows are placed in
+    let mut positions: HashMap<u64, Point<f32>> = HashMap::new();
+    positions.insert(table_id.into(), Point::ZERO);
+
+    let row_x = col_x.first().copied().unwrap_or(border.left + padding.left);
+    let row_width = grid_width - spacing.edge.width * 2.0;
+
+    for group in &grid.groups {
+        // Empty row groups don't take up any space
+        if group.start >= group.end {
+            continue;
+        }
+
+        let (Some(start), Some(end)) = (row_y.get(group.start), row_y.get(group.end.saturating_sub(1))) else {
+            continue;
+        };
+
+        let location = Point { x: row_x, y: *start };
+        let height = end + row_heights[group.end - 1] - start;
+
+        set_layout(
+            tree,
+            group.id,
+            location,
+            Size {
+                width: row_width,
+                height,
+            },
+        );
+        positions.insert(group.id.into(), location);
+    }
+
+    for (idx, row) in grid.rows.iter().enumerate() {
+        let Some(id) = row.id else {
+            continue;
+        };
+
+        let location = Point {
+            x: row_x,
+            y: row_y[idx],
+        };
+
+        let parent = positions
+            .get(&Into::<u64>::into(row.parent))
+            .copied()
+            .unwrap_or(Point::ZERO);
+
+        set_layout(
+            tree,
+            id,
+            relative_to(location, parent),
+            Size {
+                width: row_width,
+                height: row_heights[idx],
+            },
+        );
+        positions.insert(id.into(), location);
+    }
+
+    for cell in &grid.cells {
+        let width = span_size(&column_widths, cell.col, cell.col_span, spacing.between.width);
+        let height = span_size(&row_heights, cell.row, cell.row_span, spacing.between.height);
+
+        let location = Point {
+            x: col_x[cell.col],
+            y: row_y[cell.row],
+        };
+
+        let taffy_id = NodeId::from(cell.id.into());
+
+        // The cell must always be laid out again, because we move its children for the vertical alignment
+        tree.get_cache_mut(taffy_id).clear();
+
+        let output = tree.compute_child_layout(
+            taffy_id,
+            LayoutInput {
+                run_mode: RunMode::PerformLayout,
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                known_dimensions: Size {
+                    width: Some(width),
+                    height: Some(height),
+                },
+                parent_size: cell_size_parent,
+                available_space: Size {
+                    width: AvailableSpace::Definite(width),
+                    height: AvailableSpace::Definite(height),
+                },
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        let offset = match get_string(tree, cell.id, "vertical-align").as_deref() {
+            Some("middle") => (height - cell.height) / 2.0,
+            Some("bottom") => height - cell.height,
+            _ => 0.0,
+        };
+
+        if offset > 0.0 {
+            for child in tree.0.children(cell.id).unwrap_or_default() {
+                if let Some(layout) = tree.0.get_layout_mut(child) {
+                    layout.0.location.y += offset;
+                }
+            }
+        }
+
+        let cell_style = tree.get_taffy_style(cell.id).clone();
+
+        let parent = positions
+            .get(&Into::<u64>::into(cell.parent))
+            .copied()
+            .unwrap_or(Point::ZERO);
+
+        tree.set_unrounded_layout(
+            taffy_id,
+            &Layout {
+                order: 0,
+                location: relative_to(location, parent),
+                size: Size { width, height },
+                content_size: output.content_size,
+                scrollbar_size: Size::ZERO,
+                border: cell_style.border.resolve_or_zero(Some(grid_width)),
+                padding: cell_style.padding.resolve_or_zero(Some(grid_width)),
+            },
+        );
+    }
+
+    let mut top = border.top + padding.top;
+    let mut bottom = border.top + padding.top + top_captions + rows_height;
+
+    for (id, is_bottom, height) in captions {
+        let y = if is_bottom { &mut bottom } else { &mut top };
+
+        let taffy_id = NodeId::from(id.into());
+        tree.compute_child_layout(
+            taffy_id,
+            LayoutInput {
+                run_mode: RunMode::PerformLayout,
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                known_dimensions: Size {
+                    width: Some(grid_width),
+                    height: Some(height),
+                },
+                parent_size: cell_size_parent,
+                available_space: Size {
+                    width: AvailableSpace::Definite(grid_width),
+                    height: AvailableSpace::Definite(height),
+                },
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        set_layout(
+            tree,
+            id,
+            Point {
+                x: border.left + padding.left,
+                y: *y,
+            },
+            Size {
+                width: grid_width,
+                height,
+            },
+        );
+
+        *y += height;
+    }
+
+    LayoutOutput::from_sizes(size, content_size)
+}
+
+/// Walks the children of the table and places all cells in the table grid. Anonymous rows are generated for cells
+/// that are not inside a row, and every child of a row that isn't a table-cell is treated as an anonymous cell.
+fn build_grid<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    table_id: LT::NodeId,
+) -> TableGrid<LT::NodeId> {
+    let mut grid = TableGrid::new();
+
+    let children = tree.0.children(table_id).unwrap_or_default();
+
+    match get_display(tree, table_id) {
+        Display::TableRow => {
+            // A row outside a table: the anonymous table only contains this single row
+            grid.start_row(None, table_id, 0.0);
+            for child in children {
+                let (col_span, row_span) = get_spans(tree, child);
+                grid.add_cell(child, table_id, col_span, row_span);
+            }
+            grid.end_group();
+            return grid;
+        }
+        Display::TableHeaderGroup | Display::TableRowGroup | Display::TableFooterGroup => {
+            add_rows(tree, &mut grid, &children, table_id);
+            return grid;
+        }
+        _ => {}
+    }
+
+    let mut headers = Vec::new();
+    let mut bodies = Vec::new();
+    let mut footers = Vec::new();
+
+    for child in children {
+        if !tree.0.contains(&child) {
+            continue;
+        }
+
+        match get_display(tree, child) {
+            Display::TableCaption => grid.captions.push(child),
+            Display::TableColumnGroup => add_column_group(tree, &mut grid, child),
+            Display::TableColumn => add_column(tree, &mut grid, child),
+            Display::TableHeaderGroup => headers.push(child),
+            Display::TableFooterGroup => footers.push(child),
+            _ => bodies.push(child),
+        }
+    }
+
+    // Header groups are placed before, and footer groups after all other rows
+    for group in headers {
+        add_group(tree, &mut grid, group);
+    }
+
+    let mut loose = Vec::new();
+    for child in bodies {
+        match get_display(tree, child) {
+            Display::TableRowGroup => {
+                add_rows(tree, &mut grid, &loose, table_id);
+                loose.clear();
+                add_group(tree, &mut grid, child);
+            }
+            _ => loose.push(child),
+        }
+    }
+    add_rows(tree, &mut grid, &loose, table_id);
+
+    for group in footers {
+        add_group(tree, &mut grid, group);
+    }
+
+    grid
+}
+
+fn add_group<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &mut TableGrid<LT::NodeId>,
+    group_id: LT::NodeId,
+) {
+    let start = grid.rows.len();
+    let children = tree.0.children(group_id).unwrap_or_default();
+
+    add_rows(tree, grid, &children, group_id);
+
+    grid.groups.push(GridGroup {
+        id: group_id,
+        start,
+        end: grid.rows.len(),
+    });
+}
+
+/// Adds the given children of a table or row group as rows. Consecutive children that are not a row are wrapped
+/// in an anonymous row.
+fn add_rows<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &mut TableGrid<LT::NodeId>,
+    children: &[LT::NodeId],
+    parent: LT::NodeId,
+) {
+    let mut in_anonymous_row = false;
+
+    for child in children {
+        let child = *child;
+        if !tree.0.contains(&child) {
+            continue;
+        }
+
+        if get_display(tree, child) == Display::TableRow {
+            in_anonymous_row = false;
+
+            let min_height = tree
+                .get_taffy_style(child)
+                .size
+                .height
+                .maybe_resolve(None)
+                .unwrap_or(0.0);
+
+            grid.start_row(Some(child), parent, min_height);
+
+            for cell in tree.0.children(child).unwrap_or_default() {
+                if !tree.0.contains(&cell) {
+                    continue;
+                }
+
+                let (col_span, row_span) = get_spans(tree, cell);
+                grid.add_cell(cell, child, col_span, row_span);
+            }
+
+            continue;
+        }
+
+        if !in_anonymous_row {
+            grid.start_row(None, parent, 0.0);
+            in_anonymous_row = true;
+        }
+
+        let (col_span, row_span) = get_spans(tree, child);
+        grid.add_cell(child, parent, col_span, row_span);
+    }
+
+    grid.end_group();
+}
+
+fn add_column_group<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &mut TableGrid<LT::NodeId>,
+    group_id: LT::NodeId,
+) {
+    let columns = tree
+        .0
+        .children(group_id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| tree.0.contains(id))
+        .collect::<Vec<_>>();
+
+    if columns.is_empty() {
+        // A column group without columns acts as `span` columns itself
+        add_column(tree, grid, group_id);
+        return;
+    }
+
+    for column in columns {
+        add_column(tree, grid, column);
+    }
+}
+
+fn add_column<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &mut TableGrid<LT::NodeId>,
+    column_id: LT::NodeId,
+) {
+    let span = tree
+        .0
+        .get_node(column_id)
+        .and_then(|node| node.get_attribute("span"))
+        .and_then(|span| span.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_COL_SPAN);
+
+    let width = tree
+        .0
+        .get_node(column_id)
+        .and_then(|node| node.get_property("width"))
+        .filter(|prop| prop.as_unit().is_some() || prop.as_number().is_some())
+        .map(|prop| prop.unit_to_px());
+
+    for _ in 0..span {
+        grid.column_widths.push(width);
+    }
+}
+
+/// Computes the minimum and maximum width of every column in the automatic table layout
+fn auto_column_limits<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &TableGrid<LT::NodeId>,
+    col_count: usize,
+    spacing: Spacing,
+    parent_size: Size<Option<f32>>,
+) -> (Vec<f32>, Vec<f32>) {
+    let mut min = vec![0.0f32; col_count];
+    let mut max = vec![0.0f32; col_count];
+
+    for (idx, width) in grid.column_widths.iter().enumerate() {
+        if let Some(width) = width {
+            min[idx] = *width;
+            max[idx] = *width;
+        }
+    }
+
+    let mut spanning = Vec::new();
+
+    for cell in &grid.cells {
+        let (cell_min, cell_max) = cell_limits(tree, cell.id, parent_size);
+
+        if cell.col_span == 1 {
+            min[cell.col] = min[cell.col].max(cell_min);
+            max[cell.col] = max[cell.col].max(cell_max);
+        } else {
+            spanning.push((cell.col, cell.col_span, cell_min, cell_max));
+        }
+    }
+
+    // Cells spanning multiple columns distribute their widths over the columns, after the single-column cells
+    // have determined the base widths
+    spanning.sort_by_key(|(_, span, _, _)| *span);
+
+    for (col, span, cell_min, cell_max) in spanning {
+        let spacing = spacing.between.width * (span - 1) as f32;
+
+        grow_span(&mut min, col, span, cell_min - spacing);
+        grow_span(&mut max, col, span, cell_max - spacing);
+    }
+
+    for (min, max) in min.iter().zip(max.iter_mut()) {
+        *max = max.max(*min);
+    }
+
+    (min, max)
+}
+
+/// Returns the minimum and maximum content width of a cell, taking a specified width into account
+fn cell_limits<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    cell_id: LT::NodeId,
+    parent_size: Size<Option<f32>>,
+) -> (f32, f32) {
+    let min = measure_child(
+        tree,
+        cell_id,
+        Size::NONE,
+        parent_size,
+        AvailableSpace::MinContent,
+        RequestedAxis::Horizontal,
+    )
+    .width;
+
+    let max = measure_child(
+        tree,
+        cell_id,
+        Size::NONE,
+        parent_size,
+        AvailableSpace::MaxContent,
+        RequestedAxis::Horizontal,
+    )
+    .width;
+
+    let specified = tree.get_taffy_style(cell_id).size.width.maybe_resolve(None);
+
+    match specified {
+        Some(width) => {
+            let min = min.max(width);
+            (min, min)
+        }
+        None => (min, max.max(min)),
+    }
+}
+
+/// Grows the columns in the span so they are together at least `width` wide, proportionally to their current widths
+fn grow_span(widths: &mut [f32], col: usize, span: usize, width: f32) {
+    let Some(columns) = widths.get_mut(col..col + span) else {
+        return;
+    };
+
+    let current: f32 = columns.iter().sum();
+    if width <= current {
+        return;
+    }
+
+    let extra = width - current;
+
+    if current > 0.0 {
+        for column in columns.iter_mut() {
+            *column += extra * *column / current;
+        }
+    } else {
+        let count = columns.len() as f32;
+        for column in columns.iter_mut() {
+            *column += extra / count;
+        }
+    }
+}
+
+/// Computes the column widths with the fixed table layout: only the columns and the cells in the first row are
+/// used, the remaining width is divided equally over the columns without a width
+fn fixed_column_widths<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &TableGrid<LT::NodeId>,
+    col_count: usize,
+    width: f32,
+    parent_width: Option<f32>,
+) -> Vec<f32> {
+    let mut widths = vec![None; col_count];
+
+    for (idx, width) in grid.column_widths.iter().enumerate() {
+        widths[idx] = *width;
+    }
+
+    for cell in grid.cells.iter().filter(|cell| cell.row == 0) {
+        let Some(cell_width) = tree.get_taffy_style(cell.id).size.width.maybe_resolve(parent_width) else {
+            continue;
+        };
+
+        for width in widths.iter_mut().skip(cell.col).take(cell.col_span) {
+            if width.is_none() {
+                *width = Some(cell_width / cell.col_span as f32);
+            }
+        }
+    }
+
+    let used: f32 = widths.iter().flatten().sum();
+    let auto_count = widths.iter().filter(|w| w.is_none()).count();
+
+    let mut widths = widths
+        .into_iter()
+        .map(|w| w.unwrap_or_else(|| (width - used).max(0.0) / auto_count as f32))
+        .collect::<Vec<_>>();
+
+    // When all columns have a width that is less than the table, the extra space is divided over all columns
+    let total: f32 = widths.iter().sum();
+    if auto_count == 0 && total < width && !widths.is_empty() {
+        let extra = (width - total) / widths.len() as f32;
+        for w in &mut widths {
+            *w += extra;
+        }
+    }
+
+    widths
+}
+
+/// Distributes the given width over the columns. Every column gets at least its minimum width. When there is
+/// more space, columns grow towards their maximum width, and any space beyond that is divided proportionally to
+/// the maximum widths.
+fn distribute_widths(min: &[f32], max: &[f32], width: f32) -> Vec<f32> {
+    let min_sum: f32 = min.iter().sum();
+    let max_sum: f32 = max.iter().sum();
+
+    if width <= min_sum {
+        return min.to_vec();
+    }
+
+    if width <= max_sum {
+        let factor = (width - min_sum) / (max_sum - min_sum);
+        return min
+            .iter()
+            .zip(max)
+            .map(|(min, max)| min + (max - min) * factor)
+            .collect();
+    }
+
+    let extra = width - max_sum;
+
+    if max_sum > 0.0 {
+        max.iter().map(|max| max + extra * max / max_sum).collect()
+    } else if !max.is_empty() {
+        let per_column = extra / max.len() as f32;
+        max.iter().map(|max| max + per_column).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Returns the total size of `span` consecutive tracks, including the spacing between them
+fn span_size(sizes: &[f32], start: usize, span: usize, spacing: f32) -> f32 {
+    let tracks = sizes.iter().skip(start).take(span);
+    let count = tracks.len();
+
+    tracks.sum::<f32>() + spacing * count.saturating_sub(1) as f32
+}
+
+fn measure_child<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    id: LT::NodeId,
+    known_dimensions: Size<Option<f32>>,
+    parent_size: Size<Option<f32>>,
+    width: AvailableSpace,
+    axis: RequestedAxis,
+) -> Size<f32> {
+    tree.compute_child_layout(
+        NodeId::from(id.into()),
+        LayoutInput {
+            run_mode: RunMode::ComputeSize,
+            sizing_mode: SizingMode::InherentSize,
+            axis,
+            known_dimensions,
+            parent_size,
+            available_space: Size {
+                width,
+                height: AvailableSpace::MaxContent,
+            },
+            vertical_margins_are_collapsible: Line::FALSE,
+        },
+    )
+    .size
+}
+
+fn set_layout<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    id: LT::NodeId,
+    location: Point<f32>,
+    size: Size<f32>,
+) {
+    tree.set_unrounded_layout(
+        NodeId::from(id.into()),
+        &Layout {
+            order: 0,
+            location,
+            size,
+            content_size: size,
+            scrollbar_size: Size::ZERO,
+            border: Rect::ZERO,
+            padding: Rect::ZERO,
+        },
+    );
+}
+
+fn relative_to(point: Point<f32>, origin: Point<f32>) -> Point<f32> {
+    Point {
+        x: point.x - origin.x,
+        y: point.y - origin.y,
+    }
+}
+
+fn get_display<LT: LayoutTree<TaffyLayouter>>(tree: &mut LayoutDocument<LT>, id: LT::NodeId) -> Display {
+    // Makes sure the display in the cache is up-to-date
+    tree.get_taffy_style(id);
+
+    tree.0.get_cache(id).map(|cache| cache.display).unwrap_or_default()
+}
+
+fn get_string<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    id: LT::NodeId,
+    name: &str,
+) -> Option<String> {
+    tree.0
+        .get_node(id)
+        .and_then(|node| node.get_property(name))
+        .and_then(|prop| prop.as_string())
+        .map(|s| s.to_string())
+}
+
+/// Returns the (colspan, rowspan) of a cell. A rowspan of 0 means the cell spans until the end of its row group.
+fn get_spans<LT: LayoutTree<TaffyLayouter>>(tree: &mut LayoutDocument<LT>, id: LT::NodeId) -> (usize, usize) {
+    let Some(node) = tree.0.get_node(id) else {
+        return (1, 1);
+    };
+
+    let parse = |name: &str, min: usize, max: usize| {
+        node.get_attribute(name)
+            .and_then(|span| span.trim().parse::<usize>().ok())
+            .unwrap_or(1)
+            .clamp(min, max)
+    };
+
+    (parse("colspan", 1, MAX_COL_SPAN), parse("rowspan", 0, MAX_ROW_SPAN))
+}
+
+/// Parses `border-spacing`, which is either a single length for both axes or a horizontal and vertical length
+fn parse_border_spacing<LT: LayoutTree<TaffyLayouter>>(tree: &mut LayoutDocument<LT>, id: LT::NodeId) -> Size<f32> {
+    let Some(prop) = tree.0.get_node(id).and_then(|node| node.get_property("border-spacing")) else {
+        return Size::ZERO;
+    };
+
+    if let Some(list) = prop.as_list() {
+        let mut values = list
+            .iter()
+            .filter(|value| !value.is_comma())
+            .map(|value| value.unit_to_px());
+
+        let width = values.next().unwrap_or(0.0);
+        let height = values.next().unwrap_or(width);
+
+        return Size { width, height };
+    }
+
+    let spacing = prop.unit_to_px();
+
+    Size {
+        width: spacing,
+        height: spacing,
+    }
+}
+
+/// With collapsed borders, adjacent cells share their border. We approximate this by overlapping the cells with the
+/// widest border of the cells
+fn collapsed_spacing<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    grid: &TableGrid<LT::NodeId>,
+) -> Spacing {
+    let mut overlap = Size::ZERO;
+
+    for cell in &grid.cells {
+        let border = tree.get_taffy_style(cell.id).border;
+
+        let right: f32 = border.right.resolve_or_zero(None);
+        let bottom: f32 = border.bottom.resolve_or_zero(None);
+
+        overlap.width = overlap.width.max(right);
+        overlap.height = overlap.height.max(bottom);
+    }
+
+    Spacing {
+        edge: Size::ZERO,
+        between: Size {
+            width: -overlap.width,
+            height: -overlap.height,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gosub_css3::system::Css3System;
+    use gosub_html5::document::builder::DocumentBuilderImpl;
+    use gosub_html5::document::document_impl::DocumentImpl;
+    use gosub_html5::parser::Html5Parser;
+    use gosub_render_backend::geo::SizeU32;
+    use gosub_render_backend::layout::Layouter;
+    use gosub_rendering::render_tree::RenderTree;
+    use gosub_shared::byte_stream::{ByteStream, Encoding};
+    use gosub_shared::traits::css3::CssSystem;
+    use gosub_shared::traits::document::{Document, DocumentBuilder};
+
+    type Doc = DocumentImpl<Css3System>;
+
+    #[test]
+    fn grid_placement_with_spans() {
+        let mut grid = TableGrid::new();
+
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(1, 0, 1, 2);
+        grid.add_cell(2, 0, 2, 1);
+
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(3, 0, 1, 1);
+        grid.add_cell(4, 0, 1, 1);
+
+        let slots = grid
+            .cells
+            .iter()
+            .map(|cell| (cell.id, cell.row, cell.col))
+            .collect::<Vec<_>>();
+
+        // Cell 3 is placed after cell 1, which spans both rows
+        assert_eq!(slots, vec![(1, 0, 0), (2, 0, 1), (3, 1, 1), (4, 1, 2)]);
+        assert_eq!(grid.column_count(), 3);
+    }
+
+    #[test]
+    fn clip_row_spans() {
+        let mut grid = TableGrid::new();
+
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(1, 0, 1, 5);
+        grid.clip_row_spans();
+
+        assert_eq!(grid.cells[0].row_span, 1);
+    }
+
+    #[test]
+    fn zero_row_span_until_end_of_group() {
+        let mut grid = TableGrid::new();
+
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(1, 0, 1, 0);
+        grid.add_cell(2, 0, 1, 1);
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(3, 0, 1, 1);
+        grid.end_group();
+
+        grid.start_row(None, 0, 0.0);
+        grid.add_cell(4, 0, 1, 1);
+        grid.end_group();
+
+        let slots = grid
+            .cells
+            .iter()
+            .map(|cell| (cell.id, cell.row, cell.col, cell.row_span))
+            .collect::<Vec<_>>();
+
+        assert_eq!(slots, vec![(1, 0, 0, 2), (2, 0, 1, 1), (3, 1, 1, 1), (4, 2, 0, 1)]);
+    }
+
+    #[test]
+    fn empty_row_group() {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(
+            r#"<table style="border-spacing: 0"><thead></thead><tbody>
+            <tr><td style="height: 20px"></td></tr><tr><td style="height: 30px"></td></tr>
+            </tbody></table>"#,
+            Some(Encoding::UTF8),
+        );
+        stream.close();
+
+        let mut handle = DocumentBuilderImpl::new_document(None);
+        Html5Parser::<Doc, Css3System>::parse_document(&mut stream, handle.clone(), None).unwrap();
+        handle
+            .get_mut()
+            .add_stylesheet(Css3System::load_default_useragent_stylesheet());
+
+        let mut tree = RenderTree::<TaffyLayouter, Doc, Css3System>::from_document(handle.clone());
+        let root = tree.root;
+        TaffyLayouter.layout(&mut tree, root, SizeU32::new(800, 600)).unwrap();
+
+        let layouts = |name: &str| {
+            let mut layouts = tree
+                .nodes
+                .values()
+                .filter(|node| node.name == name)
+                .map(|node| (node.id, node.layout.0))
+                .collect::<Vec<_>>();
+            layouts.sort_by_key(|(id, _)| usize::from(*id));
+            layouts.into_iter().map(|(_, layout)| layout).collect::<Vec<_>>()
+        };
+
+        let tbody = layouts("tbody");
+        assert_eq!(tbody.len(), 1);
+        assert_eq!(tbody[0].location.y, 0.0);
+
+        let rows = layouts("tr");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].location.y, 0.0);
+        assert!(rows[1].location.y >= 20.0);
+        assert!(tbody[0].size.height >= 50.0);
+    }
+
+    #[test]
+    fn distribute_between_min_and_max() {
+        let widths = distribute_widths(&[10.0, 20.0], &[30.0, 20.0], 40.0);
+        assert_eq!(widths, vec![20.0, 20.0]);
+
+        let widths = distribute_widths(&[10.0, 20.0], &[30.0, 20.0], 10.0);
+        assert_eq!(widths, vec![10.0, 20.0]);
+    }
+
+    #[test]
+    fn distribute_beyond_max() {
+        let widths = distribute_widths(&[10.0, 10.0], &[30.0, 10.0], 80.0);
+        assert_eq!(widths, vec![60.0, 20.0]);
+
+        let widths = distribute_widths(&[0.0, 0.0], &[0.0, 0.0], 10.0);
+        assert_eq!(widths, vec![5.0, 5.0]);
+    }
+
+    #[test]
+    fn grow_spanned_columns() {
+        let mut widths = vec![10.0, 30.0, 5.0];
+        grow_span(&mut widths, 0, 2, 80.0);
+        assert_eq!(widths, vec![20.0, 60.0, 5.0]);
+
+        let mut widths = vec![0.0, 0.0];
+        grow_span(&mut widths, 0, 2, 10.0);
+        assert_eq!(widths, vec![5.0, 5.0]);
+    }
+
+    #[test]
+    fn span_sizes() {
+        assert_eq!(span_size(&[10.0, 20.0, 30.0], 1, 2, 2.0), 52.0);
+        assert_eq!(span_size(&[10.0, 20.0, 30.0], 2, 5, 2.0), 30.0);
+    }
+}
@@ -0,0 +1,84 @@
+use taffy::{LayoutInput, LayoutOutput, Point, RequestedAxis, Size};
+
+use gosub_render_backend::layout::LayoutTree;
+
+use crate::{LayoutDocument, TaffyLayouter};
+
+/// A box with a vertical writing mode inside a horizontal one is laid out as if it were horizontal, with its width
+/// and height swapped. The renderer rotates its content into place, so only the outer size needs to be physical
+pub fn transpose_input(input: LayoutInput) -> LayoutInput {
+    LayoutInput {
+        known_dimensions: transpose(input.known_dimensions),
+        parent_size: transpose(input.parent_size),
+        available_space: transpose(input.available_space),
+        axis: match input.axis {
+            RequestedAxis::Horizontal => RequestedAxis::Vertical,
+            RequestedAxis::Vertical => RequestedAxis::Horizontal,
+            RequestedAxis::Both => RequestedAxis::Both,
+        },
+        ..input
+    }
+}
+
+/// Swaps the size of a box that was laid out with [`transpose_input`] back to the physical size
+pub fn transpose_output(output: LayoutOutput) -> LayoutOutput {
+    LayoutOutput {
+        size: transpose(output.size),
+        content_size: transpose(output.content_size),
+        first_baselines: Point::NONE,
+        ..output
+    }
+}
+
+fn transpose<T>(size: Size<T>) -> Size<T> {
+    Size {
+        width: size.height,
+        height: size.width,
+    }
+}
+
+/// Stacks the children of a box from the bottom up. Boxes with `vertical-lr` are rotated like `vertical-rl`, so their
+/// blocks need to be placed in the opposite order to end up from left to right
+pub fn mirror_children<LT: LayoutTree<TaffyLayouter>>(tree: &mut LayoutDocument<LT>, node_id: LT::NodeId, height: f32) {
+    for child in tree.0.children(node_id).unwrap_or_default() {
+        if let Some(layout) = tree.0.get_layout_mut(child) {
+            layout.0.location.y = height - layout.0.location.y - layout.0.size.height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use taffy::{AvailableSpace, Line, RunMode, SizingMode};
+
+    use super::*;
+
+    #[test]
+    fn swaps_the_axes() {
+        let input = LayoutInput {
+            run_mode: RunMode::PerformLayout,
+            sizing_mode: SizingMode::InherentSize,
+            axis: RequestedAxis::Horizontal,
+            known_dimensions: Size {
+                width: Some(100.0),
+                height: None,
+            },
+            parent_size: Size {
+                width: Some(800.0),
+                height: Some(600.0),
+            },
+            available_space: Size {
+                width: AvailableSpace::Definite(800.0),
+                height: AvailableSpace::MaxContent,
+            },
+            vertical_margins_are_collapsible: Line::FALSE,
+        };
+
+        let transposed = transpose_input(input);
+
+        assert_eq!(transposed.axis, RequestedAxis::Vertical);
+        assert_eq!(transposed.known_dimensions.height, Some(100.0));
+        assert_eq!(transposed.parent_size.width, Some(600.0));
+        assert_eq!(transposed.available_space.width, AvailableSpace::MaxContent);
+    }
+}
@@ -3,14 +3,19 @@ use std::vec::IntoIter;
 use taffy::{
     compute_block_layout, compute_cached_layout, compute_flexbox_layout, compute_grid_layout, compute_hidden_layout,
     compute_root_layout, AvailableSpace, Cache as TaffyCache, Display as TaffyDisplay, Layout as TaffyLayout,
-    LayoutInput, LayoutOutput, LayoutPartialTree, NodeId as TaffyId, Style, TraversePartialTree,
+    LayoutInput, LayoutOutput, LayoutPartialTree, NodeId as TaffyId, RunMode, Style, TraversePartialTree,
 };
 
 use gosub_render_backend::geo::{Point, Rect, Size, SizeU32};
-use gosub_render_backend::layout::{Layout as TLayout, LayoutTree, Layouter, Node};
+use gosub_render_backend::layout::{Layout as TLayout, LayoutCache, LayoutTree, Layouter, Node};
+use gosub_render_backend::writing_mode::WritingMode;
 use gosub_shared::types::Result;
 
+use crate::compute::block::{compute_flow_layout, layout_absolute_child};
+use crate::compute::float::FloatContext;
 use crate::compute::inline::compute_inline_layout;
+use crate::compute::table::compute_table_layout;
+use crate::compute::writing_mode::{mirror_children, transpose_input, transpose_output};
 use crate::style::get_style_from_node;
 use crate::text::TextLayout;
 
@@ -96,16 +101,78 @@ pub enum Display {
     Inline,
     InlineBlock,
     Table,
+    TableHeaderGroup,
+    TableRowGroup,
+    TableFooterGroup,
+    TableRow,
+    TableCell,
+    TableCaption,
+    TableColumnGroup,
+    TableColumn,
     #[default]
     Taffy,
 }
 
+impl Display {
+    /// Returns true for the display types that are laid out by the table layout algorithm when they are
+    /// encountered outside a table (in which case an anonymous table is generated around them)
+    fn is_table_root(self) -> bool {
+        matches!(
+            self,
+            Display::Table
+                | Display::TableHeaderGroup
+                | Display::TableRowGroup
+                | Display::TableFooterGroup
+                | Display::TableRow
+        )
+    }
+}
+
+/// The side a box is floated to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Float {
+    #[default]
+    None,
+    Left,
+    Right,
+}
+
+/// The sides of floats a box must be placed below
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Clear {
+    #[default]
+    None,
+    Left,
+    Right,
+    Both,
+}
+
+/// Properties of a box that are needed to lay it out in a block formatting context
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Flow {
+    pub float: Float,
+    pub clear: Clear,
+    /// The box establishes a new block formatting context, so floats outside it don't affect its content,
+    /// and the floats inside it don't affect the content outside it
+    pub new_bfc: bool,
+    /// The box is positioned against the viewport instead of its containing block
+    pub fixed: bool,
+    pub writing_mode: WritingMode,
+}
+
 #[derive(Default)]
 #[allow(unused)]
 pub struct Cache {
     taffy: TaffyCache,
     style: Style,
     display: Display,
+    flow: Flow,
+}
+
+impl LayoutCache for Cache {
+    fn invalidate(&mut self) {
+        self.taffy.clear();
+    }
 }
 
 impl Layouter for TaffyLayouter {
@@ -121,17 +188,69 @@ impl Layouter for TaffyLayouter {
             height: AvailableSpace::Definite(space.height as f32),
         };
 
-        let mut tree = LayoutDocument(tree);
+        let mut tree = LayoutDocument(tree, FloatContext::default());
         Self::precompute_style(&mut tree, root);
         compute_root_layout(&mut tree, TaffyId::from(root.into()), size);
 
+        let viewport = taffy::Size {
+            width: space.width as f32,
+            height: space.height as f32,
+        };
+
+        Self::layout_fixed(&mut tree, root, taffy::Point::ZERO, viewport);
+
         Ok(())
     }
 }
 
 impl TaffyLayouter {
+    /// Lays out the fixed boxes against the viewport. Taffy has laid them out as absolute boxes against their parent,
+    /// so their location is corrected to be relative to the parent again.
+    fn layout_fixed<LT: LayoutTree<Self>>(
+        tree: &mut LayoutDocument<LT>,
+        id: LT::NodeId,
+        parent_pos: taffy::Point<f32>,
+        viewport: taffy::Size<f32>,
+    ) {
+        if tree.get_flow(id).fixed {
+            layout_absolute_child(
+                tree,
+                TaffyId::from(id.into()),
+                viewport,
+                taffy::Rect::ZERO,
+                taffy::Rect::ZERO,
+            );
+
+            if let Some(layout) = tree.0.get_layout_mut(id) {
+                layout.0.location.x -= parent_pos.x;
+                layout.0.location.y -= parent_pos.y;
+            }
+        }
+
+        let location = tree
+            .0
+            .get_layout(id)
+            .map(|layout| layout.0.location)
+            .unwrap_or(taffy::Point::ZERO);
+
+        let pos = taffy::Point {
+            x: parent_pos.x + location.x,
+            y: parent_pos.y + location.y,
+        };
+
+        for child in tree.0.children(id).unwrap_or_default() {
+            if tree.0.contains(&child) {
+                Self::layout_fixed(tree, child, pos, viewport);
+            }
+        }
+    }
+
     fn precompute_style<LT: LayoutTree<Self>>(tree: &mut LayoutDocument<LT>, root: LT::NodeId) {
-        tree.update_style(root);
+        // Nodes with a clean style keep the style that was computed during a previous layout pass
+        if tree.0.style_dirty(root) {
+            tree.update_style(root);
+            tree.0.clean_style(root);
+        }
 
         let Some(children) = tree.0.children(root) else {
             return;
@@ -143,8 +262,8 @@ impl TaffyLayouter {
     }
 }
 
-#[repr(transparent)]
-pub struct LayoutDocument<'a, LT: LayoutTree<TaffyLayouter>>(&'a mut LT);
+/// The tree that is laid out, together with the floats of the block formatting context that is currently laid out
+pub struct LayoutDocument<'a, LT: LayoutTree<TaffyLayouter>>(&'a mut LT, FloatContext);
 
 impl<LT: LayoutTree<TaffyLayouter>> TraversePartialTree for LayoutDocument<'_, LT> {
     type ChildIter<'a> = IntoIter<TaffyId>
@@ -195,11 +314,12 @@ impl<LT: LayoutTree<TaffyLayouter>> LayoutDocument<'_, LT> {
             return;
         };
 
-        let (style, display) = get_style_from_node(node);
+        let (style, display, flow) = get_style_from_node(node);
 
         if let Some(cache) = self.0.get_cache_mut(node_id) {
             cache.style = style;
             cache.display = display;
+            cache.flow = flow;
         }
     }
 
@@ -208,6 +328,7 @@ impl<LT: LayoutTree<TaffyLayouter>> LayoutDocument<'_, LT> {
 
         if dirty_style {
             self.update_style(node_id);
+            self.0.clean_style(node_id);
         }
 
         let cache = self
@@ -218,6 +339,41 @@ impl<LT: LayoutTree<TaffyLayouter>> LayoutDocument<'_, LT> {
         &cache.style
     }
 
+    fn get_flow(&mut self, node_id: LT::NodeId) -> Flow {
+        // Makes sure the cached style is up-to-date
+        self.get_taffy_style(node_id);
+
+        self.0.get_cache(node_id).map(|cache| cache.flow).unwrap_or_default()
+    }
+
+    /// Returns the writing mode of the parent element, anonymous inline boxes don't have a style of their own
+    fn parent_writing_mode(&mut self, node_id: LT::NodeId) -> WritingMode {
+        let mut id = self.0.parent_id(node_id);
+
+        while let Some(parent) = id {
+            if !self.0.get_node(parent).is_some_and(|node| node.is_anon_inline_parent()) {
+                return self.get_flow(parent).writing_mode;
+            }
+
+            id = self.0.parent_id(parent);
+        }
+
+        WritingMode::HorizontalTb
+    }
+
+    /// Returns true when one of the children of the node is floated or clears floats, so the node needs to be laid
+    /// out with the flow layout instead of taffy's block layout
+    fn has_float_children(&mut self, node_id: LT::NodeId) -> bool {
+        let Some(children) = self.0.children(node_id) else {
+            return false;
+        };
+
+        children.into_iter().filter(|id| self.0.contains(id)).any(|child| {
+            let flow = self.get_flow(child);
+            flow.float != Float::None || flow.clear != Clear::None
+        })
+    }
+
     fn get_taffy_style_no_update(&self, node_id: LT::NodeId) -> &Style {
         if let Some(cache) = self.0.get_cache(node_id) {
             return &cache.style;
@@ -261,15 +417,76 @@ impl<LT: LayoutTree<TaffyLayouter>> LayoutPartialTree for LayoutDocument<'_, LT>
                 }
             }
 
-            // let has_children = tree.0.child_count(node_id) > 0; //TODO: this isn't optimal, since we are now requesting the same node twice (up in get_cache and here)
-            let style = tree.get_taffy_style(node_id);
+            let writing_mode = tree.get_flow(node_id).writing_mode;
 
-            match style.display {
-                TaffyDisplay::None => compute_hidden_layout(tree, node_id_taffy),
-                TaffyDisplay::Block => compute_block_layout(tree, node_id_taffy, inputs),
-                TaffyDisplay::Flex => compute_flexbox_layout(tree, node_id_taffy, inputs),
-                TaffyDisplay::Grid => compute_grid_layout(tree, node_id_taffy, inputs),
+            // A vertical box in a horizontal one is laid out sideways, and rotated when it is painted
+            //TODO: the sizes, margins and paddings of the boxes inside it are still taken as logical values
+            let orthogonal = writing_mode.is_vertical() && !tree.parent_writing_mode(node_id).is_vertical();
+
+            let inputs = if orthogonal { transpose_input(inputs) } else { inputs };
+
+            let output = compute_box_layout(tree, node_id, inputs);
+
+            if writing_mode.is_left_to_right() && inputs.run_mode == RunMode::PerformLayout {
+                mirror_children(tree, node_id, output.size.height);
+            }
+
+            if orthogonal {
+                transpose_output(output)
+            } else {
+                output
             }
         })
     }
 }
+
+/// Lays out a box with the layout algorithm of its display type
+fn compute_box_layout<LT: LayoutTree<TaffyLayouter>>(
+    tree: &mut LayoutDocument<LT>,
+    node_id: LT::NodeId,
+    inputs: LayoutInput,
+) -> LayoutOutput {
+    let node_id_taffy = TaffyId::from(node_id.into());
+
+    // let has_children = tree.0.child_count(node_id) > 0; //TODO: this isn't optimal, since we are now requesting the same node twice (up in get_cache and here)
+    let display = tree.get_taffy_style(node_id).display;
+
+    let (layout_display, flow) = tree
+        .0
+        .get_cache(node_id)
+        .map(|cache| (cache.display, cache.flow))
+        .unwrap_or_default();
+
+    if display == TaffyDisplay::Block && layout_display == Display::Taffy {
+        // Boxes that don't establish a new block formatting context take part in the one of their parent
+        if !flow.new_bfc && (tree.1.has_floats() || tree.has_float_children(node_id)) {
+            return compute_flow_layout(tree, node_id, inputs, false);
+        }
+
+        if tree.has_float_children(node_id) {
+            let outer = std::mem::take(&mut tree.1);
+            let output = compute_flow_layout(tree, node_id, inputs, true);
+            tree.1 = outer;
+
+            return output;
+        }
+    }
+
+    // All other layout algorithms establish a new block formatting context for their children
+    let outer = std::mem::take(&mut tree.1);
+
+    let output = if display != TaffyDisplay::None && layout_display.is_table_root() {
+        compute_table_layout(tree, node_id, inputs)
+    } else {
+        match display {
+            TaffyDisplay::None => compute_hidden_layout(tree, node_id_taffy),
+            TaffyDisplay::Block => compute_block_layout(tree, node_id_taffy, inputs),
+            TaffyDisplay::Flex => compute_flexbox_layout(tree, node_id_taffy, inputs),
+            TaffyDisplay::Grid => compute_grid_layout(tree, node_id_taffy, inputs),
+        }
+    };
+
+    tree.1 = outer;
+
+    output
+}
@@ -1,19 +1,36 @@
-use taffy::Style;
+use taffy::{LengthPercentageAuto, Position, Style};
 
-use crate::Display;
+use crate::{Display, Float, Flow};
 use gosub_render_backend::layout::Node;
+use gosub_render_backend::writing_mode::WritingMode;
+use gosub_shared::traits::css3::CssProperty;
 
 mod parse;
 mod parse_properties;
 
 const SCROLLBAR_WIDTH: f32 = 16.0;
 
-pub fn get_style_from_node(node: &mut impl Node) -> (Style, Display) {
+pub fn get_style_from_node(node: &mut impl Node) -> (Style, Display, Flow) {
     //TODO: theoretically we should limit this to the taffy layouter, since it doesn't make any sense otherweise
-    let (display, disp) = parse_properties::parse_display(node);
+    let (display, mut disp) = parse_properties::parse_display(node);
+    let float = parse_properties::parse_float(node);
+    let clear = parse_properties::parse_clear(node);
+
+    // Floated inline boxes are laid out as blocks
+    if float != Float::None && matches!(disp, Display::Inline | Display::InlineBlock) {
+        disp = Display::Taffy;
+    }
+
     let overflow = parse_properties::parse_overflow(node);
-    let position = parse_properties::parse_position(node);
-    let inset = parse_properties::parse_inset(node);
+    let mut position = parse_properties::parse_position(node);
+    let mut inset = parse_properties::parse_inset(node);
+
+    // Markers outside of their list item end where the content of the list item starts
+    if node.get_attribute("marker") == Some("outside") {
+        disp = Display::Taffy;
+        position = Position::Absolute;
+        inset.right = LengthPercentageAuto::Percent(1.0);
+    }
     let size = parse_properties::parse_size(node);
     let min_size = parse_properties::parse_min_size(node);
     let max_size = parse_properties::parse_max_size(node);
@@ -41,40 +58,57 @@ pub fn get_style_from_node(node: &mut impl Node) -> (Style, Display) {
     let grid_row = parse_properties::parse_grid_row(node);
     let grid_column = parse_properties::parse_grid_column(node);
 
-    (
-        Style {
-            display,
-            overflow,
-            scrollbar_width: SCROLLBAR_WIDTH,
-            position,
-            inset,
-            size,
-            min_size,
-            max_size,
-            aspect_ratio,
-            margin,
-            padding,
-            border,
-            align_items,
-            align_self,
-            justify_items,
-            justify_self,
-            align_content,
-            justify_content,
-            gap,
-            flex_direction,
-            flex_wrap,
-            flex_basis,
-            flex_grow,
-            flex_shrink,
-            grid_template_rows,
-            grid_template_columns,
-            grid_auto_rows,
-            grid_auto_columns,
-            grid_auto_flow,
-            grid_row,
-            grid_column,
-        },
-        disp,
-    )
+    let style = Style {
+        display,
+        overflow,
+        scrollbar_width: SCROLLBAR_WIDTH,
+        position,
+        inset,
+        size,
+        min_size,
+        max_size,
+        aspect_ratio,
+        margin,
+        padding,
+        border,
+        align_items,
+        align_self,
+        justify_items,
+        justify_self,
+        align_content,
+        justify_content,
+        gap,
+        flex_direction,
+        flex_wrap,
+        flex_basis,
+        flex_grow,
+        flex_shrink,
+        grid_template_rows,
+        grid_template_columns,
+        grid_auto_rows,
+        grid_auto_columns,
+        grid_auto_flow,
+        grid_row,
+        grid_column,
+    };
+
+    let new_bfc = parse_properties::parse_new_bfc(node, &style, disp, float);
+
+    let fixed = parse_properties::parse_fixed(node);
+
+    let writing_mode = node
+        .get_property("writing-mode")
+        .and_then(|prop| prop.as_string())
+        .map(WritingMode::parse)
+        .unwrap_or_default();
+
+    let flow = Flow {
+        float,
+        clear,
+        new_bfc,
+        fixed,
+        writing_mode,
+    };
+
+    (style, disp, flow)
 }
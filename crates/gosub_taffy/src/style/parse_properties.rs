@@ -23,9 +23,19 @@ pub fn parse_display(node: &mut impl Node) -> (Display, crate::Display) {
         "block" => (Display::Block, crate::Display::Taffy),
         "flex" => (Display::Flex, crate::Display::Taffy),
         "grid" => (Display::Grid, crate::Display::Taffy),
+        "flow-root" => (Display::Block, crate::Display::Taffy),
         "inline-block" => (Display::Block, crate::Display::InlineBlock),
         "inline" => (Display::Block, crate::Display::Inline),
-        "table" => (Display::Block, crate::Display::Table),
+        "table" | "inline-table" => (Display::Block, crate::Display::Table),
+        "table-header-group" => (Display::Block, crate::Display::TableHeaderGroup),
+        "table-row-group" => (Display::Block, crate::Display::TableRowGroup),
+        "table-footer-group" => (Display::Block, crate::Display::TableFooterGroup),
+        "table-row" => (Display::Block, crate::Display::TableRow),
+        "table-cell" => (Display::Block, crate::Display::TableCell),
+        "table-caption" => (Display::Block, crate::Display::TableCaption),
+        // Columns don't generate boxes, the table algorithm only reads their width
+        "table-column-group" => (Display::None, crate::Display::TableColumnGroup),
+        "table-column" => (Display::None, crate::Display::TableColumn),
         _ => (Display::Block, crate::Display::Taffy),
     }
 }
@@ -72,18 +82,84 @@ pub fn parse_position(node: &mut impl Node) -> Position {
     };
 
     match value {
-        "relative" => Position::Relative,
-        "absolute" => Position::Absolute,
+        "relative" | "sticky" => Position::Relative,
+        // Fixed boxes are laid out against the viewport after the layout of the tree
+        "absolute" | "fixed" => Position::Absolute,
         _ => Position::Relative,
     }
 }
 
+pub fn parse_fixed(node: &mut impl Node) -> bool {
+    property_is(node, "position", &["fixed"])
+}
+
+pub fn parse_float(node: &mut impl Node) -> crate::Float {
+    let Some(float) = node.get_property("float") else {
+        return crate::Float::None;
+    };
+
+    match float.as_string() {
+        Some("left") | Some("inline-start") => crate::Float::Left,
+        Some("right") | Some("inline-end") => crate::Float::Right,
+        _ => crate::Float::None,
+    }
+}
+
+pub fn parse_clear(node: &mut impl Node) -> crate::Clear {
+    let Some(clear) = node.get_property("clear") else {
+        return crate::Clear::None;
+    };
+
+    match clear.as_string() {
+        Some("left") | Some("inline-start") => crate::Clear::Left,
+        Some("right") | Some("inline-end") => crate::Clear::Right,
+        Some("both") => crate::Clear::Both,
+        _ => crate::Clear::None,
+    }
+}
+
+/// Returns true when the box establishes a new block formatting context for its content
+pub fn parse_new_bfc(node: &mut impl Node, style: &Style, display: crate::Display, float: crate::Float) -> bool {
+    if float != crate::Float::None || style.position == Position::Absolute {
+        return true;
+    }
+
+    if style.display != Display::Block || display != crate::Display::Taffy {
+        return true;
+    }
+
+    if style.overflow.x != Overflow::Visible || style.overflow.y != Overflow::Visible {
+        return true;
+    }
+
+    // Overflow values that don't have an equivalent in taffy
+    property_is(node, "display", &["flow-root"])
+        || property_is(node, "overflow-x", &["auto", "clip"])
+        || property_is(node, "overflow-y", &["auto", "clip"])
+}
+
+fn property_is(node: &mut impl Node, name: &str, values: &[&str]) -> bool {
+    node.get_property(name)
+        .and_then(|prop| prop.as_string())
+        .is_some_and(|value| values.contains(&value))
+}
+
 pub fn parse_inset(node: &mut impl Node) -> Rect<LengthPercentageAuto> {
+    // Static boxes ignore their insets, and sticky boxes are only moved when scrolling
+    if !property_is(node, "position", &["relative", "absolute", "fixed"]) {
+        return Rect {
+            top: LengthPercentageAuto::Auto,
+            right: LengthPercentageAuto::Auto,
+            bottom: LengthPercentageAuto::Auto,
+            left: LengthPercentageAuto::Auto,
+        };
+    }
+
     Rect {
-        top: parse_len_auto(node, "inset-top"),
-        right: parse_len_auto(node, "inset-right"),
-        bottom: parse_len_auto(node, "inset-bottom"),
-        left: parse_len_auto(node, "inset-left"),
+        top: parse_len_auto(node, "top"),
+        right: parse_len_auto(node, "right"),
+        bottom: parse_len_auto(node, "bottom"),
+        left: parse_len_auto(node, "left"),
     }
 }
 
@@ -1,5 +1,5 @@
 use gosub_render_backend::layout::{Decoration, TextLayout as TLayout};
-use gosub_render_backend::Size;
+use gosub_render_backend::{Point, Size};
 use gosub_typeface::font::Font as TFont;
 use gosub_typeface::font::Glyph;
 use parley::Font as PFont;
@@ -25,8 +25,11 @@ pub struct TextLayout {
     pub font: Font,
     pub font_size: f32,
     pub size: Size,
+    pub offset: Point,
     pub coords: Vec<i16>,
-    pub decoration: Decoration,
+    pub decorations: Vec<Decoration>,
+    pub invisible: bool,
+    pub color: Option<(f32, f32, f32, f32)>,
 }
 
 impl TLayout for TextLayout {
@@ -40,6 +43,10 @@ impl TLayout for TextLayout {
         self.size
     }
 
+    fn offset(&self) -> Point {
+        self.offset
+    }
+
     fn glyphs(&self) -> &[Glyph] {
         &self.glyphs
     }
@@ -56,7 +63,15 @@ impl TLayout for TextLayout {
         &self.coords
     }
 
-    fn decorations(&self) -> &Decoration {
-        &self.decoration
+    fn decorations(&self) -> &[Decoration] {
+        &self.decorations
+    }
+
+    fn invisible(&self) -> bool {
+        self.invisible
+    }
+
+    fn color(&self) -> Option<(f32, f32, f32, f32)> {
+        self.color
     }
 }
@@ -308,5 +308,7 @@
 // }
 
 pub mod font;
+pub mod web_fonts;
+pub mod woff;
 
 pub const ROBOTO_FONT: &[u8] = include_bytes!("../../../resources/fonts/Roboto-Regular.ttf");
@@ -0,0 +1,364 @@
+//! Registry of the web fonts declared with `@font-face` rules. Layout selects the faces of a font family from the
+//! registry, which requests the faces that haven't been loaded yet. The renderer loads the requested faces, and lays
+//! out the document again when the generation of the registry changes.
+
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+
+use gosub_shared::font::{FontFace, FontFaceStyle, FontSource};
+
+pub static WEB_FONTS: LazyLock<Mutex<WebFonts>> = LazyLock::new(|| Mutex::new(WebFonts::default()));
+
+/// The data of a loaded face. The id is unique for every face, so the data only needs to be registered once
+#[derive(Debug, Clone)]
+pub struct LoadedFace {
+    pub id: u64,
+    pub data: Arc<Vec<u8>>,
+}
+
+/// A face that can be used to render text
+#[derive(Debug, Clone)]
+pub enum FaceSource {
+    /// A downloaded font file
+    Data(LoadedFace),
+    /// A font family that is installed on the system
+    Local(String),
+}
+
+/// The faces that are used for a font family
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub faces: Vec<FaceSource>,
+    /// A matching face is loading and within the block period of its `font-display`, so the text is invisible
+    pub blocked: bool,
+}
+
+/// A face that needs to be loaded from one of its sources, which are tried in order
+#[derive(Debug, Clone)]
+pub struct FontRequest {
+    pub id: u64,
+    /// Url of the stylesheet the sources are relative to
+    pub base_url: String,
+    pub sources: Vec<FontSource>,
+}
+
+#[derive(Debug, Clone)]
+enum LoadState {
+    /// The face hasn't been used by any text yet
+    Unloaded,
+    Loading {
+        since: Instant,
+        /// The renderer took the request
+        requested: bool,
+        /// Text has been hidden because of this face
+        hiding: bool,
+    },
+    Loaded(LoadedFace),
+    Local(String),
+    /// No source could be loaded, or the face loaded too late for its `font-display`
+    Failed,
+}
+
+#[derive(Debug)]
+struct WebFont {
+    id: u64,
+    face: FontFace,
+    base_url: String,
+    state: LoadState,
+}
+
+#[derive(Debug, Default)]
+pub struct WebFonts {
+    fonts: Vec<WebFont>,
+    /// Changes whenever the fonts that are used for text change, so text needs to be laid out again
+    generation: u64,
+    next_id: u64,
+}
+
+impl WebFonts {
+    /// Replaces the declared faces with the faces of the stylesheets, together with the url of their stylesheet.
+    /// Faces that have been declared before keep their load state
+    pub fn set_faces(&mut self, faces: Vec<(FontFace, String)>) {
+        let mut old = std::mem::take(&mut self.fonts);
+
+        for (face, base_url) in faces {
+            if let Some(idx) = old
+                .iter()
+                .position(|font| font.face == face && font.base_url == base_url)
+            {
+                self.fonts.push(old.swap_remove(idx));
+                continue;
+            }
+
+            self.next_id += 1;
+            self.fonts.push(WebFont {
+                id: self.next_id,
+                face,
+                base_url,
+                state: LoadState::Unloaded,
+            });
+        }
+    }
+
+    /// Returns true when a face has been declared for the family
+    pub fn has_family(&self, family: &str) -> bool {
+        self.fonts
+            .iter()
+            .any(|font| font.face.family.eq_ignore_ascii_case(family))
+    }
+
+    /// Selects the faces of a family that best match the style and weight, and that cover part of the text. A family
+    /// can consist of multiple faces with the same descriptors that cover different unicode ranges. Faces that are
+    /// used for the first time start loading, unless one of their `local()` sources is installed
+    pub fn select(
+        &mut self,
+        family: &str,
+        style: FontFaceStyle,
+        weight: f32,
+        text: &str,
+        mut is_installed: impl FnMut(&str) -> bool,
+    ) -> Selection {
+        let mut selection = Selection::default();
+
+        let candidates = self
+            .fonts
+            .iter()
+            .enumerate()
+            .filter(|(_, font)| font.face.family.eq_ignore_ascii_case(family) && font.face.covers_any(text))
+            .map(|(idx, font)| (idx, font.face.match_key(style, weight)))
+            .collect::<Vec<_>>();
+
+        let Some(best) = candidates.iter().map(|(_, key)| *key).min() else {
+            return selection;
+        };
+
+        for (idx, _) in candidates.into_iter().filter(|(_, key)| *key == best) {
+            let font = &mut self.fonts[idx];
+
+            if let LoadState::Unloaded = font.state {
+                font.state = start_loading(&font.face, &mut is_installed);
+            }
+
+            match &mut font.state {
+                LoadState::Loaded(loaded) => selection.faces.push(FaceSource::Data(loaded.clone())),
+                LoadState::Local(name) => selection.faces.push(FaceSource::Local(name.clone())),
+                LoadState::Loading { since, hiding, .. } => {
+                    if since.elapsed() < font.face.display.block_period() {
+                        *hiding = true;
+                        selection.blocked = true;
+                    }
+                }
+                LoadState::Unloaded | LoadState::Failed => {}
+            }
+        }
+
+        selection
+    }
+
+    /// Returns the faces that started loading since the last call
+    pub fn take_requests(&mut self) -> Vec<FontRequest> {
+        let mut requests = Vec::new();
+
+        for font in &mut self.fonts {
+            if let LoadState::Loading { requested, .. } = &mut font.state {
+                if !*requested {
+                    *requested = true;
+                    requests.push(FontRequest {
+                        id: font.id,
+                        base_url: font.base_url.clone(),
+                        sources: font.face.sources.clone(),
+                    });
+                }
+            }
+        }
+
+        requests
+    }
+
+    /// Stores the result of loading a face, the data is `None` when none of the sources could be loaded. A face that
+    /// loaded after the swap period of its `font-display` is not used anymore
+    pub fn finish(&mut self, id: u64, data: Option<Vec<u8>>) {
+        let Some(font) = self.fonts.iter_mut().find(|font| font.id == id) else {
+            return;
+        };
+
+        let LoadState::Loading { since, hiding, .. } = font.state else {
+            return;
+        };
+
+        font.state = match data {
+            Some(data) if font.face.display.accepts_load_after(since.elapsed()) => {
+                self.generation += 1;
+                LoadState::Loaded(LoadedFace {
+                    id,
+                    data: Arc::new(data),
+                })
+            }
+            _ => {
+                // Hidden text is shown with the fallback font
+                if hiding {
+                    self.generation += 1;
+                }
+                LoadState::Failed
+            }
+        };
+    }
+
+    /// Ends the block periods and swap periods that have passed, and returns the current generation
+    pub fn poll(&mut self) -> u64 {
+        for font in &mut self.fonts {
+            let LoadState::Loading { since, hiding, .. } = &mut font.state else {
+                continue;
+            };
+
+            let display = font.face.display;
+            let elapsed = since.elapsed();
+
+            if *hiding && elapsed >= display.block_period() {
+                *hiding = false;
+                self.generation += 1;
+            }
+
+            if !display.accepts_load_after(elapsed) {
+                font.state = LoadState::Failed;
+            }
+        }
+
+        self.generation
+    }
+
+    /// Returns true when faces are still loading
+    pub fn pending(&self) -> bool {
+        self.fonts
+            .iter()
+            .any(|font| matches!(font.state, LoadState::Loading { .. }))
+    }
+}
+
+/// Returns the state of a face that is used for the first time: an installed `local()` source is used right away,
+/// otherwise the face loads when it has a source in a supported format
+fn start_loading(face: &FontFace, is_installed: &mut impl FnMut(&str) -> bool) -> LoadState {
+    for source in &face.sources {
+        if let FontSource::Local(name) = source {
+            if is_installed(name) {
+                return LoadState::Local(name.clone());
+            }
+        }
+    }
+
+    let loadable = face
+        .sources
+        .iter()
+        .any(|source| matches!(source, FontSource::Url { .. }) && source.is_supported());
+
+    if !loadable {
+        return LoadState::Failed;
+    }
+
+    LoadState::Loading {
+        since: Instant::now(),
+        requested: false,
+        hiding: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use gosub_shared::font::FontDisplay;
+
+    use super::*;
+
+    fn face(family: &str, weight: f32, display: FontDisplay, sources: Vec<FontSource>) -> FontFace {
+        FontFace {
+            family: family.to_string(),
+            sources,
+            weight: (weight, weight),
+            style: FontFaceStyle::Normal,
+            unicode_range: Vec::new(),
+            display,
+        }
+    }
+
+    fn url(url: &str) -> FontSource {
+        FontSource::Url {
+            url: url.to_string(),
+            format: None,
+        }
+    }
+
+    #[test]
+    fn loads_best_matching_face() {
+        let mut fonts = WebFonts::default();
+        fonts.set_faces(vec![
+            (
+                face("Web", 400.0, FontDisplay::Block, vec![url("regular.ttf")]),
+                "a.css".into(),
+            ),
+            (
+                face("Web", 700.0, FontDisplay::Block, vec![url("bold.ttf")]),
+                "a.css".into(),
+            ),
+        ]);
+
+        let selection = fonts.select("web", FontFaceStyle::Normal, 700.0, "text", |_| false);
+        assert!(selection.faces.is_empty());
+        assert!(selection.blocked);
+
+        let requests = fonts.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].sources, vec![url("bold.ttf")]);
+        assert!(fonts.take_requests().is_empty());
+
+        let generation = fonts.poll();
+        fonts.finish(requests[0].id, Some(vec![1, 2, 3]));
+        assert_eq!(fonts.poll(), generation + 1);
+        assert!(!fonts.pending());
+
+        let selection = fonts.select("Web", FontFaceStyle::Normal, 700.0, "text", |_| false);
+        assert!(matches!(&selection.faces[..], [FaceSource::Data(face)] if *face.data == vec![1, 2, 3]));
+        assert!(!selection.blocked);
+    }
+
+    #[test]
+    fn installed_local_sources_are_used() {
+        let mut fonts = WebFonts::default();
+        fonts.set_faces(vec![(
+            face(
+                "Web",
+                400.0,
+                FontDisplay::Auto,
+                vec![FontSource::Local("Installed".into()), url("web.ttf")],
+            ),
+            "a.css".into(),
+        )]);
+
+        let selection = fonts.select("Web", FontFaceStyle::Normal, 400.0, "text", |name| name == "Installed");
+        assert!(matches!(&selection.faces[..], [FaceSource::Local(name)] if name == "Installed"));
+        assert!(fonts.take_requests().is_empty());
+    }
+
+    #[test]
+    fn late_loads_are_ignored_for_optional_faces() {
+        let mut fonts = WebFonts::default();
+        fonts.set_faces(vec![(
+            face("Web", 400.0, FontDisplay::Optional, vec![url("web.ttf")]),
+            "a.css".into(),
+        )]);
+
+        fonts.select("Web", FontFaceStyle::Normal, 400.0, "text", |_| false);
+        let requests = fonts.take_requests();
+
+        // Pretend the load started longer ago than the block period of optional faces
+        if let LoadState::Loading { since, .. } = &mut fonts.fonts[0].state {
+            *since -= Duration::from_secs(1);
+        }
+
+        fonts.finish(requests[0].id, Some(vec![1]));
+
+        let selection = fonts.select("Web", FontFaceStyle::Normal, 400.0, "text", |_| false);
+        assert!(selection.faces.is_empty());
+        assert!(!selection.blocked);
+    }
+}
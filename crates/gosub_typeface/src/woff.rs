@@ -0,0 +1,189 @@
+//! Decoding of web font files into the sfnt (TrueType/OpenType) data that the font collection loads.
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+/// Size of the WOFF header (<https://www.w3.org/TR/WOFF/#WOFFHeader>)
+const WOFF_HEADER_SIZE: usize = 44;
+/// Size of a table directory entry in a WOFF file
+const WOFF_ENTRY_SIZE: usize = 20;
+/// Largest sfnt data a WOFF file may unpack to, the size of the tables is taken from the file so it is capped
+const MAX_SFNT_SIZE: usize = 64 * 1024 * 1024;
+
+/// Returns the sfnt data of a font file. WOFF files are unpacked, sfnt data is returned as is. Returns `None` for
+/// formats that can't be decoded. WOFF2 is not supported, as it needs brotli and the reconstruction of its
+/// transformed `glyf` and `loca` tables
+pub fn decode_font(data: Vec<u8>) -> Option<Vec<u8>> {
+    match data.get(0..4)? {
+        b"wOFF" => decode_woff(&data),
+        [0, 1, 0, 0] | b"OTTO" | b"true" | b"ttcf" => Some(data),
+        _ => None,
+    }
+}
+
+/// Unpacks a WOFF file into sfnt data, by inflating its tables and writing a new table directory
+pub fn decode_woff(data: &[u8]) -> Option<Vec<u8>> {
+    let u16_at = |pos: usize| data.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let u32_at = |pos: usize| {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if data.get(0..4)? != b"wOFF" {
+        return None;
+    }
+
+    let flavor = u32_at(4)?;
+    let num_tables = u16_at(12)? as usize;
+
+    let mut tables = Vec::with_capacity(num_tables);
+    let mut total_size = 12 + 16 * num_tables;
+    for idx in 0..num_tables {
+        let entry = WOFF_HEADER_SIZE + idx * WOFF_ENTRY_SIZE;
+
+        let tag = u32_at(entry)?;
+        let offset = u32_at(entry + 4)? as usize;
+        let comp_length = u32_at(entry + 8)? as usize;
+        let orig_length = u32_at(entry + 12)? as usize;
+        let checksum = u32_at(entry + 16)?;
+
+        let stored = data.get(offset..offset.checked_add(comp_length)?)?;
+
+        total_size = total_size.checked_add((orig_length.checked_add(3)?) & !3)?;
+        if total_size > MAX_SFNT_SIZE {
+            return None;
+        }
+
+        // Tables are only compressed when that makes them smaller. The stream is read up to one byte past the
+        // declared length, so a table that inflates to more than it claims is rejected without unpacking all of it
+        let table = if comp_length < orig_length {
+            let mut table = Vec::with_capacity(orig_length);
+            ZlibDecoder::new(stored)
+                .take(orig_length as u64 + 1)
+                .read_to_end(&mut table)
+                .ok()?;
+            if table.len() != orig_length {
+                return None;
+            }
+            table
+        } else {
+            stored.to_vec()
+        };
+
+        tables.push((tag, checksum, table));
+    }
+
+    let mut power = 1;
+    let mut entry_selector = 0u16;
+    while power * 2 <= num_tables {
+        power *= 2;
+        entry_selector += 1;
+    }
+    let search_range = (power * 16) as u16;
+    let range_shift = (num_tables * 16) as u16 - search_range;
+
+    let mut out = Vec::new();
+    out.extend(flavor.to_be_bytes());
+    out.extend((num_tables as u16).to_be_bytes());
+    out.extend(search_range.to_be_bytes());
+    out.extend(entry_selector.to_be_bytes());
+    out.extend(range_shift.to_be_bytes());
+
+    // Tables start after the table directory and are aligned to 4 bytes
+    let mut offset = 12 + 16 * num_tables;
+    for (tag, checksum, table) in &tables {
+        out.extend(tag.to_be_bytes());
+        out.extend(checksum.to_be_bytes());
+        out.extend((offset as u32).to_be_bytes());
+        out.extend((table.len() as u32).to_be_bytes());
+
+        offset += (table.len() + 3) & !3;
+    }
+
+    for (_, _, table) in &tables {
+        out.extend(table);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    fn woff(tables: &[(&[u8; 4], Vec<u8>, usize)]) -> Vec<u8> {
+        let mut header = b"wOFF".to_vec();
+        header.extend(0x0001_0000u32.to_be_bytes());
+        header.extend(0u32.to_be_bytes());
+        header.extend((tables.len() as u16).to_be_bytes());
+        header.resize(WOFF_HEADER_SIZE, 0);
+
+        let mut offset = WOFF_HEADER_SIZE + tables.len() * WOFF_ENTRY_SIZE;
+        let mut body: Vec<u8> = Vec::new();
+        for (tag, stored, orig_length) in tables {
+            header.extend(*tag);
+            header.extend((offset as u32).to_be_bytes());
+            header.extend((stored.len() as u32).to_be_bytes());
+            header.extend((*orig_length as u32).to_be_bytes());
+            header.extend(0u32.to_be_bytes());
+
+            body.extend(stored);
+            offset += stored.len();
+        }
+
+        header.extend(body);
+        header
+    }
+
+    #[test]
+    fn unpacks_woff_tables() {
+        let glyf = vec![7u8; 64];
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&glyf).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let data = woff(&[(b"glyf", compressed, 64), (b"head", vec![1, 2, 3], 3)]);
+        let sfnt = decode_font(data).unwrap();
+
+        // Flavor, table count and search range of two tables
+        assert_eq!(&sfnt[0..4], &[0, 1, 0, 0]);
+        assert_eq!(&sfnt[4..8], &[0, 2, 0, 32]);
+
+        let directory = 12;
+        assert_eq!(&sfnt[directory..directory + 4], b"glyf");
+        assert_eq!(&sfnt[44..108], glyf.as_slice());
+        // The second table starts at the next 4 byte boundary
+        assert_eq!(&sfnt[directory + 16..directory + 20], b"head");
+        assert_eq!(&sfnt[108..111], &[1, 2, 3]);
+        assert_eq!(sfnt.len(), 112);
+    }
+
+    #[test]
+    fn rejects_oversized_tables() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&vec![0u8; 1024 * 1024]).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // The table inflates to more than its declared length
+        let data = woff(&[(b"glyf", compressed.clone(), 64 * 1024)]);
+        assert!(decode_font(data).is_none());
+
+        // The declared length is larger than any font we unpack
+        let data = woff(&[(b"glyf", compressed, u32::MAX as usize)]);
+        assert!(decode_font(data).is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(decode_font(b"wOF2 brotli".to_vec()).is_none());
+        assert!(decode_font(b"<html>".to_vec()).is_none());
+        assert_eq!(decode_font(b"OTTO...".to_vec()), Some(b"OTTO...".to_vec()));
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::mpsc;
 use url::Url;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
 use winit::window::WindowId;
 
 use gosub_render_backend::layout::{LayoutTree, Layouter};
@@ -127,9 +127,30 @@ impl<
             if let Err(e) = window.event(event_loop, &mut self.backend, event) {
                 eprintln!("Error handling window event: {e:?}");
             };
+
+            // A link was activated by the event
+            if let Some(url) = window.navigation.take() {
+                if let Err(e) = window.navigate::<P>(url, self.layouter.clone(), self.debug) {
+                    eprintln!("Error navigating: {e:?}");
+                }
+            }
         }
     }
 
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // The pages run their tasks whenever the browser is idle, and wake it up when their next timer expires
+        let wakeup = self
+            .windows
+            .values_mut()
+            .filter_map(|window| window.run_event_loops())
+            .min();
+
+        event_loop.set_control_flow(match wakeup {
+            Some(wakeup) => ControlFlow::WaitUntil(wakeup),
+            None => ControlFlow::Wait,
+        });
+    }
+
     fn suspended(&mut self, event_loop: &ActiveEventLoop) {
         for window in self.windows.values_mut() {
             window.suspended(event_loop, &mut self.backend);
@@ -1,7 +1,8 @@
-use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
-use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
 
+use gosub_html5::events::{EventData, Modifiers};
 use gosub_render_backend::layout::{LayoutTree, Layouter};
 use gosub_render_backend::{Point, RenderBackend, SizeU32, FP};
 use gosub_renderer::draw::SceneDrawer;
@@ -52,35 +53,122 @@ impl<
                     return Ok(());
                 };
 
+                // Animation frame callbacks run right before the frame is rendered, so their changes are in it
+                tab.event_loop.run_animation_frames(tab.now());
+
                 let redraw = tab.data.draw(backend, &mut self.renderer_data, size);
 
                 backend.render(&mut self.renderer_data, active_window_data)?;
 
-                if redraw {
+                if redraw || tab.event_loop.has_animation_frames() {
                     self.request_redraw();
                 }
             }
 
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let state = modifiers.state();
+
+                self.modifiers = Modifiers {
+                    ctrl: state.control_key(),
+                    shift: state.shift_key(),
+                    alt: state.alt_key(),
+                    meta: state.super_key(),
+                };
+            }
+
             WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x as FP, position.y as FP);
+
+                let data = self.mouse_data(0);
+
                 let Some(tab) = self.tabs.get_current_tab() else {
                     return Ok(());
                 };
 
+                tab.dispatch_at(position.x as FP, position.y as FP, "mousemove", data);
+
                 if tab.data.mouse_move(backend, position.x as FP, position.y as FP) {
                     self.window.request_redraw();
                 }
             }
 
-            WindowEvent::MouseWheel { delta, .. } => {
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => 0,
+                    MouseButton::Middle => 1,
+                    MouseButton::Right => 2,
+                    MouseButton::Back => 3,
+                    MouseButton::Forward => 4,
+                    MouseButton::Other(button) => button as i16,
+                };
+
+                // `buttons` holds the buttons that are pressed after the event, where the middle and right buttons
+                // have their bits swapped compared to `button`
+                let bit = match button {
+                    1 => 4,
+                    2 => 2,
+                    button => 1u16.checked_shl(button as u32).unwrap_or(0),
+                };
+
+                match state {
+                    ElementState::Pressed => self.buttons |= bit,
+                    ElementState::Released => self.buttons &= !bit,
+                }
+
+                let data = self.mouse_data(button);
+                let (x, y) = self.cursor;
+
                 let Some(tab) = self.tabs.get_current_tab() else {
                     return Ok(());
                 };
 
+                match state {
+                    ElementState::Pressed => {
+                        // Starting a text selection is the default action of pressing the left button
+                        if tab.mouse_down(x, y, data) && button == 0 {
+                            tab.data.mouse_down(backend);
+                        }
+                    }
+                    ElementState::Released => {
+                        if button == 0 {
+                            tab.data.mouse_up(backend);
+                        }
+
+                        // Only the primary button activates links
+                        if let Some(url) = tab.mouse_up(x, y, button, data) {
+                            self.navigation = Some(url);
+                        }
+                    }
+                }
+
+                // Listeners may have changed the document
+                self.window.request_redraw();
+            }
+
+            WindowEvent::MouseWheel { delta, .. } => {
                 let delta = match delta {
                     MouseScrollDelta::PixelDelta(delta) => (delta.x as f32, delta.y as f32),
                     MouseScrollDelta::LineDelta(x, y) => (x * 4.0, y * 12.0),
                 };
 
+                // The deltas of a wheel event are positive when the page scrolls down or right
+                let data = EventData::Wheel {
+                    client_x: self.cursor.0 as f64,
+                    client_y: self.cursor.1 as f64,
+                    delta_x: -delta.0 as f64,
+                    delta_y: -delta.1 as f64,
+                    modifiers: self.modifiers,
+                };
+                let (x, y) = self.cursor;
+
+                let Some(tab) = self.tabs.get_current_tab() else {
+                    return Ok(());
+                };
+
+                if !tab.dispatch_at(x, y, "wheel", data) {
+                    return Ok(());
+                }
+
                 let delta = Point::new(delta.0 as FP, delta.1 as FP);
 
                 tab.data.scroll(delta);
@@ -89,14 +177,35 @@ impl<
             }
 
             WindowEvent::KeyboardInput { event, .. } => {
-                if event.repeat || event.state == ElementState::Released {
-                    return Ok(());
-                }
+                let data = EventData::Keyboard {
+                    key: key_name(&event.logical_key),
+                    code: match event.physical_key {
+                        PhysicalKey::Code(code) => format!("{code:?}"),
+                        PhysicalKey::Unidentified(_) => "Unidentified".to_string(),
+                    },
+                    repeat: event.repeat,
+                    modifiers: self.modifiers,
+                };
 
                 let Some(tab) = self.tabs.get_current_tab() else {
                     return Ok(());
                 };
 
+                if event.state == ElementState::Released {
+                    tab.key_up(data);
+                    self.window.request_redraw();
+                    return Ok(());
+                }
+
+                let canceled = !tab.key_down(data, event.text.as_deref());
+
+                self.window.request_redraw();
+
+                // The debug shortcuts are default actions, which don't apply while typing in a form control
+                if canceled || event.repeat || tab.focused.is_some() {
+                    return Ok(());
+                }
+
                 if let PhysicalKey::Code(code) = event.physical_key {
                     match code {
                         KeyCode::KeyD => {
@@ -118,3 +227,14 @@ impl<
         Ok(())
     }
 }
+
+/// Returns the value of the `key` attribute of a keyboard event (<https://w3c.github.io/uievents-key/>)
+fn key_name(key: &Key) -> String {
+    match key {
+        Key::Character(text) => text.to_string(),
+        Key::Named(NamedKey::Space) => " ".to_string(),
+        // The names of winit follow the names of the spec
+        Key::Named(named) => format!("{named:?}"),
+        _ => "Unidentified".to_string(),
+    }
+}
@@ -1,14 +1,20 @@
 use slotmap::{DefaultKey, SlotMap};
 use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
 use url::Url;
 
+use gosub_html5::events::{Event, EventData, EventTargets};
 use gosub_render_backend::layout::{LayoutTree, Layouter};
-use gosub_render_backend::{NodeDesc, RenderBackend};
+use gosub_render_backend::{NodeDesc, RenderBackend, FP};
 use gosub_renderer::draw::SceneDrawer;
+use gosub_shared::byte_stream::Location;
+use gosub_shared::node::NodeId;
 use gosub_shared::traits::css3::CssSystem;
 use gosub_shared::traits::document::Document;
 use gosub_shared::traits::html5::Html5Parser;
+use gosub_shared::traits::node::{ElementDataType, Node, TextDataType};
 use gosub_shared::types::Result;
+use gosub_webexecutor::event_loop::EventLoop;
 
 pub struct Tabs<
     D: SceneDrawer<B, L, LT, Doc, C>,
@@ -95,6 +101,16 @@ pub struct Tab<
     pub title: String,
     pub url: Url,
     pub data: D,
+    /// Event loop that runs the tasks, timers and animation frame callbacks of the document
+    pub event_loop: EventLoop,
+    /// Time origin of the document, the times of the event loop are relative to it
+    time_origin: Instant,
+    /// Event listeners of the nodes of the document
+    pub event_targets: EventTargets,
+    /// Element that receives keyboard events
+    pub focused: Option<NodeId>,
+    /// Target of the last `mousedown`, a `click` is only fired when the button is released on the same element
+    pressed: Option<NodeId>,
     _marker: std::marker::PhantomData<(B, L, LT, Doc, C)>,
 }
 
@@ -112,6 +128,11 @@ impl<
             title,
             url,
             data,
+            event_loop: EventLoop::new(),
+            time_origin: Instant::now(),
+            event_targets: EventTargets::new(),
+            focused: None,
+            pressed: None,
             _marker: std::marker::PhantomData,
         }
     }
@@ -123,9 +144,229 @@ impl<
             title: url.as_str().to_string(),
             url,
             data,
+            event_loop: EventLoop::new(),
+            time_origin: Instant::now(),
+            event_targets: EventTargets::new(),
+            focused: None,
+            pressed: None,
             _marker: std::marker::PhantomData,
         })
     }
+
+    /// Milliseconds since the time origin of the document
+    pub fn now(&self) -> f64 {
+        self.time_origin.elapsed().as_secs_f64() * 1000.0
+    }
+
+    /// Runs the tasks of the event loop that are due. Returns true when any task ran, as it may have changed the page
+    pub fn run_tasks(&mut self) -> bool {
+        self.event_loop.tick(self.now()) > 0
+    }
+
+    /// Returns when the event loop has to run again, `None` when it is waiting for something other than time
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        if self.event_loop.has_pending_tasks() {
+            return Some(Instant::now());
+        }
+
+        let deadline = self.event_loop.next_timer_deadline()?;
+
+        Some(self.time_origin + Duration::from_secs_f64(deadline / 1000.0))
+    }
+
+    /// Dispatches a trusted event to a node of the document. Returns false when a listener canceled the event, so
+    /// its default action must not run
+    pub fn dispatch_event(&mut self, target: NodeId, event_type: &str, data: EventData) -> bool {
+        let Some(handle) = self.data.document() else {
+            return true;
+        };
+
+        let mut event = Event::trusted(event_type, data, self.now());
+
+        self.event_targets.dispatch(&handle, target, &mut event)
+    }
+
+    /// Dispatches a mouse or wheel event to the element at a position in the viewport
+    pub fn dispatch_at(&mut self, x: FP, y: FP, event_type: &str, data: EventData) -> bool {
+        match self.data.hit_test(x, y) {
+            Some(target) => self.dispatch_event(target, event_type, data),
+            None => true,
+        }
+    }
+
+    /// Fires `mousedown` and moves the focus to the element that was pressed. Returns false when the event was
+    /// canceled
+    pub fn mouse_down(&mut self, x: FP, y: FP, data: EventData) -> bool {
+        let target = self.data.hit_test(x, y);
+        self.pressed = target;
+
+        let Some(target) = target else {
+            return true;
+        };
+
+        if !self.dispatch_event(target, "mousedown", data) {
+            return false;
+        }
+
+        self.focused = Some(target).filter(|id| self.is_editable(*id));
+
+        true
+    }
+
+    /// Fires `mouseup`, and `click` (or `auxclick` for the other buttons) when the button is released on the element
+    /// it was pressed on. Returns the url of the link that was activated by a click of the primary button, if the
+    /// click wasn't canceled
+    pub fn mouse_up(&mut self, x: FP, y: FP, button: i16, data: EventData) -> Option<Url> {
+        // Releasing the button ends the press, also when it's released outside of the document
+        let pressed = self.pressed.take();
+        let target = self.data.hit_test(x, y)?;
+
+        self.dispatch_event(target, "mouseup", data.clone());
+
+        if pressed != Some(target) {
+            return None;
+        }
+
+        if button != 0 {
+            self.dispatch_event(target, "auxclick", data);
+            return None;
+        }
+
+        if !self.dispatch_event(target, "click", data) {
+            return None;
+        }
+
+        self.link_target(target)
+    }
+
+    /// Fires `keydown` at the focused element, or at the document element when nothing is focused. When it isn't
+    /// canceled and the key produces text in a focused form control, `beforeinput` is fired, and unless that is
+    /// canceled as well, the text is inserted and `input` is fired. Returns false when `keydown` was canceled
+    pub fn key_down(&mut self, data: EventData, text: Option<&str>) -> bool {
+        let Some(target) = self.focused.or_else(|| self.document_element()) else {
+            return true;
+        };
+
+        if !self.dispatch_event(target, "keydown", data) {
+            return false;
+        }
+
+        let text = text.filter(|text| !text.is_empty() && !text.chars().any(char::is_control));
+
+        let (Some(focused), Some(text)) = (self.focused, text) else {
+            return true;
+        };
+
+        let data = EventData::Input {
+            data: Some(text.to_string()),
+            input_type: "insertText".to_string(),
+        };
+
+        if self.dispatch_event(focused, "beforeinput", data.clone()) {
+            self.insert_text(focused, text);
+            self.dispatch_event(focused, "input", data);
+        }
+
+        true
+    }
+
+    pub fn key_up(&mut self, data: EventData) -> bool {
+        match self.focused.or_else(|| self.document_element()) {
+            Some(target) => self.dispatch_event(target, "keyup", data),
+            None => true,
+        }
+    }
+
+    fn document_element(&self) -> Option<NodeId> {
+        let handle = self.data.document()?;
+        let doc = handle.get();
+
+        doc.get_root()
+            .children()
+            .iter()
+            .copied()
+            .find(|id| doc.node_by_id(*id).is_some_and(|node| node.is_element_node()))
+    }
+
+    fn is_editable(&self, id: NodeId) -> bool {
+        let Some(handle) = self.data.document() else {
+            return false;
+        };
+
+        let doc = handle.get();
+
+        doc.node_by_id(id)
+            .and_then(|node| node.get_element_data())
+            .is_some_and(|data| matches!(data.name(), "input" | "textarea"))
+    }
+
+    /// Inserts text at the end of the value of a form control, as there is no caret to insert it at yet. The value of
+    /// an `<input>` is its `value` attribute, the value of a `<textarea>` is its text content
+    fn insert_text(&mut self, id: NodeId, text: &str) {
+        let Some(mut handle) = self.data.document() else {
+            return;
+        };
+
+        let Some(mut node) = handle.get().node_by_id(id).cloned() else {
+            return;
+        };
+
+        let Some(data) = node.get_element_data_mut() else {
+            return;
+        };
+
+        if data.name() == "input" {
+            let mut value = data.attribute("value").cloned().unwrap_or_default();
+            value.push_str(text);
+            data.add_attribute("value", &value);
+            handle.get_mut().update_node(node);
+            return;
+        }
+
+        let text_node = node.children().first().and_then(|child| {
+            handle
+                .get()
+                .node_by_id(*child)
+                .filter(|child| child.is_text_node())
+                .cloned()
+        });
+
+        match text_node {
+            Some(mut text_node) => {
+                if let Some(data) = text_node.get_text_data_mut() {
+                    data.value_mut().push_str(text);
+                }
+                handle.get_mut().update_node(text_node);
+            }
+            None => {
+                let text_node = Doc::new_text_node(handle.clone(), text, Location::default());
+                handle.get_mut().register_node_at(text_node, id, Some(0));
+            }
+        }
+    }
+
+    /// Returns the url of the link that contains a node, resolved against the url of the document
+    fn link_target(&self, id: NodeId) -> Option<Url> {
+        let handle = self.data.document()?;
+        let doc = handle.get();
+
+        let mut current = doc.node_by_id(id);
+
+        while let Some(node) = current {
+            if let Some(data) = node.get_element_data() {
+                if let Some(href) = data.attribute("href").filter(|_| matches!(data.name(), "a" | "area")) {
+                    return match doc.url() {
+                        Some(base) => base.join(href).ok(),
+                        None => Url::parse(href).ok(),
+                    };
+                }
+            }
+
+            current = node.parent_id().and_then(|parent| doc.node_by_id(parent));
+        }
+
+        None
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
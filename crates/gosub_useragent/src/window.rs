@@ -5,12 +5,14 @@ use std::cell::LazyCell;
 use std::ops::Deref;
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
+use std::time::Instant;
 use url::Url;
 use winit::dpi::LogicalSize;
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Icon, Window as WinitWindow, WindowId};
 
-use gosub_render_backend::geo::SizeU32;
+use gosub_html5::events::{EventData, Modifiers};
+use gosub_render_backend::geo::{SizeU32, FP};
 use gosub_render_backend::layout::{LayoutTree, Layouter};
 use gosub_render_backend::{NodeDesc, RenderBackend};
 use gosub_renderer::draw::SceneDrawer;
@@ -19,7 +21,7 @@ use gosub_shared::traits::document::Document;
 use gosub_shared::traits::html5::Html5Parser;
 use gosub_shared::types::Result;
 
-use crate::tabs::Tabs;
+use crate::tabs::{Tab, Tabs};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WindowState<'a, B: RenderBackend> {
@@ -61,6 +63,13 @@ pub struct Window<
     pub(crate) window: Arc<WinitWindow>,
     pub(crate) renderer_data: B::WindowData<'a>,
     pub(crate) tabs: Tabs<D, B, L, LT, Doc, C>,
+    /// Last position of the cursor in the viewport
+    pub(crate) cursor: (FP, FP),
+    /// Mouse buttons that are pressed, as the `buttons` attribute of mouse events
+    pub(crate) buttons: u16,
+    pub(crate) modifiers: Modifiers,
+    /// Url of a link that was activated, the current tab navigates to it once the event is handled
+    pub(crate) navigation: Option<Url>,
 }
 
 impl<
@@ -89,6 +98,10 @@ impl<
             window,
             renderer_data,
             tabs: Tabs::from_url::<P>(default_url, layouter, debug)?,
+            cursor: (0.0, 0.0),
+            buttons: 0,
+            modifiers: Modifiers::default(),
+            navigation: None,
         })
     }
 
@@ -134,6 +147,57 @@ impl<
         }
     }
 
+    /// Runs the event loops of all tabs, and requests a redraw when the current tab needs a new frame. Returns when
+    /// the event loops have to run again
+    pub fn run_event_loops(&mut self) -> Option<Instant> {
+        let active = self.tabs.active;
+        let mut redraw = false;
+        let mut wakeup: Option<Instant> = None;
+
+        for (key, tab) in self.tabs.tabs.iter_mut() {
+            // Background tabs keep running their tasks, but only the current tab is painted
+            let changed = tab.run_tasks();
+
+            if key == active.0 {
+                redraw = changed || tab.event_loop.has_animation_frames();
+            }
+
+            if let Some(next) = tab.next_wakeup() {
+                wakeup = Some(wakeup.map_or(next, |wakeup| wakeup.min(next)));
+            }
+        }
+
+        if redraw {
+            self.request_redraw();
+        }
+
+        wakeup
+    }
+
+    /// Loads a url in the current tab
+    pub fn navigate<P: Html5Parser<C, Document = Doc>>(&mut self, url: Url, layouter: L, debug: bool) -> Result<()> {
+        let tab = Tab::from_url::<P>(url, layouter, debug)?;
+
+        if let Some(current) = self.tabs.get_current_tab() {
+            *current = tab;
+        }
+
+        self.request_redraw();
+
+        Ok(())
+    }
+
+    /// The members of a mouse event at the current position of the cursor
+    pub(crate) fn mouse_data(&self, button: i16) -> EventData {
+        EventData::Mouse {
+            client_x: self.cursor.0 as f64,
+            client_y: self.cursor.1 as f64,
+            button,
+            buttons: self.buttons,
+            modifiers: self.modifiers,
+        }
+    }
+
     pub fn select_element(&mut self, id: LT::NodeId) {
         self.tabs.select_element(id);
     }
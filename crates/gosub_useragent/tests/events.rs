@@ -0,0 +1,258 @@
+//! Dispatches input through a `Tab` to the listeners of a parsed document, and checks that the default actions only
+//! run when the events aren't canceled
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+use gosub_css3::system::Css3System;
+use gosub_html5::document::builder::DocumentBuilderImpl;
+use gosub_html5::document::document_impl::DocumentImpl;
+use gosub_html5::events::{Event, EventData, ListenerOptions, Modifiers};
+use gosub_html5::parser::Html5Parser;
+use gosub_render_backend::geo::SizeU32;
+use gosub_render_backend::layout::{LayoutTree, Layouter};
+use gosub_render_backend::{NodeDesc, RenderBackend, FP};
+use gosub_renderer::draw::SceneDrawer;
+use gosub_rendering::render_tree::RenderTree;
+use gosub_shared::byte_stream::{ByteStream, Encoding};
+use gosub_shared::document::DocumentHandle;
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::document::{Document, DocumentBuilder};
+use gosub_shared::traits::node::{ElementDataType, Node, TextDataType};
+use gosub_shared::types::{Point, Result};
+use gosub_taffy::TaffyLayouter;
+use gosub_useragent::tabs::Tab;
+use gosub_vello::VelloBackend;
+use url::Url;
+
+type Doc = DocumentImpl<Css3System>;
+type TestTab =
+    Tab<TestDrawer, VelloBackend, TaffyLayouter, RenderTree<TaffyLayouter, Doc, Css3System>, Doc, Css3System>;
+
+const HTML: &str = r#"<a id="link" href="/next"><span id="label">next</span></a>
+    <input id="field" value="ab"><textarea id="area"></textarea>"#;
+
+/// A drawer without a renderer, the elements are stacked in horizontal bands of 10 pixels high
+struct TestDrawer {
+    handle: DocumentHandle<Doc, Css3System>,
+    bands: Vec<NodeId>,
+}
+
+impl<B: RenderBackend, L: Layouter, LT: LayoutTree<L>> SceneDrawer<B, L, LT, Doc, Css3System> for TestDrawer {
+    fn draw(&mut self, _backend: &mut B, _data: &mut B::WindowData<'_>, _size: SizeU32) -> bool {
+        false
+    }
+
+    fn mouse_move(&mut self, _backend: &mut B, _x: FP, _y: FP) -> bool {
+        false
+    }
+
+    fn mouse_down(&mut self, _backend: &mut B) -> bool {
+        false
+    }
+
+    fn mouse_up(&mut self, _backend: &mut B) -> bool {
+        false
+    }
+
+    fn hit_test(&mut self, _x: FP, y: FP) -> Option<NodeId> {
+        self.bands.get((y / 10.0) as usize).copied()
+    }
+
+    fn document(&self) -> Option<DocumentHandle<Doc, Css3System>> {
+        Some(self.handle.clone())
+    }
+
+    fn scroll(&mut self, _point: Point<FP>) {}
+
+    fn from_url<P>(_url: Url, _layouter: L, _debug: bool) -> Result<Self>
+    where
+        Self: Sized,
+        P: gosub_shared::traits::html5::Html5Parser<Css3System, Document = Doc>,
+    {
+        unimplemented!("the test parses the document itself")
+    }
+
+    fn clear_buffers(&mut self) {}
+
+    fn toggle_debug(&mut self) {}
+
+    fn select_element(&mut self, _id: LT::NodeId) {}
+
+    fn unselect_element(&mut self) {}
+
+    fn send_nodes(&mut self, _sender: Sender<NodeDesc>) {}
+
+    fn set_needs_redraw(&mut self) {}
+}
+
+fn tab() -> TestTab {
+    let url = Url::parse("https://example.com/index.html").unwrap();
+
+    let mut stream = ByteStream::new(Encoding::UTF8, None);
+    stream.read_from_str(HTML, Some(Encoding::UTF8));
+    stream.close();
+
+    let handle = DocumentBuilderImpl::new_document(Some(url.clone()));
+    Html5Parser::<Doc, Css3System>::parse_document(&mut stream, handle.clone(), None).unwrap();
+
+    let bands = ["label", "field", "area"].map(|id| node_id(&handle, id)).to_vec();
+
+    Tab::new("test".to_string(), url, TestDrawer { handle, bands })
+}
+
+fn node_id(handle: &DocumentHandle<Doc, Css3System>, id: &str) -> NodeId {
+    handle.get().node_by_named_id(id).unwrap().id()
+}
+
+fn document(tab: &TestTab) -> DocumentHandle<Doc, Css3System> {
+    tab.data.handle.clone()
+}
+
+/// Adds a listener that logs the type of the events and the id of their target, and cancels them when `cancel`
+/// returns true
+fn listen(tab: &TestTab, id: &str, event_type: &str, log: &Rc<RefCell<Vec<String>>>, cancel: fn(&Event) -> bool) {
+    let handle = document(tab);
+    let log = Rc::clone(log);
+
+    tab.event_targets.add_event_listener(
+        node_id(&handle, id),
+        event_type,
+        Rc::new(move |event: &mut Event| {
+            let target = event.target().and_then(|target| {
+                handle
+                    .get()
+                    .node_by_id(target)
+                    .and_then(|node| node.get_element_data().and_then(|data| data.attribute("id").cloned()))
+            });
+
+            log.borrow_mut()
+                .push(format!("{} {}", event.event_type, target.unwrap_or_default()));
+
+            if cancel(event) {
+                event.prevent_default();
+            }
+
+            Ok(())
+        }),
+        ListenerOptions::default(),
+    );
+}
+
+fn mouse(button: i16) -> EventData {
+    EventData::Mouse {
+        client_x: 0.0,
+        client_y: 0.0,
+        button,
+        buttons: 0,
+        modifiers: Modifiers::default(),
+    }
+}
+
+fn key(key: &str) -> EventData {
+    EventData::Keyboard {
+        key: key.to_string(),
+        code: String::new(),
+        repeat: false,
+        modifiers: Modifiers::default(),
+    }
+}
+
+fn click(tab: &mut TestTab, y: FP, button: i16) -> Option<Url> {
+    tab.mouse_down(0.0, y, mouse(button));
+    tab.mouse_up(0.0, y, button, mouse(button))
+}
+
+#[test]
+fn click_activates_links() {
+    let mut tab = tab();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    listen(&tab, "link", "click", &log, |_| false);
+    listen(&tab, "link", "auxclick", &log, |_| false);
+
+    // The click on the label bubbles to the link, which is activated
+    let next = Url::parse("https://example.com/next").unwrap();
+    assert_eq!(click(&mut tab, 5.0, 0), Some(next.clone()));
+    assert_eq!(*log.borrow(), vec!["click label"]);
+
+    // Other buttons don't activate links
+    assert_eq!(click(&mut tab, 5.0, 1), None);
+    assert_eq!(log.borrow().last().unwrap(), "auxclick label");
+
+    // No click when the button is released on another element, or outside of the document
+    log.borrow_mut().clear();
+    tab.mouse_down(0.0, 5.0, mouse(0));
+    assert_eq!(tab.mouse_up(0.0, 15.0, 0, mouse(0)), None);
+    tab.mouse_down(0.0, 5.0, mouse(0));
+    assert_eq!(tab.mouse_up(0.0, 100.0, 0, mouse(0)), None);
+    assert_eq!(tab.mouse_up(0.0, 5.0, 0, mouse(0)), None);
+    assert!(log.borrow().is_empty());
+
+    // Canceling the click cancels the navigation
+    listen(&tab, "label", "click", &log, |_| true);
+    assert_eq!(click(&mut tab, 5.0, 0), None);
+    assert_eq!(*log.borrow(), vec!["click label", "click label"]);
+}
+
+#[test]
+fn typing_in_form_controls() {
+    let mut tab = tab();
+    let handle = document(&tab);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    listen(
+        &tab,
+        "field",
+        "keydown",
+        &log,
+        |event| matches!(&event.data, EventData::Keyboard { key, .. } if key == "Tab"),
+    );
+    listen(
+        &tab,
+        "field",
+        "beforeinput",
+        &log,
+        |event| matches!(&event.data, EventData::Input { data: Some(data), .. } if data == "x"),
+    );
+    listen(&tab, "field", "input", &log, |_| false);
+
+    let value = |id: &str| {
+        let doc = handle.get();
+        let node = doc.node_by_id(node_id(&handle, id)).unwrap();
+        match node.get_element_data().unwrap().attribute("value") {
+            Some(value) => value.clone(),
+            None => node
+                .children()
+                .iter()
+                .filter_map(|child| doc.node_by_id(*child).and_then(|child| child.get_text_data()))
+                .map(|text| text.value().to_string())
+                .collect(),
+        }
+    };
+
+    // Nothing is focused, so there is nothing to type in
+    assert!(tab.key_down(key("c"), Some("c")));
+    assert_eq!(value("field"), "ab");
+
+    tab.mouse_down(0.0, 15.0, mouse(0));
+    assert_eq!(tab.focused, Some(node_id(&handle, "field")));
+
+    assert!(tab.key_down(key("c"), Some("c")));
+    assert_eq!(value("field"), "abc");
+    assert_eq!(*log.borrow(), vec!["keydown field", "beforeinput field", "input field"]);
+
+    // Canceling `beforeinput` or `keydown` prevents the text from being inserted
+    log.borrow_mut().clear();
+    assert!(tab.key_down(key("x"), Some("x")));
+    assert!(!tab.key_down(key("Tab"), Some("\t")));
+    assert_eq!(value("field"), "abc");
+    assert_eq!(
+        *log.borrow(),
+        vec!["keydown field", "beforeinput field", "keydown field"]
+    );
+
+    // The value of a textarea is its text content
+    tab.mouse_down(0.0, 25.0, mouse(0));
+    assert!(tab.key_down(key("h"), Some("h")));
+    assert!(tab.key_down(key("i"), Some("i")));
+    assert_eq!(value("area"), "hi");
+}
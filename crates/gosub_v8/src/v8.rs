@@ -13,13 +13,16 @@ pub use function::*;
 use gosub_shared::types::Result;
 use gosub_webexecutor::js::JSRuntime;
 pub use object::*;
+pub use promise::*;
 pub use value::*;
 
 mod array;
 mod compile;
 mod context;
 mod function;
+mod module;
 mod object;
+mod promise;
 mod value;
 
 // status of the V8 engine
@@ -150,6 +153,7 @@ impl<'a> JSRuntime for V8Engine<'a> {
     type Function = V8Function<'a>;
     type FunctionVariadic = V8FunctionVariadic<'a>;
     type Array = V8Array<'a>;
+    type Promise = V8Promise<'a>;
     type FunctionCallBack = V8FunctionCallBack<'a>;
     type FunctionCallBackVariadic = V8FunctionCallBackVariadic<'a>;
     type Args = V8Args<'a>;
@@ -168,7 +172,8 @@ impl<'a> JSRuntime for V8Engine<'a> {
 
 #[cfg(test)]
 mod tests {
-    use gosub_webexecutor::js::{JSContext, JSRuntime, JSValue};
+    use gosub_webexecutor::js::{JSContext, JSError, JSRuntime, JSValue};
+    use gosub_webexecutor::Error;
 
     use crate::v8::V8_INITIALIZED;
 
@@ -198,7 +203,6 @@ mod tests {
     }
 
     #[test]
-    #[should_panic = "called `Result::unwrap()` on an `Err` value: js: exception: SyntaxError: missing ) after argument list\nMessage: Uncaught SyntaxError: missing ) after argument list\nStacktrace: <missing information>\n\nCaused by:\n    exception: SyntaxError: missing ) after argument list\n    Message: Uncaught SyntaxError: missing ) after argument list\n    Stacktrace: <missing information>"]
     fn v8_run_invalid_syntax() {
         let mut engine = crate::v8::V8Engine::new();
 
@@ -211,8 +215,41 @@ mod tests {
         "#,
         );
 
-        assert!(result.is_err());
-        result.unwrap();
+        let err = result.err().expect("invalid syntax should fail");
+        let Some(Error::JS(JSError::Exception(exception))) = err.downcast_ref::<Error>() else {
+            panic!("expected an exception, got: {err}");
+        };
+
+        assert_eq!(exception.name, "SyntaxError");
+        assert_eq!(exception.message, "missing ) after argument list");
+        assert_eq!(exception.line, Some(2));
+        assert_eq!(
+            exception.source_line.as_deref(),
+            Some("        console.log(Hello World!);")
+        );
+    }
+
+    #[test]
+    fn v8_uncaught_exception() {
+        let mut engine = crate::v8::V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let err = context
+            .run("function fail() {\n  throw new RangeError('out of range');\n}\nfail();")
+            .err()
+            .expect("the exception should not be caught");
+        let Some(Error::JS(JSError::Exception(exception))) = err.downcast_ref::<Error>() else {
+            panic!("expected an exception, got: {err}");
+        };
+
+        assert_eq!(exception.name, "RangeError");
+        assert_eq!(exception.message, "out of range");
+        assert_eq!(exception.line, Some(2));
+        assert_eq!(exception.column, Some(3));
+        assert!(exception.stack.as_deref().unwrap().contains("at fail"));
+
+        let err = context.run("throw 'plain value'").err().unwrap();
+        assert_eq!(err.to_string(), "js: exception: plain value\n    at <anonymous>:1:1");
     }
 
     #[test]
@@ -1,4 +1,7 @@
 use std::ptr::NonNull;
+use std::rc::Rc;
+
+use url::Url;
 
 use v8::{
     CallbackScope, ContextScope, CreateParams, HandleScope, Isolate, Local, Object, OwnedIsolate, StackFrame,
@@ -6,10 +9,11 @@ use v8::{
 };
 
 use gosub_shared::types::Result;
-use gosub_webexecutor::js::{JSCompiled, JSContext, JSError, JSRuntime};
+use gosub_webexecutor::js::{JSCompiled, JSContext, JSError, JSException, JSRuntime, ModuleLoader};
 use gosub_webexecutor::Error;
 
-use crate::{FromContext, V8Compiled, V8Context, V8Engine, V8Object};
+use crate::v8::module::{evaluate_module, load_module, set_module_loader};
+use crate::{FromContext, V8Compiled, V8Context, V8Engine, V8Object, V8Promise};
 
 /// SAFETY: This is NOT thread safe, as the rest of the engine is not thread safe.
 /// This struct uses `NonNull` internally to store pointers to the V8Context "values" in one struct.
@@ -124,24 +128,48 @@ impl<'a> V8Ctx<'a> {
     }
 
     pub fn report_exception(try_catch: &mut TryCatch<HandleScope>) -> Error {
-        let mut err = String::new();
+        let mut exception = JSException::default();
+
+        if let Some(value) = try_catch.exception() {
+            exception.message = value.to_rust_string_lossy(try_catch);
 
-        if let Some(exception) = try_catch.exception() {
-            err = exception.to_rust_string_lossy(try_catch);
+            if let Ok(error) = Local::<Object>::try_from(value) {
+                if let Some(name) = Self::string_property(try_catch, error, "name") {
+                    exception.name = name;
+                }
+                if let Some(message) = Self::string_property(try_catch, error, "message") {
+                    exception.message = message;
+                }
+                exception.stack = Self::string_property(try_catch, error, "stack");
+            }
         }
 
         if let Some(m) = try_catch.message() {
-            err.push_str("\nMessage: ");
-            err.push_str(&m.get(try_catch).to_rust_string_lossy(try_catch));
-            if let Some(stacktrace) = m.get_stack_trace(try_catch) {
-                let st = Self::handle_stack_trace(try_catch, stacktrace);
-                err.push_str(&format!("\nStacktrace:\n{st}"))
-            } else {
-                err.push_str("\nStacktrace: <missing information>");
-            };
+            exception.resource = m
+                .get_script_resource_name(try_catch)
+                .filter(|name| name.is_string())
+                .map(|name| name.to_rust_string_lossy(try_catch));
+            exception.source_line = m
+                .get_source_line(try_catch)
+                .map(|line| line.to_rust_string_lossy(try_catch));
+            exception.line = m.get_line_number(try_catch);
+            exception.column = Some(m.get_start_column() + 1);
+
+            if exception.stack.is_none() {
+                if let Some(stacktrace) = m.get_stack_trace(try_catch) {
+                    exception.stack = Some(Self::handle_stack_trace(try_catch, stacktrace));
+                }
+            }
         }
 
-        Error::JS(JSError::Exception(err))
+        Error::JS(JSError::Exception(exception))
+    }
+
+    fn string_property(scope: &mut HandleScope, object: Local<Object>, name: &str) -> Option<String> {
+        let key = v8::String::new(scope, name)?;
+        let value = object.get(scope, key.into())?;
+
+        value.is_string().then(|| value.to_rust_string_lossy(scope))
     }
 
     pub fn handle_stack_trace(ctx: &mut HandleScope, stacktrace: Local<StackTrace>) -> String {
@@ -315,6 +343,23 @@ impl<'a> JSContext for V8Context<'a> {
         compiled.run()
     }
 
+    fn run_microtasks(&mut self) {
+        self.scope().perform_microtask_checkpoint();
+    }
+
+    fn set_module_loader(&mut self, loader: Rc<ModuleLoader>) {
+        set_module_loader(self.scope(), loader);
+    }
+
+    fn run_module(&mut self, url: Option<&Url>, source: &str) -> Result<<Self::RT as JSRuntime>::Promise> {
+        let try_catch = &mut TryCatch::new(self.scope());
+
+        let module = load_module(try_catch, url, source)?;
+        let promise = evaluate_module(try_catch, module)?;
+
+        Ok(V8Promise::from_promise(V8Context::clone(self), promise))
+    }
+
     fn new_global_object(&mut self, name: &str) -> Result<<Self::RT as JSRuntime>::Object> {
         let scope = self.scope();
         let obj = Object::new(scope);
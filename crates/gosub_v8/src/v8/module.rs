@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::num::NonZeroI32;
+use std::rc::Rc;
+
+use url::Url;
+use v8::{
+    CallbackScope, Data, FixedArray, Function, FunctionCallbackArguments, Global, HandleScope, Isolate, Local, Module,
+    ModuleRequest, Promise, PromiseResolver, ReturnValue, ScriptOrigin, TryCatch, Value,
+};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSError, ModuleLoader};
+use gosub_webexecutor::Error;
+
+use crate::V8Ctx;
+
+/// The modules of an isolate. It's stored in a slot of the isolate, as the callbacks of v8 can't carry any data.
+struct ModuleMap {
+    loader: Rc<ModuleLoader>,
+    /// Modules by URL, so a module that is imported more than once is only evaluated once
+    modules: HashMap<Url, Global<Module>>,
+    /// URLs of the modules by their identity hash, imports are resolved relative to the module that imports them
+    urls: HashMap<NonZeroI32, Url>,
+}
+
+fn module_error(message: impl Into<String>) -> anyhow::Error {
+    Error::JS(JSError::Module(message.into())).into()
+}
+
+fn module_map(isolate: &Isolate) -> Result<&ModuleMap> {
+    isolate
+        .get_slot::<ModuleMap>()
+        .ok_or_else(|| module_error("modules can't be used without a module loader"))
+}
+
+/// Sets the loader of the isolate and enables dynamic `import()`
+pub(crate) fn set_module_loader(scope: &mut HandleScope, loader: Rc<ModuleLoader>) {
+    match scope.get_slot_mut::<ModuleMap>() {
+        Some(map) => map.loader = loader,
+        None => {
+            scope.set_slot(ModuleMap {
+                loader,
+                modules: HashMap::new(),
+                urls: HashMap::new(),
+            });
+        }
+    }
+
+    scope.set_host_import_module_dynamically_callback(import_dynamically);
+}
+
+/// Compiles the module at `url` (`None` for an inline module script) and, recursively, all modules it imports that
+/// haven't been loaded yet. When one of them fails, the modules of the graph are removed again, so a later import
+/// loads the whole graph again instead of finding modules whose imports are missing.
+pub(crate) fn load_module<'s>(
+    scope: &mut TryCatch<HandleScope<'s>>,
+    url: Option<&Url>,
+    source: &str,
+) -> Result<Local<'s, Module>> {
+    let mut loaded = Vec::new();
+    let result = match url {
+        Some(url) => load_graph(scope, url, source, false, &mut loaded),
+        None => {
+            let base = module_map(scope)?.loader.base().clone();
+            load_graph(scope, &base, source, true, &mut loaded)
+        }
+    };
+
+    if result.is_err() {
+        if let Some(map) = scope.get_slot_mut::<ModuleMap>() {
+            for (url, hash) in loaded {
+                if let Some(url) = url {
+                    map.modules.remove(&url);
+                }
+                map.urls.remove(&hash);
+            }
+        }
+    }
+
+    result
+}
+
+/// Loads a module graph, `loaded` gets the URLs that were added to the module map (`None` when the URL was already
+/// known) and the identity hashes of the modules
+fn load_graph<'s>(
+    scope: &mut TryCatch<HandleScope<'s>>,
+    url: &Url,
+    source: &str,
+    inline: bool,
+    loaded: &mut Vec<(Option<Url>, NonZeroI32)>,
+) -> Result<Local<'s, Module>> {
+    let loader = module_map(scope)?.loader.clone();
+
+    let (Some(name), Some(code)) = (v8::String::new(scope, url.as_str()), v8::String::new(scope, source)) else {
+        return Err(module_error(format!("failed to create the source of {url}")));
+    };
+    let origin = ScriptOrigin::new(scope, name.into(), 0, 0, false, 0, None, false, false, true, None);
+    let mut source = v8::script_compiler::Source::new(code, Some(&origin));
+
+    let Some(module) = v8::script_compiler::compile_module(scope, &mut source) else {
+        return Err(V8Ctx::report_exception(scope).into());
+    };
+
+    let global = Global::new(scope, module);
+    let hash = module.get_identity_hash();
+    if let Some(map) = scope.get_slot_mut::<ModuleMap>() {
+        // an inline module script has the URL of the document, but it can't be imported, so it's only known by its
+        // identity hash, which resolves its imports against the document
+        let added = !inline && !map.modules.contains_key(url);
+        if added {
+            map.modules.insert(url.clone(), global);
+        }
+        map.urls.insert(hash, url.clone());
+        loaded.push((added.then(|| url.clone()), hash));
+    }
+
+    let requests = module.get_module_requests();
+    for i in 0..requests.length() {
+        let Some(request) = requests
+            .get(scope, i)
+            .and_then(|request| Local::<ModuleRequest>::try_from(request).ok())
+        else {
+            continue;
+        };
+
+        let specifier = request.get_specifier().to_rust_string_lossy(scope);
+        let import = loader.resolve(&specifier, Some(url))?;
+        if module_map(scope)?.modules.contains_key(&import) {
+            continue;
+        }
+
+        let source = loader.fetch(&import)?;
+        load_graph(scope, &import, &source, false, loaded)?;
+    }
+
+    Ok(module)
+}
+
+/// Links and evaluates a loaded module, the promise settles when a top-level await is done
+pub(crate) fn evaluate_module<'s>(
+    scope: &mut TryCatch<HandleScope<'s>>,
+    module: Local<'s, Module>,
+) -> Result<Local<'s, Promise>> {
+    if module.instantiate_module(scope, resolve_module).is_none() {
+        return Err(V8Ctx::report_exception(scope).into());
+    }
+
+    let Some(result) = module.evaluate(scope) else {
+        return Err(V8Ctx::report_exception(scope).into());
+    };
+
+    Local::<Promise>::try_from(result).map_err(|_| module_error("evaluating a module didn't return a promise"))
+}
+
+/// Called by v8 to link the imports of a module. All modules have been loaded by then.
+fn resolve_module<'s>(
+    context: Local<'s, v8::Context>,
+    specifier: Local<'s, v8::String>,
+    _import_attributes: Local<'s, FixedArray>,
+    referrer: Local<'s, Module>,
+) -> Option<Local<'s, Module>> {
+    let scope = &mut unsafe { CallbackScope::new(context) };
+    let specifier = specifier.to_rust_string_lossy(scope);
+
+    let module = module_map(scope).and_then(|map| {
+        let referrer = map.urls.get(&referrer.get_identity_hash());
+        let url = map.loader.resolve(&specifier, referrer)?;
+
+        map.modules
+            .get(&url)
+            .cloned()
+            .ok_or_else(|| module_error(format!("module {url} has not been loaded")))
+    });
+
+    match module {
+        Ok(module) => Some(Local::new(scope, module)),
+        Err(err) => {
+            throw_type_error(scope, &err.to_string());
+            None
+        }
+    }
+}
+
+/// Called by v8 for `import()`. The module graph is loaded right away, the promise settles once it's evaluated.
+fn import_dynamically<'s>(
+    scope: &mut HandleScope<'s>,
+    _host_defined_options: Local<'s, Data>,
+    resource_name: Local<'s, Value>,
+    specifier: Local<'s, v8::String>,
+    _import_attributes: Local<'s, FixedArray>,
+) -> Option<Local<'s, Promise>> {
+    let resolver = PromiseResolver::new(scope)?;
+    let promise = resolver.get_promise(scope);
+
+    // classic scripts have no URL, their imports are resolved against the document
+    let referrer = Url::parse(&resource_name.to_rust_string_lossy(scope)).ok();
+    let specifier = specifier.to_rust_string_lossy(scope);
+
+    let scope = &mut TryCatch::new(scope);
+    match import(scope, &specifier, referrer.as_ref()) {
+        Ok(namespace) => {
+            resolver.resolve(scope, namespace.into());
+        }
+        Err(err) => {
+            let exception = match scope.exception() {
+                Some(exception) => exception,
+                None => type_error(scope, &err.to_string())?,
+            };
+            scope.reset();
+            resolver.reject(scope, exception);
+        }
+    }
+
+    Some(promise)
+}
+
+/// Loads and evaluates the module for `import()`, returns a promise for its namespace object
+fn import<'s>(
+    scope: &mut TryCatch<HandleScope<'s>>,
+    specifier: &str,
+    referrer: Option<&Url>,
+) -> Result<Local<'s, Promise>> {
+    let loader = module_map(scope)?.loader.clone();
+    let url = loader.resolve(specifier, referrer)?;
+
+    let known = module_map(scope)?.modules.get(&url).cloned();
+    let module = match known {
+        Some(module) => Local::new(scope, module),
+        None => {
+            let source = loader.fetch(&url)?;
+            load_module(scope, Some(&url), &source)?
+        }
+    };
+
+    let evaluated = evaluate_module(scope, module)?;
+    let namespace = module.get_module_namespace();
+
+    Function::builder(|_: &mut HandleScope, args: FunctionCallbackArguments, mut rv: ReturnValue| rv.set(args.data()))
+        .data(namespace)
+        .build(scope)
+        .and_then(|on_evaluated| evaluated.then(scope, on_evaluated))
+        .ok_or_else(|| module_error(format!("failed to wait for the evaluation of {url}")))
+}
+
+fn type_error<'s>(scope: &mut HandleScope<'s>, message: &str) -> Option<Local<'s, Value>> {
+    let message = v8::String::new(scope, message)?;
+    Some(v8::Exception::type_error(scope, message))
+}
+
+fn throw_type_error(scope: &mut HandleScope, message: &str) {
+    if let Some(exception) = type_error(scope, message) {
+        scope.throw_exception(exception);
+    }
+}
@@ -100,6 +100,31 @@ impl<'a> JSObject for V8Object<'a> {
             .map(|value| V8Value::from_value(self.ctx.clone(), value))
     }
 
+    fn keys(&self) -> Result<Vec<String>> {
+        let scope = self.ctx.scope();
+
+        let names = self
+            .value
+            .get_own_property_names(scope, Default::default())
+            .ok_or_else(|| {
+                Error::JS(JSError::Generic(
+                    "failed to get the property names of an object".to_owned(),
+                ))
+            })?;
+
+        let mut keys = Vec::with_capacity(names.length() as usize);
+
+        for idx in 0..names.length() {
+            let Some(name) = names.get_index(scope, idx).and_then(|name| name.to_string(scope)) else {
+                continue;
+            };
+
+            keys.push(name.to_rust_string_lossy(scope));
+        }
+
+        Ok(keys)
+    }
+
     fn call_method(
         &self,
         name: &str,
@@ -310,7 +335,7 @@ mod tests {
     use serde_json::to_string;
 
     use gosub_webexecutor::js::{
-        IntoJSValue, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic, JSFunctionVariadic,
+        IntoJSValue, JSContext, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic, JSFunctionVariadic,
         VariadicArgsInternal,
     };
 
@@ -332,6 +357,16 @@ mod tests {
         assert_eq!(value.as_string().unwrap(), "value");
     }
 
+    #[test]
+    fn test_object_keys() {
+        let mut engine = V8Engine::new();
+        let mut ctx = engine.new_context().unwrap();
+
+        let obj = ctx.run("({ a: 1, b: 'two', 3: null })").unwrap().as_object().unwrap();
+
+        assert_eq!(obj.keys().unwrap(), ["3", "a", "b"]);
+    }
+
     #[test]
     fn test_object_accessor() {
         let mut engine = V8Engine::new();
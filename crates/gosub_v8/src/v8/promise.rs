@@ -0,0 +1,176 @@
+use v8::{Local, Promise, PromiseResolver, TryCatch};
+
+use gosub_shared::types::Result;
+use gosub_webexecutor::js::{JSError, JSPromise, JSRuntime, PromiseState};
+use gosub_webexecutor::Error;
+
+use crate::{V8Context, V8Ctx, V8Engine, V8Function, V8Value};
+
+pub struct V8Promise<'a> {
+    pub ctx: V8Context<'a>,
+    pub value: Local<'a, Promise>,
+    /// Only set for promises that are created from rust
+    resolver: Option<Local<'a, PromiseResolver>>,
+}
+
+impl<'a> V8Promise<'a> {
+    pub fn from_promise(ctx: V8Context<'a>, value: Local<'a, Promise>) -> Self {
+        Self {
+            ctx,
+            value,
+            resolver: None,
+        }
+    }
+
+    fn resolver(&self) -> Result<Local<'a, PromiseResolver>> {
+        self.resolver.ok_or_else(|| {
+            Error::JS(JSError::Generic(
+                "only promises created from rust can be settled".to_owned(),
+            ))
+            .into()
+        })
+    }
+}
+
+impl<'a> JSPromise for V8Promise<'a> {
+    type RT = V8Engine<'a>;
+
+    fn new(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self> {
+        let scope = ctx.scope();
+
+        let Some(resolver) = PromiseResolver::new(scope) else {
+            return Err(Error::JS(JSError::Generic("failed to create a promise".to_owned())).into());
+        };
+
+        let value = resolver.get_promise(scope);
+
+        Ok(Self {
+            ctx,
+            value,
+            resolver: Some(resolver),
+        })
+    }
+
+    fn resolve(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()> {
+        match self.resolver()?.resolve(self.ctx.scope(), value.value) {
+            Some(_) => Ok(()),
+            None => Err(Error::JS(JSError::Execution("failed to resolve a promise".to_owned())).into()),
+        }
+    }
+
+    fn reject(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()> {
+        match self.resolver()?.reject(self.ctx.scope(), value.value) {
+            Some(_) => Ok(()),
+            None => Err(Error::JS(JSError::Execution("failed to reject a promise".to_owned())).into()),
+        }
+    }
+
+    fn state(&self) -> PromiseState<<Self::RT as JSRuntime>::Value> {
+        let result =
+            |promise: &Self| V8Value::from_value(promise.ctx.clone(), promise.value.result(promise.ctx.scope()));
+
+        match self.value.state() {
+            v8::PromiseState::Pending => PromiseState::Pending,
+            v8::PromiseState::Fulfilled => PromiseState::Fulfilled(result(self)),
+            v8::PromiseState::Rejected => PromiseState::Rejected(result(self)),
+        }
+    }
+
+    fn then(&self, on_fulfilled: &V8Function<'a>, on_rejected: &V8Function<'a>) -> Result<Self> {
+        let try_catch = &mut TryCatch::new(self.ctx.scope());
+
+        let Some(value) = self.value.then2(try_catch, on_fulfilled.function, on_rejected.function) else {
+            return Err(V8Ctx::report_exception(try_catch).into());
+        };
+
+        Ok(Self::from_promise(self.ctx.clone(), value))
+    }
+
+    fn as_value(&self) -> <Self::RT as JSRuntime>::Value {
+        V8Value::from_value(self.ctx.clone(), Local::from(self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use gosub_webexecutor::js::{
+        Args, IntoJSValue, JSContext, JSFunction, JSFunctionCallBack, JSObject, JSRuntime, JSValue, PromiseState,
+    };
+
+    use super::*;
+
+    #[test]
+    fn resolve_from_rust() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let promise = V8Promise::new(context.clone()).unwrap();
+        let obj = context.new_global_object("test").unwrap();
+        obj.set_property("promise", &promise.as_value()).unwrap();
+
+        context
+            .run("globalThis.result = 'pending'; test.promise.then((value) => { globalThis.result = value })")
+            .unwrap();
+        assert!(matches!(promise.state(), PromiseState::Pending));
+
+        promise.resolve(&"done".to_js_value(context.clone()).unwrap()).unwrap();
+        context.run_microtasks();
+
+        let PromiseState::Fulfilled(value) = promise.state() else {
+            panic!("promise should be fulfilled");
+        };
+        assert_eq!(value.as_string().unwrap(), "done");
+        assert_eq!(context.run("result").unwrap().as_string().unwrap(), "done");
+    }
+
+    #[test]
+    fn await_from_rust() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context
+            .run("(async () => { await null; throw new TypeError('failed') })()")
+            .unwrap();
+        assert!(value.is_promise());
+
+        let rejected = Rc::new(RefCell::new(String::new()));
+
+        let on_fulfilled = V8Function::new(context.clone(), |cb| {
+            let ret = V8Value::new_undefined(cb.context()).unwrap();
+            cb.ret(ret);
+        })
+        .unwrap();
+
+        let on_rejected = {
+            let rejected = Rc::clone(&rejected);
+            V8Function::new(context.clone(), move |cb| {
+                let ctx = cb.context();
+                let reason = cb.args().get(0, ctx.clone()).unwrap();
+                *rejected.borrow_mut() = reason.as_string().unwrap();
+                cb.ret(V8Value::new_undefined(ctx).unwrap());
+            })
+            .unwrap()
+        };
+
+        value.as_promise().unwrap().then(&on_fulfilled, &on_rejected).unwrap();
+        context.run_microtasks();
+
+        assert_eq!(*rejected.borrow(), "TypeError: failed");
+    }
+
+    #[test]
+    fn settle_promise_from_javascript() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let promise = context.run("Promise.resolve(42)").unwrap().as_promise().unwrap();
+
+        assert!(matches!(promise.state(), PromiseState::Fulfilled(value) if value.as_number().unwrap() == 42.0));
+        assert!(promise
+            .resolve(&V8Value::new_undefined(context.clone()).unwrap())
+            .is_err());
+    }
+}
@@ -1,9 +1,11 @@
-use v8::{Array, Local, Value};
+use v8::{Array, ArrayBuffer, ArrayBufferView, Local, Value};
 
 use gosub_shared::types::Result;
 
-use crate::{FromContext, IntoContext, V8Array, V8Context, V8Engine, V8Object};
-use gosub_webexecutor::js::{ArrayConversion, AsArray, IntoJSValue, JSArray, JSError, JSRuntime, JSType, JSValue, Ref};
+use crate::{FromContext, IntoContext, V8Array, V8Context, V8Engine, V8Object, V8Promise};
+use gosub_webexecutor::js::{
+    ArrayConversion, AsArray, IntoJSValue, JSArray, JSError, JSRuntime, JSType, JSValue, Ref, TypedArrayKind,
+};
 use gosub_webexecutor::Error;
 
 pub struct V8Value<'a> {
@@ -43,6 +45,41 @@ impl<'a> From<V8Object<'a>> for V8Value<'a> {
     }
 }
 
+impl<'a> From<V8Promise<'a>> for V8Value<'a> {
+    fn from(promise: V8Promise<'a>) -> Self {
+        Self {
+            context: promise.ctx,
+            value: promise.value.into(),
+        }
+    }
+}
+
+fn conversion_error(message: &str) -> anyhow::Error {
+    Error::JS(JSError::Conversion(message.to_owned())).into()
+}
+
+/// Creates a typed array of the given kind over `length` elements of the buffer
+fn new_typed_array<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    kind: TypedArrayKind,
+    buffer: Local<'a, ArrayBuffer>,
+    length: usize,
+) -> Option<Local<'a, Value>> {
+    Some(match kind {
+        TypedArrayKind::Int8 => v8::Int8Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Uint8 => v8::Uint8Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Uint8Clamped => v8::Uint8ClampedArray::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Int16 => v8::Int16Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Uint16 => v8::Uint16Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Int32 => v8::Int32Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Uint32 => v8::Uint32Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Float32 => v8::Float32Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::Float64 => v8::Float64Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::BigInt64 => v8::BigInt64Array::new(scope, buffer, 0, length)?.into(),
+        TypedArrayKind::BigUint64 => v8::BigUint64Array::new(scope, buffer, 0, length)?.into(),
+    })
+}
+
 impl<'a> AsArray for V8Value<'a> {
     type Runtime = V8Engine<'a>;
 
@@ -85,6 +122,109 @@ impl<'a> JSValue for V8Value<'a> {
     impl_is!(is_null);
     impl_is!(is_undefined);
     impl_is!(is_function);
+    impl_is!(is_promise);
+    impl_is!(is_symbol);
+    impl_is!(is_date);
+    impl_is!(is_array_buffer);
+    impl_is!(is_typed_array);
+
+    fn is_bigint(&self) -> bool {
+        self.value.is_big_int()
+    }
+
+    fn as_promise(&self) -> Result<<Self::RT as JSRuntime>::Promise> {
+        let promise: Local<v8::Promise> = self
+            .value
+            .try_into()
+            .map_err(|_| conversion_error("could not convert to promise"))?;
+
+        Ok(V8Promise::from_promise(V8Context::clone(&self.context), promise))
+    }
+
+    fn as_bigint(&self) -> Result<i64> {
+        let bigint: Local<v8::BigInt> = self
+            .value
+            .try_into()
+            .map_err(|_| conversion_error("could not convert to bigint"))?;
+
+        match bigint.i64_value() {
+            (value, true) => Ok(value),
+            (_, false) => Err(conversion_error("bigint does not fit in an i64")),
+        }
+    }
+
+    fn as_symbol_description(&self) -> Result<Option<String>> {
+        let symbol: Local<v8::Symbol> = self
+            .value
+            .try_into()
+            .map_err(|_| conversion_error("could not convert to symbol"))?;
+
+        let scope = self.context.scope();
+        let description = symbol.description(scope);
+
+        if description.is_undefined() {
+            Ok(None)
+        } else {
+            Ok(Some(description.to_rust_string_lossy(scope)))
+        }
+    }
+
+    fn as_date(&self) -> Result<f64> {
+        let date: Local<v8::Date> = self
+            .value
+            .try_into()
+            .map_err(|_| conversion_error("could not convert to date"))?;
+
+        Ok(date.value_of())
+    }
+
+    fn as_bytes(&self) -> Result<Vec<u8>> {
+        let view: Local<ArrayBufferView> = if let Ok(buffer) = Local::<ArrayBuffer>::try_from(self.value) {
+            let length = buffer.byte_length();
+            v8::Uint8Array::new(self.context.scope(), buffer, 0, length)
+                .ok_or_else(|| conversion_error("could not read the array buffer"))?
+                .into()
+        } else {
+            self.value
+                .try_into()
+                .map_err(|_| conversion_error("could not convert to bytes"))?
+        };
+
+        let mut bytes = vec![0; view.byte_length()];
+        view.copy_contents(&mut bytes);
+
+        Ok(bytes)
+    }
+
+    fn typed_array_kind(&self) -> Option<TypedArrayKind> {
+        let value = self.value;
+
+        if value.is_int8_array() {
+            Some(TypedArrayKind::Int8)
+        } else if value.is_uint8_array() {
+            Some(TypedArrayKind::Uint8)
+        } else if value.is_uint8_clamped_array() {
+            Some(TypedArrayKind::Uint8Clamped)
+        } else if value.is_int16_array() {
+            Some(TypedArrayKind::Int16)
+        } else if value.is_uint16_array() {
+            Some(TypedArrayKind::Uint16)
+        } else if value.is_int32_array() {
+            Some(TypedArrayKind::Int32)
+        } else if value.is_uint32_array() {
+            Some(TypedArrayKind::Uint32)
+        } else if value.is_float32_array() {
+            Some(TypedArrayKind::Float32)
+        } else if value.is_float64_array() {
+            Some(TypedArrayKind::Float64)
+        } else if value.is_big_int64_array() {
+            Some(TypedArrayKind::BigInt64)
+        } else if value.is_big_uint64_array() {
+            Some(TypedArrayKind::BigUint64)
+        } else {
+            None
+        }
+    }
 
     fn as_array(&self) -> Result<<Self::RT as JSRuntime>::Array> {
         let array: Local<Array> = self.value.try_into()?;
@@ -110,8 +250,20 @@ impl<'a> JSValue for V8Value<'a> {
             JSType::Null
         } else if self.is_undefined() {
             JSType::Undefined
+        } else if self.is_bigint() {
+            JSType::BigInt
+        } else if self.is_symbol() {
+            JSType::Symbol
         } else if self.is_function() {
             JSType::Function
+        } else if self.is_promise() {
+            JSType::Promise
+        } else if self.is_date() {
+            JSType::Date
+        } else if self.is_array_buffer() {
+            JSType::ArrayBuffer
+        } else if self.is_typed_array() {
+            JSType::TypedArray
         } else if self.is_object() {
             JSType::Object
         } else {
@@ -184,11 +336,77 @@ impl<'a> JSValue for V8Value<'a> {
             value: Local::from(undefined),
         })
     }
+
+    fn new_bigint(ctx: <Self::RT as JSRuntime>::Context, value: i64) -> Result<Self> {
+        let value = v8::BigInt::new_from_i64(ctx.scope(), value);
+
+        Ok(Self {
+            context: V8Context::clone(&ctx),
+            value: Local::from(value),
+        })
+    }
+
+    fn new_symbol(ctx: <Self::RT as JSRuntime>::Context, description: Option<&str>) -> Result<Self> {
+        let scope = ctx.scope();
+
+        let description = match description {
+            Some(description) => Some(
+                v8::String::new(scope, description).ok_or_else(|| conversion_error("could not convert to string"))?,
+            ),
+            None => None,
+        };
+
+        let value = v8::Symbol::new(scope, description);
+
+        Ok(Self {
+            context: V8Context::clone(&ctx),
+            value: Local::from(value),
+        })
+    }
+
+    fn new_date(ctx: <Self::RT as JSRuntime>::Context, time: f64) -> Result<Self> {
+        let Some(value) = v8::Date::new(ctx.scope(), time) else {
+            return Err(conversion_error("could not create a date"));
+        };
+
+        Ok(Self {
+            context: V8Context::clone(&ctx),
+            value: Local::from(value),
+        })
+    }
+
+    fn new_array_buffer(ctx: <Self::RT as JSRuntime>::Context, data: &[u8]) -> Result<Self> {
+        let store = ArrayBuffer::new_backing_store_from_vec(data.to_vec()).make_shared();
+        let value = ArrayBuffer::with_backing_store(ctx.scope(), &store);
+
+        Ok(Self {
+            context: V8Context::clone(&ctx),
+            value: Local::from(value),
+        })
+    }
+
+    fn new_typed_array(ctx: <Self::RT as JSRuntime>::Context, kind: TypedArrayKind, data: &[u8]) -> Result<Self> {
+        if data.len() % kind.element_size() != 0 {
+            return Err(conversion_error("the data is not a whole number of elements"));
+        }
+
+        let buffer = Self::new_array_buffer(ctx.clone(), data)?;
+        let buffer: Local<ArrayBuffer> = buffer
+            .value
+            .try_into()
+            .map_err(|_| conversion_error("could not create an array buffer"))?;
+
+        let Some(value) = new_typed_array(ctx.scope(), kind, buffer, data.len() / kind.element_size()) else {
+            return Err(conversion_error("could not create a typed array"));
+        };
+
+        Ok(Self { context: ctx, value })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use gosub_webexecutor::js::{IntoRustValue, JSContext};
+    use gosub_webexecutor::js::{IntoRustValue, JSContext, JSObject};
 
     use super::*;
 
@@ -534,4 +752,77 @@ mod tests {
 
         let _: () = value.to_rust_value().unwrap();
     }
+
+    #[test]
+    fn bigint_and_symbol() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("2n ** 40n").unwrap();
+        assert!(value.is_bigint());
+        assert_eq!(value.type_of(), JSType::BigInt);
+        assert_eq!(value.as_bigint().unwrap(), 1 << 40);
+        assert!(context.run("2n ** 64n").unwrap().as_bigint().is_err());
+
+        let value = context.run("Symbol('token')").unwrap();
+        assert!(value.is_symbol());
+        assert_eq!(value.type_of(), JSType::Symbol);
+        assert_eq!(value.as_symbol_description().unwrap().as_deref(), Some("token"));
+
+        let value = V8Value::new_symbol(context.clone(), None).unwrap();
+        assert_eq!(value.as_symbol_description().unwrap(), None);
+        assert_eq!(V8Value::new_bigint(context, -5).unwrap().as_bigint().unwrap(), -5);
+    }
+
+    #[test]
+    fn date() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("new Date(Date.UTC(2024, 0, 1))").unwrap();
+        assert!(value.is_date());
+        assert_eq!(value.type_of(), JSType::Date);
+        assert_eq!(value.as_date().unwrap(), 1_704_067_200_000.0);
+
+        let value = V8Value::new_date(context.clone(), 0.0).unwrap();
+        context
+            .new_global_object("test")
+            .unwrap()
+            .set_property("date", &value)
+            .unwrap();
+        assert_eq!(
+            context.run("test.date.toISOString()").unwrap().as_string().unwrap(),
+            "1970-01-01T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn buffers() {
+        let mut engine = V8Engine::new();
+        let mut context = engine.new_context().unwrap();
+
+        let value = context.run("new Uint16Array([1, 258]).buffer").unwrap();
+        assert!(value.is_array_buffer());
+        assert_eq!(value.type_of(), JSType::ArrayBuffer);
+        assert_eq!(value.as_bytes().unwrap(), vec![1, 0, 2, 1]);
+
+        let value = context.run("new Uint8Array([1, 2, 3, 4]).subarray(1, 3)").unwrap();
+        assert!(value.is_typed_array());
+        assert_eq!(value.type_of(), JSType::TypedArray);
+        assert_eq!(value.typed_array_kind(), Some(TypedArrayKind::Uint8));
+        assert_eq!(value.as_bytes().unwrap(), vec![2, 3]);
+
+        let value = V8Value::new_typed_array(context.clone(), TypedArrayKind::Int16, &[255, 255, 2, 0]).unwrap();
+        context
+            .new_global_object("test")
+            .unwrap()
+            .set_property("array", &value)
+            .unwrap();
+        assert_eq!(context.run("test.array.join()").unwrap().as_string().unwrap(), "-1,2");
+        assert!(V8Value::new_typed_array(context.clone(), TypedArrayKind::Int32, &[1, 2]).is_err());
+
+        let value = V8Value::new_array_buffer(context, &[7, 8]).unwrap();
+        assert_eq!(value.as_bytes().unwrap(), vec![7, 8]);
+        assert_eq!(value.typed_array_kind(), None);
+    }
 }
@@ -1,5 +1,10 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
 use smallvec::SmallVec;
-use vello::kurbo::{Arc, BezPath, Cap, Join, RoundedRectRadii, Stroke};
+use vello::kurbo::{
+    Affine, Arc, BezPath, Cap, Circle, Join, PathEl, Point, Rect as VelloRect, RoundedRectRadii, Shape, Stroke,
+};
+use vello::peniko::{Brush as VelloBrush, Color as VelloColor, Fill, Mix};
 use vello::Scene;
 
 use crate::{Brush, Rect, Transform, VelloBackend};
@@ -8,6 +13,9 @@ use gosub_render_backend::{
     Border as TBorder, BorderRadius as TBorderRadius, BorderSide as TBorderSide, BorderStyle, Radius, RenderBorder,
 };
 
+/// Tolerance used when converting arcs to curves and flattening paths
+const TOLERANCE: f64 = 0.1;
+
 pub struct Border {
     pub(crate) left: Option<BorderSide>,
     pub(crate) right: Option<BorderSide>,
@@ -15,11 +23,41 @@ pub struct Border {
     pub(crate) bottom: Option<BorderSide>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Side {
-    Left,
-    Right,
     Top,
+    Right,
     Bottom,
+    Left,
+}
+
+impl Side {
+    const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+
+    /// Index of the side, and of the corner where the side starts when going clockwise around the box
+    fn index(self) -> usize {
+        match self {
+            Side::Top => 0,
+            Side::Right => 1,
+            Side::Bottom => 2,
+            Side::Left => 3,
+        }
+    }
+
+    /// Angle of the outward normal of the side
+    fn normal(self) -> f64 {
+        match self {
+            Side::Top => -FRAC_PI_2,
+            Side::Right => 0.0,
+            Side::Bottom => FRAC_PI_2,
+            Side::Left => PI,
+        }
+    }
+
+    /// Whether the side is lit in the 3D border styles, the light comes from the top left
+    fn is_lit(self) -> bool {
+        matches!(self, Side::Top | Side::Left)
+    }
 }
 
 pub struct BorderRenderOptions<'a> {
@@ -29,62 +67,177 @@ pub struct BorderRenderOptions<'a> {
     pub radius: Option<&'a BorderRadius>,
 }
 
-struct BorderRenderSideOptions<'a> {
-    side: Side,
-    segment: &'a BorderSide,
-    transform: Option<&'a Transform>,
-    radius: Option<(Radius, Radius)>,
-    rect: &'a Rect,
+/// A rectangle with elliptical corners, the radii are in the order top-left, top-right, bottom-right, bottom-left
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BorderShape {
+    rect: VelloRect,
+    radii: [(f64, f64); 4],
 }
 
-impl<'a> BorderRenderOptions<'a> {
-    fn left(&self, transform: Option<&'a Transform>) -> Option<BorderRenderSideOptions> {
-        let segment = self.border.border.left.as_ref()?;
+impl BorderShape {
+    /// Insets the shape by the given widths (top, right, bottom, left), the radii shrink with it
+    fn inset(&self, [top, right, bottom, left]: [f64; 4]) -> Self {
+        let rect = VelloRect::new(
+            self.rect.x0 + left,
+            self.rect.y0 + top,
+            (self.rect.x1 - right).max(self.rect.x0 + left),
+            (self.rect.y1 - bottom).max(self.rect.y0 + top),
+        );
+
+        let shrink = |(rx, ry): (f64, f64), x: f64, y: f64| ((rx - x).max(0.0), (ry - y).max(0.0));
+
+        Self {
+            rect,
+            radii: [
+                shrink(self.radii[0], left, top),
+                shrink(self.radii[1], right, top),
+                shrink(self.radii[2], right, bottom),
+                shrink(self.radii[3], left, bottom),
+            ],
+        }
+    }
+
+    /// Center of the ellipse of a corner
+    fn corner_center(&self, corner: usize) -> Point {
+        let (rx, ry) = self.radii[corner];
+        let r = self.rect;
+
+        match corner {
+            0 => Point::new(r.x0 + rx, r.y0 + ry),
+            1 => Point::new(r.x1 - rx, r.y0 + ry),
+            2 => Point::new(r.x1 - rx, r.y1 - ry),
+            _ => Point::new(r.x0 + rx, r.y1 - ry),
+        }
+    }
+
+    fn corner_arc(&self, corner: usize, start: f64, sweep: f64) -> Arc {
+        Arc::new(self.corner_center(corner), self.radii[corner], start, sweep, 0.0)
+    }
+
+    /// Point on the ellipse of a corner at the given angle
+    fn corner_point(&self, corner: usize, angle: f64) -> Point {
+        let center = self.corner_center(corner);
+        let (rx, ry) = self.radii[corner];
 
-        Some(BorderRenderSideOptions {
-            side: Side::Left,
-            segment,
-            transform,
-            radius: self.radius.map(|r| (r.top_left, r.bottom_left)),
-            rect: self.rect,
-        })
+        Point::new(center.x + rx * angle.cos(), center.y + ry * angle.sin())
     }
 
-    fn right(&self, transform: Option<&'a Transform>) -> Option<BorderRenderSideOptions> {
-        let segment = self.border.border.right.as_ref()?;
+    /// Appends the outline of the shape clockwise, starting at the end of the top left corner
+    fn append_to(&self, path: &mut BezPath) {
+        path.move_to(self.corner_point(0, -FRAC_PI_2));
+
+        for side in Side::ALL {
+            let corner = (side.index() + 1) % 4;
+            let arc = self.corner_arc(corner, side.normal(), FRAC_PI_2);
+
+            path.line_to(self.corner_point(corner, side.normal()));
+            append_arc(path, &arc);
+        }
+
+        path.close_path();
+    }
+
+    /// The part of the outline along a side, from halfway its start corner to halfway its end corner
+    fn side_path(&self, side: Side) -> BezPath {
+        let mut path = BezPath::new();
+
+        let start = side.index();
+        let end = (start + 1) % 4;
+
+        path.move_to(self.corner_point(start, side.normal() - FRAC_PI_4));
+        append_arc(&mut path, &self.corner_arc(start, side.normal() - FRAC_PI_4, FRAC_PI_4));
+        path.line_to(self.corner_point(end, side.normal()));
+        append_arc(&mut path, &self.corner_arc(end, side.normal(), FRAC_PI_4));
 
-        Some(BorderRenderSideOptions {
-            side: Side::Right,
-            segment,
-            transform,
-            radius: self.radius.map(|r| (r.top_right, r.bottom_right)),
-            rect: self.rect,
-        })
+        path
+    }
+}
+
+fn append_arc(path: &mut BezPath, arc: &Arc) {
+    if arc.radii.x <= 0.0 || arc.radii.y <= 0.0 || arc.sweep_angle == 0.0 {
+        return;
     }
 
-    fn top(&self, transform: Option<&'a Transform>) -> Option<BorderRenderSideOptions> {
-        let segment = self.border.border.top.as_ref()?;
+    arc.to_cubic_beziers(TOLERANCE, |p1, p2, p3| {
+        path.curve_to(p1, p2, p3);
+    });
+}
 
-        Some(BorderRenderSideOptions {
-            side: Side::Top,
-            segment,
-            transform,
-            radius: self.radius.map(|r| (r.top_left, r.top_right)),
-            rect: self.rect,
-        })
+/// The area between two shapes, painted with the even-odd rule
+fn ring(outer: &BorderShape, inner: &BorderShape) -> BezPath {
+    let mut path = BezPath::new();
+    outer.append_to(&mut path);
+    inner.append_to(&mut path);
+    path
+}
+
+/// Returns a darker or lighter version of a solid brush, other brushes are not changed
+fn shade(brush: &VelloBrush, dark: bool) -> VelloBrush {
+    match brush {
+        VelloBrush::Solid(color) if dark => {
+            let scale = |c: u8| (c as f64 * 0.5) as u8;
+            VelloBrush::Solid(VelloColor::rgba8(
+                scale(color.r),
+                scale(color.g),
+                scale(color.b),
+                color.a,
+            ))
+        }
+        _ => brush.clone(),
     }
+}
+
+/// Computes `count` points that are evenly spaced along a path, including both ends
+fn points_along(path: &BezPath, count: usize) -> Vec<Point> {
+    let length = path.perimeter(TOLERANCE);
+    if count < 2 || length <= 0.0 {
+        return match path.elements().first() {
+            Some(PathEl::MoveTo(p)) => vec![*p],
+            _ => Vec::new(),
+        };
+    }
+
+    let spacing = length / (count - 1) as f64;
+
+    let mut points = Vec::with_capacity(count);
+    let mut last = Point::ZERO;
+    let mut travelled = 0.0;
+    let mut next = 0.0;
 
-    fn bottom(&self, transform: Option<&'a Transform>) -> Option<BorderRenderSideOptions> {
-        let segment = self.border.border.bottom.as_ref()?;
+    path.flatten(TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => last = p,
+        PathEl::LineTo(p) => {
+            let segment = last.distance(p);
 
-        Some(BorderRenderSideOptions {
-            side: Side::Bottom,
-            segment,
-            transform,
-            radius: self.radius.map(|r| (r.bottom_left, r.bottom_right)),
-            rect: self.rect,
-        })
+            while next <= travelled + segment + 1e-6 && points.len() < count {
+                let t = if segment > 0.0 {
+                    (next - travelled) / segment
+                } else {
+                    0.0
+                };
+                points.push(last.lerp(p, t.clamp(0.0, 1.0)));
+                next += spacing;
+            }
+
+            travelled += segment;
+            last = p;
+        }
+        _ => {}
+    });
+
+    points
+}
+
+/// Fits a repeating pattern with the given period along a length, so the pattern starts and ends the same way.
+/// Returns the number of repetitions and the adjusted period
+fn fit_pattern(length: f64, period: f64) -> (usize, f64) {
+    if period <= 0.0 || length <= 0.0 {
+        return (1, length.max(0.0));
     }
+
+    let count = (length / period).round().max(1.0);
+
+    (count as usize, length / count)
 }
 
 impl Border {
@@ -96,256 +249,155 @@ impl Border {
             (None, None) => None,
         };
 
-        let transform = transform.as_ref();
+        let affine = transform.map(|t| t.0).unwrap_or_default();
 
-        if let Some(segment) = opts.left(transform) {
-            Self::draw_side(scene, segment);
-        }
-        if let Some(segment) = opts.right(transform) {
-            Self::draw_side(scene, segment);
-        }
-        if let Some(segment) = opts.top(transform) {
-            Self::draw_side(scene, segment);
-        }
-        if let Some(segment) = opts.bottom(transform) {
-            Self::draw_side(scene, segment);
+        let border = &opts.border.border;
+        let sides = [&border.top, &border.right, &border.bottom, &border.left];
+
+        let widths = sides.map(|side| {
+            side.as_ref()
+                .filter(|side| !matches!(side.style, BorderStyle::None | BorderStyle::Hidden))
+                .map(|side| side.width.max(0.0) as f64)
+                .unwrap_or(0.0)
+        });
+
+        let radii = opts
+            .radius
+            .map(|r| [r.top_left, r.top_right, r.bottom_right, r.bottom_left].map(|r| r.radii_f64()))
+            .unwrap_or_default();
+
+        let outer = BorderShape {
+            rect: opts.rect.0,
+            radii,
+        };
+
+        for side in Side::ALL {
+            let Some(segment) = sides[side.index()] else {
+                continue;
+            };
+
+            let width = widths[side.index()];
+            if width <= 0.0 {
+                continue;
+            }
+
+            Self::draw_side(scene, affine, &outer, widths, side, segment);
         }
     }
 
-    fn draw_side(scene: &mut Scene, opts: BorderRenderSideOptions) {
-        let border_width = opts.segment.width as f64;
-        let brush = &opts.segment.brush.0;
-        let style = opts.segment.style;
-        let radius = opts.radius;
+    /// Returns the part of the border box that belongs to a side. The corners are split by the line from the outer
+    /// corner of the border to the inner corner
+    fn side_clip(outer: &BorderShape, widths: [f64; 4], side: Side) -> BezPath {
+        let rect = outer.rect;
+        let center = rect.center();
+
+        let corners = [
+            (Point::new(rect.x0, rect.y0), (widths[3], widths[0])),
+            (Point::new(rect.x1, rect.y0), (-widths[1], widths[0])),
+            (Point::new(rect.x1, rect.y1), (-widths[1], -widths[2])),
+            (Point::new(rect.x0, rect.y1), (widths[3], -widths[2])),
+        ];
+
+        // The join line is extended into the box, far enough to split the rounded part of the corner
+        let join = |corner: usize| {
+            let (point, (dx, dy)) = corners[corner];
+            let direction = (dx.powi(2) + dy.powi(2)).sqrt();
+            if direction <= 0.0 {
+                return point;
+            }
+
+            let (rx, ry) = outer.radii[corner];
+            let extent = ((rx.max(ry) + dx.abs().max(dy.abs())) * 2.0).min(point.distance(center));
 
-        let width = opts.rect.0.width();
-        let height = opts.rect.0.height();
+            Point::new(point.x + dx / direction * extent, point.y + dy / direction * extent)
+        };
 
-        let pos = opts.rect.0.origin();
+        let start = side.index();
+        let end = (start + 1) % 4;
 
         let mut path = BezPath::new();
+        path.move_to(corners[start].0);
+        path.line_to(corners[end].0);
+        path.line_to(join(end));
+        path.line_to(join(start));
+        path.close_path();
 
-        match opts.side {
-            Side::Top => {
-                match radius {
-                    Some((left, right)) => {
-                        let offset_left = left.offset();
-                        let offset_right = right.offset();
-
-                        path.move_to((pos.x - offset_left.width as f64, pos.y - offset_left.height as f64));
-
-                        let arc = Arc::new(
-                            (pos.x + offset_left.width as f64, pos.y - offset_left.height as f64),
-                            left.radii_f64(),
-                            -std::f64::consts::PI * 3.0 / 4.0,
-                            std::f64::consts::PI / 4.0,
-                            0.0,
-                        );
-
-                        arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                            path.curve_to(p1, p2, p3);
-                        });
-
-                        path.line_to((
-                            pos.x + width - right.radi_x() as f64,
-                            pos.y - offset_right.height as f64,
-                        ));
-
-                        let arc = Arc::new(
-                            (pos.x + width - right.radi_x() as f64, pos.y + right.radi_y() as f64),
-                            right.radii_f64(),
-                            0.0,
-                            std::f64::consts::PI / 4.0,
-                            0.0,
-                        );
-
-                        arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                            path.curve_to(p1, p2, p3);
-                        });
-                    }
-                    None => {
-                        path.move_to((pos.x, pos.y));
-                        path.line_to((pos.x + width, pos.y));
-                    }
-                };
+        path
+    }
+
+    fn draw_side(
+        scene: &mut Scene,
+        affine: Affine,
+        outer: &BorderShape,
+        widths: [f64; 4],
+        side: Side,
+        segment: &BorderSide,
+    ) {
+        let width = widths[side.index()];
+        let brush = &segment.brush.0;
+
+        let inset = |fraction: f64| outer.inset(widths.map(|w| w * fraction));
+        let inner = inset(1.0);
+
+        scene.push_layer(Mix::Clip, 1.0, affine, &Self::side_clip(outer, widths, side));
+
+        match segment.style {
+            BorderStyle::Dashed => Self::draw_dashes(scene, affine, &inset(0.5).side_path(side), width, brush),
+            BorderStyle::Dotted => Self::draw_dots(scene, affine, &inset(0.5).side_path(side), width, brush),
+            BorderStyle::Double if width >= 3.0 => {
+                scene.fill(Fill::EvenOdd, affine, brush, None, &ring(outer, &inset(1.0 / 3.0)));
+                scene.fill(Fill::EvenOdd, affine, brush, None, &ring(&inset(2.0 / 3.0), &inner));
             }
-            Side::Right => match radius {
-                Some((top, bottom)) => {
-                    let offset_top = top.offset();
-                    let offset_bottom = bottom.offset();
-
-                    path.move_to((
-                        pos.x + width + offset_top.width as f64,
-                        pos.y - offset_top.height as f64,
-                    ));
-
-                    let arc = Arc::new(
-                        (
-                            pos.x + width - offset_top.width as f64,
-                            pos.y + offset_top.height as f64,
-                        ),
-                        top.radii_f64(),
-                        -std::f64::consts::PI / 4.0,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-
-                    path.line_to((
-                        pos.x + width - offset_bottom.width as f64,
-                        pos.y + height - bottom.radi_y() as f64,
-                    ));
-
-                    let arc = Arc::new(
-                        (
-                            pos.x + width - offset_bottom.width as f64,
-                            pos.y + height - offset_bottom.height as f64,
-                        ),
-                        bottom.radii_f64(),
-                        0.0,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-                }
-                None => {
-                    path.move_to((pos.x + width, pos.y));
-                    path.line_to((pos.x + width, pos.y + height));
-                }
-            },
-            Side::Bottom => match radius {
-                Some((left, right)) => {
-                    let offset_left = left.offset();
-                    let offset_right = right.offset();
-
-                    path.move_to((
-                        pos.x + width + offset_right.width as f64,
-                        pos.y + height + offset_right.height as f64,
-                    ));
-
-                    let arc = Arc::new(
-                        (
-                            pos.x + width - offset_right.width as f64,
-                            pos.y + height - offset_right.height as f64,
-                        ),
-                        right.radii_f64(),
-                        -std::f64::consts::PI * 7.0 / 4.0,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-
-                    path.line_to((pos.x + left.radi_x() as f64, pos.y + height - offset_left.height as f64));
-
-                    let arc = Arc::new(
-                        (pos.x + left.radi_x() as f64, pos.y + height - offset_left.height as f64),
-                        left.radii_f64(),
-                        -std::f64::consts::PI * 3.0 / 2.0,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-                }
-                None => {
-                    path.move_to((pos.x, pos.y + height));
-                    path.line_to((pos.x + width, pos.y + height));
-                }
-            },
-            Side::Left => match radius {
-                Some((top, bottom)) => {
-                    let offset_top = top.offset();
-                    let offset_bottom = bottom.offset();
-
-                    path.move_to((
-                        pos.x - offset_top.width as f64,
-                        pos.y + height + offset_top.height as f64,
-                    ));
-
-                    let arc = Arc::new(
-                        (
-                            pos.x + offset_top.width as f64,
-                            pos.y + height - offset_top.height as f64,
-                        ),
-                        top.radii_f64(),
-                        -std::f64::consts::PI * 5.0 / 4.0,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-
-                    path.line_to((pos.x + offset_bottom.width as f64, pos.y + bottom.radi_y() as f64));
-
-                    let arc = Arc::new(
-                        (pos.x + offset_bottom.width as f64, pos.y + bottom.radi_y() as f64),
-                        bottom.radii_f64(),
-                        -std::f64::consts::PI,
-                        std::f64::consts::PI / 4.0,
-                        0.0,
-                    );
-
-                    arc.to_cubic_beziers(0.1, |p1, p2, p3| {
-                        path.curve_to(p1, p2, p3);
-                    });
-                }
-                None => {
-                    path.move_to((pos.x, pos.y + height));
-                    path.line_to((pos.x, pos.y));
-                }
-            },
-        }
+            BorderStyle::Groove | BorderStyle::Ridge => {
+                // A groove is carved into the page, so its outer half is in the shadow on the lit sides
+                let outer_dark = side.is_lit() == (segment.style == BorderStyle::Groove);
+                let middle = inset(0.5);
 
-        let cap = match style {
-            BorderStyle::Dashed => Cap::Square,
-            BorderStyle::Dotted => Cap::Round,
-            _ => Cap::Butt,
-        };
+                let outer_brush = shade(brush, outer_dark);
+                let inner_brush = shade(brush, !outer_dark);
 
-        let dash_pattern = match style {
-            BorderStyle::Dashed => SmallVec::from([
-                border_width * 3.0,
-                border_width * 3.0,
-                border_width * 3.0,
-                border_width * 3.0,
-            ]),
-            BorderStyle::Dotted => {
-                SmallVec::from([border_width, border_width, border_width, border_width])
-                //TODO: somehow this doesn't result in circles. It is more like a rounded rectangle
+                scene.fill(Fill::EvenOdd, affine, &outer_brush, None, &ring(outer, &middle));
+                scene.fill(Fill::EvenOdd, affine, &inner_brush, None, &ring(&middle, &inner));
             }
-            _ => SmallVec::default(),
-        };
+            BorderStyle::Inset | BorderStyle::Outset => {
+                let dark = side.is_lit() == (segment.style == BorderStyle::Inset);
+
+                scene.fill(Fill::EvenOdd, affine, &shade(brush, dark), None, &ring(outer, &inner));
+            }
+            _ => scene.fill(Fill::EvenOdd, affine, brush, None, &ring(outer, &inner)),
+        }
+
+        scene.pop_layer();
+    }
+
+    /// Strokes dashes along the center line of a side. The dashes are spread evenly, and there is half a dash at each
+    /// end, which forms a whole dash with the adjacent side
+    fn draw_dashes(scene: &mut Scene, affine: Affine, path: &BezPath, width: f64, brush: &VelloBrush) {
+        let (_, period) = fit_pattern(path.perimeter(TOLERANCE), width * 6.0);
+
+        // Square caps add half the width to both ends of a dash
+        let dash = (period / 2.0 - width).max(0.0);
 
         let stroke = Stroke {
-            width: border_width,
-            join: Join::Bevel,
-            miter_limit: 0.0,
-            start_cap: cap,
-            end_cap: cap,
-            dash_pattern,
-            dash_offset: 0.0,
+            width,
+            join: Join::Miter,
+            miter_limit: 4.0,
+            start_cap: Cap::Square,
+            end_cap: Cap::Square,
+            dash_pattern: SmallVec::from([dash, period - dash]),
+            dash_offset: dash / 2.0,
         };
 
-        scene.stroke(
-            &stroke,
-            opts.transform.map(|t| t.0).unwrap_or_default(),
-            brush,
-            None,
-            &path,
-        );
+        scene.stroke(&stroke, affine, brush, None, path);
+    }
+
+    /// Fills round dots along the center line of a side, with a dot at each end that is shared with the adjacent side
+    fn draw_dots(scene: &mut Scene, affine: Affine, path: &BezPath, width: f64, brush: &VelloBrush) {
+        let (count, _) = fit_pattern(path.perimeter(TOLERANCE), width * 2.0);
+
+        for point in points_along(path, count + 1) {
+            scene.fill(Fill::NonZero, affine, brush, None, &Circle::new(point, width / 2.0));
+        }
     }
 }
 
@@ -549,3 +601,39 @@ impl From<BorderRadius> for RoundedRectRadii {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_fit_the_length() {
+        assert_eq!(fit_pattern(100.0, 30.0), (3, 100.0 / 3.0));
+        assert_eq!(fit_pattern(10.0, 30.0), (1, 10.0));
+    }
+
+    #[test]
+    fn points_are_spread_along_the_path() {
+        let mut path = BezPath::new();
+        path.move_to((0.0, 0.0));
+        path.line_to((10.0, 0.0));
+        path.line_to((10.0, 10.0));
+
+        let points = points_along(&path, 5);
+        assert_eq!(points.len(), 5);
+        assert!(points[2].distance(Point::new(10.0, 0.0)) < 1e-6);
+        assert!(points[4].distance(Point::new(10.0, 10.0)) < 1e-6);
+    }
+
+    #[test]
+    fn inset_shrinks_radii() {
+        let shape = BorderShape {
+            rect: VelloRect::new(0.0, 0.0, 100.0, 50.0),
+            radii: [(10.0, 10.0), (4.0, 4.0), (0.0, 0.0), (10.0, 20.0)],
+        };
+
+        let inner = shape.inset([2.0, 6.0, 2.0, 4.0]);
+        assert_eq!(inner.rect, VelloRect::new(4.0, 2.0, 94.0, 48.0));
+        assert_eq!(inner.radii, [(6.0, 8.0), (0.0, 2.0), (0.0, 0.0), (6.0, 18.0)]);
+    }
+}
@@ -104,7 +104,7 @@ pub fn render_text_var<'a>(
         );
 }
 
-fn to_font_ref(font: &Font) -> Option<FontRef<'_>> {
+pub(crate) fn to_font_ref(font: &Font) -> Option<FontRef<'_>> {
     use vello::skrifa::raw::FileRef;
     let file_ref = FileRef::new(font.data.as_ref()).ok()?;
     match file_ref {
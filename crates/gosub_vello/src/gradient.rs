@@ -1,4 +1,4 @@
-use vello::peniko::{ColorStop as VelloColorStop, ColorStops as VelloColorStops, Gradient as VelloGradient};
+use vello::peniko::{ColorStop as VelloColorStop, ColorStops as VelloColorStops, Extend, Gradient as VelloGradient};
 
 use crate::{Convert, VelloBackend};
 use gosub_render_backend::geo::{Point, FP};
@@ -51,6 +51,10 @@ impl TGradient<VelloBackend> for Gradient {
 
         Gradient(gradient)
     }
+
+    fn repeat(self) -> Self {
+        Gradient(self.0.with_extend(Extend::Repeat))
+    }
 }
 
 impl Convert<VelloColorStops> for ColorStops<VelloBackend> {
@@ -1,12 +1,21 @@
-use vello::kurbo::RoundedRect;
-use vello::peniko::Fill;
+use vello::kurbo::{Affine, Rect as VelloRect, RoundedRect, Vec2};
+use vello::peniko::{BlendMode, Color as VelloColor, Compose, Fill, Mix};
 use vello::Scene as VelloScene;
 
-use gosub_render_backend::{Point, RenderBackend, RenderRect, RenderText, Scene as TScene, FP};
+use gosub_render_backend::{
+    BlendMode as TBlendMode, Point, RenderBackend, RenderLayer, RenderRect, RenderShadow, RenderText, Scene as TScene,
+    FP,
+};
 
 use crate::debug::text::render_text_simple;
 use crate::{Border, BorderRenderOptions, Text, Transform, VelloBackend};
 
+/// Number of samples on each side of the center in each direction, when blurring a scene
+const BLUR_SAMPLES: i32 = 3;
+
+/// Bounds of layers that shouldn't clip anything
+const UNBOUNDED: VelloRect = VelloRect::new(-1e9, -1e9, 1e9, 1e9);
+
 pub struct Scene(pub(crate) VelloScene);
 
 impl Scene {
@@ -61,6 +70,115 @@ impl TScene<VelloBackend> for Scene {
         self.0.append(&scene.0, transform.map(|t| t.0));
     }
 
+    fn draw_shadow(&mut self, shadow: &RenderShadow<VelloBackend>) {
+        let affine = shadow.transform.as_ref().map(|t| t.0).unwrap_or_default();
+        let color = shadow.color.0;
+        let std_dev = shadow.std_dev as f64;
+
+        let rect = match &shadow.radius {
+            Some(radius) => RoundedRect::from_rect(shadow.rect.0, radius.clone()),
+            None => RoundedRect::from_rect(shadow.rect.0, 0.0),
+        };
+
+        if shadow.inset {
+            // Fill the box with the color and cut out the blurred shadow shape, so only its edges remain
+            self.0.push_layer(Mix::Clip, 1.0, affine, &rect);
+            self.0.fill(Fill::NonZero, affine, color, None, &rect);
+            self.0
+                .push_layer(BlendMode::new(Mix::Normal, Compose::DestOut), 1.0, affine, &rect);
+            self.0.draw_blurred_rounded_rect(
+                affine,
+                shadow.shadow.0,
+                VelloColor::BLACK,
+                shadow.shadow_radius as f64,
+                std_dev,
+            );
+            self.0.pop_layer();
+            self.0.pop_layer();
+            return;
+        }
+
+        // The blur fades out after about three standard deviations
+        let bounds = shadow
+            .shadow
+            .0
+            .union(shadow.rect.0)
+            .inflate(std_dev * 3.0, std_dev * 3.0);
+
+        self.0.push_layer(Mix::Normal, 1.0, affine, &bounds);
+        self.0
+            .draw_blurred_rounded_rect(affine, shadow.shadow.0, color, shadow.shadow_radius as f64, std_dev);
+        self.0
+            .push_layer(BlendMode::new(Mix::Normal, Compose::DestOut), 1.0, affine, &rect);
+        self.0.fill(Fill::NonZero, affine, VelloColor::BLACK, None, &rect);
+        self.0.pop_layer();
+        self.0.pop_layer();
+    }
+
+    fn apply_blurred_scene(&mut self, scene: &Scene, transform: Option<Transform>, std_dev: FP) {
+        if std_dev <= 0.0 {
+            self.apply_scene(scene, transform);
+            return;
+        }
+
+        let affine = transform.map(|t| t.0).unwrap_or_default();
+
+        // Sample the scene on a grid that covers three standard deviations in each direction, and add up the samples
+        // weighted by a normalized gaussian
+        let step = std_dev as f64 * 3.0 / BLUR_SAMPLES as f64;
+        let gaussian = |d: i32| (-((d as f64 * step).powi(2)) / (2.0 * (std_dev as f64).powi(2))).exp();
+        let total: f64 = (-BLUR_SAMPLES..=BLUR_SAMPLES).map(gaussian).sum();
+
+        self.0.push_layer(Mix::Normal, 1.0, Affine::IDENTITY, &UNBOUNDED);
+
+        for y in -BLUR_SAMPLES..=BLUR_SAMPLES {
+            for x in -BLUR_SAMPLES..=BLUR_SAMPLES {
+                let weight = gaussian(x) * gaussian(y) / (total * total);
+                let offset = Affine::translate(Vec2::new(x as f64 * step, y as f64 * step)) * affine;
+
+                self.0.push_layer(
+                    BlendMode::new(Mix::Normal, Compose::Plus),
+                    weight as f32,
+                    Affine::IDENTITY,
+                    &UNBOUNDED,
+                );
+                self.0.append(&scene.0, Some(offset));
+                self.0.pop_layer();
+            }
+        }
+
+        self.0.pop_layer();
+    }
+
+    fn apply_layer(&mut self, scene: &Scene, layer: &RenderLayer<VelloBackend>) {
+        let transform = layer.transform.as_ref().map(|t| t.0);
+
+        if let Some(clip) = &layer.clip {
+            match &layer.clip_radius {
+                Some(radius) => {
+                    let shape = RoundedRect::from_rect(clip.0, radius.clone());
+                    self.0.push_layer(Mix::Clip, 1.0, Affine::IDENTITY, &shape);
+                }
+                None => self.0.push_layer(Mix::Clip, 1.0, Affine::IDENTITY, &clip.0),
+            }
+        }
+
+        if layer.opacity >= 1.0 && layer.blend_mode == TBlendMode::Normal {
+            self.0.append(&scene.0, transform);
+        } else {
+            let blend = BlendMode::new(mix(layer.blend_mode), Compose::SrcOver);
+
+            self.0
+                .push_layer(blend, layer.opacity.clamp(0.0, 1.0), Affine::IDENTITY, &UNBOUNDED);
+            self.0.append(&scene.0, transform);
+            self.0.pop_layer();
+        }
+
+        if layer.clip.is_some() {
+            self.0.pop_layer();
+        }
+    }
+
     fn reset(&mut self) {
         self.0.reset()
     }
@@ -70,6 +188,27 @@ impl TScene<VelloBackend> for Scene {
     }
 }
 
+fn mix(mode: TBlendMode) -> Mix {
+    match mode {
+        TBlendMode::Normal => Mix::Normal,
+        TBlendMode::Multiply => Mix::Multiply,
+        TBlendMode::Screen => Mix::Screen,
+        TBlendMode::Overlay => Mix::Overlay,
+        TBlendMode::Darken => Mix::Darken,
+        TBlendMode::Lighten => Mix::Lighten,
+        TBlendMode::ColorDodge => Mix::ColorDodge,
+        TBlendMode::ColorBurn => Mix::ColorBurn,
+        TBlendMode::HardLight => Mix::HardLight,
+        TBlendMode::SoftLight => Mix::SoftLight,
+        TBlendMode::Difference => Mix::Difference,
+        TBlendMode::Exclusion => Mix::Exclusion,
+        TBlendMode::Hue => Mix::Hue,
+        TBlendMode::Saturation => Mix::Saturation,
+        TBlendMode::Color => Mix::Color,
+        TBlendMode::Luminosity => Mix::Luminosity,
+    }
+}
+
 impl From<VelloScene> for Scene {
     fn from(scene: VelloScene) -> Self {
         Self(scene)
@@ -1,19 +1,22 @@
 use crate::VelloBackend;
+use decoration::{draw_decoration, LineKind, RunInfo};
 use gosub_render_backend::geo::FP;
 use gosub_render_backend::layout::{Decoration, TextLayout};
 use gosub_render_backend::{RenderText, Text as TText};
 use vello::glyph::Glyph;
-use vello::kurbo::{Affine, Line, Stroke};
-use vello::peniko::{Brush, Color, Fill, Font, StyleRef};
+use vello::kurbo::Affine;
+use vello::peniko::{Fill, Font, StyleRef};
 use vello::skrifa::instance::NormalizedCoord;
 use vello::Scene;
 
+mod decoration;
+
 pub struct Text {
     glyphs: Vec<Glyph>,
     font: Font,
     fs: FP,
     coords: Vec<NormalizedCoord>,
-    decoration: Decoration,
+    decorations: Vec<Decoration>,
 }
 
 impl TText for Text {
@@ -42,7 +45,7 @@ impl TText for Text {
             font,
             fs,
             coords,
-            decoration: layout.decorations().clone(),
+            decorations: layout.decorations().to_vec(),
         }
     }
 }
@@ -60,49 +63,31 @@ impl Text {
 
         let transform = transform.with_translation((x, y).into());
 
+        let text = &render.text;
+        let run = RunInfo::new(&text.font, text.fs, &text.coords, &text.glyphs, render.rect.0.width());
+
+        // Underlines and overlines are painted below the text, line-throughs above it
+        for decoration in &text.decorations {
+            draw_decoration(
+                scene,
+                transform,
+                decoration,
+                &run,
+                &[LineKind::Underline, LineKind::Overline],
+            );
+        }
+
         scene
-            .draw_glyphs(&render.text.font)
-            .font_size(render.text.fs)
+            .draw_glyphs(&text.font)
+            .font_size(text.fs)
             .transform(transform)
             .glyph_transform(brush_transform)
-            .normalized_coords(&render.text.coords)
+            .normalized_coords(&text.coords)
             .brush(brush)
-            .draw(style, render.text.glyphs.iter().copied());
-
-        {
-            let decoration = &render.text.decoration;
-
-            let stroke = Stroke::new(decoration.width as f64);
-
-            let c = decoration.color;
-
-            let brush = Brush::Solid(Color::rgba(c.0 as f64, c.1 as f64, c.2 as f64, c.3 as f64));
-
-            let offset = decoration.x_offset as f64;
-
-            if decoration.underline {
-                let y = y + decoration.underline_offset as f64;
-
-                let line = Line::new((x + offset, y), (x + render.rect.0.width(), y));
-
-                scene.stroke(&stroke, Affine::IDENTITY, &brush, None, &line);
-            }
-
-            if decoration.overline {
-                let y = y - render.rect.0.height();
-
-                let line = Line::new((x + offset, y), (x + render.rect.0.width(), y));
-
-                scene.stroke(&stroke, Affine::IDENTITY, &brush, None, &line);
-            }
-
-            if decoration.line_through {
-                let y = y - render.rect.0.height() / 2.0;
-
-                let line = Line::new((x + offset, y), (x + render.rect.0.width(), y));
+            .draw(style, text.glyphs.iter().copied());
 
-                scene.stroke(&stroke, Affine::IDENTITY, &brush, None, &line);
-            }
+        for decoration in &text.decorations {
+            draw_decoration(scene, transform, decoration, &run, &[LineKind::LineThrough]);
         }
     }
 }
@@ -0,0 +1,472 @@
+//! Painting of text decoration lines (<https://www.w3.org/TR/css-text-decor-3/#line-decoration>). Lines are positioned
+//! with the underline and strikeout metrics of the font, unless the decorating box sets their thickness or offset.
+
+use gosub_render_backend::layout::{Decoration, DecorationStyle};
+use vello::glyph::Glyph;
+use vello::kurbo::{Affine, BezPath, Cap, Circle, Line, PathEl, Point, Stroke};
+use vello::peniko::{Brush, Color, Fill, Font};
+use vello::skrifa::instance::{LocationRef, NormalizedCoord, Size};
+use vello::skrifa::outline::{DrawSettings, OutlinePen};
+use vello::skrifa::{GlyphId, MetadataProvider};
+use vello::Scene;
+
+use crate::debug::text::to_font_ref;
+
+/// Tolerance used to flatten glyph outlines when looking for the parts that cross a line
+const TOLERANCE: f64 = 0.1;
+
+/// The lines of a decoration, which are painted at different times relative to the glyphs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LineKind {
+    Underline,
+    Overline,
+    LineThrough,
+}
+
+/// Metrics of a font in pixels, relative to the baseline with the y-axis pointing down
+#[derive(Debug, Clone, Copy)]
+struct LineMetrics {
+    /// Top edge and thickness of the underline
+    underline: (f64, f64),
+    /// Top edge and thickness of the line-through
+    strikeout: (f64, f64),
+    ascent: f64,
+    descent: f64,
+}
+
+impl LineMetrics {
+    fn new(font: &Font, font_size: f32, coords: &[NormalizedCoord]) -> Self {
+        let size = font_size as f64;
+
+        // Used when the font doesn't have the metrics
+        let mut metrics = Self {
+            underline: (size * 0.1, size / 14.0),
+            strikeout: (-size * 0.3, size / 14.0),
+            ascent: -size * 0.8,
+            descent: size * 0.2,
+        };
+
+        let Some(font_ref) = to_font_ref(font) else {
+            return metrics;
+        };
+
+        let font_metrics = font_ref.metrics(Size::new(font_size), LocationRef::new(coords));
+
+        metrics.ascent = -font_metrics.ascent as f64;
+        metrics.descent = -font_metrics.descent as f64;
+
+        if let Some(underline) = font_metrics.underline {
+            metrics.underline = (-underline.offset as f64, underline.thickness as f64);
+        }
+
+        if let Some(strikeout) = font_metrics.strikeout {
+            metrics.strikeout = (-strikeout.offset as f64, strikeout.thickness as f64);
+        }
+
+        metrics
+    }
+}
+
+/// Paints the lines of a decoration of a glyph run. The run starts at the origin of the transform, and the baseline is
+/// at y = 0. Underlines and overlines are painted below the glyphs, line-throughs above them
+pub(crate) fn draw_decoration(
+    scene: &mut Scene,
+    transform: Affine,
+    decoration: &Decoration,
+    run: &RunInfo,
+    kinds: &[LineKind],
+) {
+    let c = decoration.color;
+    let brush = Brush::Solid(Color::rgba(c.0 as f64, c.1 as f64, c.2 as f64, c.3 as f64));
+
+    let metrics = LineMetrics::new(run.font, run.font_size, run.coords);
+
+    for &kind in kinds {
+        let painted = match kind {
+            LineKind::Underline => decoration.underline,
+            LineKind::Overline => decoration.overline,
+            LineKind::LineThrough => decoration.line_through,
+        };
+
+        if !painted {
+            continue;
+        }
+
+        let (y, thickness) = line_position(kind, decoration, &metrics);
+        let direction = if kind == LineKind::Overline { -1.0 } else { 1.0 };
+
+        let start = decoration.x_offset as f64;
+        let mut segments = vec![(start, run.width)];
+
+        // Line-throughs cross the glyphs on purpose, so only the other lines skip them
+        if decoration.skip_ink && kind != LineKind::LineThrough {
+            let (top, bottom) = line_band(decoration.style, y, thickness, direction);
+            let gaps = run.ink_gaps(top, bottom, thickness);
+
+            segments = cut_gaps(start, run.width, gaps, thickness);
+        }
+
+        for (x0, x1) in segments {
+            draw_line(
+                scene,
+                transform,
+                &brush,
+                decoration.style,
+                (x0, x1),
+                y,
+                thickness,
+                direction,
+            );
+        }
+    }
+}
+
+/// The glyphs of a run that the lines are painted for
+pub(crate) struct RunInfo<'a> {
+    font: &'a Font,
+    font_size: f32,
+    coords: &'a [NormalizedCoord],
+    glyphs: &'a [Glyph],
+    width: f64,
+}
+
+impl<'a> RunInfo<'a> {
+    pub(crate) fn new(
+        font: &'a Font,
+        font_size: f32,
+        coords: &'a [NormalizedCoord],
+        glyphs: &'a [Glyph],
+        width: f64,
+    ) -> Self {
+        Self {
+            font,
+            font_size,
+            coords,
+            glyphs,
+            width,
+        }
+    }
+
+    /// Returns the horizontal extents of the glyphs whose outlines cross the band between `top` and `bottom`, widened
+    /// by `padding` on both sides
+    fn ink_gaps(&self, top: f64, bottom: f64, padding: f64) -> Vec<(f64, f64)> {
+        let Some(font_ref) = to_font_ref(self.font) else {
+            return Vec::new();
+        };
+
+        let outlines = font_ref.outline_glyphs();
+
+        let mut gaps = Vec::new();
+
+        for glyph in self.glyphs {
+            let Some(outline) = outlines.get(GlyphId::new(glyph.id as u16)) else {
+                continue;
+            };
+
+            let mut pen = PathPen::default();
+            let settings = DrawSettings::unhinted(Size::new(self.font_size), LocationRef::new(self.coords));
+            if outline.draw(settings, &mut pen).is_err() {
+                continue;
+            }
+
+            // Outlines have the y-axis pointing up
+            let path = Affine::new([1.0, 0.0, 0.0, -1.0, glyph.x as f64, glyph.y as f64]) * pen.0;
+
+            if let Some((x0, x1)) = band_extent(&path, top, bottom) {
+                gaps.push((x0 - padding, x1 + padding));
+            }
+        }
+
+        gaps
+    }
+}
+
+/// Returns the center and thickness of a line
+fn line_position(kind: LineKind, decoration: &Decoration, metrics: &LineMetrics) -> (f64, f64) {
+    let (top, font_thickness) = match kind {
+        LineKind::Underline => {
+            let thickness = metrics.underline.1;
+            let offset = decoration.underline_offset.map(|offset| offset as f64);
+
+            let top = if decoration.underline_under {
+                metrics.descent + offset.unwrap_or(0.0)
+            } else {
+                offset.unwrap_or(metrics.underline.0)
+            };
+
+            (top, thickness)
+        }
+        LineKind::Overline => (metrics.ascent, metrics.underline.1),
+        LineKind::LineThrough => metrics.strikeout,
+    };
+
+    let thickness = decoration
+        .width
+        .map(|width| width as f64)
+        .unwrap_or(font_thickness)
+        .max(1.0);
+
+    (top + thickness / 2.0, thickness)
+}
+
+/// Returns the vertical extent of a line, the second line of a double line is placed in the direction
+fn line_band(style: DecorationStyle, y: f64, thickness: f64, direction: f64) -> (f64, f64) {
+    let (a, b) = match style {
+        DecorationStyle::Double => (y - direction * thickness / 2.0, y + direction * thickness * 2.5),
+        DecorationStyle::Wavy => (y - thickness * 1.5, y + thickness * 1.5),
+        _ => (y - thickness / 2.0, y + thickness / 2.0),
+    };
+
+    (a.min(b), a.max(b))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_line(
+    scene: &mut Scene,
+    transform: Affine,
+    brush: &Brush,
+    style: DecorationStyle,
+    (x0, x1): (f64, f64),
+    y: f64,
+    thickness: f64,
+    direction: f64,
+) {
+    let stroke = Stroke::new(thickness).with_caps(Cap::Butt);
+
+    match style {
+        DecorationStyle::Solid => {
+            scene.stroke(&stroke, transform, brush, None, &Line::new((x0, y), (x1, y)));
+        }
+        DecorationStyle::Double => {
+            let y2 = y + direction * thickness * 2.0;
+
+            scene.stroke(&stroke, transform, brush, None, &Line::new((x0, y), (x1, y)));
+            scene.stroke(&stroke, transform, brush, None, &Line::new((x0, y2), (x1, y2)));
+        }
+        DecorationStyle::Dotted => {
+            let mut x = x0 + thickness / 2.0;
+            while x <= x1 - thickness / 2.0 {
+                scene.fill(
+                    Fill::NonZero,
+                    transform,
+                    brush,
+                    None,
+                    &Circle::new((x, y), thickness / 2.0),
+                );
+                x += thickness * 2.0;
+            }
+        }
+        DecorationStyle::Dashed => {
+            let stroke = stroke.with_dashes(0.0, [thickness * 3.0, thickness * 2.0]);
+
+            scene.stroke(&stroke, transform, brush, None, &Line::new((x0, y), (x1, y)));
+        }
+        DecorationStyle::Wavy => {
+            scene.stroke(&stroke, transform, brush, None, &wavy_path(x0, x1, y, thickness));
+        }
+    }
+}
+
+/// Builds a wavy line around `y`. The waves start at a multiple of their wavelength, so the waves of adjacent
+/// segments line up
+fn wavy_path(x0: f64, x1: f64, y: f64, thickness: f64) -> BezPath {
+    let half = thickness * 2.0;
+    let amplitude = thickness;
+
+    let mut path = BezPath::new();
+
+    let mut x = (x0 / (half * 2.0)).floor() * half * 2.0;
+    let mut up = true;
+
+    path.move_to((x, y));
+
+    while x < x1 {
+        let peak = if up { y - amplitude * 2.0 } else { y + amplitude * 2.0 };
+
+        path.quad_to((x + half / 2.0, peak), (x + half, y));
+
+        x += half;
+        up = !up;
+    }
+
+    clip_x(&path, x0, x1)
+}
+
+/// Cuts a flattened copy of the path to the horizontal range
+fn clip_x(path: &BezPath, x0: f64, x1: f64) -> BezPath {
+    let mut clipped = BezPath::new();
+    let mut last: Option<Point> = None;
+
+    path.flatten(TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => last = Some(p),
+        PathEl::LineTo(p) => {
+            let Some(from) = last.replace(p) else {
+                return;
+            };
+
+            let (a, b) = if from.x <= p.x { (from, p) } else { (p, from) };
+            if b.x < x0 || a.x > x1 || b.x == a.x {
+                return;
+            }
+
+            let at = |x: f64| Point::new(x, a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x));
+            let start = if a.x < x0 { at(x0) } else { a };
+            let end = if b.x > x1 { at(x1) } else { b };
+
+            if clipped.elements().is_empty() {
+                clipped.move_to(start);
+            }
+            clipped.line_to(end);
+        }
+        _ => {}
+    });
+
+    clipped
+}
+
+/// Returns the horizontal extent of the parts of the path between `top` and `bottom`
+fn band_extent(path: &BezPath, top: f64, bottom: f64) -> Option<(f64, f64)> {
+    let mut extent: Option<(f64, f64)> = None;
+    let mut start = Point::ZERO;
+    let mut last = Point::ZERO;
+
+    let mut add = |x: f64| {
+        extent = Some(match extent {
+            Some((min, max)) => (min.min(x), max.max(x)),
+            None => (x, x),
+        });
+    };
+
+    let mut segment = |a: Point, b: Point| {
+        if a.y == b.y {
+            if (top..=bottom).contains(&a.y) {
+                add(a.x);
+                add(b.x);
+            }
+            return;
+        }
+
+        let t0 = (top - a.y) / (b.y - a.y);
+        let t1 = (bottom - a.y) / (b.y - a.y);
+        let from = t0.min(t1).max(0.0);
+        let to = t0.max(t1).min(1.0);
+
+        if from <= to {
+            add(a.x + (b.x - a.x) * from);
+            add(a.x + (b.x - a.x) * to);
+        }
+    };
+
+    path.flatten(TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => {
+            start = p;
+            last = p;
+        }
+        PathEl::LineTo(p) => {
+            segment(last, p);
+            last = p;
+        }
+        PathEl::ClosePath => {
+            segment(last, start);
+            last = start;
+        }
+        _ => {}
+    });
+
+    extent
+}
+
+/// Removes the gaps from the range, and returns the parts that remain. Parts shorter than `min` are dropped, as they
+/// would only be specks between the glyphs
+fn cut_gaps(start: f64, end: f64, mut gaps: Vec<(f64, f64)>, min: f64) -> Vec<(f64, f64)> {
+    gaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut segments = Vec::new();
+    let mut x = start;
+
+    for (gap_start, gap_end) in gaps {
+        if gap_start > x {
+            segments.push((x, gap_start.min(end)));
+        }
+        x = x.max(gap_end);
+
+        if x >= end {
+            break;
+        }
+    }
+
+    if x < end {
+        segments.push((x, end));
+    }
+
+    segments.retain(|(x0, x1)| x1 - x0 >= min);
+    segments
+}
+
+/// Collects a glyph outline into a path
+#[derive(Default)]
+struct PathPen(BezPath);
+
+impl OutlinePen for PathPen {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to((x as f64, y as f64));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to((x as f64, y as f64));
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        self.0.quad_to((cx0 as f64, cy0 as f64), (x as f64, y as f64));
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        self.0
+            .curve_to((cx0 as f64, cy0 as f64), (cx1 as f64, cy1 as f64), (x as f64, y as f64));
+    }
+
+    fn close(&mut self) {
+        self.0.close_path();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::kurbo::Shape;
+
+    use super::*;
+
+    #[test]
+    fn gaps_are_cut_from_the_line() {
+        let segments = cut_gaps(0.0, 100.0, vec![(40.0, 50.0), (10.0, 20.0), (45.0, 60.0)], 1.0);
+        assert_eq!(segments, vec![(0.0, 10.0), (20.0, 40.0), (60.0, 100.0)]);
+
+        // Specks between gaps that are close together are dropped
+        let segments = cut_gaps(0.0, 100.0, vec![(10.0, 20.0), (20.5, 30.0), (95.0, 120.0)], 1.0);
+        assert_eq!(segments, vec![(0.0, 10.0), (30.0, 95.0)]);
+    }
+
+    #[test]
+    fn band_extent_of_a_descender() {
+        // A stem that reaches from the baseline down to y = 4, the underline band is at 2..3
+        let mut path = BezPath::new();
+        path.move_to((10.0, 0.0));
+        path.line_to((12.0, 0.0));
+        path.line_to((12.0, 4.0));
+        path.line_to((10.0, 4.0));
+        path.close_path();
+
+        assert_eq!(band_extent(&path, 2.0, 3.0), Some((10.0, 12.0)));
+        assert_eq!(band_extent(&path, 5.0, 6.0), None);
+    }
+
+    #[test]
+    fn wavy_lines_stay_within_the_segment() {
+        let path = wavy_path(3.0, 50.0, 10.0, 2.0);
+        let bounds = path.bounding_box();
+
+        assert!((bounds.x0 - 3.0).abs() < 1e-6);
+        assert!((bounds.x1 - 50.0).abs() < 1e-6);
+        assert!(bounds.y0 >= 10.0 - 4.0 && bounds.y1 <= 10.0 + 4.0);
+    }
+}
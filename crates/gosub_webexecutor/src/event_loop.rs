@@ -0,0 +1,566 @@
+//! The event loop of a browsing context (<https://html.spec.whatwg.org/multipage/webappapis.html#event-loops>).
+//!
+//! The event loop doesn't know about the javascript engine that runs in it: tasks, timers and animation frame
+//! callbacks are closures, so every engine (and the engine itself, for things like promise jobs) can queue work on
+//! it. The embedder drives the loop by calling [`EventLoop::tick`] when it is idle and
+//! [`EventLoop::run_animation_frames`] when it renders a frame.
+//!
+//! All times are `DOMHighResTimeStamp`s: milliseconds since the time origin of the document, as chosen by the
+//! embedder.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::rc::Rc;
+
+use log::warn;
+
+use gosub_shared::types::Result;
+
+/// A task that runs once
+pub type Task = Box<dyn FnOnce() -> Result<()>>;
+
+/// The callback of a timer, which runs again for every repetition of an interval
+pub type TimerCallback = Box<dyn FnMut() -> Result<()>>;
+
+/// A callback of `requestAnimationFrame`, which gets the time of the frame
+pub type FrameCallback = Box<dyn FnOnce(f64) -> Result<()>>;
+
+/// Timers that are nested deeper than this are clamped to [`MIN_NESTED_TIMEOUT`]
+const MAX_TIMER_NESTING_LEVEL: u32 = 5;
+
+/// Minimum timeout of deeply nested timers, in milliseconds
+const MIN_NESTED_TIMEOUT: f64 = 4.0;
+
+/// The task sources of the task queues (<https://html.spec.whatwg.org/multipage/webappapis.html#generic-task-sources>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TaskSource {
+    DomManipulation,
+    UserInteraction,
+    Networking,
+    HistoryTraversal,
+    Timer,
+    PostedMessage,
+}
+
+/// An entry of a task queue
+enum Job {
+    Task(Task),
+    /// The timer with the given id has expired
+    Timer(u32),
+}
+
+struct Timer {
+    /// Taken out while the callback runs, so the timer can be cleared from within its own callback
+    callback: Option<TimerCallback>,
+    deadline: f64,
+    /// Timeout of an interval, `None` for a timer that only runs once
+    interval: Option<f64>,
+    nesting_level: u32,
+    /// The timer has expired and its task is queued
+    queued: bool,
+}
+
+#[derive(Default)]
+struct Queues {
+    /// Task queues by their source, every job has a sequence number so the oldest task can be picked
+    tasks: BTreeMap<TaskSource, VecDeque<(u64, Job)>>,
+    next_sequence: u64,
+    microtasks: VecDeque<Task>,
+    performing_microtask_checkpoint: bool,
+    timers: HashMap<u32, Timer>,
+    next_timer_id: u32,
+    /// Nesting level of the timer task that is running, 0 when no timer task runs
+    nesting_level: u32,
+    /// Animation frame callbacks by their handle, so they run in the order they were requested
+    frame_callbacks: BTreeMap<u32, FrameCallback>,
+    next_frame_id: u32,
+    /// Time of the last tick or animation frame
+    now: f64,
+}
+
+/// Handle to an event loop. Clones share the same queues, so callbacks can hold on to a handle to queue more work
+/// while the loop is running.
+#[derive(Clone, Default)]
+pub struct EventLoop {
+    queues: Rc<RefCell<Queues>>,
+}
+
+impl EventLoop {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time of the last tick or animation frame. Timers are scheduled relative to this time
+    pub fn now(&self) -> f64 {
+        self.queues.borrow().now
+    }
+
+    pub fn queue_task(&self, source: TaskSource, task: impl FnOnce() -> Result<()> + 'static) {
+        self.queue_job(source, Job::Task(Box::new(task)));
+    }
+
+    /// Queues a microtask, which runs as soon as the running task (or the running microtask) has finished
+    pub fn queue_microtask(&self, task: impl FnOnce() -> Result<()> + 'static) {
+        self.queues.borrow_mut().microtasks.push_back(Box::new(task));
+    }
+
+    /// Schedules a callback to run once after `timeout` milliseconds, like `setTimeout`. Returns the handle of the
+    /// timer
+    pub fn set_timeout(&self, callback: impl FnMut() -> Result<()> + 'static, timeout: f64) -> u32 {
+        self.set_timer(Box::new(callback), timeout, false)
+    }
+
+    /// Schedules a callback to run every `timeout` milliseconds, like `setInterval`. Returns the handle of the timer
+    pub fn set_interval(&self, callback: impl FnMut() -> Result<()> + 'static, timeout: f64) -> u32 {
+        self.set_timer(Box::new(callback), timeout, true)
+    }
+
+    /// Clears a timeout or an interval, `clearTimeout` and `clearInterval` share the same handles
+    pub fn clear_timer(&self, id: u32) {
+        self.queues.borrow_mut().timers.remove(&id);
+    }
+
+    /// Requests a callback to run before the next frame is rendered. Returns the handle of the callback
+    pub fn request_animation_frame(&self, callback: impl FnOnce(f64) -> Result<()> + 'static) -> u32 {
+        let mut queues = self.queues.borrow_mut();
+
+        queues.next_frame_id += 1;
+        let id = queues.next_frame_id;
+
+        queues.frame_callbacks.insert(id, Box::new(callback));
+
+        id
+    }
+
+    pub fn cancel_animation_frame(&self, id: u32) {
+        self.queues.borrow_mut().frame_callbacks.remove(&id);
+    }
+
+    /// Returns true when a callback is waiting for the next frame, so the embedder should render one
+    pub fn has_animation_frames(&self) -> bool {
+        !self.queues.borrow().frame_callbacks.is_empty()
+    }
+
+    /// Returns true when tasks are queued that can run right away
+    pub fn has_pending_tasks(&self) -> bool {
+        let queues = self.queues.borrow();
+
+        !queues.microtasks.is_empty() || queues.tasks.values().any(|queue| !queue.is_empty())
+    }
+
+    /// Returns the time at which the next timer expires, the embedder should tick the loop again at that time
+    pub fn next_timer_deadline(&self) -> Option<f64> {
+        self.queues
+            .borrow()
+            .timers
+            .values()
+            .filter(|timer| !timer.queued)
+            .map(|timer| timer.deadline)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Queues the tasks of the timers that expired at `now`, and runs the tasks that are queued. Tasks that are queued
+    /// by these tasks run at the next tick, so a task that keeps queueing itself can't starve the embedder. Returns
+    /// the number of tasks that ran
+    pub fn tick(&self, now: f64) -> usize {
+        let pending = {
+            let mut queues = self.queues.borrow_mut();
+            queues.now = now;
+
+            let mut expired = queues
+                .timers
+                .iter()
+                .filter(|(_, timer)| !timer.queued && timer.deadline <= now)
+                .map(|(id, timer)| (timer.deadline, *id))
+                .collect::<Vec<_>>();
+
+            // Timers with the same deadline run in the order they were created
+            expired.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+            for (_, id) in expired {
+                if let Some(timer) = queues.timers.get_mut(&id) {
+                    timer.queued = true;
+                }
+
+                queues.push_job(TaskSource::Timer, Job::Timer(id));
+            }
+
+            queues.tasks.values().map(VecDeque::len).sum::<usize>()
+        };
+
+        // Microtasks that were queued outside of a task, like by the embedder, run before the first task
+        self.perform_microtask_checkpoint();
+
+        let mut ran = 0;
+
+        for _ in 0..pending {
+            let Some(job) = self.queues.borrow_mut().next_job() else {
+                break;
+            };
+
+            match job {
+                Job::Task(task) => report(task()),
+                Job::Timer(id) => self.run_timer(id),
+            }
+
+            self.perform_microtask_checkpoint();
+
+            ran += 1;
+        }
+
+        ran
+    }
+
+    /// Runs the animation frame callbacks with the time of the frame, before the frame is rendered
+    /// (<https://html.spec.whatwg.org/multipage/imagebitmap-and-animations.html#run-the-animation-frame-callbacks>).
+    /// Callbacks that are requested by these callbacks run at the next frame
+    pub fn run_animation_frames(&self, now: f64) {
+        let ids = {
+            let mut queues = self.queues.borrow_mut();
+            queues.now = now;
+
+            queues.frame_callbacks.keys().copied().collect::<Vec<_>>()
+        };
+
+        for id in ids {
+            // A callback can cancel the callbacks after it
+            let Some(callback) = self.queues.borrow_mut().frame_callbacks.remove(&id) else {
+                continue;
+            };
+
+            report(callback(now));
+
+            self.perform_microtask_checkpoint();
+        }
+    }
+
+    /// Runs microtasks until the queue is empty, including the microtasks that are queued by microtasks
+    pub fn perform_microtask_checkpoint(&self) {
+        {
+            let mut queues = self.queues.borrow_mut();
+
+            if queues.performing_microtask_checkpoint {
+                return;
+            }
+
+            queues.performing_microtask_checkpoint = true;
+        }
+
+        loop {
+            let Some(task) = self.queues.borrow_mut().microtasks.pop_front() else {
+                break;
+            };
+
+            report(task());
+        }
+
+        self.queues.borrow_mut().performing_microtask_checkpoint = false;
+    }
+
+    /// The timer initialization steps (<https://html.spec.whatwg.org/multipage/timers-and-user-prompts.html#timer-initialisation-steps>)
+    fn set_timer(&self, callback: TimerCallback, timeout: f64, repeat: bool) -> u32 {
+        let mut queues = self.queues.borrow_mut();
+
+        let nesting_level = queues.nesting_level;
+        let timeout = clamp_timeout(timeout, nesting_level);
+
+        queues.next_timer_id += 1;
+        let id = queues.next_timer_id;
+
+        let deadline = queues.now + timeout;
+
+        queues.timers.insert(
+            id,
+            Timer {
+                callback: Some(callback),
+                deadline,
+                interval: repeat.then_some(timeout),
+                nesting_level: nesting_level + 1,
+                queued: false,
+            },
+        );
+
+        id
+    }
+
+    fn run_timer(&self, id: u32) {
+        let (mut callback, nesting_level) = {
+            let mut queues = self.queues.borrow_mut();
+
+            // The timer may have been cleared after its task was queued
+            let Some(timer) = queues.timers.get_mut(&id) else {
+                return;
+            };
+
+            let Some(callback) = timer.callback.take() else {
+                return;
+            };
+
+            let nesting_level = timer.nesting_level;
+            queues.nesting_level = nesting_level;
+
+            (callback, nesting_level)
+        };
+
+        report(callback());
+
+        let mut queues = self.queues.borrow_mut();
+        queues.nesting_level = 0;
+
+        let now = queues.now;
+
+        let Some(timer) = queues.timers.get_mut(&id) else {
+            return;
+        };
+
+        let Some(interval) = timer.interval else {
+            queues.timers.remove(&id);
+            return;
+        };
+
+        // The interval is scheduled again as if it was created by its own task, so it is nested one level deeper
+        let interval = clamp_timeout(interval, nesting_level);
+
+        timer.callback = Some(callback);
+        timer.deadline = now + interval;
+        timer.interval = Some(interval);
+        timer.nesting_level = nesting_level + 1;
+        timer.queued = false;
+    }
+
+    fn queue_job(&self, source: TaskSource, job: Job) {
+        self.queues.borrow_mut().push_job(source, job);
+    }
+}
+
+impl Queues {
+    fn push_job(&mut self, source: TaskSource, job: Job) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.tasks.entry(source).or_default().push_back((sequence, job));
+    }
+
+    /// Picks the next task to run. User interaction is handled first to keep the page responsive, other tasks run in
+    /// the order they were queued
+    fn next_job(&mut self) -> Option<Job> {
+        let source = if self
+            .tasks
+            .get(&TaskSource::UserInteraction)
+            .is_some_and(|queue| !queue.is_empty())
+        {
+            TaskSource::UserInteraction
+        } else {
+            *self
+                .tasks
+                .iter()
+                .filter_map(|(source, queue)| Some((source, queue.front()?.0)))
+                .min_by_key(|(_, sequence)| *sequence)?
+                .0
+        };
+
+        self.tasks.get_mut(&source)?.pop_front().map(|(_, job)| job)
+    }
+}
+
+fn clamp_timeout(timeout: f64, nesting_level: u32) -> f64 {
+    // NaN and negative timeouts are treated as 0
+    let timeout = if timeout > 0.0 { timeout } else { 0.0 };
+
+    if nesting_level > MAX_TIMER_NESTING_LEVEL && timeout < MIN_NESTED_TIMEOUT {
+        MIN_NESTED_TIMEOUT
+    } else {
+        timeout
+    }
+}
+
+/// Errors of tasks are reported, they don't stop the event loop
+fn report(result: Result<()>) {
+    if let Err(e) = result {
+        warn!("Uncaught error in event loop task: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal() -> (Rc<RefCell<Vec<String>>>, impl Fn(&str) + Clone) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let push = {
+            let log = Rc::clone(&log);
+            move |entry: &str| log.borrow_mut().push(entry.to_string())
+        };
+
+        (log, push)
+    }
+
+    #[test]
+    fn microtasks_run_after_each_task() {
+        let event_loop = EventLoop::new();
+        let (log, push) = journal();
+
+        for name in ["a", "b"] {
+            let el = event_loop.clone();
+            let push = push.clone();
+
+            event_loop.queue_task(TaskSource::DomManipulation, move || {
+                push(&format!("task {name}"));
+
+                let push = push.clone();
+                el.queue_microtask(move || {
+                    push(&format!("microtask {name}"));
+                    Ok(())
+                });
+
+                Ok(())
+            });
+        }
+
+        assert_eq!(event_loop.tick(0.0), 2);
+        assert_eq!(*log.borrow(), ["task a", "microtask a", "task b", "microtask b"]);
+        assert!(!event_loop.has_pending_tasks());
+    }
+
+    #[test]
+    fn user_interaction_runs_first() {
+        let event_loop = EventLoop::new();
+        let (log, push) = journal();
+
+        let p = push.clone();
+        event_loop.queue_task(TaskSource::Networking, move || {
+            p("network");
+            Ok(())
+        });
+        event_loop.queue_task(TaskSource::UserInteraction, move || {
+            push("click");
+            Ok(())
+        });
+
+        event_loop.tick(0.0);
+
+        assert_eq!(*log.borrow(), ["click", "network"]);
+    }
+
+    #[test]
+    fn timers_run_in_order_of_their_deadline() {
+        let event_loop = EventLoop::new();
+        let (log, push) = journal();
+
+        let p = push.clone();
+        event_loop.set_timeout(
+            move || {
+                p("20");
+                Ok(())
+            },
+            20.0,
+        );
+        let p = push.clone();
+        event_loop.set_timeout(
+            move || {
+                p("10");
+                Ok(())
+            },
+            10.0,
+        );
+        let cleared = event_loop.set_timeout(
+            move || {
+                push("cleared");
+                Ok(())
+            },
+            5.0,
+        );
+
+        event_loop.clear_timer(cleared);
+
+        assert_eq!(event_loop.next_timer_deadline(), Some(10.0));
+
+        event_loop.tick(9.0);
+        assert!(log.borrow().is_empty());
+
+        event_loop.tick(25.0);
+        assert_eq!(*log.borrow(), ["10", "20"]);
+        assert_eq!(event_loop.next_timer_deadline(), None);
+    }
+
+    #[test]
+    fn nested_timers_are_clamped() {
+        let event_loop = EventLoop::new();
+        let runs = Rc::new(RefCell::new(0));
+
+        fn schedule(event_loop: &EventLoop, runs: &Rc<RefCell<u32>>) {
+            let el = event_loop.clone();
+            let runs = Rc::clone(runs);
+
+            event_loop.set_timeout(
+                move || {
+                    *runs.borrow_mut() += 1;
+                    schedule(&el, &runs);
+                    Ok(())
+                },
+                0.0,
+            );
+        }
+
+        schedule(&event_loop, &runs);
+
+        // Timers that are created by the first five nested timers run without delay
+        for _ in 0..6 {
+            event_loop.tick(0.0);
+        }
+
+        assert_eq!(*runs.borrow(), 6);
+        assert_eq!(event_loop.next_timer_deadline(), Some(4.0));
+    }
+
+    #[test]
+    fn intervals_repeat_until_cleared() {
+        let event_loop = EventLoop::new();
+        let runs = Rc::new(RefCell::new(0));
+
+        let r = Rc::clone(&runs);
+        let id = event_loop.set_interval(
+            move || {
+                *r.borrow_mut() += 1;
+                Ok(())
+            },
+            10.0,
+        );
+
+        event_loop.tick(10.0);
+        event_loop.tick(20.0);
+        assert_eq!(*runs.borrow(), 2);
+        assert_eq!(event_loop.next_timer_deadline(), Some(30.0));
+
+        event_loop.clear_timer(id);
+        event_loop.tick(30.0);
+        assert_eq!(*runs.borrow(), 2);
+    }
+
+    #[test]
+    fn animation_frames_requested_in_a_frame_run_in_the_next_frame() {
+        let event_loop = EventLoop::new();
+        let (log, push) = journal();
+
+        let el = event_loop.clone();
+        event_loop.request_animation_frame(move |time| {
+            push(&format!("frame {time}"));
+
+            let push = push.clone();
+            el.request_animation_frame(move |time| {
+                push(&format!("next frame {time}"));
+                Ok(())
+            });
+
+            Ok(())
+        });
+
+        event_loop.run_animation_frames(16.0);
+        assert_eq!(*log.borrow(), ["frame 16"]);
+        assert!(event_loop.has_animation_frames());
+
+        event_loop.run_animation_frames(32.0);
+        assert_eq!(*log.borrow(), ["frame 16", "next frame 32"]);
+        assert!(!event_loop.has_animation_frames());
+    }
+}
@@ -3,20 +3,30 @@ use thiserror::Error;
 pub use array::*;
 pub use compile::*;
 pub use context::*;
+pub use exception::*;
 pub use function::*;
+pub use idl::*;
 pub use interop::*;
+pub use module::*;
 pub use object::*;
+pub use promise::*;
 pub use runtime::*;
+pub use script::*;
 pub use value::*;
 pub use value_conversion::*;
 
 mod array;
 mod compile;
 mod context;
+mod exception;
 mod function;
+mod idl;
 mod interop;
+mod module;
 mod object;
+mod promise;
 mod runtime;
+mod script;
 mod value;
 mod value_conversion;
 
@@ -40,8 +50,11 @@ pub enum JSError {
     #[error("execution error: {0}")]
     Execution(String),
 
+    #[error("module error: {0}")]
+    Module(String),
+
     #[error("exception: {0}")]
-    Exception(String),
+    Exception(JSException),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -54,5 +67,74 @@ pub enum JSType {
     Object,
     Array,
     Function,
+    BigInt,
+    Symbol,
+    Date,
+    Promise,
+    ArrayBuffer,
+    TypedArray,
     Other(String),
 }
+
+/// Element types of typed arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypedArrayKind {
+    Int8,
+    Uint8,
+    Uint8Clamped,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+    BigInt64,
+    BigUint64,
+}
+
+impl TypedArrayKind {
+    pub const ALL: [TypedArrayKind; 11] = [
+        Self::Int8,
+        Self::Uint8,
+        Self::Uint8Clamped,
+        Self::Int16,
+        Self::Uint16,
+        Self::Int32,
+        Self::Uint32,
+        Self::Float32,
+        Self::Float64,
+        Self::BigInt64,
+        Self::BigUint64,
+    ];
+
+    /// Name of the javascript constructor, like `Uint8Array`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Int8 => "Int8Array",
+            Self::Uint8 => "Uint8Array",
+            Self::Uint8Clamped => "Uint8ClampedArray",
+            Self::Int16 => "Int16Array",
+            Self::Uint16 => "Uint16Array",
+            Self::Int32 => "Int32Array",
+            Self::Uint32 => "Uint32Array",
+            Self::Float32 => "Float32Array",
+            Self::Float64 => "Float64Array",
+            Self::BigInt64 => "BigInt64Array",
+            Self::BigUint64 => "BigUint64Array",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Size of a single element in bytes
+    pub fn element_size(self) -> usize {
+        match self {
+            Self::Int8 | Self::Uint8 | Self::Uint8Clamped => 1,
+            Self::Int16 | Self::Uint16 => 2,
+            Self::Int32 | Self::Uint32 | Self::Float32 => 4,
+            Self::Float64 | Self::BigInt64 | Self::BigUint64 => 8,
+        }
+    }
+}
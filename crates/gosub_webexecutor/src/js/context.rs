@@ -1,6 +1,10 @@
+use std::rc::Rc;
+
+use url::Url;
+
 use gosub_shared::types::Result;
 
-use crate::js::JSRuntime;
+use crate::js::{JSRuntime, ModuleLoader};
 
 //main trait for JS context (can be implemented for different JS engines like V8, SpiderMonkey, JSC, etc.)
 pub trait JSContext: Clone {
@@ -14,6 +18,19 @@ pub trait JSContext: Clone {
         compiled: &mut <Self::RT as JSRuntime>::Compiled,
     ) -> Result<<Self::RT as JSRuntime>::Value>;
 
+    /// Runs the pending promise jobs. Engines do this when a script is done, but promises that are settled from
+    /// rust outside of a script need it to call their callbacks.
+    fn run_microtasks(&mut self);
+
+    /// Sets the loader that resolves and fetches imports, for module scripts and dynamic `import()`
+    fn set_module_loader(&mut self, loader: Rc<ModuleLoader>);
+
+    /// Fetches the module graph of the module at `url` with the given source, links and evaluates it. `url` is `None`
+    /// for an inline module script, which resolves its imports against the document and can't be imported itself.
+    /// The promise settles when the evaluation is done, which can be later than this returns because of top-level
+    /// `await`.
+    fn run_module(&mut self, url: Option<&Url>, source: &str) -> Result<<Self::RT as JSRuntime>::Promise>;
+
     // fn compile_stream(&self, code: &str) -> Result<()>;
 
     fn new_global_object(&mut self, name: &str) -> Result<<Self::RT as JSRuntime>::Object>;
@@ -0,0 +1,71 @@
+use core::fmt::{Display, Formatter};
+
+/// Details of an exception that was thrown by javascript and not caught
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JSException {
+    /// Name of the error, like `TypeError`. Empty when the thrown value isn't an error object
+    pub name: String,
+    /// Message of the error, or the thrown value as a string when it isn't an error object
+    pub message: String,
+    /// The `stack` property of the error, when the engine provides one
+    pub stack: Option<String>,
+    /// Name of the script in which the exception was thrown
+    pub resource: Option<String>,
+    /// Source code of the line on which the exception was thrown
+    pub source_line: Option<String>,
+    /// Line on which the exception was thrown, starting at 1
+    pub line: Option<usize>,
+    /// Column on which the exception was thrown, starting at 1
+    pub column: Option<usize>,
+}
+
+impl JSException {
+    pub fn new(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for JSException {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "{}", self.message)?;
+        } else {
+            write!(f, "{}: {}", self.name, self.message)?;
+        }
+
+        if let Some(line) = self.line {
+            let resource = self.resource.as_deref().unwrap_or("<anonymous>");
+            write!(f, "\n    at {resource}:{line}")?;
+            if let Some(column) = self.column {
+                write!(f, ":{column}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let mut exception = JSException::new("TypeError", "x is not a function");
+        assert_eq!(exception.to_string(), "TypeError: x is not a function");
+
+        exception.line = Some(3);
+        exception.column = Some(7);
+        assert_eq!(
+            exception.to_string(),
+            "TypeError: x is not a function\n    at <anonymous>:3:7"
+        );
+
+        let exception = JSException::new("", "thrown string");
+        assert_eq!(exception.to_string(), "thrown string");
+    }
+}
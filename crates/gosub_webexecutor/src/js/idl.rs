@@ -0,0 +1,352 @@
+//! Conversions from javascript values to rust values that follow the WebIDL rules. These are used by the code
+//! that `gosub_webinterop::web_idl!` generates.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use gosub_shared::types::Result;
+
+use crate::js::{JSArray, JSError, JSGetterCallback, JSObject, JSRuntime, JSSetterCallback, JSValue};
+use crate::Error;
+
+/// Conversion of a javascript value to a rust value, implemented by the dictionaries and enums generated from WebIDL
+pub trait FromIdl<V: JSValue>: Sized {
+    /// Whether the value has the right javascript type, used to choose between overloads
+    fn is_match(value: &V) -> bool;
+
+    fn from_idl(value: &V) -> Result<Self>;
+}
+
+/// How a number is converted to an integer type, selected with the `[EnforceRange]` and `[Clamp]` extended attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerConversion {
+    /// Values outside the range of the type wrap around
+    Modulo,
+    /// Values outside the range of the type (and non-finite values) are an error
+    EnforceRange,
+    /// Values are clamped to the range of the type and rounded to the nearest integer
+    Clamp,
+}
+
+/// Integer types that WebIDL integer types are converted to
+pub trait IdlInteger: Sized {
+    const BITS: i32;
+    const MIN: f64;
+    const MAX: f64;
+
+    /// Converts the lowest `BITS` bits, interpreted as two's complement for signed types
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_idl_integer {
+    ($type:ty, $bits:expr, $min:expr, $max:expr) => {
+        impl IdlInteger for $type {
+            const BITS: i32 = $bits;
+            const MIN: f64 = $min;
+            const MAX: f64 = $max;
+
+            fn from_bits(bits: u64) -> Self {
+                bits as $type
+            }
+        }
+    };
+}
+
+/// `long long` and `unsigned long long` are limited to the integers that a javascript number represents exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+impl_idl_integer!(i8, 8, i8::MIN as f64, i8::MAX as f64);
+impl_idl_integer!(u8, 8, 0.0, u8::MAX as f64);
+impl_idl_integer!(i16, 16, i16::MIN as f64, i16::MAX as f64);
+impl_idl_integer!(u16, 16, 0.0, u16::MAX as f64);
+impl_idl_integer!(i32, 32, i32::MIN as f64, i32::MAX as f64);
+impl_idl_integer!(u32, 32, 0.0, u32::MAX as f64);
+impl_idl_integer!(i64, 64, -MAX_SAFE_INTEGER, MAX_SAFE_INTEGER);
+impl_idl_integer!(u64, 64, 0.0, MAX_SAFE_INTEGER);
+
+/// The error for a value that can't be converted, thrown as a `TypeError`
+pub fn idl_type_error(message: impl Into<String>) -> anyhow::Error {
+    Error::JS(JSError::Conversion(message.into())).into()
+}
+
+/// Converts a value to an integer type (`byte`, `octet`, `short`, `long`, ...)
+pub fn idl_integer<T: IdlInteger, V: JSValue>(value: &V, conversion: IntegerConversion) -> Result<T> {
+    let x = value.as_number()?;
+
+    match conversion {
+        IntegerConversion::EnforceRange => {
+            let x = x.trunc();
+            if !x.is_finite() || x < T::MIN || x > T::MAX {
+                return Err(idl_type_error(format!("{x} is outside the range of the integer type")));
+            }
+
+            Ok(T::from_bits(x as i64 as u64))
+        }
+        IntegerConversion::Clamp => {
+            if x.is_nan() {
+                return Ok(T::from_bits(0));
+            }
+
+            Ok(T::from_bits(x.clamp(T::MIN, T::MAX).round_ties_even() as i64 as u64))
+        }
+        IntegerConversion::Modulo => {
+            if !x.is_finite() {
+                return Ok(T::from_bits(0));
+            }
+
+            Ok(T::from_bits(x.trunc().rem_euclid(2f64.powi(T::BITS)) as u64))
+        }
+    }
+}
+
+/// Converts a value to `double`, non-finite values are only allowed for `unrestricted double`
+pub fn idl_double<V: JSValue>(value: &V, unrestricted: bool) -> Result<f64> {
+    let x = value.as_number()?;
+
+    if !unrestricted && !x.is_finite() {
+        return Err(idl_type_error(format!("{x} is not a finite number")));
+    }
+
+    Ok(x)
+}
+
+/// Converts a value to `float`, non-finite values are only allowed for `unrestricted float`
+pub fn idl_float<V: JSValue>(value: &V, unrestricted: bool) -> Result<f32> {
+    let x = value.as_number()?;
+    let y = x as f32;
+
+    if !unrestricted && !y.is_finite() {
+        return Err(idl_type_error(format!("{x} is not a finite float")));
+    }
+
+    Ok(y)
+}
+
+pub fn idl_boolean<V: JSValue>(value: &V) -> Result<bool> {
+    value.as_bool()
+}
+
+/// Converts a value to `DOMString` or `USVString`
+pub fn idl_string<V: JSValue>(value: &V) -> Result<String> {
+    if value.is_symbol() {
+        return Err(idl_type_error("a symbol can't be converted to a string"));
+    }
+
+    value.as_string()
+}
+
+/// Converts a value to `ByteString`, which only allows characters up to U+00FF
+pub fn idl_byte_string<V: JSValue>(value: &V) -> Result<String> {
+    let string = idl_string(value)?;
+
+    if string.chars().any(|c| c as u32 > 0xFF) {
+        return Err(idl_type_error(format!("{string:?} is not a byte string")));
+    }
+
+    Ok(string)
+}
+
+/// Converts a value to `object`
+pub fn idl_object<V: JSValue>(value: &V) -> Result<<V::RT as JSRuntime>::Object> {
+    if !value.is_object() {
+        return Err(idl_type_error("value is not an object"));
+    }
+
+    value.as_object()
+}
+
+/// Converts a value to a dictionary, `null` and `undefined` are an empty dictionary and give `None`
+pub fn idl_dictionary<V: JSValue>(value: &V) -> Result<Option<<V::RT as JSRuntime>::Object>> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(None);
+    }
+
+    if !value.is_object() {
+        return Err(idl_type_error("value is not a dictionary"));
+    }
+
+    value.as_object().map(Some)
+}
+
+/// Converts a value to `sequence<T>`, converting every element with `f`
+pub fn idl_sequence<V, T>(value: &V, f: impl Fn(V) -> Result<T>) -> Result<Vec<T>>
+where
+    V: JSValue,
+    V::RT: JSRuntime<Value = V>,
+{
+    if !value.is_array() {
+        return Err(idl_type_error("value is not a sequence"));
+    }
+
+    value.as_array()?.as_vec().into_iter().map(f).collect()
+}
+
+/// Converts a value to `T?`, where both `null` and `undefined` are `None`
+pub fn idl_nullable<V: JSValue, T>(value: &V, f: impl FnOnce(&V) -> Result<T>) -> Result<Option<T>> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(None);
+    }
+
+    f(value).map(Some)
+}
+
+/// Name of the property that holds the id of the rust value behind a platform object
+const BRAND: &str = "__gosub_brand";
+
+/// The rust value behind a platform object, and the interfaces the object implements
+struct Brand {
+    value: Weak<dyn Any>,
+    interfaces: &'static [&'static str],
+}
+
+#[derive(Default)]
+struct Brands {
+    brands: HashMap<u64, Brand>,
+    next_id: u64,
+    /// Number of brands at which the brands of collected objects are removed
+    prune_at: usize,
+}
+
+thread_local! {
+    static BRANDS: RefCell<Brands> = RefCell::new(Brands::default());
+}
+
+/// Defines the members of an interface on a new object, for the rust value that backs it
+pub type DefinePlatformObject<RT> =
+    fn(&mut <RT as JSRuntime>::Object, Rc<dyn Any>, <RT as JSRuntime>::Context) -> Result<()>;
+
+enum Wrapper<RT: JSRuntime> {
+    /// The object that was passed from javascript
+    Object(RT::Object),
+    /// The value hasn't been wrapped yet
+    Define(DefinePlatformObject<RT>),
+}
+
+/// An object of a WebIDL interface that is backed by a rust value (a platform object). Values of interface types are
+/// converted to this type, the `wrap_foo` function of interface `Foo` creates one from the rust value.
+pub struct PlatformObject<RT: JSRuntime> {
+    value: Rc<dyn Any>,
+    /// The interface and the interfaces it inherits from
+    interfaces: &'static [&'static str],
+    wrapper: Wrapper<RT>,
+}
+
+impl<RT: JSRuntime> PlatformObject<RT> {
+    pub fn new<T: 'static>(
+        value: Rc<RefCell<T>>,
+        interfaces: &'static [&'static str],
+        define: DefinePlatformObject<RT>,
+    ) -> Self {
+        Self {
+            value,
+            interfaces,
+            wrapper: Wrapper::Define(define),
+        }
+    }
+
+    /// Returns the rust value if it has type `T`
+    pub fn downcast<T: 'static>(&self) -> Option<Rc<RefCell<T>>> {
+        Rc::clone(&self.value).downcast::<RefCell<T>>().ok()
+    }
+
+    pub fn implements(&self, interface: &str) -> bool {
+        self.interfaces.contains(&interface)
+    }
+
+    /// Converts the object to a javascript value. An object that came from javascript is returned as is, otherwise a
+    /// new object is created with the members of the interface.
+    pub fn into_js(self, ctx: RT::Context) -> Result<RT::Value> {
+        let define = match self.wrapper {
+            Wrapper::Object(object) => return Ok(object.into()),
+            Wrapper::Define(define) => define,
+        };
+
+        let mut object = RT::Value::new_object(ctx.clone())?;
+        define(&mut object, Rc::clone(&self.value), ctx)?;
+
+        let id = BRANDS.with_borrow_mut(|brands| {
+            if brands.brands.len() >= brands.prune_at {
+                brands.brands.retain(|_, brand| brand.value.strong_count() > 0);
+                brands.prune_at = (brands.brands.len() * 2).max(64);
+            }
+
+            brands.next_id += 1;
+            brands.brands.insert(
+                brands.next_id,
+                Brand {
+                    value: Rc::downgrade(&self.value),
+                    interfaces: self.interfaces,
+                },
+            );
+
+            brands.next_id
+        });
+
+        // The getter keeps the rust value alive for as long as the object lives
+        let value = self.value;
+        object.set_property_accessor(
+            BRAND,
+            Box::new(move |cb: &mut RT::GetterCB| {
+                let _ = &value;
+                match RT::Value::new_number(cb.context().clone(), id as f64) {
+                    Ok(id) => cb.ret(id),
+                    Err(e) => cb.error(e),
+                }
+            }),
+            Box::new(|cb: &mut RT::SetterCB| cb.error(idl_type_error(format!("{BRAND} is read-only")))),
+        )?;
+
+        Ok(object.into())
+    }
+}
+
+/// Whether the value is an object that implements the interface, used to choose between overloads
+pub fn idl_implements<V: JSValue>(value: &V, interface: &str) -> bool {
+    brand(value).is_some_and(|(_, interfaces)| interfaces.contains(&interface))
+}
+
+/// Returns the rust value behind a platform object, and the interfaces the object implements
+fn brand<V: JSValue>(value: &V) -> Option<(Rc<dyn Any>, &'static [&'static str])> {
+    if !value.is_object() {
+        return None;
+    }
+
+    let id = value.as_object().ok()?.get_property(BRAND).ok()?;
+    if !id.is_number() {
+        return None;
+    }
+    let id = id.as_number().ok()? as u64;
+
+    BRANDS.with_borrow(|brands| {
+        let brand = brands.brands.get(&id)?;
+        Some((brand.value.upgrade()?, brand.interfaces))
+    })
+}
+
+/// Converts a value to an interface type. The value has to be an object that was created by the bindings of the
+/// interface, or of an interface that inherits from it.
+pub fn idl_interface<V: JSValue>(value: &V, interface: &str) -> Result<PlatformObject<V::RT>> {
+    match brand(value) {
+        Some((rust_value, interfaces)) if interfaces.contains(&interface) => Ok(PlatformObject {
+            value: rust_value,
+            interfaces,
+            wrapper: Wrapper::Object(value.as_object()?),
+        }),
+        _ => Err(idl_type_error(format!("value is not a {interface}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_bounds() {
+        assert_eq!(<u8 as IdlInteger>::MAX, 255.0);
+        assert_eq!(<i64 as IdlInteger>::MIN, -9_007_199_254_740_991.0);
+        assert_eq!(i8::from_bits(200), -56);
+        assert_eq!(u16::from_bits(0x1_0001), 1);
+    }
+}
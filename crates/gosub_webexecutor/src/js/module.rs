@@ -0,0 +1,360 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use url::Url;
+
+use gosub_shared::traits::document::ScriptKind;
+use gosub_shared::types::Result;
+
+use crate::js::JSError;
+use crate::Error;
+
+/// Fetches the source of external scripts and modules. The fetching is synchronous: modules are fetched while the
+/// module graph is built.
+pub trait ScriptFetcher {
+    /// Fetches a script of the given kind, modules are fetched in cors mode and classic scripts in no-cors mode
+    fn fetch(&self, url: &Url, kind: ScriptKind) -> Result<String>;
+}
+
+/// A specifier map of an import map, sorted so that longer prefixes come first. Entries without a URL are blocked.
+type SpecifierMap = Vec<(String, Option<Url>)>;
+
+/// Import map of a document (<https://html.spec.whatwg.org/multipage/webappapis.html#import-maps>), which remaps
+/// module specifiers to URLs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    /// Specifier maps by scope prefix, sorted so that more specific scopes come first
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+    /// Parses the JSON text of an import map. Relative URLs are resolved against `base`.
+    pub fn parse(json: &str, base: &Url) -> Result<Self> {
+        let Value::Object(map) = serde_json::from_str(json)? else {
+            return Err(module_error("import map must be a JSON object"));
+        };
+
+        let imports = match map.get("imports") {
+            Some(Value::Object(imports)) => specifier_map(imports, base),
+            Some(_) => return Err(module_error("\"imports\" of an import map must be a JSON object")),
+            None => Vec::new(),
+        };
+
+        let mut scopes = Vec::new();
+        match map.get("scopes") {
+            Some(Value::Object(entries)) => {
+                for (prefix, imports) in entries {
+                    let Value::Object(imports) = imports else {
+                        return Err(module_error(format!(
+                            "scope {prefix:?} of an import map must be a JSON object"
+                        )));
+                    };
+
+                    match base.join(prefix) {
+                        Ok(prefix) => scopes.push((prefix.to_string(), specifier_map(imports, base))),
+                        Err(_) => log::warn!("ignoring invalid import map scope {prefix:?}"),
+                    }
+                }
+            }
+            Some(_) => return Err(module_error("\"scopes\" of an import map must be a JSON object")),
+            None => {}
+        }
+        scopes.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        Ok(Self { imports, scopes })
+    }
+
+    /// Adds the entries of `other` that don't change how already known specifiers resolve
+    pub fn merge(&mut self, other: ImportMap) {
+        merge_specifier_map(&mut self.imports, other.imports);
+
+        for (prefix, imports) in other.scopes {
+            match self.scopes.iter_mut().find(|(existing, _)| *existing == prefix) {
+                Some((_, existing)) => merge_specifier_map(existing, imports),
+                None => self.scopes.push((prefix, imports)),
+            }
+        }
+        self.scopes.sort_by(|(a, _), (b, _)| b.cmp(a));
+    }
+
+    /// Resolves a module specifier that is imported from `base`
+    /// (<https://html.spec.whatwg.org/multipage/webappapis.html#resolve-a-module-specifier>)
+    pub fn resolve(&self, specifier: &str, base: &Url) -> Result<Url> {
+        let as_url = parse_url_like(specifier, base);
+        let normalized = as_url.as_ref().map_or(specifier, Url::as_str);
+
+        for (prefix, imports) in &self.scopes {
+            if prefix == base.as_str() || (prefix.ends_with('/') && base.as_str().starts_with(prefix.as_str())) {
+                if let Some(url) = resolve_imports_match(normalized, as_url.as_ref(), imports)? {
+                    return Ok(url);
+                }
+            }
+        }
+
+        if let Some(url) = resolve_imports_match(normalized, as_url.as_ref(), &self.imports)? {
+            return Ok(url);
+        }
+
+        as_url.ok_or_else(|| {
+            module_error(format!(
+                "failed to resolve module specifier {specifier:?}, relative specifiers must start with \"./\", \"../\" or \"/\""
+            ))
+        })
+    }
+}
+
+fn module_error(message: impl Into<String>) -> anyhow::Error {
+    Error::JS(JSError::Module(message.into())).into()
+}
+
+/// Parses a specifier that is a URL, or a path starting with `/`, `./` or `../`
+fn parse_url_like(specifier: &str, base: &Url) -> Option<Url> {
+    if specifier.starts_with('/') || specifier.starts_with("./") || specifier.starts_with("../") {
+        return base.join(specifier).ok();
+    }
+
+    Url::parse(specifier).ok()
+}
+
+fn specifier_map(entries: &Map<String, Value>, base: &Url) -> SpecifierMap {
+    let mut map = SpecifierMap::new();
+
+    for (key, value) in entries {
+        if key.is_empty() {
+            continue;
+        }
+
+        let key = parse_url_like(key, base).map_or_else(|| key.clone(), String::from);
+        let url = match value {
+            Value::String(address) => parse_url_like(address, base),
+            _ => None,
+        };
+
+        let url = match url {
+            Some(url) if key.ends_with('/') && !url.as_str().ends_with('/') => {
+                log::warn!("import map address of {key:?} must end with a slash, as the key does");
+                None
+            }
+            Some(url) => Some(url),
+            None => {
+                log::warn!("import map address of {key:?} is not a valid URL");
+                None
+            }
+        };
+
+        map.push((key, url));
+    }
+
+    map.sort_by(|(a, _), (b, _)| b.cmp(a));
+    map
+}
+
+fn merge_specifier_map(map: &mut SpecifierMap, other: SpecifierMap) {
+    for (key, url) in other {
+        if !map.iter().any(|(existing, _)| *existing == key) {
+            map.push((key, url));
+        }
+    }
+    map.sort_by(|(a, _), (b, _)| b.cmp(a));
+}
+
+fn resolve_imports_match(normalized: &str, as_url: Option<&Url>, imports: &SpecifierMap) -> Result<Option<Url>> {
+    for (key, url) in imports {
+        let is_prefix =
+            key.ends_with('/') && normalized.starts_with(key.as_str()) && as_url.iter().all(|url| is_special(url));
+
+        if key != normalized && !is_prefix {
+            continue;
+        }
+
+        let Some(url) = url else {
+            return Err(module_error(format!(
+                "resolving {normalized:?} is blocked by the import map"
+            )));
+        };
+
+        if key == normalized {
+            return Ok(Some(url.clone()));
+        }
+
+        // prefix match, the rest of the specifier is resolved against the address, but may not escape from it
+        match url.join(&normalized[key.len()..]) {
+            Ok(resolved) if resolved.as_str().starts_with(url.as_str()) => return Ok(Some(resolved)),
+            _ => {
+                return Err(module_error(format!(
+                    "resolving {normalized:?} with the import map entry for {key:?} leads outside of {url}"
+                )))
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_special(url: &Url) -> bool {
+    matches!(url.scheme(), "ftp" | "file" | "http" | "https" | "ws" | "wss")
+}
+
+/// Resolves and fetches the modules of a document. The engines use it to build module graphs, for module scripts
+/// and for dynamic `import()`.
+pub struct ModuleLoader {
+    /// URL of the document, which is used to resolve imports from classic scripts
+    base: Url,
+    import_map: RefCell<ImportMap>,
+    fetcher: Box<dyn ScriptFetcher>,
+    /// Sources by URL, so every module is fetched only once
+    sources: RefCell<HashMap<Url, String>>,
+}
+
+impl ModuleLoader {
+    pub fn new(base: Url, fetcher: impl ScriptFetcher + 'static) -> Self {
+        Self {
+            base,
+            import_map: RefCell::new(ImportMap::default()),
+            fetcher: Box::new(fetcher),
+            sources: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    /// Adds the import map of a `<script type="importmap">` element
+    pub fn add_import_map(&self, json: &str) -> Result<()> {
+        let import_map = ImportMap::parse(json, &self.base)?;
+        self.import_map.borrow_mut().merge(import_map);
+
+        Ok(())
+    }
+
+    /// Resolves a specifier that is imported by the module at `referrer`, or by a classic script when it is `None`
+    pub fn resolve(&self, specifier: &str, referrer: Option<&Url>) -> Result<Url> {
+        self.import_map
+            .borrow()
+            .resolve(specifier, referrer.unwrap_or(&self.base))
+    }
+
+    /// Returns the source of the module at `url`, which is fetched the first time it is needed
+    pub fn fetch(&self, url: &Url) -> Result<String> {
+        if let Some(source) = self.sources.borrow().get(url) {
+            return Ok(source.clone());
+        }
+
+        let source = self.fetcher.fetch(url, ScriptKind::Module)?;
+        self.sources.borrow_mut().insert(url.clone(), source.clone());
+
+        Ok(source)
+    }
+
+    /// Returns the source of the classic script at `url`. It isn't kept, as a classic script can't be imported and
+    /// is fetched without the CORS check of modules.
+    pub fn fetch_classic(&self, url: &Url) -> Result<String> {
+        self.fetcher.fetch(url, ScriptKind::Classic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_without_import_map() {
+        let base = Url::parse("https://example.com/app/index.html").unwrap();
+        let map = ImportMap::default();
+
+        assert_eq!(
+            map.resolve("./lib/a.js", &base).unwrap().as_str(),
+            "https://example.com/app/lib/a.js"
+        );
+        assert_eq!(
+            map.resolve("../b.js", &base).unwrap().as_str(),
+            "https://example.com/b.js"
+        );
+        assert_eq!(
+            map.resolve("/c.js", &base).unwrap().as_str(),
+            "https://example.com/c.js"
+        );
+        assert_eq!(
+            map.resolve("https://cdn.example.com/d.js", &base).unwrap().as_str(),
+            "https://cdn.example.com/d.js"
+        );
+        assert!(map.resolve("lodash", &base).is_err());
+    }
+
+    #[test]
+    fn resolve_with_import_map() {
+        let base = Url::parse("https://example.com/app/index.html").unwrap();
+        let map = ImportMap::parse(
+            r#"{
+                "imports": {
+                    "lodash": "/vendor/lodash.js",
+                    "lodash/": "/vendor/lodash/",
+                    "./old.js": "./new.js",
+                    "blocked": 5
+                },
+                "scopes": {
+                    "/app/legacy/": { "lodash": "/vendor/lodash-3.js" }
+                }
+            }"#,
+            &base,
+        )
+        .unwrap();
+
+        let resolve = |specifier: &str, base: &Url| map.resolve(specifier, base).map(String::from);
+
+        assert_eq!(
+            resolve("lodash", &base).unwrap(),
+            "https://example.com/vendor/lodash.js"
+        );
+        assert_eq!(
+            resolve("lodash/fp.js", &base).unwrap(),
+            "https://example.com/vendor/lodash/fp.js"
+        );
+        assert_eq!(resolve("./old.js", &base).unwrap(), "https://example.com/app/new.js");
+        assert!(resolve("lodash/../../secret.js", &base).is_err());
+        assert!(resolve("blocked", &base).is_err());
+
+        let legacy = Url::parse("https://example.com/app/legacy/main.js").unwrap();
+        assert_eq!(
+            resolve("lodash", &legacy).unwrap(),
+            "https://example.com/vendor/lodash-3.js"
+        );
+        assert_eq!(
+            resolve("lodash/fp.js", &legacy).unwrap(),
+            "https://example.com/vendor/lodash/fp.js"
+        );
+    }
+
+    #[test]
+    fn merge_import_maps() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let mut map = ImportMap::parse(r#"{ "imports": { "a": "/a-1.js" } }"#, &base).unwrap();
+        map.merge(ImportMap::parse(r#"{ "imports": { "a": "/a-2.js", "b": "/b.js" } }"#, &base).unwrap());
+
+        assert_eq!(map.resolve("a", &base).unwrap().as_str(), "https://example.com/a-1.js");
+        assert_eq!(map.resolve("b", &base).unwrap().as_str(), "https://example.com/b.js");
+    }
+
+    #[test]
+    fn loader_fetches_once() {
+        struct Counter(std::rc::Rc<std::cell::Cell<u32>>);
+
+        impl ScriptFetcher for Counter {
+            fn fetch(&self, url: &Url, _kind: ScriptKind) -> Result<String> {
+                self.0.set(self.0.get() + 1);
+                Ok(format!("export default {:?}", url.path()))
+            }
+        }
+
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let loader = ModuleLoader::new(Url::parse("https://example.com/").unwrap(), Counter(count.clone()));
+
+        let url = loader.resolve("./a.js", None).unwrap();
+        assert_eq!(loader.fetch(&url).unwrap(), r#"export default "/a.js""#);
+        assert_eq!(loader.fetch(&url).unwrap(), r#"export default "/a.js""#);
+        assert_eq!(count.get(), 1);
+    }
+}
@@ -11,6 +11,9 @@ pub trait JSObject: Into<<Self::RT as JSRuntime>::Value> {
 
     fn get_property(&self, name: &str) -> Result<<Self::RT as JSRuntime>::Value>;
 
+    /// Returns the names of the own enumerable properties of the object, like `Object.keys`
+    fn keys(&self) -> Result<Vec<String>>;
+
     fn call_method(
         &self,
         name: &str,
@@ -0,0 +1,39 @@
+use gosub_shared::types::Result;
+
+use crate::js::JSRuntime;
+
+/// State of a promise, with the value it settled with
+pub enum PromiseState<V> {
+    Pending,
+    Fulfilled(V),
+    Rejected(V),
+}
+
+pub trait JSPromise: Into<<Self::RT as JSRuntime>::Value> {
+    type RT: JSRuntime<Promise = Self>;
+
+    /// Creates a pending promise that is settled from rust with `resolve` or `reject`
+    fn new(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Fulfills the promise. Only promises created with `new` can be settled from rust.
+    fn resolve(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()>;
+
+    /// Rejects the promise. Only promises created with `new` can be settled from rust.
+    fn reject(&self, value: &<Self::RT as JSRuntime>::Value) -> Result<()>;
+
+    fn state(&self) -> PromiseState<<Self::RT as JSRuntime>::Value>;
+
+    /// Calls one of the functions with the value of the promise once it settles, like `promise.then` in javascript.
+    /// Returns a promise for the value the function returns.
+    fn then(
+        &self,
+        on_fulfilled: &<Self::RT as JSRuntime>::Function,
+        on_rejected: &<Self::RT as JSRuntime>::Function,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+
+    fn as_value(&self) -> <Self::RT as JSRuntime>::Value;
+}
@@ -2,7 +2,8 @@ use gosub_shared::types::Result;
 
 use crate::js::{
     Args, JSArray, JSCompiled, JSContext, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic,
-    JSFunctionVariadic, JSGetterCallback, JSObject, JSSetterCallback, JSValue, VariadicArgs, VariadicArgsInternal,
+    JSFunctionVariadic, JSGetterCallback, JSObject, JSPromise, JSSetterCallback, JSValue, VariadicArgs,
+    VariadicArgsInternal,
 };
 
 //trait around the main JS engine (e.g V8, SpiderMonkey, JSC, etc.)
@@ -16,6 +17,7 @@ pub trait JSRuntime {
     type Function: JSFunction<RT = Self>;
     type FunctionVariadic: JSFunctionVariadic<RT = Self>;
     type Array: JSArray<RT = Self>;
+    type Promise: JSPromise<RT = Self>;
     type FunctionCallBack: JSFunctionCallBack<RT = Self>;
     type FunctionCallBackVariadic: JSFunctionCallBackVariadic<RT = Self>;
     type Args: Args<RT = Self>;
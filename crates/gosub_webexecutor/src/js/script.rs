@@ -0,0 +1,96 @@
+use std::rc::Rc;
+
+use log::warn;
+use url::Url;
+
+use gosub_shared::traits::document::{Script, ScriptKind, ScriptSource, ScriptTiming};
+use gosub_shared::types::Result;
+
+use crate::js::{JSContext, JSError, JSPromise, JSRuntime, JSValue, ModuleLoader, PromiseState};
+use crate::Error;
+
+/// Runs the scripts that the parser found in a document
+/// (<https://html.spec.whatwg.org/multipage/scripting.html#script-processing-model>).
+///
+/// Blocking scripts run as soon as they are added. Deferred scripts, which includes module scripts without `async`,
+/// run in document order when the document has been parsed, and async scripts run after them. A script that fails is
+/// reported and doesn't stop the other scripts.
+///
+/// The parser doesn't pause for blocking scripts yet, so the scripts are taken from the document with
+/// `Document::take_scripts` once it has been parsed, and `finish` is called right after that.
+pub struct ScriptRunner<RT: JSRuntime> {
+    context: RT::Context,
+    loader: Rc<ModuleLoader>,
+    deferred: Vec<Script>,
+    pending_async: Vec<Script>,
+}
+
+impl<RT: JSRuntime> ScriptRunner<RT> {
+    pub fn new(mut context: RT::Context, loader: Rc<ModuleLoader>) -> Self {
+        context.set_module_loader(loader.clone());
+
+        Self {
+            context,
+            loader,
+            deferred: Vec::new(),
+            pending_async: Vec::new(),
+        }
+    }
+
+    /// Adds scripts in document order, as they are taken from the document while it's being parsed
+    pub fn add_scripts(&mut self, scripts: Vec<Script>) {
+        for script in scripts {
+            match script.timing {
+                ScriptTiming::Blocking => self.execute(&script),
+                ScriptTiming::Deferred => self.deferred.push(script),
+                ScriptTiming::Async => self.pending_async.push(script),
+            }
+        }
+    }
+
+    /// Runs the deferred and async scripts, once the document has been parsed
+    pub fn finish(&mut self) {
+        for script in std::mem::take(&mut self.deferred) {
+            self.execute(&script);
+        }
+
+        for script in std::mem::take(&mut self.pending_async) {
+            self.execute(&script);
+        }
+    }
+
+    fn execute(&mut self, script: &Script) {
+        if let Err(err) = self.try_execute(script) {
+            warn!("script of node {:?} failed: {}", script.node_id, err);
+        }
+    }
+
+    fn try_execute(&mut self, script: &Script) -> Result<()> {
+        let (url, source) = match &script.source {
+            ScriptSource::Inline(source) => (None, source.clone()),
+            ScriptSource::External(url) if script.kind == ScriptKind::Module => (Some(url), self.loader.fetch(url)?),
+            ScriptSource::External(url) => (Some(url), self.loader.fetch_classic(url)?),
+        };
+
+        match script.kind {
+            ScriptKind::ImportMap => self.loader.add_import_map(&source),
+            ScriptKind::Classic => self.context.run(&source).map(|_| ()),
+            ScriptKind::Module => self.run_module(url, &source),
+        }
+    }
+
+    fn run_module(&mut self, url: Option<&Url>, source: &str) -> Result<()> {
+        let promise = self.context.run_module(url, source)?;
+        self.context.run_microtasks();
+
+        match promise.state() {
+            PromiseState::Rejected(value) => {
+                let reason = value.as_string().unwrap_or_else(|_| "unknown error".to_owned());
+                let url = url.unwrap_or(self.loader.base());
+                Err(Error::JS(JSError::Module(format!("evaluating {url} failed: {reason}"))).into())
+            }
+            // still waiting for a top-level await, which settles in a later task
+            PromiseState::Pending | PromiseState::Fulfilled(_) => Ok(()),
+        }
+    }
+}
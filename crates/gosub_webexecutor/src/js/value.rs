@@ -1,9 +1,13 @@
 use gosub_shared::types::Result;
 
-use crate::js::{AsArray, IntoJSValue, JSRuntime, JSType};
+use crate::js::{AsArray, IntoJSValue, JSRuntime, JSType, TypedArrayKind};
 
 pub trait JSValue:
-    Sized + From<<Self::RT as JSRuntime>::Object> + From<<Self::RT as JSRuntime>::Array> + AsArray<Runtime = Self::RT>
+    Sized
+    + From<<Self::RT as JSRuntime>::Object>
+    + From<<Self::RT as JSRuntime>::Array>
+    + From<<Self::RT as JSRuntime>::Promise>
+    + AsArray<Runtime = Self::RT>
 where
     Self: Sized,
 {
@@ -19,6 +23,23 @@ where
 
     fn as_array(&self) -> Result<<Self::RT as JSRuntime>::Array>;
 
+    fn as_promise(&self) -> Result<<Self::RT as JSRuntime>::Promise>;
+
+    /// Returns the value of a BigInt, fails when it doesn't fit in an `i64`
+    fn as_bigint(&self) -> Result<i64>;
+
+    /// Returns the description of a symbol
+    fn as_symbol_description(&self) -> Result<Option<String>>;
+
+    /// Returns the time of a Date in milliseconds since the unix epoch
+    fn as_date(&self) -> Result<f64>;
+
+    /// Returns a copy of the bytes of an ArrayBuffer, or of the part of the buffer a view (like a TypedArray) covers
+    fn as_bytes(&self) -> Result<Vec<u8>>;
+
+    /// Returns the element type when the value is a TypedArray
+    fn typed_array_kind(&self) -> Option<TypedArrayKind>;
+
     fn is_string(&self) -> bool;
 
     fn is_number(&self) -> bool;
@@ -35,6 +56,18 @@ where
 
     fn is_function(&self) -> bool;
 
+    fn is_promise(&self) -> bool;
+
+    fn is_bigint(&self) -> bool;
+
+    fn is_symbol(&self) -> bool;
+
+    fn is_date(&self) -> bool;
+
+    fn is_array_buffer(&self) -> bool;
+
+    fn is_typed_array(&self) -> bool;
+
     fn type_of(&self) -> JSType;
 
     fn new_object(ctx: <Self::RT as JSRuntime>::Context) -> Result<<Self::RT as JSRuntime>::Object>;
@@ -55,4 +88,16 @@ where
     fn new_null(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self>;
 
     fn new_undefined(ctx: <Self::RT as JSRuntime>::Context) -> Result<Self>;
+
+    fn new_bigint(ctx: <Self::RT as JSRuntime>::Context, value: i64) -> Result<Self>;
+
+    fn new_symbol(ctx: <Self::RT as JSRuntime>::Context, description: Option<&str>) -> Result<Self>;
+
+    /// Creates a Date from a time in milliseconds since the unix epoch
+    fn new_date(ctx: <Self::RT as JSRuntime>::Context, time: f64) -> Result<Self>;
+
+    fn new_array_buffer(ctx: <Self::RT as JSRuntime>::Context, data: &[u8]) -> Result<Self>;
+
+    /// Creates a TypedArray over a new ArrayBuffer with the given bytes, which must be a whole number of elements
+    fn new_typed_array(ctx: <Self::RT as JSRuntime>::Context, kind: TypedArrayKind, data: &[u8]) -> Result<Self>;
 }
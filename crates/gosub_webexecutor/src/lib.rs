@@ -5,6 +5,7 @@
 
 use thiserror::Error;
 
+pub mod event_loop;
 pub mod js;
 
 #[derive(Debug, Error)]
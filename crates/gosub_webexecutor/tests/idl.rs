@@ -0,0 +1,251 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gosub_boa::BoaEngine;
+use gosub_shared::types::Result;
+use gosub_v8::V8Engine;
+use gosub_webexecutor::js::*;
+use gosub_webinterop::web_idl;
+
+web_idl!(
+    r#"
+enum ScrollBehavior { "auto", "instant", "smooth" };
+
+dictionary ScrollOptions {
+    ScrollBehavior behavior = "auto";
+};
+
+dictionary ScrollToOptions : ScrollOptions {
+    unrestricted double left;
+    unrestricted double top;
+};
+
+interface Node {
+    const unsigned short ELEMENT_NODE = 1;
+    readonly attribute DOMString nodeName;
+    attribute DOMString? textContent;
+};
+
+interface Element : Node {
+    attribute DOMString id;
+    attribute Element? parent;
+    [Pure] DOMString? getAttribute(DOMString name);
+    [Pure] DOMString idOf(Node node);
+    undefined setAttribute(DOMString name, optional DOMString value = "");
+    DOMString describe(long value);
+    DOMString describe(DOMString value);
+    DOMString describe(boolean value, ScrollBehavior behavior);
+    long sum(long... values);
+    octet clamp([Clamp] octet value);
+    long enforce([EnforceRange] long value);
+    DOMString scroll(optional ScrollToOptions options = {});
+    ScrollToOptions position();
+};
+"#
+);
+
+#[derive(Default)]
+struct TestElement {
+    id: String,
+    text: Option<String>,
+    attributes: HashMap<String, String>,
+    parent: Option<Rc<RefCell<TestElement>>>,
+}
+
+impl NodeInterface for TestElement {
+    fn node_name(&self) -> Result<String> {
+        Ok("DIV".to_owned())
+    }
+
+    fn text_content(&self) -> Result<Option<String>> {
+        Ok(self.text.clone())
+    }
+
+    fn set_text_content(&mut self, value: Option<String>) -> Result<()> {
+        self.text = value;
+        Ok(())
+    }
+}
+
+impl ElementInterface for TestElement {
+    fn id(&self) -> Result<String> {
+        Ok(self.id.clone())
+    }
+
+    fn set_id(&mut self, value: String) -> Result<()> {
+        self.id = value;
+        Ok(())
+    }
+
+    fn parent<RT: JSRuntime>(&self, _ctx: RT::Context) -> Result<Option<PlatformObject<RT>>> {
+        Ok(self.parent.as_ref().map(|parent| wrap_element(Rc::clone(parent))))
+    }
+
+    fn set_parent<RT: JSRuntime>(&mut self, value: Option<PlatformObject<RT>>, _ctx: RT::Context) -> Result<()> {
+        self.parent = value.and_then(|parent| parent.downcast::<TestElement>());
+        Ok(())
+    }
+
+    fn get_attribute(&self, name: String) -> Result<Option<String>> {
+        Ok(self.attributes.get(&name).cloned())
+    }
+
+    fn id_of<RT: JSRuntime>(&self, node: PlatformObject<RT>, _ctx: RT::Context) -> Result<String> {
+        let node = node.downcast::<TestElement>();
+        Ok(node.map(|node| node.borrow().id.clone()).unwrap_or_default())
+    }
+
+    fn set_attribute(&mut self, name: String, value: String) -> Result<()> {
+        self.attributes.insert(name, value);
+        Ok(())
+    }
+
+    fn describe(&mut self, value: i32) -> Result<String> {
+        Ok(format!("long {value}"))
+    }
+
+    fn describe_with_value(&mut self, value: String) -> Result<String> {
+        Ok(format!("string {value}"))
+    }
+
+    fn describe_with_value_and_behavior(&mut self, value: bool, behavior: ScrollBehavior) -> Result<String> {
+        Ok(format!("boolean {value} {}", behavior.as_str()))
+    }
+
+    fn sum(&mut self, values: Vec<i32>) -> Result<i32> {
+        Ok(values.iter().sum())
+    }
+
+    fn clamp(&mut self, value: u8) -> Result<u8> {
+        Ok(value)
+    }
+
+    fn enforce(&mut self, value: i32) -> Result<i32> {
+        Ok(value)
+    }
+
+    fn scroll(&mut self, options: ScrollToOptions) -> Result<String> {
+        Ok(format!(
+            "{:?} {:?} {:?}",
+            options.scroll_options.behavior, options.left, options.top
+        ))
+    }
+
+    fn position(&mut self) -> Result<ScrollToOptions> {
+        Ok(ScrollToOptions {
+            scroll_options: ScrollOptions {
+                behavior: ScrollBehavior::Smooth,
+            },
+            left: Some(1.0),
+            top: None,
+        })
+    }
+}
+
+fn idl_bindings<RT: JSRuntime>(mut engine: RT) {
+    let mut context = engine.new_context().unwrap();
+
+    let mut obj = context.new_global_object("element").unwrap();
+    define_element::<RT, _>(&mut obj, Rc::new(RefCell::new(TestElement::default())), context.clone()).unwrap();
+
+    let other = Rc::new(RefCell::new(TestElement {
+        id: "other".to_owned(),
+        ..Default::default()
+    }));
+    let other = wrap_element::<RT, _>(other).into_js(context.clone()).unwrap();
+    let test = context.new_global_object("test").unwrap();
+    test.set_property("other", &other).unwrap();
+
+    let out = context
+        .run(
+            r#"
+        const out = []
+        const fails = (f) => { try { f(); return false } catch (e) { return true } }
+
+        out.push(element.nodeName, element.ELEMENT_NODE, element.textContent)
+        element.textContent = "text"
+        element.id = "main"
+        out.push(element.textContent, element.id)
+
+        element.setAttribute("hidden")
+        element.setAttribute("title", "hello")
+        out.push(element.getAttribute("hidden"), element.getAttribute("title"), element.getAttribute("missing"))
+
+        out.push(element.describe(5), element.describe("five"), element.describe(true, "smooth"))
+        out.push(fails(() => element.describe(true, "sideways")), fails(() => element.getAttribute()))
+
+        out.push(element.sum(), element.sum(1, 2, 3))
+        out.push(element.clamp(300), element.clamp(-5), element.clamp(2.5), element.clamp(3.5))
+        out.push(element.enforce(-7.9), fails(() => element.enforce(1e20)), fails(() => element.enforce(NaN)))
+
+        out.push(element.scroll(), element.scroll({ top: 10, behavior: "instant" }), fails(() => element.scroll(5)))
+        out.push(JSON.stringify(element.position()))
+
+        out.push(element.parent, fails(() => { element.parent = {} }), fails(() => { element.parent = "other" }))
+        element.parent = test.other
+        out.push(element.parent.id, element.idOf(element.parent), element.idOf(test.other))
+        out.push(fails(() => element.idOf({ id: "fake" })), fails(() => element.idOf(null)))
+        out.push(fails(() => { element.nodeName = "P" }), element.nodeName)
+        element.parent = null
+        out.push(element.parent)
+
+        out.join("|")
+        "#,
+        )
+        .expect("failed to run");
+
+    assert_eq!(
+        out.as_string().unwrap(),
+        [
+            "DIV",
+            "1",
+            "",
+            "text",
+            "main",
+            "",
+            "hello",
+            "",
+            "long 5",
+            "string five",
+            "boolean true smooth",
+            "true",
+            "true",
+            "0",
+            "6",
+            "255",
+            "0",
+            "2",
+            "4",
+            "-7",
+            "true",
+            "true",
+            "Auto None None",
+            "Instant None Some(10.0)",
+            "true",
+            r#"{"behavior":"smooth","left":1}"#,
+            "",
+            "true",
+            "true",
+            "other",
+            "other",
+            "other",
+            "true",
+            "true",
+            "true",
+            "DIV",
+            "",
+        ]
+        .join("|")
+    );
+}
+
+#[test]
+fn idl_bindings_v8() {
+    idl_bindings(V8Engine::new());
+}
+
+#[test]
+fn idl_bindings_boa() {
+    idl_bindings(BoaEngine::new());
+}
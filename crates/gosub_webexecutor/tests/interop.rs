@@ -1,10 +1,11 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use gosub_boa::BoaEngine;
 use gosub_shared::types::Result;
 use gosub_v8::V8Engine;
 use gosub_webexecutor::js::{
-    Args, IntoJSValue, IntoRustValue, JSContext, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic,
+    Args, IntoJSValue, IntoRustValue, JSArray, JSContext, JSFunction, JSFunctionCallBack, JSFunctionCallBackVariadic,
     JSFunctionVariadic, JSGetterCallback, JSInterop, JSObject, JSRuntime, JSSetterCallback, JSValue, VariadicArgs,
     VariadicArgsInternal,
 };
@@ -97,14 +98,12 @@ impl T1 for i32 {}
 
 impl T1 for String {}
 
-#[test]
-fn macro_interop() {
+fn macro_interop<RT: JSRuntime>(mut engine: RT) {
     let test_struct = TestStruct { field: 14, field2: 14 };
 
-    let mut engine = V8Engine::new();
     let mut context = engine.new_context().unwrap();
 
-    TestStruct::implement::<V8Engine>(Rc::new(RefCell::new(test_struct)), context.clone()).unwrap();
+    TestStruct::implement::<RT>(Rc::new(RefCell::new(test_struct)), context.clone()).unwrap();
 
     let out = context
         .run(
@@ -155,11 +154,21 @@ fn macro_interop() {
 
     let arr = out.as_array().expect("failed to get array from run ret value");
 
-    for v in arr {
+    for v in arr.as_vec() {
         assert_eq!(v.as_string().unwrap(), expected.remove(0));
     }
 }
 
+#[test]
+fn macro_interop_v8() {
+    macro_interop(V8Engine::new());
+}
+
+#[test]
+fn macro_interop_boa() {
+    macro_interop(BoaEngine::new());
+}
+
 #[derive(Debug)]
 struct Test2 {
     field: i32,
@@ -498,9 +507,7 @@ impl JSInterop for Test2 {
     }
 }
 
-#[test]
-fn manual_js_interop() {
-    let mut engine = V8Engine::new();
+fn manual_js_interop<RT: JSRuntime>(mut engine: RT) {
     let mut context = engine.new_context().unwrap();
 
     let t2 = Rc::new(RefCell::new(Test2 {
@@ -508,7 +515,7 @@ fn manual_js_interop() {
         other_field: "Hello, ".to_string(),
     }));
 
-    Test2::implement::<V8Engine>(t2.clone(), context.clone()).unwrap();
+    Test2::implement::<RT>(t2.clone(), context.clone()).unwrap();
 
     let out = context
         .run(
@@ -533,3 +540,13 @@ fn manual_js_interop() {
     println!("JS: {}", out);
     println!("Rust: {:?}", t2.borrow())
 }
+
+#[test]
+fn manual_js_interop_v8() {
+    manual_js_interop(V8Engine::new());
+}
+
+#[test]
+fn manual_js_interop_boa() {
+    manual_js_interop(BoaEngine::new());
+}
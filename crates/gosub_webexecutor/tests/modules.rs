@@ -0,0 +1,299 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use url::Url;
+
+use gosub_boa::BoaEngine;
+use gosub_shared::node::NodeId;
+use gosub_shared::traits::document::{Script, ScriptKind, ScriptSource, ScriptTiming};
+use gosub_shared::types::Result;
+use gosub_v8::V8Engine;
+use gosub_webexecutor::js::*;
+
+/// Serves scripts from memory
+struct StaticFetcher(HashMap<String, &'static str>);
+
+impl ScriptFetcher for StaticFetcher {
+    fn fetch(&self, url: &Url, _kind: ScriptKind) -> Result<String> {
+        match self.0.get(url.as_str()) {
+            Some(source) => Ok(source.to_string()),
+            None => Err(anyhow::anyhow!("not found: {url}")),
+        }
+    }
+}
+
+/// Serves scripts from memory, but fails the first time a script is fetched
+struct FlakyFetcher {
+    fetcher: StaticFetcher,
+    failed: Cell<bool>,
+}
+
+impl ScriptFetcher for FlakyFetcher {
+    fn fetch(&self, url: &Url, kind: ScriptKind) -> Result<String> {
+        if url.path().ends_with("/flaky.js") && !self.failed.replace(true) {
+            return Err(anyhow::anyhow!("network error: {url}"));
+        }
+
+        self.fetcher.fetch(url, kind)
+    }
+}
+
+/// Serves scripts from memory and records which scripts were fetched as what kind
+struct RecordingFetcher {
+    fetcher: StaticFetcher,
+    fetched: Rc<RefCell<Vec<(String, ScriptKind)>>>,
+}
+
+impl ScriptFetcher for RecordingFetcher {
+    fn fetch(&self, url: &Url, kind: ScriptKind) -> Result<String> {
+        self.fetched.borrow_mut().push((url.to_string(), kind));
+        self.fetcher.fetch(url, kind)
+    }
+}
+
+fn script(id: usize, kind: ScriptKind, source: ScriptSource, timing: ScriptTiming) -> Script {
+    Script {
+        node_id: NodeId::from(id),
+        kind,
+        source,
+        timing,
+    }
+}
+
+fn module_scripts<RT: JSRuntime>(mut engine: RT) {
+    let base = Url::parse("https://example.com/app/index.html").unwrap();
+    let fetcher = StaticFetcher(HashMap::from([
+        (
+            "https://example.com/app/main.js".to_owned(),
+            r#"
+            import { add } from "./math.js";
+            import config from "config";
+            log(`main ${add(1, 2)} ${config}`);
+            "#,
+        ),
+        (
+            "https://example.com/app/math.js".to_owned(),
+            r#"
+            log("math");
+            export const add = (a, b) => a + b;
+            "#,
+        ),
+        (
+            "https://example.com/vendor/config.js".to_owned(),
+            r#"
+            log("config");
+            await Promise.resolve();
+            export default "configured";
+            "#,
+        ),
+        (
+            "https://example.com/app/lazy.js".to_owned(),
+            r#"export const lazy = "lazy";"#,
+        ),
+    ]));
+
+    let context = engine.new_context().unwrap();
+    let loader = Rc::new(ModuleLoader::new(base, fetcher));
+    let mut runner = ScriptRunner::<RT>::new(context.clone(), loader);
+
+    let inline = |source: &str| ScriptSource::Inline(source.to_owned());
+    runner.add_scripts(vec![
+        script(
+            1,
+            ScriptKind::Classic,
+            inline("var out = []; function log(value) { out.push(value) }"),
+            ScriptTiming::Blocking,
+        ),
+        script(
+            2,
+            ScriptKind::ImportMap,
+            inline(r#"{ "imports": { "config": "../vendor/config.js" } }"#),
+            ScriptTiming::Blocking,
+        ),
+        script(
+            3,
+            ScriptKind::Module,
+            ScriptSource::External(Url::parse("https://example.com/app/main.js").unwrap()),
+            ScriptTiming::Deferred,
+        ),
+        script(
+            4,
+            ScriptKind::Module,
+            inline(r#"const { lazy } = await import("./lazy.js"); log(lazy)"#),
+            ScriptTiming::Async,
+        ),
+        script(
+            5,
+            ScriptKind::Module,
+            inline(r#"import "missing"; log("never")"#),
+            ScriptTiming::Deferred,
+        ),
+        script(
+            6,
+            ScriptKind::Classic,
+            inline(r#"log("classic")"#),
+            ScriptTiming::Blocking,
+        ),
+    ]);
+
+    // only the blocking scripts have run while the document is being parsed
+    let mut ctx = context.clone();
+    assert_eq!(ctx.run("out.join()").unwrap().as_string().unwrap(), "classic");
+
+    runner.finish();
+    ctx.run_microtasks();
+
+    assert_eq!(
+        ctx.run("out.join()").unwrap().as_string().unwrap(),
+        "classic,math,config,main 3 configured,lazy"
+    );
+}
+
+/// A module graph that failed to load is loaded again when it is imported again
+fn failed_module_graph<RT: JSRuntime>(mut engine: RT) {
+    let base = Url::parse("https://example.com/index.html").unwrap();
+    let fetcher = FlakyFetcher {
+        fetcher: StaticFetcher(HashMap::from([
+            (
+                "https://example.com/a.js".to_owned(),
+                r#"import { b } from "./b.js"; log(`a ${b}`);"#,
+            ),
+            (
+                "https://example.com/b.js".to_owned(),
+                r#"import { flaky } from "./flaky.js"; export const b = `b ${flaky}`;"#,
+            ),
+            (
+                "https://example.com/flaky.js".to_owned(),
+                r#"export const flaky = "flaky";"#,
+            ),
+        ])),
+        failed: Cell::new(false),
+    };
+
+    let context = engine.new_context().unwrap();
+    let loader = Rc::new(ModuleLoader::new(base, fetcher));
+    let mut runner = ScriptRunner::<RT>::new(context.clone(), loader);
+
+    let inline = |source: &str| ScriptSource::Inline(source.to_owned());
+    runner.add_scripts(vec![
+        script(
+            1,
+            ScriptKind::Classic,
+            inline("var out = []; function log(value) { out.push(value) }"),
+            ScriptTiming::Blocking,
+        ),
+        script(
+            2,
+            ScriptKind::Module,
+            inline(r#"import "./a.js";"#),
+            ScriptTiming::Deferred,
+        ),
+        script(
+            3,
+            ScriptKind::Module,
+            inline(r#"import "./a.js";"#),
+            ScriptTiming::Deferred,
+        ),
+    ]);
+    runner.finish();
+
+    let mut ctx = context.clone();
+    ctx.run_microtasks();
+
+    assert_eq!(ctx.run("out.join()").unwrap().as_string().unwrap(), "a b flaky");
+}
+
+/// Inline module scripts have the URL of the document, but importing that URL fetches the document
+fn inline_modules<RT: JSRuntime>(mut engine: RT) {
+    let base = Url::parse("https://example.com/page.html").unwrap();
+    let fetched = Rc::new(RefCell::new(Vec::new()));
+    let fetcher = RecordingFetcher {
+        fetcher: StaticFetcher(HashMap::from([
+            ("https://example.com/classic.js".to_owned(), r#"log("classic")"#),
+            (
+                "https://example.com/page.html".to_owned(),
+                r#"export const page = "fetched";"#,
+            ),
+        ])),
+        fetched: fetched.clone(),
+    };
+
+    let context = engine.new_context().unwrap();
+    let loader = Rc::new(ModuleLoader::new(base, fetcher));
+    let mut runner = ScriptRunner::<RT>::new(context.clone(), loader);
+
+    let inline = |source: &str| ScriptSource::Inline(source.to_owned());
+    runner.add_scripts(vec![
+        script(
+            1,
+            ScriptKind::Classic,
+            inline("var out = []; function log(value) { out.push(value) }"),
+            ScriptTiming::Blocking,
+        ),
+        script(
+            2,
+            ScriptKind::Classic,
+            ScriptSource::External(Url::parse("https://example.com/classic.js").unwrap()),
+            ScriptTiming::Blocking,
+        ),
+        script(
+            3,
+            ScriptKind::Module,
+            inline(r#"log("inline")"#),
+            ScriptTiming::Deferred,
+        ),
+        script(
+            4,
+            ScriptKind::Module,
+            inline(r#"import { page } from "./page.html"; log(page)"#),
+            ScriptTiming::Deferred,
+        ),
+    ]);
+    runner.finish();
+
+    let mut ctx = context.clone();
+    ctx.run_microtasks();
+
+    assert_eq!(
+        ctx.run("out.join()").unwrap().as_string().unwrap(),
+        "classic,inline,fetched"
+    );
+    assert_eq!(
+        *fetched.borrow(),
+        [
+            ("https://example.com/classic.js".to_owned(), ScriptKind::Classic),
+            ("https://example.com/page.html".to_owned(), ScriptKind::Module),
+        ]
+    );
+}
+
+#[test]
+fn module_scripts_v8() {
+    module_scripts(V8Engine::new());
+}
+
+#[test]
+fn module_scripts_boa() {
+    module_scripts(BoaEngine::new());
+}
+
+#[test]
+fn failed_module_graph_v8() {
+    failed_module_graph(V8Engine::new());
+}
+
+#[test]
+fn failed_module_graph_boa() {
+    failed_module_graph(BoaEngine::new());
+}
+
+#[test]
+fn inline_modules_v8() {
+    inline_modules(V8Engine::new());
+}
+
+#[test]
+fn inline_modules_boa() {
+    inline_modules(BoaEngine::new());
+}
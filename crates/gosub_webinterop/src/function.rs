@@ -183,13 +183,9 @@ impl Function {
             let check = js_ty.1.get_check(&arg_name);
             let ty = js_ty.0;
 
+            // a type only matches when the value converts to it, so objects are matched by their fields too
             out.extend(quote! {
-                if #check {
-                    let Ok(#arg_name): Result<#ty> = #arg_name.to_rust_value() else {
-                            cb.error("failed to convert argument");
-                            return;
-                        };
-
+                if let Some(Ok(#arg_name)) = (#check).then(|| -> Result<#ty> { #arg_name.to_rust_value() }) {
                     #prev
                 } else
             });
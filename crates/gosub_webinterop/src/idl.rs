@@ -0,0 +1,122 @@
+use proc_macro2::TokenStream;
+
+use crate::idl::parser::{Definition, Literal, Member, Parser};
+use crate::idl::types::IdlType;
+
+mod dictionary;
+mod enumeration;
+mod interface;
+mod parser;
+mod types;
+
+/// Generates the rust glue for a WebIDL fragment, see `web_idl!`
+pub(crate) fn web_idl(source: &str) -> Result<TokenStream, String> {
+    let mut definitions = Parser::parse(source)?;
+
+    let typedefs: Vec<(String, IdlType)> = definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Typedef(typedef) => Some((typedef.name.clone(), typedef.ty.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let interfaces: Vec<String> = definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Interface(interface) => Some(interface.name.clone()),
+            _ => None,
+        })
+        .collect();
+    let interfaces: Vec<&str> = interfaces.iter().map(String::as_str).collect();
+
+    for definition in &mut definitions {
+        for ty in types_mut(definition) {
+            ty.resolve(&typedefs, &interfaces);
+        }
+    }
+
+    validate(&definitions)?;
+
+    let mut out = TokenStream::new();
+    for definition in &definitions {
+        out.extend(match definition {
+            Definition::Interface(interface) => interface::generate(interface, &definitions)?,
+            Definition::Dictionary(dictionary) => dictionary::generate(dictionary, &definitions)?,
+            Definition::Enum(enumeration) => enumeration::generate(enumeration)?,
+            // typedefs are resolved in the definitions that use them
+            Definition::Typedef(_) => TokenStream::new(),
+        });
+    }
+
+    Ok(out)
+}
+
+fn types_mut(definition: &mut Definition) -> Vec<&mut IdlType> {
+    match definition {
+        Definition::Interface(interface) => interface
+            .members
+            .iter_mut()
+            .flat_map(|member| match member {
+                Member::Const(constant) => vec![&mut constant.ty],
+                Member::Attribute(attribute) => vec![&mut attribute.ty],
+                Member::Operation(operation) => std::iter::once(&mut operation.ret)
+                    .chain(operation.args.iter_mut().map(|arg| &mut arg.ty))
+                    .collect(),
+            })
+            .collect(),
+        Definition::Dictionary(dictionary) => dictionary.members.iter_mut().map(|member| &mut member.ty).collect(),
+        Definition::Enum(_) => Vec::new(),
+        Definition::Typedef(typedef) => vec![&mut typedef.ty],
+    }
+}
+
+/// Checks what the generators can't, the enum defaults
+fn validate(definitions: &[Definition]) -> Result<(), String> {
+    let check = |owner: &str, ty: &IdlType, default: Option<&Literal>| {
+        let ty = match ty {
+            IdlType::Nullable(inner) => &**inner,
+            ty => ty,
+        };
+        let (IdlType::Named(name), Some(Literal::String(value))) = (ty, default) else {
+            return Ok(());
+        };
+
+        match definitions.iter().find_map(|definition| match definition {
+            Definition::Enum(enumeration) if &enumeration.name == name => Some(enumeration),
+            _ => None,
+        }) {
+            Some(enumeration) if !enumeration.values.contains(value) => {
+                Err(format!("{owner}: {value:?} is not a value of enum {name}"))
+            }
+            _ => Ok(()),
+        }
+    };
+
+    for definition in definitions {
+        match definition {
+            Definition::Interface(interface) => {
+                for member in &interface.members {
+                    match member {
+                        Member::Const(constant) => check(&interface.name, &constant.ty, None)?,
+                        Member::Attribute(attribute) => check(&interface.name, &attribute.ty, None)?,
+                        Member::Operation(operation) => {
+                            check(&interface.name, &operation.ret, None)?;
+                            for arg in &operation.args {
+                                check(&interface.name, &arg.ty, arg.default.as_ref())?;
+                            }
+                        }
+                    }
+                }
+            }
+            Definition::Dictionary(dictionary) => {
+                for member in &dictionary.members {
+                    check(&dictionary.name, &member.ty, member.default.as_ref())?;
+                }
+            }
+            Definition::Enum(_) | Definition::Typedef(_) => {}
+        }
+    }
+
+    Ok(())
+}
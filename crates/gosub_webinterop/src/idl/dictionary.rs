@@ -0,0 +1,175 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::idl::parser::{Definition, Dictionary};
+use crate::idl::types::{rust_ident, type_ident};
+
+/// Whether the dictionary or one of its parents has a required member, those don't implement `Default`
+fn has_required(name: &str, definitions: &[Definition]) -> bool {
+    definitions.iter().any(|definition| match definition {
+        Definition::Dictionary(dictionary) if dictionary.name == name => {
+            dictionary.members.iter().any(|member| member.required)
+                || dictionary
+                    .parent
+                    .as_ref()
+                    .is_some_and(|parent| has_required(parent, definitions))
+        }
+        _ => false,
+    })
+}
+
+pub(crate) fn generate(dictionary: &Dictionary, definitions: &[Definition]) -> Result<TokenStream, String> {
+    let name = type_ident(&dictionary.name);
+    let rt = quote! { V::RT };
+    let value = format_ident!("value");
+
+    let mut fields = Vec::new();
+    let mut defaults = Vec::new();
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+    let mut idents = Vec::new();
+
+    let parent = dictionary
+        .parent
+        .as_ref()
+        .map(|parent| (rust_ident(parent), type_ident(parent)));
+    if let Some((ident, ty)) = &parent {
+        fields.push(quote! { pub #ident: #ty });
+        defaults.push(quote! { #ident: Default::default() });
+        reads.push(quote! { let #ident = <#ty as FromIdl<V>>::from_idl(value)?; });
+        idents.push(ident.clone());
+    }
+
+    for member in &dictionary.members {
+        let context = |e: String| format!("{}.{}: {e}", dictionary.name, member.name);
+
+        if member.ty.uses_runtime() {
+            return Err(context("dictionaries can't hold any, object or interfaces".to_owned()));
+        }
+
+        let ident = rust_ident(&member.name);
+        let ty = member.ty.rust_type(&rt);
+        // optional members without a default are an `Option`, unless the type is nullable and already is one
+        let optional = !member.required && member.default.is_none() && !member.ty.is_nullable();
+
+        let field_ty = if optional {
+            quote! { Option<#ty> }
+        } else {
+            quote! { #ty }
+        };
+        fields.push(quote! { pub #ident: #field_ty });
+
+        let default = match &member.default {
+            Some(default) => member.ty.value(default).map_err(context)?,
+            None => quote! { None },
+        };
+        defaults.push(quote! { #ident: #default });
+
+        let convert = member.ty.to_rust(&value, true).map_err(context)?;
+        let js_name = &member.name;
+        let missing = if member.required {
+            let error = format!("required member {} of {} is missing", member.name, dictionary.name);
+            quote! { return Err(idl_type_error(#error)) }
+        } else {
+            default
+        };
+        let convert = if optional {
+            quote! { Some(#convert?) }
+        } else {
+            quote! { #convert? }
+        };
+        reads.push(quote! {
+            let #ident = match object.as_ref().map(|object| object.get_property(#js_name)).transpose()? {
+                Some(value) if !value.is_undefined() => #convert,
+                _ => #missing,
+            };
+        });
+
+        let write = member.ty.to_js(quote! { value }, &rt);
+        writes.push(if optional {
+            quote! {
+                if let Some(value) = &self.#ident {
+                    object.set_property(#js_name, &#write?)?;
+                }
+            }
+        } else {
+            quote! {
+                let value = &self.#ident;
+                object.set_property(#js_name, &#write?)?;
+            }
+        });
+
+        idents.push(ident);
+    }
+
+    // members are read in lexicographical order, after the members of the parent
+    let parent_reads = usize::from(parent.is_some());
+    let mut sorted: Vec<_> = dictionary.members.iter().zip(reads.drain(parent_reads..)).collect();
+    sorted.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    reads.extend(sorted.into_iter().map(|(_, read)| read));
+
+    let object = match &parent {
+        Some((ident, _)) => quote! {
+            let object = {
+                let value: V = self.#ident.to_js_value(ctx.clone())?;
+                value.as_object()?
+            };
+        },
+        None => quote! { let object = V::new_object(ctx.clone())?; },
+    };
+
+    let default_impl = if has_required(&dictionary.name, definitions) {
+        TokenStream::new()
+    } else {
+        quote! {
+            impl Default for #name {
+                fn default() -> Self {
+                    Self {
+                        #(#defaults),*
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #name {
+            #(#fields),*
+        }
+
+        #default_impl
+
+        impl<V: JSValue> FromIdl<V> for #name
+        where
+            V::RT: JSRuntime<Value = V>,
+        {
+            fn is_match(value: &V) -> bool {
+                value.is_undefined() || value.is_null() || value.is_object()
+            }
+
+            fn from_idl(value: &V) -> Result<Self> {
+                let object = idl_dictionary(value)?;
+                #(#reads)*
+
+                Ok(Self {
+                    #(#idents),*
+                })
+            }
+        }
+
+        impl<V: JSValue> IntoJSValue<V> for #name
+        where
+            V::RT: JSRuntime<Value = V>,
+        {
+            type Value = V;
+
+            fn to_js_value(&self, ctx: <V::RT as JSRuntime>::Context) -> Result<Self::Value> {
+                #object
+                #(#writes)*
+
+                Ok(V::from(object))
+            }
+        }
+    })
+}
@@ -0,0 +1,66 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::idl::parser::Enum;
+use crate::idl::types::{type_ident, variant_name};
+
+pub(crate) fn generate(enumeration: &Enum) -> Result<TokenStream, String> {
+    let name = type_ident(&enumeration.name);
+
+    if enumeration.values.is_empty() {
+        return Err(format!("enum {} has no values", enumeration.name));
+    }
+
+    let mut variants = Vec::new();
+    for value in &enumeration.values {
+        let variant = format_ident!("{}", variant_name(value));
+        if variants.contains(&variant) {
+            return Err(format!("enum {} has two values named {variant}", enumeration.name));
+        }
+        variants.push(variant);
+    }
+
+    let values = &enumeration.values;
+    let error = format!("{{:?}} is not a valid value for enum {}", enumeration.name);
+
+    Ok(quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum #name {
+            #(#variants),*
+        }
+
+        impl #name {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    #(#name::#variants => #values),*
+                }
+            }
+
+            pub fn from_value(value: &str) -> Option<Self> {
+                match value {
+                    #(#values => Some(#name::#variants),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl<V: JSValue> FromIdl<V> for #name {
+            fn is_match(value: &V) -> bool {
+                value.is_string()
+            }
+
+            fn from_idl(value: &V) -> Result<Self> {
+                let value = idl_string(value)?;
+                Self::from_value(&value).ok_or_else(|| idl_type_error(format!(#error, value)))
+            }
+        }
+
+        impl<V: JSValue> IntoJSValue<V> for #name {
+            type Value = V;
+
+            fn to_js_value(&self, ctx: <V::RT as JSRuntime>::Context) -> Result<Self::Value> {
+                V::new_string(ctx, self.as_str())
+            }
+        }
+    })
+}
@@ -0,0 +1,648 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::idl::parser::{Argument, Attribute, Const, Definition, Interface, Member, Operation};
+use crate::idl::types::{rust_ident, snake_case, type_ident, IdlType};
+
+pub(crate) fn trait_ident(name: &str) -> Ident {
+    format_ident!("{}Interface", type_ident(name))
+}
+
+pub(crate) fn define_ident(name: &str) -> Ident {
+    format_ident!("define_{}", snake_case(name))
+}
+
+pub(crate) fn wrap_ident(name: &str) -> Ident {
+    format_ident!("wrap_{}", snake_case(name))
+}
+
+/// The interface and the interfaces it inherits from, as far as they are defined in the same fragment
+fn ancestors<'a>(interface: &'a Interface, definitions: &'a [Definition]) -> Vec<&'a str> {
+    let mut names = vec![&*interface.name];
+    let mut parent = interface.parent.as_deref();
+
+    while let Some(name) = parent.filter(|name| !names.contains(name)) {
+        names.push(name);
+        parent = definitions.iter().find_map(|definition| match definition {
+            Definition::Interface(interface) if interface.name == name => interface.parent.as_deref(),
+            _ => None,
+        });
+    }
+
+    names
+}
+
+/// Operations with the same name (and staticness), which are overloads of each other
+struct OverloadSet<'a> {
+    name: &'a str,
+    is_static: bool,
+    operations: Vec<(&'a Operation, Ident)>,
+}
+
+struct Generator<'a> {
+    interface: &'a Interface,
+    trait_ident: Ident,
+    trait_items: Vec<TokenStream>,
+    defines: Vec<TokenStream>,
+    methods: HashSet<String>,
+}
+
+pub(crate) fn generate(interface: &Interface, definitions: &[Definition]) -> Result<TokenStream, String> {
+    let mut generator = Generator {
+        interface,
+        trait_ident: trait_ident(&interface.name),
+        trait_items: Vec::new(),
+        defines: Vec::new(),
+        methods: HashSet::new(),
+    };
+
+    let mut overloads: Vec<OverloadSet> = Vec::new();
+
+    for member in &interface.members {
+        match member {
+            Member::Const(constant) => generator.constant(constant)?,
+            Member::Attribute(attribute) => generator.attribute(attribute)?,
+            Member::Operation(operation) => {
+                let set = match overloads
+                    .iter_mut()
+                    .find(|set| set.name == operation.name && set.is_static == operation.is_static)
+                {
+                    Some(set) => set,
+                    None => {
+                        overloads.push(OverloadSet {
+                            name: &operation.name,
+                            is_static: operation.is_static,
+                            operations: Vec::new(),
+                        });
+                        overloads.last_mut().unwrap()
+                    }
+                };
+
+                // the first overload keeps the name, the others are named after their arguments
+                let method = if set.operations.is_empty() {
+                    rust_ident(&operation.name)
+                } else if operation.args.is_empty() {
+                    rust_ident(&format!("{}_without_args", snake_case(&operation.name)))
+                } else {
+                    let args: Vec<String> = operation.args.iter().map(|arg| snake_case(&arg.name)).collect();
+                    rust_ident(&format!("{}_with_{}", snake_case(&operation.name), args.join("_and_")))
+                };
+
+                set.operations.push((operation, method));
+            }
+        }
+    }
+
+    for set in &overloads {
+        generator.operation(set)?;
+    }
+
+    let Generator {
+        trait_ident,
+        trait_items,
+        defines,
+        ..
+    } = generator;
+
+    let define = define_ident(&interface.name);
+    let wrap = wrap_ident(&interface.name);
+    let interfaces = ancestors(interface, definitions);
+    let (supertrait, define_parent) = match &interface.parent {
+        Some(parent) => {
+            let parent_trait = self::trait_ident(parent);
+            let parent_define = define_ident(parent);
+            (
+                quote! { : #parent_trait },
+                quote! { #parent_define::<RT, T>(obj, Rc::clone(&s), ctx.clone())?; },
+            )
+        }
+        None => (TokenStream::new(), TokenStream::new()),
+    };
+
+    let doc_trait = format!(
+        " The `{}` interface, implemented by the rust type that backs it",
+        interface.name
+    );
+    let doc_define = format!(
+        " Defines the members of `{}` on `obj`, calling the methods of `{trait_ident}` on `s`",
+        interface.name
+    );
+    let doc_wrap = format!(
+        " Returns the `{}` object for `s`, which gets its members once it's converted to javascript",
+        interface.name
+    );
+    let downcast_error = format!("the value behind a {} has the wrong type", interface.name);
+
+    Ok(quote! {
+        #[doc = #doc_trait]
+        pub trait #trait_ident #supertrait {
+            #(#trait_items)*
+        }
+
+        #[doc = #doc_define]
+        #[allow(unused_variables, unused_mut)]
+        pub fn #define<RT: JSRuntime, T: #trait_ident + 'static>(
+            obj: &mut RT::Object,
+            s: Rc<RefCell<T>>,
+            ctx: RT::Context,
+        ) -> Result<()> {
+            #define_parent
+            #(#defines)*
+
+            Ok(())
+        }
+
+        #[doc = #doc_wrap]
+        pub fn #wrap<RT: JSRuntime, T: #trait_ident + 'static>(s: Rc<RefCell<T>>) -> PlatformObject<RT> {
+            PlatformObject::new(s, &[#(#interfaces),*], |obj, value, ctx| {
+                let s = value
+                    .downcast::<RefCell<T>>()
+                    .map_err(|_| idl_type_error(#downcast_error))?;
+
+                #define::<RT, T>(obj, s, ctx)
+            })
+        }
+    })
+}
+
+impl Generator<'_> {
+    fn error(&self, member: &str, message: impl std::fmt::Display) -> String {
+        format!("{}.{member}: {message}", self.interface.name)
+    }
+
+    fn add_method(&mut self, member: &str, method: &Ident) -> Result<(), String> {
+        if !self.methods.insert(method.to_string()) {
+            return Err(self.error(member, format!("the method name {method} is used twice")));
+        }
+
+        Ok(())
+    }
+
+    fn constant(&mut self, constant: &Const) -> Result<(), String> {
+        let ident = type_ident(&constant.name);
+        let ty = constant.ty.rust_type(&quote! { RT });
+        let value = constant
+            .ty
+            .value(&constant.value)
+            .map_err(|e| self.error(&constant.name, e))?;
+
+        let trait_ident = self.trait_ident.clone();
+        let name = &constant.name;
+        let to_js = constant
+            .ty
+            .to_js(quote! { <T as #trait_ident>::#ident }, &quote! { RT });
+
+        self.trait_items.push(quote! { const #ident: #ty = #value; });
+        self.defines.push(quote! {
+            obj.set_property(#name, &#to_js?)?;
+        });
+
+        Ok(())
+    }
+
+    fn attribute(&mut self, attribute: &Attribute) -> Result<(), String> {
+        let name = &attribute.name;
+        let getter = rust_ident(name);
+        let trait_ident = self.trait_ident.clone();
+        let runtime = attribute.ty.uses_runtime();
+        let ty = attribute.ty.rust_type(&quote! { RT });
+
+        self.add_method(name, &getter)?;
+
+        let (receiver, generics, ctx_param) = signature_parts(attribute.is_static, runtime, false);
+        self.trait_items.push(quote! {
+            fn #getter #generics (#receiver #ctx_param) -> Result<#ty>;
+        });
+
+        let (self_arg, turbofish, ctx_arg) = call_parts(attribute.is_static, runtime, false);
+        let to_js = attribute.ty.to_js(quote! { value }, &quote! { RT });
+        let capture = capture(attribute.is_static);
+
+        let get = quote! {
+            let getter = {
+                #capture
+                Box::new(move |cb: &mut RT::GetterCB| {
+                    let ctx = cb.context().clone();
+                    let value = <T as #trait_ident>::#getter #turbofish (#self_arg #ctx_arg)
+                        .and_then(|value| #to_js);
+
+                    match value {
+                        Ok(value) => cb.ret(value),
+                        Err(e) => cb.error(e),
+                    }
+                })
+            };
+        };
+
+        let set = if attribute.readonly {
+            let error = format!("{name} is read-only");
+
+            quote! {
+                let setter = Box::new(|cb: &mut RT::SetterCB| cb.error(idl_type_error(#error)));
+            }
+        } else {
+            let setter = rust_ident(&format!("set_{}", snake_case(name)));
+            self.add_method(name, &setter)?;
+            let (receiver, generics, ctx_param) = signature_parts(attribute.is_static, runtime, true);
+
+            self.trait_items.push(quote! {
+                fn #setter #generics (#receiver value: #ty, #ctx_param) -> Result<()>;
+            });
+
+            let convert = attribute
+                .ty
+                .to_rust(&format_ident!("value"), false)
+                .map_err(|e| self.error(name, e))?;
+            let (self_arg, turbofish, ctx_arg) = call_parts(attribute.is_static, runtime, true);
+
+            quote! {
+                let setter = {
+                    #capture
+                    Box::new(move |cb: &mut RT::SetterCB| {
+                        let ctx = cb.context().clone();
+                        let value = cb.value();
+                        let result = #convert
+                            .and_then(|value| <T as #trait_ident>::#setter #turbofish (#self_arg value, #ctx_arg));
+
+                        if let Err(e) = result {
+                            cb.error(e);
+                        }
+                    })
+                };
+            }
+        };
+
+        self.defines.push(quote! {
+            {
+                #get
+                #set
+
+                obj.set_property_accessor(#name, getter, setter)?;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn operation(&mut self, set: &OverloadSet) -> Result<(), String> {
+        let mut bodies = Vec::new();
+
+        for (operation, method) in &set.operations {
+            self.add_method(&operation.name, method)?;
+            bodies.push(self.overload(operation, method)?);
+        }
+
+        let name = set.name;
+        let body = if let [body] = &bodies[..] {
+            let required = required_args(set.operations[0].0);
+            if required == 0 {
+                body.clone()
+            } else {
+                let error = format!("{name} requires {required} arguments, but {{}} were passed");
+
+                quote! {
+                    if args.len() < #required {
+                        return Err(idl_type_error(format!(#error, args.len())));
+                    }
+
+                    #body
+                }
+            }
+        } else {
+            self.overload_resolution(set, &bodies)?
+        };
+
+        let capture = if set.is_static {
+            TokenStream::new()
+        } else {
+            quote! { let s = Rc::clone(&s); }
+        };
+
+        self.defines.push(quote! {
+            {
+                #capture
+                let call = move |ctx: RT::Context, args: Vec<RT::Value>| -> Result<RT::Value> {
+                    #body
+                };
+
+                let function = <RT::Function as JSFunction>::new(ctx.clone(), move |cb: &mut RT::FunctionCallBack| {
+                    let ctx = cb.context();
+                    let args = cb.args().as_vec(ctx.clone());
+
+                    match call(ctx, args) {
+                        Ok(value) => cb.ret(value),
+                        Err(e) => cb.error(e),
+                    }
+                })?;
+
+                obj.set_method(#name, &function)?;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Adds the trait method for one overload and returns the code that converts the arguments and calls it
+    fn overload(&mut self, operation: &Operation, method: &Ident) -> Result<TokenStream, String> {
+        let runtime = operation.ret.uses_runtime() || operation.args.iter().any(|arg| arg.ty.uses_runtime());
+        let ret = operation.ret.rust_type(&quote! { RT });
+
+        let mut params = Vec::new();
+        let mut conversions = Vec::new();
+        let mut idents = Vec::new();
+
+        for arg in &operation.args {
+            let ident = rust_ident(&arg.name);
+            let ty = arg_type(arg);
+            params.push(quote! { #ident: #ty, });
+
+            let conversion = self.argument(operation, arg)?;
+            conversions.push(quote! { let #ident = #conversion; });
+            idents.push(ident);
+        }
+
+        let (receiver, generics, ctx_param) = signature_parts(operation.is_static, runtime, !operation.pure);
+        self.trait_items.push(quote! {
+            fn #method #generics (#receiver #(#params)* #ctx_param) -> Result<#ret>;
+        });
+
+        let trait_ident = &self.trait_ident;
+        let (self_arg, turbofish, ctx_arg) = call_parts(operation.is_static, runtime, !operation.pure);
+        let call = quote! { <T as #trait_ident>::#method #turbofish (#self_arg #(#idents,)* #ctx_arg)? };
+        let ret = if operation.ret == IdlType::Undefined {
+            quote! {
+                #call;
+                <RT as JSRuntime>::Value::new_undefined(ctx.clone())
+            }
+        } else {
+            let to_js = operation.ret.to_js(quote! { value }, &quote! { RT });
+            quote! {
+                let value = #call;
+                #to_js
+            }
+        };
+
+        Ok(quote! {
+            let mut args = args.into_iter();
+            #(#conversions)*
+
+            #ret
+        })
+    }
+
+    fn argument(&self, operation: &Operation, arg: &Argument) -> Result<TokenStream, String> {
+        let value = format_ident!("value");
+        let convert = arg
+            .ty
+            .to_rust(&value, true)
+            .map_err(|e| self.error(&operation.name, format!("argument {}: {e}", arg.name)))?;
+
+        if arg.variadic {
+            return Ok(quote! {
+                args.map(|value| #convert).collect::<Result<Vec<_>>>()?
+            });
+        }
+
+        let convert = match &arg.default {
+            Some(default) => {
+                let default = arg
+                    .ty
+                    .value(default)
+                    .map_err(|e| self.error(&operation.name, format!("argument {}: {e}", arg.name)))?;
+                quote! {
+                    if value.is_undefined() {
+                        #default
+                    } else {
+                        #convert?
+                    }
+                }
+            }
+            None if arg.optional && !arg.ty.is_nullable() => quote! {
+                if value.is_undefined() {
+                    None
+                } else {
+                    Some(#convert?)
+                }
+            },
+            None => quote! { #convert? },
+        };
+
+        Ok(quote! {
+            {
+                let value = match args.next() {
+                    Some(value) => value,
+                    None => <RT as JSRuntime>::Value::new_undefined(ctx.clone())?,
+                };
+
+                #convert
+            }
+        })
+    }
+
+    /// Chooses the overload by the number of arguments, and by the type of one argument if there are several
+    /// overloads with that number of arguments
+    fn overload_resolution(&self, set: &OverloadSet, bodies: &[TokenStream]) -> Result<TokenStream, String> {
+        let max = set
+            .operations
+            .iter()
+            .map(|(operation, _)| operation.args.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut arms = Vec::new();
+        for n in 0..=max {
+            let candidates: Vec<usize> = set
+                .operations
+                .iter()
+                .enumerate()
+                .filter(|(_, (operation, _))| {
+                    let variadic = operation.args.last().is_some_and(|arg| arg.variadic);
+                    required_args(operation) <= n && (n <= operation.args.len() || variadic)
+                })
+                .map(|(index, _)| index)
+                .collect();
+
+            let selected = match &candidates[..] {
+                [] => continue,
+                [index] => quote! { #index },
+                _ => self.distinguish(set, &candidates, n)?,
+            };
+
+            arms.push(quote! { #n => #selected, });
+        }
+
+        let error = format!("no overload of {} takes {{}} arguments", set.name);
+        let indices = 0..bodies.len();
+
+        Ok(quote! {
+            let selected = match args.len().min(#max) {
+                #(#arms)*
+                n => return Err(idl_type_error(format!(#error, n))),
+            };
+
+            match selected {
+                #(#indices => { #bodies })*
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn distinguish(&self, set: &OverloadSet, candidates: &[usize], n: usize) -> Result<TokenStream, String> {
+        // the type of the argument at `index`, or the type of the variadic argument that it is part of
+        let arg = |candidate: usize, index: usize| {
+            let args = &set.operations[candidate].0.args;
+            args.get(index).or_else(|| args.last().filter(|arg| arg.variadic))
+        };
+
+        let index = (0..n)
+            .find(|&index| {
+                candidates.iter().enumerate().all(|(i, &a)| {
+                    candidates[i + 1..]
+                        .iter()
+                        .all(|&b| match (arg(a, index), arg(b, index)) {
+                            (Some(a), Some(b)) => a.ty.is_distinguishable(&b.ty),
+                            _ => false,
+                        })
+                })
+            })
+            .ok_or_else(|| {
+                self.error(
+                    set.name,
+                    format!("the overloads with {n} arguments can't be distinguished"),
+                )
+            })?;
+
+        let value = quote! { value };
+        let mut checks = Vec::new();
+        let mut fallbacks = Vec::new();
+
+        for &candidate in candidates {
+            let Some(arg) = arg(candidate, index) else {
+                continue;
+            };
+
+            let is_match = arg.ty.is_match(&value);
+            checks.push(if arg.optional {
+                quote! { if #value.is_undefined() || #is_match { #candidate } }
+            } else {
+                quote! { if #is_match { #candidate } }
+            });
+
+            if let Some(priority) = arg.ty.fallback_priority() {
+                fallbacks.push((priority, candidate));
+            }
+        }
+
+        // a value that matches none of the types is converted to a string, number or boolean, in that order
+        let fallback = match fallbacks.iter().min_by_key(|(priority, _)| *priority) {
+            Some((_, candidate)) => quote! { #candidate },
+            None => {
+                let error = format!("argument {} of {} has the wrong type", index + 1, set.name);
+                quote! { return Err(idl_type_error(#error)) }
+            }
+        };
+
+        Ok(quote! {
+            {
+                let value = &args[#index];
+                #(#checks else)* {
+                    #fallback
+                }
+            }
+        })
+    }
+}
+
+/// The number of arguments that can't be left out
+fn required_args(operation: &Operation) -> usize {
+    operation
+        .args
+        .iter()
+        .take_while(|arg| !arg.optional && !arg.variadic)
+        .count()
+}
+
+fn arg_type(arg: &Argument) -> TokenStream {
+    let ty = arg.ty.rust_type(&quote! { RT });
+
+    if arg.variadic {
+        quote! { Vec<#ty> }
+    } else if arg.optional && arg.default.is_none() && !arg.ty.is_nullable() {
+        quote! { Option<#ty> }
+    } else {
+        ty
+    }
+}
+
+/// The receiver, generics and context parameter of a trait method
+fn signature_parts(is_static: bool, runtime: bool, mutable: bool) -> (TokenStream, TokenStream, TokenStream) {
+    let receiver = match (is_static, mutable) {
+        (true, _) => TokenStream::new(),
+        (false, true) => quote! { &mut self, },
+        (false, false) => quote! { &self, },
+    };
+
+    if runtime {
+        (receiver, quote! { <RT: JSRuntime> }, quote! { ctx: RT::Context })
+    } else {
+        (receiver, TokenStream::new(), TokenStream::new())
+    }
+}
+
+/// The receiver, turbofish and context argument for calling a trait method from the closures in the define function
+fn call_parts(is_static: bool, runtime: bool, mutable: bool) -> (TokenStream, TokenStream, TokenStream) {
+    let receiver = match (is_static, mutable) {
+        (true, _) => TokenStream::new(),
+        (false, true) => quote! { &mut *s.borrow_mut(), },
+        (false, false) => quote! { &*s.borrow(), },
+    };
+
+    if runtime {
+        (receiver, quote! { ::<RT> }, quote! { ctx.clone() })
+    } else {
+        (receiver, TokenStream::new(), TokenStream::new())
+    }
+}
+
+fn capture(is_static: bool) -> TokenStream {
+    if is_static {
+        TokenStream::new()
+    } else {
+        quote! { let s = Rc::clone(&s); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parser::Parser;
+
+    #[test]
+    fn interface_ancestors() {
+        let definitions = Parser::parse(
+            "interface EventTarget {}; interface Node : EventTarget {}; interface Element : Node {}; \
+             interface HTMLElement : Element {}; interface Loop : Loop {}; interface Window : Global {};",
+        )
+        .unwrap();
+        let interface = |name: &str| {
+            definitions
+                .iter()
+                .find_map(|definition| match definition {
+                    Definition::Interface(interface) if interface.name == name => Some(interface),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(
+            ancestors(interface("HTMLElement"), &definitions),
+            ["HTMLElement", "Element", "Node", "EventTarget"]
+        );
+        assert_eq!(ancestors(interface("EventTarget"), &definitions), ["EventTarget"]);
+        assert_eq!(ancestors(interface("Loop"), &definitions), ["Loop"]);
+        // the parent is defined in another fragment
+        assert_eq!(ancestors(interface("Window"), &definitions), ["Window", "Global"]);
+    }
+}
@@ -0,0 +1,671 @@
+use crate::idl::types::{IdlType, IntegerConversion, IntegerType, StringType};
+
+#[derive(Clone, Debug)]
+pub(crate) enum Definition {
+    Interface(Interface),
+    Dictionary(Dictionary),
+    Enum(Enum),
+    Typedef(Typedef),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Interface {
+    pub(crate) name: String,
+    pub(crate) parent: Option<String>,
+    pub(crate) members: Vec<Member>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Member {
+    Const(Const),
+    Attribute(Attribute),
+    Operation(Operation),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Const {
+    pub(crate) name: String,
+    pub(crate) ty: IdlType,
+    pub(crate) value: Literal,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Attribute {
+    pub(crate) name: String,
+    pub(crate) ty: IdlType,
+    pub(crate) readonly: bool,
+    pub(crate) is_static: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Operation {
+    pub(crate) name: String,
+    pub(crate) ret: IdlType,
+    pub(crate) args: Vec<Argument>,
+    pub(crate) is_static: bool,
+    /// `[Pure]`, the operation doesn't change the object, so the rust method takes `&self`
+    pub(crate) pure: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Argument {
+    pub(crate) name: String,
+    pub(crate) ty: IdlType,
+    pub(crate) optional: bool,
+    pub(crate) variadic: bool,
+    pub(crate) default: Option<Literal>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Dictionary {
+    pub(crate) name: String,
+    pub(crate) parent: Option<String>,
+    pub(crate) members: Vec<DictionaryMember>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DictionaryMember {
+    pub(crate) name: String,
+    pub(crate) ty: IdlType,
+    pub(crate) required: bool,
+    pub(crate) default: Option<Literal>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Enum {
+    pub(crate) name: String,
+    pub(crate) values: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Typedef {
+    pub(crate) name: String,
+    pub(crate) ty: IdlType,
+}
+
+/// Constant values and defaults of arguments and dictionary members
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Literal {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Null,
+    EmptyDictionary,
+    EmptySequence,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Punct(char),
+    Ellipsis,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+
+    while let Some(&c) = chars.get(pos) {
+        let next = chars.get(pos + 1).copied();
+
+        match c {
+            '\n' => {
+                line += 1;
+                pos += 1;
+            }
+            c if c.is_whitespace() => pos += 1,
+            '/' if next == Some('/') => {
+                while chars.get(pos).is_some_and(|&c| c != '\n') {
+                    pos += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                pos += 2;
+                loop {
+                    match chars.get(pos) {
+                        Some('*') if chars.get(pos + 1) == Some(&'/') => break,
+                        Some('\n') => line += 1,
+                        Some(_) => {}
+                        None => return Err(format!("line {line}: unterminated comment")),
+                    }
+                    pos += 1;
+                }
+                pos += 2;
+            }
+            '"' => {
+                let end = chars[pos + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .ok_or_else(|| format!("line {line}: unterminated string"))?;
+                tokens.push((Token::String(chars[pos + 1..pos + 1 + end].iter().collect()), line));
+                pos += end + 2;
+            }
+            '.' => {
+                if chars.get(pos..pos + 3) != Some(&['.', '.', '.'][..]) {
+                    return Err(format!("line {line}: unexpected '.'"));
+                }
+                tokens.push((Token::Ellipsis, line));
+                pos += 3;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|c| c.is_ascii_digit() || c == '.')) => {
+                let start = pos;
+                pos += 1;
+                while let Some(&c) = chars.get(pos) {
+                    let exponent_sign = matches!(c, '-' | '+') && matches!(chars[pos - 1], 'e' | 'E');
+                    if c.is_ascii_alphanumeric() || c == '.' || exponent_sign {
+                        pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                let number: String = chars[start..pos].iter().collect();
+                let token = parse_number(&number).ok_or_else(|| format!("line {line}: invalid number {number}"))?;
+                tokens.push((token, line));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = pos;
+                while chars
+                    .get(pos)
+                    .is_some_and(|&c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    pos += 1;
+                }
+
+                // a leading underscore escapes identifiers that would otherwise be keywords
+                let ident: String = chars[start..pos].iter().collect();
+                let ident = ident.strip_prefix('_').map(str::to_owned).unwrap_or(ident);
+                tokens.push((Token::Ident(ident), line));
+            }
+            c => {
+                tokens.push((Token::Punct(c), line));
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_number(number: &str) -> Option<Token> {
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number),
+    };
+    let sign = if negative { -1 } else { 1 };
+
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).ok().map(|n| Token::Integer(sign * n));
+    }
+
+    if digits.contains(['.', 'e', 'E']) {
+        return number.parse().ok().map(Token::Float);
+    }
+
+    if digits.len() > 1 && digits.starts_with('0') {
+        return i64::from_str_radix(&digits[1..], 8)
+            .ok()
+            .map(|n| Token::Integer(sign * n));
+    }
+
+    number.parse().ok().map(Token::Integer)
+}
+
+/// Extended attributes that change how a value is converted, all others are ignored
+#[derive(Default)]
+struct ExtendedAttributes {
+    enforce_range: bool,
+    clamp: bool,
+    pure: bool,
+}
+
+impl ExtendedAttributes {
+    fn apply(&self, ty: IdlType) -> IdlType {
+        let conversion = if self.enforce_range {
+            IntegerConversion::EnforceRange
+        } else if self.clamp {
+            IntegerConversion::Clamp
+        } else {
+            return ty;
+        };
+
+        match ty {
+            IdlType::Integer(int, _) => IdlType::Integer(int, conversion),
+            IdlType::Nullable(inner) => IdlType::Nullable(Box::new(self.apply(*inner))),
+            ty => ty,
+        }
+    }
+}
+
+pub(crate) struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    pub(crate) fn parse(source: &str) -> Result<Vec<Definition>, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+
+        let mut definitions = Vec::new();
+        while parser.peek().is_some() {
+            definitions.push(parser.definition()?);
+        }
+
+        Ok(definitions)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => Some(ident),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, String> {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line);
+
+        Err(format!("line {line}: {}", message.into()))
+    }
+
+    fn eat_punct(&mut self, punct: char) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, punct: char) -> Result<(), String> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{punct}'"))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_ident() == Some(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => self.error("expected an identifier"),
+        }
+    }
+
+    fn extended_attributes(&mut self) -> Result<ExtendedAttributes, String> {
+        let mut attributes = ExtendedAttributes::default();
+
+        if !self.eat_punct('[') {
+            return Ok(attributes);
+        }
+
+        let mut depth = 0;
+        let mut item_start = true;
+        loop {
+            match self.next() {
+                Some(Token::Punct(']')) if depth == 0 => break,
+                Some(Token::Punct('[' | '(' | '{')) => depth += 1,
+                Some(Token::Punct(']' | ')' | '}')) => depth -= 1,
+                Some(Token::Punct(',')) if depth == 0 => {
+                    item_start = true;
+                    continue;
+                }
+                Some(Token::Ident(name)) if depth == 0 && item_start => match &*name {
+                    "EnforceRange" => attributes.enforce_range = true,
+                    "Clamp" => attributes.clamp = true,
+                    "Pure" => attributes.pure = true,
+                    _ => {}
+                },
+                Some(_) => {}
+                None => return self.error("unterminated extended attributes"),
+            }
+            item_start = false;
+        }
+
+        Ok(attributes)
+    }
+
+    fn definition(&mut self) -> Result<Definition, String> {
+        self.extended_attributes()?;
+
+        let keyword = self.ident()?;
+        let definition = match &*keyword {
+            "interface" if self.peek_ident() == Some("mixin") => {
+                return self.error("interface mixins are not supported")
+            }
+            "interface" => Definition::Interface(self.interface()?),
+            "dictionary" => Definition::Dictionary(self.dictionary()?),
+            "enum" => Definition::Enum(self.enumeration()?),
+            "typedef" => {
+                let ty = self.ty()?;
+                let name = self.ident()?;
+                Definition::Typedef(Typedef { name, ty })
+            }
+            "partial" | "callback" | "namespace" => {
+                return self.error(format!("{keyword} definitions are not supported"))
+            }
+            _ if self.peek_ident() == Some("includes") => return self.error("includes statements are not supported"),
+            _ => return self.error(format!("unexpected {keyword:?}")),
+        };
+
+        self.expect_punct(';')?;
+
+        Ok(definition)
+    }
+
+    fn inheritance(&mut self) -> Result<Option<String>, String> {
+        if self.eat_punct(':') {
+            Ok(Some(self.ident()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn interface(&mut self) -> Result<Interface, String> {
+        let name = self.ident()?;
+        let parent = self.inheritance()?;
+        self.expect_punct('{')?;
+
+        let mut members = Vec::new();
+        while !self.eat_punct('}') {
+            members.push(self.member()?);
+        }
+
+        Ok(Interface { name, parent, members })
+    }
+
+    fn member(&mut self) -> Result<Member, String> {
+        let attributes = self.extended_attributes()?;
+
+        if self.eat_keyword("const") {
+            let ty = self.ty()?;
+            let name = self.ident()?;
+            self.expect_punct('=')?;
+            let value = self.literal()?;
+            self.expect_punct(';')?;
+            return Ok(Member::Const(Const { name, ty, value }));
+        }
+
+        let is_static = self.eat_keyword("static");
+
+        if let Some(
+            keyword @ ("constructor" | "getter" | "setter" | "deleter" | "stringifier" | "inherit" | "iterable"
+            | "async" | "maplike" | "setlike"),
+        ) = self.peek_ident()
+        {
+            return self.error(format!("{keyword} members are not supported"));
+        }
+
+        let readonly = self.eat_keyword("readonly");
+        if self.eat_keyword("attribute") {
+            let ty = attributes.apply(self.ty()?);
+            let name = self.ident()?;
+            self.expect_punct(';')?;
+            return Ok(Member::Attribute(Attribute {
+                name,
+                ty,
+                readonly,
+                is_static,
+            }));
+        }
+        if readonly {
+            return self.error("expected 'attribute'");
+        }
+
+        let ret = self.ty()?;
+        let name = self.ident()?;
+        let args = self.arguments()?;
+        self.expect_punct(';')?;
+
+        Ok(Member::Operation(Operation {
+            name,
+            ret,
+            args,
+            is_static,
+            pure: attributes.pure,
+        }))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Argument>, String> {
+        self.expect_punct('(')?;
+
+        let mut args: Vec<Argument> = Vec::new();
+        while !self.eat_punct(')') {
+            if !args.is_empty() {
+                self.expect_punct(',')?;
+            }
+            if args.last().is_some_and(|arg| arg.variadic) {
+                return self.error("a variadic argument must be the last argument");
+            }
+
+            let attributes = self.extended_attributes()?;
+            let optional = self.eat_keyword("optional");
+            let ty = attributes.apply(self.ty()?);
+            let variadic = matches!(self.peek(), Some(Token::Ellipsis));
+            if variadic {
+                self.pos += 1;
+            }
+            let name = self.ident()?;
+
+            let default = if self.eat_punct('=') {
+                if !optional {
+                    return self.error(format!(
+                        "only optional arguments can have a default, {name} isn't optional"
+                    ));
+                }
+                Some(self.literal()?)
+            } else {
+                None
+            };
+
+            if !optional && !variadic && args.last().is_some_and(|arg| arg.optional) {
+                return self.error(format!("required argument {name} follows an optional argument"));
+            }
+
+            args.push(Argument {
+                name,
+                ty,
+                optional,
+                variadic,
+                default,
+            });
+        }
+
+        Ok(args)
+    }
+
+    fn dictionary(&mut self) -> Result<Dictionary, String> {
+        let name = self.ident()?;
+        let parent = self.inheritance()?;
+        self.expect_punct('{')?;
+
+        let mut members = Vec::new();
+        while !self.eat_punct('}') {
+            let attributes = self.extended_attributes()?;
+            let required = self.eat_keyword("required");
+            let ty = attributes.apply(self.ty()?);
+            let name = self.ident()?;
+            let default = if self.eat_punct('=') {
+                Some(self.literal()?)
+            } else {
+                None
+            };
+            self.expect_punct(';')?;
+
+            if required && default.is_some() {
+                return self.error(format!("required member {name} can't have a default"));
+            }
+
+            members.push(DictionaryMember {
+                name,
+                ty,
+                required,
+                default,
+            });
+        }
+
+        Ok(Dictionary { name, parent, members })
+    }
+
+    fn enumeration(&mut self) -> Result<Enum, String> {
+        let name = self.ident()?;
+        self.expect_punct('{')?;
+
+        let mut values = Vec::new();
+        while !self.eat_punct('}') {
+            match self.next() {
+                Some(Token::String(value)) => values.push(value),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("expected a string");
+                }
+            }
+            if !self.eat_punct(',') {
+                self.expect_punct('}')?;
+                break;
+            }
+        }
+
+        Ok(Enum { name, values })
+    }
+
+    fn ty(&mut self) -> Result<IdlType, String> {
+        let attributes = self.extended_attributes()?;
+
+        if self.peek() == Some(&Token::Punct('(')) {
+            return self.error("union types are not supported");
+        }
+
+        let name = self.ident()?;
+        let ty = match &*name {
+            "undefined" | "void" => IdlType::Undefined,
+            "boolean" => IdlType::Boolean,
+            "byte" => IdlType::Integer(IntegerType::Byte, IntegerConversion::Modulo),
+            "octet" => IdlType::Integer(IntegerType::Octet, IntegerConversion::Modulo),
+            "short" => IdlType::Integer(IntegerType::Short, IntegerConversion::Modulo),
+            "long" if self.eat_keyword("long") => IdlType::Integer(IntegerType::LongLong, IntegerConversion::Modulo),
+            "long" => IdlType::Integer(IntegerType::Long, IntegerConversion::Modulo),
+            "unsigned" => match &*self.ident()? {
+                "short" => IdlType::Integer(IntegerType::UnsignedShort, IntegerConversion::Modulo),
+                "long" if self.eat_keyword("long") => {
+                    IdlType::Integer(IntegerType::UnsignedLongLong, IntegerConversion::Modulo)
+                }
+                "long" => IdlType::Integer(IntegerType::UnsignedLong, IntegerConversion::Modulo),
+                other => return self.error(format!("unexpected unsigned {other}")),
+            },
+            "float" => IdlType::Float {
+                double: false,
+                unrestricted: false,
+            },
+            "double" => IdlType::Float {
+                double: true,
+                unrestricted: false,
+            },
+            "unrestricted" => match &*self.ident()? {
+                "float" => IdlType::Float {
+                    double: false,
+                    unrestricted: true,
+                },
+                "double" => IdlType::Float {
+                    double: true,
+                    unrestricted: true,
+                },
+                other => return self.error(format!("unexpected unrestricted {other}")),
+            },
+            "DOMString" => IdlType::String(StringType::Dom),
+            "USVString" => IdlType::String(StringType::Usv),
+            "ByteString" => IdlType::String(StringType::Byte),
+            "any" => IdlType::Any,
+            "object" => IdlType::Object,
+            "sequence" => {
+                self.expect_punct('<')?;
+                let inner = self.ty()?;
+                self.expect_punct('>')?;
+                IdlType::Sequence(Box::new(inner))
+            }
+            "Promise" | "record" | "FrozenArray" | "ObservableArray" | "bigint" | "symbol" | "ArrayBuffer"
+            | "DataView" => return self.error(format!("{name} types are not supported")),
+            _ => IdlType::Named(name),
+        };
+
+        let ty = if self.eat_punct('?') {
+            if matches!(ty, IdlType::Any | IdlType::Undefined | IdlType::Nullable(_)) {
+                return self.error("this type can't be nullable");
+            }
+            IdlType::Nullable(Box::new(ty))
+        } else {
+            ty
+        };
+
+        Ok(attributes.apply(ty))
+    }
+
+    fn literal(&mut self) -> Result<Literal, String> {
+        let literal = match self.next() {
+            Some(Token::Ident(ident)) => match &*ident {
+                "true" => Literal::Bool(true),
+                "false" => Literal::Bool(false),
+                "null" => Literal::Null,
+                "Infinity" => Literal::Float(f64::INFINITY),
+                "NaN" => Literal::Float(f64::NAN),
+                _ => {
+                    self.pos -= 1;
+                    return self.error(format!("unexpected {ident:?}"));
+                }
+            },
+            Some(Token::Punct('-')) if self.eat_keyword("Infinity") => Literal::Float(f64::NEG_INFINITY),
+            Some(Token::Integer(value)) => Literal::Integer(value),
+            Some(Token::Float(value)) => Literal::Float(value),
+            Some(Token::String(value)) => Literal::String(value),
+            Some(Token::Punct('{')) => {
+                self.expect_punct('}')?;
+                Literal::EmptyDictionary
+            }
+            Some(Token::Punct('[')) => {
+                self.expect_punct(']')?;
+                Literal::EmptySequence
+            }
+            _ => {
+                self.pos -= 1;
+                return self.error("expected a value");
+            }
+        };
+
+        Ok(literal)
+    }
+}
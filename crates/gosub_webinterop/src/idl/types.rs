@@ -0,0 +1,468 @@
+use proc_macro2::{Ident, Literal as LiteralToken, Span, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::idl::parser::Literal;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum IdlType {
+    Undefined,
+    Boolean,
+    Integer(IntegerType, IntegerConversion),
+    Float {
+        double: bool,
+        unrestricted: bool,
+    },
+    String(StringType),
+    Any,
+    Object,
+    Sequence(Box<IdlType>),
+    Nullable(Box<IdlType>),
+    /// A dictionary, enum or typedef
+    Named(String),
+    /// An interface, its values are platform objects
+    Interface(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum IntegerType {
+    Byte,
+    Octet,
+    Short,
+    UnsignedShort,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum IntegerConversion {
+    Modulo,
+    EnforceRange,
+    Clamp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum StringType {
+    /// `DOMString`
+    Dom,
+    /// `USVString`
+    Usv,
+    /// `ByteString`
+    Byte,
+}
+
+/// Groups of types that overloads can be distinguished by
+#[derive(Clone, Debug, PartialEq)]
+enum Category {
+    Boolean,
+    Numeric,
+    String,
+    Object,
+    Sequence,
+    Named(String),
+    Interface(String),
+    Any,
+    Undefined,
+}
+
+impl IntegerType {
+    fn rust_type(self) -> TokenStream {
+        match self {
+            IntegerType::Byte => quote! { i8 },
+            IntegerType::Octet => quote! { u8 },
+            IntegerType::Short => quote! { i16 },
+            IntegerType::UnsignedShort => quote! { u16 },
+            IntegerType::Long => quote! { i32 },
+            IntegerType::UnsignedLong => quote! { u32 },
+            IntegerType::LongLong => quote! { i64 },
+            IntegerType::UnsignedLongLong => quote! { u64 },
+        }
+    }
+
+    fn is_unsigned(self) -> bool {
+        matches!(
+            self,
+            IntegerType::Octet | IntegerType::UnsignedShort | IntegerType::UnsignedLong | IntegerType::UnsignedLongLong
+        )
+    }
+}
+
+impl IdlType {
+    /// Replaces named types with the type they are a typedef for, and marks the names of interfaces
+    pub(crate) fn resolve(&mut self, typedefs: &[(String, IdlType)], interfaces: &[&str]) {
+        match self {
+            IdlType::Sequence(inner) | IdlType::Nullable(inner) => inner.resolve(typedefs, interfaces),
+            IdlType::Named(name) => {
+                if let Some((_, ty)) = typedefs.iter().find(|(typedef, _)| typedef == name) {
+                    *self = ty.clone();
+                    self.resolve(typedefs, interfaces);
+                } else if interfaces.contains(&name.as_str()) {
+                    *self = IdlType::Interface(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the rust type depends on the runtime (`any`, `object` and interfaces), which makes the rust method
+    /// generic
+    pub(crate) fn uses_runtime(&self) -> bool {
+        match self {
+            IdlType::Any | IdlType::Object | IdlType::Interface(_) => true,
+            IdlType::Sequence(inner) | IdlType::Nullable(inner) => inner.uses_runtime(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn is_nullable(&self) -> bool {
+        matches!(self, IdlType::Nullable(_))
+    }
+
+    /// The rust type, `rt` is the runtime that `any`, `object` and interfaces belong to
+    pub(crate) fn rust_type(&self, rt: &TokenStream) -> TokenStream {
+        match self {
+            IdlType::Undefined => quote! { () },
+            IdlType::Boolean => quote! { bool },
+            IdlType::Integer(int, _) => int.rust_type(),
+            IdlType::Float { double: true, .. } => quote! { f64 },
+            IdlType::Float { double: false, .. } => quote! { f32 },
+            IdlType::String(_) => quote! { String },
+            IdlType::Any => quote! { <#rt as JSRuntime>::Value },
+            IdlType::Object => quote! { <#rt as JSRuntime>::Object },
+            IdlType::Sequence(inner) => {
+                let inner = inner.rust_type(rt);
+                quote! { Vec<#inner> }
+            }
+            IdlType::Nullable(inner) => {
+                let inner = inner.rust_type(rt);
+                quote! { Option<#inner> }
+            }
+            IdlType::Named(name) => {
+                let ident = type_ident(name);
+                quote! { #ident }
+            }
+            IdlType::Interface(_) => quote! { PlatformObject<#rt> },
+        }
+    }
+
+    /// Expression that converts the javascript value `value` to the rust type and evaluates to a `Result`. `value`
+    /// is either an owned value or a reference to one, only `any` needs an owned value.
+    pub(crate) fn to_rust(&self, value: &Ident, owned: bool) -> Result<TokenStream, String> {
+        let value_ref = if owned {
+            quote! { &#value }
+        } else {
+            quote! { #value }
+        };
+        let item = format_ident!("item");
+
+        Ok(match self {
+            IdlType::Undefined => return Err("undefined can only be used as a return type".to_owned()),
+            IdlType::Boolean => quote! { idl_boolean(#value_ref) },
+            IdlType::Integer(int, conversion) => {
+                let ty = int.rust_type();
+                let conversion = match conversion {
+                    IntegerConversion::Modulo => quote! { IntegerConversion::Modulo },
+                    IntegerConversion::EnforceRange => quote! { IntegerConversion::EnforceRange },
+                    IntegerConversion::Clamp => quote! { IntegerConversion::Clamp },
+                };
+                quote! { idl_integer::<#ty, _>(#value_ref, #conversion) }
+            }
+            IdlType::Float {
+                double: true,
+                unrestricted,
+            } => quote! { idl_double(#value_ref, #unrestricted) },
+            IdlType::Float {
+                double: false,
+                unrestricted,
+            } => quote! { idl_float(#value_ref, #unrestricted) },
+            IdlType::String(StringType::Byte) => quote! { idl_byte_string(#value_ref) },
+            IdlType::String(_) => quote! { idl_string(#value_ref) },
+            IdlType::Any if owned => quote! { Result::<_>::Ok(#value) },
+            IdlType::Any => return Err("any is not supported in this position".to_owned()),
+            IdlType::Object => quote! { idl_object(#value_ref) },
+            IdlType::Sequence(inner) => {
+                let inner = inner.to_rust(&item, true)?;
+                quote! { idl_sequence(#value_ref, |#item| #inner) }
+            }
+            IdlType::Nullable(inner) => {
+                let inner = inner.to_rust(&item, false)?;
+                quote! { idl_nullable(#value_ref, |#item| #inner) }
+            }
+            IdlType::Named(name) => {
+                let ident = type_ident(name);
+                quote! { <#ident as FromIdl<_>>::from_idl(#value_ref) }
+            }
+            IdlType::Interface(name) => quote! { idl_interface(#value_ref, #name) },
+        })
+    }
+
+    /// Expression that converts the rust value `expr` (or a reference to it) to a javascript value of runtime `rt` and
+    /// evaluates to a `Result`. Expects the context in `ctx`.
+    pub(crate) fn to_js(&self, expr: TokenStream, rt: &TokenStream) -> TokenStream {
+        match self {
+            IdlType::Any => quote! { Ok(#expr) },
+            IdlType::Object => quote! { Ok(<#rt as JSRuntime>::Value::from(#expr)) },
+            IdlType::Interface(_) => quote! { (#expr).into_js(ctx.clone()) },
+            IdlType::Nullable(inner) => {
+                let inner = inner.to_js(quote! { value }, rt);
+                quote! {
+                    match #expr {
+                        Some(value) => #inner,
+                        None => <#rt as JSRuntime>::Value::new_null(ctx.clone()),
+                    }
+                }
+            }
+            IdlType::Sequence(inner) => {
+                let inner = inner.to_js(quote! { item }, rt);
+                quote! {
+                    (#expr)
+                        .into_iter()
+                        .map(|item| #inner)
+                        .collect::<Result<Vec<_>>>()
+                        .and_then(|data| <#rt as JSRuntime>::Array::new_with_data(ctx.clone(), &data))
+                        .map(|array| array.as_value())
+                }
+            }
+            _ => quote! {
+                {
+                    let value: Result<<#rt as JSRuntime>::Value> = (#expr).to_js_value(ctx.clone());
+                    value
+                }
+            },
+        }
+    }
+
+    /// Expression that checks whether the javascript value (a reference) has the right type, used for overloads
+    pub(crate) fn is_match(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            IdlType::Undefined => quote! { #value.is_undefined() },
+            IdlType::Boolean => quote! { #value.is_bool() },
+            IdlType::Integer(..) | IdlType::Float { .. } => quote! { #value.is_number() },
+            IdlType::String(_) => quote! { #value.is_string() },
+            IdlType::Any => quote! { true },
+            IdlType::Object => quote! { #value.is_object() },
+            IdlType::Sequence(_) => quote! { #value.is_array() },
+            IdlType::Nullable(inner) => {
+                let inner = inner.is_match(value);
+                quote! { (#value.is_null() || #value.is_undefined() || #inner) }
+            }
+            IdlType::Named(name) => {
+                let ident = type_ident(name);
+                quote! { <#ident as FromIdl<_>>::is_match(#value) }
+            }
+            IdlType::Interface(name) => quote! { idl_implements(#value, #name) },
+        }
+    }
+
+    fn category(&self) -> Category {
+        match self {
+            IdlType::Undefined => Category::Undefined,
+            IdlType::Boolean => Category::Boolean,
+            IdlType::Integer(..) | IdlType::Float { .. } => Category::Numeric,
+            IdlType::String(_) => Category::String,
+            IdlType::Any => Category::Any,
+            IdlType::Object => Category::Object,
+            IdlType::Sequence(_) => Category::Sequence,
+            IdlType::Nullable(inner) => inner.category(),
+            IdlType::Named(name) => Category::Named(name.clone()),
+            IdlType::Interface(name) => Category::Interface(name.clone()),
+        }
+    }
+
+    /// Whether an overload can be chosen over another one by the type of the value
+    pub(crate) fn is_distinguishable(&self, other: &IdlType) -> bool {
+        let (a, b) = (self.category(), other.category());
+
+        if a == b || a == Category::Any || b == Category::Any || (self.is_nullable() && other.is_nullable()) {
+            return false;
+        }
+
+        // interfaces can inherit from each other, and a dictionary accepts any object, so interfaces are only
+        // distinguished from the types that platform objects can never match
+        !matches!(
+            (a, b),
+            (Category::Object, Category::Sequence | Category::Named(_))
+                | (Category::Sequence | Category::Named(_), Category::Object)
+                | (
+                    Category::Interface(_),
+                    Category::Object | Category::Named(_) | Category::Interface(_)
+                )
+                | (Category::Object | Category::Named(_), Category::Interface(_))
+        )
+    }
+
+    /// Priority of the overload to use when the value doesn't match any of the types, lower is preferred
+    pub(crate) fn fallback_priority(&self) -> Option<u8> {
+        match self.category() {
+            Category::String => Some(0),
+            Category::Numeric => Some(1),
+            Category::Boolean => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Rust expression for a default or constant value of this type
+    pub(crate) fn value(&self, literal: &Literal) -> Result<TokenStream, String> {
+        let mismatch = || Err(format!("{literal:?} is not a valid value for {self:?}"));
+
+        Ok(match (self, literal) {
+            (IdlType::Nullable(_), Literal::Null) => quote! { None },
+            (IdlType::Nullable(inner), literal) => {
+                let value = inner.value(literal)?;
+                quote! { Some(#value) }
+            }
+            (IdlType::Boolean, Literal::Bool(value)) => quote! { #value },
+            (IdlType::Integer(int, _), Literal::Integer(value)) => {
+                if *value < 0 && int.is_unsigned() {
+                    return mismatch();
+                }
+                signed(LiteralToken::u64_unsuffixed(value.unsigned_abs()), *value < 0)
+            }
+            (IdlType::Float { double, .. }, Literal::Integer(value)) => {
+                let abs = value.unsigned_abs() as f64;
+                let literal = if *double {
+                    LiteralToken::f64_unsuffixed(abs)
+                } else {
+                    LiteralToken::f32_unsuffixed(abs as f32)
+                };
+                signed(literal, *value < 0)
+            }
+            (IdlType::Float { double, .. }, Literal::Float(value)) => {
+                let ty = if *double {
+                    quote! { f64 }
+                } else {
+                    quote! { f32 }
+                };
+                if value.is_nan() {
+                    quote! { #ty::NAN }
+                } else if value.is_infinite() {
+                    signed(quote! { #ty::INFINITY }, *value < 0.0)
+                } else if *double {
+                    signed(LiteralToken::f64_unsuffixed(value.abs()), *value < 0.0)
+                } else {
+                    signed(LiteralToken::f32_unsuffixed(value.abs() as f32), *value < 0.0)
+                }
+            }
+            (IdlType::String(_), Literal::String(value)) => quote! { #value.to_owned() },
+            (IdlType::Named(name), Literal::String(value)) => {
+                let ident = type_ident(name);
+                let message = format!("{value:?} is not a value of {name}");
+                quote! { #ident::from_value(#value).expect(#message) }
+            }
+            (IdlType::Named(name), Literal::EmptyDictionary) => {
+                let ident = type_ident(name);
+                quote! { <#ident as Default>::default() }
+            }
+            (IdlType::Sequence(_), Literal::EmptySequence) => quote! { Vec::new() },
+            _ => return mismatch(),
+        })
+    }
+}
+
+fn signed(value: impl quote::ToTokens, negative: bool) -> TokenStream {
+    if negative {
+        quote! { -#value }
+    } else {
+        quote! { #value }
+    }
+}
+
+/// Converts a WebIDL name like `getElementById` or `innerHTML` to snake case
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+
+    for (index, &c) in chars.iter().enumerate() {
+        if c == '-' {
+            out.push('_');
+        } else if c.is_ascii_uppercase() {
+            let prev = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+
+            let boundary = prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(|p| p.is_ascii_uppercase()) && next.is_some_and(|n| n.is_ascii_lowercase()));
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// Snake case identifier for a WebIDL name, using raw identifiers for rust keywords
+pub(crate) fn rust_ident(name: &str) -> Ident {
+    let name = snake_case(name);
+
+    if syn::parse_str::<Ident>(&name).is_ok() {
+        return Ident::new(&name, Span::call_site());
+    }
+
+    match &*name {
+        "self" | "super" | "crate" => format_ident!("{}_", name),
+        _ => Ident::new_raw(&name, Span::call_site()),
+    }
+}
+
+pub(crate) fn type_ident(name: &str) -> Ident {
+    Ident::new(&name.replace('-', "_"), Span::call_site())
+}
+
+/// Name of the enum variant for a WebIDL enum value, like `NoReferrer` for `"no-referrer"`
+pub(crate) fn variant_name(value: &str) -> String {
+    let name: String = value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if name.is_empty() {
+        "Empty".to_owned()
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Value{name}")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interface_types() {
+        let typedefs = vec![(
+            "MaybeNode".to_owned(),
+            IdlType::Nullable(Box::new(IdlType::Named("Node".to_owned()))),
+        )];
+
+        let mut ty = IdlType::Sequence(Box::new(IdlType::Named("MaybeNode".to_owned())));
+        ty.resolve(&typedefs, &["Node"]);
+        let node = IdlType::Interface("Node".to_owned());
+        assert_eq!(
+            ty,
+            IdlType::Sequence(Box::new(IdlType::Nullable(Box::new(node.clone()))))
+        );
+        assert!(ty.uses_runtime());
+
+        let mut dictionary = IdlType::Named("Options".to_owned());
+        dictionary.resolve(&typedefs, &["Node"]);
+        assert_eq!(dictionary, IdlType::Named("Options".to_owned()));
+
+        assert!(node.is_distinguishable(&IdlType::String(StringType::Dom)));
+        assert!(node.is_distinguishable(&IdlType::Sequence(Box::new(IdlType::Boolean))));
+        assert!(!node.is_distinguishable(&IdlType::Object));
+        assert!(!node.is_distinguishable(&dictionary));
+        assert!(!node.is_distinguishable(&IdlType::Interface("Element".to_owned())));
+    }
+}
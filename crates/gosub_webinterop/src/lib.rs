@@ -18,6 +18,7 @@ use crate::types::{Arg, ArgVariant, Field, GenericsMatcher, ReturnType, SelfType
 use crate::utils::crate_name;
 
 mod function;
+mod idl;
 mod impl_function;
 mod impl_interop_struct;
 mod property;
@@ -156,6 +157,22 @@ pub fn web_fns(attr: TokenStream, item: TokenStream) -> TokenStream {
     out.into()
 }
 
+/// Generates bindings from a WebIDL fragment. Every interface `Foo` becomes a `FooInterface` trait for the rust
+/// type that backs it, a `define_foo::<RT, T>(obj, s, ctx)` function that installs its members on a javascript
+/// object and a `wrap_foo(s)` function that turns the rust value into a `PlatformObject`, which is what values of
+/// interface types are. Dictionaries become structs and enums become rust enums. Operations take `&mut self`,
+/// unless they are marked `[Pure]`. Like the other macros, the generated code expects `gosub_webexecutor::js::*`,
+/// `Rc`, `RefCell` and `Result` to be in scope.
+#[proc_macro]
+pub fn web_idl(input: TokenStream) -> TokenStream {
+    let source: syn::LitStr = syn::parse_macro_input!(input);
+
+    match idl::web_idl(&source.value()) {
+        Ok(out) => out.into(),
+        Err(e) => syn::Error::new(source.span(), e).to_compile_error().into(),
+    }
+}
+
 struct Options {
     refs: Option<u8>,
     marker_struct: Option<Ident>,
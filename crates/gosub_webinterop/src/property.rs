@@ -176,7 +176,7 @@ impl FunctionProperty {
                     attr.parse_nested_meta(|meta| {
                         if name_found {
                             let prim = Primitive::get(&meta.path.to_token_stream().to_string());
-                            if types.iter().any(|(_, p)| p == &prim) {
+                            if prim != Primitive::Object && types.iter().any(|(_, p)| p == &prim) {
                                 panic!("Cannot have multiple {:?}s in generic attribute", prim);
                             }
                             types.push((meta.path, prim));
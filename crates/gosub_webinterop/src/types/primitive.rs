@@ -29,7 +29,8 @@ impl Primitive {
             Primitive::String => quote! { #arg_name.is_string() },
             Primitive::Boolean => quote! { #arg_name.is_boolean() },
             Primitive::UndefinedNull => quote! { #arg_name.is_undefined() || #arg_name.is_null() },
-            Primitive::Object => quote! { #arg_name.is_object() }, //TODO we need better checks here, (e.g strict check, so fields are matched too)
+            // several rust types can be objects, the one the value converts to is chosen, see `Function::generic`
+            Primitive::Object => quote! { #arg_name.is_object() },
         }
     }
 }
@@ -1,19 +1,112 @@
+use anyhow::anyhow;
+use gosub_css3::system::Css3System;
+use gosub_html5::document::builder::DocumentBuilderImpl;
+use gosub_html5::document::document_impl::DocumentImpl;
+use gosub_html5::parser::Html5Parser;
+use gosub_jsapi::console::WritablePrinter;
+use gosub_jsapi::script::NetScriptFetcher;
+use gosub_jsapi::storage::{WebStorage, DEFAULT_QUOTA};
+use gosub_shared::byte_stream::{ByteStream, Encoding};
+use gosub_shared::document::DocumentHandle;
+use gosub_shared::traits::document::{Document, DocumentBuilder};
 use gosub_shared::types::Result;
 use gosub_v8::{V8Context, V8Engine};
-use gosub_webexecutor::js::{JSContext, JSRuntime, JSValue};
+use gosub_webexecutor::event_loop::EventLoop;
+use gosub_webexecutor::js::{JSContext, JSValue, ModuleLoader, ScriptRunner};
+use std::cell::RefCell;
 use std::env::args;
+use std::io::stdout;
+use std::rc::Rc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use url::Url;
 
 fn main() -> Result<()> {
     let file = args().nth(1).expect("no file given");
 
     let mut runtime = V8Engine::new();
-    let mut ctx: V8Context = runtime.new_context()?;
+    let printer = WritablePrinter::new(Rc::new(RefCell::new(stdout())));
+    let event_loop = EventLoop::new();
 
-    let code = std::fs::read_to_string(file)?;
+    // Relative urls of fetch() and XMLHttpRequest resolve against the script file
+    let document_url =
+        Url::from_file_path(std::fs::canonicalize(&file)?).map_err(|_| anyhow!("invalid path: {file}"))?;
 
-    let value = ctx.run(&code)?;
+    // Nothing outlives a single run, so neither of the storages is persisted
+    let local = WebStorage::in_memory(DEFAULT_QUOTA);
+    let session = WebStorage::in_memory(DEFAULT_QUOTA);
 
-    println!("Got Value: {}", value.as_string()?);
+    let document = gosub_jsapi::new_document_context(
+        &mut runtime,
+        Box::new(printer),
+        event_loop.clone(),
+        &document_url,
+        &local,
+        &session,
+    )?;
+    let mut ctx: V8Context = document.ctx.clone();
+
+    let code = std::fs::read_to_string(&file)?;
+
+    if file.ends_with(".html") || file.ends_with(".htm") {
+        run_document_scripts(ctx.clone(), &document_url, &code)?;
+    } else {
+        let value = ctx.run(&code)?;
+
+        println!("Got Value: {}", value.as_string()?);
+    }
+
+    // Keep running until no tasks, timers or animation frames are left. There is nothing to render, so animation
+    // frames run at 60 frames per second
+    let time_origin = Instant::now();
+    let now = || time_origin.elapsed().as_secs_f64() * 1000.0;
+    loop {
+        event_loop.tick(now());
+        if event_loop.has_animation_frames() {
+            event_loop.run_animation_frames(now());
+        }
+        if event_loop.has_pending_tasks() {
+            continue;
+        }
+
+        let next_frame = event_loop.has_animation_frames().then(|| now() + FRAME_TIME);
+        let Some(deadline) = [next_frame, event_loop.next_timer_deadline()]
+            .into_iter()
+            .flatten()
+            .min_by(f64::total_cmp)
+        else {
+            break;
+        };
+
+        let wait = deadline - now();
+        if wait > 0.0 {
+            sleep(Duration::from_secs_f64(wait / 1000.0));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses an html document and runs its scripts in the order the script processing model prescribes
+fn run_document_scripts(ctx: V8Context<'static>, url: &Url, html: &str) -> Result<()> {
+    let mut stream = ByteStream::new(Encoding::UTF8, None);
+    stream.read_from_str(html, Some(Encoding::UTF8));
+    stream.close();
+
+    let handle: DocumentHandle<DocumentImpl<Css3System>, Css3System> =
+        DocumentBuilderImpl::new_document(Some(url.clone()));
+    Html5Parser::<DocumentImpl<Css3System>, Css3System>::parse_document(&mut stream, handle.clone(), None)?;
+
+    let loader = Rc::new(ModuleLoader::new(url.clone(), NetScriptFetcher::new(url.clone())));
+    let mut runner = ScriptRunner::<V8Engine>::new(ctx, loader);
+
+    // The parser doesn't pause for blocking scripts, so all scripts are taken once the document has been parsed
+    let scripts = handle.get_mut().take_scripts();
+    runner.add_scripts(scripts);
+    runner.finish();
 
     Ok(())
 }
+
+/// Time between two animation frames, in milliseconds
+const FRAME_TIME: f64 = 1000.0 / 60.0;
@@ -2,6 +2,7 @@ use wasm_bindgen::prelude::wasm_bindgen;
 
 mod css;
 mod html;
+mod js;
 mod renderer;
 mod styles;
 
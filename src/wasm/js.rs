@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gosub_boa::BoaEngine;
+use gosub_jsapi::console::{Buffer, WritablePrinter};
+use gosub_webexecutor::event_loop::EventLoop;
+use gosub_webexecutor::js::{JSContext, JSValue};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+pub struct JSOutput {
+    out: String,
+    console: String,
+}
+
+#[wasm_bindgen]
+impl JSOutput {
+    pub fn to_string(&self) -> String {
+        format!("{}\n{}", self.console, self.out)
+    }
+
+    pub fn out(&self) -> String {
+        self.out.clone()
+    }
+
+    pub fn console(&self) -> String {
+        self.console.clone()
+    }
+}
+
+#[wasm_bindgen]
+pub fn js_runner(input: &str) -> JSOutput {
+    let buffer = Rc::new(RefCell::new(Buffer::new()));
+    let printer = WritablePrinter::new(Rc::clone(&buffer));
+
+    let mut runtime = BoaEngine::new();
+    let event_loop = EventLoop::new();
+    let out = match gosub_jsapi::new_context(&mut runtime, Box::new(printer), event_loop.clone()) {
+        Ok(mut ctx) => match ctx.run(input).and_then(|value| value.as_string()) {
+            Ok(value) => value,
+            Err(e) => format!("error: {e}"),
+        },
+        Err(e) => format!("failed to create context: {e}"),
+    };
+
+    // The runner can't wait for delayed timers, but microtasks and timers without a delay still run
+    while event_loop.has_pending_tasks() || event_loop.next_timer_deadline() == Some(0.0) {
+        event_loop.tick(0.0);
+    }
+
+    let console = buffer.borrow().try_to_string().unwrap_or_default();
+
+    JSOutput { out, console }
+}